@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Ingen socket used when none is configured
+pub const DEFAULT_INGEN_SOCKET: &str = "/tmp/ingen-traxdub.sock";
+
+/// Brickwall limiter placed before the outputs by the safety limiter setting, the x42 Digital Peak Limiter
+pub const DEFAULT_LIMITER_URI: &str = "http://gareus.org/oss/lv2/dpl#mono";
+
+/// Themes the UI knows about
+pub const THEMES: [&str; 2] = ["dark", "light"];
+
+/// User preferences, stored in ~/.traxdub/config.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Knob speed factor, higher values need less rotation per step
+    pub knob_sensitivity: f32,
+    /// Curve of the larger steps made by fast knob turns when editing parameters, 0 disables it
+    pub knob_acceleration: f32,
+    /// Minutes between automatic session saves, 0 disables autosave
+    pub autosave_minutes: u32,
+    /// Unix socket of the Ingen engine
+    pub ingen_socket: String,
+    /// Unix socket of a second Ingen engine for the monitor mixes, shown as the monitor group, none by default
+    pub monitor_ingen_socket: Option<String>,
+    /// Milliseconds Ingen has to create its socket and answer when starting or reconnecting
+    pub ingen_start_timeout_ms: u32,
+    /// Have Ingen write a graph bundle with each save and load the one of the last save at boot,
+    /// instead of receiving the whole state over the socket once started. Not available with a monitor engine
+    pub preload_graph: bool,
+    /// UI color theme
+    pub theme: String,
+    /// Zoom factor of the whole UI
+    pub ui_scale: f32,
+    /// Layout for small screens such as 800×480 touchscreens, with bigger text and shorter menus
+    pub compact: bool,
+    /// Speak the focused element, the menu options and the messages with speech-dispatcher, for eyes-free operation
+    pub speech: bool,
+    /// Tags offered for the nodes, each shown in its own color
+    pub node_tags: Vec<String>,
+    /// Language of the interface, a locale file in ~/.traxdub/lang, taken from the LANG environment variable when empty
+    pub language: String,
+    /// Directory of the saved sessions, ~/.traxdub/store when unset
+    pub store_dir: Option<PathBuf>,
+    /// Show the recent log entries in the UI
+    pub log_panel: bool,
+    /// Show the spectrum of the engine outputs in the UI
+    pub analyzer: bool,
+    /// Show the messages exchanged with the engine and its last raw state in the UI, for debugging
+    pub inspector: bool,
+    /// Maximum size of a log file in ~/.traxdub/logs, in kilobytes, 0 disables the log file
+    pub log_file_kb: u64,
+    /// Number of older log files kept
+    pub log_files_kept: u32,
+    /// Seconds between the checks of the UI against the engine graph, 0 disables them
+    pub reconcile_seconds: u32,
+    /// Seconds between the checks that the engine still answers, 0 disables them
+    pub health_check_seconds: u32,
+    /// Milliseconds the engine has to answer a check before the connection is considered stale
+    pub health_check_timeout_ms: u32,
+    /// Milliseconds of the output fade out and in around session switches, 0 switches at once
+    pub transition_ms: u32,
+    /// Number of saves kept per session, the oldest being deleted, 0 keeps them all
+    pub saves_kept: u32,
+    /// Directory or rsync target (host:path) updated with the session store on every save
+    pub backup_target: Option<String>,
+    /// Keep a hidden brickwall limiter right before every audio output, to protect the PA from feedback and gain mistakes
+    pub safety_limiter: bool,
+    /// LV2 plugin of the safety limiters, with one audio input and output
+    pub safety_limiter_uri: String,
+    /// Directory of the saved sessions given on the command line, never saved
+    #[serde(skip)]
+    pub store_dir_override: Option<PathBuf>,
+    /// UI scale given on the command line, never saved
+    #[serde(skip)]
+    pub ui_scale_override: Option<f32>,
+    /// Compact layout asked on the command line, never saved
+    #[serde(skip)]
+    pub compact_override: bool,
+    /// Speech asked on the command line, never saved
+    #[serde(skip)]
+    pub speech_override: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            knob_sensitivity: 1.0,
+            knob_acceleration: 1.0,
+            autosave_minutes: 0,
+            ingen_socket: DEFAULT_INGEN_SOCKET.to_string(),
+            monitor_ingen_socket: None,
+            ingen_start_timeout_ms: 10000,
+            preload_graph: false,
+            theme: THEMES[0].to_string(),
+            ui_scale: 1.0,
+            compact: false,
+            speech: false,
+            node_tags: ["Vocals", "Drums", "Bass", "Keys", "FX", "Master"].iter().map(|tag| tag.to_string()).collect(),
+            language: String::new(),
+            store_dir: None,
+            log_panel: false,
+            analyzer: false,
+            inspector: false,
+            log_file_kb: 1024,
+            log_files_kept: 5,
+            reconcile_seconds: 30,
+            health_check_seconds: 5,
+            health_check_timeout_ms: 2000,
+            transition_ms: 300,
+            saves_kept: 0,
+            backup_target: None,
+            safety_limiter: false,
+            safety_limiter_uri: DEFAULT_LIMITER_URI.to_string(),
+            store_dir_override: None,
+            ui_scale_override: None,
+            compact_override: false,
+            speech_override: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Get the TraxDub directory in the user's home
+    pub fn get_home_dir() -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home).join(".traxdub"))
+    }
+
+    /// Get the settings file path
+    fn get_config_path() -> Result<PathBuf> {
+        Ok(Self::get_home_dir()?.join("config.toml"))
+    }
+
+    /// Load the settings, using defaults when there is no file or it is unreadable
+    pub fn load() -> Self {
+        let settings = Self::get_config_path().and_then(|path| {
+            let content = fs::read_to_string(&path).context("Failed to read settings")?;
+            toml::from_str(&content).context("Failed to parse settings")
+        });
+        match settings {
+            Ok(settings) => settings,
+            Err(e) => {
+                // A missing file is the normal first start, a broken one deserves a warning
+                if Self::get_config_path().is_ok_and(|path| path.exists()) {
+                    warn!("Using default settings: {:#}", e);
+                } else {
+                    debug!("Using default settings: {:#}", e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Save the settings
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_config_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)?;
+        info!("Settings saved to {:?}", path);
+        Ok(())
+    }
+
+    /// Get the session store directory, the command line taking precedence over the file
+    pub fn store_dir(&self) -> Result<PathBuf> {
+        match self.store_dir_override.as_ref().or(self.store_dir.as_ref()) {
+            Some(dir) => expand_home(dir),
+            None => Ok(Self::get_home_dir()?.join("store")),
+        }
+    }
+
+    /// Get the UI scale and whether the layout is compact, the command line taking precedence over the file
+    pub fn display(&self) -> (f32, bool) {
+        (self.ui_scale_override.unwrap_or(self.ui_scale), self.compact || self.compact_override)
+    }
+
+    /// Check whether the focus is spoken, the command line taking precedence over the file
+    pub fn speech_enabled(&self) -> bool {
+        self.speech || self.speech_override
+    }
+
+    /// Check whether Ingen loads the graph bundle of the last save at boot, the bundles leaving out a monitor engine
+    pub fn preloads_graph(&self) -> bool {
+        self.preload_graph && self.monitor_ingen_socket.is_none()
+    }
+
+    /// Get the store directory of portable mode, next to the executable
+    pub fn portable_store_dir() -> Result<PathBuf> {
+        let exe = std::env::current_exe()?;
+        let dir = exe.parent()
+            .ok_or_else(|| anyhow::anyhow!("Executable has no parent directory"))?;
+        Ok(dir.join("traxdub-store"))
+    }
+}
+
+/// Expand a leading ~ to the user's home directory
+pub fn expand_home(path: &Path) -> Result<PathBuf> {
+    match path.strip_prefix("~") {
+        Ok(rest) => {
+            let home = std::env::var("HOME")
+                .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+            Ok(PathBuf::from(home).join(rest))
+        }
+        Err(_) => Ok(path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_settings_use_defaults() {
+        let settings: Settings = toml::from_str("knob_sensitivity = 2.0\ntheme = \"light\"").unwrap();
+        assert_eq!(settings.knob_sensitivity, 2.0);
+        assert_eq!(settings.theme, "light");
+        assert_eq!(settings.autosave_minutes, 0);
+        assert_eq!(settings.ingen_socket, DEFAULT_INGEN_SOCKET);
+        assert_eq!(settings.store_dir, None);
+    }
+
+    #[test]
+    fn test_display_override_takes_precedence() {
+        let mut settings = Settings {
+            ui_scale: 1.5,
+            ..Settings::default()
+        };
+        assert_eq!(settings.display(), (1.5, false));
+        settings.ui_scale_override = Some(0.75);
+        settings.compact_override = true;
+        assert_eq!(settings.display(), (0.75, true));
+    }
+
+    #[test]
+    fn test_store_dir_override_takes_precedence() {
+        let settings = Settings {
+            store_dir: Some(PathBuf::from("/media/usb/sessions")),
+            ..Settings::default()
+        };
+        assert_eq!(settings.store_dir().unwrap(), PathBuf::from("/media/usb/sessions"));
+
+        let settings = Settings {
+            store_dir_override: Some(PathBuf::from("/mnt/stick")),
+            ..settings
+        };
+        assert_eq!(settings.store_dir().unwrap(), PathBuf::from("/mnt/stick"));
+        assert!(!toml::to_string(&settings).unwrap().contains("/mnt/stick"));
+    }
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use jack::{AudioIn, Client, ClientOptions, ClosureProcessHandler, Control, ProcessScope};
+use log::{debug, info, warn};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::controller::driver::{Driver, Port, PortType};
+
+/// JACK client name of the analyzer
+const ANALYZER_CLIENT: &str = "TraxDub Analyzer";
+
+/// Number of process cycles buffered between the JACK thread and the analysis
+const BUFFERED_CYCLES: usize = 256;
+
+/// Number of samples of each analysis, a power of two
+pub const FFT_SIZE: usize = 4096;
+
+/// Number of bands shown, the third octaves from 20 Hz to 20 kHz
+pub const BANDS: usize = 31;
+
+/// Band centered on 1 kHz
+const REFERENCE_BAND: usize = 17;
+
+/// Level shown for silent bands, in dB
+pub const FLOOR_DB: f32 = -90.0;
+
+/// A running analyzer client
+struct AnalyzerSession {
+    stop_flag: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+    samples: Receiver<Vec<f32>>,
+    sample_rate: usize,
+    /// Latest samples, at most FFT_SIZE
+    window: Vec<f32>,
+}
+
+/// Computes the spectrum of the engine output bus
+pub struct Analyzer {
+    driver: Arc<Driver>,
+    session: Mutex<Option<AnalyzerSession>>,
+}
+
+impl Analyzer {
+    /// Create a new analyzer
+    pub fn new(driver: Arc<Driver>) -> Self {
+        Self {
+            driver,
+            session: Mutex::new(None),
+        }
+    }
+
+    /// Check whether the analyzer is running
+    pub fn is_running(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// Start tapping the engine outputs
+    pub fn start(&self) -> Result<()> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_some() {
+            return Ok(());
+        }
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+
+        // The JACK client lives in its own thread
+        let thread_stop_flag = Arc::clone(&stop_flag);
+        let handle = std::thread::spawn(move || Self::run(thread_stop_flag, ready_sender));
+
+        let (samples, sample_rate) = match ready_receiver.recv_timeout(Duration::from_secs(2)) {
+            Ok(Ok(ready)) => ready,
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                return Err(e);
+            }
+            Err(_) => {
+                stop_flag.store(true, Ordering::SeqCst);
+                return Err(anyhow::anyhow!("Analyzer did not start"));
+            }
+        };
+
+        // Connect the engine outputs, alternating left and right
+        let outputs = self.driver.get_engine_outputs(PortType::Audio)?;
+        if outputs.is_empty() {
+            warn!("No engine outputs to analyze");
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            let input_name = if i % 2 == 0 { "in_l" } else { "in_r" };
+            let input = Port {
+                name: format!("{}:{}", ANALYZER_CLIENT, input_name),
+                short_name: input_name.to_string(),
+            };
+            self.driver.connect_ports(output, &input)?;
+        }
+
+        info!("Analyzing {} output(s)", outputs.len());
+
+        *session = Some(AnalyzerSession {
+            stop_flag,
+            handle,
+            samples,
+            sample_rate,
+            window: Vec::with_capacity(FFT_SIZE),
+        });
+        Ok(())
+    }
+
+    /// Stop the analyzer client
+    pub fn stop(&self) {
+        let Some(session) = self.session.lock().unwrap().take() else {
+            return;
+        };
+        session.stop_flag.store(true, Ordering::SeqCst);
+        if session.handle.join().is_err() {
+            warn!("Analyzer thread panicked");
+        }
+        info!("Analyzer stopped");
+    }
+
+    /// Get the levels of the bands in dB from the samples received since the last call
+    /// None when no new samples came or not enough for an analysis
+    pub fn spectrum(&self) -> Option<Vec<f32>> {
+        let mut session = self.session.lock().unwrap();
+        let session = session.as_mut()?;
+
+        let mut received = false;
+        for frames in session.samples.try_iter() {
+            session.window.extend(frames);
+            received = true;
+        }
+        if session.window.len() > FFT_SIZE {
+            session.window.drain(..session.window.len() - FFT_SIZE);
+        }
+        if !received || session.window.len() < FFT_SIZE {
+            return None;
+        }
+        Some(band_levels(&session.window, session.sample_rate))
+    }
+
+    /// Analyzer thread: run the JACK client until stopped
+    fn run(stop_flag: Arc<AtomicBool>, ready: mpsc::Sender<Result<(Receiver<Vec<f32>>, usize)>>) {
+        let setup = || -> Result<_> {
+            let (client, _status) = Client::new(ANALYZER_CLIENT, ClientOptions::NO_START_SERVER)
+                .map_err(|e| anyhow::anyhow!("Failed to create JACK analyzer client: {}", e))?;
+
+            let in_l = client.register_port("in_l", AudioIn)
+                .map_err(|e| anyhow::anyhow!("Failed to register analyzer port: {}", e))?;
+            let in_r = client.register_port("in_r", AudioIn)
+                .map_err(|e| anyhow::anyhow!("Failed to register analyzer port: {}", e))?;
+
+            Ok((client, in_l, in_r))
+        };
+
+        let (client, in_l, in_r) = match setup() {
+            Ok(setup) => setup,
+            Err(e) => {
+                let _ = ready.send(Err(e));
+                return;
+            }
+        };
+        let sample_rate = client.sample_rate();
+
+        let (sample_sender, sample_receiver) = mpsc::sync_channel::<Vec<f32>>(BUFFERED_CYCLES);
+
+        // Mix both channels down and hand them over to the analysis
+        let process_callback = move |_: &Client, ps: &ProcessScope| -> Control {
+            let frames = in_l.as_slice(ps).iter()
+                .zip(in_r.as_slice(ps))
+                .map(|(l, r)| (l + r) / 2.0)
+                .collect();
+            // Cycles are dropped when the analysis is late, it only needs the latest ones
+            let _ = sample_sender.try_send(frames);
+            Control::Continue
+        };
+
+        let active_client = match client.activate_async((), ClosureProcessHandler::new(process_callback)) {
+            Ok(active_client) => active_client,
+            Err(e) => {
+                let _ = ready.send(Err(anyhow::anyhow!("Failed to activate JACK analyzer client: {}", e)));
+                return;
+            }
+        };
+
+        debug!("JACK analyzer client activated");
+        let _ = ready.send(Ok((sample_receiver, sample_rate)));
+
+        while !stop_flag.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        drop(active_client);
+    }
+}
+
+impl Drop for Analyzer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Transform complex samples in place with a radix-2 FFT, their count being a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    // Bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Get the center frequency of a band in Hz
+pub fn band_center(band: usize) -> f32 {
+    1000.0 * 2f32.powf((band as f32 - REFERENCE_BAND as f32) / 3.0)
+}
+
+/// Get the level in dB of each third-octave band of FFT_SIZE samples, a full scale sine being at 0 dB
+pub fn band_levels(samples: &[f32], sample_rate: usize) -> Vec<f32> {
+    let n = samples.len().min(FFT_SIZE);
+    // Hann window
+    let mut re: Vec<f32> = samples[..n].iter().enumerate()
+        .map(|(i, s)| s * (0.5 - 0.5 * (2.0 * PI * i as f32 / n as f32).cos()))
+        .collect();
+    re.resize(FFT_SIZE, 0.0);
+    let mut im = vec![0.0; FFT_SIZE];
+    fft(&mut re, &mut im);
+
+    // The window halves the amplitude, the spectrum splits it between both halves
+    let amplitude = |bin: usize| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() * 4.0 / FFT_SIZE as f32;
+    let bin_width = sample_rate as f32 / FFT_SIZE as f32;
+    let last_bin = FFT_SIZE / 2 - 1;
+
+    (0..BANDS)
+        .map(|band| {
+            let center = band_center(band);
+            let (low, high) = (center * 2f32.powf(-1.0 / 6.0), center * 2f32.powf(1.0 / 6.0));
+            let first = ((low / bin_width).ceil() as usize).min(last_bin);
+            let end = ((high / bin_width).ceil() as usize).min(last_bin + 1);
+            // Narrow low bands fall between bins, take the one at their center
+            let peak = if first < end {
+                (first..end).map(amplitude).fold(0.0, f32::max)
+            } else {
+                amplitude(((center / bin_width).round() as usize).min(last_bin))
+            };
+            (20.0 * peak.max(f32::MIN_POSITIVE).log10()).max(FLOOR_DB)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_band_levels_of_sine() {
+        let sample_rate = 48000;
+        let samples: Vec<f32> = (0..FFT_SIZE)
+            .map(|i| (2.0 * PI * 1000.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        let levels = band_levels(&samples, sample_rate);
+        assert_eq!(levels.len(), BANDS);
+
+        let loudest = levels.iter().enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(band, _)| band)
+            .unwrap();
+        assert_eq!(loudest, REFERENCE_BAND);
+        assert!(levels[loudest] > -3.0 && levels[loudest] < 1.0, "level {}", levels[loudest]);
+        assert!(levels[5] < -40.0);
+    }
+
+    #[test]
+    fn test_band_levels_of_silence() {
+        let levels = band_levels(&vec![0.0; FFT_SIZE], 48000);
+        assert!(levels.iter().all(|level| *level == FLOOR_DB));
+    }
+}
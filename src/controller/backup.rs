@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+
+/// Directory created on removable media to hold the backup
+pub const BACKUP_DIR_NAME: &str = "traxdub-backup";
+
+/// Serializes the backups, a save made during a backup waits for it
+static BACKUP_LOCK: Mutex<()> = Mutex::new(());
+
+/// Check whether a backup target is an rsync remote like host:path or user@host:path
+/// A colon after a slash belongs to a local path
+pub fn is_rsync_target(target: &str) -> bool {
+    match (target.find(':'), target.find('/')) {
+        (Some(colon), Some(slash)) => colon < slash,
+        (Some(colon), None) => colon > 0,
+        _ => false,
+    }
+}
+
+/// Copy the files of a directory that are missing or differ in size or modification time
+/// Files are never deleted from the backup, so that it also keeps pruned saves
+/// Returns the number of files copied
+pub fn copy_changed(source: &Path, destination: &Path) -> Result<usize> {
+    fs::create_dir_all(destination)
+        .with_context(|| format!("Cannot create backup directory {:?}", destination))?;
+
+    let mut copied = 0;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        let name = entry.file_name();
+        // Temporary files of a save in progress are not worth a copy
+        if !metadata.is_file() || name.to_string_lossy().ends_with(".tmp") {
+            continue;
+        }
+
+        let target = destination.join(&name);
+        let unchanged = fs::metadata(&target)
+            .map(|existing| existing.len() == metadata.len() && existing.modified().ok() >= metadata.modified().ok())
+            .unwrap_or(false);
+        if unchanged {
+            continue;
+        }
+
+        fs::copy(entry.path(), &target)?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Copy the session store to the backup target
+fn run_backup(store_dir: &Path, target: &str) -> Result<()> {
+    let _guard = BACKUP_LOCK.lock().unwrap();
+
+    if is_rsync_target(target) {
+        // Trailing slash: copy the content of the store, not the directory itself
+        let status = Command::new("rsync")
+            .arg("-a")
+            .arg(format!("{}/", store_dir.display()))
+            .arg(target)
+            .status()
+            .context("Failed to run rsync")?;
+        if !status.success() {
+            anyhow::bail!("rsync to {} failed: {}", target, status);
+        }
+        info!("Session store backed up to {}", target);
+    } else {
+        let copied = copy_changed(store_dir, &crate::config::expand_home(Path::new(target))?)?;
+        info!("Session store backed up to {} ({} files copied)", target, copied);
+    }
+    Ok(())
+}
+
+/// Back up the session store in the background, so that a slow card or network never holds the controller
+pub fn spawn_backup(store_dir: PathBuf, target: String) {
+    debug!("Starting backup of {:?} to {}", store_dir, target);
+    thread::spawn(move || {
+        if let Err(e) = run_backup(&store_dir, &target) {
+            warn!("Backup of the session store failed: {}", e);
+        }
+    });
+}
+
+/// Find the mounted removable media that can hold a backup, as backup directories
+pub fn removable_backup_dirs() -> Vec<PathBuf> {
+    let mut roots = vec![PathBuf::from("/mnt")];
+    if let Ok(user) = std::env::var("USER") {
+        roots.push(Path::new("/media").join(&user));
+        roots.push(Path::new("/run/media").join(&user));
+    }
+
+    let mut dirs: Vec<PathBuf> = roots.iter()
+        .filter_map(|root| fs::read_dir(root).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| path.join(BACKUP_DIR_NAME))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_rsync_target() {
+        assert!(is_rsync_target("nas:traxdub"));
+        assert!(is_rsync_target("pi@studio.local:/srv/traxdub"));
+        assert!(!is_rsync_target("/media/pi/USB/traxdub-backup"));
+        assert!(!is_rsync_target("~/backup"));
+        assert!(!is_rsync_target("/mnt/disk:1/backup"));
+    }
+
+    #[test]
+    fn test_copy_changed() {
+        let dir = std::env::temp_dir().join(format!("traxdub-backup-test-{}", std::process::id()));
+        let store = dir.join("store");
+        let backup = dir.join("backup");
+        fs::create_dir_all(&store).unwrap();
+        fs::write(store.join("2026-01-02-20-15-calm-river.txd"), "state").unwrap();
+        fs::write(store.join("calm-river.info.json.tmp"), "{").unwrap();
+
+        assert_eq!(copy_changed(&store, &backup).unwrap(), 1);
+        assert_eq!(fs::read_to_string(backup.join("2026-01-02-20-15-calm-river.txd")).unwrap(), "state");
+        assert!(!backup.join("calm-river.info.json.tmp").exists());
+        // Nothing changed, nothing copied
+        assert_eq!(copy_changed(&store, &backup).unwrap(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
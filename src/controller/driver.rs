@@ -1,8 +1,89 @@
 use anyhow::{Result};
-use jack::{Client, ClientOptions, ClosureProcessHandler, Control, MidiIn, ProcessScope, PortFlags};
+use jack::{Client, ClientOptions, ClosureProcessHandler, Control, MidiIn, MidiOut, NotificationHandler, PortFlags, PortId, ProcessScope};
 use log::{debug, error, info, warn, trace};
-use std::sync::mpsc::{channel, Receiver};
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}, Mutex};
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}, Mutex};
+use std::time::{Duration, Instant};
+
+/// MIDI clock pulses per quarter note
+const CLOCK_PULSES_PER_BEAT: usize = 24;
+
+/// Time without clock pulses after which the tempo is considered lost
+const CLOCK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Controllers reset by a MIDI panic: sustain pedal (reset to 0), All Sound Off and All Notes Off
+const PANIC_CONTROLS: [u8; 3] = [64, 120, 123];
+
+/// Messages sent by a MIDI panic, on all channels
+pub fn panic_messages() -> Vec<[u8; 3]> {
+    (0..16u8)
+        .flat_map(|channel| PANIC_CONTROLS.map(|control| [0xB0 | channel, control, 0]))
+        .collect()
+}
+
+/// Number of messages kept for the MIDI monitor
+const MONITOR_SIZE: usize = 20;
+
+/// Source name of the messages merged by the JACK control port
+pub const JACK_MIDI_SOURCE: &str = "JACK MIDI";
+
+/// Recent incoming MIDI messages and the last activity of each source, for the MIDI monitor
+#[derive(Debug, Clone, Default)]
+pub struct MidiMonitor {
+    /// Messages as (source, raw bytes), newest last
+    pub messages: VecDeque<(String, Vec<u8>)>,
+    /// Time of the last message of each source
+    pub activity: HashMap<String, Instant>,
+    /// Number of messages recorded so far, to tell when a view is outdated
+    pub generation: u64,
+}
+
+impl MidiMonitor {
+    /// Record a message, leaving out the clock pulses and active sensing that would flood the view
+    fn record(&mut self, source: &str, bytes: &[u8]) {
+        if matches!(bytes.first(), None | Some(0xF8) | Some(0xFE)) {
+            return;
+        }
+        if self.messages.len() == MONITOR_SIZE {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((source.to_string(), bytes.to_vec()));
+        self.activity.insert(source.to_string(), Instant::now());
+        self.generation += 1;
+    }
+}
+
+/// Name of a MIDI note (60 is C4)
+fn note_name(note: u8) -> String {
+    const NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+    format!("{}{}", NAMES[note as usize % 12], note as i32 / 12 - 1)
+}
+
+/// Describe a raw MIDI message for the monitor, channels numbered from 1
+pub fn describe_midi(bytes: &[u8]) -> String {
+    let data = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    let Some(&status) = bytes.first() else {
+        return "Empty".to_string();
+    };
+    let channel = (status & 0x0F) + 1;
+    match status & 0xF0 {
+        0x80 => format!("Note Off ch{} {}", channel, note_name(data(1))),
+        0x90 if data(2) == 0 => format!("Note Off ch{} {}", channel, note_name(data(1))),
+        0x90 => format!("Note On ch{} {} vel {}", channel, note_name(data(1)), data(2)),
+        0xA0 => format!("Poly AT ch{} {} = {}", channel, note_name(data(1)), data(2)),
+        0xB0 => format!("CC ch{} #{} = {}", channel, data(1), data(2)),
+        0xC0 => format!("Program ch{} {}", channel, data(1)),
+        0xD0 => format!("Aftertouch ch{} {}", channel, data(1)),
+        0xE0 => format!("Pitch Bend ch{} {}", channel, data(1) as u16 | (data(2) as u16) << 7),
+        _ => match status {
+            0xFA => "Start".to_string(),
+            0xFB => "Continue".to_string(),
+            0xFC => "Stop".to_string(),
+            _ => format!("System {:02X}", status),
+        },
+    }
+}
 
 /// Represents a JACK port with its ID and human-friendly name
 #[derive(Debug, Clone)]
@@ -44,6 +125,68 @@ impl PortType {
     }
 }
 
+/// Audio settings of the JACK server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioStatus {
+    pub sample_rate: usize,
+    pub buffer_size: u32,
+    /// Capture plus playback latency of the hardware, in frames
+    pub round_trip_frames: u32,
+}
+
+impl AudioStatus {
+    /// Round-trip latency in milliseconds
+    pub fn latency_ms(&self) -> f32 {
+        self.round_trip_frames as f32 * 1000.0 / self.sample_rate as f32
+    }
+}
+
+/// A JACK port that appeared or disappeared
+#[derive(Debug, Clone)]
+pub struct PortChange {
+    pub port: Port,
+    pub port_type: PortType,
+    /// True for ports we can read from (sources)
+    pub is_output: bool,
+    /// True when the port was registered, false when it went away
+    pub registered: bool,
+}
+
+/// JACK notification handler collecting port registrations and xruns
+struct ServerWatcher {
+    changes: Arc<Mutex<Vec<PortChange>>>,
+    xruns: Arc<AtomicUsize>,
+}
+
+impl NotificationHandler for ServerWatcher {
+    fn xrun(&mut self, _: &Client) -> Control {
+        self.xruns.fetch_add(1, Ordering::SeqCst);
+        Control::Continue
+    }
+
+    fn port_registration(&mut self, client: &Client, port_id: PortId, is_registered: bool) {
+        let Some(jack_port) = client.port_by_id(port_id) else {
+            return;
+        };
+        let Ok(name) = jack_port.name() else {
+            return;
+        };
+        let port_type = match jack_port.port_type() {
+            Ok(t) if t.contains("midi") => PortType::Midi,
+            Ok(t) if t.contains("audio") => PortType::Audio,
+            _ => return,
+        };
+        let short_name = name.split(':').next_back().unwrap_or(&name).to_string();
+        debug!("JACK port {}: {}", if is_registered { "registered" } else { "unregistered" }, name);
+        self.changes.lock().unwrap().push(PortChange {
+            port: Port { name, short_name },
+            port_type,
+            is_output: jack_port.flags().contains(PortFlags::IS_OUTPUT),
+            registered: is_registered,
+        });
+    }
+}
+
 /// MIDI event types (excluding note events as per requirements)
 #[derive(Debug, Clone)]
 pub enum MidiEvent {
@@ -69,6 +212,14 @@ pub enum MidiEvent {
         note: u8,
         pressure: u8,
     },
+    /// MIDI clock pulse (24 per quarter note)
+    Clock,
+    /// Transport start
+    Start,
+    /// Transport continue
+    Continue,
+    /// Transport stop
+    Stop,
 }
 
 impl MidiEvent {
@@ -82,6 +233,16 @@ impl MidiEvent {
         let message_type = status & 0xF0;
         let channel = status & 0x0F;
 
+        // System real-time messages (single byte, no channel)
+        match status {
+            0xF8 => return Some(MidiEvent::Clock),
+            0xFA => return Some(MidiEvent::Start),
+            0xFB => return Some(MidiEvent::Continue),
+            0xFC => return Some(MidiEvent::Stop),
+            0xFE => return None, // Active sensing
+            _ => {}
+        }
+
         match message_type {
             // Control Change
             0xB0 => {
@@ -149,12 +310,80 @@ impl MidiEvent {
             }
         }
     }
+
+    /// Encode the event back into a raw MIDI message
+    pub fn to_raw(&self) -> Vec<u8> {
+        match *self {
+            MidiEvent::ControlChange { channel, control, value } => vec![0xB0 | channel, control, value],
+            MidiEvent::ProgramChange { channel, program } => vec![0xC0 | channel, program],
+            MidiEvent::PitchBend { channel, value } => vec![0xE0 | channel, (value & 0x7F) as u8, (value >> 7) as u8],
+            MidiEvent::AfterTouch { channel, pressure } => vec![0xD0 | channel, pressure],
+            MidiEvent::PolyAfterTouch { channel, note, pressure } => vec![0xA0 | channel, note, pressure],
+            MidiEvent::Clock => vec![0xF8],
+            MidiEvent::Start => vec![0xFA],
+            MidiEvent::Continue => vec![0xFB],
+            MidiEvent::Stop => vec![0xFC],
+        }
+    }
+}
+
+/// Tempo tracker computing BPM from MIDI clock pulses
+#[derive(Debug, Default)]
+pub struct MidiClock {
+    last_pulse_frame: Option<u64>,
+    intervals: VecDeque<u64>,
+}
+
+impl MidiClock {
+    /// Register a clock pulse at the given frame and return the averaged BPM
+    /// once a full beat of pulses has been received
+    pub fn pulse(&mut self, frame: u64, sample_rate: usize) -> Option<f32> {
+        if let Some(last) = self.last_pulse_frame {
+            self.intervals.push_back(frame.saturating_sub(last));
+            if self.intervals.len() > CLOCK_PULSES_PER_BEAT {
+                self.intervals.pop_front();
+            }
+        }
+        self.last_pulse_frame = Some(frame);
+
+        if self.intervals.len() < CLOCK_PULSES_PER_BEAT {
+            return None;
+        }
+
+        let frames_per_beat: u64 = self.intervals.iter().sum();
+        if frames_per_beat == 0 {
+            return None;
+        }
+        Some(60.0 * sample_rate as f32 / frames_per_beat as f32)
+    }
+
+    /// Forget previous pulses (e.g., when the transport stops)
+    pub fn reset(&mut self) {
+        self.last_pulse_frame = None;
+        self.intervals.clear();
+    }
+}
+
+/// Last tempo computed from the incoming MIDI clock
+#[derive(Debug, Clone, Copy)]
+struct ClockTempo {
+    bpm: f32,
+    updated: Instant,
 }
 
 /// MIDI receiver that connects to JACK and processes incoming MIDI events
 pub struct Driver {
     _active_client_handle: Arc<AtomicBool>,
     client: Arc<Mutex<Option<Client>>>,
+    clock_tempo: Arc<Mutex<Option<ClockTempo>>>,
+    port_changes: Arc<Mutex<Vec<PortChange>>>,
+    xruns: Arc<AtomicUsize>,
+    /// Recent incoming messages for the MIDI monitor
+    monitor: Arc<Mutex<MidiMonitor>>,
+    /// Set to send the panic messages in the next process cycle
+    panic: Arc<AtomicBool>,
+    /// ALSA sequencer inputs used when no JACK server is available
+    alsa_connections: Mutex<Vec<midir::MidiInputConnection<MidiClock>>>,
 }
 
 impl Driver {
@@ -184,31 +413,185 @@ impl Driver {
         result
     }
 
+    /// Human-friendly name of a port, taken from its JACK aliases when they tell more than its short name
+    fn pretty_name(client: &Client, port_name: &str, short_name: &str) -> String {
+        client.port_by_name(port_name)
+            .and_then(|port| port.aliases().ok())
+            .and_then(|aliases| aliases.iter().find_map(|alias| Self::alias_label(alias, short_name)))
+            .unwrap_or_else(|| short_name.to_string())
+    }
+
+    /// Label of a JACK alias without its client prefix, if it differs from the short name
+    fn alias_label(alias: &str, short_name: &str) -> Option<String> {
+        let label = alias.split_once(':').map_or(alias, |(_, name)| name).trim();
+        (!label.is_empty() && label != short_name).then(|| label.to_string())
+    }
+
     /// Create a new JACK driver instance
     pub fn new() -> Result<Self> {
         debug!("Initializing JACK driver...");
 
-        // Create a JACK client for port queries
-        let (query_client, _status) = Client::new("TraxDub Query", ClientOptions::NO_START_SERVER)
-            .map_err(|e| anyhow::anyhow!("Failed to create JACK query client: {}", e))?;
-
-        debug!("JACK query client created: {}", query_client.name());
+        // Create a JACK client for port queries, falling back to ALSA MIDI without a JACK server
+        let query_client = match Client::new("TraxDub Query", ClientOptions::NO_START_SERVER) {
+            Ok((client, _status)) => {
+                debug!("JACK query client created: {}", client.name());
+                Some(client)
+            }
+            Err(e) => {
+                warn!("No JACK server for MIDI ({}), using the ALSA sequencer", e);
+                None
+            }
+        };
 
-        let client_storage = Arc::new(Mutex::new(Some(query_client)));
+        let client_storage = Arc::new(Mutex::new(query_client));
         let shutdown_flag = Arc::new(AtomicBool::new(false));
 
         Ok(Self {
             _active_client_handle: shutdown_flag,
             client: client_storage,
+            clock_tempo: Arc::new(Mutex::new(None)),
+            port_changes: Arc::new(Mutex::new(Vec::new())),
+            xruns: Arc::new(AtomicUsize::new(0)),
+            monitor: Arc::new(Mutex::new(MidiMonitor::default())),
+            panic: Arc::new(AtomicBool::new(false)),
+            alsa_connections: Mutex::new(Vec::new()),
         })
     }
 
+    /// Get the tempo of the incoming MIDI clock, if any clock is running
+    pub fn get_bpm(&self) -> Option<f32> {
+        let tempo = (*self.clock_tempo.lock().unwrap())?;
+        if tempo.updated.elapsed() > CLOCK_TIMEOUT {
+            return None;
+        }
+        Some(tempo.bpm)
+    }
+
+    /// Take the JACK port changes seen since the last call
+    pub fn take_port_changes(&self) -> Vec<PortChange> {
+        std::mem::take(&mut *self.port_changes.lock().unwrap())
+    }
+
+    /// Get a copy of the recent incoming MIDI messages
+    pub fn midi_monitor(&self) -> MidiMonitor {
+        self.monitor.lock().unwrap().clone()
+    }
+
+    /// Record raw MIDI bytes for the monitor, never blocking the process thread
+    fn monitor_raw_midi(monitor: &Mutex<MidiMonitor>, source: &str, bytes: &[u8]) {
+        if let Ok(mut monitor) = monitor.try_lock() {
+            monitor.record(source, bytes);
+        }
+    }
+
+    /// Feed raw MIDI bytes to the tempo tracker or the event channel
+    fn dispatch_raw_midi(
+        bytes: &[u8],
+        frame: u64,
+        sample_rate: usize,
+        midi_clock: &mut MidiClock,
+        clock_tempo: &Mutex<Option<ClockTempo>>,
+        event_sender: &Sender<MidiEvent>,
+    ) {
+        trace!("Raw MIDI bytes: {:?}", bytes);
+        // Parse the MIDI event
+        let Some(midi_event) = MidiEvent::from_raw(bytes) else {
+            trace!("Ignored or unknown MIDI event");
+            return;
+        };
+
+        // Clock pulses only feed the tempo tracker
+        match midi_event {
+            MidiEvent::Clock => {
+                if let Some(bpm) = midi_clock.pulse(frame, sample_rate) {
+                    // Never block the process thread on the tempo lock
+                    if let Ok(mut tempo) = clock_tempo.try_lock() {
+                        *tempo = Some(ClockTempo { bpm, updated: Instant::now() });
+                    }
+                }
+                return;
+            }
+            MidiEvent::Start | MidiEvent::Stop => midi_clock.reset(),
+            _ => {}
+        }
+
+        trace!("Parsed MIDI event: {:?}", midi_event);
+        // Send to channel
+        if let Err(e) = event_sender.send(midi_event) {
+            error!("Failed to send MIDI event: {}", e);
+        }
+    }
+
+    /// Listen to all ALSA sequencer MIDI inputs, used when there is no JACK server
+    fn start_alsa(&self, event_sender: Sender<MidiEvent>) -> Result<()> {
+        let probe = midir::MidiInput::new("TraxDub Controller")
+            .map_err(|e| anyhow::anyhow!("Failed to open the ALSA sequencer: {}", e))?;
+
+        let mut connections = self.alsa_connections.lock().unwrap();
+        for port in probe.ports() {
+            let Ok(port_name) = probe.port_name(&port) else {
+                continue;
+            };
+            if port_name.starts_with("TraxDub") {
+                continue;
+            }
+
+            // Each connection consumes its own sequencer client
+            let mut input = midir::MidiInput::new("TraxDub Controller")
+                .map_err(|e| anyhow::anyhow!("Failed to open the ALSA sequencer: {}", e))?;
+            input.ignore(midir::Ignore::None);
+
+            let sender = event_sender.clone();
+            let clock_tempo = Arc::clone(&self.clock_tempo);
+            let monitor = Arc::clone(&self.monitor);
+            let source = port_name.clone();
+            // Timestamps are in microseconds
+            let callback = move |timestamp: u64, bytes: &[u8], midi_clock: &mut MidiClock| {
+                Self::monitor_raw_midi(&monitor, &source, bytes);
+                Self::dispatch_raw_midi(bytes, timestamp, 1_000_000, midi_clock, &clock_tempo, &sender);
+            };
+            match input.connect(&port, "control", callback, MidiClock::default()) {
+                Ok(connection) => {
+                    debug!("Listening to ALSA MIDI input: {}", port_name);
+                    connections.push(connection);
+                }
+                Err(e) => warn!("Failed to connect ALSA MIDI input {}: {}", port_name, e),
+            }
+        }
+
+        info!("Listening to {} ALSA MIDI input port(s)", connections.len());
+        Ok(())
+    }
+
+    /// Number of xruns since start or since the count was cleared
+    pub fn xrun_count(&self) -> usize {
+        self.xruns.load(Ordering::SeqCst)
+    }
+
+    /// Clear the xrun count once the user acknowledged it
+    pub fn clear_xruns(&self) {
+        self.xruns.store(0, Ordering::SeqCst);
+    }
+
     /// Start receiving MIDI events from JACK and return the receiver channel
     pub fn start(&self) -> Result<Receiver<MidiEvent>> {
-        debug!("Starting JACK MIDI receiver...");
-
         let (event_sender, event_receiver) = channel();
+
+        if self.client.lock().unwrap().is_none() {
+            debug!("Starting ALSA MIDI receiver...");
+            self.start_alsa(event_sender)?;
+            return Ok(event_receiver);
+        }
+
+        debug!("Starting JACK MIDI receiver...");
         let shutdown_flag = Arc::clone(&self._active_client_handle);
+        let clock_tempo = Arc::clone(&self.clock_tempo);
+        let panic = Arc::clone(&self.panic);
+        let monitor = Arc::clone(&self.monitor);
+        let server_watcher = ServerWatcher {
+            changes: Arc::clone(&self.port_changes),
+            xruns: Arc::clone(&self.xruns),
+        };
 
         // Spawn a thread to keep the JACK client alive
         std::thread::spawn(move || {
@@ -220,23 +603,32 @@ impl Driver {
 
             // Create MIDI input port
             let midi_in = client
-                .register_port("control", MidiIn::default())
+                .register_port("control", MidiIn)
                 .expect("Failed to register MIDI input port");
+            let mut panic_out = client
+                .register_port("panic", MidiOut)
+                .expect("Failed to register MIDI panic port");
+
+            let mut midi_clock = MidiClock::default();
 
             // Set up process callback
-            let process_callback = move |_: &Client, ps: &ProcessScope| -> Control {
+            let process_callback = move |client: &Client, ps: &ProcessScope| -> Control {
                 // Get MIDI events from the port using iter() method
                 for raw_event in midi_in.iter(ps) {
-                    trace!("Raw MIDI bytes: {:?}", raw_event.bytes);
-                    // Parse the MIDI event
-                    if let Some(midi_event) = MidiEvent::from_raw(&raw_event.bytes) {
-                        trace!("Parsed MIDI event: {:?}", midi_event);
-                        // Send to channel
-                        if let Err(e) = event_sender.send(midi_event) {
-                            error!("Failed to send MIDI event: {}", e);
+                    let frame = ps.last_frame_time() as u64 + raw_event.time as u64;
+                    Self::monitor_raw_midi(&monitor, JACK_MIDI_SOURCE, raw_event.bytes);
+                    Self::dispatch_raw_midi(
+                        raw_event.bytes, frame, client.sample_rate(),
+                        &mut midi_clock, &clock_tempo, &event_sender,
+                    );
+                }
+
+                let mut writer = panic_out.writer(ps);
+                if panic.swap(false, Ordering::SeqCst) {
+                    for message in panic_messages() {
+                        if let Err(e) = writer.write(&jack::RawMidi { time: 0, bytes: &message }) {
+                            error!("Failed to write panic message: {:?}", e);
                         }
-                    } else {
-                        trace!("Ignored or unknown MIDI event");
                     }
                 }
 
@@ -247,7 +639,7 @@ impl Driver {
 
             // Activate the client
             let active_client = client
-                .activate_async((), process_handler)
+                .activate_async(server_watcher, process_handler)
                 .expect("Failed to activate JACK client");
 
             debug!("JACK client activated");
@@ -295,9 +687,10 @@ impl Driver {
                         ports: Vec::new(),
                     });
 
+                let short_name = port_short_name.split(':').next_back().unwrap_or("");
                 entry.ports.push(Port {
                     name: port_name.to_string(),
-                    short_name: port_short_name.to_string().split(":").last().unwrap_or("").to_string(),
+                    short_name: Self::pretty_name(client, &port_name, short_name),
                 });
             }
         }
@@ -343,9 +736,10 @@ impl Driver {
                         ports: Vec::new(),
                     });
 
+                let short_name = port_short_name.split(':').next_back().unwrap_or("");
                 entry.ports.push(Port {
                     name: port_name.to_string(),
-                    short_name: port_short_name.to_string().split(":").last().unwrap_or("").to_string(),
+                    short_name: Self::pretty_name(client, &port_name, short_name),
                 });
             }
         }
@@ -363,6 +757,28 @@ impl Driver {
         Ok(sinks)
     }
 
+    /// Get the output ports of the TraxDub Engine (the output bus)
+    /// 
+    /// # Arguments
+    /// * `port_type` - Filter by port type (Audio, Midi, or All)
+    pub fn get_engine_outputs(&self, port_type: PortType) -> Result<Vec<Port>> {
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+
+        let mut ports: Vec<Port> = client
+            .ports(Some("^TraxDub Engine:"), port_type.to_jack_type_str(), PortFlags::IS_OUTPUT)
+            .into_iter()
+            .map(|name| Port {
+                short_name: name.split(':').next_back().unwrap_or("").to_string(),
+                name,
+            })
+            .collect();
+
+        ports.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(ports)
+    }
+
     /// Connect two JACK ports
     /// 
     /// # Arguments
@@ -393,20 +809,145 @@ impl Driver {
         }
     }
 
+    /// Get the sample rate, buffer size and round-trip latency of the JACK server
+    pub fn get_audio_status(&self) -> Result<AudioStatus> {
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+
+        let buffer_size = client.buffer_size();
+
+        // Latency of the first physical port in a direction, as reported by the backend
+        let hardware_latency = |flags: PortFlags, mode: jack::LatencyType| -> u32 {
+            client.ports(None, Some("audio"), flags | PortFlags::IS_PHYSICAL)
+                .first()
+                .and_then(|name| client.port_by_name(name))
+                .map(|port| port.get_latency_range(mode).1)
+                .unwrap_or(0)
+        };
+        let capture = hardware_latency(PortFlags::IS_OUTPUT, jack::LatencyType::Capture);
+        let playback = hardware_latency(PortFlags::IS_INPUT, jack::LatencyType::Playback);
+
+        // Without latency information, assume one period each way
+        let round_trip_frames = if capture + playback > 0 { capture + playback } else { 2 * buffer_size };
+
+        Ok(AudioStatus {
+            sample_rate: client.sample_rate(),
+            buffer_size,
+            round_trip_frames,
+        })
+    }
+
+    /// Get the DSP load of the JACK server, in percent
+    pub fn get_dsp_load(&self) -> Result<f32> {
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+        Ok(client.cpu_load())
+    }
+
+    /// Ask the JACK server to use another buffer size
+    pub fn set_buffer_size(&self, frames: u32) -> Result<()> {
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+
+        info!("Requesting JACK buffer size of {} frames", frames);
+        client.set_buffer_size(frames)
+            .map_err(|e| anyhow::anyhow!("Failed to set buffer size: {:?}", e))
+    }
+
+    /// Get the names of the JACK ports connected to a port
+    pub fn get_connections(&self, port: &Port) -> Result<Vec<String>> {
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+
+        let jack_port = client.port_by_name(&port.name)
+            .ok_or_else(|| anyhow::anyhow!("JACK port not found: {}", port.name))?;
+        let peer_flags = if jack_port.flags().contains(PortFlags::IS_OUTPUT) {
+            PortFlags::IS_INPUT
+        } else {
+            PortFlags::IS_OUTPUT
+        };
+
+        Ok(client.ports(None, None, peer_flags)
+            .into_iter()
+            .filter(|other| jack_port.is_connected_to(other).unwrap_or(false))
+            .collect())
+    }
+
+    /// Disconnect all JACK connections of a port
+    pub fn disconnect_all(&self, port: &Port) -> Result<()> {
+        let connected = self.get_connections(port)?;
+
+        let client_guard = self.client.lock().unwrap();
+        let client = client_guard.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("JACK client not initialized"))?;
+        let is_output = client.port_by_name(&port.name)
+            .is_some_and(|jack_port| jack_port.flags().contains(PortFlags::IS_OUTPUT));
+
+        for other in connected {
+            let result = if is_output {
+                client.disconnect_ports_by_name(&port.name, &other)
+            } else {
+                client.disconnect_ports_by_name(&other, &port.name)
+            };
+            match result {
+                Ok(_) => debug!("Disconnected {} from {}", port.name, other),
+                Err(e) => warn!("Failed to disconnect {} from {}: {:?}", port.name, other, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reset all notes, sounds and sustains on every MIDI input: the engine inputs feeding
+    /// the synth blocks and the external synths
+    pub fn panic(&self) -> Result<()> {
+        let destinations: Vec<String> = {
+            let client_guard = self.client.lock().unwrap();
+            let client = client_guard.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("MIDI panic needs a JACK server"))?;
+            client.ports(None, PortType::Midi.to_jack_type_str(), PortFlags::IS_INPUT)
+                .into_iter()
+                .filter(|name| !name.starts_with("TraxDub Controller:"))
+                .collect()
+        };
+
+        // Reach the ports that appeared since the last panic
+        let source = Port {
+            name: "TraxDub Controller:panic".to_string(),
+            short_name: "panic".to_string(),
+        };
+        for name in destinations {
+            let destination = Port {
+                short_name: name.split(':').next_back().unwrap_or("").to_string(),
+                name,
+            };
+            if let Err(e) = self.connect_ports(&source, &destination) {
+                warn!("Panic cannot reach {}: {}", destination.name, e);
+            }
+        }
+
+        info!("MIDI panic");
+        self.panic.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
     /// Connect all MIDI input sources to the TraxDub Controller MIDI input port
     pub fn connect_all_midi_inputs(&self) -> Result<()> {
+        if self.client.lock().unwrap().is_none() {
+            // ALSA inputs are connected when the receiver starts
+            return Ok(());
+        }
         debug!("Connecting all MIDI input sources to TraxDub Controller...");
         
         let sources = self.get_sources(PortType::Midi)?;
-        let destination = Port {
-            name: "TraxDub Controller:control".to_string(),
-            short_name: "control".to_string(),
-        };
 
         let mut connected_count = 0;
         for source in sources {
             for port in source.ports {
-                match self.connect_ports(&port, &destination) {
+                match self.connect_midi_input(&port) {
                     Ok(_) => connected_count += 1,
                     Err(e) => warn!("Failed to connect {}: {}", port.name, e),
                 }
@@ -417,9 +958,19 @@ impl Driver {
         Ok(())
     }
 
+    /// Connect a MIDI source to the TraxDub Controller MIDI input port
+    pub fn connect_midi_input(&self, port: &Port) -> Result<()> {
+        let destination = Port {
+            name: "TraxDub Controller:control".to_string(),
+            short_name: "control".to_string(),
+        };
+        self.connect_ports(port, &destination)
+    }
+
     pub fn close(&self) {
         debug!("Signaling JACK client shutdown");
         self._active_client_handle.store(true, Ordering::SeqCst);
+        self.alsa_connections.lock().unwrap().clear();
         // Give the thread time to cleanup
         std::thread::sleep(std::time::Duration::from_millis(200));
     }
@@ -429,6 +980,41 @@ impl Driver {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_describe_midi() {
+        assert_eq!(describe_midi(&[0x90, 60, 100]), "Note On ch1 C4 vel 100");
+        assert_eq!(describe_midi(&[0x91, 61, 0]), "Note Off ch2 C#4");
+        assert_eq!(describe_midi(&[0xB0, 21, 64]), "CC ch1 #21 = 64");
+        assert_eq!(describe_midi(&[0xE0, 0, 64]), "Pitch Bend ch1 8192");
+
+        let mut monitor = MidiMonitor::default();
+        monitor.record("keys", &[0xF8]);
+        assert_eq!(monitor.generation, 0);
+        for _ in 0..MONITOR_SIZE + 1 {
+            monitor.record("keys", &[0x90, 60, 100]);
+        }
+        assert_eq!(monitor.messages.len(), MONITOR_SIZE);
+        assert!(monitor.activity.contains_key("keys"));
+    }
+
+    #[test]
+    fn test_panic_messages() {
+        let messages = panic_messages();
+        assert_eq!(messages.len(), 48);
+        assert_eq!(messages[0], [0xB0, 64, 0]);
+        assert_eq!(messages[47], [0xBF, 123, 0]);
+    }
+
+    #[test]
+    fn test_alias_label() {
+        assert_eq!(
+            Driver::alias_label("alsa_pcm:USB-Audio/capture_1", "capture_1"),
+            Some("USB-Audio/capture_1".to_string())
+        );
+        assert_eq!(Driver::alias_label("alsa_pcm:capture_1", "capture_1"), None);
+        assert_eq!(Driver::alias_label("", "capture_1"), None);
+    }
+
     #[test]
     fn test_parse_control_change() {
         let data = [0xB0, 0x07, 0x64]; // CC 7 (volume), value 100, channel 0
@@ -448,6 +1034,13 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_raw_round_trip() {
+        for data in [vec![0xB3, 0x15, 0x41], vec![0xC1, 0x05], vec![0xE0, 0x01, 0x40], vec![0xA2, 0x3C, 0x10], vec![0xFA]] {
+            assert_eq!(MidiEvent::from_raw(&data).unwrap().to_raw(), data);
+        }
+    }
+
     #[test]
     fn test_ignore_note_on() {
         let data = [0x90, 0x3C, 0x64]; // Note On, middle C, velocity 100
@@ -455,6 +1048,27 @@ mod tests {
         assert!(event.is_none());
     }
 
+    #[test]
+    fn test_parse_clock_messages() {
+        assert!(matches!(MidiEvent::from_raw(&[0xF8]), Some(MidiEvent::Clock)));
+        assert!(matches!(MidiEvent::from_raw(&[0xFA]), Some(MidiEvent::Start)));
+        assert!(matches!(MidiEvent::from_raw(&[0xFC]), Some(MidiEvent::Stop)));
+    }
+
+    #[test]
+    fn test_midi_clock_bpm() {
+        // 120 BPM at 48 kHz: 24000 frames per beat, 1000 frames per pulse
+        let mut clock = MidiClock::default();
+        let mut bpm = None;
+        for i in 0..=CLOCK_PULSES_PER_BEAT as u64 {
+            bpm = clock.pulse(i * 1000, 48000);
+        }
+        assert!((bpm.unwrap() - 120.0).abs() < 0.01);
+
+        clock.reset();
+        assert!(clock.pulse(100_000, 48000).is_none());
+    }
+
     #[test]
     fn test_parse_program_change() {
         let data = [0xC0, 0x05]; // Program change to program 5, channel 0
@@ -0,0 +1,325 @@
+use anyhow::Result;
+use log::{debug, info};
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::mixer::{is_hidden_block, node_of_port};
+use crate::controller::topology::check_connections;
+use crate::engine::{Connection, Engine};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Menu state for the arrange feature
+#[derive(Debug, Clone, PartialEq)]
+enum ArrangeMenuState {
+    MoveMenu,
+}
+
+/// Engine rewiring to swap two adjacent blocks
+#[derive(Debug, Default, PartialEq)]
+pub struct Rewiring {
+    pub remove: Vec<Connection>,
+    pub add: Vec<Connection>,
+}
+
+/// Get the port symbols of a node used by some connections, sorted to pair them by channel
+fn used_ports<'a>(connections: impl Iterator<Item = &'a String>) -> Vec<String> {
+    let mut ports: Vec<String> = connections.cloned().collect();
+    ports.sort();
+    ports.dedup();
+    ports
+}
+
+/// Rewire a chain "... -> upstream -> node -> ..." into "... -> node -> upstream -> ..."
+/// Ports are paired by sorted symbol, so stereo chains keep their channels
+pub fn swap_connections(connections: &[Connection], upstream: &str, node: &str) -> Result<Rewiring> {
+    let into_upstream: Vec<&Connection> = connections.iter()
+        .filter(|c| node_of_port(&c.destination) == upstream)
+        .collect();
+    let between: Vec<&Connection> = connections.iter()
+        .filter(|c| node_of_port(&c.source) == upstream && node_of_port(&c.destination) == node)
+        .collect();
+    let out_of_node: Vec<&Connection> = connections.iter()
+        .filter(|c| node_of_port(&c.source) == node)
+        .collect();
+
+    // Only swap nodes that form a simple chain
+    if between.is_empty() {
+        return Err(anyhow::anyhow!("Nodes are not connected"));
+    }
+    let feeds_others = connections.iter()
+        .any(|c| node_of_port(&c.source) == upstream && node_of_port(&c.destination) != node);
+    let fed_by_others = connections.iter()
+        .any(|c| node_of_port(&c.destination) == node && node_of_port(&c.source) != upstream);
+    if feeds_others || fed_by_others {
+        return Err(anyhow::anyhow!("Cannot move a node across a branch"));
+    }
+
+    let upstream_inputs = used_ports(into_upstream.iter().map(|c| &c.destination));
+    let upstream_outputs = used_ports(between.iter().map(|c| &c.source));
+    let node_inputs = used_ports(between.iter().map(|c| &c.destination));
+    let node_outputs = used_ports(out_of_node.iter().map(|c| &c.source));
+
+    // Port of the other list at the same position, wrapping for channel count mismatches
+    let paired = |port: &String, from: &[String], to: &[String]| -> Option<String> {
+        let index = from.iter().position(|p| p == port)?;
+        to.get(index % to.len().max(1)).cloned()
+    };
+
+    let mut rewiring = Rewiring::default();
+
+    // Sources of the upstream node now feed the node
+    for c in &into_upstream {
+        if let Some(destination) = paired(&c.destination, &upstream_inputs, &node_inputs) {
+            rewiring.add.push(Connection { source: c.source.clone(), destination });
+        }
+    }
+    // The node feeds the upstream node
+    for (i, source) in node_outputs.iter().enumerate() {
+        if let Some(destination) = upstream_inputs.get(i % upstream_inputs.len().max(1)) {
+            rewiring.add.push(Connection { source: source.clone(), destination: destination.clone() });
+        }
+    }
+    // Destinations of the node are now fed by the upstream node
+    for c in &out_of_node {
+        if let Some(source) = paired(&c.source, &node_outputs, &upstream_outputs) {
+            rewiring.add.push(Connection { source, destination: c.destination.clone() });
+        }
+    }
+
+    rewiring.remove = into_upstream.into_iter()
+        .chain(between)
+        .chain(out_of_node)
+        .cloned()
+        .collect();
+
+    Ok(rewiring)
+}
+
+/// Arrange feature for changing the order of nodes in a chain
+pub struct ArrangeFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: ArrangeMenuState,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl ArrangeFeature {
+    /// Create a new arrange feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: ArrangeMenuState::MoveMenu,
+            ui_element: None,
+        }
+    }
+
+    /// Get the move menu
+    fn get_move_menu(&self) -> Menu {
+        Menu {
+            id: "arrange_menu".to_string(),
+            label: "Move".to_string(),
+            options: vec![
+                MenuOption {
+                    id: "upstream".to_string(),
+                    label: "Move Upstream".to_string(),
+                },
+                MenuOption {
+                    id: "downstream".to_string(),
+                    label: "Move Downstream".to_string(),
+                },
+            ],
+        }
+    }
+
+    /// Get the single block neighbor of a node, upstream or downstream
+    fn neighbor(connections: &[Connection], node: &str, upstream: bool) -> Result<String> {
+        let mut neighbors: Vec<String> = connections.iter()
+            .filter_map(|c| {
+                let (from, to) = (node_of_port(&c.source), node_of_port(&c.destination));
+                if upstream && to == node {
+                    Some(from)
+                } else if !upstream && from == node {
+                    Some(to)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        neighbors.sort();
+        neighbors.dedup();
+
+        match neighbors.as_slice() {
+            [neighbor] => Ok(neighbor.clone()),
+            [] => Err(anyhow::anyhow!("Nothing to swap with")),
+            _ => Err(anyhow::anyhow!("Cannot move a node across a branch")),
+        }
+    }
+
+    /// Move a node one step upstream or downstream by swapping it with its neighbor
+    fn move_node(&self, node: &str, upstream: bool) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let neighbor = Self::neighbor(&graph.connections, node, upstream)?;
+
+        // System ports stay at the ends of the chain, with their trims and limiters
+        if !graph.blocks.iter().any(|b| b.id == neighbor) || is_hidden_block(&neighbor) {
+            return Err(anyhow::anyhow!("Already at the end of the chain"));
+        }
+
+        let (first, second) = if upstream { (neighbor.as_str(), node) } else { (node, neighbor.as_str()) };
+        let rewiring = swap_connections(&graph.connections, first, second)?;
+
+        // The connections left after the swap must not feed a node with its own output
+        let mut remaining = graph.clone();
+        remaining.connections.retain(|c| !rewiring.remove.contains(c));
+        check_connections(&remaining, &rewiring.add)?;
+
+        info!("Swapping {} and {}", first, second);
+
+        // Rewire the engine
+        for c in &rewiring.remove {
+            // Ignore error if connection doesn't exist
+            let _ = self.engine.disconnect(&c.source, &c.destination);
+        }
+        for c in &rewiring.add {
+            self.engine.connect(&c.source, &c.destination)?;
+        }
+
+        // Rewire the UI links between the nodes
+        let links = |connections: &[Connection]| {
+            let mut links: Vec<(String, String)> = connections.iter()
+                .map(|c| (node_of_port(&c.source), node_of_port(&c.destination)))
+                .collect();
+            links.sort();
+            links.dedup();
+            links
+        };
+        for (from, to) in links(&rewiring.remove) {
+            self.ui.remove_link(from, to)?;
+        }
+        for (from, to) in links(&rewiring.add) {
+            self.ui.create_link(from, to, crate::ui::LinkType::Normal)?;
+        }
+        self.ui.commit()?; // Commit the new order
+
+        Ok(())
+    }
+}
+
+impl Feature for ArrangeFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(_) => vec![ContextEntry::new(30, "move", "Move >")],
+            crate::ui::Element::Link(from_id, to_id, _) => {
+                // The order of the links leaving a node is the one of the secondary navigation
+                let model = self.ui.graph_model();
+                let mut entries = Vec::new();
+                if model.sibling(from_id, to_id, -1).is_some() {
+                    entries.push(ContextEntry::new(30, "link_earlier", "Move Link Earlier"));
+                }
+                if model.sibling(from_id, to_id, 1).is_some() {
+                    entries.push(ContextEntry::new(31, "link_later", "Move Link Later"));
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        let step = match option_id {
+            "link_earlier" => -1,
+            "link_later" => 1,
+            _ => {
+                self.set_element(element);
+                return Ok(ControllerState::BrowsingMenu);
+            }
+        };
+        if let Some(crate::ui::Element::Link(from_id, to_id, _)) = element {
+            if !self.ui.move_link(from_id, to_id, step)? {
+                self.ui.show_message("The link cannot move further")?;
+            }
+        }
+        Ok(ControllerState::Navigating)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match self.menu_state {
+            ArrangeMenuState::MoveMenu => self.get_move_menu(),
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Arrange feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        // Store the UI element if this is the first call
+        if self.ui_element.is_none() && element.is_some() {
+            self.ui_element = element.cloned();
+        }
+
+        // Handle menu closure
+        let Some(option) = option_id else {
+            debug!("Arrange feature: menu closed");
+            self.ui_element = None;
+            return Ok(ControllerState::Navigating);
+        };
+
+        let Some(crate::ui::Element::Node(node)) = self.ui_element.take() else {
+            return Err(anyhow::anyhow!("Arrange feature requires a node element"));
+        };
+
+        if let Err(e) = self.move_node(&node, option == "upstream") {
+            self.ui.show_message(&e.to_string())?;
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new arrange feature
+pub fn new_arrange_feature(engine: Arc<Engine>, ui: Arc<UI>) -> ArrangeFeature {
+    ArrangeFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(source: &str, destination: &str) -> Connection {
+        Connection {
+            source: format!("ingen:/main/{}", source),
+            destination: format!("ingen:/main/{}", destination),
+        }
+    }
+
+    #[test]
+    fn test_swap_connections() {
+        // in -> delay -> reverb -> out becomes in -> reverb -> delay -> out
+        let connections = vec![
+            connection("in", "delay/in"),
+            connection("delay/out", "reverb/in"),
+            connection("reverb/out", "out"),
+        ];
+        let rewiring = swap_connections(&connections, "ingen:/main/delay", "ingen:/main/reverb").unwrap();
+
+        assert_eq!(rewiring.remove.len(), 3);
+        assert_eq!(rewiring.add, vec![
+            connection("in", "reverb/in"),
+            connection("reverb/out", "delay/in"),
+            connection("delay/out", "out"),
+        ]);
+    }
+
+    #[test]
+    fn test_swap_refuses_branches() {
+        let connections = vec![
+            connection("in", "delay/in"),
+            connection("delay/out", "reverb/in"),
+            connection("delay/out", "chorus/in"),
+        ];
+        assert!(swap_connections(&connections, "ingen:/main/delay", "ingen:/main/reverb").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Number of bookmark slots
+pub const BOOKMARKS: u8 = 4;
+
+/// Node or link kept in a bookmark slot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Bookmark {
+    Node(String),
+    Link(String, String),
+}
+
+impl Bookmark {
+    /// Get the bookmark of a UI element
+    pub fn of_element(element: &crate::ui::Element) -> Option<Self> {
+        match element {
+            crate::ui::Element::Node(node) => Some(Bookmark::Node(node.clone())),
+            crate::ui::Element::Link(from_id, to_id, _) => Some(Bookmark::Link(from_id.clone(), to_id.clone())),
+            _ => None,
+        }
+    }
+
+    /// Get the label of the bookmark, with the node names
+    pub fn label(&self) -> String {
+        let name = |id: &str| id.rsplit('/').next().unwrap_or(id).to_string();
+        match self {
+            Bookmark::Node(node) => name(node),
+            Bookmark::Link(from_id, to_id) => format!("{} → {}", name(from_id), name(to_id)),
+        }
+    }
+}
+
+/// Bookmark feature keeping the nodes and links touched constantly one press away
+pub struct BookmarkFeature {
+    ui: Arc<UI>,
+    bookmarks: BTreeMap<u8, Bookmark>,
+    ui_element: Option<crate::ui::Element>,
+    /// Slot waiting for its button
+    learning: Option<u8>,
+}
+
+impl BookmarkFeature {
+    /// Create a new bookmark feature with the saved bookmarks
+    pub fn new(ui: Arc<UI>) -> Self {
+        Self {
+            ui,
+            bookmarks: Self::load_bookmarks(),
+            ui_element: None,
+            learning: None,
+        }
+    }
+
+    /// Get the bookmark file path
+    fn get_bookmarks_path() -> Result<PathBuf> {
+        Ok(Settings::get_home_dir()?.join("bookmarks.json"))
+    }
+
+    /// Load the saved bookmarks, none when the file is missing or invalid
+    fn load_bookmarks() -> BTreeMap<u8, Bookmark> {
+        let Ok(path) = Self::get_bookmarks_path() else {
+            return BTreeMap::new();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return BTreeMap::new();
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| warn!("Invalid bookmark file {:?}: {}", path, e))
+            .unwrap_or_default()
+    }
+
+    /// Save the bookmarks
+    fn save_bookmarks(&self) -> Result<()> {
+        let path = Self::get_bookmarks_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create bookmark directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&self.bookmarks)?)
+            .context("Failed to write bookmarks")
+    }
+
+    /// Take the slot whose button is being learned
+    pub fn take_learning_bookmark(&mut self) -> Option<u8> {
+        self.learning.take()
+    }
+
+    /// Move the grid focus to the element of a slot
+    pub fn recall(&self, slot: u8) -> Result<()> {
+        let Some(bookmark) = self.bookmarks.get(&slot) else {
+            return self.ui.show_message(&format!("Bookmark {} is empty", slot));
+        };
+        let model = self.ui.graph_model();
+        let shown = match bookmark {
+            Bookmark::Node(node) => model.nodes.contains_key(node),
+            Bookmark::Link(from_id, to_id) => model.links.contains(&(from_id.clone(), to_id.clone())),
+        };
+        if !shown {
+            return self.ui.show_message(&format!("{} is not in view", bookmark.label()));
+        }
+        debug!("Recalling bookmark {}: {:?}", slot, bookmark);
+        match bookmark {
+            Bookmark::Node(node) => self.ui.focus_node(node.clone()),
+            Bookmark::Link(from_id, to_id) => self.ui.focus_link(from_id.clone(), to_id.clone()),
+        }
+    }
+
+    /// Keep an element in a slot, replacing its previous bookmark
+    fn store(&mut self, slot: u8, bookmark: Bookmark) -> Result<()> {
+        info!("Bookmark {}: {:?}", slot, bookmark);
+        self.ui.show_message(&format!("Bookmark {}: {}", slot, bookmark.label()))?;
+        self.bookmarks.insert(slot, bookmark);
+        self.save_bookmarks()
+    }
+
+    /// Get the bookmark menu: recall the slots, keep the element in a slot or learn the button of a slot
+    fn get_bookmark_menu(&self) -> Menu {
+        let current = self.ui_element.as_ref().and_then(Bookmark::of_element);
+        let mut options: Vec<MenuOption> = self.bookmarks.iter()
+            .map(|(slot, bookmark)| MenuOption {
+                id: format!("recall_{}", slot),
+                label: format!("Go to {}: {}", slot, bookmark.label()),
+            })
+            .collect();
+        for slot in 1..=BOOKMARKS {
+            let label = match self.bookmarks.get(&slot) {
+                Some(bookmark) if current.as_ref() == Some(bookmark) => format!("Bookmark as {} ✓", slot),
+                Some(bookmark) => format!("Bookmark as {} (was {})", slot, bookmark.label()),
+                None => format!("Bookmark as {}", slot),
+            };
+            options.push(MenuOption { id: format!("store_{}", slot), label });
+        }
+        options.extend(self.bookmarks.keys().map(|slot| MenuOption {
+            id: format!("learn_{}", slot),
+            label: format!("Learn Button {}", slot),
+        }));
+
+        Menu {
+            id: "bookmarks".to_string(),
+            label: "Bookmarks".to_string(),
+            options,
+        }
+    }
+}
+
+impl Feature for BookmarkFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match Bookmark::of_element(element) {
+            Some(_) => vec![ContextEntry::new(119, "bookmarks", "Bookmarks >")],
+            None => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        self.get_bookmark_menu()
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Bookmark feature handle_menu_option: {:?}", option_id);
+
+        let Some((action, slot)) = option_id
+            .and_then(|option| option.split_once('_'))
+            .and_then(|(action, slot)| Some((action, slot.parse::<u8>().ok()?)))
+        else {
+            return Ok(ControllerState::Navigating);
+        };
+        match action {
+            "recall" => self.recall(slot)?,
+            "store" => {
+                if let Some(bookmark) = self.ui_element.as_ref().and_then(Bookmark::of_element) {
+                    self.store(slot, bookmark)?;
+                }
+            }
+            "learn" => {
+                self.learning = Some(slot);
+                self.ui.show_message(&format!("Press the button of bookmark {}", slot))?;
+                return Ok(ControllerState::LearningBookmarkButton);
+            }
+            _ => {}
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new bookmark feature
+pub fn new_bookmark_feature(ui: Arc<UI>) -> BookmarkFeature {
+    BookmarkFeature::new(ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmark_label() {
+        let link = crate::ui::Element::Link("ingen:/main/delay".to_string(), "outputs".to_string(), crate::ui::LinkType::Normal);
+        assert_eq!(Bookmark::of_element(&link).unwrap().label(), "delay → outputs");
+        assert_eq!(Bookmark::Node("ingen:/main/reverb".to_string()).label(), "reverb");
+    }
+}
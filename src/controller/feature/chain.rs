@@ -0,0 +1,470 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::group::{chain_from, view_scope};
+use crate::controller::feature::mixer::{FADER_PREFIX, destination_port, is_hidden_block, node_in_scope, source_port};
+use crate::controller::topology::check_connections;
+use crate::engine::{Connection, Engine, GRAPH_PROTOTYPE, Graph};
+use crate::ui::{Menu, MenuOption, NodeType, UI};
+
+/// Port of a block of a chain preset
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ChainPort {
+    /// Index of the block in the chain
+    pub block: usize,
+    /// Port symbol
+    pub port: String,
+}
+
+/// Block of a chain preset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainBlock {
+    /// Plugin URI
+    pub prototype: String,
+    /// Display name
+    pub name: String,
+    /// Block symbol without the number making it unique
+    pub symbol: String,
+    /// Control values keyed by port symbol
+    pub values: HashMap<String, f32>,
+}
+
+/// Blocks, connections and parameters of a chain, to insert it again on another link
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainPreset {
+    pub name: String,
+    /// Blocks in signal order
+    pub blocks: Vec<ChainBlock>,
+    /// Connections between the blocks, as (source, destination)
+    pub connections: Vec<(ChainPort, ChainPort)>,
+    /// Ports of the first block fed by the link the chain is inserted on
+    pub inputs: Vec<ChainPort>,
+    /// Ports of the last block feeding the rest of the link
+    pub outputs: Vec<ChainPort>,
+}
+
+impl ChainPreset {
+    /// Capture a chain of blocks of a graph, with their current control values
+    pub fn capture(engine: &Engine, name: &str, chain: &[String], scope: &str) -> Result<Self> {
+        let graph = engine.get_graph()?;
+        let chain_port = |port_path: &str| -> Option<ChainPort> {
+            let node = node_in_scope(port_path, scope)?;
+            let block = chain.iter().position(|b| *b == node)?;
+            let port = port_path.get(node.len() + 1..).filter(|port| !port.is_empty())?;
+            Some(ChainPort { block, port: port.to_string() })
+        };
+
+        let mut blocks = Vec::new();
+        for block_path in chain {
+            let block = graph.blocks.iter()
+                .find(|b| &b.id == block_path)
+                .ok_or_else(|| anyhow::anyhow!("Unknown block: {}", block_path))?;
+            // Keep the values of the plugin controls only
+            let controls = engine.get_block_plugin(block_path).map(|plugin| plugin.controls).unwrap_or_default();
+            let values = engine.get_control_values(block_path)?
+                .into_iter()
+                .filter(|(symbol, _)| controls.iter().any(|c| &c.id == symbol))
+                .collect();
+            let symbol = block_path.rsplit('/').next().unwrap_or(block_path)
+                .trim_end_matches(|c: char| c.is_ascii_digit())
+                .trim_end_matches('_');
+            blocks.push(ChainBlock {
+                prototype: block.prototype.clone(),
+                name: block.name.clone(),
+                symbol: if symbol.is_empty() { "block".to_string() } else { symbol.to_string() },
+                values,
+            });
+        }
+
+        let mut preset = Self {
+            name: name.to_string(),
+            blocks,
+            connections: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let last = chain.len().saturating_sub(1);
+        for (source, destination) in direct_connections(&graph, scope) {
+            match (chain_port(&source), chain_port(&destination)) {
+                (Some(source), Some(destination)) => preset.connections.push((source, destination)),
+                (None, Some(destination)) if destination.block == 0 => preset.inputs.push(destination),
+                (Some(source), None) if source.block == last => preset.outputs.push(source),
+                _ => {}
+            }
+        }
+        preset.connections.sort();
+        preset.connections.dedup();
+        preset.inputs.sort();
+        preset.inputs.dedup();
+        preset.outputs.sort();
+        preset.outputs.dedup();
+        Ok(preset)
+    }
+
+    /// Describe the preset with the names of its blocks, for a menu
+    pub fn label(&self) -> String {
+        let names: Vec<&str> = self.blocks.iter().map(|b| b.name.as_str()).collect();
+        format!("{} ({})", self.name, names.join(" → "))
+    }
+}
+
+/// Get the port connections of a graph with the hidden gains looked through, as (source, destination)
+fn direct_connections(graph: &Graph, scope: &str) -> Vec<(String, String)> {
+    let mut connections = Vec::new();
+    for connection in &graph.connections {
+        let mut queue = vec![connection.destination.clone()];
+        let mut visited = HashSet::new();
+        while let Some(destination) = queue.pop() {
+            match node_in_scope(&destination, scope) {
+                Some(node) if is_hidden_block(&node) => {
+                    if visited.insert(node.clone()) {
+                        queue.extend(graph.connections.iter()
+                            .filter(|c| node_in_scope(&c.source, scope).as_ref() == Some(&node))
+                            .map(|c| c.destination.clone()));
+                    }
+                }
+                _ => connections.push((connection.source.clone(), destination)),
+            }
+        }
+    }
+    connections
+}
+
+/// Check whether a node is a plugin block that can be copied
+fn is_copyable(graph: &Graph, node: &str) -> bool {
+    graph.blocks.iter().any(|b| b.id == node && b.prototype != GRAPH_PROTOTYPE && !b.name.starts_with(FADER_PREFIX))
+        && !is_hidden_block(node)
+}
+
+/// Menu state for the chain preset feature
+#[derive(Debug, Clone, PartialEq)]
+enum ChainMenuState {
+    PresetSelection,
+}
+
+/// Chain preset feature saving a chain of blocks and inserting it on other links
+/// Single blocks can also be copied and pasted on links
+pub struct ChainPresetFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: ChainMenuState,
+    ui_element: Option<crate::ui::Element>,
+    /// Block copied with its values, to paste on a link
+    clipboard: Option<ChainPreset>,
+}
+
+impl ChainPresetFeature {
+    /// Create a new chain preset feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: ChainMenuState::PresetSelection,
+            ui_element: None,
+            clipboard: None,
+        }
+    }
+
+    /// Get the chain preset directory
+    fn get_preset_dir() -> Result<PathBuf> {
+        Ok(Settings::get_home_dir()?.join("chains"))
+    }
+
+    /// List the chain presets, sorted by name
+    fn list_presets() -> Vec<ChainPreset> {
+        let Ok(entries) = Self::get_preset_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+            return Vec::new();
+        };
+
+        let mut presets: Vec<ChainPreset> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let content = fs::read_to_string(&path).ok()?;
+                serde_json::from_str(&content)
+                    .map_err(|e| warn!("Invalid chain preset file {:?}: {}", path, e))
+                    .ok()
+            })
+            .collect();
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        presets
+    }
+
+    /// Get the preset selection menu
+    fn get_preset_selection_menu(&self) -> Menu {
+        Menu {
+            id: "chain_preset_list".to_string(),
+            label: "Chain Presets".to_string(),
+            options: Self::list_presets().into_iter()
+                .map(|preset| MenuOption {
+                    id: preset.name.clone(),
+                    label: preset.label(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Save the chain starting at a node as a new chain preset
+    fn save_chain(&self, node: &str) -> Result<String> {
+        let chain = chain_from(&self.engine.get_graph()?, node);
+        anyhow::ensure!(!chain.is_empty(), "No chain to save from this node");
+
+        // Name the preset after the first free number
+        let existing = Self::list_presets();
+        let name = (1..)
+            .map(|i| format!("Chain {}", i))
+            .find(|name| !existing.iter().any(|p| &p.name == name))
+            .unwrap_or_default();
+
+        let preset = ChainPreset::capture(&self.engine, &name, &chain, &view_scope(&self.ui))?;
+        let dir = Self::get_preset_dir()?;
+        fs::create_dir_all(&dir)
+            .context("Failed to create chain preset directory")?;
+        fs::write(dir.join(format!("{}.json", name)), serde_json::to_string_pretty(&preset)?)
+            .context("Failed to write chain preset")?;
+
+        info!("Saved chain preset '{}' of {} blocks", name, preset.blocks.len());
+        Ok(name)
+    }
+
+    /// Create the blocks of a preset on a link, in place of the connections behind it
+    fn insert_chain(&self, preset: &ChainPreset, link_from: &str, link_to: &str) -> Result<()> {
+        anyhow::ensure!(!preset.inputs.is_empty() && !preset.outputs.is_empty(), "Chain preset {} has no inputs or outputs", preset.name);
+
+        let scope = view_scope(&self.ui);
+        let graph = self.engine.get_graph()?;
+        let source_node = node_in_scope(&source_port(&self.engine, link_from), &scope);
+        let destination_node = node_in_scope(&destination_port(&self.engine, link_to), &scope);
+        let mut behind: Vec<Connection> = graph.connections.iter()
+            .filter(|c| node_in_scope(&c.source, &scope) == source_node
+                && node_in_scope(&c.destination, &scope) == destination_node)
+            .cloned()
+            .collect();
+        anyhow::ensure!(!behind.is_empty(), "No engine connection behind this link");
+        // Pair the channels in order
+        behind.sort_by(|a, b| a.destination.cmp(&b.destination));
+
+        info!("Inserting chain preset '{}' between {} and {}", preset.name, link_from, link_to);
+
+        // Unique paths for the new blocks
+        let suffix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() % 1000000;
+        let mut paths = Vec::new();
+        for (i, block) in preset.blocks.iter().enumerate() {
+            let path = format!("{}/{}_{}", scope, block.symbol, suffix + i as u128);
+            let block_id = path.strip_prefix("ingen:/main/").unwrap_or(&path);
+            self.engine.create_block(&block.prototype, block_id)?;
+            for (symbol, value) in &block.values {
+                self.engine.set_control_parameter(&path, symbol, *value)?;
+            }
+            self.engine.set_block_name(&path, &block.name)?;
+            paths.push(path);
+        }
+        let port_path = |port: &ChainPort| -> Result<String> {
+            let block = paths.get(port.block)
+                .ok_or_else(|| anyhow::anyhow!("Chain preset {} has no block {}", preset.name, port.block))?;
+            Ok(format!("{}/{}", block, port.port))
+        };
+
+        let mut connections = Vec::new();
+        for (source, destination) in &preset.connections {
+            connections.push(Connection { source: port_path(source)?, destination: port_path(destination)? });
+        }
+        for (i, connection) in behind.iter().enumerate() {
+            let input = &preset.inputs[i % preset.inputs.len()];
+            let output = &preset.outputs[i % preset.outputs.len()];
+            connections.push(Connection { source: connection.source.clone(), destination: port_path(input)? });
+            connections.push(Connection { source: port_path(output)?, destination: connection.destination.clone() });
+        }
+
+        // A preset edited by hand may loop on itself, leave the graph as it was then
+        self.engine.invalidate_graph();
+        if let Err(e) = check_connections(&self.engine.get_graph()?, &connections) {
+            for path in &paths {
+                self.engine.delete(path)?;
+            }
+            return Err(e);
+        }
+        for connection in &connections {
+            self.engine.connect(&connection.source, &connection.destination)?;
+        }
+        for connection in &behind {
+            self.engine.disconnect(&connection.source, &connection.destination)?;
+        }
+
+        // Insert the nodes one after the other on the link
+        let mut from = link_from.to_string();
+        for (path, block) in paths.iter().zip(&preset.blocks) {
+            self.ui.insert_node(path.clone(), block.name.clone(), NodeType::Normal, from, link_to.to_string())?;
+            from = path.clone();
+        }
+        self.ui.commit()
+    }
+}
+
+impl Feature for ChainPresetFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => {
+                let graph = self.engine.get_graph().unwrap_or_default();
+                let mut entries = Vec::new();
+                if !chain_from(&graph, node).is_empty() {
+                    entries.push(ContextEntry::new(25, "save_chain", "Save Chain as Preset"));
+                }
+                if is_copyable(&graph, node) {
+                    entries.push(ContextEntry::new(26, "copy_node", "Copy"));
+                }
+                entries
+            }
+            crate::ui::Element::Link(from, to, _) if from != "inputs" && to != "outputs" => {
+                let mut entries = Vec::new();
+                if let Some(copied) = &self.clipboard {
+                    let name = copied.blocks.first().map(|b| b.name.as_str()).unwrap_or_default();
+                    entries.push(ContextEntry::new(31, "paste_node", &format!("Paste {}", name)));
+                }
+                if !Self::list_presets().is_empty() {
+                    entries.push(ContextEntry::new(32, "insert_chain", "Insert Chain Preset >"));
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        match (option_id, element) {
+            ("save_chain", Some(crate::ui::Element::Node(node))) => {
+                match self.save_chain(node) {
+                    Ok(name) => self.ui.show_message(&format!("Saved {}", name))?,
+                    Err(e) => self.ui.show_message(&e.to_string())?,
+                }
+                Ok(ControllerState::Navigating)
+            }
+            ("copy_node", Some(crate::ui::Element::Node(node))) => {
+                let copied = ChainPreset::capture(&self.engine, "Clipboard", std::slice::from_ref(node), &view_scope(&self.ui))?;
+                self.ui.show_message(&format!("Copied {}", copied.blocks[0].name))?;
+                self.clipboard = Some(copied);
+                Ok(ControllerState::Navigating)
+            }
+            ("paste_node", Some(crate::ui::Element::Link(from, to, _))) => {
+                if let Some(copied) = &self.clipboard {
+                    if let Err(e) = self.insert_chain(copied, from, to) {
+                        self.ui.show_message(&e.to_string())?;
+                    }
+                }
+                Ok(ControllerState::Navigating)
+            }
+            _ => {
+                self.set_element(element);
+                self.menu_state = ChainMenuState::PresetSelection;
+                Ok(ControllerState::BrowsingMenu)
+            }
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        match self.menu_state {
+            ChainMenuState::PresetSelection => self.get_preset_selection_menu(),
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Chain preset feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        let ui_element = self.ui_element.take().or_else(|| element.cloned());
+        let (Some(option), Some(crate::ui::Element::Link(from, to, _))) = (option_id, ui_element) else {
+            return Ok(ControllerState::Navigating);
+        };
+
+        let preset = Self::list_presets().into_iter()
+            .find(|p| p.name == option)
+            .ok_or_else(|| anyhow::anyhow!("Chain preset not found: {}", option))?;
+        match self.insert_chain(&preset, &from, &to) {
+            Ok(()) => self.ui.show_message(&format!("Inserted {}", preset.name))?,
+            Err(e) => self.ui.show_message(&e.to_string())?,
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new chain preset feature
+pub fn new_chain_preset_feature(engine: Arc<Engine>, ui: Arc<UI>) -> ChainPresetFeature {
+    ChainPresetFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::feature::mixer::MAIN_GRAPH;
+    use crate::engine::PortType;
+
+    #[test]
+    fn test_capture_and_insert_chain() {
+        let engine = Arc::new(Engine::new_mock());
+        engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_output_port("audio_out_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay_123").unwrap();
+        engine.create_block("urn:traxdub:mock:reverb", "reverb_456").unwrap();
+        engine.set_control_parameter("ingen:/main/delay_123", "time", 500.0).unwrap();
+        engine.connect("ingen:/main/audio_in_1", "ingen:/main/delay_123/in").unwrap();
+        engine.connect("ingen:/main/delay_123/out", "ingen:/main/reverb_456/in_l").unwrap();
+        engine.connect("ingen:/main/reverb_456/out_l", "ingen:/main/audio_out_1").unwrap();
+
+        let chain = vec!["ingen:/main/delay_123".to_string(), "ingen:/main/reverb_456".to_string()];
+        let preset = ChainPreset::capture(&engine, "Chain 1", &chain, MAIN_GRAPH).unwrap();
+        assert_eq!(preset.blocks[0].symbol, "delay");
+        assert_eq!(preset.blocks[0].values["time"], 500.0);
+        assert_eq!(preset.connections, vec![(
+            ChainPort { block: 0, port: "out".to_string() },
+            ChainPort { block: 1, port: "in_l".to_string() },
+        )]);
+        assert_eq!(preset.inputs, vec![ChainPort { block: 0, port: "in".to_string() }]);
+        assert_eq!(preset.outputs, vec![ChainPort { block: 1, port: "out_l".to_string() }]);
+
+        // Insert a copy after the reverb
+        let feature = ChainPresetFeature::new(Arc::clone(&engine), Arc::new(UI::new()));
+        feature.insert_chain(&preset, "ingen:/main/reverb_456", "ingen:/main/audio_out_1").unwrap();
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(graph.blocks.len(), 4);
+        assert_eq!(graph.connections.len(), 5);
+        assert!(!graph.connections.iter().any(|c| c.destination == "ingen:/main/audio_out_1"
+            && c.source == "ingen:/main/reverb_456/out_l"));
+    }
+
+    #[test]
+    fn test_copy_paste_node() {
+        let engine = Arc::new(Engine::new_mock());
+        engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_output_port("audio_out_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.set_control_parameter("ingen:/main/delay", "time", 250.0).unwrap();
+        engine.connect("ingen:/main/audio_in_1", "ingen:/main/delay/in").unwrap();
+        engine.connect("ingen:/main/delay/out", "ingen:/main/audio_out_1").unwrap();
+        assert!(is_copyable(&engine.get_graph().unwrap(), "ingen:/main/delay"));
+
+        let mut feature = ChainPresetFeature::new(Arc::clone(&engine), Arc::new(UI::new()));
+        let node = crate::ui::Element::Node("ingen:/main/delay".to_string());
+        feature.select_context_entry("copy_node", Some(&node)).unwrap();
+        let link = crate::ui::Element::Link("ingen:/main/delay".to_string(), "ingen:/main/audio_out_1".to_string(), crate::ui::LinkType::Normal);
+        feature.select_context_entry("paste_node", Some(&link)).unwrap();
+
+        let graph = engine.get_graph().unwrap();
+        let copy = graph.blocks.iter().find(|b| b.id != "ingen:/main/delay").unwrap();
+        assert_eq!(engine.get_control_values(&copy.id).unwrap()["time"], 250.0);
+        assert!(graph.connections.iter().any(|c| c.source == "ingen:/main/delay/out" && c.destination == format!("{}/in", copy.id)));
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use log::{debug, info};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::group::view_scope;
+use crate::controller::feature::mixer::{FADER_PREFIX, GAIN_CONTROL, TRIM_PREFIX, gain_control, is_hidden_block, node_in_scope, trim_block};
+use crate::engine::{Engine, Graph, PortDirection, PortType};
+use crate::ui::{Menu, UI};
+
+/// Peak level from which a port is considered clipping (full scale)
+const CLIP_LEVEL: f32 = 1.0;
+
+/// Time a clip marker stays after the last clip
+const CLIP_HOLD: Duration = Duration::from_secs(10);
+
+/// Time between the checks of the ports to monitor
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Highest peak of a clipping node
+#[derive(Debug, Clone, Copy)]
+struct Clip {
+    peak: f32,
+    last: Instant,
+}
+
+/// Get the audio output ports of which the level is checked:
+/// those of the plugin blocks, the input trims and the system outputs
+pub fn level_ports(graph: &Graph) -> HashSet<String> {
+    let block_ports = graph.blocks.iter()
+        .filter(|b| !b.name.starts_with(FADER_PREFIX))
+        .filter(|b| !is_hidden_block(&b.id) || b.id.rsplit('/').next().is_some_and(|name| name.starts_with(TRIM_PREFIX)))
+        .flat_map(|b| b.ports.iter()
+            .filter(|p| p.port_type == PortType::Audio && p.direction == PortDirection::Output)
+            .map(move |p| format!("{}/{}", b.id, p.id)));
+    let system_ports = graph.ports.iter()
+        .filter(|p| p.port_type == PortType::Audio && p.direction == PortDirection::Output)
+        .map(|p| format!("ingen:/main/{}", p.id));
+    block_ports.chain(system_ports).collect()
+}
+
+/// Get the node shown for a port whose level is checked, the input node for the output of its trim
+pub fn level_node(port_path: &str, scope: &str) -> Option<String> {
+    let node = node_in_scope(port_path, scope)?;
+    let name = node.rsplit('/').next().unwrap_or(&node);
+    match name.strip_prefix(TRIM_PREFIX) {
+        Some(input) => Some(format!("{}/{}", scope, input)),
+        None if is_hidden_block(&node) => None,
+        None => Some(node),
+    }
+}
+
+/// Get the gain reduction bringing a peak back to full scale in dB, rounded up to half a dB
+pub fn trim_suggestion(peak: f32) -> f32 {
+    let over = 20.0 * peak.max(CLIP_LEVEL).log10();
+    (over * 2.0).ceil() / 2.0
+}
+
+/// Clip feature marking the nodes whose output clips and suggesting a trim
+pub struct ClipFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    /// Ports the engine notifies the level of
+    monitored: HashSet<String>,
+    /// Last check of the ports to monitor
+    last_monitor: Option<Instant>,
+    /// Clipping nodes shown with a marker
+    clips: HashMap<String, Clip>,
+}
+
+impl ClipFeature {
+    /// Create a new clip feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            monitored: HashSet::new(),
+            last_monitor: None,
+            clips: HashMap::new(),
+        }
+    }
+
+    /// Follow the ports of the graph, check their peaks and refresh the clip markers
+    pub fn update(&mut self) -> Result<()> {
+        let now = Instant::now();
+        if self.last_monitor.is_none_or(|last| now.duration_since(last) >= MONITOR_INTERVAL) {
+            self.last_monitor = Some(now);
+            self.update_monitored_ports()?;
+        }
+
+        let scope = view_scope(&self.ui);
+        for (port, peak) in self.engine.take_peaks()? {
+            if peak < CLIP_LEVEL {
+                continue;
+            }
+            let Some(node) = level_node(&port, &scope) else {
+                continue;
+            };
+            debug!("Clip on {}: {:.2}", port, peak);
+            match self.clips.get_mut(&node) {
+                Some(clip) => {
+                    clip.peak = clip.peak.max(peak);
+                    clip.last = now;
+                }
+                None => {
+                    info!("{} clips", node);
+                    self.ui.set_node_clipping(node.clone(), true)?;
+                    self.clips.insert(node, Clip { peak, last: now });
+                }
+            }
+        }
+
+        let expired: Vec<String> = self.clips.iter()
+            .filter(|(_, clip)| now.duration_since(clip.last) >= CLIP_HOLD)
+            .map(|(node, _)| node.clone())
+            .collect();
+        for node in expired {
+            self.clear(&node)?;
+        }
+        Ok(())
+    }
+
+    /// Ask the engine for the levels of the new ports
+    fn update_monitored_ports(&mut self) -> Result<()> {
+        let ports = level_ports(&self.engine.get_graph()?);
+        for port in ports.difference(&self.monitored) {
+            self.engine.set_port_monitored(port, true)?;
+        }
+        // Ports gone with their blocks need no message
+        self.monitored = ports;
+        Ok(())
+    }
+
+    /// Remove the clip marker of a node
+    fn clear(&mut self, node: &str) -> Result<()> {
+        if self.clips.remove(node).is_some() {
+            self.ui.set_node_clipping(node.to_string(), false)?;
+        }
+        Ok(())
+    }
+
+    /// Lower the trim of a clipping input, or tell how much to lower the level into another node
+    fn apply_trim_suggestion(&mut self, node: &str) -> Result<()> {
+        let Some(clip) = self.clips.get(node).copied() else {
+            return Ok(());
+        };
+        let reduction = trim_suggestion(clip.peak);
+        let name = node.rsplit('/').next().unwrap_or(node);
+        let trim = trim_block(node);
+        let trimmed = self.engine.get_graph()?.blocks.iter().any(|b| b.id == trim);
+
+        if trimmed {
+            let level = self.engine.get_control_values(&trim)?.get(GAIN_CONTROL).copied().unwrap_or(0.0) - reduction;
+            let level = match gain_control(&self.engine) {
+                Some(control) => level.clamp(control.min, control.max),
+                None => level,
+            };
+            info!("Trimming {} to {:+.1} dB after a peak of {:.2}", name, level, clip.peak);
+            self.engine.set_control_parameter(&trim, GAIN_CONTROL, level)?;
+            self.ui.show_message(&format!("Trim {}: {:+.1} dB", name, level))?;
+        } else {
+            self.ui.show_message(&format!("Lower the level into {} by {:.1} dB", name, reduction))?;
+        }
+        self.clear(node)
+    }
+}
+
+impl Feature for ClipFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => match self.clips.get(node) {
+                Some(clip) => vec![ContextEntry::new(3, "clip_trim", &format!("Trim Clip (-{:.1} dB)", trim_suggestion(clip.peak)))],
+                None => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        if let ("clip_trim", Some(crate::ui::Element::Node(node))) = (option_id, element) {
+            self.apply_trim_suggestion(node)?;
+        }
+        Ok(ControllerState::Navigating)
+    }
+
+    fn get_menu(&self) -> Menu {
+        Menu {
+            id: "clip".to_string(),
+            label: "Clip".to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    fn handle_menu_option(&mut self, _option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new clip feature
+pub fn new_clip_feature(engine: Arc<Engine>, ui: Arc<UI>) -> ClipFeature {
+    ClipFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::feature::mixer::MAIN_GRAPH;
+
+    #[test]
+    fn test_level_node() {
+        assert_eq!(level_node("ingen:/main/delay/out", MAIN_GRAPH).as_deref(), Some("ingen:/main/delay"));
+        assert_eq!(level_node("ingen:/main/trim_audio_in_1/out", MAIN_GRAPH).as_deref(), Some("ingen:/main/audio_in_1"));
+        assert_eq!(level_node("ingen:/main/audio_out_1", MAIN_GRAPH).as_deref(), Some("ingen:/main/audio_out_1"));
+        assert_eq!(level_node("ingen:/main/bypass_wet_delay_0/out", MAIN_GRAPH), None);
+        assert_eq!(level_node("ingen:/main/group_1/delay/out", MAIN_GRAPH).as_deref(), Some("ingen:/main/group_1"));
+    }
+
+    #[test]
+    fn test_trim_suggestion() {
+        assert_eq!(trim_suggestion(1.0), 0.0);
+        assert_eq!(trim_suggestion(1.2), 2.0);
+        assert_eq!(trim_suggestion(2.0), 6.5);
+    }
+}
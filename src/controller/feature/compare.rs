@@ -0,0 +1,126 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::engine::StateContent;
+
+/// Prefix of the engine paths, left out of the listed connections
+const MAIN_PREFIX: &str = "ingen:/main/";
+
+/// Smallest control value change that is listed
+const VALUE_TOLERANCE: f32 = 1e-4;
+
+/// Differences between two saved states
+#[derive(Debug, Default, PartialEq)]
+pub struct StateDiff {
+    /// Names of the blocks only in the newer state
+    pub added_blocks: Vec<String>,
+    /// Names of the blocks only in the older state
+    pub removed_blocks: Vec<String>,
+    /// Connections only in the newer state, as (source, destination)
+    pub added_connections: Vec<(String, String)>,
+    /// Connections only in the older state
+    pub removed_connections: Vec<(String, String)>,
+    /// Control values of the blocks in both states that changed, as (block name, symbol, old, new)
+    pub changed_values: Vec<(String, String, f32, f32)>,
+}
+
+impl StateDiff {
+    /// Compare an older state with a newer one
+    pub fn between(old: &StateContent, new: &StateContent) -> Self {
+        let old_blocks: HashMap<&str, &str> = old.graph.blocks.iter().map(|b| (b.id.as_str(), b.name.as_str())).collect();
+        let new_blocks: HashMap<&str, &str> = new.graph.blocks.iter().map(|b| (b.id.as_str(), b.name.as_str())).collect();
+        let connections = |state: &StateContent| -> BTreeSet<(String, String)> {
+            state.graph.connections.iter()
+                .map(|c| (short_path(&c.source), short_path(&c.destination)))
+                .collect()
+        };
+        let (old_connections, new_connections) = (connections(old), connections(new));
+
+        let mut diff = Self {
+            added_blocks: new_blocks.iter().filter(|(id, _)| !old_blocks.contains_key(*id)).map(|(_, name)| name.to_string()).collect(),
+            removed_blocks: old_blocks.iter().filter(|(id, _)| !new_blocks.contains_key(*id)).map(|(_, name)| name.to_string()).collect(),
+            added_connections: new_connections.difference(&old_connections).cloned().collect(),
+            removed_connections: old_connections.difference(&new_connections).cloned().collect(),
+            changed_values: Vec::new(),
+        };
+
+        for (id, name) in &new_blocks {
+            let (Some(old_values), Some(new_values)) = (old.values.get(*id), new.values.get(*id)) else {
+                continue;
+            };
+            for (symbol, new_value) in new_values {
+                if let Some(old_value) = old_values.get(symbol) {
+                    if (new_value - old_value).abs() > VALUE_TOLERANCE {
+                        diff.changed_values.push((name.to_string(), symbol.clone(), *old_value, *new_value));
+                    }
+                }
+            }
+        }
+
+        diff.added_blocks.sort();
+        diff.removed_blocks.sort();
+        diff.changed_values.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        diff
+    }
+
+    /// Describe each difference on a line, for a menu
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        lines.extend(self.added_blocks.iter().map(|name| format!("+ {}", name)));
+        lines.extend(self.removed_blocks.iter().map(|name| format!("- {}", name)));
+        lines.extend(self.added_connections.iter().map(|(from, to)| format!("+ {} → {}", from, to)));
+        lines.extend(self.removed_connections.iter().map(|(from, to)| format!("- {} → {}", from, to)));
+        lines.extend(self.changed_values.iter()
+            .map(|(name, symbol, old, new)| format!("~ {} {}: {:.2} → {:.2}", name, symbol, old, new)));
+        lines
+    }
+}
+
+/// Shorten an engine path for display
+fn short_path(path: &str) -> String {
+    path.strip_prefix(MAIN_PREFIX).unwrap_or(path).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Block, Connection, Graph};
+
+    fn state(blocks: &[&str], connections: &[(&str, &str)], values: &[(&str, &str, f32)]) -> StateContent {
+        let mut content = StateContent {
+            graph: Graph {
+                blocks: blocks.iter().map(|id| Block {
+                    id: format!("ingen:/main/{}", id),
+                    name: id.to_string(),
+                    prototype: String::new(),
+                    ports: Vec::new(),
+                }).collect(),
+                connections: connections.iter().map(|(source, destination)| Connection {
+                    source: format!("ingen:/main/{}", source),
+                    destination: format!("ingen:/main/{}", destination),
+                }).collect(),
+                ports: Vec::new(),
+            },
+            values: HashMap::new(),
+        };
+        for (block, symbol, value) in values {
+            content.values.entry(format!("ingen:/main/{}", block)).or_default().insert(symbol.to_string(), *value);
+        }
+        content
+    }
+
+    #[test]
+    fn test_state_diff() {
+        let old = state(&["delay", "chorus"], &[("delay/out", "chorus/in")], &[("delay", "time", 0.5), ("delay", "feedback", 0.3)]);
+        let new = state(&["delay", "reverb"], &[("delay/out", "reverb/in")], &[("delay", "time", 0.75), ("delay", "feedback", 0.3)]);
+
+        let diff = StateDiff::between(&old, &new);
+        assert_eq!(diff.lines(), vec![
+            "+ reverb".to_string(),
+            "- chorus".to_string(),
+            "+ delay/out → reverb/in".to_string(),
+            "- delay/out → chorus/in".to_string(),
+            "~ delay time: 0.50 → 0.75".to_string(),
+        ]);
+        assert!(StateDiff::between(&new, &new).lines().is_empty());
+    }
+}
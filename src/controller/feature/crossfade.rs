@@ -0,0 +1,303 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, reconcile, feature::{ContextEntry, Feature}};
+use crate::controller::feature::group::view_scope;
+use crate::controller::feature::mixer::{self, GAIN_CONTROL, MAIN_GRAPH};
+use crate::engine::{Connection, Engine, Graph};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Prefixes of the block IDs of the crossfade gains of both sides, followed by the index of the source port
+const CROSSFADE_A: &str = "xfade_a";
+const CROSSFADE_B: &str = "xfade_b";
+
+/// Gain applied when a side is fully faded out, in dB
+const SILENCE_DB: f32 = -90.0;
+
+/// Menu state for the crossfade feature
+#[derive(Debug, Clone, PartialEq)]
+enum CrossfadeMenuState {
+    ChainSelection,
+}
+
+/// Get the gains in dB of both sides for a crossfade position between 0 (A) and 1 (B)
+/// Uses an equal-power curve so the loudness stays constant across the fade
+pub fn crossfade_gains(position: f32) -> (f32, f32) {
+    let angle = position.clamp(0.0, 1.0) * std::f32::consts::FRAC_PI_2;
+    let to_db = |gain: f32| if gain > 0.0 { (20.0 * gain.log10()).max(SILENCE_DB) } else { SILENCE_DB };
+    (to_db(angle.cos()), to_db(angle.sin()))
+}
+
+/// Check whether a block is a crossfade gain of a side
+fn is_side_gain(block_path: &str, side: &str) -> bool {
+    block_path.rsplit('/').next()
+        .and_then(|name| name.strip_prefix(side))
+        .and_then(|rest| rest.strip_prefix('_'))
+        .is_some_and(|index| index.parse::<usize>().is_ok())
+}
+
+/// Get the crossfade gains of a side, one per source port of its chain
+fn side_gains(graph: &Graph, side: &str) -> Vec<String> {
+    graph.blocks.iter()
+        .filter(|b| is_side_gain(&b.id, side))
+        .map(|b| b.id.clone())
+        .collect()
+}
+
+/// Get the connections of a chain the gains of its side go in front of
+fn side_connections(graph: &Graph, node_path: &str) -> Result<Vec<Connection>> {
+    let connections: Vec<Connection> = graph.connections.iter()
+        .filter(|c| mixer::node_of_port(&c.source) == node_path)
+        .cloned()
+        .collect();
+    anyhow::ensure!(!connections.is_empty(), "{} is not connected to anything", node_path);
+    Ok(connections)
+}
+
+/// Crossfade feature morphing between two chains with one knob
+pub struct CrossfadeFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: CrossfadeMenuState,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl CrossfadeFeature {
+    /// Create a new crossfade feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: CrossfadeMenuState::ChainSelection,
+            ui_element: None,
+        }
+    }
+
+    /// Get the selection menu of the second chain, among the nodes of the main graph
+    fn get_chain_selection_menu(&self) -> Menu {
+        let selected = match &self.ui_element {
+            Some(crate::ui::Element::Node(id)) => Some(id.as_str()),
+            _ => None,
+        };
+
+        let options = self.engine.get_graph()
+            .map(|graph| graph.blocks.into_iter()
+                .filter(|b| Some(b.id.as_str()) != selected && mixer::is_in_scope(&b.id, MAIN_GRAPH))
+                .filter(|b| !mixer::is_hidden_block(&b.id) && !mixer::is_fader(&b.id))
+                .filter(|b| !is_side_gain(&b.id, CROSSFADE_A) && !is_side_gain(&b.id, CROSSFADE_B))
+                .map(|b| MenuOption {
+                    id: b.id,
+                    label: b.name,
+                })
+                .collect())
+            .unwrap_or_else(|e| {
+                debug!("Error getting graph: {}", e);
+                Vec::new()
+            });
+
+        Menu {
+            id: "crossfade_chains".to_string(),
+            label: "Crossfade With".to_string(),
+            options,
+        }
+    }
+
+    /// Create the crossfade routing between two chains
+    /// Each source port of a chain gets its own gain so that a stereo chain stays stereo
+    fn create_crossfade(&self, chain_a: &str, chain_b: &str) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        if self.exists(&graph) {
+            return Err(anyhow::anyhow!("A crossfade already exists"));
+        }
+
+        // Both chains are checked before the first one is touched
+        let connections_a = side_connections(&graph, chain_a)?;
+        let connections_b = side_connections(&graph, chain_b)?;
+
+        info!("Inserting crossfade gains after {} and {}", chain_a, chain_b);
+        mixer::insert_gains(&self.engine, &self.ui, chain_a, &connections_a, CROSSFADE_A)?;
+        if let Err(e) = mixer::insert_gains(&self.engine, &self.ui, chain_b, &connections_b, CROSSFADE_B) {
+            if let Err(e) = self.remove_crossfade() {
+                warn!("Could not remove the half-made crossfade: {}", e);
+            }
+            return Err(e);
+        }
+
+        // Start fully on the first chain
+        self.set_position(0.0)
+    }
+
+    /// Check whether the crossfade is set up
+    fn exists(&self, graph: &Graph) -> bool {
+        !side_gains(graph, CROSSFADE_A).is_empty() || !side_gains(graph, CROSSFADE_B).is_empty()
+    }
+
+    /// Remove the crossfade gains, connecting the chains back to what the gains fed
+    fn remove_crossfade(&self) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let gains: Vec<String> = side_gains(&graph, CROSSFADE_A).into_iter()
+            .chain(side_gains(&graph, CROSSFADE_B))
+            .collect();
+        info!("Removing the crossfade gains: {:?}", gains);
+
+        self.engine.bundle(|engine| {
+            for gain in &gains {
+                let into = |c: &&Connection| mixer::node_of_port(&c.destination) == *gain;
+                let from = |c: &&Connection| mixer::node_of_port(&c.source) == *gain;
+                for source in graph.connections.iter().filter(into) {
+                    for destination in graph.connections.iter().filter(from) {
+                        engine.connect(&source.source, &destination.destination)?;
+                    }
+                }
+                engine.delete(gain)?;
+            }
+            Ok(())
+        })?;
+
+        // Show the chains linked again
+        reconcile::diff(&self.engine.get_graph()?, &self.ui.graph_model(), &view_scope(&self.ui)).repair(&self.ui)
+    }
+
+    /// Set the crossfade position between 0 (first chain) and 1 (second chain)
+    pub fn set_position(&self, position: f32) -> Result<()> {
+        let (gain_a, gain_b) = crossfade_gains(position);
+        debug!("Crossfade at {:.2}: {:.1} dB / {:.1} dB", position, gain_a, gain_b);
+
+        let graph = self.engine.get_graph()?;
+        for (side, gain) in [(CROSSFADE_A, gain_a), (CROSSFADE_B, gain_b)] {
+            for block_path in side_gains(&graph, side) {
+                self.engine.set_control_parameter(&block_path, GAIN_CONTROL, gain)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Feature for CrossfadeFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            // The gains go in the main graph, between its nodes
+            crate::ui::Element::Node(_) if view_scope(&self.ui) == MAIN_GRAPH => {
+                if self.exists(&self.engine.get_graph().unwrap_or_default()) {
+                    vec![ContextEntry::new(50, "remove_crossfade", "Remove Crossfade")]
+                } else {
+                    vec![ContextEntry::new(50, "crossfade", "Crossfade With >")]
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        if option_id == "remove_crossfade" {
+            match self.remove_crossfade() {
+                Ok(()) => self.ui.show_message("Crossfade removed")?,
+                Err(e) => self.ui.show_message(&e.to_string())?,
+            }
+            return Ok(ControllerState::Navigating);
+        }
+        self.set_element(element);
+        Ok(ControllerState::BrowsingMenu)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match self.menu_state {
+            CrossfadeMenuState::ChainSelection => self.get_chain_selection_menu(),
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Crossfade feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        // Store the UI element if this is the first call
+        if self.ui_element.is_none() && element.is_some() {
+            self.ui_element = element.cloned();
+        }
+
+        // Handle menu closure
+        let Some(chain_b) = option_id else {
+            debug!("Crossfade feature: menu closed");
+            self.ui_element = None;
+            return Ok(ControllerState::Navigating);
+        };
+
+        let Some(crate::ui::Element::Node(chain_a)) = self.ui_element.take() else {
+            return Err(anyhow::anyhow!("Crossfade feature requires a node element"));
+        };
+
+        self.create_crossfade(&chain_a, chain_b)?;
+        self.ui.show_message("Turn the crossfade knob")?;
+        Ok(ControllerState::LearningCrossfadeKnob)
+    }
+}
+
+/// Helper to create a new crossfade feature
+pub fn new_crossfade_feature(engine: Arc<Engine>, ui: Arc<UI>) -> CrossfadeFeature {
+    CrossfadeFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossfade_gains() {
+        let (a, b) = crossfade_gains(0.0);
+        assert!(a.abs() < 0.01);
+        assert_eq!(b, SILENCE_DB);
+
+        // Equal power in the middle: both sides at -3 dB
+        let (a, b) = crossfade_gains(0.5);
+        assert!((a + 3.01).abs() < 0.01);
+        assert!((b + 3.01).abs() < 0.01);
+
+        let (a, b) = crossfade_gains(1.0);
+        assert_eq!(a, SILENCE_DB);
+        assert!(b.abs() < 0.01);
+    }
+
+    #[test]
+    fn test_stereo_crossfade() {
+        let engine = Arc::new(Engine::new_mock());
+        let ui = Arc::new(UI::new());
+        engine.create_output_port("audio_out_1", crate::engine::PortType::Audio).unwrap();
+        engine.create_output_port("audio_out_2", crate::engine::PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:reverb", "reverb").unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.connect("ingen:/main/reverb/out_l", "ingen:/main/audio_out_1").unwrap();
+        engine.connect("ingen:/main/reverb/out_r", "ingen:/main/audio_out_2").unwrap();
+        engine.connect("ingen:/main/delay/out", "ingen:/main/audio_out_1").unwrap();
+
+        let feature = CrossfadeFeature::new(engine.clone(), ui);
+        feature.create_crossfade("ingen:/main/reverb", "ingen:/main/delay").unwrap();
+
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(side_gains(&graph, CROSSFADE_A).len(), 2);
+        assert_eq!(side_gains(&graph, CROSSFADE_B).len(), 1);
+        let connected = |source: &str, destination: &str| graph.connections.iter()
+            .any(|c| c.source == source && c.destination == destination);
+        assert!(connected("ingen:/main/xfade_a_0/out", "ingen:/main/audio_out_1"));
+        assert!(connected("ingen:/main/xfade_a_1/out", "ingen:/main/audio_out_2"));
+        assert!(!connected("ingen:/main/reverb/out_l", "ingen:/main/audio_out_1"));
+
+        // Removing the crossfade connects the chains back to the outputs
+        feature.remove_crossfade().unwrap();
+        let graph = engine.get_graph().unwrap();
+        assert!(!feature.exists(&graph));
+        let connected = |source: &str, destination: &str| graph.connections.iter()
+            .any(|c| c.source == source && c.destination == destination);
+        assert!(connected("ingen:/main/reverb/out_l", "ingen:/main/audio_out_1"));
+        assert!(connected("ingen:/main/reverb/out_r", "ingen:/main/audio_out_2"));
+        assert!(connected("ingen:/main/delay/out", "ingen:/main/audio_out_1"));
+
+        // A chain connected to nothing leaves the other one untouched
+        engine.create_block("urn:traxdub:mock:delay", "chorus").unwrap();
+        assert!(feature.create_crossfade("ingen:/main/reverb", "ingen:/main/chorus").is_err());
+        assert!(!feature.exists(&engine.get_graph().unwrap()));
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::controller::driver::{Driver, Port};
+use crate::controller::feature::group::view_scope;
+use crate::controller::feature::mixer::{GAIN_CONTROL, MAIN_GRAPH, create_trim, is_hidden_block, is_in_scope, source_port, trim_block};
+use crate::controller::reconcile;
+use crate::controller::topology::upstream_blocks;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::engine::{Engine, GRAPH_PROTOTYPE, Graph, PortDirection, PortType};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Gain of the return of a feedback loop in dB, low enough for the loop to die out at first
+const RETURN_GAIN_DB: f32 = -12.0;
+
+/// Get the first audio port of a block in a direction
+fn audio_port(graph: &Graph, block_path: &str, direction: PortDirection) -> Option<String> {
+    graph.blocks.iter()
+        .find(|b| b.id == block_path)?
+        .ports.iter()
+        .find(|p| p.port_type == PortType::Audio && p.direction == direction)
+        .map(|p| format!("{}/{}", block_path, p.id))
+}
+
+/// Get the name of the first pair of feedback ports not in the graph
+fn free_loop_name(graph: &Graph) -> String {
+    (1..)
+        .map(|n| format!("feedback_{}", n))
+        .find(|name| !graph.ports.iter().any(|p| p.id.starts_with(&format!("{}_", name))))
+        .unwrap()
+}
+
+/// Feedback feature sending the output of a block back to the input of a block feeding it
+/// Ingen refuses cycles in its graph, so the loop goes out through a system output and comes back
+/// through a system input connected to it in JACK, which delays it by one period. The return has a trim
+/// at a low gain to keep the loop from running away.
+pub struct FeedbackFeature {
+    driver: Arc<Driver>,
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl FeedbackFeature {
+    /// Create a new feedback feature
+    pub fn new(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            driver,
+            engine,
+            ui,
+            ui_element: None,
+        }
+    }
+
+    /// Get the blocks of the main graph a block can feed back to, itself included
+    fn targets(&self, graph: &Graph, block_path: &str) -> Vec<String> {
+        upstream_blocks(graph, block_path).into_iter()
+            .filter(|b| is_in_scope(b, MAIN_GRAPH) && !is_hidden_block(b))
+            .filter(|b| audio_port(graph, b, PortDirection::Input).is_some())
+            .collect()
+    }
+
+    /// Create a loop from the output of a block to the input of another one through JACK
+    fn create_loop(&self, source: &str, target: &str) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let output = audio_port(&graph, source, PortDirection::Output)
+            .ok_or_else(|| anyhow::anyhow!("{} has no audio output", source))?;
+        let input = audio_port(&graph, target, PortDirection::Input)
+            .ok_or_else(|| anyhow::anyhow!("{} has no audio input", target))?;
+
+        let name = free_loop_name(&graph);
+        let (send_name, return_name) = (format!("{}_send", name), format!("{}_return", name));
+        info!("Feeding {} back to {} through {}", source, target, name);
+
+        let send = self.engine.create_output_port(&send_name, PortType::Audio)?;
+        let ret = self.engine.create_input_port(&return_name, PortType::Audio)?;
+        create_trim(&self.engine, &ret)?;
+        self.engine.set_control_parameter(&trim_block(&ret), GAIN_CONTROL, RETURN_GAIN_DB)?;
+        self.engine.connect(&output, &send)?;
+        self.engine.connect(&source_port(&self.engine, &ret), &input)?;
+
+        // Retry the JACK connection as the engine ports are created asynchronously
+        let jack_port = |name: &str| Port { name: format!("TraxDub Engine:{}", name), short_name: name.to_string() };
+        let start_time = Instant::now();
+        while let Err(e) = self.driver.connect_ports(&jack_port(&send_name), &jack_port(&return_name)) {
+            if start_time.elapsed() > Duration::from_millis(1000) {
+                return Err(anyhow::anyhow!("Failed to close the feedback loop {}: {}", name, e));
+            }
+            warn!("Feedback loop connection attempt failed: {}", e);
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        // Show the new ports and their links
+        self.engine.invalidate_graph();
+        reconcile::diff(&self.engine.get_graph()?, &self.ui.graph_model(), &view_scope(&self.ui)).repair(&self.ui)
+    }
+}
+
+impl Feature for FeedbackFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) if is_in_scope(node, MAIN_GRAPH) && view_scope(&self.ui) == MAIN_GRAPH => {
+                let graph = self.engine.get_graph().unwrap_or_default();
+                let is_plugin = graph.blocks.iter().any(|b| &b.id == node && b.prototype != GRAPH_PROTOTYPE);
+                if is_plugin && audio_port(&graph, node, PortDirection::Output).is_some() && !self.targets(&graph, node).is_empty() {
+                    vec![ContextEntry::new(37, "feed_back", "Feed Back To >")]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        let options = match &self.ui_element {
+            Some(crate::ui::Element::Node(node)) => {
+                let graph = self.engine.get_graph().unwrap_or_default();
+                self.targets(&graph, node).into_iter()
+                    .map(|target| {
+                        let label = graph.blocks.iter().find(|b| b.id == target).map_or(target.clone(), |b| b.name.clone());
+                        MenuOption { id: target, label }
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        Menu {
+            id: "feedback".to_string(),
+            label: "Feed Back To".to_string(),
+            options,
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Feedback feature handle_menu_option: {:?}", option_id);
+
+        let (Some(target), Some(crate::ui::Element::Node(source))) = (option_id, self.ui_element.take()) else {
+            return Ok(ControllerState::Navigating);
+        };
+        self.create_loop(&source, target)?;
+        self.ui.show_message("Feedback loop created")?;
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new feedback feature
+pub fn new_feedback_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>) -> FeedbackFeature {
+    FeedbackFeature::new(driver, engine, ui)
+}
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::engine::{Engine, Graph};
+use crate::ui::{Menu, MenuOption, UI};
+use crate::ui::model::GraphModel;
+
+/// Prefix of the options keeping the nodes of a tag only
+const TAG_FILTER_PREFIX: &str = "tag:";
+
+/// Get the nodes shown in the grid, except the context nodes, sorted by name for the first-letter jumps
+/// With a tag, only the nodes having this tag
+pub fn goto_options(graph: &Graph, model: &GraphModel, tag: Option<&str>) -> Vec<MenuOption> {
+    let mut options: Vec<MenuOption> = model.nodes.keys()
+        .filter(|id| !model.is_context_node(id))
+        .filter(|id| tag.is_none() || model.tags.get(*id).map(|(t, _)| t.as_str()) == tag)
+        .map(|id| {
+            let label = graph.blocks.iter()
+                .find(|b| &b.id == id)
+                .map(|b| b.name.clone())
+                .unwrap_or_else(|| id.rsplit('/').next().unwrap_or(id).to_string());
+            MenuOption { id: id.clone(), label }
+        })
+        .collect();
+    options.sort_by(|a, b| a.label.to_lowercase().cmp(&b.label.to_lowercase()).then_with(|| a.id.cmp(&b.id)));
+    options
+}
+
+/// Get the options keeping the nodes of one tag, for the tags of the nodes shown, sorted
+pub fn tag_filter_options(model: &GraphModel) -> Vec<MenuOption> {
+    let mut tags: Vec<&String> = model.nodes.keys()
+        .filter_map(|id| model.tags.get(id).map(|(tag, _)| tag))
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags.into_iter()
+        .map(|tag| MenuOption {
+            id: format!("{}{}", TAG_FILTER_PREFIX, tag),
+            label: format!("Only {} >", tag),
+        })
+        .collect()
+}
+
+/// Go to feature listing the nodes by name to move the focus straight to one of them
+/// The nodes can be narrowed to the ones of a tag first
+pub struct GotoFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    /// Tag of the nodes listed, None for all of them
+    filter: Option<String>,
+}
+
+impl GotoFeature {
+    /// Create a new go to feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self { engine, ui, filter: None }
+    }
+}
+
+impl Feature for GotoFeature {
+    fn context_entries(&self, _element: &crate::ui::Element) -> Vec<ContextEntry> {
+        vec![ContextEntry::new(118, "goto", "Go to... >")]
+    }
+
+    fn set_element(&mut self, _element: Option<&crate::ui::Element>) {
+        // The menu opens on all the nodes
+        self.filter = None;
+    }
+
+    fn get_menu(&self) -> Menu {
+        let model = self.ui.graph_model();
+        let mut options = match &self.filter {
+            Some(_) => Vec::new(),
+            None => tag_filter_options(&model),
+        };
+        match self.engine.get_graph() {
+            Ok(graph) => options.extend(goto_options(&graph, &model, self.filter.as_deref())),
+            Err(e) => debug!("No graph to list the nodes: {}", e),
+        }
+        Menu {
+            id: "goto".to_string(),
+            label: self.filter.clone().unwrap_or_else(|| "Go to".to_string()),
+            options,
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        match option_id {
+            // Leaving the nodes of a tag goes back to all the nodes
+            None if self.filter.take().is_some() => return Ok(ControllerState::BrowsingMenu),
+            None => {}
+            Some(option) => {
+                if let Some(tag) = option.strip_prefix(TAG_FILTER_PREFIX) {
+                    self.filter = Some(tag.to_string());
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                debug!("Going to {}", option);
+                self.filter = None;
+                self.ui.focus_node(option.to_string())?;
+            }
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new go to feature
+pub fn new_goto_feature(engine: Arc<Engine>, ui: Arc<UI>) -> GotoFeature {
+    GotoFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Block;
+    use crate::ui::NodeType;
+
+    #[test]
+    fn test_goto_options() {
+        let graph = Graph {
+            blocks: ["reverb", "Delay", "amp"].iter().map(|name| Block {
+                id: format!("ingen:/main/{}_1", name.to_lowercase()),
+                name: name.to_string(),
+                prototype: String::new(),
+                ports: Vec::new(),
+            }).collect(),
+            ..Default::default()
+        };
+        let mut model = GraphModel::default();
+        model.create_node("inputs", NodeType::Context);
+        model.create_node("ingen:/main/reverb_1", NodeType::Normal);
+        model.create_node("ingen:/main/delay_1", NodeType::Normal);
+        model.create_node("ingen:/main/amp_1", NodeType::Normal);
+        model.create_node("ingen:/main/audio_in_1", NodeType::PortIn);
+
+        let labels: Vec<String> = goto_options(&graph, &model, None).into_iter().map(|o| o.label).collect();
+        assert_eq!(labels, vec!["amp", "audio_in_1", "Delay", "reverb"]);
+
+        model.tags.insert("ingen:/main/reverb_1".to_string(), ("FX".to_string(), 4));
+        model.tags.insert("ingen:/main/delay_1".to_string(), ("FX".to_string(), 4));
+        model.tags.insert("ingen:/main/gone_1".to_string(), ("Drums".to_string(), 1));
+        let filters: Vec<String> = tag_filter_options(&model).into_iter().map(|o| o.id).collect();
+        assert_eq!(filters, vec!["tag:FX"]);
+        let labels: Vec<String> = goto_options(&graph, &model, Some("FX")).into_iter().map(|o| o.label).collect();
+        assert_eq!(labels, vec!["Delay", "reverb"]);
+    }
+}
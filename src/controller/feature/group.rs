@@ -0,0 +1,533 @@
+use anyhow::Result;
+use log::{debug, info};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::mixer::{FADER_PREFIX, MAIN_GRAPH, is_bypassed, is_hidden_block, is_in_scope, node_in_scope, node_of_port, visible_links_in};
+use crate::controller::feature::plugin::port_type_of;
+use crate::controller::feature::rename::follow_name;
+use crate::controller::reconcile;
+use crate::engine::{Engine, GRAPH_PROTOTYPE, Graph, PortDirection, PortType};
+use crate::engine::split::MONITOR_GRAPH;
+use crate::ui::{Menu, MenuOption, NodeState, UI};
+
+/// Menu state for the group feature
+#[derive(Debug, Clone, PartialEq)]
+enum GroupMenuState {
+    ChainSelection(Vec<String>), // blocks of the chain from the selected node
+    EngineSelection(Vec<String>, String), // blocks of the chain and the graph of the other engine
+}
+
+/// Get the graph shown by the UI: the open group or the main graph
+pub fn view_scope(ui: &UI) -> String {
+    ui.view_scope().unwrap_or_else(|| MAIN_GRAPH.to_string())
+}
+
+/// Check whether a block can be moved into a group: a plugin block directly inside a graph,
+/// not a fader, a hidden gain, a bypassed block or a group itself
+fn is_groupable(graph: &Graph, block_path: &str, scope: &str) -> bool {
+    graph.blocks.iter().any(|b| b.id == block_path && b.prototype != GRAPH_PROTOTYPE && !b.name.starts_with(FADER_PREFIX))
+        && is_in_scope(block_path, scope)
+        && !is_hidden_block(block_path)
+        && !is_bypassed(graph, block_path)
+}
+
+/// Get the linear chain of blocks of the main graph starting at a node, following single links downstream
+/// The chain stops at branches, merges and blocks that cannot be grouped
+pub fn chain_from(graph: &Graph, start: &str) -> Vec<String> {
+    chain_in(graph, start, MAIN_GRAPH)
+}
+
+/// Get the linear chain of blocks starting at a node of a graph, following single links downstream
+fn chain_in(graph: &Graph, start: &str, scope: &str) -> Vec<String> {
+    if !is_groupable(graph, start, scope) {
+        return Vec::new();
+    }
+    let links = visible_links_in(graph, scope);
+    let mut chain = vec![start.to_string()];
+    loop {
+        let current = &chain[chain.len() - 1];
+        let next: Vec<&String> = links.iter()
+            .filter(|(from, _)| from == current)
+            .map(|(_, to)| to)
+            .collect();
+        let [next] = next.as_slice() else {
+            break;
+        };
+        let merges = links.iter().filter(|(_, to)| to == *next).count() > 1;
+        if merges || chain.contains(next) || !is_groupable(graph, next, scope) {
+            break;
+        }
+        chain.push(next.to_string());
+    }
+    chain
+}
+
+/// Group feature moving chains of blocks into Ingen subgraphs, shown as a single node that can be opened
+pub struct GroupFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: Option<GroupMenuState>,
+}
+
+impl GroupFeature {
+    /// Create a new group feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: None,
+        }
+    }
+
+    /// Get the name of a block
+    fn block_name(graph: &Graph, block_path: &str) -> String {
+        graph.blocks.iter()
+            .find(|b| b.id == block_path)
+            .map(|b| b.name.clone())
+            .unwrap_or_else(|| block_path.rsplit('/').next().unwrap_or(block_path).to_string())
+    }
+
+    /// Get the menu choosing where the group, or the part moved to the other engine, ends
+    fn get_chain_menu(&self, chain: &[String], label: &str) -> Menu {
+        let graph = self.engine.get_graph().unwrap_or_default();
+        Menu {
+            id: "group_chain_menu".to_string(),
+            label: label.to_string(),
+            options: chain.iter()
+                .enumerate()
+                .map(|(i, block)| MenuOption {
+                    id: format!("end_{}", i),
+                    label: if i == 0 {
+                        format!("{} Only", Self::block_name(&graph, block))
+                    } else {
+                        format!("Up to {}", Self::block_name(&graph, block))
+                    },
+                })
+                .collect(),
+        }
+    }
+
+    /// Bring the nodes in line with the graph of the current scope
+    fn show_scope(&self) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let drift = reconcile::diff(&graph, &self.ui.graph_model(), &view_scope(&self.ui));
+        drift.repair(&self.ui)?;
+        for (id, _, _) in &drift.missing_nodes {
+            if is_bypassed(&graph, id) {
+                self.ui.set_node_state(id.clone(), NodeState::Bypassed)?;
+            }
+        }
+        self.ui.commit()
+    }
+
+    /// Show the blocks of a group instead of the main graph
+    pub fn open(&self, group_path: &str) -> Result<()> {
+        info!("Opening group {}", group_path);
+        self.ui.set_view_scope(Some(group_path.to_string()));
+        self.show_scope()?;
+        let name = Self::block_name(&self.engine.get_graph()?, group_path);
+        self.ui.show_message(&format!("In {}, press back to leave", name))
+    }
+
+    /// Show the main graph again, returning false when no group was open
+    pub fn leave(&self) -> Result<bool> {
+        let Some(group_path) = self.ui.view_scope() else {
+            return Ok(false);
+        };
+        info!("Leaving group {}", group_path);
+        self.ui.set_view_scope(None);
+        self.show_scope()?;
+        Ok(true)
+    }
+
+    /// Move a chain of blocks into a new group, keeping their parameters, names and connections
+    /// Connections from and to the rest of the graph go through the ports of the group
+    /// Returns the path of the group
+    fn group_chain(&self, chain: &[String]) -> Result<String> {
+        let graph = self.engine.get_graph()?;
+        let group_id = (1..)
+            .map(|i| format!("group_{}", i))
+            .find(|id| !graph.blocks.iter().any(|b| b.id == format!("{}/{}", MAIN_GRAPH, id)))
+            .unwrap_or_default();
+        let group_path = format!("{}/{}", MAIN_GRAPH, group_id);
+        info!("Grouping {} blocks into {}", chain.len(), group_path);
+
+        self.engine.create_graph(&group_id)?;
+
+        // Recreate the blocks inside the group
+        let mut moved: HashMap<&str, String> = HashMap::new();
+        let mut names = Vec::new();
+        for block_path in chain {
+            let block = graph.blocks.iter()
+                .find(|b| &b.id == block_path)
+                .ok_or_else(|| anyhow::anyhow!("Unknown block: {}", block_path))?;
+            let symbol = block_path.rsplit('/').next().unwrap_or(block_path);
+            let block_id = format!("{}/{}", group_id, symbol);
+            self.engine.create_block(&block.prototype, &block_id)?;
+
+            let new_path = format!("{}/{}", MAIN_GRAPH, block_id);
+            for (parameter, value) in self.engine.get_control_values(block_path)? {
+                self.engine.set_control_parameter(&new_path, &parameter, value)?;
+            }
+            self.engine.set_block_name(&new_path, &block.name)?;
+            names.push(block.name.clone());
+            moved.insert(block_path.as_str(), new_path);
+        }
+
+        // Path of a port once its block is in the group
+        let relocate = |port_path: &str| -> Option<String> {
+            let node = node_of_port(port_path);
+            moved.get(node.as_str()).map(|new_path| format!("{}{}", new_path, &port_path[node.len()..]))
+        };
+
+        // Connections inside the chain stay inside, the others go through one group port per block port
+        let mut group_ports: HashMap<String, String> = HashMap::new();
+        let (mut inputs, mut outputs) = (0, 0);
+        let mut internal = Vec::new();
+        let mut external = Vec::new();
+        for connection in &graph.connections {
+            let port_type = || port_type_of(&graph, &connection.source)
+                .or_else(|| port_type_of(&graph, &connection.destination))
+                .unwrap_or(PortType::Audio);
+            match (relocate(&connection.source), relocate(&connection.destination)) {
+                (Some(source), Some(destination)) => internal.push((source, destination)),
+                (None, Some(destination)) => {
+                    let port = match group_ports.get(&destination) {
+                        Some(port) => port.clone(),
+                        None => {
+                            inputs += 1;
+                            let port = self.engine.create_input_port(&format!("{}/in_{}", group_id, inputs), port_type())?;
+                            internal.push((port.clone(), destination.clone()));
+                            group_ports.insert(destination, port.clone());
+                            port
+                        }
+                    };
+                    external.push((connection.source.clone(), port));
+                }
+                (Some(source), None) => {
+                    let port = match group_ports.get(&source) {
+                        Some(port) => port.clone(),
+                        None => {
+                            outputs += 1;
+                            let port = self.engine.create_output_port(&format!("{}/out_{}", group_id, outputs), port_type())?;
+                            internal.push((source.clone(), port.clone()));
+                            group_ports.insert(source, port.clone());
+                            port
+                        }
+                    };
+                    external.push((port, connection.destination.clone()));
+                }
+                (None, None) => {}
+            }
+        }
+        for (source, destination) in &internal {
+            self.engine.connect(source, destination)?;
+        }
+
+        // Remove the original blocks before connecting the group, so that the signal never goes through both
+        self.engine.bundle(|engine| {
+            for block_path in chain {
+                engine.delete(block_path)?;
+            }
+            external.iter().try_for_each(|(source, destination)| engine.connect(source, destination))
+        })?;
+
+        let name = match names.as_slice() {
+            [single] => single.clone(),
+            [first, ..] => format!("{} +{}", first, names.len() - 1),
+            [] => group_id.clone(),
+        };
+        self.engine.set_block_name(&group_path, &name)?;
+        let group_path = follow_name(&self.engine, &group_path, &name)?.unwrap_or(group_path);
+
+        self.show_scope()?;
+        Ok(group_path)
+    }
+
+    /// Move a chain of blocks to the graph of the other engine, keeping their parameters, names and the connections between them
+    /// Connections to the system ports follow through ports of the same name in the other engine,
+    /// the ones to the rest of the graph cannot cross engines and are left behind
+    /// Returns the number of connections left behind
+    fn move_chain(&self, chain: &[String], target: &str) -> Result<usize> {
+        let graph = self.engine.get_graph()?;
+        let scope = if target == MAIN_GRAPH { MONITOR_GRAPH } else { MAIN_GRAPH };
+        info!("Moving {} blocks from {} to {}", chain.len(), scope, target);
+
+        // Engine ids are relative to the main graph
+        let main_prefix = format!("{}/", MAIN_GRAPH);
+        let relative = |path: &str| path.strip_prefix(&main_prefix).unwrap_or(path).to_string();
+        let system_ports = |graph_path: &str| -> Vec<crate::engine::Port> {
+            if graph_path == MAIN_GRAPH {
+                graph.ports.clone()
+            } else {
+                graph.blocks.iter().find(|b| b.id == graph_path).map(|b| b.ports.clone()).unwrap_or_default()
+            }
+        };
+        let (scope_ports, target_ports) = (system_ports(scope), system_ports(target));
+
+        // Recreate the blocks in the other engine
+        let mut moved: HashMap<&str, String> = HashMap::new();
+        for block_path in chain {
+            let block = graph.blocks.iter()
+                .find(|b| &b.id == block_path)
+                .ok_or_else(|| anyhow::anyhow!("Unknown block: {}", block_path))?;
+            let symbol = block_path.rsplit('/').next().unwrap_or(block_path);
+            let new_path = (1..)
+                .map(|i| if i == 1 { format!("{}/{}", target, symbol) } else { format!("{}/{}_{}", target, symbol, i) })
+                .find(|path| !graph.blocks.iter().any(|b| &b.id == path))
+                .unwrap_or_default();
+            self.engine.create_block(&block.prototype, &relative(&new_path))?;
+            for (parameter, value) in self.engine.get_control_values(block_path)? {
+                self.engine.set_control_parameter(&new_path, &parameter, value)?;
+            }
+            self.engine.set_block_name(&new_path, &block.name)?;
+            moved.insert(block_path.as_str(), new_path);
+        }
+
+        // Path of a port once its block is moved, or of the system port of the same name in the other engine
+        let mut created = Vec::new();
+        let mut relocate = |port_path: &str| -> Result<Option<String>> {
+            let node = node_in_scope(port_path, scope).unwrap_or_default();
+            if let Some(new_path) = moved.get(node.as_str()) {
+                return Ok(Some(format!("{}{}", new_path, &port_path[node.len()..])));
+            }
+            let Some(port) = scope_ports.iter().find(|p| format!("{}/{}", scope, p.id) == port_path) else {
+                return Ok(None);
+            };
+            let path = format!("{}/{}", target, port.id);
+            let exists = target_ports.iter().any(|p| p.id == port.id) || created.contains(&path);
+            if !exists {
+                match port.direction {
+                    PortDirection::Input => self.engine.create_input_port(&relative(&path), port.port_type.clone())?,
+                    PortDirection::Output => self.engine.create_output_port(&relative(&path), port.port_type.clone())?,
+                };
+                created.push(path.clone());
+            }
+            Ok(Some(path))
+        };
+
+        let mut connections = Vec::new();
+        let mut dropped = 0;
+        for connection in &graph.connections {
+            let inside = |port_path: &str| moved.contains_key(node_in_scope(port_path, scope).unwrap_or_default().as_str());
+            if !inside(&connection.source) && !inside(&connection.destination) {
+                continue;
+            }
+            match (relocate(&connection.source)?, relocate(&connection.destination)?) {
+                (Some(source), Some(destination)) => connections.push((source, destination)),
+                _ => dropped += 1,
+            }
+        }
+
+        // Remove the original blocks before connecting the moved ones, so that the signal never goes through both
+        self.engine.bundle(|engine| {
+            for block_path in chain {
+                engine.delete(block_path)?;
+            }
+            connections.iter().try_for_each(|(source, destination)| engine.connect(source, destination))
+        })?;
+
+        self.show_scope()?;
+        Ok(dropped)
+    }
+}
+
+impl Feature for GroupFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        let mut entries = Vec::new();
+        if self.ui.view_scope().is_some() {
+            entries.push(ContextEntry::new(1, "leave_group", "Leave Group"));
+        }
+        if let crate::ui::Element::Node(node) = element {
+            let graph = self.engine.get_graph().unwrap_or_default();
+            if graph.blocks.iter().any(|b| &b.id == node && b.prototype == GRAPH_PROTOTYPE) {
+                entries.push(ContextEntry::new(5, "open_group", "Open Group"));
+            } else if is_groupable(&graph, node, MAIN_GRAPH) {
+                entries.push(ContextEntry::new(35, "group_chain", "Group Chain >"));
+            }
+            // With a monitor engine, chains move between the engines
+            if graph.blocks.iter().any(|b| b.id == MONITOR_GRAPH) {
+                if is_groupable(&graph, node, MAIN_GRAPH) {
+                    entries.push(ContextEntry::new(36, "move_engine", "Move to Monitor Engine >"));
+                } else if is_groupable(&graph, node, MONITOR_GRAPH) {
+                    entries.push(ContextEntry::new(36, "move_engine", "Move to Main Engine >"));
+                }
+            }
+        }
+        entries
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        match (option_id, element) {
+            ("leave_group", _) => {
+                self.leave()?;
+            }
+            ("open_group", Some(crate::ui::Element::Node(node))) => {
+                self.open(node)?;
+            }
+            ("group_chain", Some(crate::ui::Element::Node(node))) => {
+                let chain = chain_from(&self.engine.get_graph()?, node);
+                if !chain.is_empty() {
+                    self.menu_state = Some(GroupMenuState::ChainSelection(chain));
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+            ("move_engine", Some(crate::ui::Element::Node(node))) => {
+                let (scope, target) = if is_in_scope(node, MONITOR_GRAPH) {
+                    (MONITOR_GRAPH, MAIN_GRAPH)
+                } else {
+                    (MAIN_GRAPH, MONITOR_GRAPH)
+                };
+                let chain = chain_in(&self.engine.get_graph()?, node, scope);
+                if !chain.is_empty() {
+                    self.menu_state = Some(GroupMenuState::EngineSelection(chain, target.to_string()));
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+            _ => {}
+        }
+        Ok(ControllerState::Navigating)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            Some(GroupMenuState::ChainSelection(chain)) => self.get_chain_menu(chain, "Group Chain"),
+            Some(GroupMenuState::EngineSelection(chain, target)) if target == MONITOR_GRAPH => {
+                self.get_chain_menu(chain, "Move to Monitor Engine")
+            }
+            Some(GroupMenuState::EngineSelection(chain, _)) => self.get_chain_menu(chain, "Move to Main Engine"),
+            None => self.get_chain_menu(&[], "Group Chain"),
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Group feature handle_menu_option: {:?}", option_id);
+
+        let Some(menu_state) = self.menu_state.take() else {
+            return Ok(ControllerState::Navigating);
+        };
+        let Some(end) = option_id.and_then(|o| o.strip_prefix("end_")).and_then(|i| i.parse::<usize>().ok()) else {
+            return Ok(ControllerState::Navigating);
+        };
+
+        match menu_state {
+            GroupMenuState::ChainSelection(chain) => match self.group_chain(&chain[..=end.min(chain.len() - 1)]) {
+                Ok(group_path) => debug!("Created group {}", group_path),
+                Err(e) => self.ui.show_message(&format!("Grouping failed: {}", e))?,
+            },
+            GroupMenuState::EngineSelection(chain, target) => {
+                let chain = &chain[..=end.min(chain.len() - 1)];
+                let engine = if target == MONITOR_GRAPH { "monitor" } else { "main" };
+                match self.move_chain(chain, &target) {
+                    Ok(0) => self.ui.show_message(&format!("Moved to the {} engine", engine))?,
+                    Ok(dropped) => self.ui.show_message(&format!("Moved to the {} engine, {} connections left behind", engine, dropped))?,
+                    Err(e) => self.ui.show_message(&format!("Moving failed: {}", e))?,
+                }
+            }
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new group feature
+pub fn new_group_feature(engine: Arc<Engine>, ui: Arc<UI>) -> GroupFeature {
+    GroupFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::feature::mixer::visible_links;
+    use crate::engine::Connection;
+
+    fn graph() -> Graph {
+        let engine = Engine::new_mock();
+        engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_output_port("audio_out_1", PortType::Audio).unwrap();
+        for block in ["delay", "chorus", "reverb"] {
+            engine.create_block("urn:traxdub:mock:delay", block).unwrap();
+        }
+        let mut graph = engine.get_graph().unwrap();
+        let connection = |source: &str, destination: &str| Connection {
+            source: format!("ingen:/main/{}", source),
+            destination: format!("ingen:/main/{}", destination),
+        };
+        graph.connections = vec![
+            connection("audio_in_1", "delay/in"),
+            connection("delay/out", "chorus/in"),
+            connection("chorus/out", "reverb/in"),
+            connection("reverb/out", "audio_out_1"),
+        ];
+        graph
+    }
+
+    #[test]
+    fn test_chain_from() {
+        let mut graph = graph();
+        assert_eq!(chain_from(&graph, "ingen:/main/delay"), vec![
+            "ingen:/main/delay".to_string(),
+            "ingen:/main/chorus".to_string(),
+            "ingen:/main/reverb".to_string(),
+        ]);
+        assert!(chain_from(&graph, "ingen:/main/audio_in_1").is_empty());
+
+        // A merge into the reverb ends the chain before it
+        graph.connections.push(Connection {
+            source: "ingen:/main/audio_in_1".to_string(),
+            destination: "ingen:/main/reverb/in".to_string(),
+        });
+        assert_eq!(chain_from(&graph, "ingen:/main/delay").len(), 2);
+    }
+
+    #[test]
+    fn test_group_chain() {
+        let engine = Arc::new(Engine::new_mock());
+        engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_output_port("audio_out_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.set_control_parameter("ingen:/main/delay", "time", 500.0).unwrap();
+        engine.connect("ingen:/main/audio_in_1", "ingen:/main/delay/in").unwrap();
+        engine.connect("ingen:/main/delay/out", "ingen:/main/audio_out_1").unwrap();
+
+        let group = GroupFeature::new(Arc::clone(&engine), Arc::new(UI::new()));
+        let group_path = group.group_chain(&["ingen:/main/delay".to_string()]).unwrap();
+        // The group follows the name of its block, free again once the block is moved in
+        assert_eq!(group_path, "ingen:/main/delay");
+        assert_eq!(engine.get_control_values("ingen:/main/delay/delay").unwrap()["time"], 500.0);
+
+        let graph = engine.get_graph().unwrap();
+        assert!(!graph.blocks.iter().any(|b| b.id == "ingen:/main/delay" && b.prototype != GRAPH_PROTOTYPE));
+        let mut links: Vec<(String, String)> = visible_links(&graph).into_iter().collect();
+        links.sort();
+        assert_eq!(links, vec![
+            ("ingen:/main/audio_in_1".to_string(), "ingen:/main/delay".to_string()),
+            ("ingen:/main/delay".to_string(), "ingen:/main/audio_out_1".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_move_chain() {
+        let engine = Arc::new(Engine::new_mock().with_monitor(Engine::new_mock()));
+        engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "reverb").unwrap();
+        engine.set_control_parameter("ingen:/main/delay", "time", 500.0).unwrap();
+        engine.connect("ingen:/main/audio_in_1", "ingen:/main/delay/in").unwrap();
+        engine.connect("ingen:/main/delay/out", "ingen:/main/reverb/in").unwrap();
+
+        let group = GroupFeature::new(Arc::clone(&engine), Arc::new(UI::new()));
+        let dropped = group.move_chain(&["ingen:/main/delay".to_string()], MONITOR_GRAPH).unwrap();
+        assert_eq!(dropped, 1);
+        assert_eq!(engine.get_control_values("ingen:/main/monitor/delay").unwrap()["time"], 500.0);
+
+        // The input follows with a port of the monitor engine, the link to the reverb stays behind
+        let graph = engine.get_graph().unwrap();
+        assert!(!graph.blocks.iter().any(|b| b.id == "ingen:/main/delay"));
+        assert_eq!(visible_links_in(&graph, MONITOR_GRAPH).into_iter().collect::<Vec<_>>(), vec![
+            ("ingen:/main/monitor/audio_in_1".to_string(), "ingen:/main/monitor/delay".to_string()),
+        ]);
+        assert_eq!(chain_in(&graph, "ingen:/main/monitor/delay", MONITOR_GRAPH).len(), 1);
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::ui::{GridElement, Menu, UI};
+
+/// Number of focused elements remembered
+const HISTORY_SIZE: usize = 64;
+
+/// Ends of the virtual link focused by the home action
+const HOME_LINK: (&str, &str) = ("inputs", "outputs");
+
+/// Elements focused in the grid, with a position moved by the back and forward jumps
+#[derive(Debug, Default)]
+pub struct FocusHistory {
+    entries: Vec<GridElement>,
+    position: usize,
+}
+
+impl FocusHistory {
+    /// Record a newly focused element, dropping the elements ahead of the position
+    pub fn record(&mut self, element: GridElement) {
+        if self.entries.get(self.position) == Some(&element) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.position + 1);
+        }
+        self.entries.push(element);
+        if self.entries.len() > HISTORY_SIZE {
+            self.entries.remove(0);
+        }
+        self.position = self.entries.len() - 1;
+    }
+
+    /// Check whether an element was focused before the current one
+    pub fn can_go_back(&self) -> bool {
+        self.position > 0
+    }
+
+    /// Check whether the back jumps left elements to go forward to
+    pub fn can_go_forward(&self) -> bool {
+        self.position + 1 < self.entries.len()
+    }
+
+    /// Move to the previously focused element
+    pub fn back(&mut self) -> Option<&GridElement> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.position -= 1;
+        self.entries.get(self.position)
+    }
+
+    /// Move to the element left by a back jump
+    pub fn forward(&mut self) -> Option<&GridElement> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.position += 1;
+        self.entries.get(self.position)
+    }
+}
+
+/// History feature jumping back and forth between the focused elements, or home to the inputs → outputs link
+pub struct HistoryFeature {
+    ui: Arc<UI>,
+    history: FocusHistory,
+    /// Focus left by a jump, not recorded until the grid moved away from it
+    jumped_from: Option<GridElement>,
+}
+
+impl HistoryFeature {
+    /// Create a new history feature
+    pub fn new(ui: Arc<UI>) -> Self {
+        Self {
+            ui,
+            history: FocusHistory::default(),
+            jumped_from: None,
+        }
+    }
+
+    /// Record the element focused in the grid
+    pub fn update(&mut self) -> Result<()> {
+        let Some(focused) = self.ui.select_grid()? else {
+            return Ok(());
+        };
+        if self.jumped_from.as_ref() == Some(&focused) {
+            return Ok(());
+        }
+        self.jumped_from = None;
+        self.history.record(focused);
+        Ok(())
+    }
+
+    /// Move the grid focus to an element of the history
+    fn focus(&mut self, element: GridElement) -> Result<()> {
+        debug!("Jumping to {:?}", element);
+        self.jumped_from = self.ui.select_grid()?;
+        match element {
+            GridElement::Node(id) => self.ui.focus_node(id),
+            GridElement::Link(from_id, to_id, _) => self.ui.focus_link(from_id, to_id),
+        }
+    }
+}
+
+impl Feature for HistoryFeature {
+    fn context_entries(&self, _element: &crate::ui::Element) -> Vec<ContextEntry> {
+        let mut entries = Vec::new();
+        if self.history.can_go_back() {
+            entries.push(ContextEntry::new(120, "history_back", "Jump Back"));
+        }
+        if self.history.can_go_forward() {
+            entries.push(ContextEntry::new(121, "history_forward", "Jump Forward"));
+        }
+        entries.push(ContextEntry::new(122, "history_home", "Jump Home"));
+        entries
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        let element = match option_id {
+            "history_back" => self.history.back().cloned(),
+            "history_forward" => self.history.forward().cloned(),
+            "history_home" => Some(GridElement::Link(
+                HOME_LINK.0.to_string(),
+                HOME_LINK.1.to_string(),
+                crate::ui::LinkType::Virtual,
+            )),
+            _ => None,
+        };
+        if let Some(element) = element {
+            self.focus(element)?;
+        }
+        Ok(ControllerState::Navigating)
+    }
+
+    fn get_menu(&self) -> Menu {
+        Menu {
+            id: "history".to_string(),
+            label: "History".to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    fn handle_menu_option(&mut self, _option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new history feature
+pub fn new_history_feature(ui: Arc<UI>) -> HistoryFeature {
+    HistoryFeature::new(ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str) -> GridElement {
+        GridElement::Node(id.to_string())
+    }
+
+    #[test]
+    fn test_focus_history() {
+        let mut history = FocusHistory::default();
+        assert_eq!(history.back(), None);
+
+        history.record(node("a"));
+        history.record(node("b"));
+        history.record(node("b"));
+        history.record(node("c"));
+        assert_eq!(history.back(), Some(&node("b")));
+        assert_eq!(history.back(), Some(&node("a")));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(&node("b")));
+
+        // Focusing another element drops the ones ahead
+        history.record(node("d"));
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back(), Some(&node("b")));
+    }
+}
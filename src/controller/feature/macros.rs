@@ -0,0 +1,433 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::engine::Engine;
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Parameter driven by a macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroTarget {
+    /// Block path
+    pub block: String,
+    /// Control symbol
+    pub control: String,
+    /// Value at the start of the macro knob
+    pub min: f32,
+    /// Value at the end of the macro knob
+    pub max: f32,
+    /// Whether the parameter goes down when the knob goes up
+    #[serde(default)]
+    pub invert: bool,
+}
+
+impl MacroTarget {
+    /// Get the value of the parameter for a macro position between 0 and 1
+    pub fn value(&self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        let position = if self.invert { 1.0 - position } else { position };
+        self.min + position * (self.max - self.min)
+    }
+
+    /// Get the label of the target in the macro menu
+    fn label(&self) -> String {
+        let block = self.block.rsplit('/').next().unwrap_or(&self.block);
+        let invert = if self.invert { ", inverted" } else { "" };
+        format!("{}: {} ({}–{}{}) >", block, self.control, self.min, self.max, invert)
+    }
+}
+
+/// Named set of parameters driven together by one learned knob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroControl {
+    pub name: String,
+    pub targets: Vec<MacroTarget>,
+}
+
+/// Menu state for the macro feature
+#[derive(Debug, Clone, PartialEq)]
+enum MacroMenuState {
+    MacroList,
+    MacroDetail(String),
+    TargetDetail(String, usize),
+    ParameterSelection,
+    MacroSelection(String), // control symbol
+}
+
+/// Macro feature driving parameters of several blocks with a single knob
+pub struct MacroFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    macros: Vec<MacroControl>,
+    menu_state: MacroMenuState,
+    ui_element: Option<crate::ui::Element>,
+    /// Macro waiting for its knob
+    learning: Option<String>,
+}
+
+impl MacroFeature {
+    /// Create a new macro feature with the saved macros
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            macros: Self::load_macros(),
+            menu_state: MacroMenuState::MacroList,
+            ui_element: None,
+            learning: None,
+        }
+    }
+
+    /// Get the macro file path
+    fn get_macros_path() -> Result<PathBuf> {
+        Ok(Settings::get_home_dir()?.join("macros.json"))
+    }
+
+    /// Load the saved macros, none when the file is missing or invalid
+    fn load_macros() -> Vec<MacroControl> {
+        let Ok(path) = Self::get_macros_path() else {
+            return Vec::new();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return Vec::new();
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| warn!("Invalid macro file {:?}: {}", path, e))
+            .unwrap_or_default()
+    }
+
+    /// Save the macros
+    fn save_macros(&self) -> Result<()> {
+        let path = Self::get_macros_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create macro directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&self.macros)?)
+            .context("Failed to write macros")
+    }
+
+    /// Check whether a macro exists
+    pub fn has_macro(&self, name: &str) -> bool {
+        self.macros.iter().any(|m| m.name == name)
+    }
+
+    /// Take the macro waiting for a knob
+    pub fn take_learning_macro(&mut self) -> Option<String> {
+        self.learning.take()
+    }
+
+    /// Move all the parameters of a macro to a knob position between 0 and 1
+    pub fn set_position(&self, name: &str, position: f32) -> Result<()> {
+        let Some(macro_control) = self.macros.iter().find(|m| m.name == name) else {
+            return Ok(());
+        };
+        debug!("Macro {} at {:.2}", name, position);
+        for target in &macro_control.targets {
+            // Blocks deleted since the macro was made are skipped
+            if self.engine.get_block_plugin(&target.block).is_none() {
+                continue;
+            }
+            self.engine.set_control_parameter(&target.block, &target.control, target.value(position))?;
+        }
+        Ok(())
+    }
+
+    /// Add a parameter of the selected node to a macro, creating it when the name is new
+    fn add_target(&mut self, name: &str, control_id: &str) -> Result<()> {
+        let Some(crate::ui::Element::Node(block)) = &self.ui_element else {
+            return Err(anyhow::anyhow!("Macro feature requires a node element"));
+        };
+        let control = self.engine.get_block_plugin(block)
+            .and_then(|plugin| plugin.controls.into_iter().find(|c| c.id == control_id))
+            .ok_or_else(|| anyhow::anyhow!("Parameter not found: {}", control_id))?;
+
+        let target = MacroTarget {
+            block: block.clone(),
+            control: control.id,
+            min: control.min,
+            max: control.max,
+            invert: false,
+        };
+        match self.macros.iter_mut().find(|m| m.name == name) {
+            Some(macro_control) => {
+                macro_control.targets.retain(|t| !(t.block == target.block && t.control == target.control));
+                macro_control.targets.push(target);
+            }
+            None => self.macros.push(MacroControl {
+                name: name.to_string(),
+                targets: vec![target],
+            }),
+        }
+        info!("Added {} of {} to macro {}", control_id, block, name);
+        self.save_macros()
+    }
+
+    /// Change a target of a macro
+    fn update_target(&mut self, name: &str, index: usize, update: impl FnOnce(&mut Vec<MacroTarget>, usize)) -> Result<()> {
+        if let Some(macro_control) = self.macros.iter_mut().find(|m| m.name == name) {
+            if index < macro_control.targets.len() {
+                update(&mut macro_control.targets, index);
+            }
+        }
+        self.save_macros()
+    }
+
+    /// Get the list of macros
+    fn get_macro_list_menu(&self) -> Menu {
+        Menu {
+            id: "macro_list".to_string(),
+            label: "Macros".to_string(),
+            options: self.macros.iter()
+                .map(|m| MenuOption {
+                    id: m.name.clone(),
+                    label: format!("{} ({} parameters) >", m.name, m.targets.len()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Get the menu of a macro with its targets
+    fn get_macro_detail_menu(&self, name: &str) -> Menu {
+        let mut options = vec![MenuOption {
+            id: "learn".to_string(),
+            label: "Learn Knob".to_string(),
+        }];
+        if let Some(macro_control) = self.macros.iter().find(|m| m.name == name) {
+            options.extend(macro_control.targets.iter().enumerate().map(|(i, target)| MenuOption {
+                id: format!("target_{}", i),
+                label: target.label(),
+            }));
+        }
+        options.push(MenuOption {
+            id: "delete".to_string(),
+            label: "Delete Macro".to_string(),
+        });
+
+        Menu {
+            id: "macro_detail".to_string(),
+            label: name.to_string(),
+            options,
+        }
+    }
+
+    /// Get the range and direction menu of a macro target
+    fn get_target_menu(&self, name: &str, index: usize) -> Menu {
+        let target = self.macros.iter()
+            .find(|m| m.name == name)
+            .and_then(|m| m.targets.get(index));
+        let invert_label = if target.is_some_and(|t| t.invert) { "Normal Direction" } else { "Invert" };
+
+        Menu {
+            id: "macro_target".to_string(),
+            label: target.map(|t| t.control.clone()).unwrap_or_default(),
+            options: [
+                ("full", "Full Range"),
+                ("lower", "Lower Half"),
+                ("upper", "Upper Half"),
+                ("invert", invert_label),
+                ("remove", "Remove"),
+            ]
+            .into_iter()
+            .map(|(id, label)| MenuOption { id: id.to_string(), label: label.to_string() })
+            .collect(),
+        }
+    }
+
+    /// Get the parameters of the selected node that can be added to a macro
+    fn get_parameter_selection_menu(&self) -> Menu {
+        let controls = match &self.ui_element {
+            Some(crate::ui::Element::Node(block)) => self.engine.get_block_plugin(block)
+                .map(|plugin| plugin.controls)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        Menu {
+            id: "macro_parameters".to_string(),
+            label: "Add to Macro".to_string(),
+            options: controls.into_iter()
+                .map(|c| MenuOption { label: format!("{} >", c.name), id: c.id })
+                .collect(),
+        }
+    }
+
+    /// Get the macros a parameter can be added to, with a new one
+    fn get_macro_selection_menu(&self) -> Menu {
+        let mut options: Vec<MenuOption> = self.macros.iter()
+            .map(|m| MenuOption { id: m.name.clone(), label: m.name.clone() })
+            .collect();
+        options.push(MenuOption {
+            id: "new".to_string(),
+            label: "New Macro".to_string(),
+        });
+
+        Menu {
+            id: "macro_selection".to_string(),
+            label: "Macro".to_string(),
+            options,
+        }
+    }
+
+    /// Get the first free macro name
+    fn new_macro_name(&self) -> String {
+        (1..)
+            .map(|i| format!("Macro {}", i))
+            .find(|name| !self.has_macro(name))
+            .unwrap_or_default()
+    }
+}
+
+impl Feature for MacroFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) if !self.macros.is_empty() => vec![ContextEntry::new(55, "macros", "Macros >")],
+            crate::ui::Element::Node(node) => {
+                if self.engine.get_block_plugin(node).is_some_and(|plugin| !plugin.controls.is_empty()) {
+                    vec![ContextEntry::new(16, "add_to_macro", "Add to Macro >")]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        self.ui_element = element.cloned();
+        self.menu_state = if option_id == "add_to_macro" {
+            MacroMenuState::ParameterSelection
+        } else {
+            MacroMenuState::MacroList
+        };
+        Ok(ControllerState::BrowsingMenu)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            MacroMenuState::MacroList => self.get_macro_list_menu(),
+            MacroMenuState::MacroDetail(name) => self.get_macro_detail_menu(name),
+            MacroMenuState::TargetDetail(name, index) => self.get_target_menu(name, *index),
+            MacroMenuState::ParameterSelection => self.get_parameter_selection_menu(),
+            MacroMenuState::MacroSelection(_) => self.get_macro_selection_menu(),
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Macro feature handle_menu_option: {:?}", option_id);
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Macro feature: menu closed");
+            self.menu_state = match &self.menu_state {
+                MacroMenuState::MacroList | MacroMenuState::ParameterSelection => {
+                    self.ui_element = None;
+                    return Ok(ControllerState::Navigating);
+                }
+                MacroMenuState::MacroDetail(_) => MacroMenuState::MacroList,
+                MacroMenuState::TargetDetail(name, _) => MacroMenuState::MacroDetail(name.clone()),
+                MacroMenuState::MacroSelection(_) => MacroMenuState::ParameterSelection,
+            };
+            return Ok(ControllerState::BrowsingMenu);
+        };
+
+        match self.menu_state.clone() {
+            MacroMenuState::MacroList => {
+                self.menu_state = MacroMenuState::MacroDetail(option.to_string());
+                Ok(ControllerState::BrowsingMenu)
+            }
+            MacroMenuState::MacroDetail(name) => {
+                if let Some(index) = option.strip_prefix("target_").and_then(|i| i.parse::<usize>().ok()) {
+                    self.menu_state = MacroMenuState::TargetDetail(name, index);
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                self.menu_state = MacroMenuState::MacroList;
+                match option {
+                    "learn" => {
+                        self.learning = Some(name);
+                        self.ui.show_message("Turn the macro knob")?;
+                        Ok(ControllerState::LearningMacroKnob)
+                    }
+                    "delete" => {
+                        self.macros.retain(|m| m.name != name);
+                        self.save_macros()?;
+                        self.ui.show_message(&format!("Deleted {}", name))?;
+                        Ok(ControllerState::Navigating)
+                    }
+                    _ => Ok(ControllerState::Navigating),
+                }
+            }
+            MacroMenuState::TargetDetail(name, index) => {
+                let controls = self.macros.iter()
+                    .find(|m| m.name == name)
+                    .and_then(|m| m.targets.get(index))
+                    .and_then(|t| self.engine.get_block_plugin(&t.block)
+                        .and_then(|plugin| plugin.controls.into_iter().find(|c| c.id == t.control)));
+                self.update_target(&name, index, |targets, index| {
+                    let target = &mut targets[index];
+                    let (low, high) = controls.as_ref().map(|c| (c.min, c.max)).unwrap_or((target.min, target.max));
+                    let middle = (low + high) / 2.0;
+                    match option {
+                        "full" => (target.min, target.max) = (low, high),
+                        "lower" => (target.min, target.max) = (low, middle),
+                        "upper" => (target.min, target.max) = (middle, high),
+                        "invert" => target.invert = !target.invert,
+                        "remove" => {
+                            targets.remove(index);
+                        }
+                        _ => {}
+                    }
+                })?;
+                self.menu_state = MacroMenuState::MacroDetail(name);
+                Ok(ControllerState::BrowsingMenu)
+            }
+            MacroMenuState::ParameterSelection => {
+                self.menu_state = MacroMenuState::MacroSelection(option.to_string());
+                Ok(ControllerState::BrowsingMenu)
+            }
+            MacroMenuState::MacroSelection(control_id) => {
+                let name = if option == "new" { self.new_macro_name() } else { option.to_string() };
+                let result = self.add_target(&name, &control_id);
+                self.menu_state = MacroMenuState::MacroList;
+                self.ui_element = None;
+                match result {
+                    Ok(()) => self.ui.show_message(&format!("Added {} to {}", control_id, name))?,
+                    Err(e) => self.ui.show_message(&e.to_string())?,
+                }
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+/// Helper to create a new macro feature
+pub fn new_macro_feature(engine: Arc<Engine>, ui: Arc<UI>) -> MacroFeature {
+    MacroFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_target_value() {
+        let mut target = MacroTarget {
+            block: "ingen:/main/delay".to_string(),
+            control: "feedback".to_string(),
+            min: 0.2,
+            max: 0.6,
+            invert: false,
+        };
+        assert_eq!(target.value(0.0), 0.2);
+        assert!((target.value(0.5) - 0.4).abs() < 1e-6);
+        assert_eq!(target.value(2.0), 0.6);
+
+        target.invert = true;
+        assert_eq!(target.value(0.0), 0.6);
+        assert_eq!(target.value(1.0), 0.2);
+    }
+}
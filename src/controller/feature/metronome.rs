@@ -0,0 +1,171 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::driver::{Driver, Port, PortType};
+use crate::controller::metronome::Metronome;
+use crate::controller::tempo::Tempo;
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Menu state for the metronome feature
+#[derive(Debug, Clone, PartialEq)]
+enum MetronomeMenuState {
+    MetronomeMenu,
+    RouteSelection,
+}
+
+/// Metronome feature for playing a click routed to its own output
+pub struct MetronomeFeature {
+    driver: Arc<Driver>,
+    ui: Arc<UI>,
+    metronome: Metronome,
+    menu_state: MetronomeMenuState,
+}
+
+impl MetronomeFeature {
+    /// Create a new metronome feature
+    pub fn new(driver: Arc<Driver>, ui: Arc<UI>, tempo: Arc<Tempo>) -> Self {
+        Self {
+            metronome: Metronome::new(Arc::clone(&driver), tempo),
+            driver,
+            ui,
+            menu_state: MetronomeMenuState::MetronomeMenu,
+        }
+    }
+
+    /// Get the metronome menu
+    fn get_metronome_menu(&self) -> Menu {
+        let toggle_label = if self.metronome.is_running() { "Stop Click" } else { "Start Click" };
+
+        Menu {
+            id: "metronome_menu".to_string(),
+            label: "Metronome".to_string(),
+            options: vec![
+                MenuOption {
+                    id: "toggle".to_string(),
+                    label: toggle_label.to_string(),
+                },
+                MenuOption {
+                    id: "route".to_string(),
+                    label: "Route Click >".to_string(),
+                },
+            ],
+        }
+    }
+
+    /// Get the menu of audio destinations the click can be routed to
+    fn get_route_selection_menu(&self) -> Result<Menu> {
+        let routes = self.metronome.routes();
+        let sinks = self.driver.get_sinks(PortType::Audio)?;
+
+        let options = sinks.iter()
+            .flat_map(|sink| sink.ports.iter().map(move |port| (sink, port)))
+            .map(|(sink, port)| {
+                let routed = routes.iter().any(|r| r.name == port.name);
+                MenuOption {
+                    id: format!("port_{}", port.name),
+                    label: format!("{} {}{}", sink.name, port.short_name, if routed { " ✓" } else { "" }),
+                }
+            })
+            .collect();
+
+        Ok(Menu {
+            id: "metronome_routes".to_string(),
+            label: "Route Click To".to_string(),
+            options,
+        })
+    }
+}
+
+impl Feature for MetronomeFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) => vec![
+                ContextEntry::new(60, "metronome", "Metronome >"),
+                ContextEntry::new(90, "learn_tap", "Learn Tap Button"),
+            ],
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        if option_id == "learn_tap" {
+            // Wait for the tap button with all menus closed
+            self.ui.show_message("Press the tap tempo button")?;
+            return Ok(ControllerState::LearningTapButton);
+        }
+        Ok(ControllerState::BrowsingMenu)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            MetronomeMenuState::MetronomeMenu => self.get_metronome_menu(),
+            MetronomeMenuState::RouteSelection => {
+                self.get_route_selection_menu().unwrap_or_else(|e| {
+                    debug!("Error getting route menu: {}", e);
+                    self.get_metronome_menu()
+                })
+            }
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Metronome feature handle_menu_option: {:?}", option_id);
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Metronome feature: menu closed");
+            match self.menu_state {
+                MetronomeMenuState::MetronomeMenu => return Ok(ControllerState::Navigating),
+                MetronomeMenuState::RouteSelection => {
+                    self.menu_state = MetronomeMenuState::MetronomeMenu;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+        };
+
+        match &self.menu_state {
+            MetronomeMenuState::MetronomeMenu => {
+                match option {
+                    "toggle" => {
+                        if self.metronome.is_running() {
+                            self.metronome.stop();
+                            self.ui.show_message("Click stopped")?;
+                        } else {
+                            self.metronome.start()?;
+                            if self.metronome.routes().is_empty() {
+                                self.ui.show_message("Click started, route it to an output")?;
+                            } else {
+                                self.ui.show_message("Click started")?;
+                            }
+                        }
+                        Ok(ControllerState::Navigating)
+                    }
+                    "route" => {
+                        self.menu_state = MetronomeMenuState::RouteSelection;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    _ => Ok(ControllerState::Navigating),
+                }
+            }
+            MetronomeMenuState::RouteSelection => {
+                if let Some(port_name) = option.strip_prefix("port_") {
+                    let short_name = port_name.split(':').next_back().unwrap_or(port_name).to_string();
+                    self.metronome.route(Port {
+                        name: port_name.to_string(),
+                        short_name: short_name.clone(),
+                    })?;
+                    self.ui.show_message(&format!("Click routed to {}", short_name))?;
+                }
+                self.menu_state = MetronomeMenuState::MetronomeMenu;
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+/// Helper to create a new metronome feature
+pub fn new_metronome_feature(driver: Arc<Driver>, ui: Arc<UI>, tempo: Arc<Tempo>) -> MetronomeFeature {
+    MetronomeFeature::new(driver, ui, tempo)
+}
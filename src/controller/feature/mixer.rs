@@ -0,0 +1,1207 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Settings;
+use crate::controller::driver::MidiEvent;
+use crate::controller::knob::{self, KnobAcceleration};
+use crate::controller::{BaseControlConfig, ControllerState, KnobDirection, feature::{ContextEntry, Feature}};
+use crate::engine::{Connection, ControlPort, Engine, GRAPH_PROTOTYPE, Graph, PortDirection, PortType};
+use crate::ui::{Menu, MenuOption, UI, NodeState, NodeType};
+
+/// LV2 plugin used for the mixer faders
+pub const GAIN_PLUGIN_URI: &str = "http://lv2plug.in/plugins/eg-amp";
+
+/// Control port symbol of the gain in dB
+pub const GAIN_CONTROL: &str = "gain";
+
+/// Prefix of the block IDs of the mixer faders
+pub const FADER_PREFIX: &str = "mixer_";
+
+/// Fader step in dB for one knob step
+const FADER_STEP_DB: f32 = 0.5;
+
+/// Knob move making one fader step at the default sensitivity
+const FADER_KNOB_THRESHOLD: f32 = 64.0;
+
+/// Gain applied to muted faders, in dB
+const MUTE_DB: f32 = -90.0;
+
+/// Interval between the gain changes of a fade
+const RAMP_STEP: Duration = Duration::from_millis(20);
+
+/// Prefix of the hidden gain blocks after the outputs of a bypassed block
+pub const BYPASS_WET_PREFIX: &str = "bypass_wet_";
+
+/// Prefix of the hidden gain blocks carrying the dry signal around a bypassed block
+pub const BYPASS_DRY_PREFIX: &str = "bypass_dry_";
+
+/// Prefix of the hidden trim gain blocks right after the audio inputs
+pub const TRIM_PREFIX: &str = "trim_";
+
+/// Prefix of the hidden safety limiter blocks right before the audio outputs
+pub const LIMITER_PREFIX: &str = "limiter_";
+
+/// Duration of the crossfade between the processed and the dry signal
+const BYPASS_FADE: Duration = Duration::from_millis(100);
+
+/// Path of the main graph, the outermost graph shown by the UI
+pub const MAIN_GRAPH: &str = "ingen:/main";
+
+/// Menu ID of the mixer menu
+pub const MIXER_MENU_ID: &str = "mixer_menu";
+
+/// Menu state for the mixer feature
+#[derive(Debug, Clone, PartialEq)]
+enum MixerMenuState {
+    MixerMenu,
+    ChannelMenu(String), // fader block path
+}
+
+/// Check whether a fader is audible with solo-in-place logic:
+/// muted faders are silent, and as soon as one fader is soloed only soloed faders play
+pub fn is_audible(fader: &str, muted: &HashSet<String>, soloed: &HashSet<String>) -> bool {
+    !muted.contains(fader) && (soloed.is_empty() || soloed.contains(fader))
+}
+
+/// Get the audio input and output port symbols of the gain plugin
+pub fn gain_plugin_ports(engine: &Engine) -> Result<(String, String)> {
+    let plugin = engine.list_plugins().into_iter()
+        .find(|p| p.id == GAIN_PLUGIN_URI)
+        .ok_or_else(|| anyhow::anyhow!("Gain plugin not installed: {}", GAIN_PLUGIN_URI))?;
+    let input = plugin.ports.iter()
+        .find(|p| p.direction == PortDirection::Input && p.port_type == PortType::Audio)
+        .ok_or_else(|| anyhow::anyhow!("Gain plugin has no audio input"))?;
+    let output = plugin.ports.iter()
+        .find(|p| p.direction == PortDirection::Output && p.port_type == PortType::Audio)
+        .ok_or_else(|| anyhow::anyhow!("Gain plugin has no audio output"))?;
+    Ok((input.id.clone(), output.id.clone()))
+}
+
+/// Get the gain control of the gain plugin
+pub fn gain_control(engine: &Engine) -> Option<ControlPort> {
+    engine.list_plugins().into_iter()
+        .find(|p| p.id == GAIN_PLUGIN_URI)
+        .and_then(|p| p.controls.into_iter().find(|c| c.id == GAIN_CONTROL))
+}
+
+/// Get the gains of all faders in dB
+pub fn fader_gains(engine: &Engine) -> Result<Vec<(String, f32)>> {
+    let faders: Vec<String> = engine.get_graph()?.blocks.into_iter()
+        .filter(|b| is_fader(&b.id))
+        .map(|b| b.id)
+        .collect();
+    faders.into_iter()
+        .map(|fader| {
+            let gain = engine.get_control_values(&fader)?.get(GAIN_CONTROL).copied().unwrap_or(0.0);
+            Ok((fader, gain))
+        })
+        .collect()
+}
+
+/// Fade all faders out from their gains to silence, or in from silence to their gains
+/// Used around session switches so that the change is not heard as a click
+pub fn ramp_faders(engine: &Engine, fade_in: bool, duration: Duration) -> Result<()> {
+    if duration.is_zero() {
+        return Ok(());
+    }
+    let gains = fader_gains(engine)?;
+    if fade_in {
+        for (fader, _) in &gains {
+            engine.set_control_parameter(fader, GAIN_CONTROL, MUTE_DB)?;
+        }
+    }
+    ramp_gains(engine, &gains, fade_in, duration)
+}
+
+/// Fade faders out from the given gains to silence, or in from silence to them
+pub fn ramp_gains(engine: &Engine, gains: &[(String, f32)], fade_in: bool, duration: Duration) -> Result<()> {
+    if duration.is_zero() || gains.is_empty() {
+        return Ok(());
+    }
+
+    debug!("Fading {} {} faders over {:?}", if fade_in { "in" } else { "out" }, gains.len(), duration);
+    let steps = (duration.as_millis() / RAMP_STEP.as_millis()).max(1) as u32;
+    for step in 1..=steps {
+        // Linear in amplitude, so that the fade sounds even
+        let progress = step as f32 / steps as f32;
+        let amplitude = if fade_in { progress } else { 1.0 - progress };
+        for (fader, gain) in gains {
+            let level = if amplitude > 0.0 { (gain + 20.0 * amplitude.log10()).max(MUTE_DB) } else { MUTE_DB };
+            engine.set_control_parameter(fader, GAIN_CONTROL, level)?;
+        }
+        thread::sleep(RAMP_STEP);
+    }
+    Ok(())
+}
+
+/// Crossfade between gain blocks, from unity to silence for fade_out and from silence to unity for fade_in
+fn crossfade(engine: &Engine, fade_out: &[String], fade_in: &[String], duration: Duration) -> Result<()> {
+    let steps = (duration.as_millis() / RAMP_STEP.as_millis()).max(1) as u32;
+    for step in 1..=steps {
+        let progress = step as f32 / steps as f32;
+        for (blocks, amplitude) in [(fade_out, 1.0 - progress), (fade_in, progress)] {
+            let level = if amplitude > 0.0 { (20.0 * amplitude.log10()).max(MUTE_DB) } else { MUTE_DB };
+            for block in blocks {
+                engine.set_control_parameter(block, GAIN_CONTROL, level)?;
+            }
+        }
+        thread::sleep(RAMP_STEP);
+    }
+    Ok(())
+}
+
+/// Check whether a block is a bypass, trim or limiter helper, kept out of the UI
+pub fn is_hidden_block(block_path: &str) -> bool {
+    let name = block_path.rsplit('/').next().unwrap_or(block_path);
+    name.starts_with(BYPASS_WET_PREFIX) || name.starts_with(BYPASS_DRY_PREFIX) || name.starts_with(TRIM_PREFIX)
+        || name.starts_with(LIMITER_PREFIX)
+}
+
+/// Get the block path of the trim of an input port node, in the graph of the port
+pub fn trim_block(port_path: &str) -> String {
+    let (graph, name) = port_path.rsplit_once('/').unwrap_or((MAIN_GRAPH, port_path));
+    format!("{}/{}{}", graph, TRIM_PREFIX, name)
+}
+
+/// Insert a trim gain block at unity after a new audio input port
+pub fn create_trim(engine: &Engine, port_path: &str) -> Result<()> {
+    let (input, _) = gain_plugin_ports(engine)?;
+    let trim = trim_block(port_path);
+    // The engine creates blocks by their path relative to the main graph
+    engine.create_block(GAIN_PLUGIN_URI, trim.strip_prefix(&format!("{}/", MAIN_GRAPH)).unwrap_or(&trim))?;
+    engine.set_control_parameter(&trim, GAIN_CONTROL, 0.0)?;
+    engine.connect(port_path, &format!("{}/{}", trim, input))
+}
+
+/// Get the port to connect from for a node the UI links from:
+/// the output of its trim for a trimmed input, the node itself otherwise
+pub fn source_port(engine: &Engine, node_path: &str) -> String {
+    let trim = trim_block(node_path);
+    let trimmed = engine.get_graph().is_ok_and(|graph| graph.blocks.iter().any(|b| b.id == trim));
+    match gain_plugin_ports(engine) {
+        Ok((_, output)) if trimmed => format!("{}/{}", trim, output),
+        _ => node_path.to_string(),
+    }
+}
+
+/// Get the block path of the safety limiter of an output port node, in the graph of the port
+pub fn limiter_block(port_path: &str) -> String {
+    let (graph, name) = port_path.rsplit_once('/').unwrap_or((MAIN_GRAPH, port_path));
+    format!("{}/{}{}", graph, LIMITER_PREFIX, name)
+}
+
+/// Get the port to connect to for feeding a node, the input of the safety limiter for a limited output
+pub fn destination_port(engine: &Engine, node_path: &str) -> String {
+    let limiter = limiter_block(node_path);
+    let input = engine.get_graph().ok()
+        .and_then(|graph| graph.blocks.into_iter().find(|b| b.id == limiter))
+        .and_then(|block| block.ports.into_iter().find(|p| p.direction == PortDirection::Input && p.port_type == PortType::Audio));
+    match input {
+        Some(port) => format!("{}/{}", limiter, port.id),
+        None => node_path.to_string(),
+    }
+}
+
+/// Insert or remove the safety limiters to follow the settings, right after an output is created or a session loaded
+pub fn apply_safety_limiter(engine: &Engine, settings: &Mutex<Settings>) -> Result<usize> {
+    let (enabled, plugin_uri) = {
+        let settings = settings.lock().unwrap();
+        (settings.safety_limiter, settings.safety_limiter_uri.clone())
+    };
+    update_limiters(engine, &plugin_uri, enabled)
+}
+
+/// Insert or remove the safety limiters before the audio outputs of the main and monitor engines to follow
+/// the setting, moving the connections of the outputs to the limiters and back. Returns the number of changed outputs
+pub fn update_limiters(engine: &Engine, plugin_uri: &str, enabled: bool) -> Result<usize> {
+    let graph = engine.get_graph()?;
+    let audio_outputs = |graph_path: &str, ports: &[crate::engine::Port]| -> Vec<String> {
+        ports.iter()
+            .filter(|p| p.direction == PortDirection::Output && p.port_type == PortType::Audio)
+            .map(|p| format!("{}/{}", graph_path, p.id))
+            .collect()
+    };
+    let mut outputs = audio_outputs(MAIN_GRAPH, &graph.ports);
+    if let Some(monitor) = graph.blocks.iter().find(|b| b.id == crate::engine::split::MONITOR_GRAPH) {
+        outputs.extend(audio_outputs(&monitor.id, &monitor.ports));
+    }
+    let changed: Vec<String> = outputs.into_iter()
+        .filter(|output| graph.blocks.iter().any(|b| b.id == limiter_block(output)) != enabled)
+        .collect();
+    if changed.is_empty() {
+        return Ok(0);
+    }
+
+    let ports = if enabled {
+        let plugin = engine.list_plugins().into_iter()
+            .find(|p| p.id == plugin_uri)
+            .ok_or_else(|| anyhow::anyhow!("Limiter plugin not installed: {}", plugin_uri))?;
+        let port = |direction: PortDirection| plugin.ports.iter()
+            .find(|p| p.direction == direction && p.port_type == PortType::Audio)
+            .map(|p| p.id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Limiter plugin {} has no audio {:?}", plugin_uri, direction));
+        Some((port(PortDirection::Input)?, port(PortDirection::Output)?))
+    } else {
+        None
+    };
+
+    engine.bundle(|engine| {
+        for output in &changed {
+            let limiter = limiter_block(output);
+            match &ports {
+                Some((input, limiter_output)) => {
+                    info!("Limiting {}", output);
+                    engine.create_block(plugin_uri, limiter.strip_prefix("ingen:/main/").unwrap_or(&limiter))?;
+                    engine.connect(&format!("{}/{}", limiter, limiter_output), output)?;
+                    for c in graph.connections.iter().filter(|c| &c.destination == output) {
+                        engine.connect(&c.source, &format!("{}/{}", limiter, input))?;
+                        engine.disconnect(&c.source, output)?;
+                    }
+                }
+                None => {
+                    info!("Removing the limiter of {}", output);
+                    let prefix = format!("{}/", limiter);
+                    for c in graph.connections.iter().filter(|c| c.destination.starts_with(&prefix)) {
+                        engine.connect(&c.source, output)?;
+                    }
+                    engine.delete(&limiter)?;
+                }
+            }
+        }
+        Ok(())
+    })?;
+    Ok(changed.len())
+}
+
+/// Get the path of a wet or dry gain of a block, for the audio output at an index
+/// The gain goes in the graph of the block, a group or the main graph, and the index ends its symbol
+/// as it has no underscore, which keeps the symbol of the block whole
+fn bypass_gain_path(prefix: &str, block_path: &str, output_index: usize) -> String {
+    let (parent, symbol) = block_path.rsplit_once('/').unwrap_or((MAIN_GRAPH, block_path));
+    format!("{}/{}{}_{}", parent, prefix, symbol, output_index)
+}
+
+/// Get the block path of the block a wet or dry gain goes around, in the same graph, None for other blocks
+fn bypass_gain_owner(prefix: &str, gain_path: &str) -> Option<String> {
+    let (parent, name) = gain_path.rsplit_once('/')?;
+    let (symbol, index) = name.strip_prefix(prefix)?.rsplit_once('_')?;
+    (!index.is_empty() && index.chars().all(|c| c.is_ascii_digit())).then(|| format!("{}/{}", parent, symbol))
+}
+
+/// Check whether a block is bypassed, i.e. has dry gain blocks around it
+pub fn is_bypassed(graph: &Graph, block_path: &str) -> bool {
+    graph.blocks.iter().any(|b| bypass_gain_owner(BYPASS_DRY_PREFIX, &b.id).as_deref() == Some(block_path))
+}
+
+/// Get the node the UI shows in place of a hidden gain block: the port of a trim or a limiter, or the block
+/// a wet or dry gain goes around. Other nodes are shown as they are.
+pub fn visible_node(node_path: &str) -> String {
+    if let Some(owner) = bypass_gain_owner(BYPASS_WET_PREFIX, node_path).or_else(|| bypass_gain_owner(BYPASS_DRY_PREFIX, node_path)) {
+        return owner;
+    }
+    let (graph, name) = node_path.rsplit_once('/').unwrap_or((MAIN_GRAPH, node_path));
+    match name.strip_prefix(TRIM_PREFIX).or_else(|| name.strip_prefix(LIMITER_PREFIX)) {
+        Some(port) => format!("{}/{}", graph, port),
+        None => node_path.to_string(),
+    }
+}
+
+/// Insert a gain block at unity per source port of connections coming from a node, in front of their destinations
+/// The gains are named after a prefix and the index of their source port, so that a stereo chain stays stereo,
+/// and the UI shows them between the node and the nodes it feeds
+pub fn insert_gains(engine: &Engine, ui: &UI, node_path: &str, connections: &[Connection], prefix: &str) -> Result<Vec<String>> {
+    let (input, output) = gain_plugin_ports(engine)?;
+
+    let mut sources: Vec<&str> = Vec::new();
+    for connection in connections {
+        if !sources.contains(&connection.source.as_str()) {
+            sources.push(&connection.source);
+        }
+    }
+    let gains: Vec<String> = (0..sources.len()).map(|k| format!("{}/{}_{}", MAIN_GRAPH, prefix, k)).collect();
+
+    for (source, gain) in sources.iter().zip(&gains) {
+        engine.create_block(GAIN_PLUGIN_URI, gain.rsplit('/').next().unwrap_or(gain))?;
+        engine.connect(source, &format!("{}/{}", gain, input))?;
+    }
+
+    // Reroute the destinations through the gain of their source port, in the engine and in the UI
+    let mut shown = HashSet::new();
+    let mut rerouted = HashSet::new();
+    let mut linked = HashSet::new();
+    for connection in connections {
+        let gain = &gains[sources.iter().position(|s| *s == connection.source).unwrap_or(0)];
+        let label = gain.rsplit('/').next().unwrap_or(gain).to_string();
+        engine.connect(&format!("{}/{}", gain, output), &connection.destination)?;
+        // Ignore error if connection doesn't exist
+        let _ = engine.disconnect(&connection.source, &connection.destination);
+
+        let destination_node = visible_node(&node_of_port(&connection.destination));
+        if !linked.insert((gain.clone(), destination_node.clone())) {
+            continue;
+        }
+        if !shown.contains(gain) && rerouted.insert(destination_node.clone()) {
+            ui.insert_node(gain.clone(), label, NodeType::Normal, node_path.to_string(), destination_node)?;
+            shown.insert(gain.clone());
+            continue;
+        }
+        if shown.insert(gain.clone()) {
+            ui.create_node(gain.clone(), label, NodeType::Normal)?;
+            ui.create_link(node_path.to_string(), gain.clone(), crate::ui::LinkType::Normal)?;
+        }
+        if rerouted.insert(destination_node.clone()) {
+            ui.remove_link(node_path.to_string(), destination_node.clone())?;
+        }
+        ui.create_link(gain.clone(), destination_node, crate::ui::LinkType::Normal)?;
+    }
+    ui.commit()?; // Commit gain insertion
+
+    Ok(gains)
+}
+
+/// Check whether a block is a mixer fader
+pub fn is_fader(block_path: &str) -> bool {
+    block_path.rsplit('/').next().is_some_and(|name| name.starts_with(FADER_PREFIX))
+}
+
+/// Get the audio output ports of the main graph
+fn audio_outputs(graph: &Graph) -> HashSet<String> {
+    graph.ports.iter()
+        .filter(|p| p.direction == PortDirection::Output && p.port_type == PortType::Audio)
+        .map(|p| format!("{}/{}", MAIN_GRAPH, p.id))
+        .collect()
+}
+
+/// Get the chains feeding the audio outputs without going through a fader, by the node ending them
+/// Bypassed blocks are left out as their wet and dry gains would each get a fader
+fn unmixed_chains(graph: &Graph) -> Vec<String> {
+    let outputs = audio_outputs(graph);
+    let mut chains: Vec<String> = visible_links(graph).into_iter()
+        .filter(|(from, to)| outputs.contains(to) && !is_fader(from) && !is_bypassed(graph, from))
+        .map(|(from, _)| from)
+        .collect();
+    chains.sort();
+    chains.dedup();
+    chains
+}
+
+/// Insert faders at the end of the chains feeding the audio outputs directly, returns the number of chains
+/// Called whenever chains may have been connected to an output, so that every chain heard has its faders
+pub fn ensure_faders(engine: &Engine, ui: &UI) -> Result<usize> {
+    let graph = engine.get_graph()?;
+    let outputs = audio_outputs(&graph);
+    let chains = unmixed_chains(&graph);
+    for chain in &chains {
+        // The fader goes before the safety limiter of the output, if any
+        let connections: Vec<Connection> = graph.connections.iter()
+            .filter(|c| visible_node(&node_of_port(&c.source)) == *chain)
+            .filter(|c| outputs.contains(&visible_node(&node_of_port(&c.destination))))
+            .cloned()
+            .collect();
+        let symbol = chain.rsplit('/').next().unwrap_or(chain);
+        let prefix = (1..)
+            .map(|n| if n == 1 { format!("{}{}", FADER_PREFIX, symbol) } else { format!("{}{}_{}", FADER_PREFIX, symbol, n) })
+            .find(|prefix| !graph.blocks.iter().any(|b| b.id.starts_with(&format!("{}/{}_", MAIN_GRAPH, prefix))))
+            .unwrap();
+        info!("Inserting faders {} after {}", prefix, chain);
+        insert_gains(engine, ui, chain, &connections, &prefix)?;
+    }
+    Ok(chains.len())
+}
+
+/// Get the channels of the mixer, the faders grouped by the chain feeding them
+/// A fader fed by nothing makes a channel of its own
+fn channels(graph: &Graph) -> Vec<Channel> {
+    let links = visible_links(graph);
+    let mut faders: Vec<&String> = graph.blocks.iter()
+        .map(|b| &b.id)
+        .filter(|id| is_in_scope(id, MAIN_GRAPH) && is_fader(id))
+        .collect();
+    faders.sort();
+
+    let mut channels: Vec<Channel> = Vec::new();
+    for fader in faders {
+        let chain = links.iter()
+            .filter(|(_, to)| to == fader)
+            .map(|(from, _)| from.clone())
+            .min()
+            .unwrap_or_else(|| fader.clone());
+        match channels.iter_mut().find(|c| c.chain == chain) {
+            Some(channel) => channel.faders.push(fader.clone()),
+            None => {
+                let name = graph.blocks.iter()
+                    .find(|b| b.id == chain)
+                    .map_or_else(|| chain.rsplit('/').next().unwrap_or(&chain).to_string(), |b| b.name.clone());
+                channels.push(Channel { chain, name, faders: vec![fader.clone()] });
+            }
+        }
+    }
+    channels
+}
+
+/// Get the links shown by the UI for the engine connections, as (from_id, to_id)
+/// Wet and trim gains are looked through as if their source fed their destinations directly,
+/// and the dry paths of bypassed blocks are not shown
+pub fn visible_links(graph: &Graph) -> HashSet<(String, String)> {
+    visible_links_in(graph, MAIN_GRAPH)
+}
+
+/// Get the links shown by the UI inside a graph, the main graph or an open group
+/// Connections to the inside of a group are shown on the group node, connections outside of the graph are left out
+pub fn visible_links_in(graph: &Graph, scope: &str) -> HashSet<(String, String)> {
+    let node_of_port = |port_path: &str| node_in_scope(port_path, scope);
+    let mut links = HashSet::new();
+    for connection in &graph.connections {
+        let (Some(from_id), Some(destination)) = (node_of_port(&connection.source), node_of_port(&connection.destination)) else {
+            continue;
+        };
+        if is_hidden_block(&from_id) {
+            continue;
+        }
+        let mut queue = VecDeque::from([destination]);
+        let mut visited = HashSet::new();
+        while let Some(to_id) = queue.pop_front() {
+            if !is_hidden_block(&to_id) {
+                if to_id != from_id {
+                    links.insert((from_id.clone(), to_id));
+                }
+                continue;
+            }
+            let name = to_id.rsplit('/').next().unwrap_or(&to_id);
+            let transparent = name.starts_with(BYPASS_WET_PREFIX) || name.starts_with(TRIM_PREFIX)
+                || name.starts_with(LIMITER_PREFIX);
+            if transparent && visited.insert(to_id.clone()) {
+                queue.extend(graph.connections.iter()
+                    .filter(|c| node_of_port(&c.source).as_ref() == Some(&to_id))
+                    .filter_map(|c| node_of_port(&c.destination)));
+            }
+        }
+    }
+    links
+}
+
+/// Get the node shown for a port in a graph: the block, group or graph port directly inside it
+/// None for the ports outside of the graph
+pub fn node_in_scope(port_path: &str, scope: &str) -> Option<String> {
+    let inner = port_path.strip_prefix(scope)?.strip_prefix('/')?;
+    let child = inner.split('/').next().filter(|child| !child.is_empty())?;
+    Some(format!("{}/{}", scope, child))
+}
+
+/// Get the paths of the port nodes of every graph shown by the UI: the system ports of the main graph,
+/// and the ports of the monitor engine and of the groups
+pub fn port_nodes(graph: &Graph) -> HashSet<String> {
+    let inner_ports = graph.blocks.iter()
+        .filter(|b| b.prototype == GRAPH_PROTOTYPE)
+        .flat_map(|b| b.ports.iter().map(move |p| format!("{}/{}", b.id, p.id)));
+    graph.ports.iter()
+        .map(|p| format!("{}/{}", MAIN_GRAPH, p.id))
+        .chain(inner_ports)
+        .collect()
+}
+
+/// Check whether a block is directly inside a graph, not nested in one of its groups
+pub fn is_in_scope(block_path: &str, scope: &str) -> bool {
+    block_path.rsplit_once('/').is_some_and(|(parent, _)| parent == scope)
+}
+
+/// Extract node ID from a port path ("ingen:/main/node_id/port_id" -> "ingen:/main/node_id")
+pub fn node_of_port(port_path: &str) -> String {
+    let parts: Vec<&str> = port_path.split('/').collect();
+    if parts.len() >= 3 {
+        parts[..3].join("/")
+    } else {
+        port_path.to_string()
+    }
+}
+
+/// Channel of the mixer, the faders at the end of a chain feeding the outputs
+/// The first fader stands for the channel, it keeps the levels, the mute and the solo of the channel
+#[derive(Debug, Clone)]
+struct Channel {
+    chain: String,
+    name: String,
+    faders: Vec<String>,
+}
+
+/// Mixer feature managing the faders at the end of the chains feeding the outputs, one per audio port of the chain
+pub struct MixerFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: MixerMenuState,
+    levels: HashMap<String, f32>,
+    muted: HashSet<String>,
+    soloed: HashSet<String>,
+    /// Fader waiting for a mute button to be learned
+    learning_mute: Option<String>,
+    settings: Arc<Mutex<Settings>>,
+    /// Secondary knob move not yet turned into fader steps
+    knob_accumulator: f32,
+    knob_acceleration: KnobAcceleration,
+}
+
+impl MixerFeature {
+    /// Create a new mixer feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: MixerMenuState::MixerMenu,
+            levels: HashMap::new(),
+            muted: HashSet::new(),
+            soloed: HashSet::new(),
+            learning_mute: None,
+            settings,
+            knob_accumulator: 0.0,
+            knob_acceleration: KnobAcceleration::default(),
+        }
+    }
+
+    /// Turn a secondary knob move into steps, following the speed of the knob
+    fn knob_steps(&mut self, value: u8) -> Option<(KnobDirection, u32)> {
+        let (threshold, curve) = {
+            let settings = self.settings.lock().unwrap();
+            (knob::threshold(FADER_KNOB_THRESHOLD, settings.knob_sensitivity), settings.knob_acceleration.max(0.0))
+        };
+        let factor = self.knob_acceleration.factor(Instant::now(), curve);
+        knob::accelerated_steps(value, &mut self.knob_accumulator, threshold, factor)
+    }
+
+    /// Get the channel of a fader, any of its faders standing for it
+    fn channel(&self, block_path: &str) -> Result<Channel> {
+        channels(&self.engine.get_graph()?).into_iter()
+            .find(|c| c.faders.iter().any(|f| f == block_path))
+            .ok_or_else(|| anyhow::anyhow!("No mixer fader {}", block_path))
+    }
+
+    /// Get the level of a channel in dB, by its first fader
+    fn level(&self, block_path: &str) -> f32 {
+        self.levels.get(block_path).copied()
+            .or_else(|| gain_control(&self.engine).map(|c| c.default))
+            .unwrap_or(0.0)
+    }
+
+    /// Set the level of all the faders of a channel in dB, only heard if the channel is audible
+    fn set_level(&mut self, channel: &Channel, level: f32) -> Result<f32> {
+        let level = match gain_control(&self.engine) {
+            Some(control) => level.clamp(control.min, control.max),
+            None => level,
+        };
+        self.levels.insert(channel.faders[0].clone(), level);
+        if is_audible(&channel.faders[0], &self.muted, &self.soloed) {
+            for fader in &channel.faders {
+                self.engine.set_control_parameter(fader, GAIN_CONTROL, level)?;
+            }
+        }
+        Ok(level)
+    }
+
+    /// Get the display name of a fader
+    fn fader_name(block_path: &str) -> &str {
+        let name = block_path.rsplit('/').next().unwrap_or(block_path);
+        name.strip_prefix(FADER_PREFIX).unwrap_or(name)
+    }
+
+    /// Apply mute and solo to all channels and refresh the indicators of their faders
+    fn apply_mute_solo(&mut self) -> Result<()> {
+        for channel in channels(&self.engine.get_graph()?) {
+            let key = &channel.faders[0];
+            let audible = is_audible(key, &self.muted, &self.soloed);
+            let gain = if audible { self.level(key) } else { MUTE_DB };
+            let state = if self.muted.contains(key) {
+                NodeState::Muted
+            } else if self.soloed.contains(key) {
+                NodeState::Soloed
+            } else if !audible {
+                NodeState::Silenced
+            } else {
+                NodeState::Normal
+            };
+            for fader in &channel.faders {
+                self.engine.set_control_parameter(fader, GAIN_CONTROL, gain)?;
+                self.ui.set_node_state(fader.clone(), state.clone())?;
+            }
+        }
+        self.ui.commit()
+    }
+
+    /// Toggle the mute of the channel of a fader
+    pub fn toggle_mute(&mut self, block_path: &str) -> Result<()> {
+        let channel = self.channel(block_path)?;
+        let muted = if self.muted.remove(&channel.faders[0]) {
+            false
+        } else {
+            self.muted.insert(channel.faders[0].clone());
+            true
+        };
+        info!("{} {}", channel.name, if muted { "muted" } else { "unmuted" });
+        self.apply_mute_solo()
+    }
+
+    /// Toggle the solo of the channel of a fader
+    pub fn toggle_solo(&mut self, block_path: &str) -> Result<()> {
+        let channel = self.channel(block_path)?;
+        let soloed = if self.soloed.remove(&channel.faders[0]) {
+            false
+        } else {
+            self.soloed.insert(channel.faders[0].clone());
+            true
+        };
+        info!("{} {}", channel.name, if soloed { "soloed" } else { "unsoloed" });
+        self.apply_mute_solo()
+    }
+
+    /// Find the fader at the end of the chain a node belongs to
+    pub fn chain_fader(&self, node_path: &str) -> Result<String> {
+        ensure_faders(&self.engine, &self.ui)?;
+        let graph = self.engine.get_graph()?;
+
+        // Follow the connections downstream until reaching a fader
+        let mut queue = VecDeque::from([node_path.to_string()]);
+        let mut visited = HashSet::new();
+        while let Some(node) = queue.pop_front() {
+            if is_fader(&node) {
+                return Ok(node);
+            }
+            if !visited.insert(node.clone()) {
+                continue;
+            }
+            queue.extend(graph.connections.iter()
+                .filter(|c| node_of_port(&c.source) == node)
+                .map(|c| node_of_port(&c.destination)));
+        }
+
+        Err(anyhow::anyhow!("No mixer fader after {}, its chain does not reach an output", node_path))
+    }
+
+    /// Toggle the bypass of a block with a crossfade between its output and its dry input
+    /// The bypass routes each audio output through a wet gain at unity and adds a muted dry gain before
+    /// fading; enabling the block again fades back, connects the outputs directly and removes both gains
+    pub fn toggle_bypass(&mut self, block_path: &str) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let block = graph.blocks.iter()
+            .find(|b| b.id == block_path)
+            .ok_or_else(|| anyhow::anyhow!("Block not found: {}", block_path))?
+            .clone();
+        let audio_ports = |direction: PortDirection| -> Vec<String> {
+            block.ports.iter()
+                .filter(|p| p.direction == direction && p.port_type == PortType::Audio)
+                .map(|p| p.id.clone())
+                .collect()
+        };
+        let (inputs, outputs) = (audio_ports(PortDirection::Input), audio_ports(PortDirection::Output));
+        let wets: Vec<String> = (0..outputs.len()).map(|k| bypass_gain_path(BYPASS_WET_PREFIX, block_path, k)).collect();
+        let drys: Vec<String> = (0..outputs.len()).map(|k| bypass_gain_path(BYPASS_DRY_PREFIX, block_path, k)).collect();
+        // The engine creates blocks by their path relative to the main graph
+        let relative = |path: &str| path.strip_prefix(&format!("{}/", MAIN_GRAPH)).unwrap_or(path).to_string();
+        let (gain_in, gain_out) = gain_plugin_ports(&self.engine)?;
+
+        if is_bypassed(&graph, block_path) {
+            crossfade(&self.engine, &drys, &wets, BYPASS_FADE)?;
+            // Both paths are at unity again, the outputs take the place of the wet gains
+            self.engine.bundle(|engine| {
+                for (k, output) in outputs.iter().enumerate() {
+                    let wet_out = format!("{}/{}", wets[k], gain_out);
+                    for connection in graph.connections.iter().filter(|c| c.source == wet_out) {
+                        engine.connect(&format!("{}/{}", block_path, output), &connection.destination)?;
+                    }
+                    engine.delete(&wets[k])?;
+                    engine.delete(&drys[k])?;
+                }
+                Ok(())
+            })?;
+            info!("{} enabled", block.name);
+            self.ui.set_node_state(block_path.to_string(), NodeState::Normal)?;
+            return self.ui.commit();
+        }
+
+        for (k, output) in outputs.iter().enumerate() {
+            let output_path = format!("{}/{}", block_path, output);
+            let wet = &wets[k];
+            let wet_out = format!("{}/{}", wet, gain_out);
+            let destinations: Vec<String> = graph.connections.iter()
+                .filter(|c| c.source == output_path)
+                .map(|c| c.destination.clone())
+                .collect();
+
+            // Both paths are at unity, swap them back to back
+            self.engine.create_block(GAIN_PLUGIN_URI, &relative(wet))?;
+            self.engine.set_control_parameter(wet, GAIN_CONTROL, 0.0)?;
+            self.engine.connect(&output_path, &format!("{}/{}", wet, gain_in))?;
+            for destination in &destinations {
+                self.engine.connect(&wet_out, destination)?;
+                self.engine.disconnect(&output_path, destination)?;
+            }
+
+            // Feed the dry gain from the matching input, or the only one of a mono to stereo block
+            let dry = &drys[k];
+            self.engine.create_block(GAIN_PLUGIN_URI, &relative(dry))?;
+            self.engine.set_control_parameter(dry, GAIN_CONTROL, MUTE_DB)?;
+            if !inputs.is_empty() {
+                let input_path = format!("{}/{}", block_path, inputs[k % inputs.len()]);
+                for connection in graph.connections.iter().filter(|c| c.destination == input_path) {
+                    self.engine.connect(&connection.source, &format!("{}/{}", dry, gain_in))?;
+                }
+            }
+            for destination in &destinations {
+                self.engine.connect(&format!("{}/{}", dry, gain_out), destination)?;
+            }
+        }
+        crossfade(&self.engine, &wets, &drys, BYPASS_FADE)?;
+
+        info!("{} bypassed", block.name);
+        self.ui.set_node_state(block_path.to_string(), NodeState::Bypassed)?;
+        self.ui.commit()
+    }
+
+    /// Get the trim of an input port node in dB, if it has one
+    pub fn trim_level(&self, port_path: &str) -> Option<f32> {
+        let trim = trim_block(port_path);
+        if !self.engine.get_graph().ok()?.blocks.iter().any(|b| b.id == trim) {
+            return None;
+        }
+        Some(self.engine.get_control_values(&trim).ok()?.get(GAIN_CONTROL).copied().unwrap_or(0.0))
+    }
+
+    /// Set the trim of an input port node in dB
+    fn set_trim(&self, port_path: &str, level: f32) -> Result<()> {
+        let level = match gain_control(&self.engine) {
+            Some(control) => level.clamp(control.min, control.max),
+            None => level,
+        };
+        self.engine.set_control_parameter(&trim_block(port_path), GAIN_CONTROL, level)?;
+        let name = port_path.rsplit('/').next().unwrap_or(port_path);
+        self.ui.show_message(&format!("Trim {}: {:+.1} dB", name, level))
+    }
+
+    /// Move the trim of an input port by a number of steps in the given direction (driven by the secondary knob)
+    pub fn adjust_trim(&self, port_path: &str, direction: KnobDirection, steps: u32) -> Result<()> {
+        let Some(level) = self.trim_level(port_path) else {
+            return Ok(());
+        };
+        let step = match direction {
+            KnobDirection::Forward => FADER_STEP_DB,
+            KnobDirection::Backward => -FADER_STEP_DB,
+        } * steps as f32;
+        self.set_trim(port_path, level + step)
+    }
+
+    /// Take the fader waiting for a mute button
+    pub fn take_learning_mute(&mut self) -> Option<String> {
+        self.learning_mute.take()
+    }
+
+    /// Move the faders of a channel by a number of steps in the given direction (driven by the secondary knob)
+    pub fn adjust(&mut self, block_path: &str, direction: KnobDirection, steps: u32) -> Result<()> {
+        let step = match direction {
+            KnobDirection::Forward => FADER_STEP_DB,
+            KnobDirection::Backward => -FADER_STEP_DB,
+        } * steps as f32;
+        let channel = self.channel(block_path)?;
+        let level = self.set_level(&channel, self.level(&channel.faders[0]) + step)?;
+        self.ui.show_message(&format!("{}: {:+.1} dB", channel.name, level))
+    }
+
+    /// Get the mixer menu with one entry per chain
+    fn get_mixer_menu(&self) -> Menu {
+        let graph = self.engine.get_graph().unwrap_or_default();
+
+        let options = channels(&graph).into_iter()
+            .map(|channel| {
+                let key = &channel.faders[0];
+                let indicator = if self.muted.contains(key) {
+                    " [M]"
+                } else if self.soloed.contains(key) {
+                    " [S]"
+                } else {
+                    ""
+                };
+                MenuOption {
+                    id: key.clone(),
+                    label: format!("{} {:+.1} dB{} >", channel.name, self.level(key), indicator),
+                }
+            })
+            .collect();
+
+        Menu {
+            id: MIXER_MENU_ID.to_string(),
+            label: "Mixer".to_string(),
+            options,
+        }
+    }
+
+    /// Get the menu of a single channel, by its first fader
+    fn get_channel_menu(&self, block_path: &str) -> Menu {
+        let mute_label = if self.muted.contains(block_path) { "Unmute" } else { "Mute" };
+        let solo_label = if self.soloed.contains(block_path) { "Unsolo" } else { "Solo" };
+
+        Menu {
+            id: format!("mixer_channel_{}", Self::fader_name(block_path)),
+            label: self.channel(block_path).map_or_else(|_| Self::fader_name(block_path).to_string(), |c| c.name),
+            options: vec![
+                MenuOption {
+                    id: "mute".to_string(),
+                    label: mute_label.to_string(),
+                },
+                MenuOption {
+                    id: "solo".to_string(),
+                    label: solo_label.to_string(),
+                },
+                MenuOption {
+                    id: "reset".to_string(),
+                    label: "Reset to 0 dB".to_string(),
+                },
+                MenuOption {
+                    id: "learn_mute".to_string(),
+                    label: "Learn Mute Button".to_string(),
+                },
+            ],
+        }
+    }
+}
+
+impl Feature for MixerFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) => vec![ContextEntry::new(50, "mixer", "Mixer >")],
+            crate::ui::Element::Node(node) => {
+                let mut entries = vec![
+                    ContextEntry::new(60, "mute_chain", "Mute Chain"),
+                    ContextEntry::new(70, "solo_chain", "Solo Chain"),
+                ];
+                // Bypass applies to plugin blocks, not to ports and faders
+                let graph = self.engine.get_graph().unwrap_or_default();
+                let is_plugin = graph.blocks.iter().any(|b| &b.id == node) && !is_fader(node);
+                if is_plugin {
+                    let label = if is_bypassed(&graph, node) { "Enable" } else { "Bypass" };
+                    entries.push(ContextEntry::new(75, "bypass", label));
+                }
+                if let Some(level) = self.trim_level(node) {
+                    entries.push(ContextEntry::new(100, "reset_trim", &format!("Reset Trim ({:+.1} dB)", level)));
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        // The chains connected to the outputs since the last time get their faders before the mixer opens
+        if option_id == "mixer" {
+            if let Err(e) = ensure_faders(&self.engine, &self.ui) {
+                warn!("Could not insert the missing faders: {}", e);
+            }
+            return Ok(ControllerState::BrowsingMenu);
+        }
+        // Mute or solo the chain of the node through its mixer fader
+        let Some(crate::ui::Element::Node(node)) = element else {
+            return Ok(ControllerState::BrowsingMenu);
+        };
+        if option_id == "reset_trim" {
+            self.set_trim(node, 0.0)?;
+            return Ok(ControllerState::Navigating);
+        }
+        if option_id == "bypass" {
+            if let Err(e) = self.toggle_bypass(node) {
+                self.ui.show_message(&e.to_string())?;
+            }
+            return Ok(ControllerState::Navigating);
+        }
+        match self.chain_fader(node) {
+            Ok(fader) if option_id == "mute_chain" => self.toggle_mute(&fader)?,
+            Ok(fader) => self.toggle_solo(&fader)?,
+            Err(e) => self.ui.show_message(&e.to_string())?,
+        }
+        Ok(ControllerState::Navigating)
+    }
+
+    fn handle_midi_event(&mut self, event: &MidiEvent, controls: &BaseControlConfig, element: Option<&crate::ui::Element>) -> Result<bool> {
+        let MidiEvent::ControlChange { channel, control, value } = *event else {
+            return Ok(false);
+        };
+        if controls.secondary_knob.channel != channel || controls.secondary_knob.control != control {
+            return Ok(false);
+        }
+
+        // The secondary knob drives the focused fader of the mixer menu, or the trim of the input whose node menu is open
+        let fader = self.ui.select_menu()?
+            .filter(|f| f.menu_id == MIXER_MENU_ID)
+            .map(|f| f.option_id);
+        let trimmed_input = match element {
+            Some(crate::ui::Element::Node(node)) if fader.is_none() && self.trim_level(node).is_some() => Some(node.clone()),
+            _ => None,
+        };
+        if fader.is_none() && trimmed_input.is_none() {
+            return Ok(false);
+        }
+        if let Some((direction, steps)) = self.knob_steps(value) {
+            match (fader, trimmed_input) {
+                (Some(fader), _) => self.adjust(&fader, direction, steps)?,
+                (_, Some(port_path)) => self.adjust_trim(&port_path, direction, steps)?,
+                _ => {}
+            }
+        }
+        Ok(true)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            MixerMenuState::MixerMenu => self.get_mixer_menu(),
+            MixerMenuState::ChannelMenu(block_path) => self.get_channel_menu(block_path),
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Mixer feature handle_menu_option: {:?}", option_id);
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Mixer feature: menu closed");
+            match self.menu_state {
+                MixerMenuState::MixerMenu => return Ok(ControllerState::Navigating),
+                MixerMenuState::ChannelMenu(_) => {
+                    self.menu_state = MixerMenuState::MixerMenu;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+        };
+
+        match &self.menu_state {
+            MixerMenuState::MixerMenu => {
+                // Channel selected, show its actions
+                self.menu_state = MixerMenuState::ChannelMenu(option.to_string());
+                Ok(ControllerState::BrowsingMenu)
+            }
+            MixerMenuState::ChannelMenu(block_path) => {
+                let block_path = block_path.clone();
+                self.menu_state = MixerMenuState::MixerMenu;
+                match option {
+                    "mute" => self.toggle_mute(&block_path)?,
+                    "solo" => self.toggle_solo(&block_path)?,
+                    "reset" => {
+                        self.set_level(&self.channel(&block_path)?, 0.0)?;
+                        self.ui.show_message("Fader reset to 0 dB")?;
+                    }
+                    "learn_mute" => {
+                        self.learning_mute = Some(block_path);
+                        self.ui.show_message("Press the mute button")?;
+                        return Ok(ControllerState::LearningMuteButton);
+                    }
+                    _ => {}
+                }
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+/// Helper to create a new mixer feature
+pub fn new_mixer_feature(engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> MixerFeature {
+    MixerFeature::new(engine, ui, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solo_in_place() {
+        let mut muted = HashSet::new();
+        let mut soloed = HashSet::new();
+        assert!(is_audible("a", &muted, &soloed));
+
+        muted.insert("a".to_string());
+        assert!(!is_audible("a", &muted, &soloed));
+        assert!(is_audible("b", &muted, &soloed));
+
+        // Soloing one chain silences the others, mute still wins
+        soloed.insert("b".to_string());
+        soloed.insert("a".to_string());
+        assert!(!is_audible("a", &muted, &soloed));
+        assert!(is_audible("b", &muted, &soloed));
+        assert!(!is_audible("c", &muted, &soloed));
+    }
+
+    #[test]
+    fn test_port_nodes_of_every_graph() {
+        let port = |id: &str| crate::engine::Port { id: id.to_string(), port_type: PortType::Audio, direction: PortDirection::Input };
+        let graph = Graph {
+            blocks: vec![crate::engine::Block {
+                id: crate::engine::split::MONITOR_GRAPH.to_string(),
+                name: "monitor".to_string(),
+                prototype: GRAPH_PROTOTYPE.to_string(),
+                ports: vec![port("audio_in_1")],
+            }],
+            connections: Vec::new(),
+            ports: vec![port("audio_in_1")],
+        };
+        let mut nodes: Vec<String> = port_nodes(&graph).into_iter().collect();
+        nodes.sort();
+        assert_eq!(nodes, vec!["ingen:/main/audio_in_1", "ingen:/main/monitor/audio_in_1"]);
+    }
+
+    #[test]
+    fn test_bypass_gains_stay_in_the_group() {
+        let block = |path: &str| crate::engine::Block {
+            id: format!("ingen:/main/{}", path),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            prototype: GAIN_PLUGIN_URI.to_string(),
+            ports: Vec::new(),
+        };
+        let nested = "ingen:/main/group_1/delay";
+        let dry = bypass_gain_path(BYPASS_DRY_PREFIX, nested, 0);
+        assert_eq!(dry, "ingen:/main/group_1/bypass_dry_delay_0");
+        assert_eq!(bypass_gain_owner(BYPASS_DRY_PREFIX, &dry).as_deref(), Some(nested));
+        assert_eq!(visible_node(&bypass_gain_path(BYPASS_WET_PREFIX, nested, 1)), nested);
+
+        let graph = Graph {
+            blocks: vec![block("delay"), block("group_1/delay"), block("group_1/bypass_dry_delay_0")],
+            connections: Vec::new(),
+            ports: Vec::new(),
+        };
+        assert!(is_bypassed(&graph, nested));
+        assert!(!is_bypassed(&graph, "ingen:/main/delay"));
+    }
+
+    #[test]
+    fn test_channels_group_faders_by_chain() {
+        let connection = |source: &str, destination: &str| crate::engine::Connection {
+            source: format!("ingen:/main/{}", source),
+            destination: format!("ingen:/main/{}", destination),
+        };
+        let block = |name: &str| crate::engine::Block {
+            id: format!("ingen:/main/{}", name),
+            name: name.to_string(),
+            prototype: GAIN_PLUGIN_URI.to_string(),
+            ports: Vec::new(),
+        };
+        let output = |id: &str| crate::engine::Port { id: id.to_string(), port_type: PortType::Audio, direction: PortDirection::Output };
+        let mut graph = Graph {
+            blocks: vec![block("reverb"), block("delay"), block("limiter_audio_out_1"), block("mixer_reverb_0"), block("mixer_reverb_1")],
+            connections: vec![
+                connection("reverb/out_l", "mixer_reverb_0/in"),
+                connection("reverb/out_r", "mixer_reverb_1/in"),
+                connection("mixer_reverb_0/out", "limiter_audio_out_1/in"),
+                connection("limiter_audio_out_1/out", "audio_out_1"),
+                connection("mixer_reverb_1/out", "audio_out_2"),
+                connection("delay/out", "limiter_audio_out_1/in"),
+            ],
+            ports: vec![output("audio_out_1"), output("audio_out_2")],
+        };
+
+        assert_eq!(unmixed_chains(&graph), vec!["ingen:/main/delay"]);
+        let reverb = channels(&graph);
+        assert_eq!(reverb.len(), 1);
+        assert_eq!(reverb[0].name, "reverb");
+        assert_eq!(reverb[0].faders, vec!["ingen:/main/mixer_reverb_0", "ingen:/main/mixer_reverb_1"]);
+
+        // A fader fed by nothing stands alone
+        graph.connections.retain(|c| !c.source.starts_with("ingen:/main/reverb"));
+        let channels = channels(&graph);
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].faders, vec!["ingen:/main/mixer_reverb_0"]);
+    }
+
+    #[test]
+    fn test_visible_links_look_through_helpers() {
+        let connection = |source: &str, destination: &str| crate::engine::Connection {
+            source: format!("ingen:/main/{}", source),
+            destination: format!("ingen:/main/{}", destination),
+        };
+        let block = |name: &str| crate::engine::Block {
+            id: format!("ingen:/main/{}", name),
+            name: name.to_string(),
+            prototype: GAIN_PLUGIN_URI.to_string(),
+            ports: Vec::new(),
+        };
+        let graph = Graph {
+            blocks: vec![block("trim_audio_in_1"), block("delay"), block("bypass_wet_delay_0"), block("bypass_dry_delay_0")],
+            connections: vec![
+                connection("audio_in_1", "trim_audio_in_1/in"),
+                connection("trim_audio_in_1/out", "delay/in"),
+                connection("delay/out", "bypass_wet_delay_0/in"),
+                connection("bypass_wet_delay_0/out", "audio_out_1"),
+                connection("trim_audio_in_1/out", "bypass_dry_delay_0/in"),
+                connection("bypass_dry_delay_0/out", "audio_out_1"),
+            ],
+            ports: Vec::new(),
+        };
+
+        assert!(is_bypassed(&graph, "ingen:/main/delay"));
+        assert!(!is_bypassed(&graph, "ingen:/main/del"));
+        assert_eq!(bypass_gain_owner(BYPASS_DRY_PREFIX, "ingen:/main/bypass_dry_delay_2_0").as_deref(), Some("ingen:/main/delay_2"));
+        assert_eq!(bypass_gain_path(BYPASS_WET_PREFIX, "ingen:/main/delay", 1), "ingen:/main/bypass_wet_delay_1");
+        assert_eq!(visible_node("ingen:/main/bypass_wet_delay_0"), "ingen:/main/delay");
+        assert_eq!(visible_node("ingen:/main/limiter_audio_out_1"), "ingen:/main/audio_out_1");
+        assert!(is_hidden_block("ingen:/main/bypass_wet_delay_0"));
+        assert_eq!(trim_block("ingen:/main/audio_in_1"), "ingen:/main/trim_audio_in_1");
+        assert_eq!(trim_block("ingen:/main/monitor/audio_in_1"), "ingen:/main/monitor/trim_audio_in_1");
+        let mut links: Vec<(String, String)> = visible_links(&graph).into_iter().collect();
+        links.sort();
+        assert_eq!(links, vec![
+            ("ingen:/main/audio_in_1".to_string(), "ingen:/main/delay".to_string()),
+            ("ingen:/main/delay".to_string(), "ingen:/main/audio_out_1".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_update_limiters() {
+        let engine = Engine::new_mock();
+        let output = engine.create_output_port("audio_out_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.connect("ingen:/main/delay/out", &output).unwrap();
+
+        assert_eq!(update_limiters(&engine, "urn:traxdub:mock:limiter", true).unwrap(), 1);
+        assert_eq!(update_limiters(&engine, "urn:traxdub:mock:limiter", true).unwrap(), 0);
+        assert_eq!(destination_port(&engine, &output), "ingen:/main/limiter_audio_out_1/in");
+        let graph = engine.get_graph().unwrap();
+        assert!(is_hidden_block(&limiter_block(&output)));
+        assert_eq!(visible_links(&graph).into_iter().collect::<Vec<_>>(), vec![("ingen:/main/delay".to_string(), output.clone())]);
+
+        assert_eq!(update_limiters(&engine, "urn:traxdub:mock:limiter", false).unwrap(), 1);
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(graph.blocks.len(), 1);
+        assert_eq!(graph.connections.len(), 1);
+        assert_eq!(graph.connections[0].destination, output);
+    }
+
+    #[test]
+    fn test_visible_links_in_group() {
+        let connection = |source: &str, destination: &str| crate::engine::Connection {
+            source: format!("ingen:/main/{}", source),
+            destination: format!("ingen:/main/{}", destination),
+        };
+        let graph = Graph {
+            blocks: Vec::new(),
+            connections: vec![
+                connection("audio_in_1", "group_1/in_1"),
+                connection("group_1/in_1", "group_1/delay/in"),
+                connection("group_1/delay/out", "group_1/reverb/in"),
+                connection("group_1/reverb/out", "group_1/out_1"),
+                connection("group_1/out_1", "audio_out_1"),
+            ],
+            ports: Vec::new(),
+        };
+
+        let mut links: Vec<(String, String)> = visible_links(&graph).into_iter().collect();
+        links.sort();
+        assert_eq!(links, vec![
+            ("ingen:/main/audio_in_1".to_string(), "ingen:/main/group_1".to_string()),
+            ("ingen:/main/group_1".to_string(), "ingen:/main/audio_out_1".to_string()),
+        ]);
+
+        let mut links: Vec<(String, String)> = visible_links_in(&graph, "ingen:/main/group_1").into_iter().collect();
+        links.sort();
+        assert_eq!(links, vec![
+            ("ingen:/main/group_1/delay".to_string(), "ingen:/main/group_1/reverb".to_string()),
+            ("ingen:/main/group_1/in_1".to_string(), "ingen:/main/group_1/delay".to_string()),
+            ("ingen:/main/group_1/reverb".to_string(), "ingen:/main/group_1/out_1".to_string()),
+        ]);
+        assert!(is_in_scope("ingen:/main/group_1/delay", "ingen:/main/group_1"));
+        assert!(!is_in_scope("ingen:/main/group_1/delay", MAIN_GRAPH));
+    }
+}
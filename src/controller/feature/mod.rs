@@ -1,23 +1,185 @@
 pub mod system;
 pub mod plugin;
 pub mod persistence;
+pub mod performance;
+pub mod parameter;
+pub mod record;
+pub mod metronome;
+pub mod mixer;
+pub mod crossfade;
+pub mod preset;
+pub mod arrange;
+pub mod rename;
+pub mod settings;
+pub mod monitor;
+pub mod setlist;
+pub mod compare;
+pub mod group;
+pub mod chain;
+pub mod clip;
+pub mod macros;
+pub mod numbers;
+pub mod history;
+pub mod goto;
+pub mod bookmarks;
+pub mod tags;
+pub mod feedback;
 
 // Re-export input and output features from system module
 pub use system::{InputFeature, OutputFeature, new_input_feature, new_output_feature};
 pub use plugin::{PluginFeature, new_plugin_feature};
 pub use persistence::{PersistenceFeature, new_persistence_feature};
+pub use performance::{PerformanceFeature, KnobSlot, new_performance_feature};
+pub use parameter::{ParameterFeature, new_parameter_feature};
+pub use record::{RecordFeature, new_record_feature};
+pub use metronome::{MetronomeFeature, new_metronome_feature};
+pub use mixer::{MixerFeature, new_mixer_feature};
+pub use crossfade::{CrossfadeFeature, new_crossfade_feature};
+pub use preset::{PresetFeature, new_preset_feature};
+pub use arrange::{ArrangeFeature, new_arrange_feature};
+pub use rename::{RenameFeature, TextEditor, new_rename_feature};
+pub use settings::{SettingsFeature, new_settings_feature};
+pub use monitor::{MidiMonitorFeature, new_midi_monitor_feature};
+pub use setlist::{SetListFeature, new_set_list_feature};
+pub use group::{GroupFeature, new_group_feature};
+pub use chain::{ChainPresetFeature, new_chain_preset_feature};
+pub use clip::{ClipFeature, new_clip_feature};
+pub use macros::{MacroFeature, new_macro_feature};
+pub use numbers::{NodeNumberFeature, new_node_number_feature};
+pub use history::{HistoryFeature, new_history_feature};
+pub use goto::{GotoFeature, new_goto_feature};
+pub use bookmarks::{BookmarkFeature, new_bookmark_feature};
+pub use tags::{TagFeature, new_tag_feature};
+pub use feedback::{FeedbackFeature, new_feedback_feature};
 
 use anyhow::Result;
-use crate::ui::Menu;
-use crate::controller::ControllerState;
+use crate::ui::{Element, Menu, MenuOption};
+use crate::controller::{BaseControlConfig, ControllerState, KnobDirection};
+use crate::controller::driver::MidiEvent;
+
+/// Feature of the controller that can have its menus open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureKind {
+    Input,
+    Output,
+    Plugin,
+    Persistence,
+    Performance,
+    Parameter,
+    Record,
+    Metronome,
+    Mixer,
+    Crossfade,
+    Preset,
+    Arrange,
+    Settings,
+    Monitor,
+    SetList,
+    Group,
+    ChainPreset,
+    Clip,
+    Macro,
+    NodeNumber,
+    History,
+    Goto,
+    Bookmark,
+    Tag,
+    Feedback,
+    Rename,
+}
+
+impl FeatureKind {
+    /// All features, asked in this order for their context menu entries
+    pub const ALL: [FeatureKind; 26] = [
+        FeatureKind::Input,
+        FeatureKind::Output,
+        FeatureKind::Plugin,
+        FeatureKind::Persistence,
+        FeatureKind::Performance,
+        FeatureKind::Parameter,
+        FeatureKind::Record,
+        FeatureKind::Metronome,
+        FeatureKind::Mixer,
+        FeatureKind::Crossfade,
+        FeatureKind::Preset,
+        FeatureKind::Arrange,
+        FeatureKind::Settings,
+        FeatureKind::Monitor,
+        FeatureKind::SetList,
+        FeatureKind::Group,
+        FeatureKind::ChainPreset,
+        FeatureKind::Clip,
+        FeatureKind::Macro,
+        FeatureKind::NodeNumber,
+        FeatureKind::History,
+        FeatureKind::Goto,
+        FeatureKind::Bookmark,
+        FeatureKind::Tag,
+        FeatureKind::Feedback,
+        FeatureKind::Rename,
+    ];
+}
+
+/// Entry contributed to the context menu of a grid element
+#[derive(Debug, Clone)]
+pub struct ContextEntry {
+    /// Position in the menu, lower first
+    pub order: u32,
+    pub option: MenuOption,
+}
+
+impl ContextEntry {
+    pub fn new(order: u32, id: &str, label: &str) -> Self {
+        Self {
+            order,
+            option: MenuOption {
+                id: id.to_string(),
+                label: label.to_string(),
+            },
+        }
+    }
+}
 
 /// Feature interface for extending controller functionality
 pub trait Feature {
     /// Get the menu for this feature
     fn get_menu(&self) -> Menu;
     
+    /// Set the UI element the feature is opened on, before its first menu is built
+    fn set_element(&mut self, _element: Option<&crate::ui::Element>) {}
+    
+    /// Get the entries the feature adds to the context menu of a link or node
+    fn context_entries(&self, _element: &Element) -> Vec<ContextEntry> {
+        Vec::new()
+    }
+    
+    /// Handle the selection of one of the feature's context menu entries and return the next controller state
+    /// By default the feature's menu opens on the element
+    fn select_context_entry(&mut self, _option_id: &str, element: Option<&Element>) -> Result<ControllerState> {
+        self.set_element(element);
+        Ok(ControllerState::BrowsingMenu)
+    }
+    
+    /// Whether the current menu lists JACK ports and must be rebuilt when they change
+    fn lists_jack_ports(&self) -> bool {
+        false
+    }
+    
     /// Handle menu option selection and return the next controller state
     /// If option_id is None, the top-most menu was closed and the feature should revert to previous state
     /// element is the UI element that was focused when the feature was opened (e.g., a link)
     fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState>;
+    
+    /// Turn a page of the feature's menu with the secondary knob
+    /// Return true when the menu changed and must be rebuilt, otherwise the knob jumps in the menu
+    fn turn_page(&mut self, _menu_id: &str, _direction: KnobDirection) -> Result<bool> {
+        Ok(false)
+    }
+    
+    /// Handle a raw MIDI event received while the feature's menu, or a context menu it has entries in, is open,
+    /// before the menu navigation. The controls tell the knobs apart and the element is the one of the menu.
+    /// Return true when the event was consumed, e.g. by a feature following a knob continuously
+    fn handle_midi_event(&mut self, _event: &MidiEvent, _controls: &BaseControlConfig, _element: Option<&Element>) -> Result<bool> {
+        Ok(false)
+    }
 }
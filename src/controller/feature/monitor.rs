@@ -0,0 +1,143 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::driver::{Driver, describe_midi};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Time after its last message during which a source is shown as active
+const ACTIVITY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// MIDI monitor feature showing the incoming messages and which sources are active
+/// It also gives access to the MIDI panic and to the xrun count of the driver
+pub struct MidiMonitorFeature {
+    driver: Arc<Driver>,
+    ui: Arc<UI>,
+    /// Message count and active sources of the menu last shown
+    shown: (u64, Vec<String>),
+}
+
+impl MidiMonitorFeature {
+    /// Create a new MIDI monitor feature
+    pub fn new(driver: Arc<Driver>, ui: Arc<UI>) -> Self {
+        Self {
+            driver,
+            ui,
+            shown: (0, Vec::new()),
+        }
+    }
+
+    /// Get the sources that sent a message recently, sorted by name
+    fn active_sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = self.driver.midi_monitor().activity.into_iter()
+            .filter(|(_, time)| time.elapsed() < ACTIVITY_TIMEOUT)
+            .map(|(source, _)| source)
+            .collect();
+        sources.sort();
+        sources
+    }
+
+    /// Check whether new messages came or an indicator changed since the menu was last shown
+    pub fn take_changed(&mut self) -> bool {
+        let current = (self.driver.midi_monitor().generation, self.active_sources());
+        if current == self.shown {
+            return false;
+        }
+        self.shown = current;
+        true
+    }
+}
+
+impl Feature for MidiMonitorFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) => {
+                let mut entries = Vec::new();
+                // Clear Xruns only when some occurred
+                let xruns = self.driver.xrun_count();
+                if xruns > 0 {
+                    entries.push(ContextEntry::new(70, "clear_xruns", &format!("Clear Xruns ({})", xruns)));
+                }
+                entries.push(ContextEntry::new(72, "panic", "MIDI Panic"));
+                entries.push(ContextEntry::new(85, "midi_monitor", "MIDI Monitor >"));
+                entries.push(ContextEntry::new(97, "learn_panic", "Learn Panic Button"));
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        match option_id {
+            "clear_xruns" => {
+                // Acknowledge the xruns
+                self.driver.clear_xruns();
+                Ok(ControllerState::Navigating)
+            }
+            "panic" => {
+                match self.driver.panic() {
+                    Ok(()) => self.ui.show_message("All notes off")?,
+                    Err(e) => self.ui.show_message(&e.to_string())?,
+                }
+                Ok(ControllerState::Navigating)
+            }
+            "learn_panic" => {
+                // Wait for the panic button with all menus closed
+                self.ui.show_message("Press the panic button")?;
+                Ok(ControllerState::LearningPanicButton)
+            }
+            _ => Ok(ControllerState::BrowsingMenu),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        let monitor = self.driver.midi_monitor();
+
+        // Activity indicators first, then the messages from the newest
+        let mut sources: Vec<_> = monitor.activity.iter().collect();
+        sources.sort_by(|a, b| a.0.cmp(b.0));
+        let mut options: Vec<MenuOption> = sources.into_iter()
+            .enumerate()
+            .map(|(i, (source, time))| MenuOption {
+                id: format!("source_{}", i),
+                label: if time.elapsed() < ACTIVITY_TIMEOUT {
+                    format!("● {}", source)
+                } else {
+                    format!("○ {} ({}s ago)", source, time.elapsed().as_secs())
+                },
+            })
+            .collect();
+        options.extend(monitor.messages.iter().rev()
+            .enumerate()
+            .map(|(i, (source, bytes))| MenuOption {
+                id: format!("message_{}", i),
+                label: format!("{}: {}", source, describe_midi(bytes)),
+            }));
+        if options.is_empty() {
+            options.push(MenuOption {
+                id: "empty".to_string(),
+                label: "No MIDI received yet".to_string(),
+            });
+        }
+
+        Menu {
+            id: "midi_monitor".to_string(),
+            label: "MIDI Monitor".to_string(),
+            options,
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("MIDI monitor feature handle_menu_option: {:?}", option_id);
+
+        // The lines are read only, selecting one closes the monitor like going back
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new MIDI monitor feature
+pub fn new_midi_monitor_feature(driver: Arc<Driver>, ui: Arc<UI>) -> MidiMonitorFeature {
+    MidiMonitorFeature::new(driver, ui)
+}
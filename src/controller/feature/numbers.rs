@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Numbers offered for the nodes, reached with Program Change 0 to 31
+const NODE_NUMBERS: u8 = 32;
+
+/// Get the number reached by a Program Change, counting from 1 like the menu
+pub fn program_number(program: u8) -> u8 {
+    program.saturating_add(1)
+}
+
+/// Node number feature giving random access to nodes with Program Change
+pub struct NodeNumberFeature {
+    ui: Arc<UI>,
+    /// Node path of each assigned number
+    numbers: BTreeMap<u8, String>,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl NodeNumberFeature {
+    /// Create a new node number feature with the saved numbers
+    pub fn new(ui: Arc<UI>) -> Self {
+        Self {
+            ui,
+            numbers: Self::load_numbers(),
+            ui_element: None,
+        }
+    }
+
+    /// Get the node number file path
+    fn get_numbers_path() -> Result<PathBuf> {
+        Ok(Settings::get_home_dir()?.join("node_numbers.json"))
+    }
+
+    /// Load the saved numbers, none when the file is missing or invalid
+    fn load_numbers() -> BTreeMap<u8, String> {
+        let Ok(path) = Self::get_numbers_path() else {
+            return BTreeMap::new();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            return BTreeMap::new();
+        };
+        serde_json::from_str(&content)
+            .map_err(|e| warn!("Invalid node number file {:?}: {}", path, e))
+            .unwrap_or_default()
+    }
+
+    /// Save the numbers
+    fn save_numbers(&self) -> Result<()> {
+        let path = Self::get_numbers_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).context("Failed to create node number directory")?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&self.numbers)?)
+            .context("Failed to write node numbers")
+    }
+
+    /// Get the node reached by a Program Change
+    pub fn node_for_program(&self, program: u8) -> Option<&str> {
+        self.numbers.get(&program_number(program)).map(String::as_str)
+    }
+
+    /// Get the number of a node
+    fn number_of(&self, node: &str) -> Option<u8> {
+        self.numbers.iter().find(|(_, n)| n.as_str() == node).map(|(number, _)| *number)
+    }
+
+    /// Give a number to a node, taking it from any other node, or clear its number with None
+    fn assign(&mut self, node: &str, number: Option<u8>) -> Result<()> {
+        self.numbers.retain(|_, n| n != node);
+        if let Some(number) = number {
+            if let Some(previous) = self.numbers.insert(number, node.to_string()) {
+                debug!("Number {} moved from {} to {}", number, previous, node);
+            }
+        }
+        info!("Number of {}: {:?}", node, number);
+        self.save_numbers()
+    }
+
+    /// Get the number selection menu of a node, with the taken numbers named
+    fn get_number_menu(&self) -> Menu {
+        let node = match &self.ui_element {
+            Some(crate::ui::Element::Node(node)) => Some(node.as_str()),
+            _ => None,
+        };
+        let current = node.and_then(|node| self.number_of(node));
+
+        let mut options = Vec::new();
+        if current.is_some() {
+            options.push(MenuOption {
+                id: "clear".to_string(),
+                label: "No Number".to_string(),
+            });
+        }
+        options.extend((1..=NODE_NUMBERS).map(|number| {
+            let label = match self.numbers.get(&number) {
+                Some(_) if Some(number) == current => format!("{} ✓", number),
+                Some(n) => format!("{} ({})", number, n.rsplit('/').next().unwrap_or(n)),
+                None => number.to_string(),
+            };
+            MenuOption { id: number.to_string(), label }
+        }));
+
+        Menu {
+            id: "node_numbers".to_string(),
+            label: "Number".to_string(),
+            options,
+        }
+    }
+}
+
+impl Feature for NodeNumberFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => {
+                let label = match self.number_of(node) {
+                    Some(number) => format!("Number: {} >", number),
+                    None => "Assign Number >".to_string(),
+                };
+                vec![ContextEntry::new(41, "assign_number", &label)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        self.get_number_menu()
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Node number feature handle_menu_option: {:?}", option_id);
+
+        let (Some(option), Some(crate::ui::Element::Node(node))) = (option_id, self.ui_element.take()) else {
+            return Ok(ControllerState::Navigating);
+        };
+        let name = node.rsplit('/').next().unwrap_or(&node).to_string();
+        match option.parse::<u8>() {
+            Ok(number) => {
+                self.assign(&node, Some(number))?;
+                self.ui.show_message(&format!("{}: Program Change {}", name, number))?;
+            }
+            Err(_) => {
+                self.assign(&node, None)?;
+                self.ui.show_message(&format!("{}: no number", name))?;
+            }
+        }
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new node number feature
+pub fn new_node_number_feature(ui: Arc<UI>) -> NodeNumberFeature {
+    NodeNumberFeature::new(ui)
+}
@@ -0,0 +1,532 @@
+use anyhow::Result;
+use log::{debug, info};
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, KnobDirection, feature::{ContextEntry, Feature}};
+use crate::controller::tempo::{BeatDivision, Tempo};
+use crate::engine::{ControlPort, Engine, PortDirection, PortType};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Menu state for the parameter feature
+#[derive(Debug, Clone, PartialEq)]
+enum ParameterMenuState {
+    ParameterList,
+    ValueSelection(String), // control port symbol
+    VoiceSelection,
+}
+
+/// Voice counts offered for the synth blocks
+const VOICES: [u32; 7] = [1, 2, 4, 6, 8, 12, 16];
+
+/// Get the number of milliseconds per unit of a time-based control, if it is one
+/// Controls without a declared unit are considered time-based when their name says so
+pub fn time_scale_ms(control: &ControlPort) -> Option<f32> {
+    match control.unit.as_deref() {
+        Some("ms") => Some(1.0),
+        Some("s") => Some(1000.0),
+        Some(_) => None,
+        None => {
+            let name = control.name.to_lowercase();
+            if name.contains("time") || name.contains("delay") {
+                // Guess the unit from the range: seconds rarely go above a minute
+                Some(if control.max > 60.0 { 1.0 } else { 1000.0 })
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Tempo change from which the synced parameters are recomputed, to ignore the jitter of the MIDI clock
+const TEMPO_SYNC_TOLERANCE: f32 = 0.5;
+
+/// Get the value of a time-based control lasting a beat division at a tempo, within the control range
+pub fn tempo_synced_value(control: &ControlPort, division: BeatDivision, bpm: f32) -> Option<f32> {
+    let scale = time_scale_ms(control)?;
+    Some((division.duration_ms(bpm) / scale).clamp(control.min, control.max))
+}
+
+/// Identifier of the parameter list menu, whose pages the secondary knob turns
+const PARAMETER_LIST_MENU_ID: &str = "parameter_list";
+
+/// Split controls into pages by port group, in the order the groups first appear
+/// Controls without a group share a page without a name
+pub fn control_pages(controls: &[ControlPort]) -> Vec<(Option<String>, Vec<ControlPort>)> {
+    let mut pages: Vec<(Option<String>, Vec<ControlPort>)> = Vec::new();
+    for control in controls {
+        match pages.iter_mut().find(|(group, _)| *group == control.group) {
+            Some((_, page)) => page.push(control.clone()),
+            None => pages.push((control.group.clone(), vec![control.clone()])),
+        }
+    }
+    pages
+}
+
+/// Set all the controls of a block to their default values, returning how many were set
+pub fn reset_controls(engine: &Engine, block_id: &str) -> Result<usize> {
+    let plugin = engine.get_block_plugin(block_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown plugin for {}", block_id))?;
+    info!("Resetting the parameters of {}", block_id);
+    for control in &plugin.controls {
+        engine.set_control_parameter(block_id, &control.id, control.default)?;
+    }
+    Ok(plugin.controls.len())
+}
+
+/// Set all the controls of a block to random values within their ranges, returning how many were set
+pub fn randomize_controls(engine: &Engine, block_id: &str, rng: &mut impl Rng) -> Result<usize> {
+    let plugin = engine.get_block_plugin(block_id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown plugin for {}", block_id))?;
+    info!("Randomizing the parameters of {}", block_id);
+    for control in &plugin.controls {
+        let value = if control.max > control.min { rng.gen_range(control.min..=control.max) } else { control.default };
+        engine.set_control_parameter(block_id, &control.id, value)?;
+    }
+    Ok(plugin.controls.len())
+}
+
+/// Parameter feature for setting the control values of a block
+pub struct ParameterFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    tempo: Arc<Tempo>,
+    menu_state: ParameterMenuState,
+    ui_element: Option<crate::ui::Element>,
+    /// Port group page shown in the parameter list
+    page: usize,
+    /// Beat division followed by each tempo-synced control, keyed by block path and control symbol
+    tempo_sync: HashMap<(String, String), BeatDivision>,
+    /// Tempo the synced controls were last computed at
+    synced_bpm: Option<f32>,
+}
+
+impl ParameterFeature {
+    /// Create a new parameter feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>, tempo: Arc<Tempo>) -> Self {
+        Self {
+            engine,
+            ui,
+            tempo,
+            menu_state: ParameterMenuState::ParameterList,
+            ui_element: None,
+            page: 0,
+            tempo_sync: HashMap::new(),
+            synced_bpm: None,
+        }
+    }
+
+    /// Get the block path of the selected node
+    fn block_id(&self) -> Option<&str> {
+        match &self.ui_element {
+            Some(crate::ui::Element::Node(block_id)) => Some(block_id),
+            _ => None,
+        }
+    }
+
+    /// Get the controls of the selected node
+    fn controls(&self) -> Vec<ControlPort> {
+        self.block_id()
+            .and_then(|block_id| self.engine.get_block_plugin(block_id))
+            .map(|plugin| plugin.controls.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the parameter list menu
+    /// The current values are read back from the engine, the defaults are only assumed when it has none
+    fn get_parameter_list_menu(&self) -> Menu {
+        let values = self.block_id()
+            .and_then(|block_id| self.engine.get_control_values(block_id).ok())
+            .unwrap_or_default();
+        let pages = control_pages(&self.controls());
+        let (group, controls) = pages.get(self.page % pages.len().max(1)).cloned().unwrap_or_default();
+        let options: Vec<MenuOption> = controls.iter()
+            .map(|control| {
+                let value = values.get(&control.id).copied().unwrap_or(control.default);
+                let label = match self.synced_division(&control.id) {
+                    Some(division) => format!("{}: {} ({}) >", control.name, value, division.label()),
+                    None => format!("{}: {} >", control.name, value),
+                };
+                MenuOption {
+                    id: control.id.clone(),
+                    label,
+                }
+            })
+            .collect();
+
+        let label = if pages.len() > 1 {
+            format!("{} ({}/{})", group.as_deref().unwrap_or("Parameters"), self.page % pages.len() + 1, pages.len())
+        } else {
+            "Parameters".to_string()
+        };
+
+        Menu {
+            id: PARAMETER_LIST_MENU_ID.to_string(),
+            label,
+            options,
+        }
+    }
+
+    /// Get the value selection menu for a control
+    fn get_value_selection_menu(&self, control_id: &str) -> Menu {
+        let controls = self.controls();
+        let control = controls.iter().find(|c| c.id == control_id);
+
+        let mut options = Vec::new();
+        let mut label = control_id.to_string();
+
+        if let Some(control) = control {
+            label = control.name.clone();
+
+            options.push(MenuOption {
+                id: "default".to_string(),
+                label: format!("Default ({})", control.default),
+            });
+
+            // Offer beat divisions for time-based parameters, the chosen one following the tempo
+            if time_scale_ms(control).is_some() {
+                let synced = self.synced_division(&control.id);
+                if let Some(division) = synced {
+                    options.push(MenuOption {
+                        id: "sync_off".to_string(),
+                        label: format!("Stop Tempo Sync ({})", division.label()),
+                    });
+                }
+                let bpm = self.tempo.get_bpm();
+                for division in BeatDivision::all() {
+                    let label = match bpm {
+                        Some(bpm) => format!("{} ({:.0} ms)", division.label(), division.duration_ms(bpm)),
+                        None => division.label().to_string(),
+                    };
+                    options.push(MenuOption {
+                        id: division.id().to_string(),
+                        label: format!("{}{}", label, if synced == Some(*division) { " ✓" } else { "" }),
+                    });
+                }
+            }
+        }
+
+        Menu {
+            id: format!("parameter_value_{}", control_id),
+            label,
+            options,
+        }
+    }
+
+    /// Check whether a block is a synth, played from MIDI into audio, that can have several voices
+    fn is_synth(&self, block_id: &str) -> bool {
+        self.engine.get_block_plugin(block_id).is_some_and(|plugin| {
+            let has = |port_type: PortType, direction: PortDirection| plugin.ports.iter()
+                .any(|p| p.port_type == port_type && p.direction == direction);
+            has(PortType::Midi, PortDirection::Input) && has(PortType::Audio, PortDirection::Output)
+        })
+    }
+
+    /// Get the voice count menu, with the current count checked
+    fn get_voice_selection_menu(&self) -> Menu {
+        let current = self.block_id()
+            .and_then(|block_id| self.engine.get_polyphony(block_id).ok())
+            .unwrap_or(1);
+        Menu {
+            id: "parameter_voices".to_string(),
+            label: "Voices".to_string(),
+            options: VOICES.iter()
+                .map(|voices| {
+                    let label = if *voices == 1 { "Mono".to_string() } else { format!("{} Voices", voices) };
+                    MenuOption {
+                        id: voices.to_string(),
+                        label: format!("{}{}", label, if *voices == current { " ✓" } else { "" }),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Set a time-based parameter to the duration of a beat division at the current tempo
+    /// Returns the value sent to the engine
+    pub fn set_beat_division(&self, block_id: &str, control: &ControlPort, division: BeatDivision) -> Result<f32> {
+        let bpm = self.tempo.get_bpm()
+            .ok_or_else(|| anyhow::anyhow!("No tempo available, tap the tempo or send MIDI clock"))?;
+        let value = tempo_synced_value(control, division, bpm)
+            .ok_or_else(|| anyhow::anyhow!("Parameter {} is not time-based", control.name))?;
+        info!("Setting {} to {} at {:.1} BPM: {}", control.name, division.label(), bpm, value);
+
+        self.engine.set_control_parameter(block_id, &control.id, value)?;
+        Ok(value)
+    }
+
+    /// Get the beat division a control of the selected node follows, if synced to the tempo
+    fn synced_division(&self, control_id: &str) -> Option<BeatDivision> {
+        let block_id = self.block_id()?;
+        self.tempo_sync.get(&(block_id.to_string(), control_id.to_string())).copied()
+    }
+
+    /// Recompute the tempo-synced controls when the tempo changed
+    pub fn update_tempo_sync(&mut self) -> Result<()> {
+        if self.tempo_sync.is_empty() {
+            return Ok(());
+        }
+        let Some(bpm) = self.tempo.get_bpm() else {
+            return Ok(());
+        };
+        if self.synced_bpm.is_some_and(|synced| (synced - bpm).abs() < TEMPO_SYNC_TOLERANCE) {
+            return Ok(());
+        }
+        self.synced_bpm = Some(bpm);
+
+        let synced: Vec<_> = self.tempo_sync.iter().map(|(key, division)| (key.clone(), *division)).collect();
+        for ((block_id, control_id), division) in synced {
+            let control = self.engine.get_block_plugin(&block_id)
+                .and_then(|plugin| plugin.controls.into_iter().find(|c| c.id == control_id));
+            match control {
+                Some(control) => {
+                    self.set_beat_division(&block_id, &control, division)?;
+                }
+                None => {
+                    debug!("Dropping the tempo sync of {}/{}, gone with its block", block_id, control_id);
+                    self.tempo_sync.remove(&(block_id, control_id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the selected value option to a control
+    /// Choosing a beat division syncs the control to the tempo, any other value stops the sync
+    fn apply_value(&mut self, control_id: &str, option: &str) -> Result<()> {
+        let block_id = self.block_id()
+            .ok_or_else(|| anyhow::anyhow!("Parameter feature requires a node element"))?
+            .to_string();
+        let control = self.controls().into_iter()
+            .find(|c| c.id == control_id)
+            .ok_or_else(|| anyhow::anyhow!("Parameter not found: {}", control_id))?;
+        let key = (block_id.clone(), control.id.clone());
+
+        if option == "default" {
+            self.tempo_sync.remove(&key);
+            self.engine.set_control_parameter(&block_id, &control.id, control.default)?;
+            self.ui.show_message(&format!("{}: {}", control.name, control.default))?;
+        } else if option == "sync_off" {
+            self.tempo_sync.remove(&key);
+            self.ui.show_message(&format!("{}: tempo sync off", control.name))?;
+        } else if let Some(division) = BeatDivision::from_id(option) {
+            match self.set_beat_division(&block_id, &control, division) {
+                Ok(value) => {
+                    self.tempo_sync.insert(key, division);
+                    self.synced_bpm = self.tempo.get_bpm();
+                    self.ui.show_message(&format!("{}: {} ({}), synced to tempo", control.name, division.label(), value))?;
+                }
+                Err(e) => self.ui.show_message(&e.to_string())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Feature for ParameterFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => {
+                let mut entries = vec![ContextEntry::new(10, "parameters", "Parameters >")];
+                let plugin = self.engine.get_block_plugin(node);
+                if plugin.as_ref().is_some_and(|plugin| !plugin.controls.is_empty()) {
+                    entries.push(ContextEntry::new(11, "reset_parameters", "Reset to Defaults"));
+                    entries.push(ContextEntry::new(12, "randomize_parameters", "Randomize"));
+                }
+                if plugin.is_some_and(|plugin| plugin.has_ui) {
+                    entries.push(ContextEntry::new(13, "plugin_ui", "Open Plugin UI"));
+                }
+                if self.is_synth(node) {
+                    entries.push(ContextEntry::new(15, "voices", "Voices >"));
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        if let Some(crate::ui::Element::Node(node)) = element {
+            match option_id {
+                "reset_parameters" => {
+                    let count = reset_controls(&self.engine, node)?;
+                    self.ui.show_message(&format!("Reset {} parameters", count))?;
+                    return Ok(ControllerState::Navigating);
+                }
+                "randomize_parameters" => {
+                    let count = randomize_controls(&self.engine, node, &mut rand::thread_rng())?;
+                    self.ui.show_message(&format!("Randomized {} parameters", count))?;
+                    return Ok(ControllerState::Navigating);
+                }
+                "plugin_ui" => {
+                    let name = node.rsplit('/').next().unwrap_or(node);
+                    match self.engine.open_plugin_ui(node) {
+                        Ok(()) => self.ui.show_message(&format!("Open the UI of {} from the Ingen window", name))?,
+                        Err(e) => self.ui.show_message(&e.to_string())?,
+                    }
+                    return Ok(ControllerState::Navigating);
+                }
+                _ => {}
+            }
+        }
+        self.set_element(element);
+        self.menu_state = if option_id == "voices" {
+            ParameterMenuState::VoiceSelection
+        } else {
+            ParameterMenuState::ParameterList
+        };
+        Ok(ControllerState::BrowsingMenu)
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            ParameterMenuState::ParameterList => self.get_parameter_list_menu(),
+            ParameterMenuState::ValueSelection(control_id) => self.get_value_selection_menu(control_id),
+            ParameterMenuState::VoiceSelection => self.get_voice_selection_menu(),
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+        self.page = 0;
+    }
+
+    fn turn_page(&mut self, menu_id: &str, direction: KnobDirection) -> Result<bool> {
+        if menu_id != PARAMETER_LIST_MENU_ID || self.menu_state != ParameterMenuState::ParameterList {
+            return Ok(false);
+        }
+        let count = control_pages(&self.controls()).len();
+        if count < 2 {
+            return Ok(false);
+        }
+        self.page = match direction {
+            KnobDirection::Forward => (self.page + 1) % count,
+            KnobDirection::Backward => (self.page + count - 1) % count,
+        };
+        Ok(true)
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Parameter feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        // Store the UI element if this is the first call
+        if self.ui_element.is_none() && element.is_some() {
+            self.ui_element = element.cloned();
+        }
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Parameter feature: menu closed");
+            match self.menu_state {
+                ParameterMenuState::ParameterList => {
+                    self.ui_element = None;
+                    return Ok(ControllerState::Navigating);
+                }
+                ParameterMenuState::ValueSelection(_) => {
+                    self.menu_state = ParameterMenuState::ParameterList;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                ParameterMenuState::VoiceSelection => {
+                    self.menu_state = ParameterMenuState::ParameterList;
+                    self.ui_element = None;
+                    return Ok(ControllerState::Navigating);
+                }
+            }
+        };
+
+        match &self.menu_state {
+            ParameterMenuState::ParameterList => {
+                // Parameter selected, show values
+                self.menu_state = ParameterMenuState::ValueSelection(option.to_string());
+                Ok(ControllerState::BrowsingMenu)
+            }
+            ParameterMenuState::ValueSelection(control_id) => {
+                let control_id = control_id.clone();
+                self.apply_value(&control_id, option)?;
+                self.menu_state = ParameterMenuState::ParameterList;
+                self.ui_element = None;
+                Ok(ControllerState::Navigating)
+            }
+            ParameterMenuState::VoiceSelection => {
+                if let (Some(block_id), Ok(voices)) = (self.block_id(), option.parse::<u32>()) {
+                    self.engine.set_polyphony(block_id, voices)?;
+                    self.ui.show_message(&format!("Voices: {}", voices))?;
+                }
+                self.menu_state = ParameterMenuState::ParameterList;
+                self.ui_element = None;
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+/// Helper to create a new parameter feature
+pub fn new_parameter_feature(engine: Arc<Engine>, ui: Arc<UI>, tempo: Arc<Tempo>) -> ParameterFeature {
+    ParameterFeature::new(engine, ui, tempo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_randomize_and_reset() {
+        let engine = Engine::new_mock();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        let block = "ingen:/main/delay";
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        assert_eq!(randomize_controls(&engine, block, &mut rng).unwrap(), 2);
+        let values = engine.get_control_values(block).unwrap();
+        assert!((1.0..=2000.0).contains(&values["time"]));
+        assert!((0.0..=1.0).contains(&values["feedback"]));
+
+        reset_controls(&engine, block).unwrap();
+        let values = engine.get_control_values(block).unwrap();
+        assert_eq!(values["time"], 250.0);
+        assert_eq!(values["feedback"], 0.3);
+    }
+
+    #[test]
+    fn test_tempo_synced_value() {
+        let engine = Engine::new_mock();
+        let plugin = engine.list_plugins().into_iter()
+            .find(|p| p.id == "urn:traxdub:mock:delay")
+            .unwrap();
+        let time = plugin.controls.iter().find(|c| c.id == "time").unwrap();
+        let feedback = plugin.controls.iter().find(|c| c.id == "feedback").unwrap();
+
+        assert_eq!(tempo_synced_value(time, BeatDivision::Quarter, 120.0), Some(500.0));
+        assert_eq!(tempo_synced_value(time, BeatDivision::DottedEighth, 100.0), Some(450.0));
+        assert_eq!(tempo_synced_value(time, BeatDivision::Whole, 60.0), Some(time.max));
+        assert_eq!(tempo_synced_value(feedback, BeatDivision::Quarter, 120.0), None);
+    }
+
+    #[test]
+    fn test_control_pages() {
+        let control = |id: &str, group: Option<&str>| ControlPort {
+            id: id.to_string(),
+            name: id.to_string(),
+            min: 0.0,
+            max: 1.0,
+            default: 0.0,
+            unit: None,
+            property: None,
+            group: group.map(str::to_string),
+        };
+        let controls = [
+            control("attack", Some("Envelope")),
+            control("volume", None),
+            control("cutoff", Some("Filter")),
+            control("release", Some("Envelope")),
+        ];
+        let pages = control_pages(&controls);
+        let groups: Vec<_> = pages.iter().map(|(group, _)| group.as_deref()).collect();
+        assert_eq!(groups, [Some("Envelope"), None, Some("Filter")]);
+        let envelope: Vec<_> = pages[0].1.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(envelope, ["attack", "release"]);
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::Result;
+use log::{debug, info};
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, KnobDirection, feature::{ContextEntry, Feature}};
+use crate::engine::{ControlPort, Engine};
+use crate::ui::{Menu, MenuOption, ParameterDisplay, UI};
+
+/// Number of steps to sweep a parameter over its whole range
+const PARAMETER_STEPS: f32 = 100.0;
+
+/// Knob that a parameter can be mapped to in performance mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KnobSlot {
+    Main,
+    Secondary,
+}
+
+impl KnobSlot {
+    /// Index of the slot in a bank
+    fn index(&self) -> usize {
+        match self {
+            KnobSlot::Main => 0,
+            KnobSlot::Secondary => 1,
+        }
+    }
+
+    /// Get the slot name (lowercase)
+    fn name(&self) -> &str {
+        match self {
+            KnobSlot::Main => "main",
+            KnobSlot::Secondary => "secondary",
+        }
+    }
+}
+
+/// Parameter mapped to a knob
+#[derive(Debug, Clone)]
+struct ParameterBinding {
+    block_id: String,
+    block_name: String,
+    control: ControlPort,
+    value: f32,
+}
+
+/// Set of knob mappings that can be switched during performance
+#[derive(Debug, Clone, Default)]
+struct PerformanceBank {
+    bindings: [Option<ParameterBinding>; 2],
+}
+
+/// Menu state for the performance feature
+#[derive(Debug, Clone, PartialEq)]
+enum PerformanceMenuState {
+    PerformanceMenu,
+    ParameterSelection(KnobSlot),
+}
+
+/// Performance feature mapping the knobs to parameters instead of graph navigation
+pub struct PerformanceFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: PerformanceMenuState,
+    banks: Vec<PerformanceBank>,
+    current_bank: usize,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl PerformanceFeature {
+    /// Create a new performance feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: PerformanceMenuState::PerformanceMenu,
+            banks: vec![PerformanceBank::default()],
+            current_bank: 0,
+            ui_element: None,
+        }
+    }
+
+    /// Get the label of the current bank
+    fn bank_label(&self) -> String {
+        format!("Bank {}/{}", self.current_bank + 1, self.banks.len())
+    }
+
+    /// Get the performance menu
+    fn get_performance_menu(&self) -> Menu {
+        let mut options = vec![
+            MenuOption {
+                id: "map_main".to_string(),
+                label: "Map Main Knob >".to_string(),
+            },
+            MenuOption {
+                id: "map_secondary".to_string(),
+                label: "Map Secondary Knob >".to_string(),
+            },
+            MenuOption {
+                id: "new_bank".to_string(),
+                label: "New Bank".to_string(),
+            },
+        ];
+
+        if self.banks.len() > 1 {
+            options.push(MenuOption {
+                id: "next_bank".to_string(),
+                label: "Next Bank".to_string(),
+            });
+        }
+
+        options.push(MenuOption {
+            id: "start".to_string(),
+            label: "Start Performance".to_string(),
+        });
+
+        Menu {
+            id: "performance_menu".to_string(),
+            label: self.bank_label(),
+            options,
+        }
+    }
+
+    /// Get the parameter selection menu for the selected node
+    fn get_parameter_selection_menu(&self, slot: KnobSlot) -> Menu {
+        let options: Vec<MenuOption> = match &self.ui_element {
+            Some(crate::ui::Element::Node(block_id)) => {
+                self.engine.get_block_plugin(block_id)
+                    .map(|plugin| plugin.controls.iter()
+                        .map(|control| MenuOption {
+                            id: control.id.clone(),
+                            label: control.name.clone(),
+                        })
+                        .collect())
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+
+        Menu {
+            id: format!("performance_parameters_{}", slot.name()),
+            label: "Select Parameter".to_string(),
+            options,
+        }
+    }
+
+    /// Map a parameter of the selected node to a knob in the current bank
+    fn map_parameter(&mut self, slot: KnobSlot, control_id: &str) -> Result<()> {
+        let Some(crate::ui::Element::Node(block_id)) = &self.ui_element else {
+            return Err(anyhow::anyhow!("Performance mapping requires a node element"));
+        };
+
+        let control = self.engine.get_block_plugin(block_id)
+            .and_then(|plugin| plugin.controls.iter().find(|c| c.id == control_id).cloned())
+            .ok_or_else(|| anyhow::anyhow!("Parameter not found: {}", control_id))?;
+
+        let block_name = block_id.split('/').next_back().unwrap_or(block_id).to_string();
+
+        info!("Mapping {} of {} to {} knob in {}", control.name, block_name, slot.name(), self.bank_label());
+        self.ui.show_message(&format!("{} mapped to {} knob", control.name, slot.name()))?;
+
+        // Start from the value read back from the engine
+        let value = self.engine.get_control_values(block_id).ok()
+            .and_then(|values| values.get(&control.id).copied())
+            .unwrap_or(control.default);
+
+        self.banks[self.current_bank].bindings[slot.index()] = Some(ParameterBinding {
+            block_id: block_id.clone(),
+            block_name,
+            value,
+            control,
+        });
+
+        Ok(())
+    }
+
+    /// Refresh the parameters shown in the UI
+    fn display(&self) -> Result<()> {
+        let parameters = self.banks[self.current_bank].bindings.iter()
+            .map(|binding| binding.as_ref().map(|b| {
+                let range = b.control.max - b.control.min;
+                ParameterDisplay {
+                    name: b.control.name.clone(),
+                    title: b.block_name.clone(),
+                    value: if range > 0.0 { (b.value - b.control.min) / range } else { 0.0 },
+                }
+            }))
+            .collect();
+
+        self.ui.show_performance(self.bank_label(), parameters)
+    }
+
+    /// Enter performance mode
+    pub fn start(&self) -> Result<()> {
+        info!("Entering performance mode ({})", self.bank_label());
+        self.display()
+    }
+
+    /// Leave performance mode
+    pub fn stop(&self) -> Result<()> {
+        info!("Leaving performance mode");
+        self.ui.hide_performance()
+    }
+
+    /// Switch to the next bank of mappings
+    pub fn next_bank(&mut self) -> Result<()> {
+        self.current_bank = (self.current_bank + 1) % self.banks.len();
+        debug!("Switched to {}", self.bank_label());
+        self.display()
+    }
+
+    /// Move the parameter mapped to a knob by a number of steps in the given direction
+    pub fn adjust(&mut self, slot: KnobSlot, direction: KnobDirection, steps: u32) -> Result<()> {
+        let Some(binding) = self.banks[self.current_bank].bindings[slot.index()].as_mut() else {
+            debug!("No parameter mapped to {} knob", slot.name());
+            return Ok(());
+        };
+
+        let step = (binding.control.max - binding.control.min) / PARAMETER_STEPS * steps as f32;
+        let value = match direction {
+            KnobDirection::Forward => binding.value + step,
+            KnobDirection::Backward => binding.value - step,
+        };
+        binding.value = value.clamp(binding.control.min, binding.control.max);
+
+        self.engine.set_control_parameter(&binding.block_id, &binding.control.id, binding.value)?;
+        self.display()
+    }
+}
+
+impl Feature for PerformanceFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        let order = if matches!(element, crate::ui::Element::Node(_)) { 80 } else { 40 };
+        vec![ContextEntry::new(order, "performance", "Performance >")]
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            PerformanceMenuState::PerformanceMenu => self.get_performance_menu(),
+            PerformanceMenuState::ParameterSelection(slot) => self.get_parameter_selection_menu(*slot),
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Performance feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        // Store the UI element if this is the first call
+        if self.ui_element.is_none() && element.is_some() {
+            self.ui_element = element.cloned();
+        }
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Performance feature: menu closed");
+            match self.menu_state {
+                PerformanceMenuState::PerformanceMenu => {
+                    self.ui_element = None;
+                    return Ok(ControllerState::Navigating);
+                }
+                PerformanceMenuState::ParameterSelection(_) => {
+                    self.menu_state = PerformanceMenuState::PerformanceMenu;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+        };
+
+        let next_state = match &self.menu_state {
+            PerformanceMenuState::PerformanceMenu => {
+                match option {
+                    "map_main" | "map_secondary" => {
+                        if matches!(self.ui_element, Some(crate::ui::Element::Node(_))) {
+                            let slot = if option == "map_main" { KnobSlot::Main } else { KnobSlot::Secondary };
+                            self.menu_state = PerformanceMenuState::ParameterSelection(slot);
+                            return Ok(ControllerState::BrowsingMenu);
+                        }
+                        self.ui.show_message("Select a node to map its parameters")?;
+                        ControllerState::Navigating
+                    }
+                    "new_bank" => {
+                        self.banks.push(PerformanceBank::default());
+                        self.current_bank = self.banks.len() - 1;
+                        self.ui.show_message(&format!("{} created", self.bank_label()))?;
+                        ControllerState::Navigating
+                    }
+                    "next_bank" => {
+                        self.current_bank = (self.current_bank + 1) % self.banks.len();
+                        self.ui.show_message(&self.bank_label())?;
+                        ControllerState::Navigating
+                    }
+                    "start" => ControllerState::Performing,
+                    _ => ControllerState::Navigating,
+                }
+            }
+            PerformanceMenuState::ParameterSelection(slot) => {
+                let slot = *slot;
+                self.map_parameter(slot, option)?;
+                ControllerState::Navigating
+            }
+        };
+
+        self.menu_state = PerformanceMenuState::PerformanceMenu;
+        self.ui_element = None;
+        Ok(next_state)
+    }
+}
+
+/// Helper to create a new performance feature
+pub fn new_performance_feature(engine: Arc<Engine>, ui: Arc<UI>) -> PerformanceFeature {
+    PerformanceFeature::new(engine, ui)
+}
@@ -1,14 +1,40 @@
 use anyhow::Result;
 use log::{debug, info, warn};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
 
-use crate::controller::{ControllerState, feature::Feature};
-use crate::controller::driver::{Driver, PortType};
-use crate::engine::Engine;
+use crate::config::Settings;
+use crate::controller::{ControllerState, KnobDirection, backup, feature::{ContextEntry, Feature}};
+use crate::controller::feature::mixer;
+use crate::controller::feature::tags::tag_color;
+use crate::controller::feature::compare::StateDiff;
+use crate::controller::feature::rename::{TextEditor, TextEntry};
+use crate::controller::driver::{Driver, PortChange, PortType};
+use crate::engine::{Engine, StateContent};
+use crate::engine::export::{self, ExportFormat};
 use crate::ui::{Menu, MenuOption, UI};
+use crate::ui::model::LinkRank;
+
+/// Engine state written on exit, not matching the timestamped save names
+const EXIT_STATE_FILE: &str = "exit.txd";
+/// JACK connections written on exit
+const EXIT_CONNECTIONS_FILE: &str = "exit.jack.json";
+/// Session name of the exit snapshot
+const EXIT_SESSION_FILE: &str = "exit.session";
+/// Graph bundle written by Ingen with each save, loaded by Ingen at boot when preloading is enabled
+const PRELOAD_BUNDLE: &str = "preload.ingen";
+/// Name of the state file the preload bundle was written for
+const PRELOAD_SAVE_FILE: &str = "preload.save";
+/// Length of the notes shown in the session list
+const NOTES_PREVIEW_LENGTH: usize = 24;
+/// Time between the checks of the session for unsaved changes
+const DIRTY_CHECK_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Menu state for the persistence feature
 #[derive(Debug, Clone, PartialEq)]
@@ -16,6 +42,78 @@ enum PersistenceMenuState {
     FileMenu,
     LoadSelection,
     TimestampSelection(String), // mnemonic
+    TagFilterSelection,
+    CompareSessionSelection(Option<(String, String)>), // first save picked, as (mnemonic, timestamp)
+    CompareTimestampSelection(String, Option<(String, String)>), // mnemonic, first save picked
+    CompareResult(Vec<String>), // differences
+    ImportSelection,
+    ExportSelection,
+    UnsavedChanges(Option<(String, String)>), // save to load as (mnemonic, timestamp), None for the exit snapshot
+}
+
+/// Notes, tags and link navigation of a saved session, shared by all its saves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct SessionInfo {
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Order and visits of the links, so that the navigation habits survive restarts
+    #[serde(default)]
+    links: Vec<LinkRank>,
+    /// Tags of the blocks, by block id
+    #[serde(default)]
+    node_tags: BTreeMap<String, String>,
+}
+
+impl SessionInfo {
+    /// Add a tag, lowercase and without spaces, returning false if it is empty or already there
+    fn add_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim().to_lowercase().replace(' ', "-");
+        if tag.is_empty() || self.tags.contains(&tag) {
+            return false;
+        }
+        self.tags.push(tag);
+        self.tags.sort();
+        true
+    }
+
+    /// Format the tags and the beginning of the notes to follow the session name
+    fn summary(&self) -> String {
+        let mut summary = String::new();
+        if !self.tags.is_empty() {
+            summary.push_str(&format!(" [{}]", self.tags.join(", ")));
+        }
+        if !self.notes.is_empty() {
+            let preview: String = self.notes.chars().take(NOTES_PREVIEW_LENGTH).collect();
+            let ellipsis = if self.notes.chars().count() > NOTES_PREVIEW_LENGTH { "…" } else { "" };
+            summary.push_str(&format!(" - {}{}", preview, ellipsis));
+        }
+        summary
+    }
+}
+
+/// Session text edited with the knobs or typed in a frontend
+#[derive(Debug, Clone, PartialEq)]
+enum SessionText {
+    Notes,
+    Tag,
+}
+
+/// JACK connection of a system port, saved next to the session file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct JackConnection {
+    source: String,
+    destination: String,
+}
+
+/// System port whose JACK device was missing when the session was loaded
+#[derive(Debug, Clone, PartialEq)]
+struct PendingBinding {
+    system_port_id: String,
+    sanitized_name: String,
+    direction: crate::engine::PortDirection,
+    port_type: PortType,
 }
 
 /// Persistence feature for saving and loading engine state
@@ -23,19 +121,41 @@ pub struct PersistenceFeature {
     driver: Arc<Driver>,
     engine: Arc<Engine>,
     ui: Arc<UI>,
+    settings: Arc<Mutex<Settings>>,
     menu_state: PersistenceMenuState,
     current_mnemonic: Option<String>,
+    pending_bindings: Vec<PendingBinding>,
+    /// Time of the last save or load, for autosave
+    last_save: Instant,
+    /// Tag the session list is filtered by
+    tag_filter: Option<String>,
+    /// Session and text being edited
+    text_entry: Option<(String, SessionText, TextEntry)>,
+    /// Fingerprint of the graph and its values when saved or loaded, None to take the next one
+    saved_fingerprint: Option<u64>,
+    /// Whether the session changed since it was saved or loaded
+    dirty: bool,
+    /// Last check for unsaved changes
+    last_dirty_check: Option<Instant>,
 }
 
 impl PersistenceFeature {
     /// Create a new persistence feature
-    pub fn new(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, auto_load: bool) -> Self {
+    pub fn new(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>, auto_load: bool) -> Self {
         let mut feature = Self {
             driver,
             engine,
             ui,
+            settings,
             menu_state: PersistenceMenuState::FileMenu,
             current_mnemonic: None,
+            pending_bindings: Vec::new(),
+            last_save: Instant::now(),
+            tag_filter: None,
+            text_entry: None,
+            saved_fingerprint: None,
+            dirty: false,
+            last_dirty_check: None,
         };
         
         // Auto-load most recent save if requested
@@ -51,7 +171,7 @@ impl PersistenceFeature {
     /// Load the most recent saved state
     fn load_most_recent(&mut self) -> Result<()> {
         // Get all saved files
-        let store_dir = Self::get_store_dir()?;
+        let store_dir = self.get_store_dir()?;
         
         if !store_dir.exists() {
             return Err(anyhow::anyhow!("No saved sessions found"));
@@ -59,7 +179,7 @@ impl PersistenceFeature {
         
         let mut files: Vec<(String, String)> = Vec::new();
         
-        for entry in fs::read_dir(store_dir)? {
+        for entry in fs::read_dir(&store_dir)? {
             let entry = entry?;
             let filename = entry.file_name().to_string_lossy().to_string();
             
@@ -80,8 +200,16 @@ impl PersistenceFeature {
         info!("Auto-loading most recent session: {} ({})", 
               Self::format_mnemonic_display(mnemonic), timestamp);
         
+        // Ingen may have loaded the graph of this save at boot already
+        let preloaded = self.engine.preloaded_bundle().is_some_and(|bundle| preload_bundle(&store_dir) == Some(bundle));
+        
         // Load the state
-        self.load_state(timestamp, mnemonic)?;
+        self.load_state_files(
+            &store_dir.join(Self::build_filename(timestamp, mnemonic)),
+            &store_dir.join(Self::build_connections_filename(timestamp, mnemonic)),
+            Some(mnemonic),
+            preloaded,
+        )?;
         
         // Set the current mnemonic
         self.current_mnemonic = Some(mnemonic.clone());
@@ -93,11 +221,105 @@ impl PersistenceFeature {
         Ok(())
     }
     
+    /// Load the latest save of the session following the current one, for the scene footswitch
+    pub fn load_next_session(&mut self) -> Result<()> {
+        let mnemonics = self.get_saved_mnemonics()?;
+        let current = self.current_mnemonic.as_ref()
+            .and_then(|current| mnemonics.iter().position(|m| m == current));
+        let next = match current {
+            Some(index) => mnemonics[(index + 1) % mnemonics.len()].clone(),
+            None => mnemonics.first().cloned().ok_or_else(|| anyhow::anyhow!("No saved sessions found"))?,
+        };
+        // The switch fades through the faders, a chain left without one would be cut off
+        mixer::ensure_faders(&self.engine, &self.ui)
+            .map_err(|e| anyhow::anyhow!("Cannot fade out the session: {}", e))?;
+        self.load_session(&next)
+    }
+    
+    /// Switch to the latest save of a session
+    pub fn load_session(&mut self, mnemonic: &str) -> Result<()> {
+        let timestamp = self.get_mnemonic_timestamps(mnemonic)?.into_iter().next()
+            .ok_or_else(|| anyhow::anyhow!("No saves of session {}", mnemonic))?;
+        
+        let display_name = Self::format_mnemonic_display(mnemonic);
+        info!("Switching to session {} ({})", display_name, timestamp);
+        self.load_state(&timestamp, mnemonic)?;
+        self.current_mnemonic = Some(mnemonic.to_string());
+        self.ui.set_session_name(display_name.clone())?;
+        self.ui.show_message(&display_name)
+    }
+    
+    /// Load the latest save of the current session again, e.g. into a restarted engine
+    pub fn reload_session(&mut self) -> Result<()> {
+        match self.current_mnemonic.clone() {
+            Some(mnemonic) => self.load_session(&mnemonic),
+            None => Ok(()),
+        }
+    }
+    
+    /// Save the session when the configured autosave interval has elapsed
+    pub fn autosave_if_due(&mut self) -> Result<()> {
+        let minutes = self.settings.lock().unwrap().autosave_minutes;
+        if minutes == 0 || self.last_save.elapsed() < Duration::from_secs(minutes as u64 * 60) {
+            return Ok(());
+        }
+        info!("Autosaving session");
+        self.save_state()
+    }
+    
+    /// Check whether the session changed since it was saved or loaded
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+    
+    /// Compare the graph and its values with the ones saved or loaded, and show the unsaved changes
+    pub fn update_dirty(&mut self) -> Result<()> {
+        let now = Instant::now();
+        if self.last_dirty_check.is_some_and(|last| now.duration_since(last) < DIRTY_CHECK_INTERVAL) {
+            return Ok(());
+        }
+        self.last_dirty_check = Some(now);
+        
+        let fingerprint = self.engine_fingerprint()?;
+        let dirty = match self.saved_fingerprint {
+            Some(saved) => saved != fingerprint,
+            None => {
+                // The engine settles after a load, the first check after it is the reference
+                self.saved_fingerprint = Some(fingerprint);
+                false
+            }
+        };
+        self.set_dirty(dirty)
+    }
+    
+    /// Mark the session clean and take the current graph as the saved one, None to take it at the next check
+    fn mark_saved(&mut self, fingerprint: Option<u64>) -> Result<()> {
+        self.saved_fingerprint = fingerprint;
+        self.set_dirty(false)
+    }
+    
+    /// Show the unsaved changes flag when it changes
+    fn set_dirty(&mut self, dirty: bool) -> Result<()> {
+        if dirty != self.dirty {
+            debug!("Session dirty: {}", dirty);
+            self.dirty = dirty;
+            self.ui.set_session_dirty(dirty)?;
+        }
+        Ok(())
+    }
+    
+    /// Get the fingerprint of the engine graph and its control values
+    fn engine_fingerprint(&self) -> Result<u64> {
+        let graph = self.engine.get_graph()?;
+        let values = graph.blocks.iter()
+            .map(|b| Ok((b.id.clone(), self.engine.get_control_values(&b.id)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(graph_fingerprint(&graph, &values))
+    }
+    
     /// Get the store directory path
-    fn get_store_dir() -> Result<PathBuf> {
-        let home = std::env::var("HOME")
-            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
-        let store_dir = PathBuf::from(home).join(".traxdub").join("store");
+    fn get_store_dir(&self) -> Result<PathBuf> {
+        let store_dir = self.settings.lock().unwrap().store_dir()?;
         
         // Create directory if it doesn't exist
         if !store_dir.exists() {
@@ -141,7 +363,7 @@ impl PersistenceFeature {
     }
     
     /// Format mnemonic for display (capitalize each word, separate with spaces)
-    fn format_mnemonic_display(mnemonic: &str) -> String {
+    pub fn format_mnemonic_display(mnemonic: &str) -> String {
         mnemonic.split('-')
             .map(|word| {
                 let mut chars = word.chars();
@@ -164,6 +386,95 @@ impl PersistenceFeature {
         format!("{}-{}.txd", timestamp, mnemonic)
     }
     
+    /// Build the filename of the JACK connections saved with a session
+    fn build_connections_filename(timestamp: &str, mnemonic: &str) -> String {
+        format!("{}-{}.jack.json", timestamp, mnemonic)
+    }
+    
+    /// Build the filename of the notes and tags of a session
+    fn build_info_filename(mnemonic: &str) -> String {
+        format!("{}.info.json", mnemonic)
+    }
+    
+    /// Read the notes and tags of a session, empty when there are none
+    fn load_session_info(&self, mnemonic: &str) -> SessionInfo {
+        let path = match self.get_store_dir() {
+            Ok(store_dir) => store_dir.join(Self::build_info_filename(mnemonic)),
+            Err(_) => return SessionInfo::default(),
+        };
+        fs::read_to_string(&path).ok()
+            .and_then(|content| match serde_json::from_str(&content) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    warn!("Invalid session info {:?}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Keep the navigation ranks of the links and the tags of the blocks with a session, if they changed
+    fn save_view_info(&self, mnemonic: &str) -> Result<()> {
+        let mut info = self.load_session_info(mnemonic);
+        let links = self.ui.link_ranks();
+        // Tags of the blocks deleted since are dropped
+        let graph = self.engine.get_graph()?;
+        let node_tags: BTreeMap<String, String> = self.ui.node_tags().into_iter()
+            .filter(|(id, _)| graph.blocks.iter().any(|b| &b.id == id))
+            .map(|(id, (tag, _))| (id, tag))
+            .collect();
+        if info.links == links && info.node_tags == node_tags {
+            return Ok(());
+        }
+        info.links = links;
+        info.node_tags = node_tags;
+        self.save_session_info(mnemonic, &info)
+    }
+    
+    /// Write the notes and tags of a session
+    fn save_session_info(&self, mnemonic: &str, info: &SessionInfo) -> Result<()> {
+        let store_dir = self.get_store_dir()?;
+        write_atomic(&store_dir.join(Self::build_info_filename(mnemonic)), &serde_json::to_string_pretty(info)?, |content| {
+            serde_json::from_str::<SessionInfo>(content)?;
+            Ok(())
+        })?;
+        self.back_up(store_dir);
+        Ok(())
+    }
+    
+    /// Get the tags used by the saved sessions, sorted
+    fn get_all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.get_saved_mnemonics().unwrap_or_default().iter()
+            .flat_map(|mnemonic| self.load_session_info(mnemonic).tags)
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+    
+    /// Start editing the notes of a session or a new tag
+    fn start_text_entry(&mut self, mnemonic: &str, kind: SessionText) -> Result<()> {
+        let text = match kind {
+            SessionText::Notes => self.load_session_info(mnemonic).notes,
+            SessionText::Tag => String::new(),
+        };
+        self.text_entry = Some((mnemonic.to_string(), kind, TextEntry::new(&text)));
+        self.refresh_text_entry()
+    }
+    
+    /// Show the text entry in the UI
+    fn refresh_text_entry(&self) -> Result<()> {
+        let Some((_, kind, entry)) = &self.text_entry else {
+            return Ok(());
+        };
+        let title = match kind {
+            SessionText::Notes => "Notes",
+            SessionText::Tag => "Tag",
+        };
+        let (text, cursor) = entry.display();
+        self.ui.show_text_entry(title.to_string(), text, cursor)
+    }
+    
     /// Parse filename to extract timestamp and mnemonic
     fn parse_filename(filename: &str) -> Option<(String, String)> {
         if !filename.ends_with(".txd") {
@@ -220,65 +531,311 @@ impl PersistenceFeature {
     
     /// Save current engine state
     fn save_state(&mut self) -> Result<()> {
-        // Use existing mnemonic or generate new one
-        let mnemonic = if let Some(ref m) = self.current_mnemonic {
-            m.clone()
-        } else {
-            let m = Self::generate_mnemonic();
-            self.current_mnemonic = Some(m.clone());
-            m
-        };
+        // Use existing mnemonic or generate new one, kept once the session is stored
+        let mnemonic = self.current_mnemonic.clone().unwrap_or_else(Self::generate_mnemonic);
         
         let timestamp = Self::get_timestamp();
         let filename = Self::build_filename(&timestamp, &mnemonic);
-        let store_dir = Self::get_store_dir()?;
+        let store_dir = self.get_store_dir()?;
         let filepath = store_dir.join(&filename);
         
-        info!("Saving state to: {:?}", filepath);
-        
-        // Get raw state from engine
+        // Get raw state from engine, with the external JACK connections of the system ports
+        let _busy = self.ui.busy("Saving session")?;
         let state_data = self.engine.get_raw_state()?;
+        let graph = self.engine.get_graph()?;
+        let connections = serde_json::to_string_pretty(&self.collect_jack_connections(&graph))?;
         
-        // Write to file
-        fs::write(&filepath, state_data)?;
+        let fingerprint = self.engine_fingerprint()?;
         
-        // Update UI with mnemonic
-        let display_name = Self::format_mnemonic_display(&mnemonic);
-        self.ui.set_session_name(display_name)?;
+        // Skip the save when the latest one of the session is identical
+        if self.latest_save_hash(&mnemonic) == Some(content_hash(&state_data, &connections)) {
+            info!("Session {} unchanged since its last save, not saving", mnemonic);
+            return self.mark_session_saved(&mnemonic, fingerprint);
+        }
         
+        // The state file comes last, a save only appears in the lists once complete
+        info!("Saving state to: {:?}", filepath);
+        self.write_connections(&store_dir.join(Self::build_connections_filename(&timestamp, &mnemonic)), &connections)?;
+        self.write_state(&filepath, &state_data)?;
+        self.mark_session_saved(&mnemonic, fingerprint)?;
+        if self.settings.lock().unwrap().preloads_graph() {
+            if let Err(e) = self.save_preload_bundle(&store_dir, &filename) {
+                warn!("Failed to write the graph bundle loaded at boot: {}", e);
+            }
+        }
+        
+        if let Err(e) = self.prune_saves(&mnemonic) {
+            warn!("Failed to prune the saves of session {}: {}", mnemonic, e);
+        }
         info!("State saved successfully");
+        self.back_up(store_dir);
         Ok(())
     }
     
-    /// Load engine state from file
-    fn load_state(&self, timestamp: &str, mnemonic: &str) -> Result<()> {
-        let filename = Self::build_filename(timestamp, mnemonic);
-        let store_dir = Self::get_store_dir()?;
-        let filepath = store_dir.join(&filename);
+    /// Show a stored session as the current one, saved with the engine state of the fingerprint
+    fn mark_session_saved(&mut self, mnemonic: &str, fingerprint: u64) -> Result<()> {
+        self.current_mnemonic = Some(mnemonic.to_string());
+        self.ui.set_session_name(Self::format_mnemonic_display(mnemonic))?;
+        self.last_save = Instant::now();
+        self.mark_saved(Some(fingerprint))?;
+        if let Err(e) = self.save_view_info(mnemonic) {
+            warn!("Failed to save the link navigation and tags of session {}: {}", mnemonic, e);
+        }
+        Ok(())
+    }
+    
+    /// Have the engine write the graph bundle loaded at boot, naming the save it matches once written
+    fn save_preload_bundle(&self, store_dir: &Path, filename: &str) -> Result<()> {
+        // A failed write must not leave the previous bundle matching the new save
+        let marker = store_dir.join(PRELOAD_SAVE_FILE);
+        if marker.exists() {
+            fs::remove_file(&marker)?;
+        }
+        self.engine.save_bundle(&store_dir.join(PRELOAD_BUNDLE))?;
+        write_atomic(&marker, filename, |_| Ok(()))
+    }
+    
+    /// Copy the store to the backup target of the settings, if any
+    fn back_up(&self, store_dir: PathBuf) {
+        if let Some(target) = self.settings.lock().unwrap().backup_target.clone() {
+            backup::spawn_backup(store_dir, target);
+        }
+    }
+    
+    /// Write an engine state file, checking that it parses before it replaces the previous one
+    fn write_state(&self, path: &Path, state_data: &str) -> Result<()> {
+        write_atomic(path, state_data, |content| self.engine.validate_raw_state(content))
+    }
+    
+    /// Write a JACK connections file, checking that it parses before it replaces the previous one
+    fn write_connections(&self, path: &Path, connections: &str) -> Result<()> {
+        write_atomic(path, connections, |content| {
+            serde_json::from_str::<Vec<JackConnection>>(content)?;
+            Ok(())
+        })
+    }
+    
+    /// Hash the files of the latest save of a session, None if it has no save
+    fn latest_save_hash(&self, mnemonic: &str) -> Option<u64> {
+        let timestamp = self.get_mnemonic_timestamps(mnemonic).ok()?.into_iter().next()?;
+        let store_dir = self.get_store_dir().ok()?;
+        let state_data = fs::read_to_string(store_dir.join(Self::build_filename(&timestamp, mnemonic))).ok()?;
+        // Saves from before the connections were kept hash like an empty connection list
+        let connections = fs::read_to_string(store_dir.join(Self::build_connections_filename(&timestamp, mnemonic)))
+            .unwrap_or_default();
+        Some(content_hash(&state_data, &connections))
+    }
+    
+    /// Delete the oldest saves of a session beyond the number kept in the settings
+    fn prune_saves(&self, mnemonic: &str) -> Result<()> {
+        let kept = self.settings.lock().unwrap().saves_kept;
+        if kept == 0 {
+            return Ok(());
+        }
         
-        debug!("Loading state from: {:?}", filepath);
+        let store_dir = self.get_store_dir()?;
+        for timestamp in self.get_mnemonic_timestamps(mnemonic)?.iter().skip(kept as usize) {
+            info!("Pruning save {} of session {}", timestamp, mnemonic);
+            fs::remove_file(store_dir.join(Self::build_filename(timestamp, mnemonic)))?;
+            let connections_path = store_dir.join(Self::build_connections_filename(timestamp, mnemonic));
+            if connections_path.exists() {
+                fs::remove_file(connections_path)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Save the session to the exit snapshot, kept apart from the manual saves
+    pub fn save_exit_snapshot(&self) -> Result<()> {
+        let store_dir = self.get_store_dir()?;
+        info!("Saving exit snapshot to: {:?}", store_dir.join(EXIT_STATE_FILE));
         
-        // Read file content
-        let state_data = fs::read_to_string(&filepath)?;
+        let graph = self.engine.get_graph()?;
+        let connections = self.collect_jack_connections(&graph);
+        self.write_connections(&store_dir.join(EXIT_CONNECTIONS_FILE), &serde_json::to_string_pretty(&connections)?)?;
+        self.write_state(&store_dir.join(EXIT_STATE_FILE), &self.engine.get_raw_state()?)?;
+        if let Some(mnemonic) = &self.current_mnemonic {
+            self.save_view_info(mnemonic)?;
+        }
         
-        // Set engine state
-        self.engine.set_raw_state(&state_data)?;
+        write_atomic(&store_dir.join(EXIT_SESSION_FILE), &self.current_mnemonic.clone().unwrap_or_default(), |_| Ok(()))
+    }
+    
+    /// Check whether an exit snapshot is available
+    fn has_exit_snapshot(&self) -> bool {
+        self.get_store_dir()
+            .map(|dir| dir.join(EXIT_STATE_FILE).exists())
+            .unwrap_or(false)
+    }
+    
+    /// Load the exit snapshot, restoring the session it belonged to
+    fn resume_exit_snapshot(&mut self) -> Result<()> {
+        let store_dir = self.get_store_dir()?;
+        info!("Resuming last exit state");
+        
+        // Sessions never saved manually have no name yet
+        let mnemonic = fs::read_to_string(store_dir.join(EXIT_SESSION_FILE)).unwrap_or_default();
+        let mnemonic = mnemonic.trim();
+        self.load_state_files(
+            &store_dir.join(EXIT_STATE_FILE),
+            &store_dir.join(EXIT_CONNECTIONS_FILE),
+            Some(mnemonic).filter(|mnemonic| !mnemonic.is_empty()),
+            false,
+        )?;
+        if mnemonic.is_empty() {
+            self.current_mnemonic = None;
+        } else {
+            self.current_mnemonic = Some(mnemonic.to_string());
+            self.ui.set_session_name(Self::format_mnemonic_display(mnemonic))?;
+        }
+        Ok(())
+    }
+    
+    /// Load engine state from file
+    fn load_state(&mut self, timestamp: &str, mnemonic: &str) -> Result<()> {
+        let store_dir = self.get_store_dir()?;
+        self.load_state_files(
+            &store_dir.join(Self::build_filename(timestamp, mnemonic)),
+            &store_dir.join(Self::build_connections_filename(timestamp, mnemonic)),
+            Some(mnemonic),
+            false,
+        )
+    }
+    
+    /// Insert or remove the safety limiters of the loaded session to follow the settings
+    fn apply_safety_limiter(&self) {
+        if let Err(e) = mixer::apply_safety_limiter(&self.engine, &self.settings) {
+            warn!("Could not apply the safety limiter: {}", e);
+        }
+    }
+    
+    /// Load engine state and the JACK connections saved with it, with the link navigation of its session
+    /// An engine that loaded the state at boot only gets the UI graph and the connections
+    fn load_state_files(&mut self, filepath: &Path, connections_path: &Path, mnemonic: Option<&str>, preloaded: bool) -> Result<()> {
+        debug!("Loading state from: {:?}", filepath);
+        let busy = self.ui.busy("Loading session")?;
+        
+        if preloaded {
+            info!("Engine loaded the state at boot");
+            self.apply_safety_limiter();
+        } else {
+            // Read file content
+            let state_data = fs::read_to_string(filepath)?;
+            
+            // Fade the outputs out and back in around the switch to avoid clicks
+            let transition = Duration::from_millis(self.settings.lock().unwrap().transition_ms as u64);
+            let gains = mixer::fader_gains(&self.engine).unwrap_or_else(|e| {
+                warn!("Could not read the faders before loading: {}", e);
+                Vec::new()
+            });
+            if let Err(e) = mixer::ramp_gains(&self.engine, &gains, false, transition) {
+                warn!("Could not fade out before loading: {}", e);
+            }
+            
+            // Set engine state, bringing the faders of the running session back if it stays
+            if let Err(e) = self.engine.set_raw_state(&state_data) {
+                if let Err(e) = mixer::ramp_gains(&self.engine, &gains, true, transition) {
+                    warn!("Could not fade back in after the failed load: {}", e);
+                }
+                return Err(e);
+            }
+            
+            // Protect the outputs of the new session before it is heard, and fade in all of its chains
+            self.apply_safety_limiter();
+            if let Err(e) = mixer::ensure_faders(&self.engine, &self.ui) {
+                warn!("Could not insert the faders of the loaded session: {}", e);
+            }
+            
+            if let Err(e) = mixer::ramp_faders(&self.engine, true, transition) {
+                warn!("Could not fade in after loading: {}", e);
+            }
+        }
         
         // Get the graph from engine
+        busy.update("Building the graph");
         let graph = self.engine.get_graph()?;
         
         // Update UI with the graph
-        self.load_ui_graph(&graph)?;
+        let info = mnemonic.map(|mnemonic| self.load_session_info(mnemonic)).unwrap_or_default();
+        self.load_ui_graph(&graph, &info)?;
+        
+        // Read the JACK connections saved with the session, if any
+        busy.update("Connecting the devices");
+        let saved_connections: Vec<JackConnection> = match fs::read_to_string(connections_path) {
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                warn!("Ignoring invalid JACK connections file {:?}: {}", connections_path, e);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
         
         // Connect JACK ports for system ports
-        self.connect_system_ports(&graph)?;
+        self.connect_system_ports(&graph, &saved_connections)?;
         
+        self.last_save = Instant::now();
+        self.mark_saved(None)?;
         debug!("State loaded successfully");
         Ok(())
     }
     
-    /// Connect JACK ports for system ports in the graph
-    fn connect_system_ports(&self, graph: &crate::engine::Graph) -> Result<()> {
+    /// Collect the JACK connections of the system ports in the graph
+    fn collect_jack_connections(&self, graph: &crate::engine::Graph) -> Vec<JackConnection> {
+        let mut connections = Vec::new();
+        for port in &graph.ports {
+            let sanitized_name = port.id.split('/').next_back().unwrap_or(&port.id);
+            let engine_port = crate::controller::driver::Port {
+                name: format!("TraxDub Engine:{}", sanitized_name),
+                short_name: sanitized_name.to_string(),
+            };
+            let peers = match self.driver.get_connections(&engine_port) {
+                Ok(peers) => peers,
+                Err(e) => {
+                    warn!("Could not read JACK connections of {}: {}", engine_port.name, e);
+                    continue;
+                }
+            };
+            for peer in peers {
+                connections.push(match port.direction {
+                    crate::engine::PortDirection::Input => JackConnection {
+                        source: peer,
+                        destination: engine_port.name.clone(),
+                    },
+                    crate::engine::PortDirection::Output => JackConnection {
+                        source: engine_port.name.clone(),
+                        destination: peer,
+                    },
+                });
+            }
+        }
+        connections
+    }
+    
+    /// Restore the saved JACK connections of an engine port, returning false when none could be made
+    fn restore_saved_connections(&self, saved: &[JackConnection], engine_port_name: &str) -> bool {
+        let mut restored = false;
+        for connection in saved {
+            if connection.source != engine_port_name && connection.destination != engine_port_name {
+                continue;
+            }
+            let source = crate::controller::driver::Port {
+                name: connection.source.clone(),
+                short_name: connection.source.split(':').next_back().unwrap_or(&connection.source).to_string(),
+            };
+            let destination = crate::controller::driver::Port {
+                name: connection.destination.clone(),
+                short_name: connection.destination.split(':').next_back().unwrap_or(&connection.destination).to_string(),
+            };
+            match self.driver.connect_ports(&source, &destination) {
+                Ok(()) => restored = true,
+                Err(e) => debug!("Saved connection {} -> {} not restored: {}", source.name, destination.name, e),
+            }
+        }
+        restored
+    }
+    
+    /// Connect JACK ports for system ports in the graph, preferring the saved connections
+    /// and falling back to name matching
+    fn connect_system_ports(&mut self, graph: &crate::engine::Graph, saved: &[JackConnection]) -> Result<()> {
         debug!("Connecting system ports to JACK");
         
         // Get all sources and sinks from JACK
@@ -287,10 +844,17 @@ impl PersistenceFeature {
         let midi_sources = self.driver.get_sources(PortType::Midi)?;
         let midi_sinks = self.driver.get_sinks(PortType::Midi)?;
         
+        // Ports of the previous session are not awaited anymore
+        self.pending_bindings.clear();
+        
         // Process each system port in the graph
         for port in &graph.ports {
             // port.id is already sanitized, just extract the last segment
-            let sanitized_name = port.id.split('/').last().unwrap_or(&port.id);
+            let sanitized_name = port.id.split('/').next_back().unwrap_or(&port.id);
+            
+            if self.restore_saved_connections(saved, &format!("TraxDub Engine:{}", sanitized_name)) {
+                continue;
+            }
             
             // Determine the port type for filtering
             let driver_port_type = match port.port_type {
@@ -299,35 +863,91 @@ impl PersistenceFeature {
             };
             
             // Match based on direction and type
-            match (&port.direction, driver_port_type) {
+            let connected = match (&port.direction, driver_port_type) {
                 (crate::engine::PortDirection::Input, PortType::Audio) => {
                     // Find matching audio source
-                    self.find_and_connect_source(&audio_sources, &sanitized_name, &port.id)?;
+                    self.find_and_connect_source(&audio_sources, sanitized_name, &port.id)?
                 }
                 (crate::engine::PortDirection::Input, PortType::Midi) => {
                     // Find matching MIDI source
-                    self.find_and_connect_source(&midi_sources, &sanitized_name, &port.id)?;
+                    self.find_and_connect_source(&midi_sources, sanitized_name, &port.id)?
                 }
                 (crate::engine::PortDirection::Output, PortType::Audio) => {
                     // Find matching audio sink
-                    self.find_and_connect_sink(&audio_sinks, &sanitized_name, &port.id)?;
+                    self.find_and_connect_sink(&audio_sinks, sanitized_name, &port.id)?
                 }
                 (crate::engine::PortDirection::Output, PortType::Midi) => {
                     // Find matching MIDI sink
-                    self.find_and_connect_sink(&midi_sinks, &sanitized_name, &port.id)?;
+                    self.find_and_connect_sink(&midi_sinks, sanitized_name, &port.id)?
                 }
                 _ => {
                     // PortType::All should not occur when converting from engine::PortType
                     warn!("Unexpected port type combination for system port: {}", port.id);
+                    true
                 }
+            };
+            
+            // Wait for the device to show up later
+            if !connected {
+                self.pending_bindings.push(PendingBinding {
+                    system_port_id: port.id.clone(),
+                    sanitized_name: sanitized_name.to_string(),
+                    direction: port.direction.clone(),
+                    port_type: driver_port_type,
+                });
             }
         }
         
-        Ok(())
+        self.show_pending_bindings()
+    }
+    
+    /// Connect a system port waiting for its device when the matching JACK port registers
+    pub fn port_registered(&mut self, change: &PortChange) -> Result<()> {
+        if !change.registered {
+            return Ok(());
+        }
+        
+        let port_sanitized = Driver::sanitize_port_name(&change.port.name);
+        let Some(index) = self.pending_bindings.iter().position(|binding| {
+            let direction_matches = match binding.direction {
+                crate::engine::PortDirection::Input => change.is_output,
+                crate::engine::PortDirection::Output => !change.is_output,
+            };
+            direction_matches && binding.port_type == change.port_type && binding.sanitized_name == port_sanitized
+        }) else {
+            return Ok(());
+        };
+        
+        let binding = self.pending_bindings.remove(index);
+        let engine_port = crate::controller::driver::Port {
+            name: format!("TraxDub Engine:{}", binding.sanitized_name),
+            short_name: binding.sanitized_name.clone(),
+        };
+        let result = match binding.direction {
+            crate::engine::PortDirection::Input => self.driver.connect_ports(&change.port, &engine_port),
+            crate::engine::PortDirection::Output => self.driver.connect_ports(&engine_port, &change.port),
+        };
+        match result {
+            Ok(()) => info!("Device is back, connected {} to {}", change.port.name, binding.system_port_id),
+            Err(e) => {
+                warn!("Failed to connect {} to {}: {}", change.port.name, binding.system_port_id, e);
+                self.pending_bindings.push(binding);
+            }
+        }
+        
+        self.show_pending_bindings()
+    }
+    
+    /// Show the devices the session is still waiting for
+    fn show_pending_bindings(&self) -> Result<()> {
+        let names = self.pending_bindings.iter()
+            .map(|binding| binding.sanitized_name.clone())
+            .collect();
+        self.ui.set_waiting_devices(names)
     }
     
-    /// Find and connect a source port
-    fn find_and_connect_source(&self, sources: &[crate::controller::driver::Source], sanitized_name: &str, system_port_id: &str) -> Result<()> {
+    /// Find and connect a source port, returning false when no source matches
+    fn find_and_connect_source(&self, sources: &[crate::controller::driver::Source], sanitized_name: &str, system_port_id: &str) -> Result<bool> {
         for source in sources {
             for port in &source.ports {
                 let port_sanitized = Driver::sanitize_port_name(&port.name);
@@ -346,16 +966,16 @@ impl PersistenceFeature {
                     if let Err(e) = self.driver.connect_ports(port, &dest_port) {
                         warn!("Failed to connect {} to {}: {}", port.name, system_port_id, e);
                     }
-                    return Ok(());
+                    return Ok(true);
                 }
             }
         }
         debug!("No matching JACK source found for system port: {}", system_port_id);
-        Ok(())
+        Ok(false)
     }
     
-    /// Find and connect a sink port
-    fn find_and_connect_sink(&self, sinks: &[crate::controller::driver::Sink], sanitized_name: &str, system_port_id: &str) -> Result<()> {
+    /// Find and connect a sink port, returning false when no sink matches
+    fn find_and_connect_sink(&self, sinks: &[crate::controller::driver::Sink], sanitized_name: &str, system_port_id: &str) -> Result<bool> {
         for sink in sinks {
             for port in &sink.ports {
                 let port_sanitized = Driver::sanitize_port_name(&port.name);
@@ -374,23 +994,35 @@ impl PersistenceFeature {
                     if let Err(e) = self.driver.connect_ports(&source_port, port) {
                         warn!("Failed to connect {} to {}: {}", system_port_id, port.name, e);
                     }
-                    return Ok(());
+                    return Ok(true);
                 }
             }
         }
         debug!("No matching JACK sink found for system port: {}", system_port_id);
-        Ok(())
+        Ok(false)
     }
     
     /// Load UI graph from engine graph data
-    fn load_ui_graph(&self, graph: &crate::engine::Graph) -> Result<()> {
+    fn load_ui_graph(&self, graph: &crate::engine::Graph, info: &SessionInfo) -> Result<()> {
         debug!("Loading UI graph from engine data");
         
-        // Clear existing UI nodes and links (except system nodes)
-        // Note: We should add a method to clear the UI, but for now we'll just add nodes
+        // Remove the nodes of the previous session and their links, keeping the context nodes
+        // The session opens on its main graph
+        self.ui.set_view_scope(None);
+        let model = self.ui.graph_model();
+        for id in model.nodes.keys().filter(|id| !model.is_context_node(id)) {
+            self.ui.remove_node(id.clone())?;
+        }
+        
+        // The tags are in place before the nodes, which show them when created
+        let tags = self.settings.lock().unwrap().node_tags.clone();
+        self.ui.restore_node_tags(info.node_tags.iter()
+            .map(|(id, tag)| (id.clone(), (tag.clone(), tag_color(&tags, tag))))
+            .collect());
         
-        // Create nodes for each block
-        for block in &graph.blocks {
+        // Create nodes for each block of the main graph, except the hidden bypass gains
+        for block in graph.blocks.iter().filter(|b| crate::controller::feature::mixer::is_in_scope(&b.id, crate::controller::feature::mixer::MAIN_GRAPH)
+            && !crate::controller::feature::mixer::is_hidden_block(&b.id)) {
         
           debug!("Creating UI node: {} ", block.name);
             self.ui.create_node(
@@ -398,6 +1030,9 @@ impl PersistenceFeature {
                 block.name.clone(),
                 crate::ui::NodeType::Normal,
             )?;
+            if crate::controller::feature::mixer::is_bypassed(graph, &block.id) {
+                self.ui.set_node_state(block.id.clone(), crate::ui::NodeState::Bypassed)?;
+            }
         }
         
         // Create nodes for each system port
@@ -434,13 +1069,8 @@ impl PersistenceFeature {
             }
         }
         
-        // Create links for each connection
-        for connection in &graph.connections {
-            // Extract node IDs from port paths
-            // Port path format: "ingen:/main/node_id/port_id"
-            let from_id = self.extract_node_from_port(&connection.source);
-            let to_id = self.extract_node_from_port(&connection.destination);
-            
+        // Create links for each connection, looking through the hidden bypass gains
+        for (from_id, to_id) in crate::controller::feature::mixer::visible_links(graph) {
             debug!("Creating UI link: {} -> {}", from_id, to_id);
             
             // Only create link if we haven't already
@@ -451,62 +1081,23 @@ impl PersistenceFeature {
             }
         }
         
+        // Bring back the link order and visits of the previous runs
+        self.ui.restore_link_ranks(&info.links)?;
+        
         // Commit all graph changes at once
         self.ui.commit()?;
         
         Ok(())
     }
     
-    /// Extract node ID from a port path
-    fn extract_node_from_port(&self, port_path: &str) -> String {
-        // Port path format: "ingen:/main/node_id/port_id" or "ingen:/main/system_port"
-        // We want to extract the first three segments: "ingen:/main/node_id"
-        let parts: Vec<&str> = port_path.split('/').collect();
-        if parts.len() >= 3 {
-            parts[..3].join("/")
-        } else {
-            port_path.to_string()
-        }
-    }
-    
     /// Get list of all saved mnemonics (newest first)
-    fn get_saved_mnemonics() -> Result<Vec<String>> {
-        let store_dir = Self::get_store_dir()?;
-        
-        if !store_dir.exists() {
-            return Ok(Vec::new());
-        }
-        
-        let mut mnemonics: Vec<(String, String)> = Vec::new();
-        
-        for entry in fs::read_dir(store_dir)? {
-            let entry = entry?;
-            let filename = entry.file_name().to_string_lossy().to_string();
-            
-            if let Some((timestamp, mnemonic)) = Self::parse_filename(&filename) {
-                mnemonics.push((timestamp, mnemonic));
-            }
-        }
-        
-        // Sort by timestamp descending
-        mnemonics.sort_by(|a, b| b.0.cmp(&a.0));
-        
-        // Extract unique mnemonics in order
-        let mut unique_mnemonics = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-        
-        for (_, mnemonic) in mnemonics {
-            if seen.insert(mnemonic.clone()) {
-                unique_mnemonics.push(mnemonic);
-            }
-        }
-        
-        Ok(unique_mnemonics)
+    fn get_saved_mnemonics(&self) -> Result<Vec<String>> {
+        saved_sessions(&self.get_store_dir()?)
     }
     
     /// Get all timestamps for a given mnemonic (newest first)
-    fn get_mnemonic_timestamps(mnemonic: &str) -> Result<Vec<String>> {
-        let store_dir = Self::get_store_dir()?;
+    fn get_mnemonic_timestamps(&self, mnemonic: &str) -> Result<Vec<String>> {
+        let store_dir = self.get_store_dir()?;
         
         let mut timestamps = Vec::new();
         
@@ -529,17 +1120,55 @@ impl PersistenceFeature {
     
     /// Get the file menu
     fn get_file_menu(&self) -> Menu {
+        let mut options = vec![
+            MenuOption {
+                id: "save".to_string(),
+                label: "Save".to_string(),
+            },
+            MenuOption {
+                id: "load".to_string(),
+                label: "Load...".to_string(),
+            },
+            MenuOption {
+                id: "import".to_string(),
+                label: "Import Ingen Graph...".to_string(),
+            },
+            MenuOption {
+                id: "export".to_string(),
+                label: "Export Graph >".to_string(),
+            },
+        ];
+        
+        if self.has_exit_snapshot() {
+            options.insert(2, MenuOption {
+                id: "resume_exit".to_string(),
+                label: "Resume Last Exit State".to_string(),
+            });
+        }
+        
         Menu {
             id: "file_menu".to_string(),
             label: "File".to_string(),
+            options,
+        }
+    }
+    
+    /// Get the menu asking what to do with the unsaved changes before loading
+    fn get_unsaved_changes_menu(&self) -> Menu {
+        let name = self.current_mnemonic.as_deref()
+            .map(Self::format_mnemonic_display)
+            .unwrap_or_else(|| "Session".to_string());
+        Menu {
+            id: "unsaved_changes".to_string(),
+            label: format!("{} has unsaved changes", name),
             options: vec![
                 MenuOption {
-                    id: "save".to_string(),
-                    label: "Save".to_string(),
+                    id: "save_and_load".to_string(),
+                    label: "Save and Load".to_string(),
                 },
                 MenuOption {
-                    id: "load".to_string(),
-                    label: "Load...".to_string(),
+                    id: "discard".to_string(),
+                    label: "Load Without Saving".to_string(),
                 },
             ],
         }
@@ -547,9 +1176,43 @@ impl PersistenceFeature {
     
     /// Get the load selection menu (list of mnemonics)
     fn get_load_selection_menu(&self) -> Menu {
-        let mnemonics = Self::get_saved_mnemonics().unwrap_or_default();
+        let mnemonics = self.get_saved_mnemonics().unwrap_or_default();
         
-        let options: Vec<MenuOption> = mnemonics.iter()
+        let mut options = Vec::new();
+        if self.tag_filter.is_some() || !self.get_all_tags().is_empty() {
+            options.push(MenuOption {
+                id: "filter_tag".to_string(),
+                label: match &self.tag_filter {
+                    Some(tag) => format!("Tag: {} >", tag),
+                    None => "Filter by Tag >".to_string(),
+                },
+            });
+        }
+        
+        options.push(MenuOption {
+            id: "compare".to_string(),
+            label: "Compare...".to_string(),
+        });
+        
+        // Session ids always contain a dash, unlike the filter and compare options
+        options.extend(mnemonics.iter()
+            .map(|mnemonic| (mnemonic, self.load_session_info(mnemonic)))
+            .filter(|(_, info)| self.tag_filter.as_ref().is_none_or(|tag| info.tags.contains(tag)))
+            .map(|(mnemonic, info)| MenuOption {
+                id: mnemonic.clone(),
+                label: format!("{}{}", Self::format_mnemonic_display(mnemonic), info.summary()),
+            }));
+        
+        Menu {
+            id: "load_selection".to_string(),
+            label: "Select Session".to_string(),
+            options,
+        }
+    }
+    
+    /// Get the session menu of a comparison, for the first or the second save
+    fn get_compare_session_menu(&self, first: &Option<(String, String)>) -> Menu {
+        let options = self.get_saved_mnemonics().unwrap_or_default().iter()
             .map(|mnemonic| MenuOption {
                 id: mnemonic.clone(),
                 label: Self::format_mnemonic_display(mnemonic),
@@ -557,23 +1220,186 @@ impl PersistenceFeature {
             .collect();
         
         Menu {
-            id: "load_selection".to_string(),
-            label: "Select Session".to_string(),
+            id: "compare_session".to_string(),
+            label: if first.is_none() { "Compare" } else { "Compare With" }.to_string(),
+            options,
+        }
+    }
+    
+    /// Get the timestamp menu of a comparison
+    fn get_compare_timestamp_menu(&self, mnemonic: &str) -> Menu {
+        let options = self.get_mnemonic_timestamps(mnemonic).unwrap_or_default().iter()
+            .map(|timestamp| MenuOption {
+                id: timestamp.clone(),
+                label: Self::format_timestamp_display(timestamp),
+            })
+            .collect();
+        
+        Menu {
+            id: format!("compare_timestamp_{}", mnemonic),
+            label: Self::format_mnemonic_display(mnemonic),
+            options,
+        }
+    }
+    
+    /// Get the menu listing the differences of a comparison
+    fn get_compare_result_menu(lines: &[String]) -> Menu {
+        let mut options: Vec<MenuOption> = lines.iter()
+            .enumerate()
+            .map(|(i, line)| MenuOption {
+                id: format!("change_{}", i),
+                label: line.clone(),
+            })
+            .collect();
+        if options.is_empty() {
+            options.push(MenuOption {
+                id: "none".to_string(),
+                label: "No differences".to_string(),
+            });
+        }
+        
+        Menu {
+            id: "compare_result".to_string(),
+            label: "Changes".to_string(),
+            options,
+        }
+    }
+    
+    /// Compare two saves, given as (mnemonic, timestamp), and describe the differences
+    fn compare_saves(&self, old: &(String, String), new: &(String, String)) -> Result<Vec<String>> {
+        let store_dir = self.get_store_dir()?;
+        let read = |(mnemonic, timestamp): &(String, String)| -> Result<StateContent> {
+            let state_data = fs::read_to_string(store_dir.join(Self::build_filename(timestamp, mnemonic)))?;
+            self.engine.parse_raw_state(&state_data)
+        };
+        
+        info!("Comparing {} {} with {} {}", old.0, old.1, new.0, new.1);
+        Ok(StateDiff::between(&read(old)?, &read(new)?).lines())
+    }
+    
+    /// Get the menu of the Ingen bundles found in the store and home directories
+    fn get_import_menu(&self) -> Menu {
+        let mut dirs = Vec::new();
+        if let Ok(store_dir) = self.get_store_dir() {
+            dirs.push(store_dir);
+        }
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(PathBuf::from(home));
+        }
+        
+        let mut options: Vec<MenuOption> = find_ingen_bundles(&dirs).iter()
+            .filter(|path| !path.ends_with(PRELOAD_BUNDLE))
+            .map(|path| MenuOption {
+                id: path.display().to_string(),
+                label: path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default(),
+            })
+            .collect();
+        if options.is_empty() {
+            options.push(MenuOption {
+                id: "none".to_string(),
+                label: "No .ingen bundles found".to_string(),
+            });
+        }
+        
+        Menu {
+            id: "import_selection".to_string(),
+            label: "Import Ingen Graph".to_string(),
+            options,
+        }
+    }
+    
+    /// Add a graph saved by Ingen to the engine and show it
+    fn import_bundle(&mut self, bundle_path: &Path) -> Result<()> {
+        let _busy = self.ui.busy("Importing graph")?;
+        self.engine.import_bundle(bundle_path)?;
+        
+        let graph = self.engine.get_graph()?;
+        self.load_ui_graph(&graph, &SessionInfo::default())?;
+        // The bundle has no JACK connections, the system ports get the default ones
+        self.connect_system_ports(&graph, &[])?;
+        
+        let name = bundle_path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        self.ui.show_message(&format!("Imported {}", name))
+    }
+    
+    /// Get the menu of the export formats
+    fn get_export_menu(&self) -> Menu {
+        Menu {
+            id: "export_selection".to_string(),
+            label: "Export Graph".to_string(),
+            options: vec![
+                MenuOption {
+                    id: "dot".to_string(),
+                    label: "Graphviz (DOT)".to_string(),
+                },
+                MenuOption {
+                    id: "mermaid".to_string(),
+                    label: "Mermaid".to_string(),
+                },
+            ],
+        }
+    }
+    
+    /// Write the current graph to the exports directory of the store, returning the file path
+    fn export_graph(&self, format: ExportFormat) -> Result<PathBuf> {
+        let dir = self.get_store_dir()?.join("exports");
+        fs::create_dir_all(&dir)?;
+        let name = self.current_mnemonic.as_deref().unwrap_or("graph");
+        let path = dir.join(format!("{}-{}.{}", Self::get_timestamp(), name, format.extension()));
+        
+        fs::write(&path, export::render(&self.engine.get_graph()?, format))?;
+        info!("Graph exported to {:?}", path);
+        Ok(path)
+    }
+    
+    /// Get the tag filter selection menu
+    fn get_tag_filter_menu(&self) -> Menu {
+        let mut options = vec![MenuOption {
+            id: "all".to_string(),
+            label: "All Sessions".to_string(),
+        }];
+        options.extend(self.get_all_tags().into_iter().map(|tag| MenuOption {
+            id: format!("tag_{}", tag),
+            label: tag,
+        }));
+        
+        Menu {
+            id: "tag_filter".to_string(),
+            label: "Filter by Tag".to_string(),
             options,
         }
     }
     
     /// Get the timestamp selection menu for a mnemonic
     fn get_timestamp_selection_menu(&self, mnemonic: &str) -> Menu {
-        let timestamps = Self::get_mnemonic_timestamps(mnemonic).unwrap_or_default();
+        let timestamps = self.get_mnemonic_timestamps(mnemonic).unwrap_or_default();
+        let info = self.load_session_info(mnemonic);
         
-        let options: Vec<MenuOption> = timestamps.iter()
+        let mut options: Vec<MenuOption> = timestamps.iter()
             .map(|timestamp| MenuOption {
                 id: timestamp.clone(),
                 label: Self::format_timestamp_display(timestamp),
             })
             .collect();
         
+        // Notes and tags follow the saves
+        options.push(MenuOption {
+            id: "edit_notes".to_string(),
+            label: if info.notes.is_empty() {
+                "Add Notes...".to_string()
+            } else {
+                format!("Notes: {}", info.notes)
+            },
+        });
+        options.push(MenuOption {
+            id: "add_tag".to_string(),
+            label: "Add Tag...".to_string(),
+        });
+        options.extend(info.tags.iter().map(|tag| MenuOption {
+            id: format!("remove_tag_{}", tag),
+            label: format!("Remove Tag {}", tag),
+        }));
+        
         Menu {
             id: format!("timestamp_selection_{}", mnemonic),
             label: Self::format_mnemonic_display(mnemonic),
@@ -583,6 +1409,23 @@ impl PersistenceFeature {
 }
 
 impl Feature for PersistenceFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        let mut entries = vec![ContextEntry::new(100, "file", "File >")];
+        if let crate::ui::Element::Link(..) = element {
+            entries.push(ContextEntry::new(95, "learn_scene", "Learn Scene Footswitch"));
+        }
+        entries
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        if option_id == "learn_scene" {
+            // Wait for the footswitch with all menus closed
+            self.ui.show_message("Press the scene footswitch")?;
+            return Ok(ControllerState::LearningSceneButton);
+        }
+        Ok(ControllerState::BrowsingMenu)
+    }
+
     fn get_menu(&self) -> Menu {
         match &self.menu_state {
             PersistenceMenuState::FileMenu => self.get_file_menu(),
@@ -590,6 +1433,13 @@ impl Feature for PersistenceFeature {
             PersistenceMenuState::TimestampSelection(mnemonic) => {
                 self.get_timestamp_selection_menu(mnemonic)
             }
+            PersistenceMenuState::TagFilterSelection => self.get_tag_filter_menu(),
+            PersistenceMenuState::ImportSelection => self.get_import_menu(),
+            PersistenceMenuState::ExportSelection => self.get_export_menu(),
+            PersistenceMenuState::CompareSessionSelection(first) => self.get_compare_session_menu(first),
+            PersistenceMenuState::CompareTimestampSelection(mnemonic, _) => self.get_compare_timestamp_menu(mnemonic),
+            PersistenceMenuState::CompareResult(lines) => Self::get_compare_result_menu(lines),
+            PersistenceMenuState::UnsavedChanges(_) => self.get_unsaved_changes_menu(),
         }
     }
     
@@ -614,25 +1464,402 @@ impl Feature for PersistenceFeature {
                         self.menu_state = PersistenceMenuState::LoadSelection;
                         Ok(ControllerState::BrowsingMenu)
                     }
+                    "export" => {
+                        self.menu_state = PersistenceMenuState::ExportSelection;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    "import" => {
+                        self.menu_state = PersistenceMenuState::ImportSelection;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    "resume_exit" => {
+                        if self.dirty {
+                            self.menu_state = PersistenceMenuState::UnsavedChanges(None);
+                            return Ok(ControllerState::BrowsingMenu);
+                        }
+                        self.resume_exit_snapshot()?;
+                        self.menu_state = PersistenceMenuState::FileMenu;
+                        Ok(ControllerState::Navigating)
+                    }
                     _ => Ok(ControllerState::Navigating),
                 }
             }
             PersistenceMenuState::LoadSelection => {
+                if option == "filter_tag" {
+                    self.menu_state = PersistenceMenuState::TagFilterSelection;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                if option == "compare" {
+                    self.menu_state = PersistenceMenuState::CompareSessionSelection(None);
+                    return Ok(ControllerState::BrowsingMenu);
+                }
                 // Mnemonic selected, show timestamps
                 self.menu_state = PersistenceMenuState::TimestampSelection(option.to_string());
                 Ok(ControllerState::BrowsingMenu)
             }
+            PersistenceMenuState::CompareSessionSelection(first) => {
+                self.menu_state = PersistenceMenuState::CompareTimestampSelection(option.to_string(), first.clone());
+                Ok(ControllerState::BrowsingMenu)
+            }
+            PersistenceMenuState::CompareTimestampSelection(mnemonic, first) => {
+                let picked = (mnemonic.clone(), option.to_string());
+                self.menu_state = match first.clone() {
+                    // The older save is usually picked first, the changes go from it to the second one
+                    Some(first) => match self.compare_saves(&first, &picked) {
+                        Ok(lines) => PersistenceMenuState::CompareResult(lines),
+                        Err(e) => {
+                            self.menu_state = PersistenceMenuState::FileMenu;
+                            self.ui.show_message(&format!("Cannot compare: {}", e))?;
+                            return Ok(ControllerState::Navigating);
+                        }
+                    },
+                    None => PersistenceMenuState::CompareSessionSelection(Some(picked)),
+                };
+                Ok(ControllerState::BrowsingMenu)
+            }
+            PersistenceMenuState::ImportSelection => {
+                self.menu_state = PersistenceMenuState::FileMenu;
+                if option != "none" {
+                    if let Err(e) = self.import_bundle(Path::new(option)) {
+                        warn!("Failed to import {}: {}", option, e);
+                        self.ui.show_message(&format!("Import failed: {}", e))?;
+                    }
+                }
+                Ok(ControllerState::Navigating)
+            }
+            PersistenceMenuState::ExportSelection => {
+                self.menu_state = PersistenceMenuState::FileMenu;
+                let format = if option == "mermaid" { ExportFormat::Mermaid } else { ExportFormat::Dot };
+                match self.export_graph(format) {
+                    Ok(path) => {
+                        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                        self.ui.show_message(&format!("Exported {}", name))?;
+                    }
+                    Err(e) => self.ui.show_message(&format!("Export failed: {}", e))?,
+                }
+                Ok(ControllerState::Navigating)
+            }
+            PersistenceMenuState::CompareResult(_) => {
+                // The lines are read only
+                self.menu_state = PersistenceMenuState::FileMenu;
+                Ok(ControllerState::Navigating)
+            }
+            PersistenceMenuState::TagFilterSelection => {
+                self.tag_filter = option.strip_prefix("tag_").map(str::to_string);
+                self.menu_state = PersistenceMenuState::LoadSelection;
+                Ok(ControllerState::BrowsingMenu)
+            }
             PersistenceMenuState::TimestampSelection(mnemonic) => {
-                // Timestamp selected, load the file
-                self.load_state(option, mnemonic)?;
+                let mnemonic = mnemonic.clone();
+                if option == "edit_notes" || option == "add_tag" {
+                    let kind = if option == "edit_notes" { SessionText::Notes } else { SessionText::Tag };
+                    self.start_text_entry(&mnemonic, kind)?;
+                    self.menu_state = PersistenceMenuState::FileMenu;
+                    return Ok(ControllerState::EditingSessionInfo);
+                }
+                if let Some(tag) = option.strip_prefix("remove_tag_") {
+                    let mut info = self.load_session_info(&mnemonic);
+                    info.tags.retain(|t| t != tag);
+                    self.save_session_info(&mnemonic, &info)?;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                // Timestamp selected, load the file unless the session has unsaved changes
+                if self.dirty {
+                    self.menu_state = PersistenceMenuState::UnsavedChanges(Some((mnemonic, option.to_string())));
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                self.load_state(option, &mnemonic)?;
                 self.menu_state = PersistenceMenuState::FileMenu;
                 Ok(ControllerState::Navigating)
             }
+            PersistenceMenuState::UnsavedChanges(pending) => {
+                let pending = pending.clone();
+                self.menu_state = PersistenceMenuState::FileMenu;
+                match option {
+                    "save_and_load" => self.save_state()?,
+                    "discard" => info!("Discarding the unsaved changes"),
+                    _ => return Ok(ControllerState::Navigating),
+                }
+                match pending {
+                    Some((mnemonic, timestamp)) => self.load_state(&timestamp, &mnemonic)?,
+                    None => self.resume_exit_snapshot()?,
+                }
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+impl TextEditor for PersistenceFeature {
+    fn scroll(&mut self, direction: KnobDirection) -> Result<()> {
+        if let Some((_, _, entry)) = self.text_entry.as_mut() {
+            entry.scroll(direction);
+        }
+        self.refresh_text_entry()
+    }
+    
+    fn move_cursor(&mut self, direction: KnobDirection) -> Result<()> {
+        if let Some((_, _, entry)) = self.text_entry.as_mut() {
+            entry.move_cursor(direction);
+        }
+        self.refresh_text_entry()
+    }
+    
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        if let Some((_, _, entry)) = self.text_entry.as_mut() {
+            *entry = TextEntry::new(text);
+        }
+        self.refresh_text_entry()
+    }
+    
+    /// Store the notes or the new tag of the session
+    fn confirm(&mut self) -> Result<()> {
+        self.ui.hide_text_entry()?;
+        let Some((mnemonic, kind, entry)) = self.text_entry.take() else {
+            return Ok(());
+        };
+        
+        let mut info = self.load_session_info(&mnemonic);
+        let display_name = Self::format_mnemonic_display(&mnemonic);
+        match kind {
+            SessionText::Notes => {
+                info.notes = entry.text();
+                info!("Notes of session {} set to '{}'", display_name, info.notes);
+                self.save_session_info(&mnemonic, &info)?;
+                self.ui.show_message("Notes saved")
+            }
+            SessionText::Tag => {
+                let tag = entry.text();
+                if !info.add_tag(&tag) {
+                    return self.ui.show_message("Tag is empty or already set");
+                }
+                info!("Session {} tagged {}", display_name, tag);
+                self.save_session_info(&mnemonic, &info)?;
+                self.ui.show_message(&format!("Tagged {}", display_name))
+            }
         }
     }
+    
+    fn cancel(&mut self) -> Result<()> {
+        self.text_entry = None;
+        self.ui.hide_text_entry()
+    }
+}
+
+/// Write a file so that a crash never leaves it half written
+/// The content goes to a temporary file that is synced, read back and validated,
+/// then renamed over the previous file, which stays untouched if anything fails
+fn write_atomic(path: &Path, content: &str, validate: impl FnOnce(&str) -> Result<()>) -> Result<()> {
+    let mut temp_name = path.file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid file path {:?}", path))?
+        .to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        validate(&fs::read_to_string(&temp_path)?)
+            .map_err(|e| anyhow::anyhow!("Not replacing {:?} with an invalid file: {}", path, e))
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    
+    fs::rename(&temp_path, path)?;
+    // Make the rename itself durable
+    if let Some(dir) = path.parent() {
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Get the state file of the most recent save in a store directory
+pub fn latest_save_file(store_dir: &Path) -> Result<PathBuf> {
+    let mut saves: Vec<(String, String)> = fs::read_dir(store_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| PersistenceFeature::parse_filename(&entry.file_name().to_string_lossy()))
+        .collect();
+    saves.sort();
+    let (timestamp, mnemonic) = saves.pop()
+        .ok_or_else(|| anyhow::anyhow!("No saved sessions in {:?}", store_dir))?;
+    Ok(store_dir.join(PersistenceFeature::build_filename(&timestamp, &mnemonic)))
+}
+
+/// Get the graph bundle for Ingen to load at boot, if it was written for the most recent save of a store directory
+pub fn preload_bundle(store_dir: &Path) -> Option<PathBuf> {
+    let latest = latest_save_file(store_dir).ok()?;
+    let written_for = fs::read_to_string(store_dir.join(PRELOAD_SAVE_FILE)).ok()?;
+    let bundle = store_dir.join(PRELOAD_BUNDLE);
+    (latest.file_name()? == written_for.trim() && bundle.join("manifest.ttl").is_file()).then_some(bundle)
+}
+
+/// Find the graph bundles saved by Ingen (directories named *.ingen with a manifest) in directories
+pub fn find_ingen_bundles(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut bundles: Vec<PathBuf> = dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ingen") && path.join("manifest.ttl").is_file())
+        .collect();
+    bundles.sort();
+    bundles
+}
+
+/// Hash the state and the JACK connections of a save to detect identical saves
+fn content_hash(state_data: &str, connections: &str) -> u64 {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    state_data.hash(&mut hasher);
+    connections.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Hash the blocks, connections and ports of a graph with the control values of its blocks, to detect changes
+fn graph_fingerprint(graph: &crate::engine::Graph, values: &[(String, std::collections::HashMap<String, f32>)]) -> u64 {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(graph).unwrap_or_default().hash(&mut hasher);
+    for (block, block_values) in values {
+        block.hash(&mut hasher);
+        let mut block_values: Vec<(&String, &f32)> = block_values.iter().collect();
+        block_values.sort_by(|a, b| a.0.cmp(b.0));
+        for (symbol, value) in block_values {
+            symbol.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+/// Get the mnemonics of the sessions saved in a store directory (newest first)
+pub fn saved_sessions(store_dir: &Path) -> Result<Vec<String>> {
+    if !store_dir.exists() {
+        return Ok(Vec::new());
+    }
+    
+    let mut mnemonics: Vec<(String, String)> = Vec::new();
+    
+    for entry in fs::read_dir(store_dir)? {
+        let entry = entry?;
+        let filename = entry.file_name().to_string_lossy().to_string();
+        
+        if let Some((timestamp, mnemonic)) = PersistenceFeature::parse_filename(&filename) {
+            mnemonics.push((timestamp, mnemonic));
+        }
+    }
+    
+    // Sort by timestamp descending
+    mnemonics.sort_by(|a, b| b.0.cmp(&a.0));
+    
+    // Extract unique mnemonics in order
+    let mut unique_mnemonics = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    
+    for (_, mnemonic) in mnemonics {
+        if seen.insert(mnemonic.clone()) {
+            unique_mnemonics.push(mnemonic);
+        }
+    }
+    
+    Ok(unique_mnemonics)
 }
 
 /// Helper to create a new persistence feature
-pub fn new_persistence_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, auto_load: bool) -> PersistenceFeature {
-    PersistenceFeature::new(driver, engine, ui, auto_load)
+pub fn new_persistence_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>, auto_load: bool) -> PersistenceFeature {
+    PersistenceFeature::new(driver, engine, ui, settings, auto_load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_info() {
+        let mut info = SessionInfo::default();
+        assert_eq!(info.summary(), "");
+
+        assert!(info.add_tag(" Live Set "));
+        assert!(info.add_tag("dub"));
+        assert!(!info.add_tag("live-set"));
+        assert!(!info.add_tag(" "));
+        assert_eq!(info.tags, vec!["dub".to_string(), "live-set".to_string()]);
+
+        info.notes = "Start with the delay wide open".to_string();
+        assert_eq!(info.summary(), " [dub, live-set] - Start with the delay wid…");
+
+        // Files written before notes existed still load
+        let info: SessionInfo = serde_json::from_str(r#"{"tags": ["dub"]}"#).unwrap();
+        assert_eq!(info.notes, "");
+    }
+
+    #[test]
+    fn test_write_atomic_keeps_previous_file_when_invalid() {
+        let dir = std::env::temp_dir().join(format!("traxdub-persistence-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.jack.json");
+        let validate = |content: &str| -> Result<()> {
+            serde_json::from_str::<Vec<JackConnection>>(content)?;
+            Ok(())
+        };
+
+        write_atomic(&path, "[]", validate).unwrap();
+        assert!(write_atomic(&path, "[{\"source\": ", validate).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[]");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_ingen_bundles() {
+        let dir = std::env::temp_dir().join(format!("traxdub-bundle-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("dub.ingen")).unwrap();
+        fs::write(dir.join("dub.ingen").join("manifest.ttl"), "").unwrap();
+        // Without a manifest it is not a bundle
+        fs::create_dir_all(dir.join("empty.ingen")).unwrap();
+        fs::write(dir.join("notes.ingen"), "").unwrap();
+
+        assert_eq!(find_ingen_bundles(&[dir.clone(), dir.join("missing")]), vec![dir.join("dub.ingen")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_preload_bundle() {
+        let dir = std::env::temp_dir().join(format!("traxdub-preload-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join(PRELOAD_BUNDLE)).unwrap();
+        fs::write(dir.join(PRELOAD_BUNDLE).join("manifest.ttl"), "").unwrap();
+        fs::write(dir.join("2025-01-02-20-15-cosmic-river.txd"), "").unwrap();
+        fs::write(dir.join(PRELOAD_SAVE_FILE), "2025-01-02-20-15-cosmic-river.txd").unwrap();
+        assert_eq!(preload_bundle(&dir), Some(dir.join(PRELOAD_BUNDLE)));
+
+        // A later save without its bundle makes it outdated
+        fs::write(dir.join("2025-01-02-21-30-cosmic-river.txd"), "").unwrap();
+        assert_eq!(preload_bundle(&dir), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash("state", "[]"), content_hash("state", "[]"));
+        assert_ne!(content_hash("state", "[]"), content_hash("state", "[{}]"));
+        // The boundary between the files counts
+        assert_ne!(content_hash("ab", "c"), content_hash("a", "bc"));
+    }
+
+    #[test]
+    fn test_graph_fingerprint() {
+        let graph = crate::engine::Graph::default();
+        let values = |gain: f32| vec![(
+            "ingen:/main/amp".to_string(),
+            std::collections::HashMap::from([("gain".to_string(), gain), ("mix".to_string(), 1.0)]),
+        )];
+        assert_eq!(graph_fingerprint(&graph, &values(0.5)), graph_fingerprint(&graph, &values(0.5)));
+        assert_ne!(graph_fingerprint(&graph, &values(0.5)), graph_fingerprint(&graph, &values(0.6)));
+        assert_ne!(graph_fingerprint(&graph, &values(0.5)), graph_fingerprint(&graph, &[]));
+    }
 }
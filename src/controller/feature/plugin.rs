@@ -1,15 +1,169 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-use crate::controller::{ControllerState, feature::Feature};
-use crate::engine::{Engine, PortDirection};
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::mixer::{destination_port, ensure_faders, node_of_port, source_port};
+use crate::engine::{Engine, Graph, Plugin, PortDirection, PortType};
 use crate::ui::{Menu, MenuOption, UI, NodeType};
 
 /// Menu state for the plugin feature
 #[derive(Debug, Clone, PartialEq)]
 enum PluginMenuState {
     PluginSelection,
+    PluginInfo(String), // plugin URI
+    MidiTransforms,
+}
+
+/// Number of plugins shown in the favorites and recent sections
+const SECTION_SIZE: usize = 5;
+
+/// Option id prefixes of the favorites and recent sections
+const FAVORITE_PREFIX: &str = "favorite:";
+const RECENT_PREFIX: &str = "recent:";
+
+/// Option id of the plugin rescan action
+const RESCAN_OPTION: &str = "rescan";
+
+/// MIDI transforms offered on MIDI links, with the x42-midifilter plugins implementing them
+const MIDI_TRANSFORMS: [(&str, &str); 4] = [
+    ("Channel Remap", "http://gareus.org/oss/lv2/midifilter#channelmap"),
+    ("Transpose", "http://gareus.org/oss/lv2/midifilter#transpose"),
+    ("CC Remap", "http://gareus.org/oss/lv2/midifilter#mapcc"),
+    ("Velocity Curve", "http://gareus.org/oss/lv2/midifilter#velocityscale"),
+];
+
+/// Describe a channel count ("Mono", "Stereo" or "<n> ch")
+fn channel_name(count: usize) -> String {
+    match count {
+        0 => "None".to_string(),
+        1 => "Mono".to_string(),
+        2 => "Stereo".to_string(),
+        n => format!("{} ch", n),
+    }
+}
+
+/// Describe the audio channel layout of a plugin (e.g., "Stereo" or "Mono > Stereo")
+pub fn channel_layout(plugin: &Plugin) -> String {
+    let count = |direction: PortDirection| plugin.ports.iter()
+        .filter(|p| p.port_type == PortType::Audio && p.direction == direction)
+        .count();
+    let (inputs, outputs) = (count(PortDirection::Input), count(PortDirection::Output));
+    if inputs == outputs {
+        channel_name(inputs)
+    } else {
+        format!("{} > {}", channel_name(inputs), channel_name(outputs))
+    }
+}
+
+/// Get the type of a port from its path, for block ports and system ports
+pub fn port_type_of(graph: &Graph, port_path: &str) -> Option<PortType> {
+    if let Some(block) = graph.blocks.iter().find(|b| port_path.starts_with(&format!("{}/", b.id))) {
+        let symbol = &port_path[block.id.len() + 1..];
+        return block.ports.iter().find(|p| p.id == symbol).map(|p| p.port_type.clone());
+    }
+    let name = port_path.strip_prefix("ingen:/main/")?;
+    graph.ports.iter().find(|p| p.id == name).map(|p| p.port_type.clone())
+}
+
+/// Get the signal type carried by a link between two nodes, if it can be told
+pub fn link_port_type(graph: &Graph, from: &str, to: &str) -> Option<PortType> {
+    // Use the engine connection behind the link, otherwise the system port at one end
+    graph.connections.iter()
+        .find(|c| node_of_port(&c.source) == from && node_of_port(&c.destination) == to)
+        .and_then(|c| port_type_of(graph, &c.source))
+        .or_else(|| port_type_of(graph, from))
+        .or_else(|| port_type_of(graph, to))
+}
+
+/// Check whether a plugin can be inserted in a link of the given type
+pub fn is_compatible(plugin: &Plugin, port_type: &PortType) -> bool {
+    let has = |direction: PortDirection| plugin.ports.iter()
+        .any(|p| &p.port_type == port_type && p.direction == direction);
+    has(PortDirection::Input) && has(PortDirection::Output)
+}
+
+/// Name of a port type for messages
+fn port_type_name(port_type: &PortType) -> &'static str {
+    match port_type {
+        PortType::Audio => "audio",
+        PortType::Midi => "MIDI",
+    }
+}
+
+/// Plugin usage statistics, persisted across sessions
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginStats {
+    /// Number of insertions per plugin URI
+    #[serde(default)]
+    uses: HashMap<String, u32>,
+    /// Recently inserted plugin URIs, most recent first
+    #[serde(default)]
+    recent: Vec<String>,
+}
+
+impl PluginStats {
+    /// Get the statistics file path
+    fn get_stats_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home).join(".traxdub").join("plugin_stats.json"))
+    }
+
+    /// Load the statistics, starting empty if there are none yet
+    pub fn load() -> Self {
+        let stats = Self::get_stats_path().and_then(|path| {
+            let content = fs::read_to_string(&path).context("Failed to read plugin stats")?;
+            serde_json::from_str(&content).context("Failed to parse plugin stats")
+        });
+        stats.unwrap_or_else(|e| {
+            debug!("No plugin stats loaded: {}", e);
+            Self::default()
+        })
+    }
+
+    /// Save the statistics
+    pub fn save(&self) -> Result<()> {
+        let path = Self::get_stats_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .context("Failed to write plugin stats")?;
+        Ok(())
+    }
+
+    /// Record an insertion of a plugin
+    pub fn record(&mut self, plugin_uri: &str) {
+        *self.uses.entry(plugin_uri.to_string()).or_insert(0) += 1;
+        self.recent.retain(|uri| uri != plugin_uri);
+        self.recent.insert(0, plugin_uri.to_string());
+        self.recent.truncate(SECTION_SIZE * 2);
+    }
+
+    /// Most inserted plugins, most used first
+    pub fn favorites(&self) -> Vec<&str> {
+        let mut favorites: Vec<_> = self.uses.iter().collect();
+        favorites.sort_by(|(a_uri, a_count), (b_uri, b_count)| b_count.cmp(a_count).then(a_uri.cmp(b_uri)));
+        favorites.into_iter()
+            .take(SECTION_SIZE)
+            .map(|(uri, _)| uri.as_str())
+            .collect()
+    }
+
+    /// Recently inserted plugins not already among the favorites
+    pub fn recent(&self) -> Vec<&str> {
+        let favorites = self.favorites();
+        self.recent.iter()
+            .map(|uri| uri.as_str())
+            .filter(|uri| !favorites.contains(uri))
+            .take(SECTION_SIZE)
+            .collect()
+    }
 }
 
 /// Plugin feature for adding plugin blocks to the signal chain
@@ -18,6 +172,7 @@ pub struct PluginFeature {
     ui: Arc<UI>,
     menu_state: PluginMenuState,
     ui_element: Option<crate::ui::Element>,
+    stats: PluginStats,
 }
 
 impl PluginFeature {
@@ -28,19 +183,54 @@ impl PluginFeature {
             ui,
             menu_state: PluginMenuState::PluginSelection,
             ui_element: None,
+            stats: PluginStats::load(),
         }
     }
     
-    /// Get the plugin selection menu
+    /// Get the signal type of the selected link
+    fn link_type(&self) -> Option<PortType> {
+        let Some(crate::ui::Element::Link(from, to, _)) = &self.ui_element else {
+            return None;
+        };
+        let graph = self.engine.get_graph()
+            .map_err(|e| debug!("Error getting graph: {}", e))
+            .ok()?;
+        link_port_type(&graph, from, to)
+    }
+    
+    /// Get the plugin selection menu, with the plugins that fit the selected link
     fn get_plugin_selection_menu(&self) -> Menu {
-        let plugins = self.engine.list_plugins();
+        let link_type = self.link_type();
+        let plugins: Vec<Plugin> = self.engine.list_plugins().into_iter()
+            .filter(|plugin| link_type.as_ref().is_none_or(|t| is_compatible(plugin, t)))
+            .collect();
         
-        let options: Vec<MenuOption> = plugins.iter()
+        let mut all: Vec<MenuOption> = plugins.iter()
             .map(|plugin| MenuOption {
                 id: plugin.id.clone(),
                 label: plugin.name.clone(),
             })
             .collect();
+        // Sorted by name so the secondary knob can jump by first letter
+        all.sort_by_key(|option| option.label.to_lowercase());
+
+        // Prepend the favorites and recent sections
+        let section = |uris: Vec<&str>, prefix: &str, mark: &str| -> Vec<MenuOption> {
+            uris.into_iter()
+                .filter_map(|uri| plugins.iter().find(|p| p.id == uri))
+                .map(|plugin| MenuOption {
+                    id: format!("{}{}", prefix, plugin.id),
+                    label: format!("{} {}", mark, plugin.name),
+                })
+                .collect()
+        };
+        let mut options = vec![MenuOption {
+            id: RESCAN_OPTION.to_string(),
+            label: "Rescan Plugins".to_string(),
+        }];
+        options.extend(section(self.stats.favorites(), FAVORITE_PREFIX, "★"));
+        options.extend(section(self.stats.recent(), RECENT_PREFIX, "↺"));
+        options.extend(all);
         
         Menu {
             id: "plugin_selection".to_string(),
@@ -48,15 +238,100 @@ impl PluginFeature {
             options,
         }
     }
+    
+    /// Get the menu of the MIDI transforms, marking the ones whose plugin is missing
+    fn get_midi_transforms_menu(&self) -> Menu {
+        let plugins = self.engine.list_plugins();
+        Menu {
+            id: "midi_transforms".to_string(),
+            label: "MIDI Transform".to_string(),
+            options: MIDI_TRANSFORMS.iter()
+                .map(|(label, uri)| MenuOption {
+                    id: uri.to_string(),
+                    label: if plugins.iter().any(|p| p.id == *uri) {
+                        label.to_string()
+                    } else {
+                        format!("{} (not installed)", label)
+                    },
+                })
+                .collect(),
+        }
+    }
+    
+    /// Get the info menu of a plugin, shown before inserting it
+    /// Selecting any line inserts the plugin, going back returns to the plugin list
+    fn get_plugin_info_menu(&self, plugin_uri: &str) -> Menu {
+        let plugins = self.engine.list_plugins();
+        let Some(plugin) = plugins.iter().find(|p| p.id == plugin_uri) else {
+            return self.get_plugin_selection_menu();
+        };
+        
+        let count = |port_type: PortType, direction: PortDirection| plugin.ports.iter()
+            .filter(|p| p.port_type == port_type && p.direction == direction)
+            .count();
+        
+        let mut lines = vec![
+            format!("Insert {}", plugin.name),
+            format!("By {}", plugin.author.as_deref().unwrap_or("unknown author")),
+            format!("License: {}", plugin.license.as_deref().unwrap_or("unknown")),
+            format!("Audio: {} in / {} out", count(PortType::Audio, PortDirection::Input), count(PortType::Audio, PortDirection::Output)),
+            format!("Layout: {}", channel_layout(plugin)),
+            format!("MIDI: {} in / {} out", count(PortType::Midi, PortDirection::Input), count(PortType::Midi, PortDirection::Output)),
+            format!("Controls: {}", plugin.controls.len()),
+        ];
+        if let Some(category) = &plugin.category {
+            lines.insert(1, format!("Type: {}", category));
+        }
+        
+        Menu {
+            id: "plugin_info".to_string(),
+            label: plugin.name.clone(),
+            options: lines.into_iter()
+                .enumerate()
+                .map(|(i, label)| MenuOption { id: format!("info_{}", i), label })
+                .collect(),
+        }
+    }
 }
 
 impl Feature for PluginFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(from, to, _) if from != "inputs" => {
+                let mut entries = vec![ContextEntry::new(30, "add_plugin", "Add Plugin >")];
+                let is_midi = self.engine.get_graph()
+                    .is_ok_and(|graph| link_port_type(&graph, from, to) == Some(PortType::Midi));
+                if is_midi {
+                    entries.push(ContextEntry::new(35, "midi_transform", "MIDI Transform >"));
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        self.set_element(element);
+        self.menu_state = if option_id == "midi_transform" {
+            PluginMenuState::MidiTransforms
+        } else {
+            PluginMenuState::PluginSelection
+        };
+        Ok(ControllerState::BrowsingMenu)
+    }
+
     fn get_menu(&self) -> Menu {
-        match self.menu_state {
+        match &self.menu_state {
             PluginMenuState::PluginSelection => self.get_plugin_selection_menu(),
+            PluginMenuState::PluginInfo(plugin_uri) => self.get_plugin_info_menu(plugin_uri),
+            PluginMenuState::MidiTransforms => self.get_midi_transforms_menu(),
         }
     }
     
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+    
     fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
         debug!("Plugin feature handle_menu_option called with element: {:?}", element);
         
@@ -66,11 +341,51 @@ impl Feature for PluginFeature {
         }
         
         // Handle menu closure
-        let Some(plugin_uri) = option_id else {
+        let Some(option) = option_id else {
             debug!("Plugin feature: menu closed");
+            if let PluginMenuState::PluginInfo(_) = self.menu_state {
+                self.menu_state = PluginMenuState::PluginSelection;
+                return Ok(ControllerState::BrowsingMenu);
+            }
+            self.ui_element = None;
             return Ok(ControllerState::Navigating);
         };
         
+        // Pick up plugins installed since startup
+        if option == RESCAN_OPTION {
+            let count = {
+                let busy = self.ui.busy("Scanning plugins")?;
+                self.engine.rescan_plugins(&|step| busy.update(step))?
+            };
+            self.ui.show_message(&format!("Found {} plugins", count))?;
+            self.ui_element = None;
+            return Ok(ControllerState::Navigating);
+        }
+        
+        // Show the plugin info before inserting it
+        let plugin_uri = match &self.menu_state {
+            PluginMenuState::PluginSelection => {
+                let plugin_uri = option.strip_prefix(FAVORITE_PREFIX)
+                    .or_else(|| option.strip_prefix(RECENT_PREFIX))
+                    .unwrap_or(option);
+                self.menu_state = PluginMenuState::PluginInfo(plugin_uri.to_string());
+                return Ok(ControllerState::BrowsingMenu);
+            }
+            PluginMenuState::PluginInfo(plugin_uri) => plugin_uri.clone(),
+            // Transforms are inserted right away, their plugins are known
+            PluginMenuState::MidiTransforms => {
+                if !self.engine.list_plugins().iter().any(|p| p.id == option) {
+                    self.ui.show_message("Install x42-midifilter for the MIDI transforms")?;
+                    self.menu_state = PluginMenuState::PluginSelection;
+                    self.ui_element = None;
+                    return Ok(ControllerState::Navigating);
+                }
+                option.to_string()
+            }
+        };
+        let plugin_uri = plugin_uri.as_str();
+        self.menu_state = PluginMenuState::PluginSelection;
+        
         debug!("Plugin feature handling plugin selection: {}", plugin_uri);
         
         // Extract link information from stored element
@@ -80,72 +395,110 @@ impl Feature for PluginFeature {
             return Err(anyhow::anyhow!("Plugin feature requires a link element"));
         };
         
+        // Find the plugin in the engine's plugin list to get port information
+        let plugins = self.engine.list_plugins();
+        let plugin = plugins.iter()
+            .find(|p| p.id == plugin_uri)
+            .ok_or_else(|| anyhow::anyhow!("Plugin not found: {}", plugin_uri))?;
+        
+        // Refuse plugins that cannot carry the signal of the link
+        let link_type = self.link_type();
+        if let Some(link_type) = &link_type {
+            if !is_compatible(plugin, link_type) {
+                let other = match link_type {
+                    PortType::Audio => PortType::Midi,
+                    PortType::Midi => PortType::Audio,
+                };
+                let suggestion = if is_compatible(plugin, &other) {
+                    format!(", insert it on a {} link", port_type_name(&other))
+                } else {
+                    String::new()
+                };
+                self.ui.show_message(&format!("{} has no {} input and output{}",
+                    plugin.name, port_type_name(link_type), suggestion))?;
+                self.ui_element = None;
+                return Ok(ControllerState::Navigating);
+            }
+        }
+        let fits = |port_type: &PortType| link_type.as_ref().is_none_or(|t| t == port_type);
+        
         // Generate a unique block ID based on plugin URI and timestamp
         let block_name = plugin_uri
             .split('/')
-            .last()
+            .next_back()
             .unwrap_or(plugin_uri)
             .replace([':', '.', '#'], "_");
-        let block_id = format!("{}_{}", block_name, std::time::SystemTime::now()
+        let block_symbol = format!("{}_{}", block_name, std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() % 1000000);
         
-        debug!("Creating block: {} with plugin: {}", block_id, plugin_uri);
+        // Inside an open group the block goes to the group graph
+        let block_path = format!("{}/{}", crate::controller::feature::group::view_scope(&self.ui), block_symbol);
+        let block_id = block_path.strip_prefix("ingen:/main/").unwrap_or(&block_symbol);
         
-        // Create the block in the engine
-        self.engine.create_block(plugin_uri, &block_id)?;
+        debug!("Creating block: {} with plugin: {}", block_id, plugin_uri);
         
-        let block_path = format!("ingen:/main/{}", block_id);
+        // The source port of the link is read before the edits, which are applied at once
+        let is_system = |node: &str| node == "inputs" || node == "outputs";
+        let source = (!is_system(&link_from)).then(|| source_port(&self.engine, &link_from));
+        let destination = (!is_system(&link_to)).then(|| destination_port(&self.engine, &link_to));
+        self.engine.bundle(|engine| {
+            // Create the block in the engine
+            engine.create_block(plugin_uri, block_id)?;
+            
+            // Create connections in the engine (skip "inputs" and "outputs" system nodes)
+            if let Some(from_path) = &source {
+                // Find the first input port of the plugin matching the link
+                if let Some(input_port) = plugin.ports.iter().find(|p| p.direction == PortDirection::Input && fits(&p.port_type)) {
+                    let to_path = format!("{}/{}", block_path, input_port.id);
+                    debug!("Creating engine connection: {} -> {}", from_path, to_path);
+                    engine.connect(from_path, &to_path)?;
+                } else {
+                    warn!("Plugin {} has no input ports", plugin_uri);
+                }
+            }
+            if let Some(to_path) = &destination {
+                // Find the first output port of the plugin matching the link
+                if let Some(output_port) = plugin.ports.iter().find(|p| p.direction == PortDirection::Output && fits(&p.port_type)) {
+                    let from_path = format!("{}/{}", block_path, output_port.id);
+                    debug!("Creating engine connection: {} -> {}", from_path, to_path);
+                    engine.connect(&from_path, to_path)?;
+                } else {
+                    warn!("Plugin {} has no output ports", plugin_uri);
+                }
+            }
+
+            // Disconnect the original connection in the engine (skip system nodes)
+            if let (Some(from_path), Some(to_path)) = (&source, &destination) {
+                debug!("Disconnecting original engine connection: {} -> {}", link_from, link_to);
+                // Ignore error if connection doesn't exist
+                let _ = engine.disconnect(from_path, to_path);
+            }
+            Ok(())
+        })?;
         
         // Insert node in UI
         self.ui.insert_node(
             block_path.clone(),
-            block_id.clone(),
+            block_symbol.clone(),
             NodeType::Normal,
             link_from.clone(),
             link_to.clone(),
         )?;
         self.ui.commit()?; // Commit node insertion
         
-        // Find the plugin in the engine's plugin list to get port information
-        let plugins = self.engine.list_plugins();
-        let plugin = plugins.iter()
-            .find(|p| p.id == plugin_uri)
-            .ok_or_else(|| anyhow::anyhow!("Plugin not found: {}", plugin_uri))?;        
-        
-        // Create connections in the engine (skip "inputs" and "outputs" system nodes)
-        if link_from != "inputs" && link_from != "outputs" {
-            // Find the first input port of the plugin
-            if let Some(input_port) = plugin.ports.iter().find(|p| p.direction == PortDirection::Input) {
-                let from_path = link_from.clone();
-                let to_path = format!("{}/{}", block_path, input_port.id);
-                debug!("Creating engine connection: {} -> {}", from_path, to_path);
-                self.engine.connect(&from_path, &to_path)?;
-            } else {
-                warn!("Plugin {} has no input ports", plugin_uri);
-            }
+        // A block feeding an output ends its chain and gets the faders of the mixer
+        if let Err(e) = ensure_faders(&self.engine, &self.ui) {
+            warn!("Could not insert the faders after {}: {}", block_symbol, e);
         }
-        if link_to != "inputs" && link_to != "outputs" {
-            // Find the first output port of the plugin
-            if let Some(output_port) = plugin.ports.iter().find(|p| p.direction == PortDirection::Output) {
-                let from_path = format!("{}/{}", block_path, output_port.id);
-                let to_path = link_to.clone();
-                debug!("Creating engine connection: {} -> {}", from_path, to_path);
-                self.engine.connect(&from_path, &to_path)?;
-            } else {
-                warn!("Plugin {} has no output ports", plugin_uri);
-            }
-        }
-
-        // Disconnect the original connection in the engine (skip system nodes)
-        if link_from != "inputs" && link_from != "outputs" && link_to != "inputs" && link_to != "outputs" {
-            debug!("Disconnecting original engine connection: {} -> {}", link_from, link_to);
-            // Ignore error if connection doesn't exist
-            let _ = self.engine.disconnect(&link_from, &link_to);
+        
+        // Track the insertion for the favorites and recent sections
+        self.stats.record(plugin_uri);
+        if let Err(e) = self.stats.save() {
+            warn!("Failed to save plugin stats: {}", e);
         }
         
-        self.menu_state = PluginMenuState::PluginSelection;
         self.ui_element = None;
         Ok(ControllerState::Navigating)
     }
@@ -155,3 +508,52 @@ impl Feature for PluginFeature {
 pub fn new_plugin_feature(engine: Arc<Engine>, ui: Arc<UI>) -> PluginFeature {
     PluginFeature::new(engine, ui)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_stats_sections() {
+        let mut stats = PluginStats::default();
+        for uri in ["reverb", "delay", "reverb", "filter", "reverb", "delay"] {
+            stats.record(uri);
+        }
+
+        assert_eq!(stats.favorites(), vec!["reverb", "delay", "filter"]);
+        // Recent plugins already among the favorites are not repeated
+        assert!(stats.recent().is_empty());
+
+        for i in 0..SECTION_SIZE {
+            stats.record(&format!("plugin_{}", i));
+        }
+        assert_eq!(stats.favorites()[..2], ["reverb", "delay"]);
+        assert!(!stats.recent().contains(&"reverb"));
+        assert!(stats.recent().contains(&"plugin_4"));
+    }
+
+    #[test]
+    fn test_channel_layout() {
+        let port = |id: &str, direction: PortDirection| crate::engine::Port {
+            id: id.to_string(),
+            port_type: PortType::Audio,
+            direction,
+        };
+        let mut plugin = Plugin {
+            id: "urn:test".to_string(),
+            name: "Test".to_string(),
+            ports: vec![port("in", PortDirection::Input), port("out_l", PortDirection::Output)],
+            controls: Vec::new(),
+            category: None,
+            presets: Vec::new(),
+            author: None,
+            license: None,
+            has_ui: false,
+            latency_port: None,
+        };
+        assert_eq!(channel_layout(&plugin), "Mono");
+
+        plugin.ports.push(port("out_r", PortDirection::Output));
+        assert_eq!(channel_layout(&plugin), "Mono > Stereo");
+    }
+}
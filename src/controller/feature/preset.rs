@@ -0,0 +1,349 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::engine::{Engine, Plugin, PluginPreset};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Option id of the save action and prefixes of the user and factory presets
+const SAVE_OPTION: &str = "save";
+const USER_PREFIX: &str = "user:";
+const FACTORY_PREFIX: &str = "factory:";
+
+/// One of the two parameter sets compared on a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AbSlot {
+    A,
+    B,
+}
+
+impl AbSlot {
+    fn label(&self) -> &'static str {
+        match self {
+            AbSlot::A => "A",
+            AbSlot::B => "B",
+        }
+    }
+
+    fn other(&self) -> Self {
+        match self {
+            AbSlot::A => AbSlot::B,
+            AbSlot::B => AbSlot::A,
+        }
+    }
+}
+
+/// Parameter sets stored on a node for an A/B comparison
+#[derive(Debug, Clone, Default)]
+struct AbSnapshots {
+    a: Option<HashMap<String, f32>>,
+    b: Option<HashMap<String, f32>>,
+    /// Set applied last
+    current: Option<AbSlot>,
+}
+
+impl AbSnapshots {
+    fn slot_mut(&mut self, slot: AbSlot) -> &mut Option<HashMap<String, f32>> {
+        match slot {
+            AbSlot::A => &mut self.a,
+            AbSlot::B => &mut self.b,
+        }
+    }
+}
+
+/// Menu state for the preset feature
+#[derive(Debug, Clone, PartialEq)]
+enum PresetMenuState {
+    PresetList,
+}
+
+/// Preset feature for saving and recalling the control values of a block
+pub struct PresetFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    menu_state: PresetMenuState,
+    ui_element: Option<crate::ui::Element>,
+    /// A/B parameter sets keyed by block path
+    snapshots: HashMap<String, AbSnapshots>,
+}
+
+impl PresetFeature {
+    /// Create a new preset feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            menu_state: PresetMenuState::PresetList,
+            ui_element: None,
+            snapshots: HashMap::new(),
+        }
+    }
+
+    /// Get the block path of the selected node
+    fn block_id(&self) -> Option<&str> {
+        match &self.ui_element {
+            Some(crate::ui::Element::Node(block_id)) => Some(block_id),
+            _ => None,
+        }
+    }
+
+    /// Get the plugin of the selected node
+    fn plugin(&self) -> Option<Plugin> {
+        self.block_id().and_then(|block_id| self.engine.get_block_plugin(block_id))
+    }
+
+    /// Get the user preset directory of a plugin
+    fn get_preset_dir(plugin_uri: &str) -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home)
+            .join(".traxdub")
+            .join("presets")
+            .join(urlencoding::encode(plugin_uri).as_ref()))
+    }
+
+    /// List the user presets of a plugin, sorted by name
+    fn list_user_presets(plugin_uri: &str) -> Vec<PluginPreset> {
+        let Ok(entries) = Self::get_preset_dir(plugin_uri).and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+            return Vec::new();
+        };
+
+        let mut presets: Vec<PluginPreset> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let content = fs::read_to_string(&path).ok()?;
+                serde_json::from_str(&content)
+                    .map_err(|e| warn!("Invalid preset file {:?}: {}", path, e))
+                    .ok()
+            })
+            .collect();
+        presets.sort_by(|a, b| a.name.cmp(&b.name));
+        presets
+    }
+
+    /// Get the preset list menu
+    fn get_preset_list_menu(&self) -> Menu {
+        let mut options = vec![MenuOption {
+            id: SAVE_OPTION.to_string(),
+            label: "Save Preset...".to_string(),
+        }];
+
+        if let Some(plugin) = self.plugin() {
+            options.extend(Self::list_user_presets(&plugin.id).into_iter().map(|preset| MenuOption {
+                id: format!("{}{}", USER_PREFIX, preset.name),
+                label: preset.name,
+            }));
+            options.extend(plugin.presets.iter().map(|preset| MenuOption {
+                id: format!("{}{}", FACTORY_PREFIX, preset.name),
+                label: format!("{} (factory)", preset.name),
+            }));
+        }
+
+        Menu {
+            id: "preset_list".to_string(),
+            label: "Presets".to_string(),
+            options,
+        }
+    }
+
+    /// Save the current control values of the selected node as a new user preset
+    fn save_preset(&self) -> Result<String> {
+        let block_id = self.block_id()
+            .ok_or_else(|| anyhow::anyhow!("Preset feature requires a node element"))?;
+        let plugin = self.plugin()
+            .ok_or_else(|| anyhow::anyhow!("Unknown plugin for {}", block_id))?;
+        let values = self.control_values(block_id, &plugin)?;
+
+        // Name the preset after the first free number
+        let existing = Self::list_user_presets(&plugin.id);
+        let name = (1..)
+            .map(|i| format!("Preset {}", i))
+            .find(|name| !existing.iter().any(|p| &p.name == name))
+            .unwrap_or_default();
+
+        let dir = Self::get_preset_dir(&plugin.id)?;
+        fs::create_dir_all(&dir)
+            .context("Failed to create preset directory")?;
+        let preset = PluginPreset { name: name.clone(), values };
+        fs::write(dir.join(format!("{}.json", name)), serde_json::to_string_pretty(&preset)?)
+            .context("Failed to write preset")?;
+
+        info!("Saved preset '{}' of {}", name, plugin.id);
+        Ok(name)
+    }
+
+    /// Get the current values of the plugin controls of a block
+    fn control_values(&self, block_id: &str, plugin: &Plugin) -> Result<HashMap<String, f32>> {
+        Ok(self.engine.get_control_values(block_id)?
+            .into_iter()
+            .filter(|(symbol, _)| plugin.controls.iter().any(|c| &c.id == symbol))
+            .collect())
+    }
+
+    /// Store the current values of a block as one of its A/B sets
+    fn store_snapshot(&mut self, block_id: &str, slot: AbSlot) -> Result<()> {
+        let plugin = self.engine.get_block_plugin(block_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown plugin for {}", block_id))?;
+        let values = self.control_values(block_id, &plugin)?;
+        let snapshots = self.snapshots.entry(block_id.to_string()).or_default();
+        *snapshots.slot_mut(slot) = Some(values);
+        snapshots.current = Some(slot);
+        info!("Stored {} of {}", slot.label(), block_id);
+        Ok(())
+    }
+
+    /// Apply the other A/B set of a block, returning the set now applied
+    fn toggle_snapshot(&mut self, block_id: &str) -> Result<AbSlot> {
+        let previous = self.engine.get_control_values(block_id).unwrap_or_default();
+        let snapshots = self.snapshots.get_mut(block_id)
+            .ok_or_else(|| anyhow::anyhow!("No A/B sets stored on {}", block_id))?;
+        let slot = snapshots.current.unwrap_or(AbSlot::B).other();
+        let values = snapshots.slot_mut(slot).clone()
+            .ok_or_else(|| anyhow::anyhow!("{} is not stored", slot.label()))?;
+        snapshots.current = Some(slot);
+
+        // Only send the values that differ, for an instant switch
+        debug!("Switching {} to {}", block_id, slot.label());
+        for (symbol, value) in &values {
+            if previous.get(symbol) != Some(value) {
+                self.engine.set_control_parameter(block_id, symbol, *value)?;
+            }
+        }
+        Ok(slot)
+    }
+
+    /// Apply a preset to the selected node
+    fn apply_preset(&self, preset: &PluginPreset) -> Result<()> {
+        let block_id = self.block_id()
+            .ok_or_else(|| anyhow::anyhow!("Preset feature requires a node element"))?;
+
+        info!("Applying preset '{}' to {}", preset.name, block_id);
+        for (symbol, value) in &preset.values {
+            self.engine.set_control_parameter(block_id, symbol, *value)?;
+        }
+        Ok(())
+    }
+}
+
+impl Feature for PresetFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => {
+                let mut entries = vec![ContextEntry::new(20, "presets", "Presets >")];
+                if self.engine.get_block_plugin(node).is_some() {
+                    entries.push(ContextEntry::new(21, "store_a", "Store A"));
+                    entries.push(ContextEntry::new(22, "store_b", "Store B"));
+                    if let Some(snapshots) = self.snapshots.get(node).filter(|s| s.a.is_some() && s.b.is_some()) {
+                        let current = snapshots.current.map(|slot| slot.label()).unwrap_or_default();
+                        entries.push(ContextEntry::new(23, "toggle_ab", &format!("Toggle A-B ({})", current)));
+                    }
+                }
+                entries
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        let Some(crate::ui::Element::Node(node)) = element else {
+            return Ok(ControllerState::Navigating);
+        };
+        match option_id {
+            "store_a" | "store_b" => {
+                let slot = if option_id == "store_a" { AbSlot::A } else { AbSlot::B };
+                self.store_snapshot(node, slot)?;
+                self.ui.show_message(&format!("Stored {}", slot.label()))?;
+                Ok(ControllerState::Navigating)
+            }
+            "toggle_ab" => {
+                let slot = self.toggle_snapshot(node)?;
+                self.ui.show_message(&format!("Switched to {}", slot.label()))?;
+                Ok(ControllerState::Navigating)
+            }
+            _ => {
+                self.set_element(element);
+                Ok(ControllerState::BrowsingMenu)
+            }
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        match self.menu_state {
+            PresetMenuState::PresetList => self.get_preset_list_menu(),
+        }
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Preset feature handle_menu_option: {:?} with element: {:?}", option_id, element);
+
+        // Store the UI element if this is the first call
+        if self.ui_element.is_none() && element.is_some() {
+            self.ui_element = element.cloned();
+        }
+
+        // Handle menu closure
+        let Some(option) = option_id else {
+            debug!("Preset feature: menu closed");
+            self.ui_element = None;
+            return Ok(ControllerState::Navigating);
+        };
+
+        if option == SAVE_OPTION {
+            let name = self.save_preset()?;
+            self.ui.show_message(&format!("Saved {}", name))?;
+        } else if let Some(plugin) = self.plugin() {
+            let preset = if let Some(name) = option.strip_prefix(USER_PREFIX) {
+                Self::list_user_presets(&plugin.id).into_iter().find(|p| p.name == name)
+            } else if let Some(name) = option.strip_prefix(FACTORY_PREFIX) {
+                plugin.presets.into_iter().find(|p| p.name == name)
+            } else {
+                None
+            };
+            let preset = preset.ok_or_else(|| anyhow::anyhow!("Preset not found: {}", option))?;
+            self.apply_preset(&preset)?;
+            self.ui.show_message(&format!("Loaded {}", preset.name))?;
+        }
+
+        self.ui_element = None;
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new preset feature
+pub fn new_preset_feature(engine: Arc<Engine>, ui: Arc<UI>) -> PresetFeature {
+    PresetFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_ab() {
+        let engine = Arc::new(Engine::new_mock());
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        let block = "ingen:/main/delay";
+        let mut feature = PresetFeature::new(Arc::clone(&engine), Arc::new(UI::new()));
+
+        engine.set_control_parameter(block, "time", 250.0).unwrap();
+        feature.store_snapshot(block, AbSlot::A).unwrap();
+        assert!(feature.toggle_snapshot(block).is_err());
+        engine.set_control_parameter(block, "time", 500.0).unwrap();
+        feature.store_snapshot(block, AbSlot::B).unwrap();
+
+        assert_eq!(feature.toggle_snapshot(block).unwrap(), AbSlot::A);
+        assert_eq!(engine.get_control_values(block).unwrap()["time"], 250.0);
+        assert_eq!(feature.toggle_snapshot(block).unwrap(), AbSlot::B);
+        assert_eq!(engine.get_control_values(block).unwrap()["time"], 500.0);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::driver::Driver;
+use crate::controller::recorder::Recorder;
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Record feature for capturing the output bus to audio files
+pub struct RecordFeature {
+    ui: Arc<UI>,
+    recorder: Recorder,
+}
+
+impl RecordFeature {
+    /// Create a new record feature
+    pub fn new(driver: Arc<Driver>, ui: Arc<UI>) -> Self {
+        Self {
+            ui,
+            recorder: Recorder::new(driver),
+        }
+    }
+
+    /// Get the elapsed time of the running recording
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.recorder.elapsed()
+    }
+
+    /// Get the record menu
+    fn get_record_menu(&self) -> Menu {
+        let option = match self.recorder.elapsed() {
+            Some(elapsed) => MenuOption {
+                id: "stop".to_string(),
+                label: format!("Stop Recording ({})", format_elapsed(elapsed)),
+            },
+            None => MenuOption {
+                id: "start".to_string(),
+                label: "Start Recording".to_string(),
+            },
+        };
+
+        Menu {
+            id: "record_menu".to_string(),
+            label: "Record".to_string(),
+            options: vec![option],
+        }
+    }
+}
+
+/// Format a duration as minutes and seconds
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Feature for RecordFeature {
+    fn context_entries(&self, _element: &crate::ui::Element) -> Vec<ContextEntry> {
+        vec![ContextEntry::new(101, "record", "Record >")]
+    }
+
+    fn get_menu(&self) -> Menu {
+        self.get_record_menu()
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Record feature handle_menu_option: {:?}", option_id);
+
+        match option_id {
+            Some("start") => {
+                let path = self.recorder.start()?;
+                let filename = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                self.ui.show_message(&format!("Recording to {}", filename))?;
+            }
+            Some("stop") => {
+                let path = self.recorder.stop()?;
+                let filename = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+                self.ui.show_message(&format!("Saved {}", filename))?;
+            }
+            _ => {}
+        }
+
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new record feature
+pub fn new_record_feature(driver: Arc<Driver>, ui: Arc<UI>) -> RecordFeature {
+    RecordFeature::new(driver, ui)
+}
+
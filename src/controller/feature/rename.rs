@@ -0,0 +1,326 @@
+use anyhow::Result;
+use log::{info, warn};
+use std::sync::Arc;
+
+use crate::controller::{ControllerState, KnobDirection, reconcile, feature::{ContextEntry, Feature}};
+use crate::controller::feature::group::view_scope;
+use crate::controller::feature::mixer::{FADER_PREFIX, MAIN_GRAPH, is_bypassed, is_hidden_block};
+use crate::engine::Engine;
+use crate::ui::{Menu, UI};
+
+/// Characters the knob scrolls through, starting with a space
+const CHARSET: &str = " ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_.";
+
+/// Text edited one character at a time with knobs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextEntry {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl TextEntry {
+    /// Start editing a text, with the cursor after its last character
+    pub fn new(text: &str) -> Self {
+        let chars: Vec<char> = text.chars().collect();
+        Self { cursor: chars.len(), chars }
+    }
+
+    /// Scroll the character under the cursor through the character set
+    /// At the end of the text, scrolling appends a new character
+    pub fn scroll(&mut self, direction: KnobDirection) {
+        let charset: Vec<char> = CHARSET.chars().collect();
+        if self.cursor == self.chars.len() {
+            self.chars.push(' ');
+        }
+
+        let current = charset.iter().position(|c| *c == self.chars[self.cursor]).unwrap_or(0);
+        let next = match direction {
+            KnobDirection::Forward => (current + 1) % charset.len(),
+            KnobDirection::Backward => (current + charset.len() - 1) % charset.len(),
+        };
+        self.chars[self.cursor] = charset[next];
+    }
+
+    /// Move the cursor, up to one position after the last character
+    pub fn move_cursor(&mut self, direction: KnobDirection) {
+        // Trailing spaces behind the cursor are dropped when leaving them
+        match direction {
+            KnobDirection::Forward => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            KnobDirection::Backward => {
+                self.cursor = self.cursor.saturating_sub(1);
+                while self.chars.len() > self.cursor + 1 && self.chars.last() == Some(&' ') {
+                    self.chars.pop();
+                }
+            }
+        }
+    }
+
+    /// Get the edited text without surrounding spaces
+    pub fn text(&self) -> String {
+        self.chars.iter().collect::<String>().trim().to_string()
+    }
+
+    /// Get the raw characters and the cursor position for display
+    pub fn display(&self) -> (String, usize) {
+        (self.chars.iter().collect(), self.cursor)
+    }
+}
+
+/// Get the Ingen symbol of a name: lower case letters, digits and underscores, not starting with a digit
+/// None when the name has no letter or digit
+pub fn symbol_of_name(name: &str) -> Option<String> {
+    let mut symbol = String::new();
+    for c in name.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() {
+            symbol.push(c);
+        } else if !symbol.is_empty() && !symbol.ends_with('_') {
+            symbol.push('_');
+        }
+    }
+    let symbol = symbol.trim_end_matches('_');
+    match symbol.chars().next() {
+        None => None,
+        Some(first) if first.is_ascii_digit() => Some(format!("_{}", symbol)),
+        Some(_) => Some(symbol.to_string()),
+    }
+}
+
+/// Move a block to the path following its name, so that the Ingen paths and the saved states read like the UI
+/// Bypassed blocks and faders keep their path, their helper blocks being named after it
+/// Returns the new path, None when the block stays where it is
+pub fn follow_name(engine: &Engine, block_path: &str, name: &str) -> Result<Option<String>> {
+    let (Some(symbol), Some((parent, current))) = (symbol_of_name(name), block_path.rsplit_once('/')) else {
+        return Ok(None);
+    };
+    // A number added to make the path unique already follows the name
+    let follows = current.strip_prefix(symbol.as_str())
+        .is_some_and(|rest| rest.is_empty() || rest.strip_prefix('_').is_some_and(|n| n.parse::<u32>().is_ok()));
+    let graph = engine.get_graph()?;
+    if follows || current.starts_with(FADER_PREFIX) || symbol.starts_with(FADER_PREFIX) || is_bypassed(&graph, block_path) {
+        return Ok(None);
+    }
+
+    // The blocks and the ports of the graph share the same paths
+    let ports = if parent == MAIN_GRAPH {
+        graph.ports.clone()
+    } else {
+        graph.blocks.iter().find(|b| b.id == parent).map(|b| b.ports.clone()).unwrap_or_default()
+    };
+    let taken = |path: &String| graph.blocks.iter().any(|b| &b.id == path)
+        || ports.iter().any(|p| format!("{}/{}", parent, p.id) == *path)
+        || is_hidden_block(path);
+    let Some(new_path) = (1..20)
+        .map(|i| if i == 1 { format!("{}/{}", parent, symbol) } else { format!("{}/{}_{}", parent, symbol, i) })
+        .find(|path| !taken(path)) else {
+        return Ok(None);
+    };
+
+    engine.move_block(block_path, &new_path)?;
+    Ok(Some(new_path))
+}
+
+/// Feature editing a text with the knobs, or with the text typed in a frontend
+pub trait TextEditor {
+    /// Scroll the character under the cursor
+    fn scroll(&mut self, direction: KnobDirection) -> Result<()>;
+
+    /// Move the cursor
+    fn move_cursor(&mut self, direction: KnobDirection) -> Result<()>;
+
+    /// Replace the whole text
+    fn set_text(&mut self, text: &str) -> Result<()>;
+
+    /// Apply the edited text
+    fn confirm(&mut self) -> Result<()>;
+
+    /// Leave without applying the text
+    fn cancel(&mut self) -> Result<()>;
+}
+
+/// Rename feature for giving blocks a custom name
+pub struct RenameFeature {
+    engine: Arc<Engine>,
+    ui: Arc<UI>,
+    block_id: Option<String>,
+    entry: TextEntry,
+}
+
+impl RenameFeature {
+    /// Create a new rename feature
+    pub fn new(engine: Arc<Engine>, ui: Arc<UI>) -> Self {
+        Self {
+            engine,
+            ui,
+            block_id: None,
+            entry: TextEntry::default(),
+        }
+    }
+
+    /// Start renaming a block, editing its current name
+    pub fn start(&mut self, block_id: &str) -> Result<()> {
+        let name = self.engine.get_graph()?
+            .blocks
+            .into_iter()
+            .find(|b| b.id == block_id)
+            .map(|b| b.name)
+            .ok_or_else(|| anyhow::anyhow!("Only plugin blocks can be renamed"))?;
+
+        self.block_id = Some(block_id.to_string());
+        self.entry = TextEntry::new(&name);
+        self.refresh()
+    }
+
+    /// Replace the node of a block moved to a new path, keeping its tag and the focus
+    fn show_moved(&self, block_path: &str, new_path: &str) -> Result<()> {
+        if let Some((tag, color)) = self.ui.graph_model().tags.get(block_path).cloned() {
+            self.ui.set_node_tag(block_path.to_string(), None)?;
+            self.ui.set_node_tag(new_path.to_string(), Some((&tag, color)))?;
+        }
+        let graph = self.engine.get_graph()?;
+        reconcile::diff(&graph, &self.ui.graph_model(), &view_scope(&self.ui)).repair(&self.ui)?;
+        self.ui.focus_node(new_path.to_string())
+    }
+
+    /// Show the text entry in the UI
+    fn refresh(&self) -> Result<()> {
+        let (text, cursor) = self.entry.display();
+        self.ui.show_text_entry("Rename".to_string(), text, cursor)
+    }
+}
+
+impl TextEditor for RenameFeature {
+    /// Scroll the character under the cursor
+    fn scroll(&mut self, direction: KnobDirection) -> Result<()> {
+        self.entry.scroll(direction);
+        self.refresh()
+    }
+
+    /// Move the cursor
+    fn move_cursor(&mut self, direction: KnobDirection) -> Result<()> {
+        self.entry.move_cursor(direction);
+        self.refresh()
+    }
+
+    /// Replace the name with a text typed in a frontend
+    fn set_text(&mut self, text: &str) -> Result<()> {
+        self.entry = TextEntry::new(text);
+        self.refresh()
+    }
+
+    /// Apply the new name to the block and the UI node
+    fn confirm(&mut self) -> Result<()> {
+        self.ui.hide_text_entry()?;
+        let Some(block_id) = self.block_id.take() else {
+            return Ok(());
+        };
+
+        let name = self.entry.text();
+        if name.is_empty() {
+            self.ui.show_message("Name cannot be empty")?;
+            return Ok(());
+        }
+
+        info!("Renaming {} to '{}'", block_id, name);
+        self.engine.set_block_name(&block_id, &name)?;
+        match follow_name(&self.engine, &block_id, &name) {
+            Ok(Some(new_path)) => return self.show_moved(&block_id, &new_path),
+            Ok(None) => {}
+            Err(e) => warn!("Keeping the path of {}: {}", block_id, e),
+        }
+        self.ui.set_node_label(block_id, name)?;
+        self.ui.commit()?; // Commit label change
+        Ok(())
+    }
+
+    /// Leave without renaming
+    fn cancel(&mut self) -> Result<()> {
+        self.block_id = None;
+        self.ui.hide_text_entry()
+    }
+}
+
+impl Feature for RenameFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(_) => vec![ContextEntry::new(40, "rename", "Rename...")],
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, _option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        // Edit the name of the selected node with the knobs
+        let Some(crate::ui::Element::Node(node)) = element else {
+            return Ok(ControllerState::Navigating);
+        };
+        match self.start(node) {
+            Ok(()) => Ok(ControllerState::Renaming),
+            Err(e) => {
+                self.ui.show_message(&e.to_string())?;
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+
+    /// The name is edited in a text entry, not in a menu
+    fn get_menu(&self) -> Menu {
+        Menu {
+            id: "rename".to_string(),
+            label: "Rename".to_string(),
+            options: Vec::new(),
+        }
+    }
+
+    fn handle_menu_option(&mut self, _option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new rename feature
+pub fn new_rename_feature(engine: Arc<Engine>, ui: Arc<UI>) -> RenameFeature {
+    RenameFeature::new(engine, ui)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_entry() {
+        let mut entry = TextEntry::new("delay");
+
+        // Append " A" at the end
+        entry.scroll(KnobDirection::Forward);
+        entry.scroll(KnobDirection::Backward);
+        entry.move_cursor(KnobDirection::Forward);
+        entry.scroll(KnobDirection::Forward);
+        assert_eq!(entry.text(), "delay A");
+
+        // Edit the first character
+        for _ in 0..7 {
+            entry.move_cursor(KnobDirection::Backward);
+        }
+        for _ in 0..26 {
+            entry.scroll(KnobDirection::Backward);
+        }
+        assert_eq!(entry.text(), "Delay A");
+
+        // Scrolling wraps around the character set
+        let mut entry = TextEntry::new("");
+        entry.scroll(KnobDirection::Backward);
+        assert_eq!(entry.text(), ".");
+    }
+
+    #[test]
+    fn test_follow_name() {
+        assert_eq!(symbol_of_name("Tape Echo (2)").as_deref(), Some("tape_echo_2"));
+        assert_eq!(symbol_of_name("3 Band EQ").as_deref(), Some("_3_band_eq"));
+        assert_eq!(symbol_of_name("--"), None);
+
+        let engine = Engine::new_mock();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "tape_echo").unwrap();
+        assert_eq!(follow_name(&engine, "ingen:/main/delay", "Tape Echo").unwrap().as_deref(), Some("ingen:/main/tape_echo_2"));
+        assert_eq!(follow_name(&engine, "ingen:/main/tape_echo_2", "Tape echo").unwrap(), None);
+        assert!(engine.get_graph().unwrap().blocks.iter().any(|b| b.id == "ingen:/main/tape_echo_2"));
+    }
+}
@@ -0,0 +1,316 @@
+use anyhow::Result;
+use log::{debug, info, warn};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::controller::feature::persistence::{PersistenceFeature, saved_sessions};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// File of the set list in the session store, so that it travels with the sessions
+const SET_LIST_FILE: &str = "setlist.json";
+
+/// Menu state for the set list feature
+#[derive(Debug, Clone, PartialEq)]
+enum SetListMenuState {
+    SetListMenu,
+    ItemMenu(usize), // item index
+    AddSelection,
+}
+
+/// Set list feature: an ordered list of sessions played one after the other on stage
+pub struct SetListFeature {
+    ui: Arc<UI>,
+    settings: Arc<Mutex<Settings>>,
+    menu_state: SetListMenuState,
+    /// Session mnemonics in playing order
+    items: Vec<String>,
+    /// Index of the item playing, None before the first one
+    position: Option<usize>,
+    /// Session selected in the menu, waiting to be loaded
+    pending_load: Option<String>,
+}
+
+impl SetListFeature {
+    /// Create a new set list feature, with the set list of the session store
+    pub fn new(ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> Self {
+        let mut feature = Self {
+            ui,
+            settings,
+            menu_state: SetListMenuState::SetListMenu,
+            items: Vec::new(),
+            position: None,
+            pending_load: None,
+        };
+        match feature.load() {
+            Ok(items) => feature.items = items,
+            Err(e) => debug!("No set list loaded: {}", e),
+        }
+        feature
+    }
+
+    /// Get the set list file path
+    fn get_path(&self) -> Result<PathBuf> {
+        Ok(self.settings.lock().unwrap().store_dir()?.join(SET_LIST_FILE))
+    }
+
+    /// Read the set list file
+    fn load(&self) -> Result<Vec<String>> {
+        let content = fs::read_to_string(self.get_path()?)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Write the set list file
+    fn save(&self) -> Result<()> {
+        let path = self.get_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&self.items)?)?;
+        Ok(())
+    }
+
+    /// Check whether the set list has items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Move to the item at an index and return its session, None if there is no such item
+    pub fn jump(&mut self, index: usize) -> Option<String> {
+        let mnemonic = self.items.get(index)?.clone();
+        self.position = Some(index);
+        Some(mnemonic)
+    }
+
+    /// Move by a number of items and return the session to load, None at the ends of the list
+    pub fn step(&mut self, delta: isize) -> Option<String> {
+        let index = match self.position {
+            Some(position) => position.checked_add_signed(delta)?,
+            None if delta > 0 => 0,
+            None => return None,
+        };
+        self.jump(index)
+    }
+
+    /// Take the session selected in the menu
+    pub fn take_pending_load(&mut self) -> Option<String> {
+        self.pending_load.take()
+    }
+
+    /// Show the item following the one playing
+    pub fn show_up_next(&self) -> Result<()> {
+        let next = self.position.map_or(0, |position| position + 1);
+        let label = self.items.get(next).map(|mnemonic| format!("{} ({}/{})",
+            PersistenceFeature::format_mnemonic_display(mnemonic), next + 1, self.items.len()));
+        self.ui.set_up_next(label)
+    }
+
+    /// Get the set list menu with the items in order and the actions
+    fn get_set_list_menu(&self) -> Menu {
+        let mut options: Vec<MenuOption> = self.items.iter()
+            .enumerate()
+            .map(|(i, mnemonic)| MenuOption {
+                id: format!("item_{}", i),
+                label: format!("{}{}. {} >", if self.position == Some(i) { "▶ " } else { "" },
+                    i + 1, PersistenceFeature::format_mnemonic_display(mnemonic)),
+            })
+            .collect();
+        options.push(MenuOption {
+            id: "add".to_string(),
+            label: "Add Session >".to_string(),
+        });
+        if !self.items.is_empty() {
+            options.push(MenuOption {
+                id: "clear".to_string(),
+                label: "Clear".to_string(),
+            });
+        }
+        options.push(MenuOption {
+            id: "learn_next".to_string(),
+            label: "Learn Next Button".to_string(),
+        });
+        options.push(MenuOption {
+            id: "learn_previous".to_string(),
+            label: "Learn Previous Button".to_string(),
+        });
+
+        Menu {
+            id: "setlist_menu".to_string(),
+            label: "Set List".to_string(),
+            options,
+        }
+    }
+
+    /// Get the actions on an item
+    fn get_item_menu(&self, index: usize) -> Menu {
+        let mut options = vec![MenuOption {
+            id: "play".to_string(),
+            label: "Play Now".to_string(),
+        }];
+        if index > 0 {
+            options.push(MenuOption {
+                id: "move_up".to_string(),
+                label: "Move Up".to_string(),
+            });
+        }
+        options.push(MenuOption {
+            id: "remove".to_string(),
+            label: "Remove".to_string(),
+        });
+
+        Menu {
+            id: format!("setlist_item_{}", index),
+            label: self.items.get(index)
+                .map(|mnemonic| PersistenceFeature::format_mnemonic_display(mnemonic))
+                .unwrap_or_default(),
+            options,
+        }
+    }
+
+    /// Get the saved sessions to add to the set list
+    fn get_add_selection_menu(&self) -> Menu {
+        let sessions = self.settings.lock().unwrap().store_dir()
+            .and_then(|dir| saved_sessions(&dir))
+            .unwrap_or_else(|e| {
+                warn!("Failed to list the saved sessions: {}", e);
+                Vec::new()
+            });
+
+        Menu {
+            id: "setlist_add".to_string(),
+            label: "Add Session".to_string(),
+            options: sessions.iter()
+                .map(|mnemonic| MenuOption {
+                    id: mnemonic.clone(),
+                    label: PersistenceFeature::format_mnemonic_display(mnemonic),
+                })
+                .collect(),
+        }
+    }
+
+    /// Apply a change of the items and keep the position on the same item when possible
+    fn edit(&mut self, change: impl FnOnce(&mut Vec<String>)) -> Result<()> {
+        let playing = self.position.and_then(|position| self.items.get(position).cloned());
+        change(&mut self.items);
+        self.position = playing.and_then(|mnemonic| self.items.iter().position(|m| *m == mnemonic));
+        self.save()?;
+        self.show_up_next()
+    }
+}
+
+impl Feature for SetListFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) => vec![ContextEntry::new(105, "setlist", "Set List >")],
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        match &self.menu_state {
+            SetListMenuState::SetListMenu => self.get_set_list_menu(),
+            SetListMenuState::ItemMenu(index) => self.get_item_menu(*index),
+            SetListMenuState::AddSelection => self.get_add_selection_menu(),
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Set list feature handle_menu_option: {:?}", option_id);
+
+        // Handle menu closure - revert to the set list menu
+        let Some(option) = option_id else {
+            if self.menu_state == SetListMenuState::SetListMenu {
+                return Ok(ControllerState::Navigating);
+            }
+            self.menu_state = SetListMenuState::SetListMenu;
+            return Ok(ControllerState::BrowsingMenu);
+        };
+
+        match self.menu_state.clone() {
+            SetListMenuState::SetListMenu => {
+                if let Some(index) = option.strip_prefix("item_").and_then(|i| i.parse().ok()) {
+                    self.menu_state = SetListMenuState::ItemMenu(index);
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+                match option {
+                    "add" => {
+                        self.menu_state = SetListMenuState::AddSelection;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    "clear" => {
+                        self.edit(|items| items.clear())?;
+                        Ok(ControllerState::Navigating)
+                    }
+                    "learn_next" => {
+                        self.ui.show_message("Press the next button")?;
+                        Ok(ControllerState::LearningNextButton)
+                    }
+                    "learn_previous" => {
+                        self.ui.show_message("Press the previous button")?;
+                        Ok(ControllerState::LearningPreviousButton)
+                    }
+                    _ => Ok(ControllerState::Navigating),
+                }
+            }
+            SetListMenuState::ItemMenu(index) => {
+                self.menu_state = SetListMenuState::SetListMenu;
+                match option {
+                    "play" => {
+                        self.pending_load = self.jump(index);
+                        Ok(ControllerState::Navigating)
+                    }
+                    "move_up" => {
+                        self.edit(|items| items.swap(index - 1, index))?;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    "remove" => {
+                        self.edit(|items| { items.remove(index); })?;
+                        Ok(ControllerState::BrowsingMenu)
+                    }
+                    _ => Ok(ControllerState::BrowsingMenu),
+                }
+            }
+            SetListMenuState::AddSelection => {
+                // The same session can come back later in the set
+                let mnemonic = option.to_string();
+                info!("Adding {} to the set list", mnemonic);
+                self.edit(|items| items.push(mnemonic))?;
+                self.menu_state = SetListMenuState::SetListMenu;
+                Ok(ControllerState::BrowsingMenu)
+            }
+        }
+    }
+}
+
+/// Helper to create a new set list feature
+pub fn new_set_list_feature(ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> SetListFeature {
+    SetListFeature::new(ui, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_through_set_list() {
+        let mut set_list = SetListFeature {
+            ui: Arc::new(UI::new()),
+            settings: Arc::new(Mutex::new(Settings::default())),
+            menu_state: SetListMenuState::SetListMenu,
+            items: vec!["opening".to_string(), "ballad".to_string()],
+            position: None,
+            pending_load: None,
+        };
+
+        assert_eq!(set_list.step(-1), None);
+        assert_eq!(set_list.step(1).as_deref(), Some("opening"));
+        assert_eq!(set_list.step(1).as_deref(), Some("ballad"));
+        // The ends of the list stay on the last item
+        assert_eq!(set_list.step(1), None);
+        assert_eq!(set_list.position, Some(1));
+        assert_eq!(set_list.step(-1).as_deref(), Some("opening"));
+        assert_eq!(set_list.jump(5), None);
+    }
+}
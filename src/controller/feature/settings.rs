@@ -0,0 +1,505 @@
+use anyhow::Result;
+use log::{debug, warn};
+use std::sync::{Arc, Mutex};
+
+use crate::config::{Settings, THEMES};
+use crate::controller::{ControllerState, backup, feature::{ContextEntry, Feature}};
+use crate::controller::driver::Driver;
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Buffer sizes offered in the settings, in frames
+const BUFFER_SIZES: [u32; 7] = [32, 64, 128, 256, 512, 1024, 2048];
+
+/// Knob sensitivities offered in the settings
+const KNOB_SENSITIVITIES: [f32; 5] = [0.5, 1.0, 1.5, 2.0, 3.0];
+
+/// Knob acceleration curves offered in the settings (0 is off)
+const KNOB_ACCELERATIONS: [f32; 4] = [0.0, 1.0, 1.5, 2.0];
+
+/// UI scales offered in the settings
+const UI_SCALES: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+
+/// Autosave intervals offered in the settings, in minutes (0 is off)
+const AUTOSAVE_MINUTES: [u32; 6] = [0, 1, 5, 10, 15, 30];
+
+/// Numbers of saves kept per session offered in the settings (0 keeps all)
+const SAVES_KEPT: [u32; 5] = [0, 5, 10, 20, 50];
+
+/// Session transition fades offered in the settings, in milliseconds (0 is off)
+const TRANSITION_MS: [u32; 5] = [0, 100, 300, 500, 1000];
+
+/// Menu state for the settings feature
+#[derive(Debug, Clone, PartialEq)]
+enum SettingsMenuState {
+    SettingsMenu,
+    KnobSensitivitySelection,
+    KnobAccelerationSelection,
+    AutosaveSelection,
+    SavesKeptSelection,
+    BackupSelection,
+    TransitionSelection,
+    ThemeSelection,
+    ScaleSelection,
+    LanguageSelection,
+    BufferSizeSelection,
+}
+
+/// Get the label of a knob acceleration curve
+fn acceleration_label(curve: f32) -> String {
+    match curve {
+        0.0 => "Off".to_string(),
+        1.0 => "Linear".to_string(),
+        2.0 => "Quadratic".to_string(),
+        _ => format!("Curve {}", curve),
+    }
+}
+
+/// Settings feature for the user preferences and the audio server
+pub struct SettingsFeature {
+    driver: Arc<Driver>,
+    ui: Arc<UI>,
+    settings: Arc<Mutex<Settings>>,
+    menu_state: SettingsMenuState,
+}
+
+impl SettingsFeature {
+    /// Create a new settings feature
+    pub fn new(driver: Arc<Driver>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> Self {
+        Self {
+            driver,
+            ui,
+            settings,
+            menu_state: SettingsMenuState::SettingsMenu,
+        }
+    }
+
+    /// Get the settings menu
+    fn get_settings_menu(&self) -> Menu {
+        Menu {
+            id: "settings_menu".to_string(),
+            label: "Settings".to_string(),
+            options: vec![
+                MenuOption {
+                    id: "knob_sensitivity".to_string(),
+                    label: "Knob Sensitivity >".to_string(),
+                },
+                MenuOption {
+                    id: "knob_acceleration".to_string(),
+                    label: "Knob Acceleration >".to_string(),
+                },
+                MenuOption {
+                    id: "autosave".to_string(),
+                    label: "Autosave >".to_string(),
+                },
+                MenuOption {
+                    id: "saves_kept".to_string(),
+                    label: "Saves Kept >".to_string(),
+                },
+                MenuOption {
+                    id: "backup".to_string(),
+                    label: "Backup >".to_string(),
+                },
+                MenuOption {
+                    id: "transition".to_string(),
+                    label: "Transition >".to_string(),
+                },
+                MenuOption {
+                    id: "theme".to_string(),
+                    label: "Theme >".to_string(),
+                },
+                MenuOption {
+                    id: "ui_scale".to_string(),
+                    label: "UI Scale >".to_string(),
+                },
+                MenuOption {
+                    id: "language".to_string(),
+                    label: "Language >".to_string(),
+                },
+                MenuOption {
+                    id: "compact".to_string(),
+                    label: if self.settings.lock().unwrap().display().1 { "Standard Layout" } else { "Compact Layout" }.to_string(),
+                },
+                MenuOption {
+                    id: "speech".to_string(),
+                    label: if self.settings.lock().unwrap().speech_enabled() { "Stop Speaking" } else { "Speak Focus" }.to_string(),
+                },
+                MenuOption {
+                    id: "buffer_size".to_string(),
+                    label: "Buffer Size >".to_string(),
+                },
+                MenuOption {
+                    id: "log_panel".to_string(),
+                    label: if self.settings.lock().unwrap().log_panel { "Hide Log" } else { "Show Log" }.to_string(),
+                },
+                MenuOption {
+                    id: "analyzer".to_string(),
+                    label: if self.settings.lock().unwrap().analyzer { "Hide Analyzer" } else { "Show Analyzer" }.to_string(),
+                },
+                MenuOption {
+                    id: "inspector".to_string(),
+                    label: if self.settings.lock().unwrap().inspector { "Hide Inspector" } else { "Show Inspector" }.to_string(),
+                },
+                MenuOption {
+                    id: "safety_limiter".to_string(),
+                    label: if self.settings.lock().unwrap().safety_limiter { "Disable Safety Limiter" } else { "Enable Safety Limiter" }.to_string(),
+                },
+            ],
+        }
+    }
+
+    /// Build a menu of values with the current one checked
+    fn get_choice_menu<T: PartialEq>(id: &str, label: &str, values: &[T], current: &T, option: impl Fn(&T) -> (String, String)) -> Menu {
+        let options = values.iter()
+            .map(|value| {
+                let (id, label) = option(value);
+                let checked = if value == current { " ✓" } else { "" };
+                MenuOption { id, label: format!("{}{}", label, checked) }
+            })
+            .collect();
+
+        Menu {
+            id: id.to_string(),
+            label: label.to_string(),
+            options,
+        }
+    }
+
+    /// Change the settings and save them
+    fn update_settings(&self, update: impl FnOnce(&mut Settings)) {
+        let mut settings = self.settings.lock().unwrap();
+        update(&mut settings);
+        if let Err(e) = settings.save() {
+            warn!("Failed to save settings: {}", e);
+        }
+    }
+
+    /// Apply the UI scale and layout of the settings
+    fn apply_display(&self) -> Result<()> {
+        let (scale, compact) = self.settings.lock().unwrap().display();
+        self.ui.set_display(scale, compact)?;
+        self.ui.commit() // Redraw the grid at the new size
+    }
+
+    /// Get the backup menu, with the removable media found and the configured target checked
+    fn get_backup_menu(&self) -> Menu {
+        let current = self.settings.lock().unwrap().backup_target.clone();
+        let mut targets: Vec<String> = backup::removable_backup_dirs().iter()
+            .map(|dir| dir.display().to_string())
+            .collect();
+        // A target set in the settings file stays selectable even when not mounted
+        if let Some(target) = &current {
+            if !targets.contains(target) {
+                targets.insert(0, target.clone());
+            }
+        }
+
+        let mut options = vec![MenuOption {
+            id: "off".to_string(),
+            label: format!("Off{}", if current.is_none() { " ✓" } else { "" }),
+        }];
+        options.extend(targets.into_iter().map(|target| MenuOption {
+            label: format!("{}{}", target, if current.as_ref() == Some(&target) { " ✓" } else { "" }),
+            id: format!("target_{}", target),
+        }));
+
+        Menu {
+            id: "settings_backup".to_string(),
+            label: "Backup".to_string(),
+            options,
+        }
+    }
+
+    /// Get the buffer size menu, with the latency of each size and the current one checked
+    fn get_buffer_size_menu(&self) -> Result<Menu> {
+        let status = self.driver.get_audio_status()?;
+
+        let options = BUFFER_SIZES.iter()
+            .map(|&frames| {
+                let latency_ms = frames as f32 * 1000.0 / status.sample_rate as f32;
+                let current = if frames == status.buffer_size { " ✓" } else { "" };
+                MenuOption {
+                    id: format!("frames_{}", frames),
+                    label: format!("{} frames ({:.1} ms){}", frames, latency_ms, current),
+                }
+            })
+            .collect();
+
+        Ok(Menu {
+            id: "settings_buffer_size".to_string(),
+            label: "Buffer Size".to_string(),
+            options,
+        })
+    }
+}
+
+impl Feature for SettingsFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Link(..) => vec![ContextEntry::new(80, "settings", "Settings >")],
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        let settings = self.settings.lock().unwrap().clone();
+        match &self.menu_state {
+            SettingsMenuState::SettingsMenu => self.get_settings_menu(),
+            SettingsMenuState::KnobSensitivitySelection => Self::get_choice_menu(
+                "settings_knob_sensitivity", "Knob Sensitivity",
+                &KNOB_SENSITIVITIES, &settings.knob_sensitivity,
+                |value| (format!("sensitivity_{}", value), format!("×{}", value)),
+            ),
+            SettingsMenuState::KnobAccelerationSelection => Self::get_choice_menu(
+                "settings_knob_acceleration", "Knob Acceleration",
+                &KNOB_ACCELERATIONS, &settings.knob_acceleration,
+                |&curve| (format!("acceleration_{}", curve), acceleration_label(curve)),
+            ),
+            SettingsMenuState::AutosaveSelection => Self::get_choice_menu(
+                "settings_autosave", "Autosave",
+                &AUTOSAVE_MINUTES, &settings.autosave_minutes,
+                |&minutes| (
+                    format!("autosave_{}", minutes),
+                    if minutes == 0 { "Off".to_string() } else { format!("Every {} min", minutes) },
+                ),
+            ),
+            SettingsMenuState::SavesKeptSelection => Self::get_choice_menu(
+                "settings_saves_kept", "Saves Kept",
+                &SAVES_KEPT, &settings.saves_kept,
+                |&kept| (
+                    format!("kept_{}", kept),
+                    if kept == 0 { "All".to_string() } else { format!("Last {}", kept) },
+                ),
+            ),
+            SettingsMenuState::BackupSelection => self.get_backup_menu(),
+            SettingsMenuState::TransitionSelection => Self::get_choice_menu(
+                "settings_transition", "Transition",
+                &TRANSITION_MS, &settings.transition_ms,
+                |&ms| (
+                    format!("transition_{}", ms),
+                    if ms == 0 { "Off".to_string() } else { format!("{} ms", ms) },
+                ),
+            ),
+            SettingsMenuState::ThemeSelection => Self::get_choice_menu(
+                "settings_theme", "Theme",
+                &THEMES, &settings.theme.as_str(),
+                |theme| (format!("theme_{}", theme), theme[..1].to_uppercase() + &theme[1..]),
+            ),
+            SettingsMenuState::ScaleSelection => Self::get_choice_menu(
+                "settings_ui_scale", "UI Scale",
+                &UI_SCALES, &settings.display().0,
+                |scale| (format!("scale_{}", scale), format!("{}%", scale * 100.0)),
+            ),
+            SettingsMenuState::LanguageSelection => {
+                // Empty follows the system language, English needs no locale file
+                let mut languages = vec![String::new(), "en".to_string()];
+                languages.extend(crate::ui::i18n::available_languages().into_iter().filter(|language| language != "en"));
+                Self::get_choice_menu(
+                    "settings_language", "Language",
+                    &languages, &settings.language,
+                    |language| (format!("language_{}", language), match language.as_str() {
+                        "" => "System".to_string(),
+                        "en" => "English".to_string(),
+                        _ => language.clone(),
+                    }),
+                )
+            }
+            SettingsMenuState::BufferSizeSelection => {
+                self.get_buffer_size_menu().unwrap_or_else(|e| {
+                    debug!("Error getting buffer size menu: {}", e);
+                    self.get_settings_menu()
+                })
+            }
+        }
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Settings feature handle_menu_option: {:?}", option_id);
+
+        // Handle menu closure - revert to previous menu state
+        let Some(option) = option_id else {
+            debug!("Settings feature: menu closed");
+            match self.menu_state {
+                SettingsMenuState::SettingsMenu => return Ok(ControllerState::Navigating),
+                _ => {
+                    self.menu_state = SettingsMenuState::SettingsMenu;
+                    return Ok(ControllerState::BrowsingMenu);
+                }
+            }
+        };
+
+        match &self.menu_state {
+            SettingsMenuState::SettingsMenu => {
+                self.menu_state = match option {
+                    "knob_sensitivity" => SettingsMenuState::KnobSensitivitySelection,
+                    "knob_acceleration" => SettingsMenuState::KnobAccelerationSelection,
+                    "autosave" => SettingsMenuState::AutosaveSelection,
+                    "saves_kept" => SettingsMenuState::SavesKeptSelection,
+                    "backup" => SettingsMenuState::BackupSelection,
+                    "transition" => SettingsMenuState::TransitionSelection,
+                    "theme" => SettingsMenuState::ThemeSelection,
+                    "ui_scale" => SettingsMenuState::ScaleSelection,
+                    "language" => SettingsMenuState::LanguageSelection,
+                    "compact" => {
+                        // The menu choice replaces the command line one
+                        self.update_settings(|settings| {
+                            settings.compact = !settings.display().1;
+                            settings.compact_override = false;
+                        });
+                        self.apply_display()?;
+                        return Ok(ControllerState::Navigating);
+                    }
+                    "speech" => {
+                        // The menu choice replaces the command line one
+                        let mut enabled = false;
+                        self.update_settings(|settings| {
+                            settings.speech = !settings.speech_enabled();
+                            settings.speech_override = false;
+                            enabled = settings.speech;
+                        });
+                        self.ui.set_speech(enabled);
+                        return Ok(ControllerState::Navigating);
+                    }
+                    "buffer_size" => SettingsMenuState::BufferSizeSelection,
+                    "log_panel" => {
+                        let mut visible = false;
+                        self.update_settings(|settings| {
+                            settings.log_panel = !settings.log_panel;
+                            visible = settings.log_panel;
+                        });
+                        self.ui.set_log_panel(visible)?;
+                        return Ok(ControllerState::Navigating);
+                    }
+                    "analyzer" => {
+                        // The controller starts or stops the analyzer with the setting
+                        self.update_settings(|settings| settings.analyzer = !settings.analyzer);
+                        return Ok(ControllerState::Navigating);
+                    }
+                    "inspector" => {
+                        // The controller shows the inspector with the setting
+                        self.update_settings(|settings| settings.inspector = !settings.inspector);
+                        return Ok(ControllerState::Navigating);
+                    }
+                    "safety_limiter" => {
+                        // The controller inserts or removes the limiters with the setting
+                        self.update_settings(|settings| settings.safety_limiter = !settings.safety_limiter);
+                        return Ok(ControllerState::Navigating);
+                    }
+                    _ => return Ok(ControllerState::Navigating),
+                };
+                Ok(ControllerState::BrowsingMenu)
+            }
+            SettingsMenuState::KnobSensitivitySelection => {
+                if let Some(sensitivity) = option.strip_prefix("sensitivity_").and_then(|s| s.parse::<f32>().ok()) {
+                    self.update_settings(|settings| settings.knob_sensitivity = sensitivity);
+                    self.ui.show_message(&format!("Knob sensitivity: ×{}", sensitivity))?;
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::KnobAccelerationSelection => {
+                if let Some(curve) = option.strip_prefix("acceleration_").and_then(|c| c.parse::<f32>().ok()) {
+                    self.update_settings(|settings| settings.knob_acceleration = curve);
+                    self.ui.show_message(&format!("Knob acceleration: {}", acceleration_label(curve)))?;
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::AutosaveSelection => {
+                if let Some(minutes) = option.strip_prefix("autosave_").and_then(|m| m.parse::<u32>().ok()) {
+                    self.update_settings(|settings| settings.autosave_minutes = minutes);
+                    if minutes == 0 {
+                        self.ui.show_message("Autosave off")?;
+                    } else {
+                        self.ui.show_message(&format!("Autosave every {} min", minutes))?;
+                    }
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::SavesKeptSelection => {
+                if let Some(kept) = option.strip_prefix("kept_").and_then(|k| k.parse::<u32>().ok()) {
+                    self.update_settings(|settings| settings.saves_kept = kept);
+                    if kept == 0 {
+                        self.ui.show_message("Keeping all saves")?;
+                    } else {
+                        self.ui.show_message(&format!("Keeping the last {} saves", kept))?;
+                    }
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::BackupSelection => {
+                let target = option.strip_prefix("target_").map(str::to_string);
+                match &target {
+                    Some(target) => self.ui.show_message(&format!("Backing up to {}", target))?,
+                    None => self.ui.show_message("Backup off")?,
+                }
+                self.update_settings(|settings| settings.backup_target = target.clone());
+                // Bring the new target up to date right away
+                if let Some(target) = target {
+                    match self.settings.lock().unwrap().store_dir() {
+                        Ok(store_dir) => backup::spawn_backup(store_dir, target),
+                        Err(e) => warn!("No store directory to back up: {}", e),
+                    }
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::TransitionSelection => {
+                if let Some(ms) = option.strip_prefix("transition_").and_then(|m| m.parse::<u32>().ok()) {
+                    self.update_settings(|settings| settings.transition_ms = ms);
+                    if ms == 0 {
+                        self.ui.show_message("Transition off")?;
+                    } else {
+                        self.ui.show_message(&format!("Transition: {} ms", ms))?;
+                    }
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::ThemeSelection => {
+                if let Some(theme) = option.strip_prefix("theme_") {
+                    self.update_settings(|settings| settings.theme = theme.to_string());
+                    self.ui.set_theme(theme.to_string())?;
+                    self.ui.commit()?; // Recolor the grid
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::ScaleSelection => {
+                if let Some(scale) = option.strip_prefix("scale_").and_then(|s| s.parse::<f32>().ok()) {
+                    self.update_settings(|settings| {
+                        settings.ui_scale = scale;
+                        settings.ui_scale_override = None;
+                    });
+                    self.apply_display()?;
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::LanguageSelection => {
+                if let Some(language) = option.strip_prefix("language_") {
+                    self.update_settings(|settings| settings.language = language.to_string());
+                    self.ui.set_language(crate::ui::i18n::language(language).as_deref());
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+            SettingsMenuState::BufferSizeSelection => {
+                if let Some(frames) = option.strip_prefix("frames_").and_then(|f| f.parse::<u32>().ok()) {
+                    match self.driver.set_buffer_size(frames) {
+                        Ok(()) => self.ui.show_message(&format!("Buffer size: {} frames", frames))?,
+                        Err(e) => self.ui.show_message(&e.to_string())?,
+                    }
+                }
+                self.menu_state = SettingsMenuState::SettingsMenu;
+                Ok(ControllerState::Navigating)
+            }
+        }
+    }
+}
+
+/// Helper to create a new settings feature
+pub fn new_settings_feature(driver: Arc<Driver>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> SettingsFeature {
+    SettingsFeature::new(driver, ui, settings)
+}
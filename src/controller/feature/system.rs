@@ -1,11 +1,12 @@
 use anyhow::Result;
 use log::{debug, warn};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
+use crate::config::Settings;
 use crate::controller::driver::{Driver, PortType};
-use crate::controller::{ControllerState, feature::Feature};
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
 use crate::engine::Engine;
 use crate::ui::{Menu, MenuOption, UI, NodeType};
 
@@ -22,24 +23,30 @@ enum SystemMenuState {
     PortTypeSelection,
     EndpointList(PortType), // Contains the selected port type (source/sink list)
     PortList(PortType, String), // Contains port type and endpoint name
+    SynthSelection(String, String), // Endpoint name and MIDI port name, for performer input
 }
 
+/// Option id of the synth selection that only adds the MIDI port
+const PORT_ONLY_OPTION: &str = "port_only";
+
 /// System feature for managing audio/MIDI inputs or outputs
 pub struct SystemFeature {
     driver: Arc<Driver>,
     engine: Arc<Engine>,
     ui: Arc<UI>,
+    settings: Arc<Mutex<Settings>>,
     menu_state: SystemMenuState,
     direction: SystemDirection,
 }
 
 impl SystemFeature {
     /// Create a new system feature with specified direction
-    pub fn new(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, direction: SystemDirection) -> Self {
+    pub fn new(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>, direction: SystemDirection) -> Self {
         Self {
             driver,
             engine,
             ui,
+            settings,
             menu_state: SystemMenuState::PortTypeSelection,
             direction
         }
@@ -130,36 +137,35 @@ impl SystemFeature {
 
     /// Get the port selection menu for a specific endpoint
     fn get_port_menu(&self, port_type: PortType, endpoint_name: &str) -> Result<Menu> {
-        let options: Vec<MenuOption> = match self.direction {
-            SystemDirection::Input => {
-                let endpoints = self.driver.get_sources(port_type)?;
-                let endpoint = endpoints.iter()
-                    .find(|e| e.name == endpoint_name)
-                    .ok_or_else(|| anyhow::anyhow!("{} not found: {}", 
-                        self.endpoint_type_name_cap(), endpoint_name))?;
+        let ports = match self.direction {
+            SystemDirection::Input => self.driver.get_sources(port_type)?
+                .into_iter()
+                .find(|e| e.name == endpoint_name)
+                .map(|e| e.ports),
+            SystemDirection::Output => self.driver.get_sinks(port_type)?
+                .into_iter()
+                .find(|e| e.name == endpoint_name)
+                .map(|e| e.ports),
+        }.ok_or_else(|| anyhow::anyhow!("{} not found: {}", 
+            self.endpoint_type_name_cap(), endpoint_name))?;
 
-                endpoint.ports.iter().map(|port| {
-                    MenuOption {
-                        id: format!("port_{}", port.name),
-                        label: port.short_name.clone(),
-                    }
-                }).collect()
-            }
-            SystemDirection::Output => {
-                let endpoints = self.driver.get_sinks(port_type)?;
-                let endpoint = endpoints.iter()
-                    .find(|e| e.name == endpoint_name)
-                    .ok_or_else(|| anyhow::anyhow!("{} not found: {}", 
-                        self.endpoint_type_name_cap(), endpoint_name))?;
+        // Offer audio L/R pairs as a single entry before their left port
+        let names: Vec<&str> = ports.iter().map(|port| port.name.as_str()).collect();
+        let pairs = if port_type == PortType::Audio { stereo_pairs(&names) } else { Vec::new() };
 
-                endpoint.ports.iter().map(|port| {
-                    MenuOption {
-                        id: format!("port_{}", port.name),
-                        label: port.short_name.clone(),
-                    }
-                }).collect()
+        let mut options = Vec::new();
+        for (index, port) in ports.iter().enumerate() {
+            if let Some((_, right)) = pairs.iter().find(|(left, _)| *left == index) {
+                options.push(MenuOption {
+                    id: format!("pair_{}|{}", port.name, ports[*right].name),
+                    label: format!("Stereo Pair: {} + {}", port.short_name, ports[*right].short_name),
+                });
             }
-        };
+            options.push(MenuOption {
+                id: format!("port_{}", port.name),
+                label: port.short_name.clone(),
+            });
+        }
 
         Ok(Menu {
             id: format!("{}_ports_{}", self.direction_name(), endpoint_name),
@@ -167,9 +173,262 @@ impl SystemFeature {
             options,
         })
     }
+
+    /// Get the blocks with a MIDI input, which can be played from a keyboard, as (block path, MIDI input symbol)
+    fn synth_blocks(&self) -> Result<Vec<(String, String)>> {
+        Ok(self.engine.get_graph()?.blocks.into_iter()
+            .filter(|b| !crate::controller::feature::mixer::is_hidden_block(&b.id))
+            .filter_map(|b| {
+                let input = b.ports.iter().find(|p| p.direction == crate::engine::PortDirection::Input
+                    && p.port_type == crate::engine::PortType::Midi)?;
+                Some((b.id.clone(), input.id.clone()))
+            })
+            .collect())
+    }
+
+    /// Get the menu to connect a new MIDI input to a synth block
+    fn get_synth_menu(&self, port_name: &str) -> Result<Menu> {
+        let mut options: Vec<MenuOption> = self.synth_blocks()?.into_iter()
+            .map(|(block_path, _)| MenuOption {
+                id: format!("synth_{}", block_path),
+                label: block_path.rsplit('/').next().unwrap_or(&block_path).to_string(),
+            })
+            .collect();
+        options.push(MenuOption {
+            id: PORT_ONLY_OPTION.to_string(),
+            label: "Port Only".to_string(),
+        });
+
+        Ok(Menu {
+            id: "input_synths".to_string(),
+            label: format!("Play {} On", port_name.split(':').next_back().unwrap_or(port_name)),
+            options,
+        })
+    }
+
+    /// Connect a MIDI system port to the MIDI input of a synth block, in the engine and in the UI
+    fn connect_to_synth(&self, port_path: &str, block_path: &str) -> Result<()> {
+        let (_, input) = self.synth_blocks()?.into_iter()
+            .find(|(path, _)| path == block_path)
+            .ok_or_else(|| anyhow::anyhow!("No MIDI input on {}", block_path))?;
+        self.engine.connect(port_path, &format!("{}/{}", block_path, input))?;
+        self.ui.create_link(port_path.to_string(), block_path.to_string(), crate::ui::LinkType::Normal)?;
+        self.ui.commit()
+    }
+
+    /// Create an engine port for a JACK port, connect it and insert its node in the UI
+    /// Returns the engine path of the port
+    fn add_port(&self, port_name: &str, port_type: PortType, element: Option<&crate::ui::Element>) -> Result<String> {
+        // Sanitize the port name
+        let sanitized_name = Driver::sanitize_port_name(port_name);
+        debug!("Sanitized port name: {}", sanitized_name);
+        
+        // Convert PortType from driver to engine
+        let engine_port_type = match port_type {
+            PortType::Audio => crate::engine::PortType::Audio,
+            PortType::Midi => crate::engine::PortType::Midi,
+            PortType::All => crate::engine::PortType::Audio, // Default to Audio if All
+        };
+        
+        // Create port in engine (input or output based on direction)
+        let port_path = match self.direction {
+            SystemDirection::Input => {
+                self.engine.create_input_port(&sanitized_name, engine_port_type)?
+            }
+            SystemDirection::Output => {
+                self.engine.create_output_port(&sanitized_name, engine_port_type)?
+            }
+        };
+        
+        debug!("Created {} port at path: {}", self.direction_name(), port_path);
+        
+        // Give audio inputs a trim to tame hot signals before the chain
+        if self.direction == SystemDirection::Input && port_type == PortType::Audio {
+            if let Err(e) = crate::controller::feature::mixer::create_trim(&self.engine, &port_path) {
+                warn!("No trim for {}: {}", port_path, e);
+            }
+        }
+        
+        // Protect audio outputs with the safety limiter before they are connected
+        if self.direction == SystemDirection::Output && port_type == PortType::Audio {
+            if let Err(e) = crate::controller::feature::mixer::apply_safety_limiter(&self.engine, &self.settings) {
+                warn!("No safety limiter for {}: {}", port_path, e);
+            }
+        }
+        
+        // Set up JACK ports for connection based on direction
+        let (source_port, destination_port) = match self.direction {
+            SystemDirection::Input => {
+                // Input: connect FROM external source TO engine
+                (
+                    crate::controller::driver::Port {
+                        name: port_name.to_string(),
+                        short_name: port_name.split(':').next_back().unwrap_or(port_name).to_string(),
+                    },
+                    crate::controller::driver::Port {
+                        name: format!("TraxDub Engine:{}", sanitized_name),
+                        short_name: sanitized_name.clone(),
+                    }
+                )
+            }
+            SystemDirection::Output => {
+                // Output: connect FROM engine TO external destination
+                (
+                    crate::controller::driver::Port {
+                        name: format!("TraxDub Engine:{}", sanitized_name),
+                        short_name: sanitized_name.clone(),
+                    },
+                    crate::controller::driver::Port {
+                        name: port_name.to_string(),
+                        short_name: port_name.split(':').next_back().unwrap_or(port_name).to_string(),
+                    }
+                )
+            }
+        };
+        
+        // Retry connection as the engine port is created asynchronously
+        let max_duration = Duration::from_millis(1000);
+        let retry_interval = Duration::from_millis(50);
+        let start_time = std::time::Instant::now();
+        let mut connected = false;
+        
+        while start_time.elapsed() < max_duration {
+            match self.driver.connect_ports(&source_port, &destination_port) {
+                Ok(_) => {
+                    connected = true;
+                    break;
+                }
+                Err(e) => {
+                    warn!("Connection attempt failed ({}ms elapsed): {}", 
+                          start_time.elapsed().as_millis(), e);
+                    thread::sleep(retry_interval);
+                }
+            }
+        }
+        
+        if !connected {
+            return Err(anyhow::anyhow!(
+                "Failed to connect ports after {} retries over {}ms",
+                max_duration.as_millis() / retry_interval.as_millis(),
+                max_duration.as_millis()
+            ));
+        }
+        
+        debug!("Successfully created and connected {} port: {}", 
+               self.direction_name(), port_path);
+        
+        let node_type = match self.direction {
+            SystemDirection::Input => NodeType::PortIn,
+            SystemDirection::Output => NodeType::PortOut,
+        };
+
+        // Insert port node in UI, using link from/to if available
+        // If the selected link type is PortIn or PortOut, use "inputs" and "outputs"
+        // to avoid chaining PortIn nodes or PortOut nodes
+        let (link_from, link_to) = if let Some(crate::ui::Element::Link(from, to, link_type)) = element {
+            // If link type is PortIn or PortOut, use inputs/outputs to avoid chaining
+            if matches!(link_type, crate::ui::LinkType::PortIn | crate::ui::LinkType::PortOut) {
+                ("inputs".to_string(), "outputs".to_string())
+            } else {
+                (from.clone(), to.clone())
+            }
+        } else {
+            ("inputs".to_string(), "outputs".to_string())
+        };
+
+        self.ui.insert_node(
+            port_path.clone(),
+            port_name.split(':').next_back().unwrap_or(port_name).to_string(),
+            node_type,
+            link_from.clone(),
+            link_to.clone(),
+        )?;
+        self.ui.commit()?; // Commit system port insertion
+        
+        // Create connections in the engine (skip "inputs" and "outputs" context nodes)
+        if link_from != "inputs" {
+            debug!("Creating engine connection: {} -> {}", link_from, port_path);
+            self.engine.connect(&link_from, &port_path)?;
+        }
+        if link_to != "outputs" {
+            let source = crate::controller::feature::mixer::source_port(&self.engine, &port_path);
+            debug!("Creating engine connection: {} -> {}", source, link_to);
+            self.engine.connect(&source, &link_to)?;
+        }
+        
+        // The chains now reaching an audio output get the faders of the mixer
+        if self.direction == SystemDirection::Output && port_type == PortType::Audio {
+            if let Err(e) = crate::controller::feature::mixer::ensure_faders(&self.engine, &self.ui) {
+                warn!("Could not insert the faders before {}: {}", port_path, e);
+            }
+        }
+        Ok(port_path)
+    }
+
+    /// Remove a system port: disconnect it in JACK, delete it in the engine
+    /// and remove its node and links from the UI
+    pub fn remove_port(&self, port_path: &str) -> Result<()> {
+        let sanitized_name = port_path.rsplit('/').next().unwrap_or(port_path);
+        let jack_port = crate::controller::driver::Port {
+            name: format!("TraxDub Engine:{}", sanitized_name),
+            short_name: sanitized_name.to_string(),
+        };
+
+        // The engine port may already be gone in JACK, removal goes on anyway
+        if let Err(e) = self.driver.disconnect_all(&jack_port) {
+            warn!("Could not disconnect {}: {}", jack_port.name, e);
+        }
+
+        // The trim of an input or the safety limiter of an output goes with the port
+        let graph = self.engine.get_graph()?;
+        for helper in [crate::controller::feature::mixer::trim_block(port_path), crate::controller::feature::mixer::limiter_block(port_path)] {
+            if graph.blocks.iter().any(|b| b.id == helper) {
+                self.engine.delete(&helper)?;
+            }
+        }
+        self.engine.delete(port_path)?;
+        self.ui.remove_node(port_path.to_string())?;
+        self.ui.commit()?;
+
+        debug!("Removed system port: {}", port_path);
+        Ok(())
+    }
 }
 
 impl Feature for SystemFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match (element, &self.direction) {
+            (crate::ui::Element::Link(from, _, _), SystemDirection::Input) if from == "inputs" => {
+                vec![ContextEntry::new(10, "add_input", "Add Input >")]
+            }
+            (crate::ui::Element::Link(_, to, _), SystemDirection::Output) if to == "outputs" => {
+                vec![ContextEntry::new(20, "add_output", "Add Output >")]
+            }
+            // System ports of both directions are removed through the input feature
+            (crate::ui::Element::Node(node), SystemDirection::Input) => {
+                let is_port = self.engine.get_graph()
+                    .map(|graph| graph.ports.iter().any(|port| &port.id == node))
+                    .unwrap_or(false);
+                if is_port {
+                    vec![ContextEntry::new(110, "remove_port", "Remove")]
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn select_context_entry(&mut self, option_id: &str, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        let Some(crate::ui::Element::Node(node)) = element.filter(|_| option_id == "remove_port") else {
+            return Ok(ControllerState::BrowsingMenu);
+        };
+        if let Err(e) = self.remove_port(node) {
+            self.ui.show_message(&e.to_string())?;
+        }
+        Ok(ControllerState::Navigating)
+    }
+
     fn get_menu(&self) -> Menu {
         match &self.menu_state {
             SystemMenuState::PortTypeSelection => self.get_port_type_menu(),
@@ -185,9 +444,19 @@ impl Feature for SystemFeature {
                     self.get_port_type_menu()
                 })
             }
+            SystemMenuState::SynthSelection(_, port_name) => {
+                self.get_synth_menu(port_name).unwrap_or_else(|e| {
+                    debug!("Error getting synth menu: {}", e);
+                    self.get_port_type_menu()
+                })
+            }
         }
     }
 
+    fn lists_jack_ports(&self) -> bool {
+        self.menu_state != SystemMenuState::PortTypeSelection
+    }
+
     fn handle_menu_option(&mut self, option_id: Option<&str>, element: Option<&crate::ui::Element>) -> Result<ControllerState> {
         debug!("{} feature option {} called with element: {:?}", self.direction_name_cap(), option_id.unwrap_or("None"), element);
         
@@ -207,6 +476,10 @@ impl Feature for SystemFeature {
                     self.menu_state = SystemMenuState::EndpointList(*port_type);
                     return Ok(ControllerState::BrowsingMenu);
                 }
+                SystemMenuState::SynthSelection(endpoint_name, _) => {
+                    self.menu_state = SystemMenuState::PortList(PortType::Midi, endpoint_name.clone());
+                    return Ok(ControllerState::BrowsingMenu);
+                }
             }
         };
 
@@ -236,6 +509,14 @@ impl Feature for SystemFeature {
                 }
             }
             SystemMenuState::PortList(port_type, endpoint_name) => {
+                let port_type = *port_type;
+                // A performer's MIDI input can go straight to a synth block
+                let offer_synths = self.direction == SystemDirection::Input && port_type == PortType::Midi
+                    && !self.synth_blocks()?.is_empty();
+                if let Some(port_name) = option_id.strip_prefix("port_").filter(|_| offer_synths) {
+                    self.menu_state = SystemMenuState::SynthSelection(endpoint_name.clone(), port_name.to_string());
+                    return Ok(ControllerState::BrowsingMenu);
+                }
                 if let Some(port_name) = option_id.strip_prefix("port_") {
                     debug!("Selected {} port: {} from {}: {}", 
                            match port_type {
@@ -245,146 +526,59 @@ impl Feature for SystemFeature {
                            },
                            port_name, self.endpoint_type_name(), endpoint_name);
                     
-                    // Sanitize the port name
-                    let sanitized_name = Driver::sanitize_port_name(port_name);
-                    debug!("Sanitized port name: {}", sanitized_name);
-                    
-                    // Convert PortType from driver to engine
-                    let engine_port_type = match port_type {
-                        PortType::Audio => crate::engine::PortType::Audio,
-                        PortType::Midi => crate::engine::PortType::Midi,
-                        PortType::All => crate::engine::PortType::Audio, // Default to Audio if All
-                    };
-                    
-                    // Create port in engine (input or output based on direction)
-                    let port_path = match self.direction {
-                        SystemDirection::Input => {
-                            self.engine.create_input_port(&sanitized_name, engine_port_type)?
-                        }
-                        SystemDirection::Output => {
-                            self.engine.create_output_port(&sanitized_name, engine_port_type)?
-                        }
-                    };
-                    
-                    debug!("Created {} port at path: {}", self.direction_name(), port_path);
-                    
-                    // Set up JACK ports for connection based on direction
-                    let (source_port, destination_port) = match self.direction {
-                        SystemDirection::Input => {
-                            // Input: connect FROM external source TO engine
-                            (
-                                crate::controller::driver::Port {
-                                    name: port_name.to_string(),
-                                    short_name: port_name.split(':').last().unwrap_or(port_name).to_string(),
-                                },
-                                crate::controller::driver::Port {
-                                    name: format!("TraxDub Engine:{}", sanitized_name),
-                                    short_name: sanitized_name.clone(),
-                                }
-                            )
-                        }
-                        SystemDirection::Output => {
-                            // Output: connect FROM engine TO external destination
-                            (
-                                crate::controller::driver::Port {
-                                    name: format!("TraxDub Engine:{}", sanitized_name),
-                                    short_name: sanitized_name.clone(),
-                                },
-                                crate::controller::driver::Port {
-                                    name: port_name.to_string(),
-                                    short_name: port_name.split(':').last().unwrap_or(port_name).to_string(),
-                                }
-                            )
-                        }
-                    };
-                    
-                    // Retry connection as the engine port is created asynchronously
-                    let max_duration = Duration::from_millis(1000);
-                    let retry_interval = Duration::from_millis(50);
-                    let start_time = std::time::Instant::now();
-                    let mut connected = false;
-                    
-                    while start_time.elapsed() < max_duration {
-                        match self.driver.connect_ports(&source_port, &destination_port) {
-                            Ok(_) => {
-                                connected = true;
-                                break;
-                            }
-                            Err(e) => {
-                                warn!("Connection attempt failed ({}ms elapsed): {}", 
-                                      start_time.elapsed().as_millis(), e);
-                                thread::sleep(retry_interval);
-                            }
-                        }
-                    }
+                    self.add_port(port_name, port_type, element)?;
+                } else if let Some((left, right)) = option_id.strip_prefix("pair_").and_then(|pair| pair.split_once('|')) {
+                    debug!("Selected stereo pair {} + {} from {}: {}", left, right, self.endpoint_type_name(), endpoint_name);
                     
-                    if !connected {
-                        return Err(anyhow::anyhow!(
-                            "Failed to connect ports after {} retries over {}ms",
-                            max_duration.as_millis() / retry_interval.as_millis(),
-                            max_duration.as_millis()
-                        ));
-                    }
-                    
-                    debug!("Successfully created and connected {} port: {}", 
-                           self.direction_name(), port_path);
-                    
-                    let port_type = match self.direction {
-                        SystemDirection::Input => NodeType::PortIn,
-                        SystemDirection::Output => NodeType::PortOut,
-                    };
-
-                    // Insert port node in UI, using link from/to if available
-                    // If the selected link type is PortIn or PortOut, use "inputs" and "outputs"
-                    // to avoid chaining PortIn nodes or PortOut nodes
-                    let (link_from, link_to) = if let Some(crate::ui::Element::Link(from, to, link_type)) = &element {
-                        // If link type is PortIn or PortOut, use inputs/outputs to avoid chaining
-                        if matches!(link_type, crate::ui::LinkType::PortIn | crate::ui::LinkType::PortOut) {
-                            ("inputs".to_string(), "outputs".to_string())
-                        } else {
-                            (from.clone(), to.clone())
-                        }
-                    } else {
-                        ("inputs".to_string(), "outputs".to_string())
-                    };
-
-                    self.ui.insert_node(
-                        port_path.clone(),
-                        port_name.split(':').last().unwrap_or(port_name).to_string(),
-                        port_type,
-                        link_from.clone(),
-                        link_to.clone(),
-                    )?;
-                    self.ui.commit()?; // Commit system port insertion
-                    
-                    // Create connections in the engine (skip "inputs" and "outputs" context nodes)
-                    if link_from != "inputs" {
-                        debug!("Creating engine connection: {} -> {}", link_from, port_path);
-                        self.engine.connect(&link_from, &port_path)?;
-                    }
-                    if link_to != "outputs" {
-                        debug!("Creating engine connection: {} -> {}", port_path, link_to);
-                        self.engine.connect(&port_path, &link_to)?;
-                    }
-                    
-                    self.menu_state = SystemMenuState::PortTypeSelection;
-                    Ok(ControllerState::Navigating)
-                } else {
-                    self.menu_state = SystemMenuState::PortTypeSelection;
-                    Ok(ControllerState::Navigating)
+                    self.add_port(left, port_type, element)?;
+                    self.add_port(right, port_type, element)?;
                 }
+                self.menu_state = SystemMenuState::PortTypeSelection;
+                Ok(ControllerState::Navigating)
+            }
+            SystemMenuState::SynthSelection(_, port_name) => {
+                let port_name = port_name.clone();
+                self.menu_state = SystemMenuState::PortTypeSelection;
+                let port_path = self.add_port(&port_name, PortType::Midi, element)?;
+                if let Some(block_path) = option_id.strip_prefix("synth_") {
+                    self.connect_to_synth(&port_path, block_path)?;
+                }
+                Ok(ControllerState::Navigating)
             }
         }
     }
 }
 
+/// Find obvious stereo pairs among port names ("capture_1"/"capture_2", "out_L"/"out_R")
+/// and return them as (left, right) indexes
+fn stereo_pairs(names: &[&str]) -> Vec<(usize, usize)> {
+    names.iter().enumerate()
+        .filter_map(|(left, name)| {
+            let partner = right_channel_name(name)?;
+            let right = names.iter().position(|other| *other == partner)?;
+            Some((left, right))
+        })
+        .collect()
+}
+
+/// Name of the right channel port matching a port name that looks like a left channel
+fn right_channel_name(name: &str) -> Option<String> {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if stem.len() < name.len() {
+        let number: u32 = name[stem.len()..].parse().ok()?;
+        return (number % 2 == 1).then(|| format!("{}{}", stem, number + 1));
+    }
+    [("left", "right"), ("Left", "Right"), ("LEFT", "RIGHT"), ("L", "R"), ("l", "r")].iter()
+        .find_map(|(left, right)| name.strip_suffix(left).map(|stem| format!("{}{}", stem, right)))
+}
+
 /// Input feature for managing audio/MIDI inputs
 /// This is a type alias for SystemFeature configured for input direction
 pub type InputFeature = SystemFeature;
 
 /// Helper to create a new input feature
-pub fn new_input_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>) -> InputFeature {
-    SystemFeature::new(driver, engine, ui, SystemDirection::Input)
+pub fn new_input_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> InputFeature {
+    SystemFeature::new(driver, engine, ui, settings, SystemDirection::Input)
 }
 
 /// Output feature for managing audio/MIDI outputs
@@ -392,6 +586,18 @@ pub fn new_input_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>)
 pub type OutputFeature = SystemFeature;
 
 /// Helper to create a new output feature
-pub fn new_output_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>) -> OutputFeature {
-    SystemFeature::new(driver, engine, ui, SystemDirection::Output)
+pub fn new_output_feature(driver: Arc<Driver>, engine: Arc<Engine>, ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> OutputFeature {
+    SystemFeature::new(driver, engine, ui, settings, SystemDirection::Output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_pairs() {
+        let names = ["system:capture_1", "system:capture_2", "system:capture_3", "fx:out_L", "fx:out_R", "fx:send"];
+        assert_eq!(stereo_pairs(&names), vec![(0, 1), (3, 4)]);
+        assert_eq!(stereo_pairs(&["system:capture_2", "system:capture_3"]), vec![]);
+    }
 }
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use log::debug;
+use std::sync::{Arc, Mutex};
+
+use crate::config::Settings;
+use crate::controller::{ControllerState, feature::{ContextEntry, Feature}};
+use crate::ui::{Menu, MenuOption, UI};
+
+/// Number of tag colors of the UI, the tags beyond reuse them
+pub const TAG_COLORS: usize = 6;
+
+/// Get the color of a tag, following its position in the tags of the settings
+/// Tags no longer in the settings get the color after the last one
+pub fn tag_color(tags: &[String], tag: &str) -> usize {
+    tags.iter().position(|t| t == tag).unwrap_or(tags.len()) % TAG_COLORS
+}
+
+/// Tag feature marking nodes with a colored tag such as "Vocals" or "FX", to find them in large rigs
+pub struct TagFeature {
+    ui: Arc<UI>,
+    settings: Arc<Mutex<Settings>>,
+    ui_element: Option<crate::ui::Element>,
+}
+
+impl TagFeature {
+    /// Create a new tag feature
+    pub fn new(ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> Self {
+        Self {
+            ui,
+            settings,
+            ui_element: None,
+        }
+    }
+
+    /// Get the tag menu of a node, with its tag checked
+    fn get_tag_menu(&self) -> Menu {
+        let current = match &self.ui_element {
+            Some(crate::ui::Element::Node(node)) => self.ui.graph_model().tags.get(node).map(|(tag, _)| tag.clone()),
+            _ => None,
+        };
+        let mut options = vec![MenuOption {
+            id: "none".to_string(),
+            label: format!("No Tag{}", if current.is_none() { " ✓" } else { "" }),
+        }];
+        options.extend(self.settings.lock().unwrap().node_tags.iter().map(|tag| MenuOption {
+            id: format!("tag_{}", tag),
+            label: format!("{}{}", tag, if current.as_ref() == Some(tag) { " ✓" } else { "" }),
+        }));
+
+        Menu {
+            id: "tags".to_string(),
+            label: "Tag".to_string(),
+            options,
+        }
+    }
+}
+
+impl Feature for TagFeature {
+    fn context_entries(&self, element: &crate::ui::Element) -> Vec<ContextEntry> {
+        match element {
+            crate::ui::Element::Node(node) => {
+                let label = match self.ui.graph_model().tags.get(node) {
+                    Some((tag, _)) => format!("Tag: {} >", tag),
+                    None => "Tag >".to_string(),
+                };
+                vec![ContextEntry::new(42, "tag_node", &label)]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_menu(&self) -> Menu {
+        self.get_tag_menu()
+    }
+
+    fn set_element(&mut self, element: Option<&crate::ui::Element>) {
+        self.ui_element = element.cloned();
+    }
+
+    fn handle_menu_option(&mut self, option_id: Option<&str>, _element: Option<&crate::ui::Element>) -> Result<ControllerState> {
+        debug!("Tag feature handle_menu_option: {:?}", option_id);
+
+        let (Some(option), Some(crate::ui::Element::Node(node))) = (option_id, self.ui_element.take()) else {
+            return Ok(ControllerState::Navigating);
+        };
+        let tag = option.strip_prefix("tag_").map(|tag| {
+            let color = tag_color(&self.settings.lock().unwrap().node_tags, tag);
+            (tag, color)
+        });
+        self.ui.set_node_tag(node, tag)?;
+        Ok(ControllerState::Navigating)
+    }
+}
+
+/// Helper to create a new tag feature
+pub fn new_tag_feature(ui: Arc<UI>, settings: Arc<Mutex<Settings>>) -> TagFeature {
+    TagFeature::new(ui, settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_color() {
+        let tags: Vec<String> = ["Vocals", "FX"].iter().map(|tag| tag.to_string()).collect();
+        assert_eq!(tag_color(&tags, "FX"), 1);
+        assert_eq!(tag_color(&tags, "Drums"), 2);
+    }
+}
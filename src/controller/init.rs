@@ -93,6 +93,15 @@ impl Controller {
                     secondary_knob: assignment.clone(), // Placeholder
                     selection_button: assignment.clone(), // Placeholder
                     back_button: assignment,              // Placeholder
+                    tap_button: None,
+                    crossfade_knob: None,
+                    scene_button: None,
+                    panic_button: None,
+                    next_button: None,
+                    previous_button: None,
+                    mute_buttons: Default::default(),
+                    macro_knobs: Default::default(),
+                    bookmark_buttons: Default::default(),
                 });
             } else if let Some(config) = &mut self.base_control_config {
                 config.main_knob = assignment;
@@ -209,4 +218,352 @@ impl Controller {
 
         Ok(())
     }
+
+    /// Learn the tap tempo button assignment
+    pub(super) fn learn_tap_button(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the base controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .iter()
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring base control event during tap button learning");
+                    return Ok(());
+                }
+            }
+
+            info!("Learned tap button: channel={}, cc={}", channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                config.tap_button = Some(MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Tap button learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the crossfade knob assignment
+    pub(super) fn learn_crossfade_knob(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, .. } = event {
+            // Ignore the base controls and the tap button
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during crossfade knob learning");
+                    return Ok(());
+                }
+            }
+
+            info!("Learned crossfade knob: channel={}, cc={}", channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                config.crossfade_knob = Some(MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Knob,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Crossfade knob learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the mute button of the mixer fader selected in the mixer
+    pub(super) fn learn_mute_button(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the base controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.scene_button.as_ref())
+                    .chain(config.panic_button.as_ref())
+                    .chain(config.next_button.as_ref())
+                    .chain(config.previous_button.as_ref())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during mute button learning");
+                    return Ok(());
+                }
+            }
+
+            let Some(fader) = self.mixer_feature.as_mut().and_then(|m| m.take_learning_mute()) else {
+                self.state = ControllerState::Navigating;
+                return Ok(());
+            };
+
+            info!("Learned mute button for {}: channel={}, cc={}", fader, channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                // A button mutes a single fader
+                config.mute_buttons.retain(|_, a| !(a.channel == channel && a.control == control));
+                config.mute_buttons.insert(fader, MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Mute button learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the knob of the macro selected in the macro menu
+    pub(super) fn learn_macro_knob(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, .. } = event {
+            // Ignore the base controls and the other learned controls
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.scene_button.as_ref())
+                    .chain(config.panic_button.as_ref())
+                    .chain(config.next_button.as_ref())
+                    .chain(config.previous_button.as_ref())
+                    .chain(config.mute_buttons.values())
+                    .chain(config.bookmark_buttons.values())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during macro knob learning");
+                    return Ok(());
+                }
+            }
+
+            let Some(name) = self.macro_feature.as_mut().and_then(|m| m.take_learning_macro()) else {
+                self.state = ControllerState::Navigating;
+                return Ok(());
+            };
+
+            info!("Learned knob of {}: channel={}, cc={}", name, channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                // A knob drives a single macro
+                config.macro_knobs.retain(|_, a| !(a.channel == channel && a.control == control));
+                config.macro_knobs.insert(name, MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Knob,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Macro knob learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the button of the bookmark selected in the bookmark menu
+    pub(super) fn learn_bookmark_button(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the other learned controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.scene_button.as_ref())
+                    .chain(config.panic_button.as_ref())
+                    .chain(config.next_button.as_ref())
+                    .chain(config.previous_button.as_ref())
+                    .chain(config.mute_buttons.values())
+                    .chain(config.macro_knobs.values())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during bookmark button learning");
+                    return Ok(());
+                }
+            }
+
+            let Some(slot) = self.bookmark_feature.as_mut().and_then(|b| b.take_learning_bookmark()) else {
+                self.state = ControllerState::Navigating;
+                return Ok(());
+            };
+
+            info!("Learned button of bookmark {}: channel={}, cc={}", slot, channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                // A button recalls a single bookmark
+                config.bookmark_buttons.retain(|_, a| !(a.channel == channel && a.control == control));
+                config.bookmark_buttons.insert(slot, MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Bookmark button learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the scene footswitch assignment
+    pub(super) fn learn_scene_button(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the already-learned controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.panic_button.as_ref())
+                    .chain(config.next_button.as_ref())
+                    .chain(config.previous_button.as_ref())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during scene footswitch learning");
+                    return Ok(());
+                }
+            }
+
+            info!("Learned scene footswitch: channel={}, cc={}", channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                config.scene_button = Some(MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Scene footswitch learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the panic button assignment
+    pub(super) fn learn_panic_button(&mut self, event: driver::MidiEvent) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the already-learned controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.scene_button.as_ref())
+                    .chain(config.next_button.as_ref())
+                    .chain(config.previous_button.as_ref())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during panic button learning");
+                    return Ok(());
+                }
+            }
+
+            info!("Learned panic button: channel={}, cc={}", channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                config.panic_button = Some(MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+            }
+
+            self.save_config()?;
+            self.ui.show_message("Panic button learned")?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
+
+    /// Learn the next or previous button of the set list
+    pub(super) fn learn_set_list_button(&mut self, event: driver::MidiEvent, next: bool) -> Result<()> {
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            // Ignore releases and the already-learned controls
+            if value == 0 {
+                return Ok(());
+            }
+            if let Some(config) = &self.base_control_config {
+                let other = if next { &config.previous_button } else { &config.next_button };
+                if [&config.main_knob, &config.secondary_knob, &config.selection_button, &config.back_button]
+                    .into_iter()
+                    .chain(config.tap_button.as_ref())
+                    .chain(config.crossfade_knob.as_ref())
+                    .chain(config.scene_button.as_ref())
+                    .chain(config.panic_button.as_ref())
+                    .chain(other.as_ref())
+                    .any(|a| a.channel == channel && a.control == control)
+                {
+                    debug!("Ignoring already-learned control event during set list button learning");
+                    return Ok(());
+                }
+            }
+
+            let name = if next { "next" } else { "previous" };
+            info!("Learned {} button: channel={}, cc={}", name, channel, control);
+
+            if let Some(config) = &mut self.base_control_config {
+                let assignment = Some(MidiAssignment {
+                    channel,
+                    control,
+                    control_type: ControlType::Button,
+                });
+                if next {
+                    config.next_button = assignment;
+                } else {
+                    config.previous_button = assignment;
+                }
+            }
+
+            self.save_config()?;
+            self.ui.show_message(&format!("Set list {} button learned", name))?;
+
+            self.state = ControllerState::Navigating;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use crate::controller::KnobDirection;
+
+/// Interval between knob events from which a turn is slow, moving one step at a time
+const SLOW_INTERVAL: Duration = Duration::from_millis(120);
+
+/// Highest factor applied to the moves of a fast turn
+const MAX_ACCELERATION: f32 = 16.0;
+
+/// Get the knob move making one step for a base move, scaled down by the knob sensitivity setting
+pub fn threshold(base: f32, sensitivity: f32) -> f32 {
+    base / sensitivity.max(0.1)
+}
+
+/// Speed tracker of a knob, scaling its moves by how fast it turns
+#[derive(Debug, Default)]
+pub struct KnobAcceleration {
+    last: Option<Instant>,
+}
+
+impl KnobAcceleration {
+    /// Get the factor of a knob event from the time since the previous one
+    /// The curve is the exponent applied to the speed ratio, 0 keeping every turn at full resolution
+    pub fn factor(&mut self, now: Instant, curve: f32) -> f32 {
+        match self.last.replace(now) {
+            Some(last) if curve > 0.0 => {
+                let interval = now.duration_since(last).as_secs_f32().max(0.001);
+                (SLOW_INTERVAL.as_secs_f32() / interval).max(1.0).powf(curve).min(MAX_ACCELERATION)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Accumulate a relative knob value scaled by an acceleration factor and get the steps it makes
+/// The remainder is kept, so that slow turns still move one step per threshold
+pub fn accelerated_steps(value: u8, accumulator: &mut f32, threshold: f32, factor: f32) -> Option<(KnobDirection, u32)> {
+    let delta = value as f32 - 64.0;
+    *accumulator += delta * factor;
+
+    let steps = (accumulator.abs() / threshold).floor();
+    if steps < 1.0 {
+        return None;
+    }
+    // Turning clockwise sends values above 64, going backward like the menu navigation
+    let direction = if *accumulator > 0.0 {
+        KnobDirection::Backward
+    } else {
+        KnobDirection::Forward
+    };
+    *accumulator -= accumulator.signum() * steps * threshold;
+    Some((direction, steps as u32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acceleration_factor() {
+        let start = Instant::now();
+        let mut acceleration = KnobAcceleration::default();
+        assert_eq!(acceleration.factor(start, 1.0), 1.0);
+        assert_eq!(acceleration.factor(start + Duration::from_millis(500), 1.0), 1.0);
+
+        let fast = acceleration.factor(start + Duration::from_millis(530), 1.0);
+        assert!((fast - 4.0).abs() < 0.01, "factor {}", fast);
+        let steep = acceleration.factor(start + Duration::from_millis(560), 2.0);
+        assert_eq!(steep, MAX_ACCELERATION);
+        assert_eq!(acceleration.factor(start + Duration::from_millis(570), 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_accelerated_steps() {
+        let mut accumulator = 0.0;
+        assert_eq!(accelerated_steps(65, &mut accumulator, 2.0, 1.0), None);
+        assert_eq!(accelerated_steps(65, &mut accumulator, 2.0, 1.0), Some((KnobDirection::Backward, 1)));
+        assert_eq!(accumulator, 0.0);
+
+        assert_eq!(accelerated_steps(63, &mut accumulator, 2.0, 5.0), Some((KnobDirection::Forward, 2)));
+        assert_eq!(accumulator, -1.0);
+    }
+}
@@ -0,0 +1,260 @@
+use anyhow::Result;
+use jack::{AudioOut, Client, ClientOptions, ClosureProcessHandler, Control, ProcessScope};
+use log::{debug, info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::controller::driver::{Driver, Port};
+use crate::controller::tempo::Tempo;
+
+/// JACK client name of the metronome
+const METRONOME_CLIENT: &str = "TraxDub Click";
+
+/// Name of the click output port
+const CLICK_PORT: &str = "click";
+
+/// Beats per bar, the first one is accented
+const BEATS_PER_BAR: u32 = 4;
+
+/// Length of a click in seconds
+const CLICK_LENGTH: f32 = 0.03;
+
+/// Click pitch in Hz for regular and accented beats
+const CLICK_FREQUENCY: f32 = 1000.0;
+const ACCENT_FREQUENCY: f32 = 1500.0;
+
+/// Click generator producing short decaying sine bursts on each beat
+#[derive(Debug, Default)]
+pub struct ClickGenerator {
+    frames_since_beat: f32,
+    beat: u32,
+    started: bool,
+}
+
+impl ClickGenerator {
+    /// Fill an output buffer with clicks at the given tempo (silence without tempo)
+    pub fn fill(&mut self, out: &mut [f32], bpm: Option<f32>, sample_rate: f32) {
+        let Some(bpm) = bpm.filter(|b| *b > 0.0) else {
+            out.fill(0.0);
+            self.started = false;
+            return;
+        };
+
+        let frames_per_beat = sample_rate * 60.0 / bpm;
+        let click_frames = sample_rate * CLICK_LENGTH;
+
+        for sample in out.iter_mut() {
+            // Start a new beat, the first click sounds immediately
+            if !self.started {
+                self.started = true;
+                self.frames_since_beat = 0.0;
+                self.beat = 0;
+            } else if self.frames_since_beat >= frames_per_beat {
+                self.frames_since_beat -= frames_per_beat;
+                self.beat = (self.beat + 1) % BEATS_PER_BAR;
+            }
+
+            *sample = if self.frames_since_beat < click_frames {
+                let frequency = if self.beat == 0 { ACCENT_FREQUENCY } else { CLICK_FREQUENCY };
+                let t = self.frames_since_beat / sample_rate;
+                let envelope = 1.0 - self.frames_since_beat / click_frames;
+                (2.0 * std::f32::consts::PI * frequency * t).sin() * envelope * envelope
+            } else {
+                0.0
+            };
+
+            self.frames_since_beat += 1.0;
+        }
+    }
+}
+
+/// A running click client
+struct MetronomeSession {
+    stop_flag: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Metronome playing a click at the current tempo on its own JACK output
+pub struct Metronome {
+    driver: Arc<Driver>,
+    tempo: Arc<Tempo>,
+    session: Mutex<Option<MetronomeSession>>,
+    routes: Mutex<Vec<Port>>,
+}
+
+impl Metronome {
+    /// Create a new metronome
+    pub fn new(driver: Arc<Driver>, tempo: Arc<Tempo>) -> Self {
+        Self {
+            driver,
+            tempo,
+            session: Mutex::new(None),
+            routes: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// The JACK port of the click output
+    fn click_port() -> Port {
+        Port {
+            name: format!("{}:{}", METRONOME_CLIENT, CLICK_PORT),
+            short_name: CLICK_PORT.to_string(),
+        }
+    }
+
+    /// Check whether the click is running
+    pub fn is_running(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// Start the click and connect it to its routes
+    pub fn start(&self) -> Result<()> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_some() {
+            return Ok(());
+        }
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+
+        let tempo = Arc::clone(&self.tempo);
+        let thread_stop_flag = Arc::clone(&stop_flag);
+        let handle = std::thread::spawn(move || {
+            Self::run(tempo, thread_stop_flag, ready_sender)
+        });
+
+        // Wait for the client to be activated before connecting it
+        match ready_receiver.recv_timeout(Duration::from_secs(2)) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                return Err(e);
+            }
+            Err(_) => {
+                stop_flag.store(true, Ordering::SeqCst);
+                return Err(anyhow::anyhow!("Metronome did not start"));
+            }
+        }
+
+        for destination in self.routes.lock().unwrap().iter() {
+            if let Err(e) = self.driver.connect_ports(&Self::click_port(), destination) {
+                warn!("Failed to route click to {}: {}", destination.name, e);
+            }
+        }
+
+        info!("Metronome started");
+        *session = Some(MetronomeSession { stop_flag, handle });
+        Ok(())
+    }
+
+    /// Stop the click
+    pub fn stop(&self) {
+        if let Some(session) = self.session.lock().unwrap().take() {
+            session.stop_flag.store(true, Ordering::SeqCst);
+            if session.handle.join().is_err() {
+                warn!("Metronome thread panicked");
+            }
+            info!("Metronome stopped");
+        }
+    }
+
+    /// Route the click to a destination port, remembered across restarts
+    pub fn route(&self, destination: Port) -> Result<()> {
+        if self.is_running() {
+            self.driver.connect_ports(&Self::click_port(), &destination)?;
+        }
+        info!("Click routed to {}", destination.name);
+
+        let mut routes = self.routes.lock().unwrap();
+        if !routes.iter().any(|r| r.name == destination.name) {
+            routes.push(destination);
+        }
+        Ok(())
+    }
+
+    /// Get the destinations the click is routed to
+    pub fn routes(&self) -> Vec<Port> {
+        self.routes.lock().unwrap().clone()
+    }
+
+    /// Metronome thread: run the JACK client and follow the tempo until stopped
+    fn run(tempo: Arc<Tempo>, stop_flag: Arc<AtomicBool>, ready: mpsc::Sender<Result<()>>) {
+        let client = Client::new(METRONOME_CLIENT, ClientOptions::NO_START_SERVER)
+            .map_err(|e| anyhow::anyhow!("Failed to create JACK metronome client: {}", e))
+            .and_then(|(client, _status)| {
+                let port = client.register_port(CLICK_PORT, AudioOut)
+                    .map_err(|e| anyhow::anyhow!("Failed to register click port: {}", e))?;
+                Ok((client, port))
+            });
+
+        let (client, mut click_out) = match client {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = ready.send(Err(e));
+                return;
+            }
+        };
+
+        // Tempo shared with the process thread as raw f32 bits (0 means no tempo)
+        let bpm_bits = Arc::new(AtomicU32::new(0));
+        let process_bpm_bits = Arc::clone(&bpm_bits);
+        let mut generator = ClickGenerator::default();
+
+        let process_callback = move |client: &Client, ps: &ProcessScope| -> Control {
+            let bpm = f32::from_bits(process_bpm_bits.load(Ordering::Relaxed));
+            let bpm = if bpm > 0.0 { Some(bpm) } else { None };
+            generator.fill(click_out.as_mut_slice(ps), bpm, client.sample_rate() as f32);
+            Control::Continue
+        };
+
+        let active_client = match client.activate_async((), ClosureProcessHandler::new(process_callback)) {
+            Ok(active_client) => active_client,
+            Err(e) => {
+                let _ = ready.send(Err(anyhow::anyhow!("Failed to activate JACK metronome client: {}", e)));
+                return;
+            }
+        };
+
+        debug!("JACK metronome client activated");
+        let _ = ready.send(Ok(()));
+
+        while !stop_flag.load(Ordering::SeqCst) {
+            let bpm = tempo.get_bpm().unwrap_or(0.0);
+            bpm_bits.store(bpm.to_bits(), Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        drop(active_client);
+        debug!("JACK metronome client closed");
+    }
+}
+
+impl Drop for Metronome {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_generator_beats() {
+        let mut generator = ClickGenerator::default();
+        let mut out = vec![0.0; 48000];
+        generator.fill(&mut out, Some(120.0), 48000.0);
+
+        // Clicks start at each beat (every 24000 frames at 120 BPM) and stay short
+        let click_frames = (48000.0 * CLICK_LENGTH) as usize;
+        assert!(out[1..click_frames].iter().any(|s| s.abs() > 0.1));
+        assert!(out[click_frames + 10..24000].iter().all(|s| *s == 0.0));
+        assert!(out[24001..24000 + click_frames].iter().any(|s| s.abs() > 0.1));
+
+        // No tempo, no click
+        generator.fill(&mut out, None, 48000.0);
+        assert!(out.iter().all(|s| *s == 0.0));
+    }
+}
@@ -1,17 +1,30 @@
 pub mod init;
 pub mod driver;
 pub mod feature;
+pub mod tempo;
+pub mod recorder;
+pub mod analyzer;
+pub mod metronome;
+pub mod server;
+pub mod repl;
+pub mod replay;
+pub mod reconcile;
+pub mod topology;
+pub mod backup;
+pub mod knob;
 
+use crate::config::Settings;
 use crate::engine::Engine;
 use crate::ui::UI;
-use crate::controller::feature::Feature;
+use crate::controller::feature::{Feature, FeatureKind, TextEditor};
 use anyhow::Result;
-use log::{debug, error, warn, trace};
+use log::{debug, error, info, warn, trace};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Base MIDI control assignments
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +33,30 @@ pub struct BaseControlConfig {
     pub secondary_knob: MidiAssignment,
     pub selection_button: MidiAssignment,
     pub back_button: MidiAssignment,
+    #[serde(default)]
+    pub tap_button: Option<MidiAssignment>,
+    #[serde(default)]
+    pub crossfade_knob: Option<MidiAssignment>,
+    /// Footswitch switching to the next saved session
+    #[serde(default)]
+    pub scene_button: Option<MidiAssignment>,
+    /// Button sending a MIDI panic
+    #[serde(default)]
+    pub panic_button: Option<MidiAssignment>,
+    /// Buttons moving to the next and previous item of the set list
+    #[serde(default)]
+    pub next_button: Option<MidiAssignment>,
+    #[serde(default)]
+    pub previous_button: Option<MidiAssignment>,
+    /// Mute buttons keyed by mixer fader block path
+    #[serde(default)]
+    pub mute_buttons: HashMap<String, MidiAssignment>,
+    /// Macro knobs keyed by macro name
+    #[serde(default)]
+    pub macro_knobs: HashMap<String, MidiAssignment>,
+    /// Bookmark buttons keyed by bookmark slot
+    #[serde(default)]
+    pub bookmark_buttons: HashMap<u8, MidiAssignment>,
 }
 
 /// MIDI assignment for a control
@@ -58,8 +95,20 @@ pub enum ControllerState {
     LearningSecondaryKnob,
     LearningSelectionButton,
     LearningBackButton,
+    LearningTapButton,
+    LearningCrossfadeKnob,
+    LearningMuteButton,
+    LearningMacroKnob,
+    LearningBookmarkButton,
+    LearningSceneButton,
+    LearningPanicButton,
+    LearningNextButton,
+    LearningPreviousButton,
     Navigating,
     BrowsingMenu,
+    Performing,
+    Renaming,
+    EditingSessionInfo,
 }
 
 /// Main controller that processes MIDI events and coordinates engine and UI
@@ -67,53 +116,182 @@ pub struct Controller {
     ui: Arc<UI>,
     engine: Arc<Engine>,
     driver: Arc<driver::Driver>,
+    tempo: Arc<tempo::Tempo>,
+    settings: Arc<Mutex<Settings>>,
     state: ControllerState,
     base_control_config: Option<BaseControlConfig>,
     config_path: PathBuf,
     force_init: bool,
     main_knob_accumulator: f32,
     secondary_knob_accumulator: f32,
+    main_knob_acceleration: knob::KnobAcceleration,
+    secondary_knob_acceleration: knob::KnobAcceleration,
     input_feature: Option<feature::InputFeature>,
     output_feature: Option<feature::OutputFeature>,
     plugin_feature: Option<feature::PluginFeature>,
     persistence_feature: Option<feature::PersistenceFeature>,
-    current_feature: Option<*mut dyn Feature>,
+    performance_feature: Option<feature::PerformanceFeature>,
+    parameter_feature: Option<feature::ParameterFeature>,
+    record_feature: Option<feature::RecordFeature>,
+    metronome_feature: Option<feature::MetronomeFeature>,
+    mixer_feature: Option<feature::MixerFeature>,
+    crossfade_feature: Option<feature::CrossfadeFeature>,
+    preset_feature: Option<feature::PresetFeature>,
+    arrange_feature: Option<feature::ArrangeFeature>,
+    rename_feature: Option<feature::RenameFeature>,
+    settings_feature: Option<feature::SettingsFeature>,
+    monitor_feature: Option<feature::MidiMonitorFeature>,
+    setlist_feature: Option<feature::SetListFeature>,
+    group_feature: Option<feature::GroupFeature>,
+    chain_preset_feature: Option<feature::ChainPresetFeature>,
+    clip_feature: Option<feature::ClipFeature>,
+    macro_feature: Option<feature::MacroFeature>,
+    node_number_feature: Option<feature::NodeNumberFeature>,
+    history_feature: Option<feature::HistoryFeature>,
+    goto_feature: Option<feature::GotoFeature>,
+    bookmark_feature: Option<feature::BookmarkFeature>,
+    tag_feature: Option<feature::TagFeature>,
+    feedback_feature: Option<feature::FeedbackFeature>,
+    /// The feature whose menus are open
+    current_feature: Option<FeatureKind>,
     /// The UI element that was selected when opening the current feature
     current_element: Option<crate::ui::Element>,
+    /// Id of the open context menu
+    context_menu_id: Option<String>,
+    /// Feature contributing each entry of the open context menu
+    context_entries: HashMap<String, FeatureKind>,
+    /// Tempo currently shown in the UI (tenths of BPM)
+    displayed_tempo: Option<i32>,
+    /// Recording time currently shown in the UI (seconds)
+    displayed_recording: Option<u64>,
+    /// Audio settings currently shown in the UI
+    displayed_audio_status: Option<driver::AudioStatus>,
+    /// Xrun count currently shown in the UI
+    displayed_xruns: usize,
+    /// DSP load currently shown in the UI (percent)
+    displayed_dsp_load: Option<u32>,
+    /// Spectrum analyzer of the engine outputs, running while enabled in the settings
+    analyzer: analyzer::Analyzer,
+    /// Sequence number of the next log entry to send to the log panel
+    displayed_log_sequence: u64,
+    /// Whether the inspector is shown
+    inspector_visible: bool,
+    /// Sequence number of the next protocol message to send to the inspector
+    displayed_protocol_sequence: u64,
+    /// Sequence number of the raw state shown in the inspector
+    displayed_state_sequence: Option<u64>,
+    /// Time of the last reconciliation of the UI with the engine
+    last_reconcile: Instant,
+    /// Time of the last check that the engine answers
+    last_health_check: Instant,
+    /// Time of the last check for blocks reaching no output
+    last_orphan_check: Instant,
+    /// Blocks reaching no output at the last check, warned about once
+    orphans: Vec<String>,
+    /// Time of the last check of the safety limiters against the setting
+    last_limiter_check: Instant,
+    /// Time of the last refresh of the latencies shown under the nodes
+    last_latency_check: Instant,
+    /// Latencies shown under the nodes, by node
+    displayed_latencies: HashMap<String, String>,
+    /// Engine status currently shown in the UI
+    displayed_engine_status: Option<bool>,
+    /// Commands typed on stdin or sent by remote frontends
+    command_receiver: Option<std::sync::mpsc::Receiver<repl::ReplCommand>>,
+    /// Recorded events fed instead of the MIDI inputs
+    replay_events: Option<Vec<replay::TimedEvent>>,
+    /// Recording of the processed MIDI events
+    event_recorder: Option<replay::EventRecorder>,
 }
 
-// Mark Controller as Send - the raw pointer is only used within the controller's methods
-// and never sent across threads
-unsafe impl Send for Controller {}
-
 impl Controller {
     /// Create a new controller instance
-    pub fn new(ui: Arc<UI>, engine: Arc<Engine>, force_init: bool, new_session: bool) -> Result<Self> {
+    pub fn new(ui: Arc<UI>, engine: Arc<Engine>, settings: Arc<Mutex<Settings>>, force_init: bool, new_session: bool) -> Result<Self> {
         let config_path = Self::get_config_path();
         
         // Create JACK driver
         let driver = Arc::new(driver::Driver::new()?);
+        let tempo = Arc::new(tempo::Tempo::new(Arc::clone(&driver)));
+        let analyzer = analyzer::Analyzer::new(Arc::clone(&driver));
         
         let mut controller = Self {
             ui: ui.clone(),
             engine: engine.clone(),
             driver,
+            tempo,
+            settings,
             state: ControllerState::Initializing,
             base_control_config: None,
             config_path,
             force_init,
             main_knob_accumulator: 0.0,
             secondary_knob_accumulator: 0.0,
+            main_knob_acceleration: knob::KnobAcceleration::default(),
+            secondary_knob_acceleration: knob::KnobAcceleration::default(),
             input_feature: None,
             output_feature: None,
             plugin_feature: None,
             persistence_feature: None,
+            performance_feature: None,
+            parameter_feature: None,
+            record_feature: None,
+            metronome_feature: None,
+            mixer_feature: None,
+            crossfade_feature: None,
+            preset_feature: None,
+            arrange_feature: None,
+            rename_feature: None,
+            settings_feature: None,
+            monitor_feature: None,
+            setlist_feature: None,
+            group_feature: None,
+            chain_preset_feature: None,
+            clip_feature: None,
+            macro_feature: None,
+            node_number_feature: None,
+            history_feature: None,
+            goto_feature: None,
+            bookmark_feature: None,
+            tag_feature: None,
+            feedback_feature: None,
             current_feature: None,
+            context_menu_id: None,
+            context_entries: HashMap::new(),
             current_element: None,
+            displayed_tempo: None,
+            displayed_recording: None,
+            displayed_audio_status: None,
+            displayed_xruns: 0,
+            displayed_dsp_load: None,
+            analyzer,
+            displayed_log_sequence: 0,
+            inspector_visible: false,
+            displayed_protocol_sequence: 0,
+            displayed_state_sequence: None,
+            last_reconcile: Instant::now(),
+            last_health_check: Instant::now(),
+            last_orphan_check: Instant::now(),
+            orphans: Vec::new(),
+            last_limiter_check: Instant::now(),
+            last_latency_check: Instant::now(),
+            displayed_latencies: HashMap::new(),
+            displayed_engine_status: None,
+            command_receiver: None,
+            replay_events: None,
+            event_recorder: None,
         };
         
         controller.initialize()?;
         
+        let (theme, log_panel, (scale, compact), speech) = {
+            let settings = controller.settings.lock().unwrap();
+            (settings.theme.clone(), settings.log_panel, settings.display(), settings.speech_enabled())
+        };
+        controller.ui.set_theme(theme)?;
+        controller.ui.set_display(scale, compact)?;
+        controller.ui.set_log_panel(log_panel)?;
+        controller.ui.set_speech(speech);
+        
         // Create initial context nodes in UI
         controller.ui.create_node("inputs".to_string(), "Inputs".to_string(), crate::ui::NodeType::Context)?;
         controller.ui.create_node("outputs".to_string(), "Outputs".to_string(), crate::ui::NodeType::Context)?;
@@ -127,6 +305,7 @@ impl Controller {
             Arc::clone(&controller.driver),
             engine.clone(),
             ui.clone(),
+            Arc::clone(&controller.settings),
             auto_load,
         ));
         
@@ -135,6 +314,7 @@ impl Controller {
             Arc::clone(&controller.driver),
             Arc::clone(&engine),
             Arc::clone(&ui),
+            Arc::clone(&controller.settings),
         ));
         
         // Initialize output feature with driver and engine
@@ -142,6 +322,7 @@ impl Controller {
             Arc::clone(&controller.driver),
             Arc::clone(&engine),
             Arc::clone(&ui),
+            Arc::clone(&controller.settings),
         ));
         
         // Initialize plugin feature
@@ -150,23 +331,311 @@ impl Controller {
             Arc::clone(&ui),
         ));
         
+        // Initialize performance feature
+        controller.performance_feature = Some(feature::new_performance_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize parameter feature
+        controller.parameter_feature = Some(feature::new_parameter_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+            Arc::clone(&controller.tempo),
+        ));
+        
+        // Initialize record feature
+        controller.record_feature = Some(feature::new_record_feature(
+            Arc::clone(&controller.driver),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize metronome feature
+        controller.metronome_feature = Some(feature::new_metronome_feature(
+            Arc::clone(&controller.driver),
+            Arc::clone(&ui),
+            Arc::clone(&controller.tempo),
+        ));
+        
+        // Initialize settings feature
+        controller.settings_feature = Some(feature::new_settings_feature(
+            Arc::clone(&controller.driver),
+            Arc::clone(&ui),
+            Arc::clone(&controller.settings),
+        ));
+        
+        // Initialize MIDI monitor feature
+        controller.monitor_feature = Some(feature::new_midi_monitor_feature(
+            Arc::clone(&controller.driver),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize set list feature
+        let setlist = feature::new_set_list_feature(
+            Arc::clone(&ui),
+            Arc::clone(&controller.settings),
+        );
+        if !setlist.is_empty() {
+            setlist.show_up_next()?;
+        }
+        controller.setlist_feature = Some(setlist);
+        
+        // Initialize mixer feature
+        controller.mixer_feature = Some(feature::new_mixer_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+            Arc::clone(&controller.settings),
+        ));
+        
+        // Initialize crossfade feature
+        controller.crossfade_feature = Some(feature::new_crossfade_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize preset feature
+        controller.preset_feature = Some(feature::new_preset_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize arrange feature
+        controller.arrange_feature = Some(feature::new_arrange_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize rename feature
+        controller.rename_feature = Some(feature::new_rename_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize group feature
+        controller.group_feature = Some(feature::new_group_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize chain preset feature
+        controller.chain_preset_feature = Some(feature::new_chain_preset_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize clip feature
+        controller.clip_feature = Some(feature::new_clip_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize macro feature
+        controller.macro_feature = Some(feature::new_macro_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize node number feature
+        controller.node_number_feature = Some(feature::new_node_number_feature(Arc::clone(&ui)));
+        
+        // Initialize history feature
+        controller.history_feature = Some(feature::new_history_feature(Arc::clone(&ui)));
+        
+        // Initialize go to feature
+        controller.goto_feature = Some(feature::new_goto_feature(
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
+        // Initialize bookmark feature
+        controller.bookmark_feature = Some(feature::new_bookmark_feature(Arc::clone(&ui)));
+        
+        // Initialize tag feature
+        controller.tag_feature = Some(feature::new_tag_feature(
+            Arc::clone(&ui),
+            Arc::clone(&controller.settings),
+        ));
+        
+        // Initialize feedback feature
+        controller.feedback_feature = Some(feature::new_feedback_feature(
+            Arc::clone(&controller.driver),
+            Arc::clone(&engine),
+            Arc::clone(&ui),
+        ));
+        
         Ok(controller)
     }
     
+    /// Get a reference to a feature
+    fn feature(&self, kind: FeatureKind) -> Option<&dyn Feature> {
+        match kind {
+            FeatureKind::Input => self.input_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Output => self.output_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Plugin => self.plugin_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Persistence => self.persistence_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Performance => self.performance_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Parameter => self.parameter_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Record => self.record_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Metronome => self.metronome_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Mixer => self.mixer_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Crossfade => self.crossfade_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Preset => self.preset_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Arrange => self.arrange_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Settings => self.settings_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Monitor => self.monitor_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::SetList => self.setlist_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Group => self.group_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::ChainPreset => self.chain_preset_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Clip => self.clip_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Macro => self.macro_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::NodeNumber => self.node_number_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::History => self.history_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Goto => self.goto_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Bookmark => self.bookmark_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Tag => self.tag_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Feedback => self.feedback_feature.as_ref().map(|f| f as &dyn Feature),
+            FeatureKind::Rename => self.rename_feature.as_ref().map(|f| f as &dyn Feature),
+        }
+    }
+    
+    /// Get a mutable reference to a feature
+    fn feature_mut(&mut self, kind: FeatureKind) -> Option<&mut dyn Feature> {
+        match kind {
+            FeatureKind::Input => self.input_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Output => self.output_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Plugin => self.plugin_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Persistence => self.persistence_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Performance => self.performance_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Parameter => self.parameter_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Record => self.record_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Metronome => self.metronome_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Mixer => self.mixer_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Crossfade => self.crossfade_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Preset => self.preset_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Arrange => self.arrange_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Settings => self.settings_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Monitor => self.monitor_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::SetList => self.setlist_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Group => self.group_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::ChainPreset => self.chain_preset_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Clip => self.clip_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Macro => self.macro_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::NodeNumber => self.node_number_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::History => self.history_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Goto => self.goto_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Bookmark => self.bookmark_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Tag => self.tag_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Feedback => self.feedback_feature.as_mut().map(|f| f as &mut dyn Feature),
+            FeatureKind::Rename => self.rename_feature.as_mut().map(|f| f as &mut dyn Feature),
+        }
+    }
+    
     /// Get a reference to the current active feature
     fn current_feature(&self) -> Option<&dyn Feature> {
-        self.current_feature.map(|ptr| unsafe { &*ptr })
+        self.current_feature.and_then(|kind| self.feature(kind))
     }
     
     /// Get a mutable reference to the current active feature
     fn current_feature_mut(&mut self) -> Option<&mut dyn Feature> {
-        self.current_feature.map(|ptr| unsafe { &mut *ptr })
+        self.current_feature.and_then(|kind| self.feature_mut(kind))
     }
     
     /// Process a MIDI event
     pub fn process_midi_event(&mut self, event: driver::MidiEvent) -> Result<()> {
         trace!("Processing event: {:?} in state {:?}", event, self.state);
         
+        // The tap tempo button works in every operating state
+        if matches!(self.state, ControllerState::Navigating | ControllerState::BrowsingMenu | ControllerState::Performing)
+            && self.is_tap_button(&event)
+        {
+            if let Some(bpm) = self.tempo.tap() {
+                debug!("Tapped tempo: {:.1} BPM", bpm);
+            }
+            return self.update_tempo_display();
+        }
+        
+        // The panic button works in every operating state
+        if matches!(self.state, ControllerState::Navigating | ControllerState::BrowsingMenu | ControllerState::Performing)
+            && self.is_panic_button(&event)
+        {
+            if let Err(e) = self.driver.panic() {
+                self.ui.show_message(&e.to_string())?;
+            }
+            return Ok(());
+        }
+        
+        // Program Change jumps to the node given its number while navigating, before the set list
+        if self.state == ControllerState::Navigating {
+            if let driver::MidiEvent::ProgramChange { program, .. } = event {
+                let node = self.node_number_feature.as_ref()
+                    .and_then(|numbers| numbers.node_for_program(program))
+                    .map(str::to_string);
+                if let Some(node) = node {
+                    return self.jump_to_node(node);
+                }
+            }
+        }
+        
+        // The bookmark buttons move the focus to their bookmark while navigating
+        if self.state == ControllerState::Navigating {
+            if let Some(slot) = self.bookmark_button_slot(&event) {
+                if let Some(bookmarks) = &self.bookmark_feature {
+                    bookmarks.recall(slot)?;
+                }
+                return Ok(());
+            }
+        }
+        
+        // The set list buttons and Program Change move through the set list while navigating
+        if self.state == ControllerState::Navigating {
+            let item = match (self.set_list_step(&event), self.setlist_feature.as_mut()) {
+                (Some(delta), Some(setlist)) => Some(setlist.step(delta)),
+                (None, Some(setlist)) if !setlist.is_empty() => match event {
+                    driver::MidiEvent::ProgramChange { program, .. } => Some(setlist.jump(program as usize)),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(item) = item {
+                return match item {
+                    Some(mnemonic) => self.play_set_list_item(&mnemonic),
+                    None => self.ui.show_message("End of the set list"),
+                };
+            }
+        }
+        
+        // The scene footswitch switches sessions while navigating
+        if self.state == ControllerState::Navigating && self.is_scene_button(&event) {
+            if let Some(persistence) = self.persistence_feature.as_mut() {
+                if let Err(e) = persistence.load_next_session() {
+                    self.ui.show_message(&e.to_string())?;
+                }
+            }
+            return Ok(());
+        }
+        
+        // The crossfade knob and mute buttons work in every operating state
+        if matches!(self.state, ControllerState::Navigating | ControllerState::BrowsingMenu | ControllerState::Performing) {
+            if let Some(position) = self.crossfade_position(&event) {
+                if let Some(crossfade) = &self.crossfade_feature {
+                    crossfade.set_position(position)?;
+                }
+                return Ok(());
+            }
+            if let Some((name, position)) = self.macro_position(&event) {
+                if let Some(macros) = &self.macro_feature {
+                    macros.set_position(&name, position)?;
+                }
+                return Ok(());
+            }
+            if let Some(fader) = self.mute_button_fader(&event) {
+                if let Some(mixer) = self.mixer_feature.as_mut() {
+                    mixer.toggle_mute(&fader)?;
+                }
+                return Ok(());
+            }
+        }
+        
         match self.state {
             ControllerState::LearningSelectionKnob => {
                 self.learn_main_knob(event)?;
@@ -180,12 +649,45 @@ impl Controller {
             ControllerState::LearningBackButton => {
                 self.learn_back_button(event)?;
             }
+            ControllerState::LearningTapButton => {
+                self.learn_tap_button(event)?;
+            }
+            ControllerState::LearningCrossfadeKnob => {
+                self.learn_crossfade_knob(event)?;
+            }
+            ControllerState::LearningMuteButton => {
+                self.learn_mute_button(event)?;
+            }
+            ControllerState::LearningMacroKnob => {
+                self.learn_macro_knob(event)?;
+            }
+            ControllerState::LearningBookmarkButton => {
+                self.learn_bookmark_button(event)?;
+            }
+            ControllerState::LearningSceneButton => {
+                self.learn_scene_button(event)?;
+            }
+            ControllerState::LearningPanicButton => {
+                self.learn_panic_button(event)?;
+            }
+            ControllerState::LearningNextButton => {
+                self.learn_set_list_button(event, true)?;
+            }
+            ControllerState::LearningPreviousButton => {
+                self.learn_set_list_button(event, false)?;
+            }
             ControllerState::Navigating => {
                 self.process_event_navigating_state(event)?;
             }
             ControllerState::BrowsingMenu => {
                 self.process_event_browsing_menu_state(event)?;
             }
+            ControllerState::Performing => {
+                self.process_event_performing_state(event)?;
+            }
+            ControllerState::Renaming | ControllerState::EditingSessionInfo => {
+                self.process_event_text_entry_state(event)?;
+            }
             _ => {
                 warn!("Received event in unexpected state: {:?}", self.state);
             }
@@ -196,19 +698,19 @@ impl Controller {
     
     /// Process events when in navigating state
     fn process_event_navigating_state(&mut self, event: driver::MidiEvent) -> Result<()> {
-        const DELTA_THRESHOLD: f32 = 256.0;
+        let delta_threshold = self.knob_threshold(256.0);
         
         if let driver::MidiEvent::ControlChange { channel, control, value } = event {
             if let Some(config) = &self.base_control_config {
                 // Check if it's the main knob
                 if config.main_knob.channel == channel && config.main_knob.control == control {
-                    if let Some(direction) = Self::process_knob_value(value, &mut self.main_knob_accumulator, DELTA_THRESHOLD) {
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.main_knob_accumulator, delta_threshold) {
                         self.ui.navigate_grid(NavigationLevel::Main, direction)?;
                     }
                 }
                 // Check if it's the secondary knob
                 else if config.secondary_knob.channel == channel && config.secondary_knob.control == control {
-                    if let Some(direction) = Self::process_knob_value(value, &mut self.secondary_knob_accumulator, DELTA_THRESHOLD) {
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.secondary_knob_accumulator, delta_threshold) {
                         self.ui.navigate_grid(NavigationLevel::Secondary, direction)?;
                     }
                 }
@@ -218,74 +720,25 @@ impl Controller {
                         debug!("Selected grid element: {:?}", element);
                         
                         match element {
-                            crate::ui::GridElement::Link(ref from_id, ref to_id, _) => {
-                                // Store as Element for backwards compatibility
-                                self.current_element = Some(crate::ui::Element::Link(from_id.clone(), to_id.clone(), crate::ui::LinkType::Normal));
-                                
-                                // Build menu options based on link endpoints
-                                let mut options = Vec::new();
-                                
-                                if from_id == "inputs" {
-                                    options.push(crate::ui::MenuOption {
-                                        id: "add_input".to_string(),
-                                        label: "Add Input >".to_string(),
-                                    });
-                                }
-                                
-                                if to_id == "outputs" {
-                                    options.push(crate::ui::MenuOption {
-                                        id: "add_output".to_string(),
-                                        label: "Add Output >".to_string(),
-                                    });
-                                }
-                                
-                                // Add plugin option if upstream is not "inputs"
-                                if from_id != "inputs" {
-                                    options.push(crate::ui::MenuOption {
-                                        id: "add_plugin".to_string(),
-                                        label: "Add Plugin >".to_string(),
-                                    });
-                                }
-                                
-                                // Add File option (always available)
-                                options.push(crate::ui::MenuOption {
-                                    id: "file".to_string(),
-                                    label: "File >".to_string(),
-                                });
-                                
-                                // Open menu if we have at least one option
-                                if !options.is_empty() {
-                                    let menu = crate::ui::Menu {
-                                        id: format!("link_{}_{}", from_id, to_id),
-                                        label: format!("{} → {}", from_id, to_id),
-                                        options,
-                                    };
-                                    self.ui.open_menu(menu)?;
-                                    self.state = ControllerState::BrowsingMenu;
-                                }
+                            crate::ui::GridElement::Link(from_id, to_id, _) => {
+                                let menu_id = format!("link_{}_{}", from_id, to_id);
+                                let label = format!("{} → {}", from_id, to_id);
+                                self.open_context_menu(crate::ui::Element::Link(from_id, to_id, crate::ui::LinkType::Normal), menu_id, label)?;
                             }
-                            crate::ui::GridElement::Node(ref node_id) => {
-                                // Store as Element for backwards compatibility \n                                self.current_element = Some(crate::ui::Element::Node(node_id.clone()));
-                                
-                                // For node elements, only show File menu
-                                let menu = crate::ui::Menu {
-                                    id: "node_menu".to_string(),
-                                    label: "Node".to_string(),
-                                    options: vec![
-                                        crate::ui::MenuOption {
-                                            id: "file".to_string(),
-                                            label: "File >".to_string(),
-                                        },
-                                    ],
-                                };
-                                self.ui.open_menu(menu)?;
-                                self.state = ControllerState::BrowsingMenu;
+                            crate::ui::GridElement::Node(node_id) => {
+                                self.open_context_menu(crate::ui::Element::Node(node_id), "node_menu".to_string(), "Node".to_string())?;
                             }
                         }
                     }
                 }
                 // Check if it's the back button (button press: value > 0)
                 else if config.back_button.channel == channel && config.back_button.control == control && value > 0 {
+                    // Back leaves an open group
+                    if let Some(group) = &self.group_feature {
+                        if group.leave()? {
+                            return Ok(());
+                        }
+                    }
                     if let Err(e) = self.ui.close_menu() {
                         debug!("No menu to close: {}", e);
                     }
@@ -298,17 +751,60 @@ impl Controller {
     
     /// Process events when in browsing menu state
     fn process_event_browsing_menu_state(&mut self, event: driver::MidiEvent) -> Result<()> {
-        const DELTA_THRESHOLD: f32 = 256.0;
+        // The open feature gets the first look at every event, or the features with entries in the open context menu
+        if let Some(controls) = self.base_control_config.clone() {
+            let in_context_menu = self.context_menu_id.is_some()
+                && self.ui.select_menu()?.is_some_and(|f| self.context_menu_id.as_deref() == Some(f.menu_id.as_str()));
+            let kinds: Vec<FeatureKind> = if in_context_menu {
+                FeatureKind::ALL.into_iter().filter(|kind| self.context_entries.values().any(|k| k == kind)).collect()
+            } else {
+                self.current_feature.into_iter().collect()
+            };
+            let element = self.current_element.clone();
+            for kind in kinds {
+                if let Some(feature) = self.feature_mut(kind) {
+                    if feature.handle_midi_event(&event, &controls, element.as_ref())? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        
+        let delta_threshold = self.knob_threshold(256.0);
         
         if let driver::MidiEvent::ControlChange { channel, control, value } = event {
             if let Some(config) = &self.base_control_config {
                 // Check if it's the main knob (navigate menu options)
                 if config.main_knob.channel == channel && config.main_knob.control == control {
-                    if let Some(direction) = Self::process_knob_value(value, &mut self.main_knob_accumulator, DELTA_THRESHOLD) {
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.main_knob_accumulator, delta_threshold) {
                         self.ui.navigate_menu(direction)?;
                     }
                 }
-                // Secondary knob is not used in menu browsing
+                // Check if it's the secondary knob (jumps in long menus)
+                else if config.secondary_knob.channel == channel && config.secondary_knob.control == control {
+                    let focused = self.ui.select_menu()?;
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.secondary_knob_accumulator, delta_threshold) {
+                        match focused {
+                            Some(focused) => {
+                                // Features with pages rebuild their menu, the others jump in it
+                                let turned = match self.current_feature_mut() {
+                                    Some(feature) => feature.turn_page(&focused.menu_id, direction.clone())?,
+                                    None => false,
+                                };
+                                if turned {
+                                    if let Some(feature) = self.current_feature() {
+                                        let menu = feature.get_menu();
+                                        self.ui.close_menu()?;
+                                        self.ui.open_menu(menu)?;
+                                    }
+                                } else {
+                                    self.ui.jump_menu(direction)?;
+                                }
+                            }
+                            None => self.ui.jump_menu(direction)?,
+                        }
+                    }
+                }
                 // Check if it's the selection button (select menu option)
                 else if config.selection_button.channel == channel && config.selection_button.control == control && value > 0 {
                     if let Some(menu_option) = self.ui.select_menu()? {
@@ -316,39 +812,17 @@ impl Controller {
                         
                         let option_id = &menu_option.option_id;
                         
-                        // Handle special link menu options
-                        if option_id == "add_input" {
-                            self.current_feature = self.input_feature.as_mut().map(|f| f as *mut dyn Feature);
-                            // Open the input feature menu on top of the link menu
-                            if let Some(feature) = self.current_feature() {
-                                let menu = feature.get_menu();
-                                self.ui.open_menu(menu)?;
-                            }
-                            return Ok(());
-                        } else if option_id == "add_output" {
-                            self.current_feature = self.output_feature.as_mut().map(|f| f as *mut dyn Feature);
-                            // Open the output feature menu on top of the link menu
-                            if let Some(feature) = self.current_feature() {
-                                let menu = feature.get_menu();
-                                self.ui.open_menu(menu)?;
-                            }
-                            return Ok(());
-                        } else if option_id == "add_plugin" {
-                            self.current_feature = self.plugin_feature.as_mut().map(|f| f as *mut dyn Feature);
-                            // Open the plugin feature menu on top of the link menu
-                            if let Some(feature) = self.current_feature() {
-                                let menu = feature.get_menu();
-                                self.ui.open_menu(menu)?;
-                            }
-                            return Ok(());
-                        } else if option_id == "file" {
-                            self.current_feature = self.persistence_feature.as_mut().map(|f| f as *mut dyn Feature);
-                            // Open the file feature menu on top of the current menu
-                            if let Some(feature) = self.current_feature() {
-                                let menu = feature.get_menu();
-                                self.ui.open_menu(menu)?;
+                        // Context menu entries go to the feature or controller action that contributed them
+                        if self.context_menu_id.as_deref() == Some(menu_option.menu_id.as_str()) {
+                            if let Some(kind) = self.context_entries.get(option_id).copied() {
+                                self.current_feature = Some(kind);
+                                let current_elem = self.current_element.clone();
+                                let next_state = match self.feature_mut(kind) {
+                                    Some(feature) => feature.select_context_entry(option_id, current_elem.as_ref())?,
+                                    None => ControllerState::Navigating,
+                                };
+                                return self.enter_feature_state(next_state);
                             }
-                            return Ok(());
                         }
                         
                         // Handle menu option through the current active feature
@@ -358,42 +832,190 @@ impl Controller {
                         } else {
                             ControllerState::Navigating
                         };
-                        
-                        match next_state {
-                            ControllerState::BrowsingMenu => {
-                                // Open the next menu from the current feature
-                                if let Some(feature) = self.current_feature() {
-                                    let menu = feature.get_menu();
-                                    self.ui.open_menu(menu)?;
-                                }
-                            }
-                            ControllerState::Navigating => {
-                                // Close all menus and return to navigating
-                                self.ui.close_all_menus()?;
-                                self.current_feature = None;
-                                self.current_element = None;
-                                self.state = ControllerState::Navigating;
-                            }
-                            _ => {
-                                // For other states, just transition
-                                self.state = next_state;
-                            }
-                        }
+                        self.enter_feature_state(next_state)?;
                     }
                 }
                 // Check if it's the back button (close menu and return to Navigating state)
+                else if config.back_button.channel == channel && config.back_button.control == control && value > 0 && self.ui.back()? {
+                    // The feature learns its menu was closed, even the last one
+                    let current_elem = self.current_element.clone();
+                    if let Some(feature) = self.current_feature_mut() {
+                        feature.handle_menu_option(None, current_elem.as_ref())?;
+                    }
+
+                    // If no more menus, return to Navigating
+                    if !self.ui.is_menu_open() {
+                        self.current_feature = None;
+                        self.state = ControllerState::Navigating;
+                    }
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Open the context menu of a link or node, composed of the entries of the features
+    fn open_context_menu(&mut self, element: crate::ui::Element, menu_id: String, label: String) -> Result<()> {
+        let mut entries: Vec<(feature::ContextEntry, FeatureKind)> = Vec::new();
+        for kind in FeatureKind::ALL {
+            if let Some(feature) = self.feature(kind) {
+                entries.extend(feature.context_entries(&element).into_iter().map(|entry| (entry, kind)));
+            }
+        }
+        entries.sort_by_key(|(entry, _)| entry.order);
+        
+        self.context_entries = entries.iter()
+            .map(|(entry, kind)| (entry.option.id.clone(), *kind))
+            .collect();
+        self.context_menu_id = Some(menu_id.clone());
+        self.current_element = Some(element);
+        
+        // Open menu if we have at least one option
+        if !entries.is_empty() {
+            let menu = crate::ui::Menu {
+                id: menu_id,
+                label,
+                options: entries.into_iter().map(|(entry, _)| entry.option).collect(),
+            };
+            self.ui.open_menu(menu)?;
+            self.state = ControllerState::BrowsingMenu;
+        }
+        Ok(())
+    }
+    
+    /// Move to the state returned by a feature, opening its next menu or closing all menus
+    fn enter_feature_state(&mut self, next_state: ControllerState) -> Result<()> {
+        match next_state {
+            ControllerState::BrowsingMenu => {
+                // Open the next menu from the current feature
+                if let Some(feature) = self.current_feature() {
+                    let menu = feature.get_menu();
+                    self.ui.open_menu(menu)?;
+                }
+            }
+            ControllerState::Navigating => {
+                // Close all menus and return to navigating
+                self.ui.close_all_menus()?;
+                self.current_feature = None;
+                self.current_element = None;
+                self.state = ControllerState::Navigating;
+            }
+            ControllerState::Performing => {
+                // Close all menus and hand the knobs over to the performance feature
+                self.ui.close_all_menus()?;
+                self.current_feature = None;
+                self.current_element = None;
+                if let Some(performance) = &self.performance_feature {
+                    performance.start()?;
+                }
+                self.state = ControllerState::Performing;
+            }
+            ControllerState::Renaming | ControllerState::EditingSessionInfo => {
+                // Close all menus, the knobs edit the text shown by the feature
+                self.ui.close_all_menus()?;
+                self.current_feature = None;
+                self.current_element = None;
+                self.state = next_state;
+            }
+            ControllerState::LearningCrossfadeKnob | ControllerState::LearningMuteButton | ControllerState::LearningMacroKnob
+            | ControllerState::LearningBookmarkButton | ControllerState::LearningNextButton | ControllerState::LearningPreviousButton
+            | ControllerState::LearningTapButton | ControllerState::LearningSceneButton | ControllerState::LearningPanicButton => {
+                // Close all menus and wait for the control to learn
+                self.ui.close_all_menus()?;
+                self.current_feature = None;
+                self.current_element = None;
+                self.state = next_state;
+            }
+            _ => {
+                // For other states, just transition
+                self.state = next_state;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Process events when in performing state
+    /// Knobs drive the mapped parameters, the selection button switches banks
+    /// and the back button returns to navigation
+    fn process_event_performing_state(&mut self, event: driver::MidiEvent) -> Result<()> {
+        let delta_threshold = self.knob_threshold(64.0);
+        let curve = self.knob_acceleration_curve();
+        
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            if let Some(config) = &self.base_control_config {
+                let Some(performance) = self.performance_feature.as_mut() else {
+                    return Ok(());
+                };
+                
+                // Check if it's the main knob
+                if config.main_knob.channel == channel && config.main_knob.control == control {
+                    let factor = self.main_knob_acceleration.factor(Instant::now(), curve);
+                    if let Some((direction, steps)) = knob::accelerated_steps(value, &mut self.main_knob_accumulator, delta_threshold, factor) {
+                        performance.adjust(feature::KnobSlot::Main, direction, steps)?;
+                    }
+                }
+                // Check if it's the secondary knob
+                else if config.secondary_knob.channel == channel && config.secondary_knob.control == control {
+                    let factor = self.secondary_knob_acceleration.factor(Instant::now(), curve);
+                    if let Some((direction, steps)) = knob::accelerated_steps(value, &mut self.secondary_knob_accumulator, delta_threshold, factor) {
+                        performance.adjust(feature::KnobSlot::Secondary, direction, steps)?;
+                    }
+                }
+                // Check if it's the selection button (switch bank)
+                else if config.selection_button.channel == channel && config.selection_button.control == control && value > 0 {
+                    performance.next_bank()?;
+                }
+                // Check if it's the back button (leave performance mode)
                 else if config.back_button.channel == channel && config.back_button.control == control && value > 0 {
-                    if self.ui.back()? {
-                        // If no more menus, return to Navigating
-                        if !self.ui.is_menu_open() {
-                            self.current_feature = None;
-                            self.state = ControllerState::Navigating;
+                    performance.stop()?;
+                    self.state = ControllerState::Navigating;
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Process events when in a text entry state (renaming or editing session notes and tags)
+    /// The main knob scrolls characters, the secondary knob moves the cursor,
+    /// the selection button applies the text and the back button cancels
+    fn process_event_text_entry_state(&mut self, event: driver::MidiEvent) -> Result<()> {
+        let char_threshold = self.knob_threshold(64.0);
+        let cursor_threshold = self.knob_threshold(256.0);
+        
+        if let driver::MidiEvent::ControlChange { channel, control, value } = event {
+            if let Some(config) = self.base_control_config.clone() {
+                let state = self.state.clone();
+                
+                // Check if it's the main knob
+                if config.main_knob.channel == channel && config.main_knob.control == control {
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.main_knob_accumulator, char_threshold) {
+                        if let Some(editor) = self.text_editor(&state) {
+                            editor.scroll(direction)?;
                         }
-
-                        let current_elem = self.current_element.clone();
-                        if let Some(feature) = self.current_feature_mut() {
-                            feature.handle_menu_option(None, current_elem.as_ref())?;
-                        }                        
+                    }
+                }
+                // Check if it's the secondary knob
+                else if config.secondary_knob.channel == channel && config.secondary_knob.control == control {
+                    if let Some(direction) = Self::process_knob_value(value, &mut self.secondary_knob_accumulator, cursor_threshold) {
+                        if let Some(editor) = self.text_editor(&state) {
+                            editor.move_cursor(direction)?;
+                        }
+                    }
+                }
+                // Check if it's the selection button (apply the text)
+                else if config.selection_button.channel == channel && config.selection_button.control == control && value > 0 {
+                    self.state = ControllerState::Navigating;
+                    if let Some(editor) = self.text_editor(&state) {
+                        editor.confirm()?;
+                    }
+                }
+                // Check if it's the back button (cancel)
+                else if config.back_button.channel == channel && config.back_button.control == control && value > 0 {
+                    self.state = ControllerState::Navigating;
+                    if let Some(editor) = self.text_editor(&state) {
+                        editor.cancel()?;
                     }
                 }
             }
@@ -402,6 +1024,25 @@ impl Controller {
         Ok(())
     }
     
+    /// Get the feature editing a text in a text entry state
+    fn text_editor(&mut self, state: &ControllerState) -> Option<&mut dyn TextEditor> {
+        match state {
+            ControllerState::Renaming => self.rename_feature.as_mut().map(|f| f as &mut dyn TextEditor),
+            ControllerState::EditingSessionInfo => self.persistence_feature.as_mut().map(|f| f as &mut dyn TextEditor),
+            _ => None,
+        }
+    }
+    
+    /// Scale a knob threshold by the configured sensitivity
+    fn knob_threshold(&self, base: f32) -> f32 {
+        knob::threshold(base, self.settings.lock().unwrap().knob_sensitivity)
+    }
+    
+    /// Get the acceleration curve of the knobs editing parameters
+    fn knob_acceleration_curve(&self) -> f32 {
+        self.settings.lock().unwrap().knob_acceleration.max(0.0)
+    }
+    
     /// Process knob value and return navigation direction if threshold is reached
     fn process_knob_value(value: u8, accumulator: &mut f32, threshold: f32) -> Option<KnobDirection> {
         let delta = if value >= 64 {
@@ -425,29 +1066,752 @@ impl Controller {
         }
     }
     
+    /// Check whether an event is a press of the tap tempo button
+    fn is_tap_button(&self, event: &driver::MidiEvent) -> bool {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return false;
+        };
+        self.base_control_config.as_ref()
+            .and_then(|config| config.tap_button.as_ref())
+            .is_some_and(|tap| tap.channel == channel && tap.control == control && value > 0)
+    }
+    
+    /// Check whether the event is a press of the scene footswitch
+    fn is_scene_button(&self, event: &driver::MidiEvent) -> bool {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return false;
+        };
+        self.base_control_config.as_ref()
+            .and_then(|config| config.scene_button.as_ref())
+            .is_some_and(|scene| scene.channel == channel && scene.control == control && value > 0)
+    }
+    
+    /// Get the set list step of the event if it is a press of the next or previous button
+    fn set_list_step(&self, event: &driver::MidiEvent) -> Option<isize> {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return None;
+        };
+        let config = self.base_control_config.as_ref()?;
+        let pressed = |button: Option<&MidiAssignment>| {
+            button.is_some_and(|b| b.channel == channel && b.control == control && value > 0)
+        };
+        if pressed(config.next_button.as_ref()) {
+            Some(1)
+        } else if pressed(config.previous_button.as_ref()) {
+            Some(-1)
+        } else {
+            None
+        }
+    }
+    
+    /// Load the session of a set list item and show the one coming next
+    fn play_set_list_item(&mut self, mnemonic: &str) -> Result<()> {
+        if let Some(persistence) = self.persistence_feature.as_mut() {
+            if let Err(e) = persistence.load_session(mnemonic) {
+                self.ui.show_message(&e.to_string())?;
+            }
+        }
+        match &self.setlist_feature {
+            Some(setlist) => setlist.show_up_next(),
+            None => Ok(()),
+        }
+    }
+    
+    /// Load the set list item selected in its menu
+    fn update_set_list(&mut self) -> Result<()> {
+        match self.setlist_feature.as_mut().and_then(|setlist| setlist.take_pending_load()) {
+            Some(mnemonic) => self.play_set_list_item(&mnemonic),
+            None => Ok(()),
+        }
+    }
+    
+    /// Check whether the event is a press of the panic button
+    fn is_panic_button(&self, event: &driver::MidiEvent) -> bool {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return false;
+        };
+        self.base_control_config.as_ref()
+            .and_then(|config| config.panic_button.as_ref())
+            .is_some_and(|panic| panic.channel == channel && panic.control == control && value > 0)
+    }
+    
+    /// Get the crossfade position if the event comes from the crossfade knob
+    /// The crossfade knob is an absolute control, 0 is the first chain and 127 the second
+    fn crossfade_position(&self, event: &driver::MidiEvent) -> Option<f32> {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return None;
+        };
+        let knob = self.base_control_config.as_ref()?.crossfade_knob.as_ref()?;
+        if knob.channel == channel && knob.control == control {
+            Some(value as f32 / 127.0)
+        } else {
+            None
+        }
+    }
+    
+    /// Focus a numbered node, or open its context menu when it already has the focus
+    fn jump_to_node(&mut self, node: String) -> Result<()> {
+        let graph = self.engine.get_graph()?;
+        let exists = graph.blocks.iter().any(|b| b.id == node) || feature::mixer::port_nodes(&graph).contains(&node);
+        if !exists {
+            let name = node.rsplit('/').next().unwrap_or(&node).to_string();
+            return self.ui.show_message(&format!("{} is gone", name));
+        }
+        let focused = matches!(self.ui.select_grid()?, Some(crate::ui::GridElement::Node(id)) if id == node);
+        if focused {
+            self.open_context_menu(crate::ui::Element::Node(node), "node_menu".to_string(), "Node".to_string())
+        } else {
+            debug!("Jumping to {}", node);
+            self.ui.focus_node(node)
+        }
+    }
+    
+    /// Get the macro and its position from a macro knob event, for the macros that still exist
+    fn macro_position(&self, event: &driver::MidiEvent) -> Option<(String, f32)> {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return None;
+        };
+        let macros = self.macro_feature.as_ref()?;
+        self.base_control_config.as_ref()?.macro_knobs.iter()
+            .find(|(name, knob)| knob.channel == channel && knob.control == control && macros.has_macro(name))
+            .map(|(name, _)| (name.clone(), value as f32 / 127.0))
+    }
+    
+    /// Get the bookmark slot of a bookmark button press
+    fn bookmark_button_slot(&self, event: &driver::MidiEvent) -> Option<u8> {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return None;
+        };
+        if value == 0 {
+            return None;
+        }
+        self.base_control_config.as_ref()?.bookmark_buttons.iter()
+            .find(|(_, button)| button.channel == channel && button.control == control)
+            .map(|(slot, _)| *slot)
+    }
+    
+    /// Get the mixer fader of a mute button press
+    fn mute_button_fader(&self, event: &driver::MidiEvent) -> Option<String> {
+        let driver::MidiEvent::ControlChange { channel, control, value } = *event else {
+            return None;
+        };
+        if value == 0 {
+            return None;
+        }
+        self.base_control_config.as_ref()?.mute_buttons.iter()
+            .find(|(_, button)| button.channel == channel && button.control == control)
+            .map(|(fader, _)| fader.clone())
+    }
+    
+    /// Refresh the tempo shown in the UI when the clock or tapped tempo changes
+    fn update_tempo_display(&mut self) -> Result<()> {
+        let bpm = self.tempo.get_bpm();
+        let tempo = bpm.map(|b| (b * 10.0).round() as i32);
+        if tempo != self.displayed_tempo {
+            self.displayed_tempo = tempo;
+            self.ui.set_tempo(bpm)?;
+        }
+        Ok(())
+    }
+    
+    /// Refresh the recording time shown in the UI every second
+    fn update_recording_display(&mut self) -> Result<()> {
+        let elapsed = self.record_feature.as_ref().and_then(|f| f.elapsed());
+        let seconds = elapsed.map(|e| e.as_secs());
+        if seconds != self.displayed_recording {
+            self.displayed_recording = seconds;
+            self.ui.set_recording(elapsed)?;
+        }
+        Ok(())
+    }
+    
+    /// Refresh the sample rate, buffer size and latency shown in the UI when they change
+    fn update_audio_status_display(&mut self) -> Result<()> {
+        let Ok(status) = self.driver.get_audio_status() else {
+            return Ok(());
+        };
+        if Some(status) != self.displayed_audio_status {
+            self.displayed_audio_status = Some(status);
+            self.ui.set_audio_status(status.sample_rate, status.buffer_size, status.latency_ms())?;
+        }
+        Ok(())
+    }
+    
+    /// Refresh the DSP load meter when the load changes
+    fn update_dsp_load_display(&mut self) -> Result<()> {
+        let Ok(load) = self.driver.get_dsp_load() else {
+            return Ok(());
+        };
+        let percent = load.round().max(0.0) as u32;
+        if Some(percent) != self.displayed_dsp_load {
+            self.displayed_dsp_load = Some(percent);
+            self.ui.set_dsp_load(percent)?;
+        }
+        Ok(())
+    }
+    
+    /// Start or stop the analyzer following its setting and show the latest spectrum
+    fn update_analyzer(&mut self) -> Result<()> {
+        let enabled = self.settings.lock().unwrap().analyzer;
+        if enabled != self.analyzer.is_running() {
+            if enabled {
+                if let Err(e) = self.analyzer.start() {
+                    // Do not retry on every loop
+                    self.settings.lock().unwrap().analyzer = false;
+                    self.ui.show_message("Analyzer unavailable")?;
+                    return Err(e);
+                }
+            } else {
+                self.analyzer.stop();
+                self.ui.set_spectrum(None)?;
+            }
+        }
+        if let Some(bands) = self.analyzer.spectrum() {
+            self.ui.set_spectrum(Some(bands))?;
+        }
+        Ok(())
+    }
+    
+    /// Refresh the xrun counter, alerting when xruns occur during performance
+    fn update_xrun_display(&mut self) -> Result<()> {
+        let count = self.driver.xrun_count();
+        if count == self.displayed_xruns {
+            return Ok(());
+        }
+        if count > self.displayed_xruns && self.state == ControllerState::Performing {
+            self.ui.show_message(&format!("Xrun! ({} so far)", count))?;
+        }
+        self.displayed_xruns = count;
+        self.ui.set_xruns(count)
+    }
+    
+    /// Send the new log entries to the log panel
+    fn update_log_panel(&mut self) -> Result<()> {
+        let entries = crate::logging::entries_since(self.displayed_log_sequence);
+        let Some(last) = entries.last() else {
+            return Ok(());
+        };
+        self.displayed_log_sequence = last.sequence + 1;
+        self.ui.append_log(entries.into_iter()
+            .map(|entry| (entry.level.as_str().to_lowercase(), entry.message))
+            .collect())
+    }
+    
+    /// Show or hide the inspector following its setting and send it the new protocol messages and raw state
+    fn update_inspector(&mut self) -> Result<()> {
+        let enabled = self.settings.lock().unwrap().inspector;
+        if enabled != self.inspector_visible {
+            self.inspector_visible = enabled;
+            self.ui.set_inspector_panel(enabled)?;
+            if enabled {
+                // Start with the current state rather than the one read last
+                self.engine.get_raw_state()?;
+            }
+        }
+        if !enabled {
+            return Ok(());
+        }
+        let messages = crate::engine::trace::messages_since(self.displayed_protocol_sequence);
+        if let Some(last) = messages.last() {
+            self.displayed_protocol_sequence = last.sequence + 1;
+            self.ui.append_protocol_messages(messages.into_iter()
+                .map(|message| (message.time.format("%H:%M:%S%.3f").to_string(), message.outgoing, crate::engine::trace::preview(&message.text)))
+                .collect())?;
+        }
+        if let Some(state) = crate::engine::trace::last_state() {
+            if self.displayed_state_sequence != Some(state.sequence) {
+                self.displayed_state_sequence = Some(state.sequence);
+                self.ui.set_inspector_state(&state.time.format("%H:%M:%S%.3f").to_string(), &crate::engine::trace::pretty_turtle(&state.text))?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Compare the UI with the engine graph and repair the differences, returning their number
+    fn reconcile(&mut self) -> Result<usize> {
+        // Other engine clients may have changed the graph since it was cached
+        self.engine.invalidate_graph();
+        let graph = self.engine.get_graph()?;
+        let drift = reconcile::diff(&graph, &self.ui.graph_model(), &feature::group::view_scope(&self.ui));
+        if !drift.is_empty() {
+            drift.report();
+            drift.repair(&self.ui)?;
+            self.ui.show_message(&format!("Repaired {} differences with the engine", drift.len()))?;
+        }
+        Ok(drift.len())
+    }
+    
+    /// Reconcile the UI with the engine when the configured interval has elapsed
+    fn update_reconcile(&mut self) -> Result<()> {
+        let seconds = self.settings.lock().unwrap().reconcile_seconds;
+        if seconds == 0 || self.last_reconcile.elapsed() < Duration::from_secs(seconds as u64) {
+            return Ok(());
+        }
+        self.last_reconcile = Instant::now();
+        
+        // Features may be in the middle of changing the graph while their menus are open
+        if self.state != ControllerState::Navigating {
+            return Ok(());
+        }
+        self.reconcile().map(|_| ())
+    }
+    
+    /// Warn about the blocks whose signal newly reaches no output, every couple of seconds
+    fn update_orphans(&mut self) -> Result<()> {
+        if self.last_orphan_check.elapsed() < Duration::from_secs(2) || self.state != ControllerState::Navigating {
+            return Ok(());
+        }
+        self.last_orphan_check = Instant::now();
+        
+        let graph = self.engine.get_graph()?;
+        let orphans = topology::orphans(&graph);
+        let new_orphans: Vec<&String> = orphans.iter().filter(|o| !self.orphans.contains(o)).collect();
+        if !new_orphans.is_empty() {
+            let names: Vec<&str> = new_orphans.iter()
+                .map(|o| graph.blocks.iter().find(|b| &&b.id == o).map_or(o.as_str(), |b| b.name.as_str()))
+                .collect();
+            warn!("No path to an output from {}", new_orphans.iter().map(|o| o.as_str()).collect::<Vec<_>>().join(", "));
+            self.ui.show_message(&format!("No path to an output from {}", names.join(", ")))?;
+        }
+        self.orphans = orphans;
+        Ok(())
+    }
+    
+    /// Insert or remove the safety limiters to follow the setting every second
+    /// New outputs and loaded sessions get their limiters right away, this catches the outputs added otherwise
+    fn update_safety_limiters(&mut self) -> Result<()> {
+        if self.last_limiter_check.elapsed() < Duration::from_secs(1) || self.state != ControllerState::Navigating {
+            return Ok(());
+        }
+        self.last_limiter_check = Instant::now();
+        
+        let (enabled, plugin_uri) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.safety_limiter, settings.safety_limiter_uri.clone())
+        };
+        match feature::mixer::update_limiters(&self.engine, &plugin_uri, enabled) {
+            Ok(0) => Ok(()),
+            Ok(count) if enabled => self.ui.show_message(&format!("Safety limiters added: {}", count)),
+            Ok(count) => self.ui.show_message(&format!("Safety limiters removed: {}", count)),
+            Err(e) => {
+                // Do not retry on every check
+                self.settings.lock().unwrap().safety_limiter = false;
+                self.ui.show_message("Safety limiter unavailable")?;
+                Err(e)
+            }
+        }
+    }
+    
+    /// Show the latency added by each node and the latency at the outputs, every second
+    /// The outputs of the main and the monitor engines include the round trip of the audio interface
+    fn update_latency_display(&mut self) -> Result<()> {
+        if self.last_latency_check.elapsed() < Duration::from_secs(1) {
+            return Ok(());
+        }
+        self.last_latency_check = Instant::now();
+        let Ok(status) = self.driver.get_audio_status() else {
+            return Ok(());
+        };
+        
+        use crate::engine::PortDirection;
+        
+        let graph = self.engine.get_graph()?;
+        let mut latencies = HashMap::new();
+        for block in &graph.blocks {
+            let Some(symbol) = self.engine.get_block_plugin(&block.id).and_then(|plugin| plugin.latency_port) else {
+                continue;
+            };
+            if let Some(frames) = self.engine.get_control_values(&block.id)?.get(&symbol) {
+                latencies.insert(block.id.clone(), frames.max(0.0) as u32);
+            }
+        }
+        let totals = topology::chain_latencies(&graph, &latencies);
+        let total = |id: &str| totals.get(id).copied().unwrap_or(0);
+        let ms = |frames: u32| frames as f32 * 1000.0 / status.sample_rate as f32;
+        
+        let model = self.ui.graph_model();
+        let mut shown = HashMap::new();
+        for (id, node_type) in &model.nodes {
+            match node_type {
+                crate::ui::NodeType::Normal => {
+                    let own = match graph.blocks.iter().find(|b| &b.id == id) {
+                        // A group adds the latency of its slowest path
+                        Some(block) if block.prototype == crate::engine::GRAPH_PROTOTYPE => {
+                            let slowest = |direction: PortDirection| block.ports.iter()
+                                .filter(|p| p.direction == direction)
+                                .map(|p| total(&format!("{}/{}", id, p.id)))
+                                .max()
+                                .unwrap_or(0);
+                            slowest(PortDirection::Output).saturating_sub(slowest(PortDirection::Input))
+                        }
+                        _ => latencies.get(id).copied().unwrap_or(0),
+                    };
+                    if own > 0 {
+                        shown.insert(id.clone(), format!("+{:.1} ms", ms(own)));
+                    }
+                }
+                crate::ui::NodeType::PortOut => {
+                    let parent = id.rsplit_once('/').map(|(parent, _)| parent);
+                    let hardware = match parent {
+                        Some(feature::mixer::MAIN_GRAPH | crate::engine::split::MONITOR_GRAPH) => status.latency_ms(),
+                        _ => 0.0,
+                    };
+                    let latency = ms(total(id)) + hardware;
+                    if latency > 0.0 {
+                        shown.insert(id.clone(), format!("{:.1} ms", latency));
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        // Nodes gone from the view lose their latency with them
+        self.displayed_latencies.retain(|id, _| model.nodes.contains_key(id));
+        for (id, latency) in &shown {
+            if self.displayed_latencies.get(id) != Some(latency) {
+                self.ui.set_node_latency(id.clone(), Some(latency.clone()))?;
+            }
+        }
+        for id in self.displayed_latencies.keys().filter(|id| !shown.contains_key(*id)) {
+            self.ui.set_node_latency(id.clone(), None)?;
+        }
+        self.displayed_latencies = shown;
+        Ok(())
+    }
+    
+    /// Check that the engine answers when the configured interval has elapsed, reconnecting when it does not
+    fn update_health_check(&mut self) -> Result<()> {
+        let (seconds, timeout_ms) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.health_check_seconds, settings.health_check_timeout_ms)
+        };
+        if seconds == 0 || self.last_health_check.elapsed() < Duration::from_secs(seconds as u64) {
+            return Ok(());
+        }
+        
+        let result = self.engine.ping(Duration::from_millis(timeout_ms as u64));
+        let connected = match result {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Engine check failed: {}", e);
+                self.show_engine_status(false)?;
+                match self.engine.reconnect() {
+                    Ok(restarted) => {
+                        info!("Reconnected to the engine");
+                        if restarted {
+                            self.ui.show_message("Engine restarted")?;
+                            if let Some(persistence) = self.persistence_feature.as_mut() {
+                                persistence.reload_session()?;
+                            }
+                        }
+                        true
+                    }
+                    Err(e) => {
+                        warn!("Reconnecting to the engine failed: {}", e);
+                        false
+                    }
+                }
+            }
+        };
+        // Measured from the end, a failed reconnection having its own delays
+        self.last_health_check = Instant::now();
+        self.show_engine_status(connected)
+    }
+    
+    /// Show what the engine printed on its error output around a failed operation
+    fn show_engine_errors(&self) {
+        let lines = self.engine.take_error_output();
+        if !lines.is_empty() {
+            if let Err(e) = self.ui.show_message(&format!("Ingen: {}", lines.join(" · "))) {
+                warn!("Error showing the engine errors: {}", e);
+            }
+        }
+    }
+    
+    /// Show the engine status when it changes
+    fn show_engine_status(&mut self, connected: bool) -> Result<()> {
+        if self.displayed_engine_status != Some(connected) {
+            self.displayed_engine_status = Some(connected);
+            self.ui.set_engine_status(connected)?;
+        }
+        Ok(())
+    }
+    
+    /// Connect new MIDI controllers and rebuild menus listing JACK ports when ports come and go
+    fn handle_port_changes(&mut self) -> Result<()> {
+        let changes = self.driver.take_port_changes();
+        if changes.is_empty() {
+            return Ok(());
+        }
+        
+        for change in &changes {
+            if let Some(persistence) = self.persistence_feature.as_mut() {
+                if let Err(e) = persistence.port_registered(change) {
+                    warn!("Failed to reconnect {}: {}", change.port.name, e);
+                }
+            }
+            
+            let is_midi_source = change.registered && change.is_output
+                && change.port_type == driver::PortType::Midi;
+            if !is_midi_source || change.port.name.starts_with("TraxDub") {
+                continue;
+            }
+            match self.driver.connect_midi_input(&change.port) {
+                Ok(()) => info!("Listening to new MIDI input: {}", change.port.name),
+                Err(e) => warn!("Failed to connect {}: {}", change.port.name, e),
+            }
+        }
+        
+        // Replace the open device menu so it shows the current ports
+        if self.state == ControllerState::BrowsingMenu && self.ui.menu_stack_size() > 1 {
+            if let Some(feature) = self.current_feature() {
+                if feature.lists_jack_ports() {
+                    let menu = feature.get_menu();
+                    self.ui.close_menu()?;
+                    self.ui.open_menu(menu)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Replace the open MIDI monitor menu when new messages came or an indicator changed
+    fn update_midi_monitor(&mut self) -> Result<()> {
+        if self.state != ControllerState::BrowsingMenu || self.current_feature != Some(FeatureKind::Monitor) {
+            return Ok(());
+        }
+        let Some(monitor) = self.monitor_feature.as_mut() else {
+            return Ok(());
+        };
+        if monitor.take_changed() {
+            let menu = monitor.get_menu();
+            self.ui.close_menu()?;
+            self.ui.open_menu(menu)?;
+        }
+        Ok(())
+    }
+    
+    /// Drive the controller with commands read from stdin or sent by remote frontends
+    pub fn attach_commands(&mut self, receiver: std::sync::mpsc::Receiver<repl::ReplCommand>) {
+        self.command_receiver = Some(receiver);
+    }
+    
+    /// Process the events of a recording instead of the MIDI inputs
+    pub fn attach_replay(&mut self, events: Vec<replay::TimedEvent>) {
+        self.replay_events = Some(events);
+    }
+    
+    /// Record the processed MIDI events
+    pub fn attach_recorder(&mut self, recorder: replay::EventRecorder) {
+        self.event_recorder = Some(recorder);
+    }
+    
+    /// Process the commands received since the last loop
+    fn process_commands(&mut self) -> Result<()> {
+        let commands: Vec<repl::ReplCommand> = match &self.command_receiver {
+            Some(receiver) => receiver.try_iter().collect(),
+            None => return Ok(()),
+        };
+        for command in commands {
+            self.process_command(command)?;
+        }
+        Ok(())
+    }
+    
+    /// Turn a command into the MIDI events of the learned base controls
+    fn process_command(&mut self, command: repl::ReplCommand) -> Result<()> {
+        if command == repl::ReplCommand::State {
+            println!("{:?}", self.state);
+            return Ok(());
+        }
+        if command == repl::ReplCommand::Reconcile {
+            let differences = self.reconcile()?;
+            println!("{} differences with the engine repaired", differences);
+            return Ok(());
+        }
+        if let repl::ReplCommand::ControlChange { channel, control, value } = command {
+            return self.process_midi_event(driver::MidiEvent::ControlChange { channel, control, value });
+        }
+        if let repl::ReplCommand::Text(text) = &command {
+            let state = self.state.clone();
+            match self.text_editor(&state) {
+                Some(editor) => editor.set_text(text)?,
+                None => println!("No text entry open"),
+            }
+            return Ok(());
+        }
+        
+        let Some(config) = self.base_control_config.clone() else {
+            println!("Base controls not learned yet, use cc to simulate them");
+            return Ok(());
+        };
+        let press = |assignment: &MidiAssignment| driver::MidiEvent::ControlChange {
+            channel: assignment.channel,
+            control: assignment.control,
+            value: 127,
+        };
+        
+        match command {
+            repl::ReplCommand::Navigate { level, direction, steps } => {
+                let knob = match level {
+                    NavigationLevel::Main => &config.main_knob,
+                    NavigationLevel::Secondary => &config.secondary_knob,
+                };
+                // Relative knob values: below 64 turns forward, above 64 turns backward
+                let (value, preload) = match direction {
+                    KnobDirection::Forward => (63, f32::MIN),
+                    KnobDirection::Backward => (65, f32::MAX),
+                };
+                for _ in 0..steps {
+                    // Preload the accumulator so that a single detent always makes a step
+                    match level {
+                        NavigationLevel::Main => self.main_knob_accumulator = preload,
+                        NavigationLevel::Secondary => self.secondary_knob_accumulator = preload,
+                    }
+                    self.process_midi_event(driver::MidiEvent::ControlChange { channel: knob.channel, control: knob.control, value })?;
+                }
+                // Do not leave a preload behind for states that ignore the knob
+                self.main_knob_accumulator = 0.0;
+                self.secondary_knob_accumulator = 0.0;
+                Ok(())
+            }
+            repl::ReplCommand::Select => self.process_midi_event(press(&config.selection_button)),
+            repl::ReplCommand::Back => self.process_midi_event(press(&config.back_button)),
+            repl::ReplCommand::Tap => match &config.tap_button {
+                Some(tap) => self.process_midi_event(press(tap)),
+                None => {
+                    println!("No tap button learned");
+                    Ok(())
+                }
+            },
+            repl::ReplCommand::ControlChange { .. } | repl::ReplCommand::Text(_)
+            | repl::ReplCommand::State | repl::ReplCommand::Reconcile => Ok(()),
+        }
+    }
+    
     /// Run loop with signal handling for graceful shutdown
     pub fn run_until_signal(&mut self, running: Arc<AtomicBool>) -> Result<()> {
         debug!("Controller running in state: {:?}", self.state);
         
-        // Start MIDI receiver and get the event channel
-        let event_receiver = self.driver.start()?;
-        
-        self.driver.connect_all_midi_inputs()?;
+        // Start MIDI receiver and get the event channel, unless replaying a recording
+        let replaying = self.replay_events.is_some();
+        let event_receiver = match self.replay_events.take() {
+            Some(events) => replay::spawn(events),
+            None => {
+                let receiver = self.driver.start()?;
+                self.driver.connect_all_midi_inputs()?;
+                receiver
+            }
+        };
         
         // Note: All features are initialized in Controller::new()
         
         // Process events from the receiver until signal
         while running.load(Ordering::SeqCst) {
+            if let Err(e) = self.update_tempo_display() {
+                warn!("Error updating tempo display: {}", e);
+            }
+            if let Err(e) = self.update_recording_display() {
+                warn!("Error updating recording display: {}", e);
+            }
+            if let Err(e) = self.update_audio_status_display() {
+                warn!("Error updating audio status display: {}", e);
+            }
+            if let Err(e) = self.update_dsp_load_display() {
+                warn!("Error updating DSP load display: {}", e);
+            }
+            if let Err(e) = self.update_analyzer() {
+                warn!("Error updating analyzer: {}", e);
+            }
+            if let Err(e) = self.update_xrun_display() {
+                warn!("Error updating xrun display: {}", e);
+            }
+            if let Some(parameter) = self.parameter_feature.as_mut() {
+                if let Err(e) = parameter.update_tempo_sync() {
+                    warn!("Error updating tempo-synced parameters: {}", e);
+                }
+            }
+            if let Some(clip) = self.clip_feature.as_mut() {
+                if let Err(e) = clip.update() {
+                    warn!("Error checking clips: {}", e);
+                }
+            }
+            if let Some(persistence) = self.persistence_feature.as_mut() {
+                if let Err(e) = persistence.update_dirty() {
+                    warn!("Error checking for unsaved changes: {}", e);
+                }
+            }
+            if let Some(history) = self.history_feature.as_mut() {
+                if let Err(e) = history.update() {
+                    warn!("Error recording the focus history: {}", e);
+                }
+            }
+            if let Err(e) = self.ui.update_visits() {
+                warn!("Error recording the link visits: {}", e);
+            }
+            if let Err(e) = self.ui.announce_focus() {
+                warn!("Error announcing the focus: {}", e);
+            }
+            if let Err(e) = self.update_log_panel() {
+                warn!("Error updating log panel: {}", e);
+            }
+            if let Err(e) = self.update_inspector() {
+                warn!("Error updating inspector: {}", e);
+            }
+            if let Some(persistence) = self.persistence_feature.as_mut() {
+                if let Err(e) = persistence.autosave_if_due() {
+                    warn!("Error autosaving session: {}", e);
+                }
+            }
+            if let Err(e) = self.handle_port_changes() {
+                warn!("Error handling JACK port changes: {}", e);
+            }
+            if let Err(e) = self.update_health_check() {
+                warn!("Error checking the engine: {}", e);
+            }
+            if let Err(e) = self.update_reconcile() {
+                warn!("Error reconciling the UI with the engine: {}", e);
+            }
+            if let Err(e) = self.update_orphans() {
+                warn!("Error checking for blocks without output: {}", e);
+            }
+            if let Err(e) = self.update_safety_limiters() {
+                warn!("Error updating the safety limiters: {}", e);
+            }
+            if let Err(e) = self.update_latency_display() {
+                warn!("Error updating latency display: {}", e);
+            }
+            if let Err(e) = self.update_midi_monitor() {
+                warn!("Error updating MIDI monitor: {}", e);
+            }
+            if let Err(e) = self.update_set_list() {
+                warn!("Error playing set list item: {}", e);
+            }
+            if let Err(e) = self.process_commands() {
+                warn!("Error processing command: {}", e);
+                self.show_engine_errors();
+            }
+            
             match event_receiver.recv_timeout(Duration::from_millis(100)) {
                 Ok(event) => {
+                    if let Some(recorder) = self.event_recorder.as_mut() {
+                        if let Err(e) = recorder.record(&event) {
+                            warn!("Error recording MIDI event: {}", e);
+                        }
+                    }
                     if let Err(e) = self.process_midi_event(event) {
                         warn!("Error processing event: {}", e);
+                        self.show_engine_errors();
                     }
                 }
                 Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
                     // No event received, continue loop to check signal
                     continue;
                 }
+                Err(_) if replaying => {
+                    info!("End of the replayed events, shutting down");
+                    break;
+                }
                 Err(e) => {
                     error!("Event receiver error: {}", e);
                     break;
@@ -455,6 +1819,20 @@ impl Controller {
             }
         }
         
+        // Keep the session for "Resume Last Exit State" while the engine and JACK are still up
+        if let Some(persistence) = self.persistence_feature.as_ref() {
+            if persistence.is_dirty() {
+                warn!("Quitting with unsaved changes, they are kept in the exit snapshot");
+            }
+            if let Err(e) = persistence.save_exit_snapshot() {
+                warn!("Failed to save exit snapshot: {}", e);
+            }
+        }
+        
+        // Finish any running recording and click before the JACK clients go away
+        self.record_feature = None;
+        self.metronome_feature = None;
+        
         self.driver.close();
 
         debug!("Controller shutting down gracefully");
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use log::warn;
+use std::collections::{HashMap, HashSet};
+
+use crate::controller::feature::mixer::{MAIN_GRAPH, is_hidden_block, is_in_scope, visible_links_in};
+use crate::engine::{Graph, PortDirection};
+use crate::ui::model::GraphModel;
+use crate::ui::{LinkType, NodeType, UI};
+
+/// Differences between the engine graph and the nodes and links shown by the UI
+#[derive(Debug, Default, PartialEq)]
+pub struct Drift {
+    /// Engine nodes missing in the UI, with their label and type
+    pub missing_nodes: Vec<(String, String, NodeType)>,
+    /// UI nodes no longer in the engine
+    pub stale_nodes: Vec<String>,
+    /// Engine connections missing in the UI
+    pub missing_links: Vec<(String, String)>,
+    /// UI links between engine nodes that are not connected anymore
+    pub stale_links: Vec<(String, String)>,
+}
+
+impl Drift {
+    /// Check whether the UI matches the engine
+    pub fn is_empty(&self) -> bool {
+        self.missing_nodes.is_empty() && self.stale_nodes.is_empty()
+            && self.missing_links.is_empty() && self.stale_links.is_empty()
+    }
+
+    /// Number of differences
+    pub fn len(&self) -> usize {
+        self.missing_nodes.len() + self.stale_nodes.len() + self.missing_links.len() + self.stale_links.len()
+    }
+
+    /// Log each difference
+    pub fn report(&self) {
+        for (id, _, _) in &self.missing_nodes {
+            warn!("Node {} is in the engine but not in the UI", id);
+        }
+        for id in &self.stale_nodes {
+            warn!("Node {} is in the UI but not in the engine", id);
+        }
+        for (from_id, to_id) in &self.missing_links {
+            warn!("Connection {} -> {} is in the engine but not in the UI", from_id, to_id);
+        }
+        for (from_id, to_id) in &self.stale_links {
+            warn!("Link {} -> {} is in the UI but not in the engine", from_id, to_id);
+        }
+    }
+
+    /// Update the UI to match the engine
+    pub fn repair(&self, ui: &UI) -> Result<()> {
+        for id in &self.stale_nodes {
+            ui.remove_node(id.clone())?;
+        }
+        for (from_id, to_id) in &self.stale_links {
+            ui.remove_link(from_id.clone(), to_id.clone())?;
+        }
+        for (id, label, node_type) in &self.missing_nodes {
+            ui.create_node(id.clone(), label.clone(), node_type.clone())?;
+            // Attach system ports to their context node
+            match node_type {
+                NodeType::PortIn => ui.create_link("inputs".to_string(), id.clone(), LinkType::PortIn)?,
+                NodeType::PortOut => ui.create_link(id.clone(), "outputs".to_string(), LinkType::PortOut)?,
+                _ => {}
+            }
+        }
+        for (from_id, to_id) in &self.missing_links {
+            ui.create_link(from_id.clone(), to_id.clone(), LinkType::Normal)?;
+        }
+        ui.commit()
+    }
+}
+
+/// Compare the engine graph shown in a scope (the main graph or an open group) with the UI model
+/// Links to the context nodes (inputs and outputs) have no engine connection and are not compared,
+/// and the hidden bypass blocks are looked through like the UI does
+/// In a group, the group ports take the place of the system ports
+pub fn diff(graph: &Graph, model: &GraphModel, scope: &str) -> Drift {
+    let mut expected_nodes: HashMap<String, (String, NodeType)> = HashMap::new();
+    for block in graph.blocks.iter().filter(|b| is_in_scope(&b.id, scope) && !is_hidden_block(&b.id)) {
+        expected_nodes.insert(block.id.clone(), (block.name.clone(), NodeType::Normal));
+    }
+    let ports = if scope == MAIN_GRAPH {
+        graph.ports.as_slice()
+    } else {
+        graph.blocks.iter().find(|b| b.id == scope).map(|b| b.ports.as_slice()).unwrap_or_default()
+    };
+    for port in ports {
+        let node_type = match port.direction {
+            PortDirection::Input => NodeType::PortIn,
+            PortDirection::Output => NodeType::PortOut,
+        };
+        expected_nodes.insert(format!("{}/{}", scope, port.id), (port.id.clone(), node_type));
+    }
+    let expected_links: HashSet<(String, String)> = visible_links_in(graph, scope);
+
+    let mut drift = Drift::default();
+    for (id, (label, node_type)) in &expected_nodes {
+        if !model.nodes.contains_key(id) {
+            drift.missing_nodes.push((id.clone(), label.clone(), node_type.clone()));
+        }
+    }
+    for id in model.nodes.keys() {
+        if !model.is_context_node(id) && !expected_nodes.contains_key(id) {
+            drift.stale_nodes.push(id.clone());
+        }
+    }
+    for link in &expected_links {
+        if !model.links.contains(link) {
+            drift.missing_links.push(link.clone());
+        }
+    }
+    for (from_id, to_id) in &model.links {
+        // Links of removed nodes go away with them
+        let between_engine_nodes = expected_nodes.contains_key(from_id) && expected_nodes.contains_key(to_id);
+        if between_engine_nodes && !expected_links.contains(&(from_id.clone(), to_id.clone())) {
+            drift.stale_links.push((from_id.clone(), to_id.clone()));
+        }
+    }
+
+    drift.missing_nodes.sort_by(|a, b| a.0.cmp(&b.0));
+    drift.stale_nodes.sort();
+    drift.missing_links.sort();
+    drift.stale_links.sort();
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Block, Connection, Port, PortType};
+
+    fn block(id: &str) -> Block {
+        Block {
+            id: format!("ingen:/main/{}", id),
+            name: id.to_string(),
+            prototype: String::new(),
+            ports: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let graph = Graph {
+            blocks: vec![block("delay"), block("reverb")],
+            connections: vec![Connection {
+                source: "ingen:/main/delay/out".to_string(),
+                destination: "ingen:/main/reverb/in".to_string(),
+            }],
+            ports: vec![Port { id: "audio_in_1".to_string(), port_type: PortType::Audio, direction: PortDirection::Input }],
+        };
+
+        let mut model = GraphModel::default();
+        model.create_node("inputs", NodeType::Context);
+        model.create_node("outputs", NodeType::Context);
+        model.insert_node("ingen:/main/delay", NodeType::Normal, "inputs", "outputs");
+        model.insert_node("ingen:/main/chorus", NodeType::Normal, "ingen:/main/delay", "outputs");
+        model.create_node("ingen:/main/audio_in_1", NodeType::PortIn);
+        model.create_link("ingen:/main/audio_in_1", "ingen:/main/delay");
+
+        let drift = diff(&graph, &model, MAIN_GRAPH);
+        assert_eq!(drift.missing_nodes, vec![("ingen:/main/reverb".to_string(), "reverb".to_string(), NodeType::Normal)]);
+        assert_eq!(drift.stale_nodes, vec!["ingen:/main/chorus".to_string()]);
+        assert_eq!(drift.missing_links, vec![("ingen:/main/delay".to_string(), "ingen:/main/reverb".to_string())]);
+        assert_eq!(drift.stale_links, vec![("ingen:/main/audio_in_1".to_string(), "ingen:/main/delay".to_string())]);
+        assert_eq!(drift.len(), 4);
+    }
+}
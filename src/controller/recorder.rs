@@ -0,0 +1,251 @@
+use anyhow::{Context, Result};
+use jack::{AudioIn, Client, ClientOptions, ClosureProcessHandler, Control, ProcessScope};
+use log::{debug, info, warn};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::controller::driver::{Driver, Port, PortType};
+
+/// JACK client name of the recorder
+const RECORDER_CLIENT: &str = "TraxDub Recorder";
+
+/// Number of process cycles buffered between the JACK thread and the file writer
+const BUFFERED_CYCLES: usize = 512;
+
+/// A running recording
+struct RecordingSession {
+    path: PathBuf,
+    started: Instant,
+    stop_flag: Arc<AtomicBool>,
+    handle: JoinHandle<Result<()>>,
+}
+
+/// Records the engine output bus to stereo WAV files
+pub struct Recorder {
+    driver: Arc<Driver>,
+    session: Mutex<Option<RecordingSession>>,
+}
+
+impl Recorder {
+    /// Create a new recorder
+    pub fn new(driver: Arc<Driver>) -> Self {
+        Self {
+            driver,
+            session: Mutex::new(None),
+        }
+    }
+
+    /// Get the recordings directory path
+    fn get_recordings_dir() -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        let dir = PathBuf::from(home).join(".traxdub").join("recordings");
+
+        // Create directory if it doesn't exist
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            info!("Created recordings directory: {:?}", dir);
+        }
+
+        Ok(dir)
+    }
+
+    /// Check whether a recording is running
+    pub fn is_recording(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    /// Get the elapsed time of the running recording
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.session.lock().unwrap().as_ref().map(|s| s.started.elapsed())
+    }
+
+    /// Start recording the engine outputs to a new file
+    pub fn start(&self) -> Result<PathBuf> {
+        let mut session = self.session.lock().unwrap();
+        if session.is_some() {
+            return Err(anyhow::anyhow!("Recording already running"));
+        }
+
+        let filename = format!("{}.wav", chrono::Local::now().format("%Y-%m-%d-%H-%M-%S"));
+        let path = Self::get_recordings_dir()?.join(filename);
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+
+        // The JACK client lives in its own thread, which also writes the file
+        let thread_path = path.clone();
+        let thread_stop_flag = Arc::clone(&stop_flag);
+        let handle = std::thread::spawn(move || {
+            Self::run(thread_path, thread_stop_flag, ready_sender)
+        });
+
+        // Stop the thread, closing its client, and drop the file when the recording cannot start
+        let abort = |handle: JoinHandle<Result<()>>, e: anyhow::Error| -> Result<PathBuf> {
+            stop_flag.store(true, Ordering::SeqCst);
+            let _ = handle.join();
+            let _ = fs::remove_file(&path);
+            Err(e)
+        };
+
+        // Wait for the client to be activated before connecting it
+        match ready_receiver.recv_timeout(Duration::from_secs(2)) {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => return abort(handle, e),
+            Err(_) => return abort(handle, anyhow::anyhow!("Recorder did not start")),
+        }
+
+        let outputs = match self.connect_outputs() {
+            Ok(outputs) => outputs,
+            Err(e) => return abort(handle, e),
+        };
+
+        info!("Recording {} output(s) to {:?}", outputs, path);
+
+        *session = Some(RecordingSession {
+            path: path.clone(),
+            started: Instant::now(),
+            stop_flag,
+            handle,
+        });
+
+        Ok(path)
+    }
+
+    /// Connect the engine outputs to the recorder client, alternating left and right
+    fn connect_outputs(&self) -> Result<usize> {
+        let outputs = self.driver.get_engine_outputs(PortType::Audio)?;
+        if outputs.is_empty() {
+            warn!("No engine outputs to record");
+        }
+        for (i, output) in outputs.iter().enumerate() {
+            let input_name = if i % 2 == 0 { "in_l" } else { "in_r" };
+            let input = Port {
+                name: format!("{}:{}", RECORDER_CLIENT, input_name),
+                short_name: input_name.to_string(),
+            };
+            self.driver.connect_ports(output, &input)?;
+        }
+        Ok(outputs.len())
+    }
+
+    /// Stop the running recording and return the recorded file
+    pub fn stop(&self) -> Result<PathBuf> {
+        let session = self.session.lock().unwrap().take()
+            .ok_or_else(|| anyhow::anyhow!("No recording running"))?;
+
+        session.stop_flag.store(true, Ordering::SeqCst);
+        session.handle.join()
+            .map_err(|_| anyhow::anyhow!("Recorder thread panicked"))??;
+
+        info!("Recording saved to {:?} ({}s)", session.path, session.started.elapsed().as_secs());
+        Ok(session.path)
+    }
+
+    /// Recorder thread: run the JACK client and write incoming audio until stopped
+    fn run(path: PathBuf, stop_flag: Arc<AtomicBool>, ready: mpsc::Sender<Result<()>>) -> Result<()> {
+        let setup = || -> Result<_> {
+            let (client, _status) = Client::new(RECORDER_CLIENT, ClientOptions::NO_START_SERVER)
+                .map_err(|e| anyhow::anyhow!("Failed to create JACK recorder client: {}", e))?;
+
+            let in_l = client.register_port("in_l", AudioIn)
+                .map_err(|e| anyhow::anyhow!("Failed to register recorder port: {}", e))?;
+            let in_r = client.register_port("in_r", AudioIn)
+                .map_err(|e| anyhow::anyhow!("Failed to register recorder port: {}", e))?;
+
+            let spec = hound::WavSpec {
+                channels: 2,
+                sample_rate: client.sample_rate() as u32,
+                bits_per_sample: 32,
+                sample_format: hound::SampleFormat::Float,
+            };
+            let writer = hound::WavWriter::create(&path, spec)
+                .with_context(|| format!("Failed to create {:?}", path))?;
+
+            Ok((client, in_l, in_r, writer))
+        };
+
+        let (client, in_l, in_r, mut writer) = match setup() {
+            Ok(setup) => setup,
+            Err(e) => {
+                let _ = ready.send(Err(e));
+                return Ok(());
+            }
+        };
+
+        let (sample_sender, sample_receiver) = mpsc::sync_channel::<Vec<f32>>(BUFFERED_CYCLES);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let process_dropped = Arc::clone(&dropped);
+
+        // Interleave both channels and hand them over to the writer
+        let process_callback = move |_: &Client, ps: &ProcessScope| -> Control {
+            let left = in_l.as_slice(ps);
+            let right = in_r.as_slice(ps);
+            let mut frames = Vec::with_capacity(left.len() * 2);
+            for (l, r) in left.iter().zip(right) {
+                frames.push(*l);
+                frames.push(*r);
+            }
+            // Never block the process thread on a slow disk
+            if sample_sender.try_send(frames).is_err() {
+                process_dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Control::Continue
+        };
+
+        let active_client = match client.activate_async((), ClosureProcessHandler::new(process_callback)) {
+            Ok(active_client) => active_client,
+            Err(e) => {
+                let _ = ready.send(Err(anyhow::anyhow!("Failed to activate JACK recorder client: {}", e)));
+                return Ok(());
+            }
+        };
+
+        debug!("JACK recorder client activated");
+        let _ = ready.send(Ok(()));
+
+        while !stop_flag.load(Ordering::SeqCst) {
+            match sample_receiver.recv_timeout(Duration::from_millis(100)) {
+                Ok(frames) => {
+                    for sample in frames {
+                        writer.write_sample(sample)?;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        // Stop the client and flush what is still buffered
+        drop(active_client);
+        for frames in sample_receiver.try_iter() {
+            for sample in frames {
+                writer.write_sample(sample)?;
+            }
+        }
+        writer.finalize()?;
+
+        let dropped = dropped.load(Ordering::Relaxed);
+        if dropped > 0 {
+            warn!("Recorder dropped {} process cycle(s)", dropped);
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Make sure a running recording ends up in a valid file
+        if self.is_recording() {
+            if let Err(e) = self.stop() {
+                warn!("Failed to stop recording: {}", e);
+            }
+        }
+    }
+}
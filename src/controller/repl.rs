@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use std::io::BufRead;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use crate::controller::{KnobDirection, NavigationLevel};
+
+/// Help text printed by the help command
+const HELP: &str = "\
+Commands:
+  nav fwd|back [steps]     turn the main knob
+  jump fwd|back [steps]    turn the secondary knob
+  select | menu            press the selection button
+  back                     press the back button
+  tap                      press the tap tempo button
+  cc <channel> <control> <value>
+                           send a raw control change (e.g. while learning)
+  text <text>              replace the text being entered (names, notes, tags)
+  state                    print the controller state
+  reconcile                repair the differences between the UI and the engine
+  help                     print this help";
+
+/// Command typed on stdin or sent by a remote frontend, turned into synthetic controller events
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplCommand {
+    Navigate {
+        level: NavigationLevel,
+        direction: KnobDirection,
+        steps: u32,
+    },
+    Select,
+    Back,
+    Tap,
+    ControlChange {
+        channel: u8,
+        control: u8,
+        value: u8,
+    },
+    Text(String),
+    State,
+    Reconcile,
+}
+
+impl ReplCommand {
+    /// Parse a command line
+    pub fn parse(line: &str) -> Result<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [level @ ("nav" | "jump"), direction, rest @ ..] => {
+                let level = if *level == "nav" { NavigationLevel::Main } else { NavigationLevel::Secondary };
+                let direction = match *direction {
+                    "fwd" | "forward" => KnobDirection::Forward,
+                    "back" | "backward" => KnobDirection::Backward,
+                    other => return Err(anyhow!("Unknown direction '{}', use fwd or back", other)),
+                };
+                let steps = match rest {
+                    [] => 1,
+                    [steps] => steps.parse().map_err(|_| anyhow!("Invalid step count '{}'", steps))?,
+                    _ => return Err(anyhow!("Too many arguments")),
+                };
+                Ok(Self::Navigate { level, direction, steps })
+            }
+            ["select"] | ["menu"] => Ok(Self::Select),
+            ["back"] => Ok(Self::Back),
+            ["tap"] => Ok(Self::Tap),
+            ["cc", channel, control, value] => Ok(Self::ControlChange {
+                channel: channel.parse().map_err(|_| anyhow!("Invalid channel '{}'", channel))?,
+                control: control.parse().map_err(|_| anyhow!("Invalid control '{}'", control))?,
+                value: value.parse().map_err(|_| anyhow!("Invalid value '{}'", value))?,
+            }),
+            ["text", ..] => {
+                // Keep the spacing of the text, only the command word and its separator are removed
+                let text = line.trim_start().strip_prefix("text").unwrap_or_default();
+                Ok(Self::Text(text.strip_prefix(' ').unwrap_or(text).to_string()))
+            }
+            ["state"] => Ok(Self::State),
+            ["reconcile"] => Ok(Self::Reconcile),
+            _ => Err(anyhow!("Unknown command '{}', type help for the list", line.trim())),
+        }
+    }
+}
+
+/// Read commands from stdin in a background thread
+/// Parse errors and help are answered directly, valid commands go to the sender
+pub fn spawn(sender: Sender<ReplCommand>) {
+    thread::spawn(move || {
+        println!("TraxDub command interface ready, type help for the list of commands");
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.trim() == "help" {
+                println!("{}", HELP);
+                continue;
+            }
+            match ReplCommand::parse(&line) {
+                Ok(command) => {
+                    debug!("Command from stdin: {:?}", command);
+                    if sender.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => println!("{}", e),
+            }
+        }
+        warn!("Command interface closed");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            ReplCommand::parse("nav fwd").unwrap(),
+            ReplCommand::Navigate { level: NavigationLevel::Main, direction: KnobDirection::Forward, steps: 1 }
+        );
+        assert_eq!(
+            ReplCommand::parse("  jump back 3 ").unwrap(),
+            ReplCommand::Navigate { level: NavigationLevel::Secondary, direction: KnobDirection::Backward, steps: 3 }
+        );
+        assert_eq!(ReplCommand::parse("menu").unwrap(), ReplCommand::Select);
+        assert_eq!(
+            ReplCommand::parse("cc 0 21 127").unwrap(),
+            ReplCommand::ControlChange { channel: 0, control: 21, value: 127 }
+        );
+        assert_eq!(
+            ReplCommand::parse("text Dub  intro ").unwrap(),
+            ReplCommand::Text("Dub  intro ".to_string())
+        );
+        assert!(ReplCommand::parse("nav up").is_err());
+        assert!(ReplCommand::parse("cc 0 300 1").is_err());
+        assert!(ReplCommand::parse("dance").is_err());
+    }
+}
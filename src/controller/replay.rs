@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::controller::driver::MidiEvent;
+
+/// A MIDI event with its time since the start of the recording
+#[derive(Debug, Clone)]
+pub struct TimedEvent {
+    pub time: Duration,
+    pub event: MidiEvent,
+}
+
+/// Records the MIDI events reaching the controller to a file
+/// Each line holds the milliseconds since start and the raw bytes in hex, e.g. "1520 b0 15 41"
+pub struct EventRecorder {
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl EventRecorder {
+    /// Create the recording file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create MIDI recording {:?}", path))?;
+        info!("Recording MIDI events to {:?}", path);
+        Ok(Self {
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+        })
+    }
+
+    /// Append an event, flushed right away so the file survives a crash
+    pub fn record(&mut self, event: &MidiEvent) -> Result<()> {
+        writeln!(self.writer, "{}", format_line(self.start.elapsed(), event))?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Format an event as a recording line
+fn format_line(time: Duration, event: &MidiEvent) -> String {
+    let bytes: Vec<String> = event.to_raw().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{} {}", time.as_millis(), bytes.join(" "))
+}
+
+/// Parse a recording line, None for blank lines and # comments
+fn parse_line(line: &str) -> Result<Option<TimedEvent>> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut words = line.split_whitespace();
+    let time = words.next()
+        .and_then(|ms| ms.parse::<u64>().ok())
+        .ok_or_else(|| anyhow!("Missing time in '{}'", line))?;
+    let bytes = words
+        .map(|b| u8::from_str_radix(b, 16))
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .map_err(|_| anyhow!("Invalid MIDI bytes in '{}'", line))?;
+    let event = MidiEvent::from_raw(&bytes)
+        .ok_or_else(|| anyhow!("Unsupported MIDI message in '{}'", line))?;
+
+    Ok(Some(TimedEvent { time: Duration::from_millis(time), event }))
+}
+
+/// Load the events of a recording
+pub fn load(path: &Path) -> Result<Vec<TimedEvent>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read MIDI recording {:?}", path))?;
+
+    let mut events = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if let Some(event) = parse_line(line).with_context(|| format!("{:?} line {}", path, number + 1))? {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Feed the events with their original timing, the channel closes after the last one
+pub fn spawn(events: Vec<TimedEvent>) -> Receiver<MidiEvent> {
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        info!("Replaying {} MIDI events", events.len());
+        let start = Instant::now();
+        for timed in events {
+            if let Some(wait) = timed.time.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+            debug!("Replaying {:?}", timed.event);
+            if sender.send(timed.event).is_err() {
+                return;
+            }
+        }
+        info!("MIDI replay finished");
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_round_trip() {
+        let event = MidiEvent::ControlChange { channel: 0, control: 21, value: 65 };
+        let line = format_line(Duration::from_millis(1520), &event);
+        assert_eq!(line, "1520 b0 15 41");
+
+        let parsed = parse_line(&line).unwrap().unwrap();
+        assert_eq!(parsed.time, Duration::from_millis(1520));
+        assert!(matches!(parsed.event, MidiEvent::ControlChange { channel: 0, control: 21, value: 65 }));
+    }
+
+    #[test]
+    fn test_parse_line_errors() {
+        assert!(parse_line("# learned on a nanoKONTROL").unwrap().is_none());
+        assert!(parse_line("   ").unwrap().is_none());
+        assert!(parse_line("b0 15 41").is_err());
+        assert!(parse_line("10 zz").is_err());
+        assert!(parse_line("10 90 3c 64").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::{anyhow, Result};
+use jack::{Client, ClientOptions};
+use log::{debug, info, warn};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time allowed for a started JACK server to accept clients
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Interval between two readiness checks
+const STARTUP_POLL: Duration = Duration::from_millis(200);
+
+/// Kind of JACK server to start
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ServerKind {
+    /// Classic jackd on the ALSA backend
+    Jackd,
+    /// PipeWire with its JACK compatibility layer
+    Pipewire,
+}
+
+/// Parameters of the JACK server to start
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub kind: ServerKind,
+    /// ALSA device (e.g. "hw:0"), ignored by PipeWire
+    pub device: String,
+    /// Sample rate in Hz
+    pub rate: u32,
+    /// Buffer size in frames
+    pub frames: u32,
+}
+
+/// JACK server started by TraxDub when none was running
+pub struct JackServer {
+    process: Mutex<Option<Child>>,
+}
+
+impl JackServer {
+    /// Check whether a JACK server accepts clients
+    pub fn is_running() -> bool {
+        Client::new("TraxDub Probe", ClientOptions::NO_START_SERVER).is_ok()
+    }
+
+    /// Start a JACK server unless one is already running, and wait until it accepts clients
+    pub fn start(config: &ServerConfig) -> Result<Self> {
+        if Self::is_running() {
+            info!("JACK server already running");
+            return Ok(Self { process: Mutex::new(None) });
+        }
+
+        let mut command = match config.kind {
+            ServerKind::Jackd => {
+                let mut command = Command::new("jackd");
+                command
+                    .arg("-d").arg("alsa")
+                    .arg("-d").arg(&config.device)
+                    .arg("-r").arg(config.rate.to_string())
+                    .arg("-p").arg(config.frames.to_string());
+                command
+            }
+            ServerKind::Pipewire => {
+                // PipeWire picks its devices itself, JACK clients request the latency
+                std::env::set_var("PIPEWIRE_LATENCY", format!("{}/{}", config.frames, config.rate));
+                Command::new("pipewire")
+            }
+        };
+
+        debug!("Starting JACK server: {:?}", command);
+        let child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start JACK server: {}. Make sure it is installed.", e))?;
+        info!("JACK server started (PID: {})", child.id());
+
+        let server = Self { process: Mutex::new(Some(child)) };
+
+        // Wait for the server to accept clients
+        let start_time = Instant::now();
+        while start_time.elapsed() < STARTUP_TIMEOUT {
+            if Self::is_running() {
+                debug!("JACK server ready after {}ms", start_time.elapsed().as_millis());
+                return Ok(server);
+            }
+            if let Some(status) = server.process.lock().unwrap().as_mut().and_then(|p| p.try_wait().ok().flatten()) {
+                return Err(anyhow!("JACK server exited during startup with {}", status));
+            }
+            thread::sleep(STARTUP_POLL);
+        }
+
+        server.close();
+        Err(anyhow!("JACK server not ready after {}s", STARTUP_TIMEOUT.as_secs()))
+    }
+
+    /// Stop the JACK server if TraxDub started it
+    pub fn close(&self) {
+        if let Some(mut process) = self.process.lock().unwrap().take() {
+            let pid = process.id();
+            debug!("Sending SIGTERM to JACK server (PID: {})", pid);
+
+            #[cfg(unix)]
+            {
+                use nix::sys::signal::{self, Signal};
+                use nix::unistd::Pid;
+
+                let _ = signal::kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
+            }
+
+            match process.wait() {
+                Ok(status) => debug!("JACK server exited with {}", status),
+                Err(e) => warn!("Error waiting for JACK server to exit: {}", e),
+            }
+        }
+    }
+}
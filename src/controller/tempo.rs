@@ -0,0 +1,190 @@
+use log::debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::controller::driver::Driver;
+
+/// Number of tap intervals averaged to compute the tapped tempo
+const TAP_HISTORY: usize = 4;
+
+/// Pause after which a tap starts a new measurement
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Tempo tracker computing BPM from button taps
+#[derive(Debug, Default)]
+pub struct TapTempo {
+    last_tap: Option<Instant>,
+    intervals: Vec<Duration>,
+    bpm: Option<f32>,
+}
+
+impl TapTempo {
+    /// Register a tap at the given instant and return the updated BPM
+    pub fn tap(&mut self, now: Instant) -> Option<f32> {
+        if let Some(last) = self.last_tap {
+            let interval = now.duration_since(last);
+            if interval > TAP_TIMEOUT {
+                // Too long since the last tap, start over
+                self.intervals.clear();
+            } else {
+                self.intervals.push(interval);
+                if self.intervals.len() > TAP_HISTORY {
+                    self.intervals.remove(0);
+                }
+            }
+        }
+        self.last_tap = Some(now);
+
+        if !self.intervals.is_empty() {
+            let total: Duration = self.intervals.iter().sum();
+            let average = total.as_secs_f32() / self.intervals.len() as f32;
+            if average > 0.0 {
+                self.bpm = Some(60.0 / average);
+            }
+        }
+
+        self.bpm
+    }
+
+    /// Get the last tapped tempo
+    pub fn bpm(&self) -> Option<f32> {
+        self.bpm
+    }
+}
+
+/// Note value used to express time-based parameters relative to the tempo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeatDivision {
+    Whole,
+    Half,
+    Quarter,
+    DottedQuarter,
+    QuarterTriplet,
+    Eighth,
+    DottedEighth,
+    EighthTriplet,
+    Sixteenth,
+}
+
+impl BeatDivision {
+    /// All divisions, longest first
+    pub fn all() -> &'static [BeatDivision] {
+        &[
+            BeatDivision::Whole,
+            BeatDivision::Half,
+            BeatDivision::DottedQuarter,
+            BeatDivision::Quarter,
+            BeatDivision::QuarterTriplet,
+            BeatDivision::DottedEighth,
+            BeatDivision::Eighth,
+            BeatDivision::EighthTriplet,
+            BeatDivision::Sixteenth,
+        ]
+    }
+
+    /// Length of the division in quarter notes
+    pub fn beats(&self) -> f32 {
+        match self {
+            BeatDivision::Whole => 4.0,
+            BeatDivision::Half => 2.0,
+            BeatDivision::Quarter => 1.0,
+            BeatDivision::DottedQuarter => 1.5,
+            BeatDivision::QuarterTriplet => 2.0 / 3.0,
+            BeatDivision::Eighth => 0.5,
+            BeatDivision::DottedEighth => 0.75,
+            BeatDivision::EighthTriplet => 1.0 / 3.0,
+            BeatDivision::Sixteenth => 0.25,
+        }
+    }
+
+    /// Identifier used in menus
+    pub fn id(&self) -> &'static str {
+        match self {
+            BeatDivision::Whole => "1/1",
+            BeatDivision::Half => "1/2",
+            BeatDivision::Quarter => "1/4",
+            BeatDivision::DottedQuarter => "1/4d",
+            BeatDivision::QuarterTriplet => "1/4t",
+            BeatDivision::Eighth => "1/8",
+            BeatDivision::DottedEighth => "1/8d",
+            BeatDivision::EighthTriplet => "1/8t",
+            BeatDivision::Sixteenth => "1/16",
+        }
+    }
+
+    /// Human-readable label
+    pub fn label(&self) -> &'static str {
+        match self {
+            BeatDivision::Whole => "1/1",
+            BeatDivision::Half => "1/2",
+            BeatDivision::Quarter => "1/4",
+            BeatDivision::DottedQuarter => "1/4 dotted",
+            BeatDivision::QuarterTriplet => "1/4 triplet",
+            BeatDivision::Eighth => "1/8",
+            BeatDivision::DottedEighth => "1/8 dotted",
+            BeatDivision::EighthTriplet => "1/8 triplet",
+            BeatDivision::Sixteenth => "1/16",
+        }
+    }
+
+    /// Find a division by its identifier
+    pub fn from_id(id: &str) -> Option<BeatDivision> {
+        Self::all().iter().copied().find(|d| d.id() == id)
+    }
+
+    /// Duration of the division in milliseconds at the given tempo
+    pub fn duration_ms(&self, bpm: f32) -> f32 {
+        self.beats() * 60_000.0 / bpm
+    }
+}
+
+/// Tempo source combining the incoming MIDI clock with tap tempo
+pub struct Tempo {
+    driver: Arc<Driver>,
+    taps: Mutex<TapTempo>,
+}
+
+impl Tempo {
+    /// Create a new tempo source
+    pub fn new(driver: Arc<Driver>) -> Self {
+        Self {
+            driver,
+            taps: Mutex::new(TapTempo::default()),
+        }
+    }
+
+    /// Register a tap of the tap tempo button
+    pub fn tap(&self) -> Option<f32> {
+        let bpm = self.taps.lock().unwrap().tap(Instant::now());
+        debug!("Tap tempo: {:?}", bpm);
+        bpm
+    }
+
+    /// Get the current tempo, preferring a running MIDI clock over tapped tempo
+    pub fn get_bpm(&self) -> Option<f32> {
+        self.driver.get_bpm().or_else(|| self.taps.lock().unwrap().bpm())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tap_tempo() {
+        let mut taps = TapTempo::default();
+        let start = Instant::now();
+        assert!(taps.tap(start).is_none());
+        for i in 1..=4 {
+            taps.tap(start + Duration::from_millis(500 * i));
+        }
+        assert!((taps.bpm().unwrap() - 120.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_beat_division_duration() {
+        assert_eq!(BeatDivision::Quarter.duration_ms(120.0), 500.0);
+        assert_eq!(BeatDivision::DottedEighth.duration_ms(120.0), 375.0);
+        assert_eq!(BeatDivision::from_id("1/8d"), Some(BeatDivision::DottedEighth));
+    }
+}
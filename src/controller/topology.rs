@@ -0,0 +1,208 @@
+use anyhow::Result;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::controller::feature::mixer::{MAIN_GRAPH, is_hidden_block};
+use crate::engine::split::MONITOR_GRAPH;
+use crate::engine::{Connection, GRAPH_PROTOTYPE, Graph, PortDirection};
+
+/// Get the node the signal flows through at a port
+/// The signal goes through a plugin block from any input to any output, while the ports of the system,
+/// of the groups and of the monitor engine pass it along on their own
+fn flow_node(graph: &Graph, port_path: &str) -> String {
+    match port_path.rsplit_once('/') {
+        Some((parent, _)) if graph.blocks.iter().any(|b| b.id == parent && b.prototype != GRAPH_PROTOTYPE) => parent.to_string(),
+        _ => port_path.to_string(),
+    }
+}
+
+/// Get the flow nodes fed by each flow node
+fn flow_edges(graph: &Graph) -> HashMap<String, HashSet<String>> {
+    let mut edges: HashMap<String, HashSet<String>> = HashMap::new();
+    for connection in &graph.connections {
+        edges.entry(flow_node(graph, &connection.source))
+            .or_default()
+            .insert(flow_node(graph, &connection.destination));
+    }
+    edges
+}
+
+/// Get the flow nodes reached from a flow node, itself included
+fn reachable(edges: &HashMap<String, HashSet<String>>, start: &str) -> HashSet<String> {
+    let mut visited = HashSet::from([start.to_string()]);
+    let mut queue = VecDeque::from([start.to_string()]);
+    while let Some(node) = queue.pop_front() {
+        for next in edges.get(&node).into_iter().flatten() {
+            if visited.insert(next.clone()) {
+                queue.push_back(next.clone());
+            }
+        }
+    }
+    visited
+}
+
+/// Check whether connecting two ports would feed a node with its own output
+pub fn creates_cycle(graph: &Graph, source: &str, destination: &str) -> bool {
+    reachable(&flow_edges(graph), &flow_node(graph, destination)).contains(&flow_node(graph, source))
+}
+
+/// Check that connections can be made one after the other without feedback cycle
+/// Ingen refuses to run a graph with a cycle, feedback goes through a loop outside of it instead
+pub fn check_connections(graph: &Graph, connections: &[Connection]) -> Result<()> {
+    let mut graph = graph.clone();
+    for connection in connections {
+        anyhow::ensure!(!creates_cycle(&graph, &connection.source, &connection.destination),
+            "Connecting {} to {} would make a feedback cycle, use Feed Back To instead", connection.source, connection.destination);
+        graph.connections.push(connection.clone());
+    }
+    Ok(())
+}
+
+/// Get the blocks feeding a block, directly or through others, itself included
+pub fn upstream_blocks(graph: &Graph, block_path: &str) -> Vec<String> {
+    let edges = flow_edges(graph);
+    let mut blocks: Vec<String> = graph.blocks.iter()
+        .filter(|b| b.prototype != GRAPH_PROTOTYPE && reachable(&edges, &b.id).contains(block_path))
+        .map(|b| b.id.clone())
+        .collect();
+    blocks.sort();
+    blocks
+}
+
+/// Get the blocks with outputs whose signal reaches no output port of the main or the monitor engine
+/// The hidden gain blocks follow the nodes they are attached to and are left out
+pub fn orphans(graph: &Graph) -> Vec<String> {
+    let mut sinks: HashSet<String> = graph.ports.iter()
+        .filter(|p| p.direction == PortDirection::Output)
+        .map(|p| format!("{}/{}", MAIN_GRAPH, p.id))
+        .collect();
+    if let Some(monitor) = graph.blocks.iter().find(|b| b.id == MONITOR_GRAPH) {
+        sinks.extend(monitor.ports.iter()
+            .filter(|p| p.direction == PortDirection::Output)
+            .map(|p| format!("{}/{}", MONITOR_GRAPH, p.id)));
+    }
+
+    let edges = flow_edges(graph);
+    let mut orphans: Vec<String> = graph.blocks.iter()
+        .filter(|b| b.prototype != GRAPH_PROTOTYPE && !is_hidden_block(&b.id))
+        .filter(|b| b.ports.iter().any(|p| p.direction == PortDirection::Output))
+        .filter(|b| reachable(&edges, &b.id).is_disjoint(&sinks))
+        .map(|b| b.id.clone())
+        .collect();
+    orphans.sort();
+    orphans
+}
+
+/// Get the latency accumulated up to each node of the graph, in frames
+/// A node adds its own latency to the largest one among the nodes feeding it, so the output ports get the
+/// latency of their slowest chain. The latencies of the blocks are given by block path.
+pub fn chain_latencies(graph: &Graph, latencies: &HashMap<String, u32>) -> HashMap<String, u32> {
+    let mut feeders: HashMap<String, HashSet<String>> = HashMap::new();
+    for (from, targets) in flow_edges(graph) {
+        for to in targets {
+            feeders.entry(to).or_default().insert(from.clone());
+        }
+    }
+
+    // Depth first from each node, a node in progress counting as nothing to stop at cycles
+    fn total(node: &str, feeders: &HashMap<String, HashSet<String>>, latencies: &HashMap<String, u32>,
+             totals: &mut HashMap<String, u32>, in_progress: &mut HashSet<String>) -> u32 {
+        if let Some(total) = totals.get(node) {
+            return *total;
+        }
+        if !in_progress.insert(node.to_string()) {
+            return 0;
+        }
+        let upstream = feeders.get(node).into_iter().flatten()
+            .map(|feeder| total(feeder, feeders, latencies, totals, in_progress))
+            .max()
+            .unwrap_or(0);
+        let result = upstream + latencies.get(node).copied().unwrap_or(0);
+        in_progress.remove(node);
+        totals.insert(node.to_string(), result);
+        result
+    }
+
+    let mut totals = HashMap::new();
+    let nodes: HashSet<String> = feeders.keys().cloned().chain(latencies.keys().cloned()).collect();
+    for node in nodes {
+        total(&node, &feeders, latencies, &mut totals, &mut HashSet::new());
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Block, Port, PortType};
+
+    fn port(id: &str, direction: PortDirection) -> Port {
+        Port { id: id.to_string(), port_type: PortType::Audio, direction }
+    }
+
+    fn block(id: &str) -> Block {
+        Block {
+            id: format!("ingen:/main/{}", id),
+            name: id.to_string(),
+            prototype: "http://example.org/delay".to_string(),
+            ports: vec![port("in", PortDirection::Input), port("out", PortDirection::Output)],
+        }
+    }
+
+    fn connection(source: &str, destination: &str) -> Connection {
+        Connection { source: format!("ingen:/main/{}", source), destination: format!("ingen:/main/{}", destination) }
+    }
+
+    #[test]
+    fn test_cycles_and_orphans() {
+        let group = Block {
+            id: "ingen:/main/group".to_string(),
+            name: "group".to_string(),
+            prototype: GRAPH_PROTOTYPE.to_string(),
+            ports: vec![port("in", PortDirection::Input), port("out", PortDirection::Output)],
+        };
+        let graph = Graph {
+            blocks: vec![block("delay"), group, block("group/reverb"), block("chorus")],
+            connections: vec![
+                connection("audio_in_1", "delay/in"),
+                connection("delay/out", "group/in"),
+                connection("group/in", "group/reverb/in"),
+                connection("group/reverb/out", "group/out"),
+                connection("group/out", "audio_out_1"),
+            ],
+            ports: vec![port("audio_in_1", PortDirection::Input), port("audio_out_1", PortDirection::Output)],
+        };
+
+        assert!(creates_cycle(&graph, "ingen:/main/group/reverb/out", "ingen:/main/delay/in"));
+        assert!(creates_cycle(&graph, "ingen:/main/delay/out", "ingen:/main/delay/in"));
+        assert!(!creates_cycle(&graph, "ingen:/main/chorus/out", "ingen:/main/delay/in"));
+        assert!(check_connections(&graph, &[connection("delay/out", "chorus/in"), connection("chorus/out", "delay/in")]).is_err());
+        assert_eq!(upstream_blocks(&graph, "ingen:/main/group/reverb"), vec!["ingen:/main/delay", "ingen:/main/group/reverb"]);
+        assert_eq!(orphans(&graph), vec!["ingen:/main/chorus"]);
+    }
+
+    #[test]
+    fn test_chain_latencies() {
+        let graph = Graph {
+            blocks: vec![block("delay"), block("reverb"), block("chorus")],
+            connections: vec![
+                connection("audio_in_1", "delay/in"),
+                connection("audio_in_1", "chorus/in"),
+                connection("delay/out", "reverb/in"),
+                connection("reverb/out", "audio_out_1"),
+                connection("chorus/out", "audio_out_1"),
+            ],
+            ports: vec![port("audio_in_1", PortDirection::Input), port("audio_out_1", PortDirection::Output)],
+        };
+        let latencies = HashMap::from([
+            ("ingen:/main/delay".to_string(), 64),
+            ("ingen:/main/reverb".to_string(), 32),
+            ("ingen:/main/chorus".to_string(), 80),
+        ]);
+
+        let totals = chain_latencies(&graph, &latencies);
+        assert_eq!(totals["ingen:/main/reverb"], 96);
+        assert_eq!(totals["ingen:/main/chorus"], 80);
+        assert_eq!(totals["ingen:/main/audio_out_1"], 96);
+        assert_eq!(totals["ingen:/main/audio_in_1"], 0);
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::lv2::Lv2World;
+use super::Plugin;
+
+/// Cached plugins of an LV2 bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CachedBundle {
+    /// Latest modification time of the bundle and its files, in seconds since the epoch
+    mtime: u64,
+    /// Plugins defined in the bundle
+    plugins: Vec<Plugin>,
+}
+
+/// Version of the cache format, older caches are rebuilt
+const CACHE_VERSION: u32 = 3;
+
+/// Persistent cache of LV2 plugin metadata, keyed by bundle directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PluginCache {
+    #[serde(default)]
+    version: u32,
+    bundles: HashMap<String, CachedBundle>,
+}
+
+impl PluginCache {
+    /// Get the cache file path
+    fn get_cache_path() -> Result<PathBuf> {
+        let home = std::env::var("HOME")
+            .map_err(|_| anyhow::anyhow!("HOME environment variable not set"))?;
+        Ok(PathBuf::from(home).join(".traxdub").join("lv2_cache.json"))
+    }
+
+    /// Load the cache, starting empty if there is none or it is unreadable
+    fn load() -> Self {
+        let cache = Self::get_cache_path().and_then(|path| {
+            let content = fs::read_to_string(&path).context("Failed to read LV2 cache")?;
+            let cache: Self = serde_json::from_str(&content).context("Failed to parse LV2 cache")?;
+            if cache.version != CACHE_VERSION {
+                return Err(anyhow::anyhow!("Outdated LV2 cache version {}", cache.version));
+            }
+            Ok(cache)
+        });
+        cache.unwrap_or_else(|e| {
+            debug!("No LV2 cache loaded: {}", e);
+            Self::default()
+        })
+    }
+
+    /// Save the cache
+    fn save(&self) -> Result<()> {
+        let path = Self::get_cache_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string(self)?)
+            .context("Failed to write LV2 cache")?;
+        Ok(())
+    }
+
+    /// Get the LV2 search path, as used by lilv
+    fn lv2_path() -> Vec<PathBuf> {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        let path = std::env::var("LV2_PATH")
+            .unwrap_or_else(|_| format!("{}/.lv2:/usr/local/lib/lv2:/usr/lib/lv2", home));
+        path.split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| PathBuf::from(dir.replacen('~', &home, 1)))
+            .collect()
+    }
+
+    /// Latest modification time of a bundle directory and the files it contains
+    fn bundle_mtime(bundle: &Path) -> u64 {
+        let mtime = |path: &Path| fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let files = fs::read_dir(bundle)
+            .map(|entries| entries.filter_map(|e| e.ok()).map(|e| mtime(&e.path())).max().unwrap_or(0))
+            .unwrap_or(0);
+        mtime(bundle).max(files)
+    }
+
+    /// Cache key of a bundle, canonical so lilv and directory scan paths match
+    fn bundle_key(bundle: &Path) -> String {
+        fs::canonicalize(bundle)
+            .unwrap_or_else(|_| bundle.to_path_buf())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Scan the LV2 path for bundle directories and their modification times
+    fn scan_bundles() -> HashMap<String, u64> {
+        let mut bundles = HashMap::new();
+        for dir in Self::lv2_path() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if path.is_dir() && path.extension().is_some_and(|ext| ext == "lv2") {
+                    let key = Self::bundle_key(&path);
+                    bundles.entry(key).or_insert_with(|| Self::bundle_mtime(&path));
+                }
+            }
+        }
+        bundles
+    }
+
+    /// Get the bundles that are new or changed since they were cached
+    fn changed_bundles(&self, scanned: &HashMap<String, u64>) -> Vec<String> {
+        scanned.iter()
+            .filter(|(bundle, mtime)| self.bundles.get(*bundle).map(|b| b.mtime) != Some(**mtime))
+            .map(|(bundle, _)| bundle.clone())
+            .collect()
+    }
+
+    /// Discover all installed LV2 plugins, rescanning only the bundles changed since the last run
+    /// The steps of the scan are reported to progress
+    pub fn discover(progress: &dyn Fn(&str)) -> Result<Vec<Plugin>> {
+        let mut cache = Self::load();
+        let scanned = Self::scan_bundles();
+
+        // Forget removed bundles
+        let cached_count = cache.bundles.len();
+        cache.bundles.retain(|bundle, _| scanned.contains_key(bundle));
+        let removed = cached_count - cache.bundles.len();
+
+        let changed = cache.changed_bundles(&scanned);
+        if changed.is_empty() {
+            info!("LV2 plugin cache is up to date ({} bundles)", cache.bundles.len());
+        } else if cache.bundles.is_empty() {
+            info!("Scanning all LV2 bundles...");
+            progress(&format!("Scanning {} LV2 bundles", changed.len()));
+            let world = Lv2World::new()?;
+            cache.update(&world, &scanned, &changed);
+        } else {
+            info!("Rescanning {} changed LV2 bundles", changed.len());
+            let world = Lv2World::empty()?;
+            // Bundles without plugins hold specifications, needed for plugin classes
+            let specifications = cache.bundles.iter()
+                .filter(|(_, b)| b.plugins.is_empty())
+                .map(|(bundle, _)| bundle);
+            for (i, bundle) in changed.iter().enumerate() {
+                progress(&format!("Scanning LV2 bundle {}/{}", i + 1, changed.len()));
+                world.load_bundle(Path::new(bundle));
+            }
+            for bundle in specifications {
+                world.load_bundle(Path::new(bundle));
+            }
+            world.load_specifications();
+            cache.update(&world, &scanned, &changed);
+        }
+
+        if !changed.is_empty() || removed > 0 {
+            cache.version = CACHE_VERSION;
+            if let Err(e) = cache.save() {
+                warn!("Failed to save LV2 cache: {}", e);
+            }
+        }
+
+        Ok(cache.bundles.into_values().flat_map(|b| b.plugins).collect())
+    }
+
+    /// Replace the changed bundles with the plugins found in a world
+    fn update(&mut self, world: &Lv2World, scanned: &HashMap<String, u64>, changed: &[String]) {
+        let mut found: HashMap<String, Vec<Plugin>> = HashMap::new();
+        for (bundle, plugins) in world.list_plugins_by_bundle() {
+            found.entry(Self::bundle_key(Path::new(&bundle))).or_default().extend(plugins);
+        }
+        for bundle in changed {
+            let plugins = found.remove(bundle).unwrap_or_default();
+            let mtime = scanned.get(bundle).copied().unwrap_or(0);
+            self.bundles.insert(bundle.clone(), CachedBundle { mtime, plugins });
+        }
+        if !found.is_empty() {
+            debug!("Ignoring plugins from bundles outside the LV2 path: {:?}", found.keys());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_bundles() {
+        let mut cache = PluginCache::default();
+        cache.bundles.insert("/lv2/a.lv2".to_string(), CachedBundle { mtime: 10, plugins: Vec::new() });
+        cache.bundles.insert("/lv2/b.lv2".to_string(), CachedBundle { mtime: 20, plugins: Vec::new() });
+
+        let scanned = HashMap::from([
+            ("/lv2/a.lv2".to_string(), 10),
+            ("/lv2/b.lv2".to_string(), 25),
+            ("/lv2/c.lv2".to_string(), 5),
+        ]);
+
+        let mut changed = cache.changed_bundles(&scanned);
+        changed.sort();
+        assert_eq!(changed, vec!["/lv2/b.lv2", "/lv2/c.lv2"]);
+    }
+}
@@ -0,0 +1,206 @@
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use super::protocol::{self, IngenProtocol, Request};
+use super::{EngineBackend, PortDirection};
+
+/// Prefix of the paths of the main graph
+const MAIN_PREFIX: &str = "ingen:/main/";
+
+/// Message exchanged with Ingen, one JSON object per line of a capture file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    /// Local time in RFC 3339 format
+    pub time: String,
+    /// Whether the message was sent to Ingen, received otherwise
+    pub outgoing: bool,
+    pub text: String,
+}
+
+/// Outcome of a capture replayed against an engine
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Requests applied to the engine
+    pub applied: usize,
+    /// Requests without effect on the graph, such as gets and monitoring
+    pub ignored: usize,
+    /// Requests the engine refused, with the reason
+    pub failures: Vec<String>,
+    /// Messages that could not be parsed, with the reason
+    pub unreadable: Vec<String>,
+    /// Differences between the states received and the replayed graph at the same point
+    pub differences: Vec<String>,
+}
+
+static CAPTURE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// Start appending the messages exchanged with Ingen to a capture file
+pub fn start(path: &Path) -> Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)
+        .with_context(|| format!("Failed to open protocol capture {:?}", path))?;
+    if CAPTURE.set(Mutex::new(file)).is_err() {
+        return Err(anyhow!("Protocol capture already started"));
+    }
+    info!("Capturing the Ingen protocol to {:?}", path);
+    Ok(())
+}
+
+/// Append a message to the capture file, if one is started
+/// Each line is written at once so the file survives a crash
+pub fn record(outgoing: bool, text: &str) {
+    let Some(file) = CAPTURE.get() else {
+        return;
+    };
+    let message = CapturedMessage { time: chrono::Local::now().to_rfc3339(), outgoing, text: text.to_string() };
+    let result = serde_json::to_string(&message)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| writeln!(file.lock().unwrap(), "{}", line).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        warn!("Failed to write protocol capture: {}", e);
+    }
+}
+
+/// Load the messages of a capture file
+pub fn load(path: &Path) -> Result<Vec<CapturedMessage>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read protocol capture {:?}", path))?;
+
+    let mut messages = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message = serde_json::from_str(line)
+            .with_context(|| format!("{:?} line {}", path, number + 1))?;
+        messages.push(message);
+    }
+    info!("Loaded {} protocol messages from {:?}", messages.len(), path);
+    Ok(messages)
+}
+
+/// Apply the requests sent in a capture to an engine, usually the mock one
+/// The states received after a get of the main graph are compared with the replayed graph
+pub fn replay(messages: &[CapturedMessage], engine: &dyn EngineBackend) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    let mut awaiting_state = false;
+
+    for message in messages {
+        if !message.outgoing {
+            if let Err(e) = IngenProtocol::parse_response(&message.text) {
+                report.unreadable.push(format!("{} received: {}", message.time, e));
+                continue;
+            }
+            if awaiting_state && message.text.contains("a patch:Put") {
+                awaiting_state = false;
+                compare_state(message, engine, &mut report);
+            }
+            continue;
+        }
+
+        let requests = match IngenProtocol::parse_requests(&message.text) {
+            Ok(requests) => requests,
+            Err(e) => {
+                report.unreadable.push(format!("{} sent: {}", message.time, e));
+                continue;
+            }
+        };
+        for request in requests {
+            if request == (Request::Get { subject: MAIN_PREFIX.to_string() }) {
+                awaiting_state = true;
+            }
+            match apply(&request, engine) {
+                Ok(true) => report.applied += 1,
+                Ok(false) => report.ignored += 1,
+                Err(e) => report.failures.push(format!("{} {:?}: {}", message.time, request, e)),
+            }
+        }
+    }
+    report
+}
+
+/// Apply a request to an engine, returning whether it changes the graph
+fn apply(request: &Request, engine: &dyn EngineBackend) -> Result<bool> {
+    let id = |path: &str| path.strip_prefix(MAIN_PREFIX).unwrap_or(path).to_string();
+    match request {
+        Request::CreateBlock { path, prototype } => engine.create_block(prototype, &id(path))?,
+        Request::CreateGraph { path } => engine.create_graph(&id(path))?,
+        Request::CreatePort { path, port_type, direction: PortDirection::Input } => {
+            engine.create_input_port(&id(path), port_type.clone())?;
+        }
+        Request::CreatePort { path, port_type, direction: PortDirection::Output } => {
+            engine.create_output_port(&id(path), port_type.clone())?;
+        }
+        Request::Connect { source, destination } => engine.connect(source, destination)?,
+        Request::Disconnect { source, destination } => engine.disconnect(source, destination)?,
+        Request::Delete { path } => engine.delete(path)?,
+        Request::Move { path, destination } => engine.move_block(path, destination)?,
+        Request::SetProperty { subject, property, value: Some(value) } if property == protocol::INGEN_VALUE => {
+            let (block, symbol) = subject.rsplit_once('/')
+                .ok_or_else(|| anyhow!("Not a port path: {}", subject))?;
+            let value = value.parse::<f32>().map_err(|_| anyhow!("Not a control value: {}", value))?;
+            engine.set_control_parameter(block, symbol, value)?;
+        }
+        Request::SetProperty { subject, property, value: Some(value) } if property == protocol::LV2_NAME => {
+            engine.set_block_name(subject, value)?;
+        }
+        Request::SetProperty { .. } | Request::Get { .. } => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Report the blocks and connections of a received state missing from the replayed graph, and the reverse
+fn compare_state(message: &CapturedMessage, engine: &dyn EngineBackend, report: &mut ReplayReport) {
+    let received = match IngenProtocol::parse_graph(&message.text) {
+        Ok(graph) => graph,
+        Err(e) => {
+            report.unreadable.push(format!("{} state: {}", message.time, e));
+            return;
+        }
+    };
+    let replayed = match engine.get_graph() {
+        Ok(graph) => graph,
+        Err(e) => {
+            report.failures.push(format!("{} reading the replayed graph: {}", message.time, e));
+            return;
+        }
+    };
+
+    let items = |graph: &super::Graph| -> BTreeSet<String> {
+        graph.blocks.iter().map(|block| format!("block {}", block.id))
+            .chain(graph.connections.iter().map(|c| format!("connection {} -> {}", c.source, c.destination)))
+            .collect()
+    };
+    let (received, replayed) = (items(&received), items(&replayed));
+    report.differences.extend(received.difference(&replayed).map(|item| format!("{} only in Ingen: {}", message.time, item)));
+    report.differences.extend(replayed.difference(&received).map(|item| format!("{} only in the replay: {}", message.time, item)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Engine, PortType};
+
+    #[test]
+    fn test_replay() {
+        let sent = |text: String| CapturedMessage { time: String::new(), outgoing: true, text };
+        let messages = vec![
+            sent(IngenProtocol::get_init_message().to_string()),
+            sent(IngenProtocol::build_create_graph("group").unwrap()),
+            sent(IngenProtocol::build_create_port("audio_out", &PortType::Audio, &PortDirection::Output).unwrap()),
+            sent(IngenProtocol::build_set_property("ingen:/main/group", protocol::LV2_NAME, &protocol::PropertyValue::String("Drums")).unwrap()),
+            sent(IngenProtocol::build_set_property("ingen:/main/audio_out", protocol::INGEN_BROADCAST, &protocol::PropertyValue::Bool(true)).unwrap()),
+            sent(IngenProtocol::build_create_block("missing", "http://example.org/missing").unwrap()),
+            CapturedMessage { time: String::new(), outgoing: false, text: "not turtle".to_string() },
+        ];
+        let engine = Engine::new_mock();
+        let report = replay(&messages, &*engine);
+        assert_eq!((report.applied, report.ignored, report.failures.len(), report.unreadable.len()), (3, 1, 1, 1));
+        assert_eq!(engine.get_graph().unwrap().blocks[0].name, "Drums");
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+
+use super::{Graph, PortDirection};
+
+/// Prefix of the engine paths, left out of the rendered names
+const MAIN_PREFIX: &str = "ingen:/main/";
+
+/// Text format of an exported graph
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Graphviz DOT
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+}
+
+impl ExportFormat {
+    /// File extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Dot => "dot",
+            ExportFormat::Mermaid => "mmd",
+        }
+    }
+}
+
+/// Node of the rendered graph: a block or a system port
+struct Node {
+    id: String,
+    label: String,
+    is_port: bool,
+}
+
+/// Split a port path into its node path and port symbol
+/// System ports are nodes of their own and have no symbol
+fn split_port(graph: &Graph, port_path: &str) -> (String, Option<String>) {
+    let short = port_path.strip_prefix(MAIN_PREFIX).unwrap_or(port_path);
+    if graph.ports.iter().any(|p| p.id == short) {
+        return (short.to_string(), None);
+    }
+    match short.rsplit_once('/') {
+        Some((node, symbol)) => (node.to_string(), Some(symbol.to_string())),
+        None => (short.to_string(), None),
+    }
+}
+
+/// Turn a path into an identifier both formats accept
+fn node_id(path: &str) -> String {
+    path.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// Collect the nodes in a stable order: input ports, blocks, output ports
+fn nodes(graph: &Graph) -> Vec<Node> {
+    let port_nodes = |direction: PortDirection| graph.ports.iter()
+        .filter(move |p| p.direction == direction)
+        .map(|p| Node { id: node_id(&p.id), label: p.id.clone(), is_port: true });
+
+    let mut blocks: Vec<Node> = graph.blocks.iter()
+        .map(|b| {
+            let path = b.id.strip_prefix(MAIN_PREFIX).unwrap_or(&b.id);
+            Node { id: node_id(path), label: b.name.clone(), is_port: false }
+        })
+        .collect();
+    blocks.sort_by(|a, b| a.id.cmp(&b.id));
+
+    port_nodes(PortDirection::Input)
+        .chain(blocks)
+        .chain(port_nodes(PortDirection::Output))
+        .collect()
+}
+
+/// Collect the edges between nodes, labeled with the connected port symbols
+fn edges(graph: &Graph) -> BTreeSet<(String, String, String)> {
+    graph.connections.iter()
+        .map(|c| {
+            let (from, from_symbol) = split_port(graph, &c.source);
+            let (to, to_symbol) = split_port(graph, &c.destination);
+            let label = match (from_symbol, to_symbol) {
+                (Some(from_symbol), Some(to_symbol)) => format!("{} → {}", from_symbol, to_symbol),
+                (Some(symbol), None) | (None, Some(symbol)) => symbol,
+                (None, None) => String::new(),
+            };
+            (node_id(&from), node_id(&to), label)
+        })
+        .collect()
+}
+
+/// Render a graph as text in the given format
+pub fn render(graph: &Graph, format: ExportFormat) -> String {
+    let mut lines = Vec::new();
+    match format {
+        ExportFormat::Dot => {
+            lines.push("digraph traxdub {".to_string());
+            lines.push("    rankdir=LR;".to_string());
+            for node in nodes(graph) {
+                let shape = if node.is_port { "ellipse" } else { "box" };
+                lines.push(format!("    {} [label=\"{}\", shape={}];", node.id, node.label.replace('"', "\\\""), shape));
+            }
+            for (from, to, label) in edges(graph) {
+                lines.push(format!("    {} -> {} [label=\"{}\"];", from, to, label));
+            }
+            lines.push("}".to_string());
+        }
+        ExportFormat::Mermaid => {
+            lines.push("flowchart LR".to_string());
+            for node in nodes(graph) {
+                let label = node.label.replace('"', "#quot;");
+                if node.is_port {
+                    lines.push(format!("    {}([\"{}\"])", node.id, label));
+                } else {
+                    lines.push(format!("    {}[\"{}\"]", node.id, label));
+                }
+            }
+            for (from, to, label) in edges(graph) {
+                if label.is_empty() {
+                    lines.push(format!("    {} --> {}", from, to));
+                } else {
+                    lines.push(format!("    {} -->|\"{}\"| {}", from, label, to));
+                }
+            }
+        }
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Block, Connection, Port, PortType};
+
+    fn graph() -> Graph {
+        Graph {
+            blocks: vec![Block {
+                id: "ingen:/main/delay_1".to_string(),
+                name: "Tape Delay".to_string(),
+                prototype: String::new(),
+                ports: Vec::new(),
+            }],
+            connections: vec![
+                Connection { source: "ingen:/main/audio_in_1".to_string(), destination: "ingen:/main/delay_1/in".to_string() },
+                Connection { source: "ingen:/main/delay_1/out".to_string(), destination: "ingen:/main/audio_out_1".to_string() },
+            ],
+            ports: vec![
+                Port { id: "audio_in_1".to_string(), port_type: PortType::Audio, direction: PortDirection::Input },
+                Port { id: "audio_out_1".to_string(), port_type: PortType::Audio, direction: PortDirection::Output },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_render_dot() {
+        assert_eq!(render(&graph(), ExportFormat::Dot), "\
+digraph traxdub {
+    rankdir=LR;
+    audio_in_1 [label=\"audio_in_1\", shape=ellipse];
+    delay_1 [label=\"Tape Delay\", shape=box];
+    audio_out_1 [label=\"audio_out_1\", shape=ellipse];
+    audio_in_1 -> delay_1 [label=\"in\"];
+    delay_1 -> audio_out_1 [label=\"out\"];
+}
+");
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        assert_eq!(render(&graph(), ExportFormat::Mermaid), "\
+flowchart LR
+    audio_in_1([\"audio_in_1\"])
+    delay_1[\"Tape Delay\"]
+    audio_out_1([\"audio_out_1\"])
+    audio_in_1 -->|\"in\"| delay_1
+    delay_1 -->|\"out\"| audio_out_1
+");
+    }
+}
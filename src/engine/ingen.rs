@@ -0,0 +1,1133 @@
+use anyhow::{anyhow, Result};
+use log::{debug, info, warn, trace};
+use std::io::Write;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use super::protocol::{self, IngenProtocol};
+use super::{cache, EngineBackend, Graph, Plugin, PortDirection, PortType, StateContent};
+
+/// Engine backend driving an Ingen instance through its Unix socket
+pub struct IngenBackend {
+    ingen_process: Mutex<Option<std::process::Child>>,
+    socket_path: String,
+    /// Time Ingen has to create its socket and answer
+    start_timeout: Duration,
+    /// Graph bundle Ingen loaded when it started
+    preloaded_bundle: Option<PathBuf>,
+    socket: Mutex<Option<UnixStream>>,
+    /// List of available LV2 plugins
+    plugins: Mutex<Vec<Plugin>>,
+    /// Buffer for leftover bytes after null terminator
+    read_buffer: Mutex<Vec<u8>>,
+    /// Plugin URI of each known block, keyed by block path
+    block_prototypes: Mutex<HashMap<String, String>>,
+    /// Last parsed graph, dropped on every change made through this backend
+    graph_cache: Mutex<GraphCache>,
+    /// Control values of each block, read back from Ingen after a load or a creation
+    /// and kept up to date with the values set through this backend
+    control_values: Mutex<HashMap<String, HashMap<String, f32>>>,
+    /// Messages drained from the socket without being awaited, holding the port level notifications
+    /// and the control values set by other clients
+    notifications: Mutex<Vec<u8>>,
+    /// Ports whose level Ingen broadcasts
+    monitored_ports: Mutex<HashSet<String>>,
+    /// Ingen GUI process showing the plugin UIs
+    gui_process: Mutex<Option<std::process::Child>>,
+    /// Last lines Ingen printed on its error output, with their time
+    error_output: ErrorOutput,
+    /// Messages collected by each thread since it started a bundle, sent together when it ends
+    bundles: Mutex<HashMap<ThreadId, Vec<String>>>,
+}
+
+/// Size above which the unread notifications are dropped, when nobody takes the peaks
+const MAX_NOTIFICATIONS: usize = 1 << 20;
+
+/// Interval between the checks that a starting Ingen is ready
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of error output lines of Ingen kept to explain a failure
+const ERROR_OUTPUT_LINES: usize = 5;
+
+/// Age above which the error output lines of Ingen are too old to explain a failure
+const ERROR_OUTPUT_AGE: Duration = Duration::from_secs(10);
+
+/// Last error output lines of Ingen with their time, filled by the thread reading them
+type ErrorOutput = Arc<Mutex<VecDeque<(Instant, String)>>>;
+
+/// Forward the lines Ingen prints to the log until it exits, keeping the last error lines when a buffer is given
+fn forward_output(pipe: impl std::io::Read + Send + 'static, error_output: Option<ErrorOutput>) {
+    use std::io::BufRead;
+
+    thread::spawn(move || {
+        for line in std::io::BufReader::new(pipe).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match &error_output {
+                Some(error_output) => {
+                    warn!(target: "ingen", "Ingen: {}", line);
+                    let mut error_output = error_output.lock().unwrap();
+                    if error_output.len() == ERROR_OUTPUT_LINES {
+                        error_output.pop_front();
+                    }
+                    error_output.push_back((Instant::now(), line));
+                }
+                None => info!(target: "ingen", "Ingen: {}", line),
+            }
+        }
+    });
+}
+
+/// Last parsed graph and the number of changes made so far
+/// A graph fetched while a change was made is not cached, as it may predate the change
+#[derive(Default)]
+struct GraphCache {
+    generation: u64,
+    graph: Option<Graph>,
+}
+
+impl IngenBackend {
+    /// Start or connect to Ingen and discover its plugins, reporting each step to `progress`
+    /// Ingen has `start_timeout` to create its socket and answer, when starting and when reconnecting
+    /// A started Ingen loads the `preload` graph bundle itself, an external one is left as it is
+    pub fn new(use_external: bool, socket_path: &str, start_timeout: Duration, preload: Option<&Path>, progress: &dyn Fn(&str)) -> Result<Self> {
+        debug!("Initializing Ingen backend...");
+
+        let engine = Self {
+            ingen_process: Mutex::new(None),
+            socket_path: socket_path.to_string(),
+            start_timeout,
+            preloaded_bundle: preload.filter(|_| !use_external).map(Path::to_path_buf),
+            socket: Mutex::new(None),
+            plugins: Mutex::new(Vec::new()),
+            read_buffer: Mutex::new(Vec::new()),
+            block_prototypes: Mutex::new(HashMap::new()),
+            graph_cache: Mutex::new(GraphCache::default()),
+            control_values: Mutex::new(HashMap::new()),
+            notifications: Mutex::new(Vec::new()),
+            monitored_ports: Mutex::new(HashSet::new()),
+            gui_process: Mutex::new(None),
+            error_output: Arc::new(Mutex::new(VecDeque::new())),
+            bundles: Mutex::new(HashMap::new()),
+        };
+
+        // Start Ingen in the background (unless using external)
+        if !use_external {
+            progress(if preload.is_some() { "Starting Ingen with the last session" } else { "Starting Ingen" });
+            engine.start_ingen(preload)?;
+        } else {
+            info!("Using external Ingen instance");
+        }
+        
+        // Connect to Ingen socket, explaining a failure with what Ingen printed
+        progress("Connecting to Ingen");
+        if let Err(e) = engine.connect_socket() {
+            let output = engine.take_error_output();
+            return Err(if output.is_empty() { e } else { anyhow!("{} ({})", e, output.join(" · ")) });
+        }
+
+        engine.rescan_plugins(progress)?;
+
+        Ok(engine)
+    }
+
+    /// Start the Ingen process, loading a graph bundle if given
+    fn start_ingen(&self, preload: Option<&Path>) -> Result<()> {
+        debug!("Starting Ingen process...");
+
+        use std::process::{Command, Stdio};
+
+        let mut command = Command::new("ingen");
+        command
+            .arg("-e")  // Engine mode
+            .arg("-S")  // Socket path
+            .arg(&self.socket_path)
+            .arg("-n")  // Client name
+            .arg("TraxDub Engine");
+        if let Some(bundle) = preload {
+            info!("Ingen loads {:?} at boot", bundle);
+            command.arg("-l").arg(bundle);  // Graph to load
+        }
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start ingen process: {}. Make sure ingen is installed.", e))?;
+
+        info!("Ingen process started (PID: {:?}), waiting for it to answer", child.id());
+        if let Some(stdout) = child.stdout.take() {
+            forward_output(stdout, None);
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forward_output(stderr, Some(self.error_output.clone()));
+        }
+        *self.ingen_process.lock().unwrap() = Some(child);
+
+        Ok(())
+    }
+
+    /// Wait until Ingen has created its socket and answers on it, up to the start timeout
+    fn connect_socket(&self) -> Result<()> {
+        debug!("Connecting to Ingen socket...");
+        
+        let deadline = Instant::now() + self.start_timeout;
+        let mut last_error = anyhow!("Socket {} not created", self.socket_path);
+        loop {
+            if self.ingen_exited()? {
+                return Err(anyhow!("Ingen exited while starting"));
+            }
+            if Path::new(&self.socket_path).exists() {
+                match self.handshake(deadline) {
+                    Ok(()) => {
+                        info!("Connected to Ingen socket");
+                        return Ok(());
+                    }
+                    Err(e) => {
+                        debug!("Ingen not ready yet: {}", e);
+                        *self.socket.lock().unwrap() = None;
+                        last_error = e;
+                    }
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(anyhow!("Ingen not ready after {} ms: {}", self.start_timeout.as_millis(), last_error));
+            }
+            thread::sleep(READINESS_POLL_INTERVAL);
+        }
+    }
+
+    /// Connect to the socket, send the initialization message and check that Ingen answers before the deadline
+    fn handshake(&self, deadline: Instant) -> Result<()> {
+        let stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| anyhow!("Failed to connect to Ingen socket: {}", e))?;
+        *self.socket.lock().unwrap() = Some(stream);
+        
+        // Send initialization message with RDF prefixes
+        debug!("Sending initialization message to Ingen");
+        self.write_message(IngenProtocol::get_init_message())?;
+        
+        self.ping(deadline.saturating_duration_since(Instant::now()).max(READINESS_POLL_INTERVAL))
+    }
+
+    /// Check whether the Ingen process started here has exited
+    fn ingen_exited(&self) -> Result<bool> {
+        Ok(match self.ingen_process.lock().unwrap().as_mut() {
+            Some(process) => process.try_wait()?.is_some(),
+            None => false,
+        })
+    }
+
+    /// Drain any pending response data from the socket
+    fn drain_response(&self) -> Result<()> {
+        use std::io::Read;
+        
+        let mut socket_guard = self.socket.lock().unwrap();
+        if let Some(socket) = socket_guard.as_mut() {
+            // Set non-blocking mode with minimal timeout
+            socket.set_read_timeout(Some(Duration::from_millis(1)))
+                .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
+            
+            let mut drain_buf = [0u8; 4096];
+            let mut total_drained = 0;
+            let mut notifications = self.notifications.lock().unwrap();
+            if notifications.len() > MAX_NOTIFICATIONS {
+                notifications.clear();
+            }
+            
+            // Keep reading until no bytes are available, keeping them for the level notifications
+            loop {
+                match socket.read(&mut drain_buf) {
+                    Ok(0) => break, // EOF
+                    Ok(n) => {
+                        total_drained += n;
+                        notifications.extend_from_slice(&drain_buf[..n]);
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || 
+                              e.kind() == std::io::ErrorKind::TimedOut => {
+                        // No more data available
+                        break;
+                    }
+                    Err(e) => return Err(anyhow!("Failed to drain socket: {}", e)),
+                }
+            }
+            
+            if total_drained > 0 {
+                debug!("Drained {} bytes from response stream", total_drained);
+                // Only captured, the notifications would flood the inspector
+                let start = notifications.len() - total_drained;
+                super::capture::record(false, &String::from_utf8_lossy(&notifications[start..]));
+            }
+            
+            // Clear the read buffer as well
+            self.read_buffer.lock().unwrap().clear();
+            
+            Ok(())
+        } else {
+            Err(anyhow!("Not connected to Ingen socket"))
+        }
+    }
+
+    /// Send an edit to Ingen via the Unix socket, or add it to the bundle started by the calling thread
+    fn send_message(&self, message: &str) -> Result<()> {
+        if let Some(messages) = self.bundles.lock().unwrap().get_mut(&thread::current().id()) {
+            trace!("Adding message to the bundle: {}", message);
+            messages.push(message.to_string());
+            return Ok(());
+        }
+        self.write_message(message)
+    }
+
+    /// Write a message to the Ingen socket
+    /// Requests waiting for an answer are written right away, answered with the graph before an open bundle
+    fn write_message(&self, message: &str) -> Result<()> {
+        trace!("Sending message to Ingen: {}", message);
+        
+        // Drain any pending responses before sending new message
+        self.drain_response()?;
+
+        let mut socket_guard = self.socket.lock().unwrap();
+        if let Some(socket) = socket_guard.as_mut() {
+            socket.write_all(message.as_bytes())
+                .map_err(|e| anyhow!("Failed to write to Ingen socket: {}", e))?;
+            socket.flush()
+                .map_err(|e| anyhow!("Failed to flush Ingen socket: {}", e))?;
+            super::trace::record(true, message);
+            super::capture::record(true, message);
+            Ok(())
+        } else {
+            Err(anyhow!("Not connected to Ingen socket"))
+        }
+    }
+
+    /// Receive a message from Ingen via Unix socket
+    fn receive_message(&self) -> Result<String> {
+        use std::io::Read;
+        
+        loop {
+            debug!("Receiving message from Ingen...");
+            
+            // Get any buffered bytes from the previous read
+            let mut read_buffer_guard = self.read_buffer.lock().unwrap();
+            let buffered = read_buffer_guard.clone();
+            read_buffer_guard.clear();
+            drop(read_buffer_guard);
+            
+            let mut buffer = buffered;
+            let mut bundle_start_seq: Option<String> = None;
+            let mut bundle_end_seq: Option<String> = None;
+            let mut found_bundle_end = false;
+            
+            let mut socket_guard = self.socket.lock().unwrap();
+            if let Some(socket) = socket_guard.as_mut() {
+                // Set a longer read timeout to handle large messages
+                socket.set_read_timeout(Some(Duration::from_secs(30)))
+                    .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
+                
+                let mut temp_buf = [0u8; 4096];
+                
+                // Keep reading until we find the complete bundle (BundleStart -> BundleEnd -> ".")
+                loop {
+                    match socket.read(&mut temp_buf) {
+                        Ok(0) => {
+                            // EOF - connection closed
+                            if buffer.is_empty() {
+                                return Err(anyhow!("Connection closed by Ingen"));
+                            }
+                            break;
+                        }
+                        Ok(n) => {
+                            // Filter out null bytes and append to buffer
+                            for &byte in &temp_buf[..n] {
+                                if byte != 0 {
+                                    buffer.push(byte);
+                                }
+                            }
+                            
+                            // Convert current buffer to string for line parsing
+                            let buffer_str = String::from_utf8_lossy(&buffer);
+                            
+                            // Look for BundleStart sequence number if not found yet
+                            if bundle_start_seq.is_none() {
+                                if let Some(start_pos) = buffer_str.find("a ingen:BundleStart") {
+                                    // Find the next line with patch:sequenceNumber
+                                    if let Some(seq_pos) = buffer_str[start_pos..].find("patch:sequenceNumber") {
+                                        let after_seq = &buffer_str[start_pos + seq_pos + 20..]; // Skip "patch:sequenceNumber"
+                                        // Extract the number in quotes
+                                        if let Some(quote_start) = after_seq.find('"') {
+                                            if let Some(quote_end) = after_seq[quote_start + 1..].find('"') {
+                                                let seq_num = &after_seq[quote_start + 1..quote_start + 1 + quote_end];
+                                                debug!("Bundle start sequence: {}", seq_num);
+                                                bundle_start_seq = Some(seq_num.to_string());
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            
+                            // Look for BundleEnd sequence number if start found but end not yet
+                            if bundle_start_seq.is_some() && bundle_end_seq.is_none() {
+                                if let Some(end_pos) = buffer_str.find("a ingen:BundleEnd") {
+                                    // Find the next line with patch:sequenceNumber
+                                    if let Some(seq_pos) = buffer_str[end_pos..].find("patch:sequenceNumber") {
+                                        let after_seq = &buffer_str[end_pos + seq_pos + 20..]; // Skip "patch:sequenceNumber"
+                                        // Extract the number in quotes
+                                        if let Some(quote_start) = after_seq.find('"') {
+                                            if let Some(quote_end) = after_seq[quote_start + 1..].find('"') {
+                                                let seq_num = &after_seq[quote_start + 1..quote_start + 1 + quote_end];
+                                                debug!("Bundle end sequence: {}", seq_num);
+                                                bundle_end_seq = Some(seq_num.to_string());
+                                                found_bundle_end = true;
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            
+                            // If we found bundle end, look for the final dot
+                            if found_bundle_end {
+                                if let Some(dot_pos) = buffer_str.rfind('.') {
+                                    // Check if this dot is the last non-whitespace character
+                                    let after_dot = &buffer_str[dot_pos + 1..];
+                                    if after_dot.trim().is_empty() {
+                                        // Found the response boundary
+                                        debug!("Found response boundary at dot position {}", dot_pos);
+                                        
+                                        // Check if there are more bytes available to read
+                                        socket.set_read_timeout(Some(Duration::from_millis(1)))
+                                            .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
+                                        
+                                        let mut peek_buf = [0u8; 1];
+                                        match socket.read(&mut peek_buf) {
+                                            Ok(0) => {
+                                                // No more data, we can stop
+                                                // Save any bytes after the dot for next read
+                                                let dot_byte_pos = buffer_str[..=dot_pos].len();
+                                                if buffer.len() > dot_byte_pos {
+                                                    let remaining = &buffer[dot_byte_pos..];
+                                                    let mut read_buffer_guard = self.read_buffer.lock().unwrap();
+                                                    read_buffer_guard.extend_from_slice(remaining);
+                                                }
+                                                
+                                                // Truncate buffer at the dot (inclusive)
+                                                buffer.truncate(dot_byte_pos);
+                                                break;
+                                            }
+                                            Ok(_) => {
+                                                // More data available, put the peeked byte back into buffer
+                                                if peek_buf[0] != 0 {
+                                                    buffer.push(peek_buf[0]);
+                                                }
+                                                // Reset timeout and continue reading
+                                                socket.set_read_timeout(Some(Duration::from_secs(30)))
+                                                    .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
+                                                // Continue to next read
+                                            }
+                                            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
+                                                // Timeout means no more data available
+                                                // Save any bytes after the dot for next read
+                                                let dot_byte_pos = buffer_str[..=dot_pos].len();
+                                                if buffer.len() > dot_byte_pos {
+                                                    let remaining = &buffer[dot_byte_pos..];
+                                                    let mut read_buffer_guard = self.read_buffer.lock().unwrap();
+                                                    read_buffer_guard.extend_from_slice(remaining);
+                                                }
+                                                
+                                                // Truncate buffer at the dot (inclusive)
+                                                buffer.truncate(dot_byte_pos);
+                                                break;
+                                            }
+                                            Err(e) => return Err(anyhow!("Failed to peek socket: {}", e)),
+                                        }
+                                    }
+
+                                }
+                            }
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            // Timeout
+                            if !buffer.is_empty() {
+                                warn!("Read timeout after receiving {} bytes", buffer.len());
+                                break;
+                            } else {
+                                return Err(anyhow!("Timeout waiting for data from Ingen"));
+                            }
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                            // Interrupted system call, retry
+                            continue;
+                        }
+                        Err(e) => return Err(anyhow!("Failed to read from Ingen socket: {}", e)),
+                    }
+                }
+                
+                debug!("Received {} bytes from Ingen", buffer.len());
+                
+                // Convert buffer to String
+                let message = String::from_utf8_lossy(&buffer).to_string();
+                trace!("Response buffer:\n{}", message);
+                super::trace::record(false, &message);
+                super::capture::record(false, &message);
+                
+                // Drop the socket guard before checking message content
+                drop(socket_guard);
+                
+                // Check if this message contains actual content (patch:Put) - if not, ignore and receive again
+                if !message.contains("a patch:Put") {
+                    debug!("Message doesn't contain 'a patch:Put', ignoring and receiving next message");
+                    continue;
+                }
+                
+                return Ok(message);
+            } else {
+                return Err(anyhow!("Not connected to Ingen socket"));
+            }
+        }
+    }
+
+    /// Discover available LV2 plugins from Ingen
+    fn discover_plugins(&self) -> Result<Vec<String>> {
+        debug!("Discovering LV2 plugins from Ingen...");
+        
+        self.write_message(&IngenProtocol::build_get_plugins()?)?;
+        let plugins = 
+            IngenProtocol::parse_get_plugins(&self.receive_message()?)?;
+        
+        debug!("Ingen reported {} plugins", plugins.len());
+        Ok(plugins)
+    }
+}
+
+impl EngineBackend for IngenBackend {
+    /// Discover the available plugins from Ingen and LV2, returning how many were found
+    /// Can be called at runtime to pick up newly installed plugins
+    fn rescan_plugins(&self, progress: &dyn Fn(&str)) -> Result<usize> {
+        // Discover available plugins from Ingen
+        progress("Listing the plugins of Ingen");
+        let ingen_plugin_iris = self.discover_plugins()?;
+        
+        // Get full plugin metadata from LV2, rescanning only the bundles changed since the last run
+        let all_lv2_plugins = cache::PluginCache::discover(progress)?;
+        
+        // Filter to keep only plugins that Ingen knows about
+        let plugins: Vec<Plugin> = all_lv2_plugins.into_iter()
+            .filter(|plugin| ingen_plugin_iris.contains(&plugin.id))
+            .collect();
+        
+        info!("Found {} plugins", plugins.len());        
+
+        trace!("Available plugins: {:?}", plugins);
+
+        let count = plugins.len();
+        *self.plugins.lock().unwrap() = plugins;
+        Ok(count)
+    }
+
+    /// Get the list of available plugins
+    fn list_plugins(&self) -> Vec<Plugin> {
+        self.plugins.lock().unwrap().clone()
+    }
+
+    /// Get the plugin a block was instantiated from
+    fn get_block_plugin(&self, block_path: &str) -> Option<Plugin> {
+        let prototypes = self.block_prototypes.lock().unwrap();
+        let plugin_uri = prototypes.get(block_path)?;
+        self.plugins.lock().unwrap().iter().find(|p| &p.id == plugin_uri).cloned()
+    }
+
+    /// Create a new block (plugin instance)
+    fn create_block(&self, plugin_uri: &str, block_id: &str) -> Result<()> {
+        info!("Creating block '{}' with plugin '{}'", block_id, plugin_uri);
+        
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_create_block(block_id, plugin_uri)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+        
+        let block_path = format!("ingen:/main/{}", block_id);
+        self.control_values.lock().unwrap().remove(&block_path);
+        self.block_prototypes.lock().unwrap().insert(block_path, plugin_uri.to_string());
+        
+        Ok(())
+    }
+
+    /// Create an empty subgraph
+    fn create_graph(&self, graph_id: &str) -> Result<()> {
+        info!("Creating graph '{}'", graph_id);
+        
+        let message = IngenProtocol::build_create_graph(graph_id)?;
+        
+        self.invalidate_graph();
+        self.send_message(&message)?;
+        
+        Ok(())
+    }
+
+    /// Set a control parameter on a block
+    /// 
+    /// # Arguments
+    /// * `block_id` - Block path (e.g., "ingen:/main/block_id")
+    /// * `parameter_name` - Symbol of the control port
+    /// * `value` - New parameter value
+    fn set_control_parameter(
+        &self,
+        block_id: &str,
+        parameter_name: &str,
+        value: f32,
+    ) -> Result<()> {
+        debug!("Setting '{}' of '{}' to {}", parameter_name, block_id, value);
+
+        // Patch parameters are not ports, the plugin receives them as messages on its atom input
+        let plugin = self.get_block_plugin(block_id);
+        let property = plugin.as_ref()
+            .and_then(|plugin| plugin.controls.iter().find(|c| c.id == parameter_name))
+            .and_then(|control| control.property.clone());
+        let message = match property {
+            Some(property) => {
+                let input = plugin.as_ref()
+                    .and_then(|plugin| plugin.ports.iter()
+                        .find(|p| p.port_type == PortType::Midi && p.direction == PortDirection::Input))
+                    .ok_or_else(|| anyhow!("No atom input to set '{}' of '{}'", parameter_name, block_id))?;
+                IngenProtocol::build_set_parameter(
+                    &format!("{}/{}", block_id, input.id),
+                    &property,
+                    &protocol::PropertyValue::Float(value),
+                )?
+            }
+            None => IngenProtocol::build_set_property(
+                &format!("{}/{}", block_id, parameter_name),
+                protocol::INGEN_VALUE,
+                &protocol::PropertyValue::Float(value),
+            )?,
+        };
+        
+        // Send to Ingen
+        self.send_message(&message)?;
+
+        if let Some(values) = self.control_values.lock().unwrap().get_mut(block_id) {
+            values.insert(parameter_name.to_string(), value);
+        }
+        Ok(())
+    }
+
+    /// Set the display name of a block
+    fn set_block_name(&self, block_id: &str, name: &str) -> Result<()> {
+        info!("Naming '{}' '{}'", block_id, name);
+
+        let message = IngenProtocol::build_set_property(
+            block_id,
+            protocol::LV2_NAME,
+            &protocol::PropertyValue::String(name),
+        )?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        Ok(())
+    }
+
+    /// Set the number of voices of a block through the polyphony of its graph
+    fn set_polyphony(&self, block_id: &str, voices: u32) -> Result<()> {
+        info!("Setting '{}' to {} voices", block_id, voices);
+        
+        // A polyphonic block gets the voices of its graph, the graph keeps them for its other blocks
+        if voices > 1 {
+            let message = IngenProtocol::build_set_property(
+                &parent_graph(block_id),
+                protocol::INGEN_POLYPHONY,
+                &protocol::PropertyValue::Int(voices as i32),
+            )?;
+            self.send_message(&message)?;
+        }
+        let message = IngenProtocol::build_set_property(
+            block_id,
+            protocol::INGEN_POLYPHONIC,
+            &protocol::PropertyValue::Bool(voices > 1),
+        )?;
+        self.send_message(&message)?;
+        
+        Ok(())
+    }
+
+    /// Get the number of voices of a block
+    fn get_polyphony(&self, block_id: &str) -> Result<u32> {
+        let response = self.get_raw_state()?;
+        let polyphonic = IngenProtocol::parse_property(&response, block_id, protocol::INGEN_POLYPHONIC)?;
+        if polyphonic.as_deref() != Some("true") {
+            return Ok(1);
+        }
+        let polyphony = IngenProtocol::parse_property(&response, &parent_graph(block_id), protocol::INGEN_POLYPHONY)?;
+        Ok(polyphony.and_then(|p| p.parse().ok()).unwrap_or(1))
+    }
+
+    /// Get the current control values of a block, keyed by port symbol
+    fn get_control_values(&self, block_id: &str) -> Result<HashMap<String, f32>> {
+        if let Some(values) = self.control_values.lock().unwrap().get(block_id) {
+            return Ok(values.clone());
+        }
+        debug!("Reading back control values of '{}'", block_id);
+        
+        // Read back all the blocks at once, the first query after a load would be followed by the others
+        let response = self.get_raw_state()?;
+        let graph = IngenProtocol::parse_graph(&response)?;
+        let mut cache = self.control_values.lock().unwrap();
+        for block in &graph.blocks {
+            cache.insert(block.id.clone(), IngenProtocol::parse_control_values(&response, &block.id)?);
+        }
+        let values = match cache.get(block_id) {
+            Some(values) => values.clone(),
+            None => IngenProtocol::parse_control_values(&response, block_id)?,
+        };
+        Ok(values)
+    }
+
+    /// Ask Ingen to broadcast the level of a port
+    fn set_port_monitored(&self, port_path: &str, monitored: bool) -> Result<()> {
+        debug!("Monitoring of '{}': {}", port_path, monitored);
+        
+        let message = IngenProtocol::build_set_property(
+            port_path,
+            protocol::INGEN_BROADCAST,
+            &protocol::PropertyValue::Bool(monitored),
+        )?;
+        self.send_message(&message)?;
+        
+        let mut monitored_ports = self.monitored_ports.lock().unwrap();
+        if monitored {
+            monitored_ports.insert(port_path.to_string());
+        } else {
+            monitored_ports.remove(port_path);
+        }
+        Ok(())
+    }
+
+    /// Get the highest level broadcast for each port since the last call
+    fn take_peaks(&self) -> Result<HashMap<String, f32>> {
+        self.drain_response()?;
+        
+        // Keep an incomplete last message for the next call
+        let complete = {
+            let mut notifications = self.notifications.lock().unwrap();
+            let end = notifications.iter().rposition(|b| *b == 0)
+                .into_iter()
+                .chain(notifications.windows(2).rposition(|w| w == b".\n").map(|p| p + 1))
+                .max();
+            match end {
+                Some(end) => {
+                    let rest = notifications.split_off(end + 1);
+                    std::mem::replace(&mut *notifications, rest)
+                }
+                None => return Ok(HashMap::new()),
+            }
+        };
+        let messages = String::from_utf8_lossy(&complete).replace('\0', "\n");
+        
+        // Control ports are notified too when set, only the monitored ports have levels
+        let monitored_ports = self.monitored_ports.lock().unwrap();
+        let mut peaks = HashMap::new();
+        match IngenProtocol::parse_port_values(&messages) {
+            Ok(values) => {
+                let mut control_values = self.control_values.lock().unwrap();
+                for (port, value) in values {
+                    if monitored_ports.contains(&port) {
+                        let peak = peaks.entry(port).or_insert(0.0f32);
+                        *peak = peak.max(value.abs());
+                    } else if let Some((block, symbol)) = port.rsplit_once('/') {
+                        // Mirror the values set by other clients, like the plugin UIs of the Ingen GUI
+                        if let Some(values) = control_values.get_mut(block) {
+                            values.insert(symbol.to_string(), value);
+                        }
+                    }
+                }
+            }
+            Err(e) => debug!("Skipping unreadable notifications: {}", e),
+        }
+        Ok(peaks)
+    }
+
+    /// Send a lightweight get and wait for any answer, kept with the notifications as it may hold port values
+    fn ping(&self, timeout: Duration) -> Result<()> {
+        use std::io::Read;
+
+        let message = IngenProtocol::build_get_engine()?;
+        self.write_message(&message)?;
+
+        let mut socket_guard = self.socket.lock().unwrap();
+        let socket = socket_guard.as_mut().ok_or_else(|| anyhow!("Not connected to Ingen socket"))?;
+        socket.set_read_timeout(Some(timeout))
+            .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
+        let mut buffer = [0u8; 4096];
+        match socket.read(&mut buffer) {
+            Ok(0) => Err(anyhow!("Connection closed by Ingen")),
+            Ok(n) => {
+                super::capture::record(false, &String::from_utf8_lossy(&buffer[..n]));
+                self.notifications.lock().unwrap().extend_from_slice(&buffer[..n]);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
+                Err(anyhow!("No answer from Ingen within {} ms", timeout.as_millis()))
+            }
+            Err(e) => Err(anyhow!("Failed to read from Ingen socket: {}", e)),
+        }
+    }
+
+    /// Connect to the socket again, starting Ingen again first when it was started here and exited
+    fn reconnect(&self) -> Result<bool> {
+        info!("Reconnecting to Ingen");
+        *self.socket.lock().unwrap() = None;
+        self.read_buffer.lock().unwrap().clear();
+        self.notifications.lock().unwrap().clear();
+        self.invalidate_graph();
+
+        let exited = self.ingen_exited()?;
+        if exited {
+            warn!("Ingen exited, starting it again");
+            // The new instance starts empty
+            self.control_values.lock().unwrap().clear();
+            self.monitored_ports.lock().unwrap().clear();
+            self.start_ingen(None)?;
+        }
+        self.connect_socket()?;
+        Ok(exited)
+    }
+
+    /// Take the recent lines Ingen printed on its error output
+    fn take_error_output(&self) -> Vec<String> {
+        self.error_output.lock().unwrap()
+            .drain(..)
+            .filter(|(time, _)| time.elapsed() < ERROR_OUTPUT_AGE)
+            .map(|(_, line)| line)
+            .collect()
+    }
+
+    /// Start the Ingen GUI on the same socket, from which the UI of the block is opened
+    /// Ingen runs the plugins, so their UIs must live in a client it talks to
+    fn open_plugin_ui(&self, block_id: &str) -> Result<()> {
+        use std::process::{Command, Stdio};
+
+        let mut gui_process = self.gui_process.lock().unwrap();
+        if let Some(process) = gui_process.as_mut() {
+            if matches!(process.try_wait(), Ok(None)) {
+                debug!("Ingen GUI already running for '{}'", block_id);
+                return Ok(());
+            }
+        }
+
+        let child = Command::new("ingen")
+            .arg("-g")  // GUI mode
+            .arg("-c")  // Connect to the engine
+            .arg(format!("unix://{}", self.socket_path))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start the Ingen GUI: {}", e))?;
+
+        info!("Ingen GUI started for '{}' (PID: {})", block_id, child.id());
+        *gui_process = Some(child);
+        Ok(())
+    }
+
+    /// Connect two ports
+    fn connect(&self, source: &str, destination: &str) -> Result<()> {
+        info!("Connecting '{}' to '{}'", source, destination);
+
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_connect(source, destination)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        Ok(())
+    }
+
+    /// Disconnect two ports
+    fn disconnect(&self, source: &str, destination: &str) -> Result<()> {
+        info!("Disconnecting '{}' from '{}'", source, destination);
+
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_disconnect(source, destination)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        Ok(())
+    }
+
+    /// Create an input port
+    fn create_input_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        info!("Creating {:?} input port '{}'", port_type, port_name);
+
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_create_port(port_name, &port_type, &PortDirection::Input)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        // Return the port path
+        Ok(format!("ingen:/main/{}", port_name))
+    }
+
+    /// Create an output port
+    fn create_output_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        info!("Creating {:?} output port '{}'", port_type, port_name);
+
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_create_port(port_name, &port_type, &PortDirection::Output)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        // Return the port path
+        Ok(format!("ingen:/main/{}", port_name))
+    }
+
+    /// Delete a block or a system port
+    fn delete(&self, path: &str) -> Result<()> {
+        info!("Deleting '{}'", path);
+
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_delete(path)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        self.block_prototypes.lock().unwrap().remove(path);
+        let nested = format!("{}/", path);
+        self.control_values.lock().unwrap().retain(|block, _| block != path && !block.starts_with(&nested));
+        self.monitored_ports.lock().unwrap().retain(|port| !port.starts_with(&nested));
+        Ok(())
+    }
+
+    fn begin_bundle(&self) -> Result<()> {
+        let mut bundles = self.bundles.lock().unwrap();
+        if bundles.contains_key(&thread::current().id()) {
+            return Err(anyhow!("A bundle is already started, bundles cannot be nested"));
+        }
+        bundles.insert(thread::current().id(), Vec::new());
+        IngenProtocol::set_bundled(true);
+        Ok(())
+    }
+
+    fn end_bundle(&self) -> Result<()> {
+        let Some(messages) = self.bundles.lock().unwrap().remove(&thread::current().id()) else {
+            return Ok(());
+        };
+        IngenProtocol::set_bundled(false);
+        if messages.is_empty() {
+            return Ok(());
+        }
+        debug!("Sending a bundle of {} messages", messages.len());
+        self.write_message(&IngenProtocol::build_bundle(&messages)?)
+    }
+
+    fn move_block(&self, block_path: &str, new_path: &str) -> Result<()> {
+        info!("Moving '{}' to '{}'", block_path, new_path);
+
+        let message = IngenProtocol::build_move(block_path, new_path)?;
+        
+        // Send to Ingen
+        self.invalidate_graph();
+        self.send_message(&message)?;
+
+        // The cached block details follow the block and its ports
+        let relocate = |path: &str| super::moved_path(path, block_path, new_path).unwrap_or_else(|| path.to_string());
+        let mut prototypes = self.block_prototypes.lock().unwrap();
+        *prototypes = prototypes.drain().map(|(path, prototype)| (relocate(&path), prototype)).collect();
+        drop(prototypes);
+        let mut values = self.control_values.lock().unwrap();
+        *values = values.drain().map(|(path, values)| (relocate(&path), values)).collect();
+        drop(values);
+        let mut monitored = self.monitored_ports.lock().unwrap();
+        *monitored = monitored.drain().map(|port| relocate(&port)).collect();
+        Ok(())
+    }
+
+    /// Get the raw state of the engine as a string
+    fn get_raw_state(&self) -> Result<String> {
+        info!("Getting raw engine state");
+        
+        // Build RDF message using protocol module
+        let message = IngenProtocol::build_get_state()?;
+        
+        // Send to Ingen
+        self.write_message(&message)?;
+        
+        // Receive response (full state)
+        let response = self.receive_message()?;
+        super::trace::record_state(&response);
+        
+        Ok(response)
+    }
+
+    /// Set the raw state of the engine from a string
+    fn set_raw_state(&self, state_data: &str) -> Result<()> {
+        debug!("Setting raw engine state ({} bytes)", state_data.len());
+        
+        // Send data diretly to Ingen
+        self.invalidate_graph();
+        self.control_values.lock().unwrap().clear();
+        self.monitored_ports.lock().unwrap().clear();
+        self.send_message(state_data)?;
+        
+        Ok(())
+    }
+
+    /// Check that a state is valid Turtle
+    fn validate_raw_state(&self, state_data: &str) -> Result<()> {
+        IngenProtocol::parse_response(state_data)?;
+        Ok(())
+    }
+
+    /// Parse a saved state like a state response
+    fn parse_raw_state(&self, state_data: &str) -> Result<StateContent> {
+        let graph = IngenProtocol::parse_graph(state_data)?;
+        let mut values = HashMap::new();
+        for block in &graph.blocks {
+            values.insert(block.id.clone(), IngenProtocol::parse_control_values(state_data, &block.id)?);
+        }
+        Ok(StateContent { graph, values })
+    }
+
+    /// Let Ingen parse the bundle into the main graph
+    fn import_bundle(&self, bundle_path: &Path) -> Result<()> {
+        let bundle_path = bundle_path.canonicalize()?;
+        info!("Importing Ingen bundle {:?}", bundle_path);
+        
+        // Bundle URIs end with a slash like the directory they name
+        let source_uri = format!("file://{}/", bundle_path.display());
+        let message = IngenProtocol::build_copy(&source_uri, "ingen:/main")?;
+        
+        self.invalidate_graph();
+        self.control_values.lock().unwrap().clear();
+        self.send_message(&message)?;
+        
+        Ok(())
+    }
+
+    /// Ask Ingen to write the main graph as a bundle, waiting until it answered
+    fn save_bundle(&self, bundle_path: &Path) -> Result<()> {
+        let (Some(dir), Some(name)) = (bundle_path.parent(), bundle_path.file_name()) else {
+            return Err(anyhow!("Invalid bundle path {:?}", bundle_path));
+        };
+        let bundle_path = dir.canonicalize()?.join(name);
+        info!("Saving Ingen bundle {:?}", bundle_path);
+        
+        let destination = format!("file://{}/", bundle_path.display());
+        let message = IngenProtocol::build_copy("ingen:/main/", &destination)?;
+        self.write_message(&message)?;
+        
+        // Ingen handles the messages in order, the answer to a ping comes once the bundle is written
+        self.ping(self.start_timeout)
+    }
+
+    fn preloaded_bundle(&self) -> Option<PathBuf> {
+        self.preloaded_bundle.clone()
+    }
+
+    /// Get the current graph from Ingen
+    fn get_graph(&self) -> Result<Graph> {
+        let generation = {
+            let cache = self.graph_cache.lock().unwrap();
+            if let Some(graph) = &cache.graph {
+                trace!("Using cached graph");
+                return Ok(graph.clone());
+            }
+            cache.generation
+        };
+        
+        debug!("Getting graph from Ingen");
+        
+        // Build RDF message to get the graph
+        let message = IngenProtocol::build_get_state()?;
+        
+        // Send to Ingen
+        self.write_message(&message)?;
+        
+        // Receive and parse response
+        let response = self.receive_message()?;
+        let graph = IngenProtocol::parse_graph(&response)?;
+        
+        // Remember block prototypes for parameter lookup
+        let mut prototypes = self.block_prototypes.lock().unwrap();
+        for block in &graph.blocks {
+            prototypes.insert(block.id.clone(), block.prototype.clone());
+        }
+        drop(prototypes);
+        
+        trace!("Parsed graph: {} blocks, {} connections, {} system ports", 
+               graph.blocks.len(), graph.connections.len(), graph.ports.len());
+        trace!("Blocks: {:?}", graph.blocks);
+        trace!("Connections: {:?}", graph.connections);
+        trace!("System ports: {:?}", graph.ports);
+        
+        let mut cache = self.graph_cache.lock().unwrap();
+        if cache.generation == generation {
+            cache.graph = Some(graph.clone());
+        }
+        
+        Ok(graph)
+    }
+
+    /// Drop the cached graph
+    fn invalidate_graph(&self) {
+        let mut cache = self.graph_cache.lock().unwrap();
+        cache.generation += 1;
+        cache.graph = None;
+    }
+
+    fn close(&self) {
+        debug!("Shutting down Ingen backend...");
+        
+        if let Some(mut gui) = self.gui_process.lock().unwrap().take() {
+            let _ = gui.kill();
+            let _ = gui.wait();
+        }
+        
+        // Clean up Ingen process if running
+        if let Some(mut process) = self.ingen_process.lock().unwrap().take() {
+            // Get the process ID
+            let pid = process.id();
+            debug!("Sending SIGTERM to Ingen process (PID: {})", pid);
+            
+            // Send SIGTERM signal using nix crate
+            #[cfg(unix)]
+            {
+                use nix::sys::signal::{self, Signal};
+                use nix::unistd::Pid;
+                
+                let _ = signal::kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
+            }
+            
+            // Wait for the process to exit gracefully
+            match process.wait() {
+                Ok(status) => debug!("Ingen process exited with {}", status),
+                Err(e) => eprintln!("Error waiting for Ingen process to exit: {}", e),
+            }
+        }
+    }
+}
+
+/// Get the URI of the graph holding a block, with the trailing slash Ingen uses for the main graph
+fn parent_graph(block_id: &str) -> String {
+    match block_id.rsplit_once('/') {
+        Some((parent, _)) if parent != "ingen:/main" => parent.to_string(),
+        _ => "ingen:/main/".to_string(),
+    }
+}
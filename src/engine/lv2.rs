@@ -1,8 +1,10 @@
 use anyhow::Result;
-use log::debug;
-use std::ffi::CStr;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::path::Path;
 
-use super::{Plugin, Port, PortType, PortDirection};
+use super::{ControlPort, Plugin, PluginPreset, Port, PortType, PortDirection};
 
 /// LV2 plugin discovery using lilv
 pub struct Lv2World {
@@ -27,11 +29,53 @@ impl Lv2World {
         }
     }
     
-    /// Get the list of all available plugins
-    pub fn list_plugins(&self) -> Vec<Plugin> {
+    /// Create an empty LV2 world, bundles are then loaded one by one
+    pub fn empty() -> Result<Self> {
+        debug!("Initializing empty LV2 world...");
+        
+        unsafe {
+            let world = lilv_sys::lilv_world_new();
+            if world.is_null() {
+                return Err(anyhow::anyhow!("Failed to create lilv world"));
+            }
+            Ok(Self { world })
+        }
+    }
+    
+    /// Load a single bundle directory
+    pub fn load_bundle(&self, bundle: &Path) {
+        // Bundle URIs must end with a slash
+        let path = format!("{}/", bundle.to_string_lossy().trim_end_matches('/'));
+        let Ok(path) = CString::new(path) else {
+            warn!("Invalid bundle path: {:?}", bundle);
+            return;
+        };
+        
+        unsafe {
+            let uri = lilv_sys::lilv_new_file_uri(self.world, std::ptr::null(), path.as_ptr());
+            if uri.is_null() {
+                warn!("Failed to create bundle URI for {:?}", bundle);
+                return;
+            }
+            lilv_sys::lilv_world_load_bundle(self.world, uri);
+            lilv_sys::lilv_node_free(uri);
+        }
+    }
+    
+    /// Load specifications and plugin classes after loading bundles one by one
+    pub fn load_specifications(&self) {
+        unsafe {
+            lilv_sys::lilv_world_load_specifications(self.world);
+            lilv_sys::lilv_world_load_plugin_classes(self.world);
+        }
+    }
+    
+    /// Get all available plugins grouped by bundle directory path
+    pub fn list_plugins_by_bundle(&self) -> HashMap<String, Vec<Plugin>> {
         debug!("Listing LV2 plugins...");
         
-        let mut plugins = Vec::new();
+        let mut plugins: HashMap<String, Vec<Plugin>> = HashMap::new();
+        let mut count = 0;
         
         unsafe {
             let all_plugins = lilv_sys::lilv_world_get_all_plugins(self.world);
@@ -67,17 +111,213 @@ impl Lv2World {
                 
                 if !id.is_empty() {
                     let ports = self.get_plugin_ports(plugin);
-                    plugins.push(Plugin { id, name, ports });
+                    let mut controls = self.get_plugin_controls(plugin);
+                    controls.extend(self.get_plugin_parameters(plugin));
+                    let category = Self::get_plugin_category(plugin);
+                    let presets = self.get_plugin_presets(plugin);
+                    let author = Self::take_string(lilv_sys::lilv_plugin_get_author_name(plugin));
+                    let license = self.get_plugin_license(plugin);
+                    let has_ui = Self::has_plugin_ui(plugin);
+                    let latency_port = Self::get_latency_port(plugin);
+                    let bundle = Self::get_plugin_bundle(plugin).unwrap_or_default();
+                    plugins.entry(bundle).or_default().push(Plugin {
+                        id,
+                        name,
+                        ports,
+                        controls,
+                        category,
+                        presets,
+                        author,
+                        license,
+                        has_ui,
+                        latency_port,
+                    });
+                    count += 1;
                 }
                 
                 iter = lilv_sys::lilv_plugins_next(all_plugins, iter);
             }
         }
         
-        debug!("Discovered {} LV2 plugins", plugins.len());
+        debug!("Discovered {} LV2 plugins", count);
         plugins
     }
     
+    /// Get the class label of a plugin (e.g., "Reverb")
+    unsafe fn get_plugin_category(plugin: *const lilv_sys::LilvPlugin) -> Option<String> {
+        let class = lilv_sys::lilv_plugin_get_class(plugin);
+        if class.is_null() {
+            return None;
+        }
+        let label = lilv_sys::lilv_plugin_class_get_label(class);
+        if label.is_null() {
+            return None;
+        }
+        let label_cstr = lilv_sys::lilv_node_as_string(label);
+        if label_cstr.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(label_cstr).to_string_lossy().to_string())
+    }
+    
+    /// Check whether a plugin ships its own UI
+    unsafe fn has_plugin_ui(plugin: *const lilv_sys::LilvPlugin) -> bool {
+        let uis = lilv_sys::lilv_plugin_get_uis(plugin);
+        if uis.is_null() {
+            return false;
+        }
+        let has_ui = lilv_sys::lilv_uis_size(uis) > 0;
+        lilv_sys::lilv_uis_free(uis);
+        has_ui
+    }
+    
+    /// Get the symbol of the port reporting the latency of a plugin (lv2:latency), if it has one
+    unsafe fn get_latency_port(plugin: *const lilv_sys::LilvPlugin) -> Option<String> {
+        if !lilv_sys::lilv_plugin_has_latency(plugin) {
+            return None;
+        }
+        let index = lilv_sys::lilv_plugin_get_latency_port_index(plugin);
+        let port = lilv_sys::lilv_plugin_get_port_by_index(plugin, index);
+        if port.is_null() {
+            return None;
+        }
+        let symbol_cstr = lilv_sys::lilv_node_as_string(lilv_sys::lilv_port_get_symbol(plugin, port));
+        if symbol_cstr.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(symbol_cstr).to_string_lossy().to_string())
+    }
+    
+    /// Get the license of a plugin, shortened from its URI (e.g., ".../licenses/GPL" -> "GPL")
+    fn get_plugin_license(&self, plugin: *const lilv_sys::LilvPlugin) -> Option<String> {
+        unsafe {
+            let license_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://usefulinc.com/ns/doap#license\0".as_ptr() as *const i8,
+            );
+            
+            let values = lilv_sys::lilv_plugin_get_value(plugin, license_predicate);
+            let license = if !values.is_null() {
+                let first = lilv_sys::lilv_nodes_get_first(values);
+                let license = if !first.is_null() {
+                    let cstr = lilv_sys::lilv_node_as_string(first);
+                    if !cstr.is_null() {
+                        let uri = CStr::from_ptr(cstr).to_string_lossy().to_string();
+                        Some(uri.trim_end_matches('/').rsplit(['/', '#']).next().unwrap_or(&uri).to_string())
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                };
+                lilv_sys::lilv_nodes_free(values);
+                license
+            } else {
+                None
+            };
+            
+            lilv_sys::lilv_node_free(license_predicate);
+            license
+        }
+    }
+    
+    /// Get the factory presets of a plugin
+    fn get_plugin_presets(&self, plugin: *const lilv_sys::LilvPlugin) -> Vec<PluginPreset> {
+        let mut presets = Vec::new();
+        
+        unsafe {
+            let preset_class = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/ext/presets#Preset\0".as_ptr() as *const i8,
+            );
+            let label_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://www.w3.org/2000/01/rdf-schema#label\0".as_ptr() as *const i8,
+            );
+            let port_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/lv2core#port\0".as_ptr() as *const i8,
+            );
+            let symbol_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/lv2core#symbol\0".as_ptr() as *const i8,
+            );
+            let value_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/ext/presets#value\0".as_ptr() as *const i8,
+            );
+            
+            let related = lilv_sys::lilv_plugin_get_related(plugin, preset_class);
+            if !related.is_null() {
+                let mut iter = lilv_sys::lilv_nodes_begin(related);
+                while !lilv_sys::lilv_nodes_is_end(related, iter) {
+                    let preset = lilv_sys::lilv_nodes_get(related, iter);
+                    lilv_sys::lilv_world_load_resource(self.world, preset);
+                    
+                    // Get preset name, falling back to the end of its URI
+                    let label_node = lilv_sys::lilv_world_get(self.world, preset, label_predicate, std::ptr::null());
+                    let name = Self::take_string(label_node).unwrap_or_else(|| {
+                        let uri = CStr::from_ptr(lilv_sys::lilv_node_as_string(preset)).to_string_lossy().to_string();
+                        uri.rsplit(['#', '/']).next().unwrap_or(&uri).to_string()
+                    });
+                    
+                    // Get port values
+                    let mut values = HashMap::new();
+                    let ports = lilv_sys::lilv_world_find_nodes(self.world, preset, port_predicate, std::ptr::null());
+                    if !ports.is_null() {
+                        let mut port_iter = lilv_sys::lilv_nodes_begin(ports);
+                        while !lilv_sys::lilv_nodes_is_end(ports, port_iter) {
+                            let port = lilv_sys::lilv_nodes_get(ports, port_iter);
+                            let symbol = Self::take_string(
+                                lilv_sys::lilv_world_get(self.world, port, symbol_predicate, std::ptr::null()));
+                            let value = Self::take_float(
+                                lilv_sys::lilv_world_get(self.world, port, value_predicate, std::ptr::null()));
+                            if let (Some(symbol), Some(value)) = (symbol, value) {
+                                values.insert(symbol, value);
+                            }
+                            port_iter = lilv_sys::lilv_nodes_next(ports, port_iter);
+                        }
+                        lilv_sys::lilv_nodes_free(ports);
+                    }
+                    
+                    if !values.is_empty() {
+                        presets.push(PluginPreset { name, values });
+                    }
+                    
+                    iter = lilv_sys::lilv_nodes_next(related, iter);
+                }
+                lilv_sys::lilv_nodes_free(related);
+            }
+            
+            lilv_sys::lilv_node_free(preset_class);
+            lilv_sys::lilv_node_free(label_predicate);
+            lilv_sys::lilv_node_free(port_predicate);
+            lilv_sys::lilv_node_free(symbol_predicate);
+            lilv_sys::lilv_node_free(value_predicate);
+        }
+        
+        presets
+    }
+    
+    /// Get the bundle directory path of a plugin, without trailing slash
+    unsafe fn get_plugin_bundle(plugin: *const lilv_sys::LilvPlugin) -> Option<String> {
+        let bundle_uri = lilv_sys::lilv_plugin_get_bundle_uri(plugin);
+        if bundle_uri.is_null() {
+            return None;
+        }
+        let uri_cstr = lilv_sys::lilv_node_as_uri(bundle_uri);
+        if uri_cstr.is_null() {
+            return None;
+        }
+        let path_cstr = lilv_sys::lilv_file_uri_parse(uri_cstr, std::ptr::null_mut());
+        if path_cstr.is_null() {
+            return None;
+        }
+        let path = CStr::from_ptr(path_cstr).to_string_lossy().trim_end_matches('/').to_string();
+        lilv_sys::lilv_free(path_cstr as *mut std::os::raw::c_void);
+        Some(path)
+    }
+    
     /// Get the ports of a plugin
     fn get_plugin_ports(&self, plugin: *const lilv_sys::LilvPlugin) -> Vec<Port> {
         let mut ports = Vec::new();
@@ -165,6 +405,239 @@ impl Lv2World {
         
         ports
     }
+    
+    /// Get the control input ports (parameters) of a plugin
+    fn get_plugin_controls(&self, plugin: *const lilv_sys::LilvPlugin) -> Vec<ControlPort> {
+        let mut controls = Vec::new();
+        
+        unsafe {
+            let num_ports = lilv_sys::lilv_plugin_get_num_ports(plugin);
+            
+            let input_class = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/lv2core#InputPort\0".as_ptr() as *const i8,
+            );
+            let control_class = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/lv2core#ControlPort\0".as_ptr() as *const i8,
+            );
+            let unit_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/extensions/units#unit\0".as_ptr() as *const i8,
+            );
+            let group_predicate = lilv_sys::lilv_new_uri(
+                self.world,
+                b"http://lv2plug.in/ns/ext/port-groups#group\0".as_ptr() as *const i8,
+            );
+            
+            for i in 0..num_ports {
+                let port = lilv_sys::lilv_plugin_get_port_by_index(plugin, i);
+                
+                // Only keep control input ports
+                if !lilv_sys::lilv_port_is_a(plugin, port, input_class)
+                    || !lilv_sys::lilv_port_is_a(plugin, port, control_class)
+                {
+                    continue;
+                }
+                
+                // Get port symbol (ID)
+                let symbol_node = lilv_sys::lilv_port_get_symbol(plugin, port);
+                let symbol_cstr = lilv_sys::lilv_node_as_string(symbol_node);
+                let id = if !symbol_cstr.is_null() {
+                    CStr::from_ptr(symbol_cstr).to_string_lossy().to_string()
+                } else {
+                    continue; // Skip ports without symbols
+                };
+                
+                // Get port name, falling back to the symbol
+                let name_node = lilv_sys::lilv_port_get_name(plugin, port);
+                let name = if !name_node.is_null() {
+                    let name_cstr = lilv_sys::lilv_node_as_string(name_node);
+                    let name_string = if !name_cstr.is_null() {
+                        CStr::from_ptr(name_cstr).to_string_lossy().to_string()
+                    } else {
+                        id.clone()
+                    };
+                    lilv_sys::lilv_node_free(name_node);
+                    name_string
+                } else {
+                    id.clone()
+                };
+                
+                // Get value range
+                let mut def_node: *mut lilv_sys::LilvNode = std::ptr::null_mut();
+                let mut min_node: *mut lilv_sys::LilvNode = std::ptr::null_mut();
+                let mut max_node: *mut lilv_sys::LilvNode = std::ptr::null_mut();
+                lilv_sys::lilv_port_get_range(plugin, port, &mut def_node, &mut min_node, &mut max_node);
+                
+                let min = Self::take_float(min_node).unwrap_or(0.0);
+                let max = Self::take_float(max_node).unwrap_or(1.0);
+                let default = Self::take_float(def_node).unwrap_or(min);
+                
+                // Get unit symbol from the units URI (e.g., "...units#ms" -> "ms")
+                let unit_node = lilv_sys::lilv_port_get(plugin, port, unit_predicate);
+                let unit = if !unit_node.is_null() {
+                    let unit_cstr = lilv_sys::lilv_node_as_uri(unit_node);
+                    let unit_string = if !unit_cstr.is_null() {
+                        let uri = CStr::from_ptr(unit_cstr).to_string_lossy().to_string();
+                        uri.rsplit('#').next().map(|u| u.to_string())
+                    } else {
+                        None
+                    };
+                    lilv_sys::lilv_node_free(unit_node);
+                    unit_string
+                } else {
+                    None
+                };
+                
+                controls.push(ControlPort {
+                    id,
+                    name,
+                    min,
+                    max,
+                    default,
+                    unit,
+                    property: None,
+                    group: self.get_port_group(plugin, port, group_predicate),
+                });
+            }
+            
+            lilv_sys::lilv_node_free(input_class);
+            lilv_sys::lilv_node_free(control_class);
+            lilv_sys::lilv_node_free(unit_predicate);
+            lilv_sys::lilv_node_free(group_predicate);
+        }
+        
+        controls
+    }
+    
+    /// Get the label of the port group of a port, falling back to the end of the group URI
+    unsafe fn get_port_group(
+        &self,
+        plugin: *const lilv_sys::LilvPlugin,
+        port: *const lilv_sys::LilvPort,
+        group_predicate: *mut lilv_sys::LilvNode,
+    ) -> Option<String> {
+        let group = lilv_sys::lilv_port_get(plugin, port, group_predicate);
+        if group.is_null() {
+            return None;
+        }
+        let uri_cstr = lilv_sys::lilv_node_as_string(group);
+        let uri = if !uri_cstr.is_null() {
+            CStr::from_ptr(uri_cstr).to_string_lossy().to_string()
+        } else {
+            String::new()
+        };
+        
+        let mut label = None;
+        for predicate_uri in [&b"http://www.w3.org/2000/01/rdf-schema#label\0"[..], &b"http://lv2plug.in/ns/lv2core#name\0"[..]] {
+            let predicate = lilv_sys::lilv_new_uri(self.world, predicate_uri.as_ptr() as *const i8);
+            label = Self::take_string(lilv_sys::lilv_world_get(self.world, group, predicate, std::ptr::null()));
+            lilv_sys::lilv_node_free(predicate);
+            if label.is_some() {
+                break;
+            }
+        }
+        lilv_sys::lilv_node_free(group);
+        
+        label.or_else(|| uri.rsplit(['#', '/']).next().filter(|name| !name.is_empty()).map(|name| name.to_string()))
+    }
+    
+    /// Get the numeric patch parameters a plugin accepts through its atom input (patch:writable)
+    fn get_plugin_parameters(&self, plugin: *const lilv_sys::LilvPlugin) -> Vec<ControlPort> {
+        let mut parameters = Vec::new();
+        
+        unsafe {
+            let uri = |uri: &[u8]| lilv_sys::lilv_new_uri(self.world, uri.as_ptr() as *const i8);
+            let writable_predicate = uri(b"http://lv2plug.in/ns/ext/patch#writable\0");
+            let label_predicate = uri(b"http://www.w3.org/2000/01/rdf-schema#label\0");
+            let range_predicate = uri(b"http://www.w3.org/2000/01/rdf-schema#range\0");
+            let minimum_predicate = uri(b"http://lv2plug.in/ns/lv2core#minimum\0");
+            let maximum_predicate = uri(b"http://lv2plug.in/ns/lv2core#maximum\0");
+            let default_predicate = uri(b"http://lv2plug.in/ns/lv2core#default\0");
+            let unit_predicate = uri(b"http://lv2plug.in/ns/extensions/units#unit\0");
+            
+            let writables = lilv_sys::lilv_plugin_get_value(plugin, writable_predicate);
+            if !writables.is_null() {
+                let mut iter = lilv_sys::lilv_nodes_begin(writables);
+                while !lilv_sys::lilv_nodes_is_end(writables, iter) {
+                    let parameter = lilv_sys::lilv_nodes_get(writables, iter);
+                    iter = lilv_sys::lilv_nodes_next(writables, iter);
+                    
+                    let get = |predicate| lilv_sys::lilv_world_get(self.world, parameter, predicate, std::ptr::null());
+                    let property_cstr = lilv_sys::lilv_node_as_uri(parameter);
+                    if property_cstr.is_null() {
+                        continue;
+                    }
+                    let property = CStr::from_ptr(property_cstr).to_string_lossy().to_string();
+                    
+                    // Only numeric parameters fit a knob, paths and strings are left out
+                    let range = Self::take_string(get(range_predicate)).unwrap_or_default();
+                    let toggle = range.ends_with("#Bool");
+                    if !toggle && !["#Float", "#Double", "#Int", "#Long"].iter().any(|t| range.ends_with(t)) {
+                        debug!("Skipping parameter {} of range '{}'", property, range);
+                        continue;
+                    }
+                    
+                    let id = property.rsplit(['#', '/']).next().unwrap_or(&property).to_string();
+                    let name = Self::take_string(get(label_predicate)).unwrap_or_else(|| id.clone());
+                    let min = Self::take_float(get(minimum_predicate)).unwrap_or(0.0);
+                    let max = Self::take_float(get(maximum_predicate)).unwrap_or(1.0);
+                    let default = Self::take_float(get(default_predicate)).unwrap_or(min);
+                    let unit = Self::take_string(get(unit_predicate))
+                        .and_then(|uri| uri.rsplit('#').next().map(|u| u.to_string()));
+                    
+                    parameters.push(ControlPort {
+                        id,
+                        name,
+                        min: if toggle { 0.0 } else { min },
+                        max: if toggle { 1.0 } else { max },
+                        default,
+                        unit,
+                        property: Some(property),
+                        group: None,
+                    });
+                }
+                lilv_sys::lilv_nodes_free(writables);
+            }
+            
+            for predicate in [writable_predicate, label_predicate, range_predicate, minimum_predicate,
+                              maximum_predicate, default_predicate, unit_predicate] {
+                lilv_sys::lilv_node_free(predicate);
+            }
+        }
+        
+        parameters
+    }
+    
+    /// Read a node as a string and free it
+    unsafe fn take_string(node: *mut lilv_sys::LilvNode) -> Option<String> {
+        if node.is_null() {
+            return None;
+        }
+        let cstr = lilv_sys::lilv_node_as_string(node);
+        let value = if !cstr.is_null() {
+            Some(CStr::from_ptr(cstr).to_string_lossy().to_string())
+        } else {
+            None
+        };
+        lilv_sys::lilv_node_free(node);
+        value
+    }
+    
+    /// Read a numeric node as a float and free it
+    unsafe fn take_float(node: *mut lilv_sys::LilvNode) -> Option<f32> {
+        if node.is_null() {
+            return None;
+        }
+        let value = if lilv_sys::lilv_node_is_float(node) || lilv_sys::lilv_node_is_int(node) {
+            Some(lilv_sys::lilv_node_as_float(node))
+        } else {
+            None
+        };
+        lilv_sys::lilv_node_free(node);
+        value
+    }
 }
 
 impl Drop for Lv2World {
@@ -0,0 +1,432 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{Block, Connection, ControlPort, EngineBackend, GRAPH_PROTOTYPE, Graph, Plugin, Port, PortDirection, PortType, StateContent};
+
+/// Prefix of the paths in the main graph, as used by Ingen
+const MAIN_PREFIX: &str = "ingen:/main/";
+
+/// Latency reported by the mock plugins that have one, in frames
+const MOCK_LATENCY_FRAMES: f32 = 64.0;
+
+/// Graph and control values of the mock engine, also used as its raw state
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MockState {
+    graph: Graph,
+    /// Control values keyed by block path, then by port symbol
+    values: HashMap<String, HashMap<String, f32>>,
+    /// Voices of the polyphonic blocks, keyed by block path
+    #[serde(default)]
+    polyphony: HashMap<String, u32>,
+}
+
+/// Engine backend simulating blocks and connections in memory, without processing audio
+pub struct MockBackend {
+    plugins: Vec<Plugin>,
+    state: Mutex<MockState>,
+}
+
+impl MockBackend {
+    /// Create an empty mock graph offering a few simulated plugins
+    pub fn new() -> Self {
+        Self {
+            plugins: mock_plugins(),
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Find a plugin by URI
+    fn plugin(&self, plugin_uri: &str) -> Result<&Plugin> {
+        self.plugins.iter()
+            .find(|p| p.id == plugin_uri)
+            .ok_or_else(|| anyhow!("Plugin not available in the mock engine: {}", plugin_uri))
+    }
+}
+
+impl EngineBackend for MockBackend {
+    fn rescan_plugins(&self, _progress: &dyn Fn(&str)) -> Result<usize> {
+        Ok(self.plugins.len())
+    }
+
+    fn list_plugins(&self) -> Vec<Plugin> {
+        self.plugins.clone()
+    }
+
+    fn get_block_plugin(&self, block_path: &str) -> Option<Plugin> {
+        let state = self.state.lock().unwrap();
+        let block = state.graph.blocks.iter().find(|b| b.id == block_path)?;
+        self.plugin(&block.prototype).ok().cloned()
+    }
+
+    fn create_block(&self, plugin_uri: &str, block_id: &str) -> Result<()> {
+        info!("Creating mock block '{}' with plugin '{}'", block_id, plugin_uri);
+
+        let plugin = self.plugin(plugin_uri)?;
+        let path = format!("{}{}", MAIN_PREFIX, block_id);
+        let mut state = self.state.lock().unwrap();
+        if state.graph.blocks.iter().any(|b| b.id == path) {
+            return Err(anyhow!("Block already exists: {}", path));
+        }
+
+        state.graph.blocks.push(Block {
+            id: path.clone(),
+            name: block_id.to_string(),
+            prototype: plugin.id.clone(),
+            ports: plugin.ports.clone(),
+        });
+        let mut values: HashMap<String, f32> = plugin.controls.iter().map(|c| (c.id.clone(), c.default)).collect();
+        if let Some(symbol) = &plugin.latency_port {
+            values.insert(symbol.clone(), MOCK_LATENCY_FRAMES);
+        }
+        state.values.insert(path, values);
+        Ok(())
+    }
+
+    fn create_graph(&self, graph_id: &str) -> Result<()> {
+        info!("Creating mock graph '{}'", graph_id);
+
+        let path = format!("{}{}", MAIN_PREFIX, graph_id);
+        let mut state = self.state.lock().unwrap();
+        if state.graph.blocks.iter().any(|b| b.id == path) {
+            return Err(anyhow!("Block already exists: {}", path));
+        }
+
+        state.graph.blocks.push(Block {
+            id: path.clone(),
+            name: graph_id.to_string(),
+            prototype: GRAPH_PROTOTYPE.to_string(),
+            ports: Vec::new(),
+        });
+        state.values.insert(path, HashMap::new());
+        Ok(())
+    }
+
+    fn set_control_parameter(&self, block_id: &str, parameter_name: &str, value: f32) -> Result<()> {
+        debug!("Setting '{}' of mock block '{}' to {}", parameter_name, block_id, value);
+
+        let mut state = self.state.lock().unwrap();
+        let values = state.values.get_mut(block_id)
+            .ok_or_else(|| anyhow!("Unknown block: {}", block_id))?;
+        values.insert(parameter_name.to_string(), value);
+        Ok(())
+    }
+
+    fn set_block_name(&self, block_id: &str, name: &str) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let block = state.graph.blocks.iter_mut()
+            .find(|b| b.id == block_id)
+            .ok_or_else(|| anyhow!("Unknown block: {}", block_id))?;
+        block.name = name.to_string();
+        Ok(())
+    }
+
+    fn set_polyphony(&self, block_id: &str, voices: u32) -> Result<()> {
+        debug!("Setting mock block '{}' to {} voices", block_id, voices);
+
+        let mut state = self.state.lock().unwrap();
+        if !state.values.contains_key(block_id) {
+            return Err(anyhow!("Unknown block: {}", block_id));
+        }
+        if voices > 1 {
+            state.polyphony.insert(block_id.to_string(), voices);
+        } else {
+            state.polyphony.remove(block_id);
+        }
+        Ok(())
+    }
+
+    fn get_polyphony(&self, block_id: &str) -> Result<u32> {
+        Ok(self.state.lock().unwrap().polyphony.get(block_id).copied().unwrap_or(1))
+    }
+
+    fn get_control_values(&self, block_id: &str) -> Result<HashMap<String, f32>> {
+        self.state.lock().unwrap().values.get(block_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown block: {}", block_id))
+    }
+
+    fn connect(&self, source: &str, destination: &str) -> Result<()> {
+        info!("Connecting mock '{}' to '{}'", source, destination);
+
+        let connection = Connection {
+            source: source.to_string(),
+            destination: destination.to_string(),
+        };
+        let mut state = self.state.lock().unwrap();
+        if !state.graph.connections.contains(&connection) {
+            state.graph.connections.push(connection);
+        }
+        Ok(())
+    }
+
+    fn disconnect(&self, source: &str, destination: &str) -> Result<()> {
+        info!("Disconnecting mock '{}' from '{}'", source, destination);
+
+        self.state.lock().unwrap().graph.connections
+            .retain(|c| c.source != source || c.destination != destination);
+        Ok(())
+    }
+
+    fn create_input_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        create_port(&mut self.state.lock().unwrap(), port_name, port_type, PortDirection::Input)
+    }
+
+    fn create_output_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        create_port(&mut self.state.lock().unwrap(), port_name, port_type, PortDirection::Output)
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        info!("Deleting mock '{}'", path);
+
+        let mut state = self.state.lock().unwrap();
+        let name = path.strip_prefix(MAIN_PREFIX).unwrap_or(path);
+        // Blocks of a subgraph go with it
+        let below = format!("{}/", path);
+        state.graph.blocks.retain(|b| b.id != path && !b.id.starts_with(&below));
+        state.graph.ports.retain(|p| p.id != name);
+        state.values.retain(|block, _| block != path && !block.starts_with(&below));
+        state.polyphony.retain(|block, _| block != path && !block.starts_with(&below));
+        // Ports of a subgraph are listed with it
+        if let Some((parent, symbol)) = path.rsplit_once('/') {
+            if let Some(block) = state.graph.blocks.iter_mut().find(|b| b.id == parent) {
+                block.ports.retain(|p| p.id != symbol);
+            }
+        }
+
+        // Arcs of a block go to its ports, below its path
+        state.graph.connections.retain(|c| {
+            ![&c.source, &c.destination].iter().any(|end| *end == path || end.starts_with(&below))
+        });
+        Ok(())
+    }
+
+    fn move_block(&self, block_path: &str, new_path: &str) -> Result<()> {
+        info!("Moving mock '{}' to '{}'", block_path, new_path);
+
+        let mut state = self.state.lock().unwrap();
+        if block_path.rsplit_once('/').map(|(parent, _)| parent) != new_path.rsplit_once('/').map(|(parent, _)| parent) {
+            return Err(anyhow!("Cannot move {} to another graph", block_path));
+        }
+        if !state.graph.blocks.iter().any(|b| b.id == block_path) {
+            return Err(anyhow!("Unknown block: {}", block_path));
+        }
+        if state.graph.blocks.iter().any(|b| b.id == new_path) {
+            return Err(anyhow!("Block already exists: {}", new_path));
+        }
+
+        // The blocks of a group, the values and the arcs follow
+        let relocate = |path: &str| super::moved_path(path, block_path, new_path).unwrap_or_else(|| path.to_string());
+        for block in state.graph.blocks.iter_mut() {
+            block.id = relocate(&block.id);
+        }
+        for connection in state.graph.connections.iter_mut() {
+            connection.source = relocate(&connection.source);
+            connection.destination = relocate(&connection.destination);
+        }
+        state.values = std::mem::take(&mut state.values).into_iter().map(|(block, values)| (relocate(&block), values)).collect();
+        state.polyphony = std::mem::take(&mut state.polyphony).into_iter().map(|(block, voices)| (relocate(&block), voices)).collect();
+        Ok(())
+    }
+
+    fn get_raw_state(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&*self.state.lock().unwrap())?)
+    }
+
+    fn set_raw_state(&self, state_data: &str) -> Result<()> {
+        let state: MockState = serde_json::from_str(state_data)
+            .context("Not a session saved with the mock engine")?;
+        *self.state.lock().unwrap() = state;
+        Ok(())
+    }
+
+    fn validate_raw_state(&self, state_data: &str) -> Result<()> {
+        serde_json::from_str::<MockState>(state_data)
+            .context("Not a session saved with the mock engine")?;
+        Ok(())
+    }
+
+    fn parse_raw_state(&self, state_data: &str) -> Result<StateContent> {
+        let state: MockState = serde_json::from_str(state_data)
+            .context("Not a session saved with the mock engine")?;
+        Ok(StateContent { graph: state.graph, values: state.values })
+    }
+
+    fn import_bundle(&self, bundle_path: &Path) -> Result<()> {
+        Err(anyhow!("Cannot import {:?}, Ingen graphs need the Ingen engine", bundle_path))
+    }
+
+    fn get_graph(&self) -> Result<Graph> {
+        Ok(self.state.lock().unwrap().graph.clone())
+    }
+
+    fn close(&self) {
+        debug!("Shutting down mock engine");
+    }
+}
+
+/// Add a system port, or a port of a subgraph when the name has its path, to the mock graph and return its path
+fn create_port(state: &mut MockState, port_name: &str, port_type: PortType, direction: PortDirection) -> Result<String> {
+    info!("Creating mock {:?} {:?} port '{}'", port_type, direction, port_name);
+
+    if let Some((graph_id, symbol)) = port_name.rsplit_once('/') {
+        let path = format!("{}{}", MAIN_PREFIX, graph_id);
+        let graph = state.graph.blocks.iter_mut()
+            .find(|b| b.id == path && b.prototype == GRAPH_PROTOTYPE)
+            .ok_or_else(|| anyhow!("Unknown graph: {}", path))?;
+        if !graph.ports.iter().any(|p| p.id == symbol) {
+            graph.ports.push(Port { id: symbol.to_string(), port_type, direction });
+        }
+    } else if !state.graph.ports.iter().any(|p| p.id == port_name) {
+        state.graph.ports.push(Port { id: port_name.to_string(), port_type, direction });
+    }
+    Ok(format!("{}{}", MAIN_PREFIX, port_name))
+}
+
+/// Build a port of a simulated plugin
+fn port(id: &str, port_type: PortType, direction: PortDirection) -> Port {
+    Port { id: id.to_string(), port_type, direction }
+}
+
+/// Build a control of a simulated plugin
+fn control(id: &str, name: &str, min: f32, max: f32, default: f32, unit: Option<&str>) -> ControlPort {
+    ControlPort {
+        id: id.to_string(),
+        name: name.to_string(),
+        min,
+        max,
+        default,
+        unit: unit.map(str::to_string),
+        property: None,
+        group: None,
+    }
+}
+
+/// Plugins offered by the mock engine, including the gain plugin used by the mixer
+fn mock_plugins() -> Vec<Plugin> {
+    use PortDirection::{Input, Output};
+    use PortType::{Audio, Midi};
+
+    let plugin = |id: &str, name: &str, category: &str, ports: Vec<Port>, controls: Vec<ControlPort>| Plugin {
+        id: id.to_string(),
+        name: name.to_string(),
+        ports,
+        controls,
+        category: Some(category.to_string()),
+        presets: Vec::new(),
+        author: Some("TraxDub".to_string()),
+        license: None,
+        has_ui: false,
+        latency_port: None,
+    };
+
+    vec![
+        plugin(
+            crate::controller::feature::mixer::GAIN_PLUGIN_URI, "Simple Amplifier", "Amplifier",
+            vec![port("in", Audio, Input), port("out", Audio, Output)],
+            vec![control("gain", "Gain", -90.0, 24.0, 0.0, Some("db"))],
+        ),
+        plugin(
+            "urn:traxdub:mock:delay", "Mock Delay", "Delay",
+            vec![port("in", Audio, Input), port("out", Audio, Output)],
+            vec![
+                control("time", "Time", 1.0, 2000.0, 250.0, Some("ms")),
+                control("feedback", "Feedback", 0.0, 1.0, 0.3, None),
+            ],
+        ),
+        plugin(
+            "urn:traxdub:mock:reverb", "Mock Reverb", "Reverb",
+            vec![
+                port("in_l", Audio, Input), port("in_r", Audio, Input),
+                port("out_l", Audio, Output), port("out_r", Audio, Output),
+            ],
+            vec![
+                control("size", "Room Size", 0.0, 1.0, 0.5, None),
+                control("mix", "Mix", 0.0, 1.0, 0.3, None),
+            ],
+        ),
+        plugin(
+            "urn:traxdub:mock:synth", "Mock Synth", "Instrument",
+            vec![port("midi_in", Midi, Input), port("out", Audio, Output)],
+            vec![control("cutoff", "Cutoff", 20.0, 20000.0, 2000.0, Some("hz"))],
+        ),
+        Plugin {
+            latency_port: Some("latency".to_string()),
+            ..plugin(
+                "urn:traxdub:mock:limiter", "Mock Limiter", "Limiter",
+                vec![port("in", Audio, Input), port("out", Audio, Output)],
+                vec![control("ceiling", "Ceiling", -24.0, 0.0, -1.0, Some("db"))],
+            )
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_graph() {
+        let engine = MockBackend::new();
+        let input = engine.create_input_port("audio_in_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        engine.connect(&input, "ingen:/main/delay/in").unwrap();
+        engine.set_control_parameter("ingen:/main/delay", "time", 500.0).unwrap();
+
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(graph.blocks.len(), 1);
+        assert_eq!(graph.ports.len(), 1);
+        assert_eq!(graph.connections.len(), 1);
+        assert_eq!(engine.get_control_values("ingen:/main/delay").unwrap()["time"], 500.0);
+        assert!(engine.create_block("urn:unknown", "other").is_err());
+        engine.set_polyphony("ingen:/main/delay", 4).unwrap();
+        assert_eq!(engine.get_polyphony("ingen:/main/delay").unwrap(), 4);
+
+        // Raw state round trip, then deleting the block drops its arcs
+        let state = engine.get_raw_state().unwrap();
+        engine.delete("ingen:/main/delay").unwrap();
+        assert!(engine.get_graph().unwrap().connections.is_empty());
+        engine.set_raw_state(&state).unwrap();
+        assert_eq!(engine.get_graph().unwrap().blocks.len(), 1);
+    }
+
+    #[test]
+    fn test_mock_move() {
+        let engine = MockBackend::new();
+        engine.create_graph("group_1").unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "group_1/delay").unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "echo").unwrap();
+        engine.connect("ingen:/main/group_1/delay/out", "ingen:/main/group_1/delay/in").unwrap();
+        engine.move_block("ingen:/main/group_1", "ingen:/main/tape").unwrap();
+
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(graph.blocks[1].id, "ingen:/main/tape/delay");
+        assert_eq!(graph.connections[0].source, "ingen:/main/tape/delay/out");
+        assert!(engine.get_control_values("ingen:/main/tape/delay").is_ok());
+        assert!(engine.move_block("ingen:/main/echo", "ingen:/main/tape").is_err());
+        assert!(engine.move_block("ingen:/main/echo", "ingen:/main/tape/echo").is_err());
+    }
+
+    #[test]
+    fn test_mock_subgraph() {
+        let engine = MockBackend::new();
+        engine.create_graph("group_1").unwrap();
+        let input = engine.create_input_port("group_1/in_1", PortType::Audio).unwrap();
+        engine.create_block("urn:traxdub:mock:delay", "group_1/delay").unwrap();
+        engine.connect(&input, "ingen:/main/group_1/delay/in").unwrap();
+
+        let graph = engine.get_graph().unwrap();
+        assert_eq!(graph.blocks.len(), 2);
+        assert!(graph.ports.is_empty());
+        assert_eq!(graph.blocks[0].ports.len(), 1);
+
+        // Deleting the graph deletes its blocks and arcs
+        engine.delete("ingen:/main/group_1").unwrap();
+        let graph = engine.get_graph().unwrap();
+        assert!(graph.blocks.is_empty());
+        assert!(graph.connections.is_empty());
+    }
+}
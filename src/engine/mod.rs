@@ -1,16 +1,23 @@
 pub mod protocol;
 pub mod lv2;
-
-use anyhow::{anyhow, Result};
-use log::{debug, info, warn, trace};
+pub mod cache;
+pub mod ingen;
+pub mod mock;
+pub mod export;
+pub mod trace;
+pub mod capture;
+pub mod split;
+
+use anyhow::Result;
+use log::info;
 use serde::{Deserialize, Serialize};
-use std::io::Write;
-use std::os::unix::net::UnixStream;
-use std::sync::Mutex;
-use std::thread;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
-use protocol::IngenProtocol;
+/// Prototype of the blocks that are subgraphs (groups) rather than plugin instances
+pub const GRAPH_PROTOTYPE: &str = "http://drobilla.net/ns/ingen#Graph";
 
 /// Port type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -37,8 +44,31 @@ pub struct Port {
     pub direction: PortDirection,
 }
 
+/// Control port information (plugin parameter)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlPort {
+    /// Port symbol
+    pub id: String,
+    /// Human-readable port name
+    pub name: String,
+    /// Minimum value
+    pub min: f32,
+    /// Maximum value
+    pub max: f32,
+    /// Default value
+    pub default: f32,
+    /// LV2 unit symbol (e.g., "ms", "s", "hz"), if declared
+    pub unit: Option<String>,
+    /// URI of the patch parameter, for parameters set through atom messages instead of a control port
+    #[serde(default)]
+    pub property: Option<String>,
+    /// Label of the port group the control belongs to (e.g., "Envelope"), if declared
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
 /// Plugin metadata
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Plugin {
     /// The plugin IRI/URI
     pub id: String,
@@ -46,6 +76,35 @@ pub struct Plugin {
     pub name: String,
     /// List of ports
     pub ports: Vec<Port>,
+    /// List of control input ports (parameters)
+    pub controls: Vec<ControlPort>,
+    /// Plugin class label (e.g., "Reverb", "Delay"), if known
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Factory presets shipped with the plugin
+    #[serde(default)]
+    pub presets: Vec<PluginPreset>,
+    /// Author name, if declared
+    #[serde(default)]
+    pub author: Option<String>,
+    /// License (e.g., "GPL"), if declared
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Whether the plugin ships its own UI
+    #[serde(default)]
+    pub has_ui: bool,
+    /// Symbol of the output control port reporting the latency of the plugin in frames, if any
+    #[serde(default)]
+    pub latency_port: Option<String>,
+}
+
+/// Named set of control values for a plugin
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PluginPreset {
+    /// Preset name
+    pub name: String,
+    /// Control values keyed by port symbol
+    pub values: HashMap<String, f32>,
 }
 
 /// Block in the graph (plugin instance)
@@ -55,6 +114,8 @@ pub struct Block {
     pub id: String,
     /// Block name
     pub name: String,
+    /// Plugin URI the block was instantiated from
+    pub prototype: String,
     /// List of ports
     pub ports: Vec<Port>,
 }
@@ -69,7 +130,7 @@ pub struct Connection {
 }
 
 /// Graph representation of the current Ingen state
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Graph {
     /// List of blocks in the graph
     pub blocks: Vec<Block>,
@@ -79,542 +140,206 @@ pub struct Graph {
     pub ports: Vec<Port>,
 }
 
-/// Engine module that encapsulates an Ingen instance
-pub struct Engine {
-    ingen_process: Mutex<Option<std::process::Child>>,
-    socket: Mutex<Option<UnixStream>>,
-    /// List of available LV2 plugins
-    plugins: Vec<Plugin>,
-    /// Buffer for leftover bytes after null terminator
-    read_buffer: Mutex<Vec<u8>>,
+/// Graph and control values read from a saved raw state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateContent {
+    /// Graph of the state
+    pub graph: Graph,
+    /// Control values keyed by block path, then by port symbol
+    pub values: HashMap<String, HashMap<String, f32>>,
 }
 
-impl Engine {
-    /// Create a new engine instance
-    /// 
-    /// # Arguments
-    /// * `use_external` - If true, connect to an external Ingen instance instead of starting a new one
-    pub fn new(use_external: bool) -> Result<Self> {
-        debug!("Initializing Engine...");
-
-        let mut engine = Self {
-            ingen_process: Mutex::new(None),
-            socket: Mutex::new(None),
-            plugins: Vec::new(),
-            read_buffer: Mutex::new(Vec::new()),
-        };
-
-        // Start Ingen in the background (unless using external)
-        if !use_external {
-            engine.start_ingen()?;
-        } else {
-            info!("Using external Ingen instance");
-        }
-        
-        // Connect to Ingen socket
-        engine.connect_socket()?;
-
-        // Discover available plugins from Ingen
-        let ingen_plugin_iris = engine.discover_plugins()?;
-        
-        // Get full plugin metadata from LV2
-        let lv2_world = lv2::Lv2World::new()?;
-        let all_lv2_plugins = lv2_world.list_plugins();
-        
-        // Filter to keep only plugins that Ingen knows about
-        engine.plugins = all_lv2_plugins.into_iter()
-            .filter(|plugin| ingen_plugin_iris.contains(&plugin.id))
-            .collect();
-        
-        info!("Found {} plugins", engine.plugins.len());        
-
-        trace!("Available plugins: {:?}", engine.plugins);
-
-        Ok(engine)
-    }
+/// Get the path of a block, or of anything below it, once the block is moved to a new path
+/// None for the paths outside of the block
+pub fn moved_path(path: &str, block_path: &str, new_path: &str) -> Option<String> {
+    let rest = path.strip_prefix(block_path)?;
+    (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", new_path, rest))
+}
 
-    /// Start the Ingen process
-    fn start_ingen(&mut self) -> Result<()> {
-        debug!("Starting Ingen process...");
-
-        use std::process::{Command, Stdio};
-        use std::thread;
-        use std::time::Duration;
-
-        let child = Command::new("ingen")
-            .arg("-e")  // Engine mode
-            .arg("-S")  // Socket path
-            .arg("/tmp/ingen-traxdub.sock")
-            .arg("-n")  // Client name
-            .arg("TraxDub Engine")
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .map_err(|e| anyhow!("Failed to start ingen process: {}. Make sure ingen is installed.", e))?;
-
-        info!("Ingen process started (PID: {:?})", child.id());
-        *self.ingen_process.lock().unwrap() = Some(child);
-
-        // Give Ingen time to initialize and create the socket
-        debug!("Waiting for Ingen to initialize...");
-        thread::sleep(Duration::from_millis(500));
+/// Operations on the audio graph, implemented by Ingen and by an in-memory mock
+pub trait EngineBackend: Send + Sync {
+    /// Discover the available plugins, returning how many were found
+    /// Can be called at runtime to pick up newly installed plugins, the scan steps are reported to progress
+    fn rescan_plugins(&self, progress: &dyn Fn(&str)) -> Result<usize>;
 
+    /// Get the list of available plugins
+    fn list_plugins(&self) -> Vec<Plugin>;
+
+    /// Get the plugin a block was instantiated from
+    fn get_block_plugin(&self, block_path: &str) -> Option<Plugin>;
+
+    /// Create a new block (plugin instance)
+    fn create_block(&self, plugin_uri: &str, block_id: &str) -> Result<()>;
+
+    /// Create an empty subgraph (e.g. "group_1" for "ingen:/main/group_1"), shown as a block of the graph
+    /// Its blocks and ports are created with paths below it, like "group_1/delay" or "group_1/in_1"
+    fn create_graph(&self, graph_id: &str) -> Result<()>;
+
+    /// Set a control parameter on a block
+    fn set_control_parameter(&self, block_id: &str, parameter_name: &str, value: f32) -> Result<()>;
+
+    /// Set the display name of a block
+    fn set_block_name(&self, block_id: &str, name: &str) -> Result<()>;
+
+    /// Set the number of voices of a block, 1 for a monophonic block
+    /// The voices are those of the graph holding the block, shared by its polyphonic blocks
+    fn set_polyphony(&self, block_id: &str, voices: u32) -> Result<()>;
+
+    /// Get the number of voices of a block, 1 when it is not polyphonic
+    fn get_polyphony(&self, block_id: &str) -> Result<u32>;
+
+    /// Get the current control values of a block, keyed by port symbol
+    fn get_control_values(&self, block_id: &str) -> Result<HashMap<String, f32>>;
+
+    /// Ask the engine to notify the peak level of an audio port, or stop it
+    /// Backends that do not process audio have no level to notify
+    fn set_port_monitored(&self, _port_path: &str, _monitored: bool) -> Result<()> {
         Ok(())
     }
 
-    /// Connect to the Ingen Unix socket
-    fn connect_socket(&mut self) -> Result<()> {
-        debug!("Connecting to Ingen socket...");
-        
-        let socket_path = "/tmp/ingen-traxdub.sock";
-        
-        // Retry connection a few times in case Ingen is still initializing
-        let mut attempts = 0;
-        let max_attempts = 10;
-        
-        loop {
-            match UnixStream::connect(socket_path) {
-                Ok(stream) => {
-                    info!("Connected to Ingen socket");
-                    *self.socket.lock().unwrap() = Some(stream);
-                    
-                    // Send initialization message with RDF prefixes
-                    debug!("Sending initialization message to Ingen");
-                    self.send_message(IngenProtocol::get_init_message())?;
-                    
-                    return Ok(());
-                }
-                Err(e) => {
-                    attempts += 1;
-                    if attempts >= max_attempts {
-                        return Err(anyhow!("Failed to connect to Ingen socket after {} attempts: {}", max_attempts, e));
-                    }
-                    warn!("Socket connection attempt {} failed, retrying...", attempts);
-                    thread::sleep(Duration::from_millis(200));
-                }
-            }
-        }
-    }
-    
-    /// Drain any pending response data from the socket
-    fn drain_response(&self) -> Result<()> {
-        use std::io::Read;
-        
-        let mut socket_guard = self.socket.lock().unwrap();
-        if let Some(socket) = socket_guard.as_mut() {
-            // Set non-blocking mode with minimal timeout
-            socket.set_read_timeout(Some(Duration::from_millis(1)))
-                .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
-            
-            let mut drain_buf = [0u8; 4096];
-            let mut total_drained = 0;
-            
-            // Keep reading and discarding bytes until none are available
-            loop {
-                match socket.read(&mut drain_buf) {
-                    Ok(0) => break, // EOF
-                    Ok(n) => {
-                        total_drained += n;
-                        // Continue draining
-                    }
-                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || 
-                              e.kind() == std::io::ErrorKind::TimedOut => {
-                        // No more data available
-                        break;
-                    }
-                    Err(e) => return Err(anyhow!("Failed to drain socket: {}", e)),
-                }
-            }
-            
-            if total_drained > 0 {
-                debug!("Drained {} bytes from response stream", total_drained);
-            }
-            
-            // Clear the read buffer as well
-            self.read_buffer.lock().unwrap().clear();
-            
-            Ok(())
-        } else {
-            Err(anyhow!("Not connected to Ingen socket"))
-        }
-    }
-    
-    /// Send a message to Ingen via the Unix socket
-    fn send_message(&self, message: &str) -> Result<()> {
-        trace!("Sending message to Ingen: {}", message);
-        
-        // Drain any pending responses before sending new message
-        self.drain_response()?;
-
-        let mut socket_guard = self.socket.lock().unwrap();
-        if let Some(socket) = socket_guard.as_mut() {
-            socket.write_all(message.as_bytes())
-                .map_err(|e| anyhow!("Failed to write to Ingen socket: {}", e))?;
-            socket.flush()
-                .map_err(|e| anyhow!("Failed to flush Ingen socket: {}", e))?;
-            Ok(())
-        } else {
-            Err(anyhow!("Not connected to Ingen socket"))
-        }
+    /// Get the highest level notified for each monitored port since the last call, 1.0 being full scale
+    fn take_peaks(&self) -> Result<HashMap<String, f32>> {
+        Ok(HashMap::new())
     }
 
-    /// Receive a message from Ingen via Unix socket
-    fn receive_message(&self) -> Result<String> {
-        use std::io::Read;
-        
-        loop {
-            debug!("Receiving message from Ingen...");
-            
-            // Get any buffered bytes from the previous read
-            let mut read_buffer_guard = self.read_buffer.lock().unwrap();
-            let buffered = read_buffer_guard.clone();
-            read_buffer_guard.clear();
-            drop(read_buffer_guard);
-            
-            let mut buffer = buffered;
-            let mut bundle_start_seq: Option<String> = None;
-            let mut bundle_end_seq: Option<String> = None;
-            let mut found_bundle_end = false;
-            
-            let mut socket_guard = self.socket.lock().unwrap();
-            if let Some(socket) = socket_guard.as_mut() {
-                // Set a longer read timeout to handle large messages
-                socket.set_read_timeout(Some(Duration::from_secs(30)))
-                    .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
-                
-                let mut temp_buf = [0u8; 4096];
-                
-                // Keep reading until we find the complete bundle (BundleStart -> BundleEnd -> ".")
-                loop {
-                    match socket.read(&mut temp_buf) {
-                        Ok(0) => {
-                            // EOF - connection closed
-                            if buffer.is_empty() {
-                                return Err(anyhow!("Connection closed by Ingen"));
-                            }
-                            break;
-                        }
-                        Ok(n) => {
-                            // Filter out null bytes and append to buffer
-                            for &byte in &temp_buf[..n] {
-                                if byte != 0 {
-                                    buffer.push(byte);
-                                }
-                            }
-                            
-                            // Convert current buffer to string for line parsing
-                            let buffer_str = String::from_utf8_lossy(&buffer);
-                            
-                            // Look for BundleStart sequence number if not found yet
-                            if bundle_start_seq.is_none() {
-                                if let Some(start_pos) = buffer_str.find("a ingen:BundleStart") {
-                                    // Find the next line with patch:sequenceNumber
-                                    if let Some(seq_pos) = buffer_str[start_pos..].find("patch:sequenceNumber") {
-                                        let after_seq = &buffer_str[start_pos + seq_pos + 20..]; // Skip "patch:sequenceNumber"
-                                        // Extract the number in quotes
-                                        if let Some(quote_start) = after_seq.find('"') {
-                                            if let Some(quote_end) = after_seq[quote_start + 1..].find('"') {
-                                                let seq_num = &after_seq[quote_start + 1..quote_start + 1 + quote_end];
-                                                debug!("Bundle start sequence: {}", seq_num);
-                                                bundle_start_seq = Some(seq_num.to_string());
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            // Look for BundleEnd sequence number if start found but end not yet
-                            if bundle_start_seq.is_some() && bundle_end_seq.is_none() {
-                                if let Some(end_pos) = buffer_str.find("a ingen:BundleEnd") {
-                                    // Find the next line with patch:sequenceNumber
-                                    if let Some(seq_pos) = buffer_str[end_pos..].find("patch:sequenceNumber") {
-                                        let after_seq = &buffer_str[end_pos + seq_pos + 20..]; // Skip "patch:sequenceNumber"
-                                        // Extract the number in quotes
-                                        if let Some(quote_start) = after_seq.find('"') {
-                                            if let Some(quote_end) = after_seq[quote_start + 1..].find('"') {
-                                                let seq_num = &after_seq[quote_start + 1..quote_start + 1 + quote_end];
-                                                debug!("Bundle end sequence: {}", seq_num);
-                                                bundle_end_seq = Some(seq_num.to_string());
-                                                found_bundle_end = true;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            
-                            // If we found bundle end, look for the final dot
-                            if found_bundle_end {
-                                if let Some(dot_pos) = buffer_str.rfind('.') {
-                                    // Check if this dot is the last non-whitespace character
-                                    let after_dot = &buffer_str[dot_pos + 1..];
-                                    if after_dot.trim().is_empty() {
-                                        // Found the response boundary
-                                        debug!("Found response boundary at dot position {}", dot_pos);
-                                        
-                                        // Check if there are more bytes available to read
-                                        socket.set_read_timeout(Some(Duration::from_millis(1)))
-                                            .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
-                                        
-                                        let mut peek_buf = [0u8; 1];
-                                        match socket.read(&mut peek_buf) {
-                                            Ok(0) => {
-                                                // No more data, we can stop
-                                                // Save any bytes after the dot for next read
-                                                let dot_byte_pos = buffer_str[..=dot_pos].len();
-                                                if buffer.len() > dot_byte_pos {
-                                                    let remaining = &buffer[dot_byte_pos..];
-                                                    let mut read_buffer_guard = self.read_buffer.lock().unwrap();
-                                                    read_buffer_guard.extend_from_slice(remaining);
-                                                }
-                                                
-                                                // Truncate buffer at the dot (inclusive)
-                                                buffer.truncate(dot_byte_pos);
-                                                break;
-                                            }
-                                            Ok(_) => {
-                                                // More data available, put the peeked byte back into buffer
-                                                if peek_buf[0] != 0 {
-                                                    buffer.push(peek_buf[0]);
-                                                }
-                                                // Reset timeout and continue reading
-                                                socket.set_read_timeout(Some(Duration::from_secs(30)))
-                                                    .map_err(|e| anyhow!("Failed to set read timeout: {}", e))?;
-                                                // Continue to next read
-                                            }
-                                            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut => {
-                                                // Timeout means no more data available
-                                                // Save any bytes after the dot for next read
-                                                let dot_byte_pos = buffer_str[..=dot_pos].len();
-                                                if buffer.len() > dot_byte_pos {
-                                                    let remaining = &buffer[dot_byte_pos..];
-                                                    let mut read_buffer_guard = self.read_buffer.lock().unwrap();
-                                                    read_buffer_guard.extend_from_slice(remaining);
-                                                }
-                                                
-                                                // Truncate buffer at the dot (inclusive)
-                                                buffer.truncate(dot_byte_pos);
-                                                break;
-                                            }
-                                            Err(e) => return Err(anyhow!("Failed to peek socket: {}", e)),
-                                        }
-                                    }
-
-                                }
-                            }
-                        }
-                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                            // Timeout
-                            if !buffer.is_empty() {
-                                warn!("Read timeout after receiving {} bytes", buffer.len());
-                                break;
-                            } else {
-                                return Err(anyhow!("Timeout waiting for data from Ingen"));
-                            }
-                        }
-                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
-                            // Interrupted system call, retry
-                            continue;
-                        }
-                        Err(e) => return Err(anyhow!("Failed to read from Ingen socket: {}", e)),
-                    }
-                }
-                
-                debug!("Received {} bytes from Ingen", buffer.len());
-                
-                // Convert buffer to String
-                let message = String::from_utf8_lossy(&buffer).to_string();
-                trace!("Response buffer:\n{}", message);
-                
-                // Drop the socket guard before checking message content
-                drop(socket_guard);
-                
-                // Check if this message contains actual content (patch:Put) - if not, ignore and receive again
-                if !message.contains("a patch:Put") {
-                    debug!("Message doesn't contain 'a patch:Put', ignoring and receiving next message");
-                    continue;
-                }
-                
-                return Ok(message);
-            } else {
-                return Err(anyhow!("Not connected to Ingen socket"));
-            }
-        }
+    /// Show the native UI of a plugin block in a separate window
+    fn open_plugin_ui(&self, _block_id: &str) -> Result<()> {
+        Err(anyhow::anyhow!("Plugin UIs are not available with this engine"))
     }
 
-    /// Discover available LV2 plugins from Ingen
-    fn discover_plugins(&mut self) -> Result<Vec<String>> {
-        debug!("Discovering LV2 plugins from Ingen...");
-        
-        self.send_message(&IngenProtocol::build_get_plugins()?)?;
-        let plugins = 
-            IngenProtocol::parse_get_plugins(&self.receive_message()?)?;
-        
-        debug!("Ingen reported {} plugins", plugins.len());
-        Ok(plugins)
+    /// Check that the engine still answers within a timeout
+    /// Backends running in the process always answer
+    fn ping(&self, _timeout: Duration) -> Result<()> {
+        Ok(())
     }
 
-    /// Get the list of available plugins
-    pub fn list_plugins(&self) -> &[Plugin] {
-        &self.plugins
+    /// Connect to the engine again after a failed check, returning whether it was restarted with an empty graph
+    fn reconnect(&self) -> Result<bool> {
+        Ok(false)
     }
 
-    /// Create a new block (plugin instance)
-    pub fn create_block(&self, plugin_uri: &str, block_id: &str) -> Result<()> {
-        info!("Creating block '{}' with plugin '{}'", block_id, plugin_uri);
-        
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_create_block(block_id, plugin_uri)?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
-        
-        Ok(())
+    /// Take the lines the engine printed on its error output in the last seconds, to explain a failure
+    fn take_error_output(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    /// Duplicate a plugin instance
-    pub fn duplicate_block(&self, source_block_id: &str, new_block_id: &str) -> Result<()> {
-        // TODO: Implement
+    /// Connect two ports
+    fn connect(&self, source: &str, destination: &str) -> Result<()>;
+
+    /// Disconnect two ports
+    fn disconnect(&self, source: &str, destination: &str) -> Result<()>;
+
+    /// Create an input port, returning its path
+    fn create_input_port(&self, port_name: &str, port_type: PortType) -> Result<String>;
+
+    /// Create an output port, returning its path
+    fn create_output_port(&self, port_name: &str, port_type: PortType) -> Result<String>;
+
+    /// Delete a block or a system port
+    fn delete(&self, path: &str) -> Result<()>;
+
+    /// Move a block or a group to a new path in the same graph, its ports and connections following
+    fn move_block(&self, block_path: &str, new_path: &str) -> Result<()>;
+
+    /// Collect the next edits of the calling thread to apply them at once, so that the graph is never seen half changed
+    /// Backends without bundles apply each edit right away. Bundles cannot be nested
+    fn begin_bundle(&self) -> Result<()> {
         Ok(())
     }
 
-    /// Set a control parameter on a block
-    pub fn set_control_parameter(
-        &self,
-        block_id: &str,
-        parameter_name: &str,
-        value: f32,
-    ) -> Result<()> {
-        // TODO: Implement
+    /// Apply the edits collected since begin_bundle
+    fn end_bundle(&self) -> Result<()> {
         Ok(())
     }
 
-    /// Connect two ports
-    pub fn connect(&self, source: &str, destination: &str) -> Result<()> {
-        info!("Connecting '{}' to '{}'", source, destination);
+    /// Get the raw state of the engine as a string
+    fn get_raw_state(&self) -> Result<String>;
 
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_connect(source, destination)?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
+    /// Set the raw state of the engine from a string
+    fn set_raw_state(&self, state_data: &str) -> Result<()>;
 
-        Ok(())
-    }
+    /// Check that a raw state parses, without applying it
+    fn validate_raw_state(&self, state_data: &str) -> Result<()>;
 
-    /// Disconnect two ports
-    pub fn disconnect(&self, source: &str, destination: &str) -> Result<()> {
-        info!("Disconnecting '{}' from '{}'", source, destination);
+    /// Read the graph and the control values of a raw state, without applying it
+    fn parse_raw_state(&self, state_data: &str) -> Result<StateContent>;
 
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_disconnect(source, destination)?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
+    /// Add the blocks, ports and connections of a graph bundle saved by Ingen (a .ingen directory)
+    fn import_bundle(&self, bundle_path: &Path) -> Result<()>;
 
-        Ok(())
+    /// Write the graph as a bundle that the engine can load at boot
+    fn save_bundle(&self, _bundle_path: &Path) -> Result<()> {
+        Err(anyhow::anyhow!("Graph bundles are not available with this engine"))
     }
 
-    /// Create an input port
-    pub fn create_input_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
-        info!("Creating {:?} input port '{}'", port_type, port_name);
+    /// Get the graph bundle the engine loaded at boot, if any
+    fn preloaded_bundle(&self) -> Option<PathBuf> {
+        None
+    }
 
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_create_port(port_name, &port_type, &PortDirection::Input)?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
+    /// Get the current graph
+    /// Backends may return a cached graph, reflecting only the changes made through them
+    fn get_graph(&self) -> Result<Graph>;
 
-        // Return the port path
-        Ok(format!("ingen:/main/{}", port_name))
-    }
+    /// Forget any cached graph, so that the next get_graph also shows changes made by other engine clients
+    fn invalidate_graph(&self) {}
 
-    /// Create an output port
-    pub fn create_output_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
-        info!("Creating {:?} output port '{}'", port_type, port_name);
+    /// Release the engine resources
+    fn close(&self);
+}
 
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_create_port(port_name, &port_type, &PortDirection::Output)?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
+/// Engine module giving access to the selected backend
+pub struct Engine {
+    backend: Box<dyn EngineBackend>,
+}
 
-        // Return the port path
-        Ok(format!("ingen:/main/{}", port_name))
+impl Engine {
+    /// Create a new engine instance backed by Ingen
+    /// 
+    /// # Arguments
+    /// * `use_external` - If true, connect to an external Ingen instance instead of starting a new one
+    /// * `socket_path` - Unix socket of the Ingen instance
+    /// * `start_timeout` - Time Ingen has to create its socket and answer
+    /// * `preload` - Graph bundle loaded by a started Ingen at boot
+    /// * `progress` - Called with each startup step
+    pub fn new(use_external: bool, socket_path: &str, start_timeout: Duration, preload: Option<&Path>, progress: &dyn Fn(&str)) -> Result<Self> {
+        Ok(Self {
+            backend: Box::new(ingen::IngenBackend::new(use_external, socket_path, start_timeout, preload, progress)?),
+        })
     }
 
-    /// Get the raw state of the engine as a string
-    pub fn get_raw_state(&self) -> Result<String> {
-        info!("Getting raw engine state");
-        
-        // Build RDF message using protocol module
-        let message = IngenProtocol::build_get_state()?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
-        
-        // Receive response (full state)
-        let response = self.receive_message()?;
-        
-        Ok(response)
+    /// Create a new engine instance simulating the graph in memory
+    pub fn new_mock() -> Self {
+        info!("Using the mock engine, no audio will be processed");
+        Self {
+            backend: Box::new(mock::MockBackend::new()),
+        }
     }
 
-    /// Set the raw state of the engine from a string
-    pub fn set_raw_state(&self, state_data: &str) -> Result<()> {
-        debug!("Setting raw engine state ({} bytes)", state_data.len());
-        
-        // Send data diretly to Ingen
-        self.send_message(state_data)?;
-        
-        Ok(())
+    /// Add a monitor engine, shown as the monitor group of the main graph
+    pub fn with_monitor(self, monitor: Engine) -> Self {
+        info!("Using a separate monitor engine");
+        Self {
+            backend: Box::new(split::SplitBackend::new(self.backend, monitor.backend)),
+        }
     }
 
-    /// Get the current graph from Ingen
-    pub fn get_graph(&self) -> Result<Graph> {
-        debug!("Getting graph from Ingen");
-        
-        // Build RDF message to get the graph
-        let message = IngenProtocol::build_get_state()?;
-        
-        // Send to Ingen
-        self.send_message(&message)?;
-        
-        // Receive and parse response
-        let response = self.receive_message()?;
-        let graph = IngenProtocol::parse_graph(&response)?;
-        
-        trace!("Parsed graph: {} blocks, {} connections, {} system ports", 
-               graph.blocks.len(), graph.connections.len(), graph.ports.len());
-        trace!("Blocks: {:?}", graph.blocks);
-        trace!("Connections: {:?}", graph.connections);
-        trace!("System ports: {:?}", graph.ports);
-        
-        Ok(graph)
+    /// Apply the edits made by a function as one bundle
+    /// The edits made before a failure are applied as well, like they would be one by one
+    pub fn bundle<T>(&self, edits: impl FnOnce(&Engine) -> Result<T>) -> Result<T> {
+        self.begin_bundle()?;
+        let result = edits(self);
+        let end = self.end_bundle();
+        let value = result?;
+        end?;
+        Ok(value)
     }
+}
 
-    pub fn close(&self) {
-        debug!("Shutting down Engine...");
-        
-        // Clean up Ingen process if running
-        if let Some(mut process) = self.ingen_process.lock().unwrap().take() {
-            // Get the process ID
-            let pid = process.id();
-            debug!("Sending SIGTERM to Ingen process (PID: {})", pid);
-            
-            // Send SIGTERM signal using nix crate
-            #[cfg(unix)]
-            {
-                use nix::sys::signal::{self, Signal};
-                use nix::unistd::Pid;
-                
-                let _ = signal::kill(Pid::from_raw(pid as i32), Signal::SIGTERM);
-            }
-            
-            // Wait for the process to exit gracefully
-            match process.wait() {
-                Ok(status) => debug!("Ingen process exited with {}", status),
-                Err(e) => eprintln!("Error waiting for Ingen process to exit: {}", e),
-            }
-        }
+impl Deref for Engine {
+    type Target = dyn EngineBackend;
+
+    fn deref(&self) -> &Self::Target {
+        self.backend.as_ref()
     }
-}
\ No newline at end of file
+}
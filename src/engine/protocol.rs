@@ -7,6 +7,7 @@ use sophia::api::source::TripleSource;
 use sophia::api::term::{BnodeId, IriRef, SimpleTerm};
 use sophia::inmem::graph::FastGraph;
 use sophia_turtle::{parser::turtle, serializer::turtle::TurtleSerializer};
+use std::cell::Cell;
 use std::sync::atomic::{AtomicU32, Ordering};
 
 use super::{PortType, PortDirection};
@@ -16,12 +17,73 @@ const INGEN_NS: &str = "http://drobilla.net/ns/ingen#";
 const LV2_NS: &str = "http://lv2plug.in/ns/lv2core#";
 const PATCH_NS: &str = "http://lv2plug.in/ns/ext/patch#";
 const ATOM_NS: &str = "http://lv2plug.in/ns/ext/atom#";
+const XSD_NS: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Property holding the current value of a control port
+pub const INGEN_VALUE: &str = "http://drobilla.net/ns/ingen#value";
+
+/// Property holding the name of a block
+pub const LV2_NAME: &str = "http://lv2plug.in/ns/lv2core#name";
+
+/// Property holding the number of voices of a graph
+pub const INGEN_POLYPHONY: &str = "http://drobilla.net/ns/ingen#polyphony";
+
+/// Property telling whether a block has one instance per voice of its graph
+pub const INGEN_POLYPHONIC: &str = "http://drobilla.net/ns/ingen#polyphonic";
+
+/// Property asking Ingen to notify the value of a port, the peak level for audio ports
+pub const INGEN_BROADCAST: &str = "http://drobilla.net/ns/ingen#broadcast";
+
+/// Value of a property set with patch:Set
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue<'a> {
+    Float(f32),
+    Int(i32),
+    Bool(bool),
+    String(&'a str),
+}
+
+impl PropertyValue<'_> {
+    /// Convert to a typed RDF literal
+    fn to_term(&self) -> SimpleTerm<'static> {
+        let (lexical, datatype) = match self {
+            PropertyValue::Float(v) => (v.to_string(), "float"),
+            PropertyValue::Int(v) => (v.to_string(), "int"),
+            PropertyValue::Bool(v) => (v.to_string(), "boolean"),
+            PropertyValue::String(v) => (v.to_string(), "string"),
+        };
+        SimpleTerm::LiteralDatatype(
+            MownStr::from(lexical),
+            IriRef::new_unchecked(MownStr::from(format!("{}{}", XSD_NS, datatype))),
+        )
+    }
+}
+
+/// Request sent to Ingen, as read back from a message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    CreateBlock { path: String, prototype: String },
+    CreateGraph { path: String },
+    CreatePort { path: String, port_type: PortType, direction: PortDirection },
+    Connect { source: String, destination: String },
+    Disconnect { source: String, destination: String },
+    Delete { path: String },
+    Move { path: String, destination: String },
+    /// Property set to a literal value, None for structured values such as patch parameters
+    SetProperty { subject: String, property: String, value: Option<String> },
+    Get { subject: String },
+}
 
 // Global sequence number counter
 static SEQUENCE_NUMBER: AtomicU32 = AtomicU32::new(0);
 // Global blank node ID counter
 static BLANK_NODE_COUNTER: AtomicU32 = AtomicU32::new(1);
 
+thread_local! {
+    // Whether the messages built on this thread go into a bundle, whose start and end carry the sequence number
+    static BUNDLED: Cell<bool> = const { Cell::new(false) };
+}
+
 /// Ingen protocol message builder and parser using RDF/Turtle
 pub struct IngenProtocol;
 
@@ -128,6 +190,40 @@ impl IngenProtocol {
         Self::serialize_graph(&graph, &put_node)
     }
 
+    /// Build an RDF graph to create an empty subgraph
+    pub fn build_create_graph(graph_id: &str) -> Result<String> {
+        debug!("Building create_graph message for '{}'", graph_id);
+        
+        let mut graph = FastGraph::new();
+        let ingen = Namespace::new(INGEN_NS)?;
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let graph_path = format!("ingen:/main/{}", graph_id);
+        
+        let body_node = Self::create_blank_node();
+        let put_node = Self::create_blank_node();
+        
+        graph.insert(&body_node, &rdf::type_, &ingen.get("Graph")?)?;
+        
+        // Build patch:Put structure
+        graph.insert(&put_node, &rdf::type_, &patch.get("Put")?)?;
+        graph.insert(&put_node, &patch.get("subject")?, &IriRef::new_unchecked(graph_path.as_str()))?;
+        graph.insert(&put_node, &patch.get("body")?, &body_node)?;
+        
+        Self::serialize_graph(&graph, &put_node)
+    }
+
+    /// Get the graph an arc between two ports belongs to: the deepest graph holding both ends
+    /// Ports of a subgraph itself count as inside it, so its internal arcs go to the subgraph
+    pub fn arc_parent(source: &str, destination: &str) -> String {
+        let common: Vec<&str> = source.split('/')
+            .zip(destination.split('/'))
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect();
+        format!("{}/", common.join("/"))
+    }
+
     /// Build an RDF graph to connect two ports
     pub fn build_connect(source: &str, destination: &str) -> Result<String> {
         debug!("Building connect message: '{}' -> '{}'", source, destination);
@@ -147,7 +243,8 @@ impl IngenProtocol {
                 
         // Build patch:Put structure
         graph.insert(&put_node, &rdf::type_, &patch.get("Put")?)?;
-        graph.insert(&put_node, &patch.get("subject")?, &IriRef::new_unchecked("ingen:/main/"))?;
+        let parent = Self::arc_parent(source, destination);
+        graph.insert(&put_node, &patch.get("subject")?, &IriRef::new_unchecked(parent.as_str()))?;
         graph.insert(&put_node, &patch.get("body")?, &arc_node)?;
         Self::serialize_graph(&graph, &put_node)
     }
@@ -171,7 +268,8 @@ impl IngenProtocol {
                 
         // Build patch:Delete structure
         graph.insert(&delete_node, &rdf::type_, &patch.get("Delete")?)?;
-        graph.insert(&delete_node, &patch.get("subject")?, &IriRef::new_unchecked("ingen:/main/"))?;
+        let parent = Self::arc_parent(source, destination);
+        graph.insert(&delete_node, &patch.get("subject")?, &IriRef::new_unchecked(parent.as_str()))?;
         graph.insert(&delete_node, &patch.get("body")?, &arc_node)?;
         
         Self::serialize_graph(&graph, &delete_node)
@@ -181,24 +279,124 @@ impl IngenProtocol {
     pub fn build_delete(path: &str) -> Result<String> {
         debug!("Building delete message for '{}'", path);
         
-        let graph = FastGraph::new();
-        let root = Self::create_blank_node();
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let delete_node = Self::create_blank_node();
+        
+        // Build patch:Delete structure on the object itself
+        graph.insert(&delete_node, &rdf::type_, &patch.get("Delete")?)?;
+        graph.insert(&delete_node, &patch.get("subject")?, &IriRef::new_unchecked(path))?;
+        
+        Self::serialize_graph(&graph, &delete_node)
+    }
 
-        // TODO: Implement
+    /// Build an RDF graph to move a block or graph to a new path in the same parent graph
+    /// Ingen moves its ports and connections along
+    pub fn build_move(path: &str, destination: &str) -> Result<String> {
+        debug!("Building move message: '{}' -> '{}'", path, destination);
+        
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let move_node = Self::create_blank_node();
+        
+        graph.insert(&move_node, &rdf::type_, &patch.get("Move")?)?;
+        graph.insert(&move_node, &patch.get("subject")?, &IriRef::new_unchecked(path))?;
+        graph.insert(&move_node, &patch.get("destination")?, &IriRef::new_unchecked(destination))?;
         
-        Self::serialize_graph(&graph, &root)
+        Self::serialize_graph(&graph, &move_node)
     }
 
     /// Build an RDF graph to set a property/parameter
-    pub fn build_set_property(subject: &str, property: &str, value: &str) -> Result<String> {
+    /// 
+    /// # Arguments
+    /// * `subject` - Path of the block or port (e.g., "ingen:/main/block/gain")
+    /// * `property` - Full URI of the property to set
+    /// * `value` - Value to assign
+    pub fn build_set_property(subject: &str, property: &str, value: &PropertyValue) -> Result<String> {
         debug!("Building set_property message for '{}'", subject);
         
-        let graph = FastGraph::new();
-        let root = Self::create_blank_node();
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let set_node = Self::create_blank_node();
+        
+        // Build patch:Set structure
+        graph.insert(&set_node, &rdf::type_, &patch.get("Set")?)?;
+        graph.insert(&set_node, &patch.get("subject")?, &IriRef::new_unchecked(subject))?;
+        graph.insert(&set_node, &patch.get("property")?, &IriRef::new_unchecked(property))?;
+        graph.insert(&set_node, &patch.get("value")?, &value.to_term())?;
+        
+        Self::serialize_graph(&graph, &set_node)
+    }
 
-        // TODO: Implement
+    /// Build an RDF graph to set a patch parameter of a plugin, writing a patch:Set object to its atom input port
+    pub fn build_set_parameter(port_path: &str, parameter: &str, value: &PropertyValue) -> Result<String> {
+        debug!("Building set_parameter message for '{}' on '{}'", parameter, port_path);
         
-        Self::serialize_graph(&graph, &root)
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        let ingen = Namespace::new(INGEN_NS)?;
+        
+        let set_node = Self::create_blank_node();
+        let parameter_node = Self::create_blank_node();
+        
+        // The value of the port is the message the plugin receives
+        graph.insert(&parameter_node, &rdf::type_, &patch.get("Set")?)?;
+        graph.insert(&parameter_node, &patch.get("property")?, &IriRef::new_unchecked(parameter))?;
+        graph.insert(&parameter_node, &patch.get("value")?, &value.to_term())?;
+        
+        graph.insert(&set_node, &rdf::type_, &patch.get("Set")?)?;
+        graph.insert(&set_node, &patch.get("subject")?, &IriRef::new_unchecked(port_path))?;
+        graph.insert(&set_node, &patch.get("property")?, &ingen.get("value")?)?;
+        graph.insert(&set_node, &patch.get("value")?, &parameter_node)?;
+        
+        Self::serialize_graph(&graph, &set_node)
+    }
+
+    /// Build an RDF graph to load a graph bundle saved by Ingen into a graph of the engine
+    pub fn build_copy(source_uri: &str, destination: &str) -> Result<String> {
+        debug!("Building copy message: '{}' -> '{}'", source_uri, destination);
+        
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let copy_node = Self::create_blank_node();
+        
+        // Ingen loads the bundle when the copy goes from a file to the engine
+        graph.insert(&copy_node, &rdf::type_, &patch.get("Copy")?)?;
+        graph.insert(&copy_node, &patch.get("subject")?, &IriRef::new_unchecked(source_uri))?;
+        graph.insert(&copy_node, &patch.get("destination")?, &IriRef::new_unchecked(destination))?;
+        
+        Self::serialize_graph(&graph, &copy_node)
+    }
+
+    /// Build the next messages of the calling thread without sequence number to put them in a bundle, or with again
+    pub fn set_bundled(bundled: bool) {
+        BUNDLED.set(bundled);
+    }
+
+    /// Wrap messages into a bundle that Ingen applies at once, between a start and an end sharing one sequence number
+    /// Other clients and the notifications never see the graph in the middle of the bundle
+    pub fn build_bundle(messages: &[String]) -> Result<String> {
+        debug!("Building bundle of {} messages", messages.len());
+        
+        let ingen = Namespace::new(INGEN_NS)?;
+        let seq_num = SEQUENCE_NUMBER.fetch_add(1, Ordering::SeqCst);
+        let marker = |class: &str| -> Result<String> {
+            let mut graph = FastGraph::new();
+            let node = Self::create_blank_node();
+            graph.insert(&node, &rdf::type_, &ingen.get(class)?)?;
+            Self::serialize_sequenced(&graph, &node, Some(seq_num))
+        };
+        
+        let mut bundle = marker("BundleStart")?;
+        for message in messages {
+            bundle.push_str(message);
+        }
+        bundle.push_str(&marker("BundleEnd")?);
+        Ok(bundle)
     }
 
     /// Build an RDF graph to query for available plugins
@@ -233,6 +431,19 @@ impl IngenProtocol {
         Self::serialize_graph(&graph, &get_node)
     }
 
+    /// Build an RDF graph to get the engine properties, a small answer telling that the engine is alive
+    pub fn build_get_engine() -> Result<String> {
+        let mut graph = FastGraph::new();
+        let patch = Namespace::new(PATCH_NS)?;
+        
+        let get_node = Self::create_blank_node();
+        
+        graph.insert(&get_node, &rdf::type_, &patch.get("Get")?)?;
+        graph.insert(&get_node, &patch.get("subject")?, &IriRef::new_unchecked("ingen:/engine"))?;
+        
+        Self::serialize_graph(&graph, &get_node)
+    }
+
     /// Parse an RDF response from Ingen
     pub fn parse_response(turtle_data: &str) -> Result<FastGraph> {
         debug!("Parsing Ingen response");
@@ -326,22 +537,28 @@ impl IngenProtocol {
 
     /// Serialize a graph to Turtle format
     fn serialize_graph(graph: &FastGraph, root_node: &SimpleTerm) -> Result<String> {
+        // Increment and get the sequence number, unless the message goes into a bundle
+        let seq_num = (!BUNDLED.get()).then(|| SEQUENCE_NUMBER.fetch_add(1, Ordering::SeqCst));
+        Self::serialize_sequenced(graph, root_node, seq_num)
+    }
+
+    /// Serialize a graph to Turtle format, with a given sequence number if any
+    fn serialize_sequenced(graph: &FastGraph, root_node: &SimpleTerm, seq_num: Option<u32>) -> Result<String> {
         // Clone the graph to add sequence number
         let mut graph_with_seq = graph.clone();
         
-        // Increment and get the sequence number
-        let seq_num = SEQUENCE_NUMBER.fetch_add(1, Ordering::SeqCst);
-        
-        // Add sequence number to the provided root node
-        let patch = Namespace::new(PATCH_NS)?;
-        
-        // Create the sequence number literal with xsd:int datatype
-        let seq_literal = SimpleTerm::LiteralDatatype(
-            MownStr::from(seq_num.to_string()),
-            IriRef::new_unchecked("http://www.w3.org/2001/XMLSchema#int".into())
-        );
-        
-        graph_with_seq.insert(root_node, &patch.get("sequenceNumber")?, &seq_literal)?;
+        if let Some(seq_num) = seq_num {
+            // Add sequence number to the provided root node
+            let patch = Namespace::new(PATCH_NS)?;
+            
+            // Create the sequence number literal with xsd:int datatype
+            let seq_literal = SimpleTerm::LiteralDatatype(
+                MownStr::from(seq_num.to_string()),
+                IriRef::new_unchecked("http://www.w3.org/2001/XMLSchema#int".into())
+            );
+            
+            graph_with_seq.insert(root_node, &patch.get("sequenceNumber")?, &seq_literal)?;
+        }
         
         let mut serializer = TurtleSerializer::new_stringifier();
         
@@ -370,6 +587,7 @@ impl IngenProtocol {
         
         // Find all blocks from patch:Put messages
         let ingen_block = ingen.get("Block")?;
+        let ingen_graph = ingen.get("Graph")?;
         let lv2_name = lv2.get("name")?;
         let lv2_symbol = lv2.get("symbol")?;
         let lv2_prototype = lv2.get("prototype")?;
@@ -382,8 +600,8 @@ impl IngenProtocol {
         let patch_subject = patch.get("subject")?;
         let patch_body = patch.get("body")?;
         
-        // Collect all block subjects from patch:Put messages
-        let mut block_subjects = std::collections::HashSet::new();
+        // Collect all block subjects (with their prototype) from patch:Put messages
+        let mut block_subjects = std::collections::HashMap::new();
         for triple in graph.triples() {
             let triple = triple.map_err(|e| anyhow!("Error iterating triples: {}", e))?;
             
@@ -409,9 +627,23 @@ impl IngenProtocol {
                 
                 // Check if the body contains ingen:Block type
                 if let (Some(uri), Some(body)) = (subject_uri, body_node) {
+                    // Subgraphs are shown as blocks, the main graph itself is not
+                    let is_subgraph = uri.strip_prefix("ingen:/main/").is_some_and(|path| !path.is_empty())
+                        && graph.triples_matching([body], [&rdf::type_], [&ingen_graph]).next().is_some();
+                    if is_subgraph {
+                        block_subjects.insert(uri.clone(), super::GRAPH_PROTOTYPE.to_string());
+                        continue;
+                    }
                     for t in graph.triples_matching([body], [&rdf::type_], [&ingen_block]) {
                         if t.is_ok() {
-                            block_subjects.insert(uri.clone());
+                            let mut prototype = String::new();
+                            for p in graph.triples_matching([body], [&lv2_prototype], sophia::api::term::matcher::Any) {
+                                let p = p.map_err(|e| anyhow!("Error finding prototype: {}", e))?;
+                                if let Some(iri) = p.o().iri() {
+                                    prototype = iri.to_string();
+                                }
+                            }
+                            block_subjects.insert(uri.clone(), prototype);
                             break;
                         }
                     }
@@ -420,7 +652,7 @@ impl IngenProtocol {
         }
         
         // Process each block
-        for block_id in block_subjects {
+        for (block_id, prototype) in block_subjects {
             let block_iri = IriRef::new_unchecked(block_id.as_str());
             
             // Get block name from the block subject itself or from patch:Put body
@@ -464,7 +696,8 @@ impl IngenProtocol {
                     
                     // Check if this port belongs to this block
                     if let (Some(port_uri), Some(body)) = (subject_uri, body_node) {
-                        if port_uri.starts_with(&block_id) && port_uri != block_id {
+                        // Only the direct children, not the ports of "delay_2" for "delay" or of nested blocks
+                        if port_uri.rsplit_once('/').is_some_and(|(parent, _)| parent == block_id) {
                             let mut is_audio = false;
                             let mut is_atom = false;
                             let mut is_input = false;
@@ -512,6 +745,7 @@ impl IngenProtocol {
             blocks.push(Block {
                 id: block_id,
                 name,
+                prototype,
                 ports,
             });
         }
@@ -646,6 +880,220 @@ impl IngenProtocol {
         
         Ok(Graph { blocks, connections, ports: system_ports })
     }
+
+    /// Get the value of a property of a subject from the patch:Put messages of a state response, as its lexical form
+    pub fn parse_property(response: &str, subject: &str, property: &str) -> Result<Option<String>> {
+        let graph = Self::parse_response(response)?;
+        
+        let patch = Namespace::new(PATCH_NS)?;
+        let patch_put = patch.get("Put")?;
+        let patch_subject = patch.get("subject")?;
+        let patch_body = patch.get("body")?;
+        let property = IriRef::new_unchecked(property);
+        let subject = IriRef::new_unchecked(subject);
+        
+        for triple in graph.triples_matching(sophia::api::term::matcher::Any, [&patch_subject], [&subject]) {
+            let triple = triple.map_err(|e| anyhow!("Error iterating triples: {}", e))?;
+            let put_node = triple.s();
+            if graph.triples_matching([put_node], [&rdf::type_], [&patch_put]).next().is_none() {
+                continue;
+            }
+            for t in graph.triples_matching([put_node], [&patch_body], sophia::api::term::matcher::Any) {
+                let t = t.map_err(|e| anyhow!("Error finding body: {}", e))?;
+                for p in graph.triples_matching([t.o()], [&property], sophia::api::term::matcher::Any) {
+                    let p = p.map_err(|e| anyhow!("Error finding property: {}", e))?;
+                    if let Some(value) = p.o().lexical_form() {
+                        return Ok(Some(value.to_string()));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parse the port values notified with patch:Set messages, as (port path, value) pairs
+    pub fn parse_port_values(messages: &str) -> Result<Vec<(String, f32)>> {
+        let graph = Self::parse_response(messages)?;
+        
+        let ingen = Namespace::new(INGEN_NS)?;
+        let patch = Namespace::new(PATCH_NS)?;
+        let ingen_value = ingen.get("value")?;
+        let patch_set = patch.get("Set")?;
+        let patch_subject = patch.get("subject")?;
+        let patch_property = patch.get("property")?;
+        let patch_value = patch.get("value")?;
+        
+        let mut values = Vec::new();
+        for triple in graph.triples_matching(sophia::api::term::matcher::Any, [&rdf::type_], [&patch_set]) {
+            let triple = triple.map_err(|e| anyhow!("Error iterating triples: {}", e))?;
+            let set_node = triple.s();
+            if graph.triples_matching([set_node], [&patch_property], [&ingen_value]).next().is_none() {
+                continue;
+            }
+            let subject = graph.triples_matching([set_node], [&patch_subject], sophia::api::term::matcher::Any)
+                .filter_map(|t| t.ok())
+                .find_map(|t| t.o().iri().map(|iri| iri.to_string()));
+            let value = graph.triples_matching([set_node], [&patch_value], sophia::api::term::matcher::Any)
+                .filter_map(|t| t.ok())
+                .find_map(|t| t.o().lexical_form().and_then(|v| v.parse::<f32>().ok()));
+            if let (Some(subject), Some(value)) = (subject, value) {
+                values.push((subject, value));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Parse the requests of a message sent to Ingen, such as the messages of a protocol capture
+    /// Statements that are not patch requests, like the prefixes sent first, give no request
+    pub fn parse_requests(message: &str) -> Result<Vec<Request>> {
+        let graph = Self::parse_response(message)?;
+        
+        let ingen = Namespace::new(INGEN_NS)?;
+        let lv2 = Namespace::new(LV2_NS)?;
+        let patch = Namespace::new(PATCH_NS)?;
+        let ingen_arc = ingen.get("Arc")?;
+        let ingen_block = ingen.get("Block")?;
+        let ingen_graph = ingen.get("Graph")?;
+        let lv2_audio_port = lv2.get("AudioPort")?;
+        let lv2_output_port = lv2.get("OutputPort")?;
+        let lv2_input_port = lv2.get("InputPort")?;
+        let patch_put = patch.get("Put")?;
+        let patch_delete = patch.get("Delete")?;
+        let patch_set = patch.get("Set")?;
+        let patch_get = patch.get("Get")?;
+        let patch_move = patch.get("Move")?;
+        let patch_subject = patch.get("subject")?;
+        let patch_body = patch.get("body")?;
+        let patch_destination = patch.get("destination")?;
+        let patch_property = patch.get("property")?;
+        let patch_value = patch.get("value")?;
+        let ingen_tail = ingen.get("tail")?;
+        let ingen_head = ingen.get("head")?;
+        let lv2_prototype = lv2.get("prototype")?;
+        
+        let iri = |node, predicate| graph.triples_matching([node], [predicate], sophia::api::term::matcher::Any)
+            .filter_map(|t| t.ok())
+            .find_map(|t| t.o().iri().map(|iri| iri.to_string()));
+        let body = |node| graph.triples_matching([node], [&patch_body], sophia::api::term::matcher::Any)
+            .filter_map(|t| t.ok())
+            .map(|t| t.o())
+            .next();
+        let is_a = |node, class| graph.triples_matching([node], [&rdf::type_], [class]).next().is_some();
+        
+        let mut requests = Vec::new();
+        for triple in graph.triples_matching(sophia::api::term::matcher::Any, [&rdf::type_], sophia::api::term::matcher::Any) {
+            let triple = triple.map_err(|e| anyhow!("Error iterating triples: {}", e))?;
+            let node = triple.s();
+            
+            // Nested objects such as the patch:Set of a plugin parameter have no subject
+            let Some(subject) = iri(node, &patch_subject) else {
+                continue;
+            };
+            let arc = body(node).filter(|body| is_a(*body, &ingen_arc)).map(|arc| (
+                iri(arc, &ingen_tail).unwrap_or_default(),
+                iri(arc, &ingen_head).unwrap_or_default(),
+            ));
+            
+            let request = if triple.o() == &patch_put {
+                let Some(body) = body(node) else {
+                    continue;
+                };
+                if let Some((source, destination)) = arc {
+                    Request::Connect { source, destination }
+                } else if is_a(body, &ingen_block) {
+                    Request::CreateBlock { path: subject, prototype: iri(body, &lv2_prototype).unwrap_or_default() }
+                } else if is_a(body, &ingen_graph) {
+                    Request::CreateGraph { path: subject }
+                } else if is_a(body, &lv2_input_port) || is_a(body, &lv2_output_port) {
+                    Request::CreatePort {
+                        path: subject,
+                        port_type: if is_a(body, &lv2_audio_port) { PortType::Audio } else { PortType::Midi },
+                        direction: if is_a(body, &lv2_output_port) { PortDirection::Output } else { PortDirection::Input },
+                    }
+                } else {
+                    continue;
+                }
+            } else if triple.o() == &patch_delete {
+                match arc {
+                    Some((source, destination)) => Request::Disconnect { source, destination },
+                    None => Request::Delete { path: subject },
+                }
+            } else if triple.o() == &patch_set {
+                let Some(property) = iri(node, &patch_property) else {
+                    continue;
+                };
+                let value = graph.triples_matching([node], [&patch_value], sophia::api::term::matcher::Any)
+                    .filter_map(|t| t.ok())
+                    .find_map(|t| t.o().lexical_form().map(|v| v.to_string()));
+                Request::SetProperty { subject, property, value }
+            } else if triple.o() == &patch_get {
+                Request::Get { subject }
+            } else if triple.o() == &patch_move {
+                let Some(destination) = iri(node, &patch_destination) else {
+                    continue;
+                };
+                Request::Move { path: subject, destination }
+            } else {
+                continue;
+            };
+            requests.push(request);
+        }
+        Ok(requests)
+    }
+
+    /// Parse the current control port values of a block from a state response
+    /// Returns the values keyed by port symbol
+    pub fn parse_control_values(response: &str, block_path: &str) -> Result<std::collections::HashMap<String, f32>> {
+        debug!("Parsing control values of {} from Ingen response", block_path);
+        
+        let graph = Self::parse_response(response)?;
+        
+        let ingen = Namespace::new(INGEN_NS)?;
+        let patch = Namespace::new(PATCH_NS)?;
+        let ingen_value = ingen.get("value")?;
+        let patch_put = patch.get("Put")?;
+        let patch_subject = patch.get("subject")?;
+        let patch_body = patch.get("body")?;
+        
+        let port_prefix = format!("{}/", block_path);
+        let mut values = std::collections::HashMap::new();
+        
+        for triple in graph.triples_matching(sophia::api::term::matcher::Any, [&rdf::type_], [&patch_put]) {
+            let triple = triple.map_err(|e| anyhow!("Error iterating triples: {}", e))?;
+            let put_node = triple.s();
+            
+            let mut subject_uri: Option<String> = None;
+            let mut body_node = None;
+            
+            for t in graph.triples_matching([put_node], [&patch_subject], sophia::api::term::matcher::Any) {
+                let t = t.map_err(|e| anyhow!("Error finding subject: {}", e))?;
+                if let Some(iri) = t.o().iri() {
+                    subject_uri = Some(iri.to_string());
+                }
+            }
+            
+            for t in graph.triples_matching([put_node], [&patch_body], sophia::api::term::matcher::Any) {
+                let t = t.map_err(|e| anyhow!("Error finding body: {}", e))?;
+                body_node = Some(t.o());
+            }
+            
+            // Keep the ports of the block that carry a value
+            let (Some(uri), Some(body)) = (subject_uri, body_node) else {
+                continue;
+            };
+            let Some(symbol) = uri.strip_prefix(&port_prefix).filter(|s| !s.contains('/')) else {
+                continue;
+            };
+            for t in graph.triples_matching([body], [&ingen_value], sophia::api::term::matcher::Any) {
+                let t = t.map_err(|e| anyhow!("Error finding value: {}", e))?;
+                if let Some(value) = t.o().lexical_form().and_then(|v| v.parse::<f32>().ok()) {
+                    values.insert(symbol.to_string(), value);
+                }
+            }
+        }
+        
+        Ok(values)
+    }
 }
 
 #[cfg(test)]
@@ -659,11 +1107,162 @@ mod tests {
         println!("{}", message);
     }
     
+    #[test]
+    fn test_build_set_property() {
+        let message = IngenProtocol::build_set_property(
+            "ingen:/main/delay/feedback", INGEN_VALUE, &PropertyValue::Float(0.5)).unwrap();
+        assert!(message.contains("Set"));
+        assert!(message.contains("ingen:/main/delay/feedback"));
+        assert!(message.contains("0.5"));
+    }
+    
+    #[test]
+    fn test_build_set_parameter() {
+        let message = IngenProtocol::build_set_parameter(
+            "ingen:/main/synth/control", "http://example.org/synth#cutoff", &PropertyValue::Float(0.25)).unwrap();
+        assert_eq!(message.matches("Set").count(), 2);
+        assert!(message.contains("ingen:/main/synth/control"));
+        assert!(message.contains("http://example.org/synth#cutoff"));
+        assert!(message.contains("0.25"));
+    }
+    
+    #[test]
+    fn test_build_delete() {
+        let message = IngenProtocol::build_delete("ingen:/main/audio_in_1").unwrap();
+        assert!(message.contains("Delete"));
+        assert!(message.contains("ingen:/main/audio_in_1"));
+    }
+    
+    #[test]
+    fn test_build_bundle() {
+        IngenProtocol::set_bundled(true);
+        let messages = vec![
+            IngenProtocol::build_create_block("delay", "http://example.org/delay").unwrap(),
+            IngenProtocol::build_connect("ingen:/main/audio_in_1", "ingen:/main/delay/in").unwrap(),
+        ];
+        IngenProtocol::set_bundled(false);
+        assert!(messages.iter().all(|m| !m.contains("sequenceNumber")));
+        let bundle = IngenProtocol::build_bundle(&messages).unwrap();
+        assert!(bundle.find("BundleStart").unwrap() < bundle.find("BundleEnd").unwrap());
+        assert_eq!(IngenProtocol::parse_requests(&bundle).unwrap().len(), 2);
+
+        // The start and the end share the sequence number of the bundle
+        let number = |marker: &str| {
+            let after = &bundle[bundle.find(marker).unwrap()..];
+            let start = after.find("sequenceNumber").unwrap();
+            after[start..].split('"').nth(1).unwrap().to_string()
+        };
+        assert_eq!(number("BundleStart"), number("BundleEnd"));
+    }
+    
+    #[test]
+    fn test_parse_control_values() {
+        let response = "[] a patch:Put ;
+    patch:subject <ingen:/main/delay/feedback> ;
+    patch:body [ a lv2:InputPort , lv2:ControlPort ; lv2:symbol \"feedback\" ; ingen:value 0.5 ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/delay/in> ;
+    patch:body [ a lv2:InputPort , lv2:AudioPort ; lv2:symbol \"in\" ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/reverb/mix> ;
+    patch:body [ a lv2:InputPort , lv2:ControlPort ; lv2:symbol \"mix\" ; ingen:value 0.3 ] .
+";
+        let values = IngenProtocol::parse_control_values(response, "ingen:/main/delay").unwrap();
+        assert_eq!(values.len(), 1);
+        assert_eq!(values.get("feedback"), Some(&0.5));
+    }
+    
+    #[test]
+    fn test_parse_property() {
+        let response = "[] a patch:Put ;
+    patch:subject <ingen:/main/> ;
+    patch:body [ a ingen:Graph ; ingen:polyphony 8 ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/synth> ;
+    patch:body [ a ingen:Block ; ingen:polyphonic true ] .
+";
+        assert_eq!(IngenProtocol::parse_property(response, "ingen:/main/", INGEN_POLYPHONY).unwrap().as_deref(), Some("8"));
+        assert_eq!(IngenProtocol::parse_property(response, "ingen:/main/synth", INGEN_POLYPHONIC).unwrap().as_deref(), Some("true"));
+        assert_eq!(IngenProtocol::parse_property(response, "ingen:/main/synth", INGEN_POLYPHONY).unwrap(), None);
+    }
+    
+    #[test]
+    fn test_parse_port_values() {
+        let messages = "[] a patch:Set ;
+    patch:subject <ingen:/main/delay/out> ;
+    patch:property ingen:value ;
+    patch:value \"1.25\"^^xsd:float .
+[] a patch:Set ;
+    patch:subject <ingen:/main/delay> ;
+    patch:property lv2:name ;
+    patch:value \"Delay\" .
+";
+        let values = IngenProtocol::parse_port_values(messages).unwrap();
+        assert_eq!(values, vec![("ingen:/main/delay/out".to_string(), 1.25)]);
+    }
+    
+    #[test]
+    fn test_arc_parent() {
+        assert_eq!(IngenProtocol::arc_parent("ingen:/main/audio_in_1", "ingen:/main/delay/in"), "ingen:/main/");
+        assert_eq!(IngenProtocol::arc_parent("ingen:/main/group_1/in_1", "ingen:/main/group_1/delay/in"), "ingen:/main/group_1/");
+        assert_eq!(IngenProtocol::arc_parent("ingen:/main/delay/out", "ingen:/main/group_1/in_1"), "ingen:/main/");
+    }
+    
+    #[test]
+    fn test_parse_graph_with_subgraph() {
+        let response = "[] a patch:Put ;
+    patch:subject <ingen:/main/> ;
+    patch:body [ a ingen:Graph ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/group_1> ;
+    patch:body [ a ingen:Graph ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/group_1/in_1> ;
+    patch:body [ a lv2:InputPort , lv2:AudioPort ; lv2:symbol \"in_1\" ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/group_1/delay> ;
+    patch:body [ a ingen:Block ; lv2:prototype <urn:delay> ] .
+[] a patch:Put ;
+    patch:subject <ingen:/main/group_1/delay/in> ;
+    patch:body [ a lv2:InputPort , lv2:AudioPort ; lv2:symbol \"in\" ] .
+";
+        let graph = IngenProtocol::parse_graph(response).unwrap();
+        assert_eq!(graph.blocks.len(), 2);
+        let group = graph.blocks.iter().find(|b| b.id == "ingen:/main/group_1").unwrap();
+        assert_eq!(group.prototype, crate::engine::GRAPH_PROTOTYPE);
+        // The ports of the nested block are not the group's
+        assert_eq!(group.ports.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), vec!["in_1"]);
+        assert!(graph.ports.is_empty());
+    }
+    
     #[test]
     fn test_build_connect() {
         let message = IngenProtocol::build_connect("ingen:/main/audio_in_1", "ingen:/main/audio_out_1").unwrap();
         println!("\nConnect ports message:");
         println!("{}", message);
     }
+
+    #[test]
+    fn test_parse_requests() {
+        let messages = [
+            IngenProtocol::get_init_message().to_string(),
+            IngenProtocol::build_create_block("delay", "http://example.org/delay").unwrap(),
+            IngenProtocol::build_create_port("midi_in", &PortType::Midi, &PortDirection::Input).unwrap(),
+            IngenProtocol::build_disconnect("ingen:/main/delay/out", "ingen:/main/audio_out").unwrap(),
+            IngenProtocol::build_set_parameter("ingen:/main/synth/control", "http://example.org/gain", &PropertyValue::Float(0.5)).unwrap(),
+            IngenProtocol::build_delete("ingen:/main/delay").unwrap(),
+            IngenProtocol::build_move("ingen:/main/delay_1", "ingen:/main/tape_echo").unwrap(),
+        ];
+        let requests: Vec<Vec<Request>> = messages.iter().map(|m| IngenProtocol::parse_requests(m).unwrap()).collect();
+        assert_eq!(requests, vec![
+            vec![],
+            vec![Request::CreateBlock { path: "ingen:/main/delay".to_string(), prototype: "http://example.org/delay".to_string() }],
+            vec![Request::CreatePort { path: "ingen:/main/midi_in".to_string(), port_type: PortType::Midi, direction: PortDirection::Input }],
+            vec![Request::Disconnect { source: "ingen:/main/delay/out".to_string(), destination: "ingen:/main/audio_out".to_string() }],
+            vec![Request::SetProperty { subject: "ingen:/main/synth/control".to_string(), property: INGEN_VALUE.to_string(), value: None }],
+            vec![Request::Delete { path: "ingen:/main/delay".to_string() }],
+            vec![Request::Move { path: "ingen:/main/delay_1".to_string(), destination: "ingen:/main/tape_echo".to_string() }],
+        ]);
+    }
 }
 
@@ -0,0 +1,344 @@
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{Block, Connection, EngineBackend, GRAPH_PROTOTYPE, Graph, Plugin, PortType, StateContent};
+
+/// Prefix of the paths in the main graph, as used by Ingen
+const MAIN_PREFIX: &str = "ingen:/main/";
+
+/// Graph standing for the monitor engine inside the main graph
+pub const MONITOR_GRAPH: &str = "ingen:/main/monitor";
+
+/// Block id of the monitor graph, relative to the main graph
+const MONITOR_ID: &str = "monitor";
+
+/// Line separating the raw state of the main engine from the one of the monitor engine
+const MONITOR_STATE_MARKER: &str = "# traxdub:monitor-engine";
+
+/// Check whether a node lives in the monitor engine: the monitor graph or anything below it
+pub fn is_monitor_node(path: &str) -> bool {
+    path.strip_prefix(MONITOR_GRAPH).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Engine backend driving two engines, the main one for the front of house and a separate one for the monitors
+/// The monitor engine shows as a group of the main graph: its paths are below ingen:/main/monitor/,
+/// and its system ports are the ports of the group. Connections cannot cross from one engine to the other.
+pub struct SplitBackend {
+    main: Box<dyn EngineBackend>,
+    monitor: Box<dyn EngineBackend>,
+}
+
+impl SplitBackend {
+    /// Combine a main and a monitor engine
+    pub fn new(main: Box<dyn EngineBackend>, monitor: Box<dyn EngineBackend>) -> Self {
+        Self { main, monitor }
+    }
+
+    /// Get the engine of a path and the path in that engine
+    fn route_path(&self, path: &str) -> Result<(&dyn EngineBackend, String)> {
+        if path == MONITOR_GRAPH {
+            return Err(anyhow!("The monitor graph stands for the monitor engine and cannot be changed"));
+        }
+        match path.strip_prefix(MONITOR_GRAPH).and_then(|rest| rest.strip_prefix('/')) {
+            Some(inner) => Ok((self.monitor.as_ref(), format!("{}{}", MAIN_PREFIX, inner))),
+            None => Ok((self.main.as_ref(), path.to_string())),
+        }
+    }
+
+    /// Get the engine of an id relative to the main graph and the id in that engine
+    fn route_id(&self, id: &str) -> Result<(&dyn EngineBackend, String)> {
+        if id == MONITOR_ID {
+            return Err(anyhow!("The name '{}' is kept for the monitor engine", MONITOR_ID));
+        }
+        match id.strip_prefix(MONITOR_ID).and_then(|rest| rest.strip_prefix('/')) {
+            Some(inner) => Ok((self.monitor.as_ref(), inner.to_string())),
+            None => Ok((self.main.as_ref(), id.to_string())),
+        }
+    }
+
+    /// Check whether an id relative to the main graph is in the monitor engine
+    fn is_monitor_id(id: &str) -> bool {
+        id.starts_with(&format!("{}/", MONITOR_ID))
+    }
+
+    /// Get a path of the monitor engine as seen in the combined graph
+    fn from_monitor(path: &str) -> String {
+        match path.strip_prefix(MAIN_PREFIX) {
+            Some(inner) => format!("{}/{}", MONITOR_GRAPH, inner),
+            None => path.to_string(),
+        }
+    }
+
+    /// Add the graph of the monitor engine to the one of the main engine, as the monitor group
+    fn merge_graphs(mut main: Graph, monitor: Graph) -> Graph {
+        main.blocks.push(Block {
+            id: MONITOR_GRAPH.to_string(),
+            name: "Monitor".to_string(),
+            prototype: GRAPH_PROTOTYPE.to_string(),
+            ports: monitor.ports,
+        });
+        main.blocks.extend(monitor.blocks.into_iter().map(|block| Block { id: Self::from_monitor(&block.id), ..block }));
+        main.connections.extend(monitor.connections.into_iter().map(|c| Connection {
+            source: Self::from_monitor(&c.source),
+            destination: Self::from_monitor(&c.destination),
+        }));
+        main
+    }
+
+    /// Split a combined raw state into the ones of the main and monitor engines
+    /// States saved without a monitor engine only have the main part
+    fn split_state(state_data: &str) -> (&str, Option<&str>) {
+        match state_data.split_once(&format!("\n{}\n", MONITOR_STATE_MARKER)) {
+            Some((main, monitor)) => (main, Some(monitor)),
+            None => (state_data, None),
+        }
+    }
+}
+
+impl EngineBackend for SplitBackend {
+    fn rescan_plugins(&self, progress: &dyn Fn(&str)) -> Result<usize> {
+        self.monitor.rescan_plugins(progress)?;
+        self.main.rescan_plugins(progress)
+    }
+
+    fn list_plugins(&self) -> Vec<Plugin> {
+        self.main.list_plugins()
+    }
+
+    fn get_block_plugin(&self, block_path: &str) -> Option<Plugin> {
+        let (engine, path) = self.route_path(block_path).ok()?;
+        engine.get_block_plugin(&path)
+    }
+
+    fn create_block(&self, plugin_uri: &str, block_id: &str) -> Result<()> {
+        let (engine, id) = self.route_id(block_id)?;
+        engine.create_block(plugin_uri, &id)
+    }
+
+    fn create_graph(&self, graph_id: &str) -> Result<()> {
+        let (engine, id) = self.route_id(graph_id)?;
+        engine.create_graph(&id)
+    }
+
+    fn set_control_parameter(&self, block_id: &str, parameter_name: &str, value: f32) -> Result<()> {
+        let (engine, path) = self.route_path(block_id)?;
+        engine.set_control_parameter(&path, parameter_name, value)
+    }
+
+    fn set_block_name(&self, block_id: &str, name: &str) -> Result<()> {
+        let (engine, path) = self.route_path(block_id)?;
+        engine.set_block_name(&path, name)
+    }
+
+    fn set_polyphony(&self, block_id: &str, voices: u32) -> Result<()> {
+        let (engine, path) = self.route_path(block_id)?;
+        engine.set_polyphony(&path, voices)
+    }
+
+    fn get_polyphony(&self, block_id: &str) -> Result<u32> {
+        let (engine, path) = self.route_path(block_id)?;
+        engine.get_polyphony(&path)
+    }
+
+    fn get_control_values(&self, block_id: &str) -> Result<HashMap<String, f32>> {
+        if block_id == MONITOR_GRAPH {
+            return Ok(HashMap::new());
+        }
+        let (engine, path) = self.route_path(block_id)?;
+        engine.get_control_values(&path)
+    }
+
+    fn set_port_monitored(&self, port_path: &str, monitored: bool) -> Result<()> {
+        let (engine, path) = self.route_path(port_path)?;
+        engine.set_port_monitored(&path, monitored)
+    }
+
+    fn take_peaks(&self) -> Result<HashMap<String, f32>> {
+        let mut peaks = self.main.take_peaks()?;
+        peaks.extend(self.monitor.take_peaks()?.into_iter().map(|(port, peak)| (Self::from_monitor(&port), peak)));
+        Ok(peaks)
+    }
+
+    fn open_plugin_ui(&self, block_id: &str) -> Result<()> {
+        let (engine, path) = self.route_path(block_id)?;
+        engine.open_plugin_ui(&path)
+    }
+
+    fn ping(&self, timeout: Duration) -> Result<()> {
+        self.main.ping(timeout)?;
+        self.monitor.ping(timeout).map_err(|e| anyhow!("Monitor engine: {}", e))
+    }
+
+    fn reconnect(&self) -> Result<bool> {
+        let main_restarted = self.main.reconnect()?;
+        let monitor_restarted = self.monitor.reconnect().map_err(|e| anyhow!("Monitor engine: {}", e))?;
+        Ok(main_restarted || monitor_restarted)
+    }
+
+    fn take_error_output(&self) -> Vec<String> {
+        let mut lines = self.main.take_error_output();
+        lines.extend(self.monitor.take_error_output().into_iter().map(|line| format!("Monitor: {}", line)));
+        lines
+    }
+
+    fn connect(&self, source: &str, destination: &str) -> Result<()> {
+        if is_monitor_node(source) != is_monitor_node(destination) {
+            return Err(anyhow!("Cannot connect {} to {}, they are in different engines", source, destination));
+        }
+        let (engine, source) = self.route_path(source)?;
+        let (_, destination) = self.route_path(destination)?;
+        engine.connect(&source, &destination)
+    }
+
+    fn disconnect(&self, source: &str, destination: &str) -> Result<()> {
+        if is_monitor_node(source) != is_monitor_node(destination) {
+            return Ok(());
+        }
+        let (engine, source) = self.route_path(source)?;
+        let (_, destination) = self.route_path(destination)?;
+        engine.disconnect(&source, &destination)
+    }
+
+    fn create_input_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        let (engine, name) = self.route_id(port_name)?;
+        let path = engine.create_input_port(&name, port_type)?;
+        Ok(if Self::is_monitor_id(port_name) { Self::from_monitor(&path) } else { path })
+    }
+
+    fn create_output_port(&self, port_name: &str, port_type: PortType) -> Result<String> {
+        let (engine, name) = self.route_id(port_name)?;
+        let path = engine.create_output_port(&name, port_type)?;
+        Ok(if Self::is_monitor_id(port_name) { Self::from_monitor(&path) } else { path })
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        let (engine, path) = self.route_path(path)?;
+        engine.delete(&path)
+    }
+
+    fn move_block(&self, block_path: &str, new_path: &str) -> Result<()> {
+        if is_monitor_node(block_path) != is_monitor_node(new_path) {
+            return Err(anyhow!("Cannot move {} from one engine to the other", block_path));
+        }
+        let (engine, path) = self.route_path(block_path)?;
+        let (_, new_path) = self.route_path(new_path)?;
+        engine.move_block(&path, &new_path)
+    }
+
+    fn begin_bundle(&self) -> Result<()> {
+        self.main.begin_bundle()?;
+        if let Err(e) = self.monitor.begin_bundle() {
+            self.main.end_bundle()?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn end_bundle(&self) -> Result<()> {
+        let main = self.main.end_bundle();
+        self.monitor.end_bundle()?;
+        main
+    }
+
+    fn get_raw_state(&self) -> Result<String> {
+        Ok(format!("{}\n{}\n{}", self.main.get_raw_state()?, MONITOR_STATE_MARKER, self.monitor.get_raw_state()?))
+    }
+
+    fn set_raw_state(&self, state_data: &str) -> Result<()> {
+        let (main, monitor) = Self::split_state(state_data);
+        self.main.set_raw_state(main)?;
+        match monitor {
+            Some(monitor) => self.monitor.set_raw_state(monitor),
+            None => {
+                debug!("State saved without a monitor engine, keeping the monitor graph");
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_raw_state(&self, state_data: &str) -> Result<()> {
+        let (main, monitor) = Self::split_state(state_data);
+        self.main.validate_raw_state(main)?;
+        if let Some(monitor) = monitor {
+            self.monitor.validate_raw_state(monitor)?;
+        }
+        Ok(())
+    }
+
+    fn parse_raw_state(&self, state_data: &str) -> Result<StateContent> {
+        let (main, monitor) = Self::split_state(state_data);
+        let main = self.main.parse_raw_state(main)?;
+        let Some(monitor) = monitor else {
+            return Ok(main);
+        };
+        let monitor = self.monitor.parse_raw_state(monitor)?;
+        let mut values = main.values;
+        values.extend(monitor.values.into_iter().map(|(block, values)| (Self::from_monitor(&block), values)));
+        Ok(StateContent { graph: Self::merge_graphs(main.graph, monitor.graph), values })
+    }
+
+    fn import_bundle(&self, bundle_path: &Path) -> Result<()> {
+        self.main.import_bundle(bundle_path)
+    }
+
+    fn save_bundle(&self, bundle_path: &Path) -> Result<()> {
+        Err(anyhow!("Cannot save {:?}, graph bundles leave out the monitor engine", bundle_path))
+    }
+
+    fn preloaded_bundle(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn get_graph(&self) -> Result<Graph> {
+        Ok(Self::merge_graphs(self.main.get_graph()?, self.monitor.get_graph()?))
+    }
+
+    fn invalidate_graph(&self) {
+        self.main.invalidate_graph();
+        self.monitor.invalidate_graph();
+    }
+
+    fn close(&self) {
+        self.monitor.close();
+        self.main.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::mock::MockBackend;
+
+    #[test]
+    fn test_split_routing() {
+        let engine = SplitBackend::new(Box::new(MockBackend::new()), Box::new(MockBackend::new()));
+        engine.create_block("urn:traxdub:mock:delay", "delay").unwrap();
+        let input = engine.create_input_port("monitor/audio_in_1", PortType::Audio).unwrap();
+        assert_eq!(input, "ingen:/main/monitor/audio_in_1");
+        engine.create_block("urn:traxdub:mock:delay", "monitor/delay").unwrap();
+        engine.connect(&input, "ingen:/main/monitor/delay/in").unwrap();
+        engine.set_control_parameter("ingen:/main/monitor/delay", "time", 250.0).unwrap();
+        assert!(engine.connect("ingen:/main/delay/out", "ingen:/main/monitor/delay/in").is_err());
+        assert!(engine.create_graph("monitor").is_err());
+
+        let graph = engine.get_graph().unwrap();
+        let ids: Vec<&str> = graph.blocks.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec!["ingen:/main/delay", MONITOR_GRAPH, "ingen:/main/monitor/delay"]);
+        assert_eq!(graph.blocks[1].ports.len(), 1);
+        assert!(graph.ports.is_empty());
+        assert_eq!(graph.connections[0].destination, "ingen:/main/monitor/delay/in");
+        assert_eq!(engine.monitor.get_control_values("ingen:/main/delay").unwrap()["time"], 250.0);
+
+        // The raw state holds both engines
+        let state = engine.get_raw_state().unwrap();
+        let content = engine.parse_raw_state(&state).unwrap();
+        assert_eq!(content.graph, graph);
+        assert_eq!(content.values["ingen:/main/monitor/delay"]["time"], 250.0);
+        engine.delete("ingen:/main/monitor/delay").unwrap();
+        engine.set_raw_state(&state).unwrap();
+        assert_eq!(engine.get_graph().unwrap(), graph);
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// Number of protocol messages kept for the inspector
+const RECENT_CAPACITY: usize = 100;
+
+/// Number of characters of a message shown in the inspector, the whole states being too long to follow
+const PREVIEW_LENGTH: usize = 4000;
+
+/// Message exchanged with the engine, kept for the inspector
+#[derive(Debug, Clone)]
+pub struct ProtocolMessage {
+    /// Position of the message in the whole exchange, increasing
+    pub sequence: u64,
+    pub time: DateTime<Local>,
+    /// Whether the message was sent to the engine, received otherwise
+    pub outgoing: bool,
+    pub text: String,
+}
+
+/// Raw state read last from the engine
+#[derive(Debug, Clone)]
+pub struct RawState {
+    /// Sequence number of the message holding it
+    pub sequence: u64,
+    pub time: DateTime<Local>,
+    pub text: String,
+}
+
+/// Recent messages, the last raw state and the sequence number of the next message
+#[derive(Default)]
+struct Trace {
+    messages: VecDeque<ProtocolMessage>,
+    state: Option<RawState>,
+    next_sequence: u64,
+}
+
+static TRACE: OnceLock<Mutex<Trace>> = OnceLock::new();
+
+/// Keep a message sent to or received from the engine, returning its sequence number
+pub fn record(outgoing: bool, text: &str) -> u64 {
+    let mut trace = TRACE.get_or_init(Default::default).lock().unwrap();
+    let sequence = trace.next_sequence;
+    trace.next_sequence += 1;
+    if trace.messages.len() == RECENT_CAPACITY {
+        trace.messages.pop_front();
+    }
+    trace.messages.push_back(ProtocolMessage { sequence, time: Local::now(), outgoing, text: text.to_string() });
+    sequence
+}
+
+/// Keep the raw state read from the engine
+pub fn record_state(text: &str) {
+    let mut trace = TRACE.get_or_init(Default::default).lock().unwrap();
+    let sequence = trace.next_sequence.saturating_sub(1);
+    trace.state = Some(RawState { sequence, time: Local::now(), text: text.to_string() });
+}
+
+/// Get the kept messages from a sequence number on
+pub fn messages_since(sequence: u64) -> Vec<ProtocolMessage> {
+    let trace = TRACE.get_or_init(Default::default).lock().unwrap();
+    trace.messages.iter()
+        .filter(|message| message.sequence >= sequence)
+        .cloned()
+        .collect()
+}
+
+/// Get the raw state read last, if any
+pub fn last_state() -> Option<RawState> {
+    TRACE.get_or_init(Default::default).lock().unwrap().state.clone()
+}
+
+/// Get the beginning of a message laid out for reading
+pub fn preview(text: &str) -> String {
+    let pretty = pretty_turtle(text);
+    match pretty.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{}\n…", &pretty[..end]),
+        None => pretty,
+    }
+}
+
+/// Lay out Turtle text with one statement per line and one indented line per predicate
+/// Strings and IRIs are kept as they are, the rest of the white space is replaced
+pub fn pretty_turtle(text: &str) -> String {
+    let mut result = String::new();
+    let mut depth = 1;
+    let mut chars = text.chars().peekable();
+    let mut pending_space = false;
+
+    // Start a new line, indented to the nesting depth when inside a statement
+    let new_line = |result: &mut String, depth: usize| {
+        while result.ends_with(' ') {
+            result.pop();
+        }
+        result.push('\n');
+        result.push_str(&"    ".repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            pending_space = !result.is_empty() && !result.ends_with('\n') && !result.ends_with(' ');
+            continue;
+        }
+        if pending_space {
+            result.push(' ');
+            pending_space = false;
+        }
+        match c {
+            '"' | '<' => {
+                // Copy the string or IRI up to its end, with its escapes
+                let end = if c == '"' { '"' } else { '>' };
+                result.push(c);
+                while let Some(inner) = chars.next() {
+                    result.push(inner);
+                    if inner == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            result.push(escaped);
+                        }
+                    } else if inner == end {
+                        break;
+                    }
+                }
+            }
+            ';' => {
+                result.push(';');
+                new_line(&mut result, depth);
+            }
+            '[' if chars.peek() == Some(&']') => {
+                chars.next();
+                result.push_str("[]");
+            }
+            '[' => {
+                result.push('[');
+                depth += 1;
+                new_line(&mut result, depth);
+            }
+            ']' => {
+                depth = depth.saturating_sub(1).max(1);
+                new_line(&mut result, depth);
+                result.push(']');
+            }
+            '.' if chars.peek().is_none_or(|next| next.is_whitespace()) => {
+                while result.ends_with(' ') {
+                    result.pop();
+                }
+                result.push_str(" .\n");
+                depth = 1;
+            }
+            _ => result.push(c),
+        }
+    }
+    // Without the trailing spaces and the indentation of the blank lines
+    result.lines()
+        .map(|line| if line.trim().is_empty() { "" } else { line.trim_end() })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pretty_turtle() {
+        let text = "@prefix patch: <http://lv2plug.in/ns/ext/patch#> .\n[] a patch:Set ; patch:subject <ingen:/main/delay> ; patch:body [ ingen:value 0.5 ; rdfs:label \"A ; b.\" ] .";
+        assert_eq!(pretty_turtle(text), "@prefix patch: <http://lv2plug.in/ns/ext/patch#> .\n\
+            [] a patch:Set ;\n    patch:subject <ingen:/main/delay> ;\n    patch:body [\n        ingen:value 0.5 ;\n        \
+            rdfs:label \"A ; b.\"\n    ] .");
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Number of log entries kept for the in-app log panel
+const RECENT_CAPACITY: usize = 200;
+
+/// Log entry kept for the in-app log panel
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Position of the entry in the whole log, increasing
+    pub sequence: u64,
+    pub level: Level,
+    pub message: String,
+}
+
+/// Recent entries and the sequence number of the next one
+static RECENT: OnceLock<Mutex<(VecDeque<LogEntry>, u64)>> = OnceLock::new();
+
+/// Log file, once started
+static LOG_FILE: OnceLock<Mutex<LogFile>> = OnceLock::new();
+
+/// Log file renamed with a number suffix when it reaches its maximum size
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    /// Number of rotated files kept besides the current one
+    kept: u32,
+}
+
+impl LogFile {
+    /// Open the log file for appending
+    fn open(path: &Path, max_size: u64, kept: u32) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), file, size, max_size, kept })
+    }
+
+    /// Get the path of a rotated file, 1 being the most recent
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shift the rotated files and start a new one
+    fn rotate(&mut self) -> Result<()> {
+        if self.kept == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.kept).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    /// Append a line, rotating first if it would exceed the maximum size
+    fn write_line(&mut self, line: &str) -> Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+}
+
+/// Logger printing what RUST_LOG selects, as text or JSON lines,
+/// and keeping the recent info, warning and error entries for the log panel
+struct Logger {
+    filter: env_logger::Logger,
+    json: bool,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info || self.filter.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.filter.matches(record) {
+            if self.json {
+                let line = json!({
+                    "time": chrono::Local::now().to_rfc3339(),
+                    "level": record.level().as_str(),
+                    "target": record.target(),
+                    "message": record.args().to_string(),
+                });
+                eprintln!("{}", line);
+            } else {
+                self.filter.log(record);
+            }
+        }
+
+        if let Some(log_file) = LOG_FILE.get() {
+            if record.level() <= Level::Info || self.filter.matches(record) {
+                let line = format!("{} {:5} {}: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                    record.level(), record.target(), record.args());
+                // A failing log file cannot be reported through the log
+                if let Err(e) = log_file.lock().unwrap().write_line(&line) {
+                    eprintln!("Failed to write log file: {}", e);
+                }
+            }
+        }
+
+        if record.level() <= Level::Info {
+            let mut recent = RECENT.get_or_init(Default::default).lock().unwrap();
+            let sequence = recent.1;
+            recent.1 += 1;
+            if recent.0.len() == RECENT_CAPACITY {
+                recent.0.pop_front();
+            }
+            recent.0.push_back(LogEntry {
+                sequence,
+                level: record.level(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+
+    fn flush(&self) {
+        self.filter.flush();
+        if let Some(log_file) = LOG_FILE.get() {
+            let _ = log_file.lock().unwrap().file.flush();
+        }
+    }
+}
+
+/// Install the logger, printing JSON lines instead of text with `json`
+pub fn init(json: bool) -> Result<()> {
+    let filter = env_logger::Builder::from_default_env().build();
+    log::set_max_level(filter.filter().max(LevelFilter::Info));
+    log::set_boxed_logger(Box::new(Logger { filter, json }))?;
+    Ok(())
+}
+
+/// Also write the log to a file, keeping `kept` older files of at most `max_size` bytes
+pub fn start_file(path: &Path, max_size: u64, kept: u32) -> Result<()> {
+    let log_file = LogFile::open(path, max_size, kept)?;
+    if LOG_FILE.set(Mutex::new(log_file)).is_err() {
+        return Err(anyhow::anyhow!("Log file already started"));
+    }
+    log::info!("Logging to {:?}", path);
+    Ok(())
+}
+
+/// Get the kept entries from the given sequence number on
+pub fn entries_since(sequence: u64) -> Vec<LogEntry> {
+    let recent = RECENT.get_or_init(Default::default).lock().unwrap();
+    recent.0.iter()
+        .filter(|entry| entry.sequence >= sequence)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_file_rotation() {
+        let dir = std::env::temp_dir().join(format!("traxdub-log-test-{}", std::process::id()));
+        let path = dir.join("traxdub.log");
+        let mut log_file = LogFile::open(&path, 20, 2).unwrap();
+
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            log_file.write_line(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth line\n");
+        assert_eq!(fs::read_to_string(log_file.rotated_path(1)).unwrap(), "third line\n");
+        assert_eq!(fs::read_to_string(log_file.rotated_path(2)).unwrap(), "second line\n");
+        assert!(!log_file.rotated_path(3).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,16 +1,21 @@
+mod config;
 mod controller;
 mod engine;
+mod logging;
 mod ui;
 use anyhow::Result;
-use clap::Parser;
-use log::{debug, info};
-use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use clap::{Parser, Subcommand};
+use log::{debug, error, info};
+use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 
 /// TraxDub - Live music station application
 #[derive(Parser, Debug)]
 #[command(name = "traxdub")]
 #[command(about = "A live music station application with MIDI control", long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    
     /// Force re-initialization of base controls
     #[arg(short, long)]
     init: bool,
@@ -22,43 +27,299 @@ struct Args {
     /// Start with a new session (don't load last saved state)
     #[arg(short, long)]
     new: bool,
+    
+    /// Directory of the saved sessions (e.g. on a USB stick), overriding the config file
+    #[arg(long, value_name = "DIR")]
+    store_dir: Option<std::path::PathBuf>,
+    
+    /// Keep the saved sessions in a traxdub-store directory next to the executable
+    #[arg(long, conflicts_with = "store_dir")]
+    portable: bool,
+    
+    /// Read navigation commands from stdin (type help for the list)
+    #[arg(long)]
+    repl: bool,
+    
+    /// Serve the UI events and accept commands over a local WebSocket
+    #[arg(long, value_name = "ADDRESS", num_args = 0..=1, default_missing_value = "127.0.0.1:9001")]
+    websocket: Option<String>,
+    
+    /// Serve a read-only view of the UI to browsers on the local network, e.g. for a screen facing the performer
+    #[arg(long, value_name = "ADDRESS", num_args = 0..=1, default_missing_value = "0.0.0.0:8080")]
+    web_ui: Option<String>,
+    
+    /// Let the browsers of the web UI drive the controller with their keyboard
+    #[arg(long, requires = "web_ui")]
+    web_ui_control: bool,
+    
+    /// Feed the MIDI events of a recording instead of the MIDI inputs, and quit at its end
+    #[arg(long, value_name = "FILE")]
+    replay: Option<std::path::PathBuf>,
+    
+    /// Record the MIDI events reaching the controller, for later replay
+    #[arg(long, value_name = "FILE")]
+    record: Option<std::path::PathBuf>,
+    
+    /// Simulate the graph in memory instead of using Ingen (for demos and development)
+    #[arg(long, conflicts_with = "external")]
+    mock: bool,
+    
+    /// Zoom factor of the UI, overriding the config file
+    #[arg(long, value_name = "SCALE")]
+    ui_scale: Option<f32>,
+    
+    /// Use the compact layout for small screens such as 800x480 touchscreens
+    #[arg(long)]
+    compact: bool,
+    
+    /// Speak the focused element and menu option, for eyes-free operation
+    #[arg(long)]
+    speech: bool,
+    
+    /// Append the messages exchanged with Ingen to a capture file, for replay against the mock engine
+    #[arg(long, value_name = "FILE")]
+    trace_protocol: Option<std::path::PathBuf>,
+    
+    /// Print logs as JSON lines
+    #[arg(long)]
+    log_json: bool,
+    
+    /// Start a JACK server if none is running
+    #[arg(long)]
+    start_jack: bool,
+    
+    /// JACK server to start
+    #[arg(long, value_enum, default_value = "jackd")]
+    jack_server: controller::server::ServerKind,
+    
+    /// Audio device of the started JACK server
+    #[arg(long, default_value = "hw:0")]
+    jack_device: String,
+    
+    /// Sample rate of the started JACK server
+    #[arg(long, default_value_t = 48000)]
+    jack_rate: u32,
+    
+    /// Buffer size in frames of the started JACK server
+    #[arg(long, default_value_t = 256)]
+    jack_frames: u32,
+}
+
+/// Tasks run instead of the station
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the graph of a saved session as Graphviz DOT or Mermaid text
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "dot")]
+        format: engine::export::ExportFormat,
+        
+        /// Saved session file, the most recent save of the store when omitted
+        file: Option<std::path::PathBuf>,
+    },
+    /// Print the raw state of the running engine and the protocol messages exchanged to read it
+    Inspect,
+    /// Replay the requests of a protocol capture against the mock engine and report what differs
+    ReplayProtocol {
+        /// Capture file written with --trace-protocol
+        file: std::path::PathBuf,
+    },
+}
+
+/// Print the graph of a saved session, parsed like the engine in use would
+fn export_session(settings: &config::Settings, mock: bool, format: engine::export::ExportFormat, file: Option<std::path::PathBuf>) -> Result<()> {
+    let path = match file {
+        Some(path) => path,
+        None => controller::feature::persistence::latest_save_file(&settings.store_dir()?)?,
+    };
+    info!("Exporting the graph of {:?}", path);
+    let state_data = std::fs::read_to_string(&path)?;
+    let graph = if mock {
+        engine::Engine::new_mock().parse_raw_state(&state_data)?.graph
+    } else {
+        engine::protocol::IngenProtocol::parse_graph(&state_data)?
+    };
+    print!("{}", engine::export::render(&graph, format));
+    Ok(())
+}
+
+/// Print the raw state of the running Ingen, or of an empty mock engine, laid out for reading
+fn inspect_engine(settings: &config::Settings, mock: bool) -> Result<()> {
+    if mock {
+        print!("{}", engine::Engine::new_mock().get_raw_state()?);
+        return Ok(());
+    }
+    let timeout = std::time::Duration::from_millis(settings.ingen_start_timeout_ms as u64);
+    let engine = engine::Engine::new(true, &settings.ingen_socket, timeout, None, &|step| debug!("{}", step))?;
+    let first = engine::trace::messages_since(0).last().map_or(0, |message| message.sequence + 1);
+    let state = engine.get_raw_state();
+    for message in engine::trace::messages_since(first) {
+        println!("# {} {}", message.time.format("%H:%M:%S%.3f"), if message.outgoing { "sent" } else { "received" });
+        println!("{}\n", engine::trace::pretty_turtle(&message.text));
+    }
+    engine.close();
+    println!("# State");
+    println!("{}", engine::trace::pretty_turtle(&state?));
+    Ok(())
+}
+
+/// Replay a protocol capture against the mock engine, printing the report and the resulting graph
+fn replay_protocol(file: &std::path::Path) -> Result<()> {
+    let messages = engine::capture::load(file)?;
+    let engine = engine::Engine::new_mock();
+    let report = engine::capture::replay(&messages, &*engine);
+    println!("# {} requests applied, {} ignored", report.applied, report.ignored);
+    for (title, lines) in [("Failed", &report.failures), ("Unreadable", &report.unreadable), ("Differences", &report.differences)] {
+        if !lines.is_empty() {
+            println!("# {}", title);
+            lines.iter().for_each(|line| println!("{}", line));
+        }
+    }
+    println!("# Graph");
+    print!("{}", engine::export::render(&engine.get_graph()?, engine::export::ExportFormat::Dot));
+    Ok(())
+}
+
+/// Start the engine and the controller, showing each step on the startup screen
+fn start_station(args: &Args, ui: Arc<ui::UI>, settings: Arc<Mutex<config::Settings>>) -> Result<controller::Controller> {
+    let monitor_socket = settings.lock().unwrap().monitor_ingen_socket.clone();
+    let engine = if args.mock {
+        let engine = engine::Engine::new_mock();
+        match monitor_socket {
+            Some(_) => engine.with_monitor(engine::Engine::new_mock()),
+            None => engine,
+        }
+    } else {
+        let (socket, timeout, preload) = {
+            let settings = settings.lock().unwrap();
+            // The last session is loaded by Ingen itself when its bundle is up to date
+            let preload = settings.store_dir().ok()
+                .filter(|_| settings.preloads_graph() && !args.new)
+                .and_then(|dir| controller::feature::persistence::preload_bundle(&dir));
+            (settings.ingen_socket.clone(), std::time::Duration::from_millis(settings.ingen_start_timeout_ms as u64), preload)
+        };
+        let engine = engine::Engine::new(args.external, &socket, timeout, preload.as_deref(), &|step| {
+            let _ = ui.set_startup(Some(step));
+        })?;
+        match &monitor_socket {
+            Some(monitor_socket) => engine.with_monitor(engine::Engine::new(args.external, monitor_socket, timeout, None, &|step| {
+                let _ = ui.set_startup(Some(&format!("Monitor engine: {}", step)));
+            })?),
+            None => engine,
+        }
+    };
+    let engine = Arc::new(engine);
+    ui.set_startup(Some(if args.new { "Starting the controller" } else { "Loading the last session" }))?;
+    let mut controller = controller::Controller::new(ui.clone(), engine.clone(), settings, args.init, args.new)?;
+    let (command_sender, command_receiver) = std::sync::mpsc::channel();
+    if args.repl {
+        controller::repl::spawn(command_sender.clone());
+    }
+    if let Some(address) = &args.websocket {
+        ui::remote::start(address, ui.clone(), Some(command_sender.clone()), false)?;
+    }
+    if let Some(address) = &args.web_ui {
+        ui::remote::start(address, ui.clone(), args.web_ui_control.then(|| command_sender.clone()), true)?;
+    }
+    controller.attach_commands(command_receiver);
+    if let Some(path) = &args.record {
+        controller.attach_recorder(controller::replay::EventRecorder::create(path)?);
+    }
+    if let Some(path) = &args.replay {
+        controller.attach_replay(controller::replay::load(path)?);
+    }
+    ui.set_startup(None)?;
+
+    debug!("TraxDub initialized");
+    Ok(controller)
 }
 
 fn main() -> Result<()> {
-    // Initialize logger
-    env_logger::init();
-    
     // Parse command-line arguments
     let args = Args::parse();
     
+    // Initialize logger
+    logging::init(args.log_json)?;
+    
     debug!("Starting TraxDub...");
     
+    // Initialize modules
+    let mut settings = config::Settings::load();
+    if settings.log_file_kb > 0 {
+        let path = config::Settings::get_home_dir()?.join("logs").join("traxdub.log");
+        if let Err(e) = logging::start_file(&path, settings.log_file_kb * 1024, settings.log_files_kept) {
+            log::warn!("Logging to {:?} failed: {}", path, e);
+        }
+    }
+    settings.store_dir_override = if args.portable {
+        Some(config::Settings::portable_store_dir()?)
+    } else {
+        args.store_dir.clone()
+    };
+    info!("Session store: {:?}", settings.store_dir()?);
+    settings.ui_scale_override = args.ui_scale;
+    settings.compact_override = args.compact;
+    settings.speech_override = args.speech;
+    
+    if let Some(path) = &args.trace_protocol {
+        engine::capture::start(path)?;
+    }
+    
+    match args.command {
+        Some(Command::Export { format, file }) => return export_session(&settings, args.mock, format, file),
+        Some(Command::Inspect) => return inspect_engine(&settings, args.mock),
+        Some(Command::ReplayProtocol { file }) => return replay_protocol(&file),
+        None => {}
+    }
+    
     // Set up Ctrl-C handler
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
     
-    // Initialize modules
+    // Start JACK first as Ingen and the controller need it
+    let jack_server = if args.start_jack {
+        Some(controller::server::JackServer::start(&controller::server::ServerConfig {
+            kind: args.jack_server,
+            device: args.jack_device.clone(),
+            rate: args.jack_rate,
+            frames: args.jack_frames,
+        })?)
+    } else {
+        None
+    };
+    
     let ui = Arc::new(ui::UI::new());
-    let engine = Arc::new(engine::Engine::new(args.external)?);
-    let mut controller = controller::Controller::new(ui.clone(), engine.clone(), args.init, args.new)?;
+    ui.set_language(ui::i18n::language(&settings.language).as_deref());
+    ui.set_startup(Some("Starting"))?;
+    let settings = Arc::new(Mutex::new(settings));
     
     ctrlc::set_handler(move || {
         info!("Received Ctrl-C, shutting down");
         r.store(false, Ordering::SeqCst);
     })?;
-
-    debug!("TraxDub initialized");
     
     // Use scoped threads to avoid Send requirement
     let result = std::thread::scope(|s| {
-        // Start the controller in a background thread
+        // Start the station in a background thread, the window shows the startup steps meanwhile
         let controller_running = running.clone();
-        s.spawn(move || {
-            let _ = controller.run_until_signal(controller_running);
+        let controller_ui = ui.clone();
+        let station = s.spawn(move || {
+            let result = start_station(&args, controller_ui.clone(), settings).map(|mut controller| {
+                let _ = controller.run_until_signal(controller_running.clone());
+            });
+            if let Err(e) = &result {
+                // Keep the failure on the startup screen until the window is closed
+                error!("Startup failed: {:#}", e);
+                let _ = controller_ui.set_startup_failed(&e.to_string());
+                while controller_running.load(Ordering::SeqCst) {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+            }
 
             // Close the UI
             debug!("Closing UI...");
             let _ = ui::window::close();
+            result.map(|_| ())
         });
         
         // Run the UI window on the main thread (required for most platforms)
@@ -66,11 +327,17 @@ fn main() -> Result<()> {
             ui.get_message_queue(),
             ui.get_focused_grid_element(),
             ui.get_focused_menu_option(),
+            running.clone(),
         );
 
         // Return the first error if any occurred
-        ui_result
+        let station_result = station.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Station thread panicked")));
+        station_result.and(ui_result)
     });
     
+    if let Some(server) = jack_server {
+        server.close();
+    }
+    
     result
 }
@@ -0,0 +1,19 @@
+/// Get an embedded UI file and its content type, shared by the window and the web UI
+pub fn get(path: &str) -> Option<(&'static str, &'static [u8])> {
+    let asset: (&str, &[u8]) = match path {
+        "/" | "/index.html" => ("text/html", include_bytes!("window.html")),
+        "/style.css" => ("text/css", include_bytes!("style.css")),
+        "/window.js" => ("application/javascript", include_bytes!("window.js")),
+        "/console.js" => ("application/javascript", include_bytes!("console.js")),
+        "/logo.svg" => ("image/svg+xml", include_bytes!("logo.svg")),
+        "/logo.js" => ("application/javascript", include_bytes!("logo.js")),
+        "/menu.js" => ("application/javascript", include_bytes!("menu.js")),
+        "/grid.js" => ("application/javascript", include_bytes!("grid.js")),
+        "/view.js" => ("application/javascript", include_bytes!("view.js")),
+        "/rotary.js" => ("application/javascript", include_bytes!("rotary.js")),
+        "/control.js" => ("application/javascript", include_bytes!("control.js")),
+        "/oxanium.ttf" => ("font/ttf", include_bytes!("oxanium.ttf")),
+        _ => return None,
+    };
+    Some(asset)
+}
@@ -1,14 +1,17 @@
-function createGrid(svgElement) {
+function createGrid(svgElement, view = null) {
     let rowCount = 1;
     let columnCount = 2;
-    let boxes = new Map(); // id -> { box, row, col, group }
-    let lines = new Map(); // key -> { fromId, toId, linkType, path }
+    let boxes = new Map(); // id -> { box, row, col, group, placed }
+    let lines = new Map(); // key -> { fromId, toId, linkType, path, order, visitedLast }
     let animatingBoxes = new Map(); // id -> { startPos, endPos, startTime, duration }
     let pendingChanges = new Set(); // Set of box ids with pending position changes
+    let layoutNeeded = false; // Whether boxes or lines changed since the last layout
+    let columnOffsets = []; // Distance of each column from the first one, following the widest box of the columns
     let firstCommit = true;
     let focusCircle = null; // Circle to indicate focused line
     let focusedElement = null; // { type: 'box'|'line', id: string }
-    let lastFocusedLine = null; // Track last focused line for preference when returning from box
+    let lineOrder = 0; // Order given to the next line, lines of a box are navigated in this order
+    let lineVisits = 0; // Number of line focuses, the most recently visited line of a box is preferred
     let circleHideTimeout = null; // Timeout for hiding the focus circle
 
     const svgNS = "http://www.w3.org/2000/svg";
@@ -18,25 +21,29 @@ function createGrid(svgElement) {
     const boxMinWidth = 50;
     const boxHeight = 26;
 
-    // Color functions
+    // Color functions, following the theme variables of the page
+    function getThemeColor(name, fallback) {
+        return getComputedStyle(document.body).getPropertyValue(name).trim() || fallback;
+    }
+
     function getBoxHighlightColor(box) {
         if(box.active === false) {
-            return '#067575';
+            return getThemeColor('--accent-dim', '#067575');
         } else {
-            return '#66ffff';
+            return getThemeColor('--accent', '#66ffff');
         }
     }
 
     function getLineHighlightColor(fromBox, toBox) {
         if((fromBox.active === false) ||  (toBox.active === false)) {
-            return '#067575';
+            return getThemeColor('--accent-dim', '#067575');
         } else {
-            return '#66ffff';
+            return getThemeColor('--accent', '#66ffff');
         }
     }
 
     function getBackgroundColor(element) {
-        return '#1a1a1a';
+        return getThemeColor('--background', '#1a1a1a');
     }
 
     function updateBoxStates() {
@@ -165,7 +172,7 @@ function createGrid(svgElement) {
     const containerGroup = document.createElementNS(svgNS, 'g');
     containerGroup.setAttribute('id', 'grid-container');
     containerGroup.style.opacity = '0'; // Start invisible
-    svgElement.firstElementChild.firstElementChild.appendChild(containerGroup);
+    (view ? view.content : svgElement.firstElementChild.firstElementChild).appendChild(containerGroup);
 
     // Create container for lines (behind boxes)
     const linesGroup = document.createElementNS(svgNS, 'g');
@@ -177,21 +184,14 @@ function createGrid(svgElement) {
     boxesGroup.setAttribute('id', 'boxes-container');
     containerGroup.appendChild(boxesGroup);
 
-    function getHorizontalSpacing() {
-        if (columnCount <= 1) return maxHorizontalSpacing;
-        // Interpolate between max and min based on column count
-        // At 2 columns: maxHorizontalSpacing, approaches minHorizontalSpacing as columns increase
-        const ratio = Math.max(0, (columnCount - 2) / 10); // Reaches min at ~12 columns
-        return maxHorizontalSpacing - ratio * (maxHorizontalSpacing - minHorizontalSpacing);
+    function getColumnOffset(col) {
+        return columnOffsets[col] ?? col * maxHorizontalSpacing;
     }
 
     function getCellPosition(row, col) {
-        const hSpacing = getHorizontalSpacing();
-        const gridWidth = (columnCount - 1) * hSpacing;
+        const gridWidth = getColumnOffset(columnCount - 1);
 
-        // Use rowCount - 1 for even row counts to avoid position shifts
-        const effectiveRowCount = rowCount % 2 === 0 ? rowCount - 1 : rowCount;
-        const gridHeight = (effectiveRowCount - 1) * verticalSpacing;
+        const gridHeight = (rowCount - 1) * verticalSpacing;
 
         // Get SVG viewBox to center the grid
         const viewBox = svgElement.getAttribute('viewBox').split(' ');
@@ -205,11 +205,46 @@ function createGrid(svgElement) {
         const startY = centerY - gridHeight / 2;
 
         return {
-            x: startX + col * hSpacing,
+            x: startX + getColumnOffset(col),
             y: startY + row * verticalSpacing
         };
     }
 
+    // Get the final place and size of a box, not the one of its animation
+    function getBoxRect(id) {
+        const { row, col, group } = boxes.get(id);
+        const pos = getCellPosition(row, col);
+        const width = parseFloat(group.querySelector('rect').getAttribute('width')) || boxMinWidth;
+        return { x: pos.x, y: pos.y - boxHeight / 2, width, height: boxHeight };
+    }
+
+    // Get the middle of the focused box or line, null without focus
+    function getFocusPoint() {
+        if (!focusedElement) return null;
+        if (focusedElement.type === 'box') {
+            if (!boxes.has(focusedElement.id)) return null;
+            const rect = getBoxRect(focusedElement.id);
+            return { x: rect.x + rect.width / 2, y: rect.y + rect.height / 2 };
+        }
+        const line = lines.get(focusedElement.id);
+        if (!line || !boxes.has(line.fromId) || !boxes.has(line.toId)) return null;
+        const from = getBoxRect(line.fromId);
+        const to = getBoxRect(line.toId);
+        return { x: (from.x + from.width + to.x) / 2, y: (from.y + to.y + boxHeight) / 2 };
+    }
+
+    // Let the view follow the focus and show the boxes in its minimap
+    function updateView() {
+        if (!view) return;
+        const rects = [...boxes]
+            .filter(([, { box }]) => !box.invisible)
+            .map(([id]) => ({
+                ...getBoxRect(id),
+                focused: focusedElement !== null && focusedElement.type === 'box' && focusedElement.id === id
+            }));
+        view.show(rects, getFocusPoint());
+    }
+
     function setSize(newRowCount, newColumnCount) {
         rowCount = newRowCount;
         columnCount = newColumnCount;
@@ -221,15 +256,86 @@ function createGrid(svgElement) {
         });
     }
 
-    function setBox(id, box, row, col) {
-        const pos = getCellPosition(row, col);
+    // Place the boxes in layers following the signal flow: the inputs on the left, the outputs on the right
+    // Each box goes one column after its furthest source, the rows of a column follow the rows of the
+    // sources to keep the lines short, and the previous rows break the ties so small edits move few boxes
+    function layoutBoxes() {
+        layoutNeeded = false;
+
+        const incoming = new Map();
+        boxes.forEach((_, id) => incoming.set(id, []));
+        lines.forEach(({ fromId, toId }) => {
+            if (fromId !== toId && boxes.has(fromId) && incoming.has(toId)) {
+                incoming.get(toId).push(fromId);
+            }
+        });
+
+        // Columns in topological order, a box of a feedback loop is taken when nothing else is ready
+        const columns = new Map();
+        const byPreviousPosition = (a, b) => {
+            const boxA = boxes.get(a);
+            const boxB = boxes.get(b);
+            return (boxA.col - boxB.col) || (boxA.row - boxB.row) || a.localeCompare(b);
+        };
+        const waiting = [...boxes.keys()].sort(byPreviousPosition);
+        while (waiting.length > 0) {
+            const ready = waiting.findIndex(id => incoming.get(id).every(fromId => columns.has(fromId)));
+            const [id] = waiting.splice(ready === -1 ? 0 : ready, 1);
+            const sourceColumns = incoming.get(id).filter(fromId => columns.has(fromId)).map(fromId => columns.get(fromId));
+            columns.set(id, sourceColumns.length > 0 ? Math.max(...sourceColumns) + 1 : 0);
+        }
+        if (columns.has('inputs')) columns.set('inputs', 0);
+        const lastColumn = Math.max(1, ...[...columns].filter(([id]) => id !== 'outputs').map(([, col]) => col + 1));
+        if (columns.has('outputs')) columns.set('outputs', lastColumn);
+
+        const layers = Array.from({ length: lastColumn + 1 }, () => []);
+        columns.forEach((col, id) => layers[col].push(id));
+
+        // Wide boxes push the next columns, keeping room for the lines between them
+        const offsets = [0];
+        layers.forEach((layer, col) => {
+            const widest = Math.max(boxMinWidth, ...layer.map(id => parseFloat(boxes.get(id).group.querySelector('rect').getAttribute('width')) || 0));
+            offsets.push(offsets[col] + Math.max(maxHorizontalSpacing, widest + 2 * minHorizontalSpacing));
+        });
+        offsets.pop();
+        const height = Math.max(1, ...layers.map(layer => layer.length));
+        if (height !== rowCount || lastColumn + 1 !== columnCount || offsets.some((offset, col) => offset !== columnOffsets[col])) {
+            columnOffsets = offsets;
+            setSize(height, lastColumn + 1);
+        }
+
+        // Rows by the mean row of the sources, column after column, the shorter columns centered
+        const rows = new Map();
+        layers.forEach(layer => {
+            const weight = id => {
+                const sources = incoming.get(id).filter(fromId => rows.has(fromId));
+                if (sources.length === 0) return boxes.get(id).row;
+                return sources.reduce((sum, fromId) => sum + rows.get(fromId), 0) / sources.length;
+            };
+            const weights = new Map(layer.map(id => [id, weight(id)]));
+            layer.sort((a, b) => (weights.get(a) - weights.get(b)) || (boxes.get(a).row - boxes.get(b).row) || a.localeCompare(b));
+            layer.forEach((id, index) => rows.set(id, index + (height - layer.length) / 2));
+        });
+
+        layers.forEach((layer, col) => {
+            layer.forEach(id => {
+                const entry = boxes.get(id);
+                const row = rows.get(id);
+                if (entry.row !== row || entry.col !== col) {
+                    entry.row = row;
+                    entry.col = col;
+                    pendingChanges.add(id);
+                }
+            });
+        });
+    }
+
+    function setBox(id, box) {
 
         if (boxes.has(id)) {
             // Update existing box
             const existing = boxes.get(id);
             existing.box = box || existing.box;
-            existing.row = row || existing.row;
-            existing.col = col || existing.col;
 
             const text = existing.group.querySelector('text');
             const rect = existing.group.querySelector('rect');
@@ -243,6 +349,9 @@ function createGrid(svgElement) {
             rect.setAttribute('width', boxWidth);
             rect.setAttribute('x', '0');
             text.setAttribute('x', boxWidth / 2); // Center text in box
+            const latency = existing.group.querySelector('text.latency');
+            if (latency) latency.setAttribute('x', boxWidth / 2);
+            layoutNeeded = true;
 
             // Handle invisible boxes
             if (box.invisible) {
@@ -256,9 +365,8 @@ function createGrid(svgElement) {
             // Mark this box as having pending changes
             pendingChanges.add(id);
         } else {
-            // Create new box
+            // Create new box, placed by the next layout
             const group = document.createElementNS(svgNS, 'g');
-            group.setAttribute('transform', `translate(${pos.x}, ${pos.y})`);
 
             const text = document.createElementNS(svgNS, 'text');
             text.setAttribute('x', '0'); // Will be updated after measuring
@@ -295,7 +403,9 @@ function createGrid(svgElement) {
             // Insert rect before text to render behind it
             group.insertBefore(rect, text);
 
-            boxes.set(id, { box, row, col, group });
+            boxes.set(id, { box, row: 0, col: 0, group, placed: false });
+            pendingChanges.add(id);
+            layoutNeeded = true;
 
             // Animate expansion
             setTimeout(() => {
@@ -326,12 +436,30 @@ function createGrid(svgElement) {
         rect.setAttribute('y', '0');
         text.setAttribute('opacity', '0');
         boxes.delete(id);
+        pendingChanges.delete(id);
+        layoutNeeded = true;
 
         setTimeout(() => {
             group.parentNode.removeChild(group);
         }, 250);
     }
 
+    // Remove a box with all its lines, moving the focus away from them
+    function removeNode(id) {
+        const touching = [...lines.values()].filter(({ fromId, toId }) => fromId === id || toId === id);
+        const focused = focusedElement && (focusedElement.id === id ||
+            touching.some(({ fromId, toId }) => focusedElement.id === `${fromId}-${toId}`));
+        if (focused) unfocus();
+
+        touching.forEach(({ fromId, toId }) => removeLine(fromId, toId));
+        removeBox(id);
+
+        if (focused && lines.size > 0) {
+            const { fromId, toId } = lines.values().next().value;
+            focusLine(fromId, toId);
+        }
+    }
+
     function getBoxEnds(id) {
         if (!boxes.has(id)) return null;
 
@@ -409,10 +537,10 @@ function createGrid(svgElement) {
     function addLine(fromId, toId, linkType = 'normal') {
         const key = `${fromId}-${toId}`;
 
-        // Remove existing line if any
-        if (lines.has(key)) {
-            const { path } = lines.get(key);
-            linesGroup.removeChild(path);
+        // Remove existing line if any, keeping its rank
+        const existing = lines.get(key);
+        if (existing) {
+            linesGroup.removeChild(existing.path);
         }
 
         // Create new line
@@ -429,7 +557,36 @@ function createGrid(svgElement) {
         path.setAttribute('d', pathData);
 
         linesGroup.appendChild(path);
-        lines.set(key, { fromId, toId, linkType, path });
+        layoutNeeded = layoutNeeded || !existing;
+        lines.set(key, {
+            fromId,
+            toId,
+            linkType,
+            path,
+            order: existing ? existing.order : ++lineOrder,
+            visitedLast: existing ? existing.visitedLast : 0
+        });
+    }
+
+    function setLineRanks(ranks) {
+        ranks.forEach(({ fromId, toId, order, visitedLast }) => {
+            const line = lines.get(`${fromId}-${toId}`);
+            if (!line) return;
+            line.order = order;
+            line.visitedLast = visitedLast;
+            lineOrder = Math.max(lineOrder, order);
+            lineVisits = Math.max(lineVisits, visitedLast);
+        });
+    }
+
+    // Get the line before (step -1) or after (step 1) a line among the lines leaving the same box
+    function siblingLine(key, step) {
+        const current = lines.get(key);
+        if (!current) return null;
+        const siblings = [...lines.values()]
+            .filter(({ fromId }) => fromId === current.fromId)
+            .sort((a, b) => a.order - b.order);
+        return siblings[siblings.indexOf(current) + step] || null;
     }
 
     function removeLine(fromId, toId) {
@@ -440,6 +597,7 @@ function createGrid(svgElement) {
         const { path } = lines.get(key);
         path.parentNode.removeChild(path);
         lines.delete(key);
+        layoutNeeded = true;
     }
 
     function unfocus() {
@@ -524,8 +682,8 @@ function createGrid(svgElement) {
         // Track focused element
         focusedElement = { type: 'line', id: key };
 
-        // Save this as the last focused line for preference
-        lastFocusedLine = key;
+        // Remember the visit for the preference when coming back to one of its boxes
+        lines.get(key).visitedLast = ++lineVisits;
         
         // Send focus change to Rust
         const { linkType } = lines.get(key) || {};
@@ -536,6 +694,7 @@ function createGrid(svgElement) {
             toId: toIdPart,
             linkType: linkType || 'normal'
         });
+        updateView();
     }
 
     function focusBox(id, startPos = null, edge = null) {
@@ -593,6 +752,7 @@ function createGrid(svgElement) {
             // Set focused element immediately
             focusedElement = { type: 'box', id };
             sendGridFocusChanged({ type: 'grid_node', id });
+            updateView();
             return;
         }
 
@@ -607,6 +767,7 @@ function createGrid(svgElement) {
         // Track focused element
         focusedElement = { type: 'box', id };
         sendGridFocusChanged({ type: 'grid_node', id });
+        updateView();
     }
 
     function moveFocusUp() {
@@ -634,11 +795,16 @@ function createGrid(svgElement) {
             });
 
             if (nearestBox) {
-                // Reset line preference when moving box-to-box
-                lastFocusedLine = null;
                 focusBox(nearestBox);
             }
         } else if (focusedElement.type === 'line') {
+            // Lines leaving the same box come first, in their order
+            const sibling = siblingLine(focusedElement.id, -1);
+            if (sibling) {
+                focusLine(sibling.fromId, sibling.toId);
+                return;
+            }
+
             // Navigate to closest line above that shares column with start or end box
             const [fromId, toId] = focusedElement.id.split('-');
             const fromBox = boxes.get(fromId);
@@ -692,8 +858,6 @@ function createGrid(svgElement) {
             });
 
             if (nearestLine) {
-                // Reset line preference when moving line-to-line
-                lastFocusedLine = null;
                 const [newFromId, newToId] = nearestLine.split('-');
                 focusLine(newFromId, newToId);
             }
@@ -725,11 +889,16 @@ function createGrid(svgElement) {
             });
 
             if (nearestBox) {
-                // Reset line preference when moving box-to-box
-                lastFocusedLine = null;
                 focusBox(nearestBox);
             }
         } else if (focusedElement.type === 'line') {
+            // Lines leaving the same box come first, in their order
+            const sibling = siblingLine(focusedElement.id, 1);
+            if (sibling) {
+                focusLine(sibling.fromId, sibling.toId);
+                return;
+            }
+
             // Navigate to closest line below that shares column with start or end box
             const [fromId, toId] = focusedElement.id.split('-');
             const fromBox = boxes.get(fromId);
@@ -783,8 +952,6 @@ function createGrid(svgElement) {
             });
 
             if (nearestLine) {
-                // Reset line preference when moving line-to-line
-                lastFocusedLine = null;
                 const [newFromId, newToId] = nearestLine.split('-');
                 focusLine(newFromId, newToId);
             }
@@ -801,28 +968,29 @@ function createGrid(svgElement) {
 
             let nearestLine = null;
             let minRowDistance = Infinity;
-            let preferredLine = null; // Check if last focused line is available
+            let preferredLine = null; // Most recently visited line of the box
 
             // Check incoming lines (upstream)
-            lines.forEach(({ fromId, toId }, lineKey) => {
+            lines.forEach((line) => {
+                const { fromId, toId, order, visitedLast } = line;
                 if (toId === focusedElement.id) {
                     const fromBox = boxes.get(fromId);
                     if (!fromBox) return;
 
-                    // Check if this is the last focused line
-                    if (lastFocusedLine && lineKey === lastFocusedLine) {
-                        preferredLine = { fromId, toId };
+                    if (visitedLast > 0 && (!preferredLine || visitedLast > preferredLine.visitedLast)) {
+                        preferredLine = line;
                     }
 
+                    // Lines at the same distance are taken in their order
                     const rowDistance = Math.abs(fromBox.row - current.row);
-                    if (rowDistance < minRowDistance) {
+                    if (rowDistance < minRowDistance || (rowDistance === minRowDistance && order < nearestLine.order)) {
                         minRowDistance = rowDistance;
-                        nearestLine = { fromId, toId };
+                        nearestLine = line;
                     }
                 }
             });
 
-            // Prefer the last focused line if available, otherwise use nearest
+            // Prefer the most recently visited line, otherwise use nearest
             const selectedLine = preferredLine || nearestLine;
 
             if (selectedLine) {
@@ -858,28 +1026,29 @@ function createGrid(svgElement) {
 
             let nearestLine = null;
             let minRowDistance = Infinity;
-            let preferredLine = null; // Check if last focused line is available
+            let preferredLine = null; // Most recently visited line of the box
 
             // Check outgoing lines (downstream)
-            lines.forEach(({ fromId, toId }, lineKey) => {
+            lines.forEach((line) => {
+                const { fromId, toId, order, visitedLast } = line;
                 if (fromId === focusedElement.id) {
                     const toBox = boxes.get(toId);
                     if (!toBox) return;
 
-                    // Check if this is the last focused line
-                    if (lastFocusedLine && lineKey === lastFocusedLine) {
-                        preferredLine = { fromId, toId };
+                    if (visitedLast > 0 && (!preferredLine || visitedLast > preferredLine.visitedLast)) {
+                        preferredLine = line;
                     }
 
+                    // Lines at the same distance are taken in their order
                     const rowDistance = Math.abs(toBox.row - current.row);
-                    if (rowDistance < minRowDistance) {
+                    if (rowDistance < minRowDistance || (rowDistance === minRowDistance && order < nearestLine.order)) {
                         minRowDistance = rowDistance;
-                        nearestLine = { fromId, toId };
+                        nearestLine = line;
                     }
                 }
             });
 
-            // Prefer the last focused line if available, otherwise use nearest
+            // Prefer the most recently visited line, otherwise use nearest
             const selectedLine = preferredLine || nearestLine;
 
             if (selectedLine) {
@@ -919,6 +1088,10 @@ function createGrid(svgElement) {
         updateBoxStates();
         updateElementColors();
 
+        if (layoutNeeded) {
+            layoutBoxes();
+        }
+        updateView();
         if (pendingChanges.size === 0) return;
 
         const startTime = Date.now();
@@ -929,11 +1102,20 @@ function createGrid(svgElement) {
         pendingChanges.forEach(id => {
             if (!boxes.has(id)) return;
 
-            const { row, col, group } = boxes.get(id);
+            const entry = boxes.get(id);
+            const { row, col, group } = entry;
+            const endPos = getCellPosition(row, col);
+
+            // New boxes appear in place, the other ones make room for them
+            if (!entry.placed) {
+                entry.placed = true;
+                group.setAttribute('transform', `translate(${endPos.x}, ${endPos.y})`);
+                return;
+            }
+
             const currentTransform = group.getAttribute('transform');
             const match = currentTransform.match(/translate\(([^,]+),\s*([^)]+)\)/);
             const startPos = match ? { x: parseFloat(match[1]), y: parseFloat(match[2]) } : { x: 0, y: 0 };
-            const endPos = getCellPosition(row, col);
 
             animatingBoxes.set(id, { startPos, endPos, startTime, duration });
 
@@ -969,12 +1151,69 @@ function createGrid(svgElement) {
         return focusedElement;
     }
 
+    function setBoxState(id, state) {
+        const entry = boxes.get(id);
+        if (!entry) return;
+        entry.group.classList.remove('muted', 'soloed', 'silenced', 'bypassed');
+        if (state) {
+            entry.group.classList.add(state);
+        }
+    }
+
+    function setBoxTag(id, color) {
+        const entry = boxes.get(id);
+        if (!entry) return;
+        for (const name of [...entry.group.classList].filter(name => name.startsWith('tag-'))) {
+            entry.group.classList.remove(name);
+        }
+        if (color !== null && color !== undefined) {
+            entry.group.classList.add(`tag-${color}`);
+        }
+    }
+
+    // Mark the boxes of the nodes living in the monitor engine
+    function setBoxEngine(id, engine) {
+        const entry = boxes.get(id);
+        if (!entry) return;
+        entry.group.classList.toggle('engine-monitor', engine === 'monitor');
+    }
+
+    function setBoxClipping(id, clipping) {
+        const entry = boxes.get(id);
+        if (!entry) return;
+        entry.group.classList.toggle('clipping', clipping);
+    }
+
+    // Show the latency of a node under its box, null to hide it
+    function setBoxLatency(id, latency) {
+        const entry = boxes.get(id);
+        if (!entry) return;
+        let text = entry.group.querySelector('text.latency');
+        if (latency === null || latency === undefined) {
+            if (text) text.remove();
+            return;
+        }
+        if (!text) {
+            text = document.createElementNS(svgNS, 'text');
+            text.setAttribute('class', 'latency');
+            text.setAttribute('y', boxHeight / 2 + 10);
+            text.setAttribute('text-anchor', 'middle');
+            text.setAttribute('dominant-baseline', 'middle');
+            text.setAttribute('font-size', '11');
+            entry.group.appendChild(text);
+        }
+        text.setAttribute('x', parseFloat(entry.group.querySelector('rect').getAttribute('width')) / 2);
+        text.textContent = latency;
+    }
+
     return {
         setSize,
         setBox,
         removeBox,
+        removeNode,
         addLine,
         removeLine,
+        setLineRanks,
         focusLine,
         focusBox,
         moveFocusUp,
@@ -982,6 +1221,11 @@ function createGrid(svgElement) {
         moveFocusLeft,
         moveFocusRight,
         commit,
-        getFocusedElement
+        getFocusedElement,
+        setBoxState,
+        setBoxTag,
+        setBoxEngine,
+        setBoxClipping,
+        setBoxLatency
     };
 }
@@ -0,0 +1,71 @@
+/// Menu path and session status shown at the top of the window and on the console
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Header {
+    /// Labels of the open menus, the top-most last
+    pub menu_path: Vec<String>,
+    /// Name of the current session
+    pub session: Option<String>,
+    /// Whether the session changed since it was saved
+    pub dirty: bool,
+    /// Current tempo
+    pub bpm: Option<f32>,
+    /// DSP load in percent
+    pub dsp_load: Option<u32>,
+}
+
+impl Header {
+    /// Get the path of the open menus, e.g. "File → Load → Cosmic River"
+    pub fn breadcrumb(&self) -> String {
+        self.menu_path.join(" → ")
+    }
+
+    /// Get the status line with the known items: session name with its dirty flag, tempo and DSP load
+    pub fn status(&self) -> String {
+        let session = self.session.as_ref()
+            .map(|name| format!("{}{}", name, if self.dirty { " *" } else { "" }));
+        let bpm = self.bpm.map(|bpm| format!("{:.1} BPM", bpm));
+        let dsp_load = self.dsp_load.map(|percent| format!("DSP {}%", percent));
+        [session, bpm, dsp_load].into_iter().flatten().collect::<Vec<_>>().join(" · ")
+    }
+
+    /// Get the line printed on the console, the status followed by the menu path
+    pub fn line(&self) -> String {
+        match (self.status(), self.breadcrumb()) {
+            (status, breadcrumb) if breadcrumb.is_empty() => status,
+            (status, breadcrumb) if status.is_empty() => breadcrumb,
+            (status, breadcrumb) => format!("{} | {}", status, breadcrumb),
+        }
+    }
+
+    /// Check whether the console line must be printed again: the menu path or the session changed
+    /// The tempo and the load move too often for the console, they follow with the next line
+    pub fn console_changed(&self, previous: &Header) -> bool {
+        self.menu_path != previous.menu_path || self.session != previous.session || self.dirty != previous.dirty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_lines() {
+        let mut header = Header::default();
+        assert_eq!(header.line(), "");
+
+        header.menu_path = vec!["File".to_string(), "Load".to_string(), "Cosmic River".to_string()];
+        assert_eq!(header.line(), "File → Load → Cosmic River");
+
+        header.session = Some("Cosmic River".to_string());
+        header.dirty = true;
+        header.bpm = Some(120.0);
+        header.dsp_load = Some(23);
+        assert_eq!(header.status(), "Cosmic River * · 120.0 BPM · DSP 23%");
+
+        let previous = header.clone();
+        header.dsp_load = Some(24);
+        assert!(!header.console_changed(&previous));
+        header.menu_path.pop();
+        assert!(header.console_changed(&previous));
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::Settings;
+
+/// Translations of the user-visible strings, keyed by their English text
+///
+/// A locale file ~/.traxdub/lang/<language>.json maps the English strings to their translation.
+/// Keys with `{}` placeholders match the formatted strings, the values taking the places of the
+/// `{}` of the translation in order, or of `{1}`, `{2}`... when the language needs another order:
+/// `"Bookmark {}: {}": "Favori {} : {}"`. The values are translated too, so that `"{} ✓": "{} ✓"`
+/// keeps the checked menu options translated.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    exact: HashMap<String, String>,
+    /// Literal parts around the placeholders of a key, with its translation
+    patterns: Vec<(Vec<String>, String)>,
+}
+
+impl Catalog {
+    /// Parse a locale file
+    pub fn parse(content: &str) -> Result<Self> {
+        let entries: HashMap<String, String> = serde_json::from_str(content)?;
+        let mut catalog = Self::default();
+        for (key, translation) in entries {
+            if key.replace("{}", "").is_empty() {
+                // A key made of placeholders only would match any text
+                warn!("Ignoring the translation of {:?}", key);
+            } else if key.contains("{}") {
+                catalog.patterns.push((key.split("{}").map(str::to_string).collect(), translation));
+            } else {
+                catalog.exact.insert(key, translation);
+            }
+        }
+        // The pattern with the most literal text is the most specific, it is tried first
+        catalog.patterns.sort_by_key(|(parts, _)| std::cmp::Reverse(parts.iter().map(String::len).sum::<usize>()));
+        Ok(catalog)
+    }
+
+    /// Load the locale file of a language, an empty catalog keeping the English strings when it is missing or invalid
+    pub fn load(language: &str) -> Self {
+        let catalog = lang_dir().and_then(|dir| {
+            let path = dir.join(format!("{}.json", language));
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("No locale file {:?}", path))?;
+            Self::parse(&content).with_context(|| format!("Invalid locale file {:?}", path))
+        });
+        match catalog {
+            Ok(catalog) => {
+                info!("Using the {} translations ({} strings)", language, catalog.exact.len() + catalog.patterns.len());
+                catalog
+            }
+            Err(e) => {
+                warn!("{:#}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Translate a string, unchanged when the catalog does not know it
+    pub fn translate(&self, text: &str) -> String {
+        if let Some(translation) = self.exact.get(text) {
+            return translation.clone();
+        }
+        self.patterns.iter()
+            .find_map(|(parts, translation)| Some((match_pattern(parts, text)?, translation)))
+            .map(|(values, translation)| {
+                let values: Vec<String> = values.iter().map(|value| self.translate(value)).collect();
+                fill(translation, &values)
+            })
+            .unwrap_or_else(|| text.to_string())
+    }
+}
+
+/// Get the values of the placeholders of a key matching a text
+fn match_pattern<'a>(parts: &[String], text: &'a str) -> Option<Vec<&'a str>> {
+    let (first, rest) = parts.split_first()?;
+    let mut remaining = text.strip_prefix(first.as_str())?;
+    let mut values = Vec::new();
+    for (index, part) in rest.iter().enumerate() {
+        if index + 1 == rest.len() {
+            values.push(remaining.strip_suffix(part.as_str())?);
+        } else {
+            let end = remaining.find(part.as_str())?;
+            values.push(&remaining[..end]);
+            remaining = &remaining[end + part.len()..];
+        }
+    }
+    Some(values)
+}
+
+/// Put the values in the placeholders of a translation
+fn fill(translation: &str, values: &[String]) -> String {
+    let mut result = String::new();
+    let mut next = 0;
+    let mut rest = translation;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some((placeholder, tail)) = after.find('}').map(|end| (&after[..end], &after[end + 1..])) else {
+            result.push('{');
+            rest = after;
+            continue;
+        };
+        let index = if placeholder.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            placeholder.parse::<usize>().ok().filter(|&position| position > 0).map(|position| position - 1)
+        };
+        match index {
+            Some(index) => {
+                result.push_str(values.get(index).map(String::as_str).unwrap_or_default());
+                rest = tail;
+            }
+            None => {
+                result.push('{');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Get the directory of the locale files
+fn lang_dir() -> Result<PathBuf> {
+    Ok(Settings::get_home_dir()?.join("lang"))
+}
+
+/// Get the language of the interface: the setting, or the one of the LANG environment variable when empty
+/// None for English, the language of the strings in the code
+pub fn language(setting: &str) -> Option<String> {
+    let locale = if setting.is_empty() {
+        std::env::var("LANG").ok()?
+    } else {
+        setting.to_string()
+    };
+    let code = locale.split(['_', '.', '@']).next()?.to_lowercase();
+    match code.as_str() {
+        "" | "en" | "c" | "posix" => None,
+        _ => Some(code),
+    }
+}
+
+/// Get the languages having a locale file, sorted
+pub fn available_languages() -> Vec<String> {
+    let Ok(entries) = lang_dir().and_then(|dir| Ok(fs::read_dir(dir)?)) else {
+        debug!("No locale directory");
+        return Vec::new();
+    };
+    let mut languages: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| path.file_stem()?.to_str().map(str::to_string))
+        .collect();
+    languages.sort();
+    languages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let catalog = Catalog::parse(r#"{
+            "Settings": "Réglages",
+            "Bookmark {}: {}": "Favori {} : {}",
+            "Bookmark {} is empty": "Le favori {} est vide",
+            "{} is gone": "{1} a disparu",
+            "Off": "Aus",
+            "{} ✓": "{} ✓"
+        }"#).unwrap();
+        assert_eq!(catalog.translate("Settings"), "Réglages");
+        assert_eq!(catalog.translate("Bookmark 2: delay → outputs"), "Favori 2 : delay → outputs");
+        assert_eq!(catalog.translate("Bookmark 3 is empty"), "Le favori 3 est vide");
+        assert_eq!(catalog.translate("reverb is gone"), "reverb a disparu");
+        assert_eq!(catalog.translate("Off ✓"), "Aus ✓");
+        assert_eq!(catalog.translate("Load"), "Load");
+    }
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language("fr_CH.UTF-8"), Some("fr".to_string()));
+        assert_eq!(language("de"), Some("de".to_string()));
+        assert_eq!(language("en_US"), None);
+    }
+}
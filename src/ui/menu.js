@@ -65,9 +65,18 @@ function showMenu(options, menuId = 'menu') {
         }
     }
     
+    // Menus opened in the compact layout show fewer, bigger options
+    const maxVisible = document.body.classList.contains('layout-compact') ? 6 : 10;
+
     function render() {
         menuDiv.innerHTML = '';
-        const maxVisible = 10;
+        // Show the position in long menus
+        if (menuOptions.length > maxVisible) {
+            let header = document.createElement('div');
+            header.className = 'menu-position';
+            header.textContent = `${selected + 1}/${menuOptions.length}`;
+            menuDiv.appendChild(header);
+        }
         const visibleCount = Math.min(maxVisible, menuOptions.length);
         const halfAbove = Math.floor((visibleCount - 1) / 2);
         const halfBelow = Math.floor(visibleCount / 2);
@@ -86,6 +95,56 @@ function showMenu(options, menuId = 'menu') {
     }
     render();
 
+    // First letter of an option label, used for alphabetical jumps
+    function initial(index) {
+        return (menuOptions[index].label[0] || '').toUpperCase();
+    }
+
+    // Jump to the next/previous first letter in sorted menus, or by a page otherwise
+    // Sections prepended to a sorted list (e.g. favorites) are reached by wrapping around
+    function jump(direction) {
+        const count = menuOptions.length;
+        if (count <= maxVisible) return;
+
+        // Options are sorted by label from this index on
+        let sortedFrom = count - 1;
+        while (sortedFrom > 0 && menuOptions[sortedFrom - 1].label.localeCompare(
+            menuOptions[sortedFrom].label, undefined, { sensitivity: 'base' }) <= 0) {
+            sortedFrom--;
+        }
+
+        if (count - sortedFrom <= maxVisible) {
+            const step = direction === 'forward' ? maxVisible : -maxVisible;
+            selected = Math.min(count - 1, Math.max(0, selected + step));
+        } else if (selected < sortedFrom) {
+            selected = direction === 'forward' ? sortedFrom : 0;
+        } else if (direction === 'forward') {
+            const letter = initial(selected);
+            let i = selected;
+            while (i < count && initial(i) === letter) i++;
+            selected = i < count ? i : 0;
+        } else {
+            // Go to the start of the current group, or of the previous one if already there
+            let i = selected;
+            if (i === sortedFrom) {
+                if (sortedFrom > 0) {
+                    selected = 0;
+                    render();
+                    sendMenuFocus();
+                    return;
+                }
+                i = count - 1;
+            } else if (initial(i - 1) !== initial(i)) {
+                i--;
+            }
+            const letter = initial(i);
+            while (i > sortedFrom && initial(i - 1) === letter) i--;
+            selected = i;
+        }
+        render();
+        sendMenuFocus();
+    }
+
     document.body.appendChild(menuDiv);
     
     // Send initial focus
@@ -285,6 +344,7 @@ function showMenu(options, menuId = 'menu') {
         currentMenu = {
             moveUp: () => currentMenu && currentMenu._moveUp(),
             moveDown: () => currentMenu && currentMenu._moveDown(),
+            jump: (direction) => currentMenu && currentMenu._jump(direction),
             getSelected: () => currentMenu && currentMenu._getSelected(),
             close: () => currentMenu && currentMenu._close(),
             exit: () => currentMenu && currentMenu._exit(),
@@ -302,6 +362,7 @@ function showMenu(options, menuId = 'menu') {
                 sendMenuFocus();
             },
             _getSelected: () => menuOptions[selected],
+            _jump: jump,
             _close,
             _exit: exit
         };
@@ -352,6 +413,7 @@ function showMenu(options, menuId = 'menu') {
     const menu = {
         moveUp: () => currentMenu && currentMenu._moveUp(),
         moveDown: () => currentMenu && currentMenu._moveDown(),
+        jump: (direction) => currentMenu && currentMenu._jump(direction),
         getSelected: () => currentMenu && currentMenu._getSelected(),
         close: () => currentMenu && currentMenu._close(),
         exit: () => currentMenu && currentMenu._exit(),
@@ -370,6 +432,7 @@ function showMenu(options, menuId = 'menu') {
             sendMenuFocus();
         },
         _getSelected: () => menuOptions[selected],
+        _jump: jump,
         _close,
         _exit: exit
     };
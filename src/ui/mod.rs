@@ -1,10 +1,19 @@
 pub mod window;
+pub mod remote;
+pub mod assets;
+pub mod model;
+pub mod header;
+pub mod i18n;
+pub mod speech;
 
 use anyhow::{Result, Context};
-use log::{debug, trace};
+use log::{debug, info, trace};
 use serde_json::json;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
 use crate::controller::{NavigationLevel, KnobDirection};
 
@@ -23,6 +32,15 @@ pub struct Menu {
     pub options: Vec<MenuOption>,
 }
 
+/// Parameter displayed on the control board
+#[derive(Debug, Clone)]
+pub struct ParameterDisplay {
+    pub name: String,
+    pub title: String,
+    /// Value normalized between 0 and 1
+    pub value: f32,
+}
+
 /// Grid element type (node or link)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum GridElement {
@@ -63,6 +81,18 @@ pub enum LinkType {
     Virtual,
 }
 
+/// Node state indicator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeState {
+    Normal,
+    Muted,
+    Soloed,
+    /// Silenced because other nodes are soloed
+    Silenced,
+    /// Bypassed, the dry signal goes around the node
+    Bypassed,
+}
+
 /// UI node
 #[derive(Debug, Clone)]
 pub struct Node {
@@ -81,12 +111,48 @@ pub struct Link {
     pub link_type: LinkType,
 }
 
+/// Busy indicator shown while a long operation runs, hidden when dropped
+pub struct Busy {
+    ui: Arc<UI>,
+    message: String,
+}
+
+impl Busy {
+    /// Show the current step of the operation
+    pub fn update(&self, step: &str) {
+        let message = format!("{}: {}", self.ui.translate(&self.message), self.ui.translate(step));
+        if let Err(e) = self.ui.set_busy(Some(&message)) {
+            debug!("Cannot show busy step: {}", e);
+        }
+    }
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        if let Err(e) = self.ui.set_busy(None) {
+            debug!("Cannot hide busy indicator: {}", e);
+        }
+    }
+}
+
 pub struct UI {
     session_name: Mutex<Option<String>>, // Current session mnemonic
+    view_scope: Mutex<Option<String>>, // Path of the group shown instead of the main graph
     message_queue: Arc<Mutex<VecDeque<String>>>,
     menu_stack_size: Arc<Mutex<usize>>,
     focused_grid_element: Arc<Mutex<Option<GridElement>>>,
     focused_menu_option: Arc<Mutex<Option<MenuOptionElement>>>,
+    remote: Arc<Mutex<remote::RemoteState>>,
+    /// Nodes and links shown, for the reconciliation with the engine
+    graph: Mutex<model::GraphModel>,
+    /// Menu path and status shown at the top
+    header: Mutex<header::Header>,
+    /// Translations of the menus, messages and prompts
+    catalog: Mutex<i18n::Catalog>,
+    /// Spoken focus of the accessibility mode
+    announcer: Mutex<speech::Announcer>,
+    /// Commands waiting for the render thread, with their type
+    commands: Sender<(String, String)>,
 }
 
 impl UI {
@@ -97,16 +163,50 @@ impl UI {
         let menu_stack_size = Arc::new(Mutex::new(0));
         let focused_grid_element = Arc::new(Mutex::new(None));
         let focused_menu_option = Arc::new(Mutex::new(None));
+        let remote = Arc::new(Mutex::new(remote::RemoteState::default()));
+        let (commands, receiver) = channel();
+        Self::spawn_render_thread(receiver, Arc::clone(&message_queue), Arc::clone(&remote));
         
         Self {
             session_name: Mutex::new(None),
+            view_scope: Mutex::new(None),
             message_queue,
             menu_stack_size,
             focused_grid_element,
             focused_menu_option,
+            remote,
+            graph: Mutex::new(model::GraphModel::default()),
+            header: Mutex::new(header::Header::default()),
+            catalog: Mutex::new(i18n::Catalog::default()),
+            announcer: Mutex::new(speech::Announcer::default()),
+            commands,
         }
     }
     
+    /// Deliver the queued commands to the window and the remote frontends
+    /// Commands queued together are delivered in one batch, so callers never wait on the frontends' locks
+    fn spawn_render_thread(
+        receiver: Receiver<(String, String)>,
+        message_queue: Arc<Mutex<VecDeque<String>>>,
+        remote: Arc<Mutex<remote::RemoteState>>,
+    ) {
+        thread::spawn(move || {
+            while let Ok(first) = receiver.recv() {
+                let batch: Vec<(String, String)> = std::iter::once(first).chain(receiver.try_iter()).collect();
+                trace!("Rendering {} UI commands", batch.len());
+                
+                let mut remote = remote.lock().unwrap();
+                for (msg_type, message) in &batch {
+                    remote.publish(msg_type, message);
+                }
+                drop(remote);
+                
+                message_queue.lock().unwrap().extend(batch.into_iter().map(|(_, message)| message));
+            }
+            debug!("UI render thread stopped");
+        });
+    }
+    
     /// Get the message queue for passing to window::run
     pub fn get_message_queue(&self) -> Arc<Mutex<VecDeque<String>>> {
         Arc::clone(&self.message_queue)
@@ -122,6 +222,116 @@ impl UI {
         Arc::clone(&self.focused_menu_option)
     }
 
+    /// Get the commands rebuilding the current view and a channel of the next ones, for remote frontends
+    pub fn subscribe(&self) -> (Vec<String>, Receiver<String>) {
+        self.remote.lock().unwrap().subscribe()
+    }
+
+    /// Get the nodes and links currently shown
+    pub fn graph_model(&self) -> model::GraphModel {
+        self.graph.lock().unwrap().clone()
+    }
+
+    /// Change the header, show it when it changed and print it on the console when the menus or the session changed
+    fn update_header(&self, change: impl FnOnce(&mut header::Header)) -> Result<()> {
+        let mut header = self.header.lock().unwrap();
+        let previous = header.clone();
+        change(&mut header);
+        if *header == previous {
+            return Ok(());
+        }
+        if header.console_changed(&previous) {
+            info!("{}", header.line());
+        }
+        let data = json!({
+            "breadcrumb": header.breadcrumb(),
+            "status": header.status()
+        });
+        drop(header);
+        self.send_command("set_header", data)
+    }
+
+    /// Use the translations of a language, None for the English strings of the code
+    pub fn set_language(&self, language: Option<&str>) {
+        debug!("Setting language: {:?}", language);
+        *self.catalog.lock().unwrap() = language.map(i18n::Catalog::load).unwrap_or_default();
+    }
+
+    /// Record a visit of the focused link, for the navigation to prefer it when coming back to its nodes
+    pub fn update_visits(&self) -> Result<()> {
+        if let Some(GridElement::Link(from_id, to_id, _)) = self.select_grid()? {
+            self.graph.lock().unwrap().visit(&from_id, &to_id);
+        }
+        Ok(())
+    }
+
+    /// Get the navigation ranks of the links, to keep them with the session
+    pub fn link_ranks(&self) -> Vec<model::LinkRank> {
+        self.graph.lock().unwrap().link_ranks()
+    }
+
+    /// Restore the navigation ranks of the links saved with a session
+    pub fn restore_link_ranks(&self, saved: &[model::LinkRank]) -> Result<()> {
+        let ranks = {
+            let mut graph = self.graph.lock().unwrap();
+            graph.restore_ranks(saved);
+            graph.link_ranks()
+        };
+        self.send_link_ranks(&ranks)
+    }
+
+    /// Move a link before (step -1) or after (step 1) its sibling in the navigation order, returning false if it cannot move
+    pub fn move_link(&self, from_id: &str, to_id: &str, step: i64) -> Result<bool> {
+        let ranks = self.graph.lock().unwrap().move_link(from_id, to_id, step);
+        if ranks.is_empty() {
+            return Ok(false);
+        }
+        debug!("Moved link {} -> {} by {}", from_id, to_id, step);
+        self.send_link_ranks(&ranks)?;
+        Ok(true)
+    }
+
+    /// Send the navigation ranks of some links
+    fn send_link_ranks(&self, ranks: &[model::LinkRank]) -> Result<()> {
+        let links: Vec<_> = ranks.iter()
+            .map(|rank| json!({
+                "fromId": rank.from_id,
+                "toId": rank.to_id,
+                "order": rank.order,
+                "visitedLast": rank.visited_last
+            }))
+            .collect();
+        self.send_command("set_link_ranks", json!({
+            "links": links
+        }))
+    }
+
+    /// Start or stop speaking the focus, the menu options and the messages
+    pub fn set_speech(&self, enabled: bool) {
+        debug!("Setting speech: {}", enabled);
+        self.announcer.lock().unwrap().set_enabled(enabled);
+    }
+
+    /// Speak the focused menu option, or the focused grid element when no menu is open, once per focus change
+    pub fn announce_focus(&self) -> Result<()> {
+        let description = if self.is_menu_open() {
+            let option = self.select_menu()?;
+            option.and_then(|option| self.announcer.lock().unwrap().menu_option_label(&option))
+        } else {
+            let element = self.select_grid()?;
+            element.map(|element| self.translate(&self.announcer.lock().unwrap().describe_grid_element(&element)))
+        };
+        if let Some(description) = description {
+            self.announcer.lock().unwrap().announce_focus(description);
+        }
+        Ok(())
+    }
+
+    /// Translate a user-visible string
+    pub fn translate(&self, text: &str) -> String {
+        self.catalog.lock().unwrap().translate(text)
+    }
+
     /// Send a command to the JavaScript UI
     fn send_command(&self, msg_type: &str, data: serde_json::Value) -> Result<()> {
         let message = json!({
@@ -132,10 +342,8 @@ impl UI {
             .context("Failed to serialize UI command")?;
         
         trace!("Queuing UI command: {}", msg_type);
-        self.message_queue
-            .lock()
-            .unwrap()
-            .push_back(msg_str);
+        self.commands.send((msg_type.to_string(), msg_str))
+            .map_err(|_| anyhow::anyhow!("UI render thread stopped"))?;
         Ok(())
     }
 
@@ -150,12 +358,27 @@ impl UI {
             NodeType::PortOut => "portOut",
             NodeType::Context => "context",
         };
+        // The context nodes are named by the app, the other ones by the user or the plugins
+        let label = match node_type {
+            NodeType::Context => self.translate(&name),
+            _ => name,
+        };
+        self.graph.lock().unwrap().create_node(&id, node_type.clone());
+        self.announcer.lock().unwrap().set_node_label(&id, &label);
         
         self.send_command("create_node", json!({
             "id": id,
-            "label": name,
-            "nodeType": node_type_str
-        }))
+            "label": label,
+            "nodeType": node_type_str,
+            "engine": if crate::engine::split::is_monitor_node(&id) { "monitor" } else { "main" }
+        }))?;
+        
+        // A node coming back in view keeps its tag
+        let tag = self.graph.lock().unwrap().tags.get(&id).cloned();
+        match tag {
+            Some((tag, color)) => self.send_node_tag(&id, Some((&tag, color))),
+            None => Ok(()),
+        }
     }
 
 
@@ -171,6 +394,7 @@ impl UI {
             LinkType::PortOut => "portOut",
             LinkType::Virtual => "virtual",
         };
+        self.graph.lock().unwrap().create_link(&from_id, &to_id);
         
         self.send_command("create_link", json!({
             "fromId": from_id,
@@ -179,6 +403,88 @@ impl UI {
         }))
     }
 
+    /// Set the state indicator of a node
+    pub fn set_node_state(&self, id: String, state: NodeState) -> Result<()> {
+        trace!("Node state: {} {:?}", id, state);
+        
+        let state_str = match state {
+            NodeState::Normal => "normal",
+            NodeState::Muted => "muted",
+            NodeState::Soloed => "soloed",
+            NodeState::Silenced => "silenced",
+            NodeState::Bypassed => "bypassed",
+        };
+        
+        self.send_command("set_node_state", json!({
+            "id": id,
+            "state": state_str
+        }))
+    }
+
+    /// Show or hide the clip marker of a node
+    pub fn set_node_clipping(&self, id: String, clipping: bool) -> Result<()> {
+        trace!("Node clipping: {} {}", id, clipping);
+        
+        self.send_command("set_node_clipping", json!({
+            "id": id,
+            "clipping": clipping
+        }))
+    }
+
+    /// Show the latency of a node under it (e.g., "+1.3 ms"), or hide it with None
+    pub fn set_node_latency(&self, id: String, latency: Option<String>) -> Result<()> {
+        trace!("Node latency: {} {:?}", id, latency);
+        
+        self.send_command("set_node_latency", json!({
+            "id": id,
+            "latency": latency
+        }))
+    }
+
+    /// Tag a node, showing it in the color of the tag, or clear its tag with None
+    pub fn set_node_tag(&self, id: String, tag: Option<(&str, usize)>) -> Result<()> {
+        debug!("Node tag: {} {:?}", id, tag);
+        {
+            let mut graph = self.graph.lock().unwrap();
+            match tag {
+                Some((tag, color)) => graph.tags.insert(id.clone(), (tag.to_string(), color)),
+                None => graph.tags.remove(&id),
+            };
+        }
+        self.send_node_tag(&id, tag)
+    }
+
+    /// Get the tags of the nodes, including the ones out of view
+    pub fn node_tags(&self) -> HashMap<String, (String, usize)> {
+        self.graph.lock().unwrap().tags.clone()
+    }
+
+    /// Replace the tags of the nodes, before the nodes of a session are created
+    pub fn restore_node_tags(&self, tags: HashMap<String, (String, usize)>) {
+        debug!("Restoring {} node tags", tags.len());
+        self.graph.lock().unwrap().tags = tags;
+    }
+
+    /// Show the tag of a node in the webview
+    fn send_node_tag(&self, id: &str, tag: Option<(&str, usize)>) -> Result<()> {
+        self.send_command("set_node_tag", json!({
+            "id": id,
+            "tag": tag.map(|(tag, _)| tag),
+            "color": tag.map(|(_, color)| color)
+        }))
+    }
+
+    /// Remove the link between two nodes
+    pub fn remove_link(&self, from_id: String, to_id: String) -> Result<()> {
+        trace!("Removing link: {} -> {}", from_id, to_id);
+        self.graph.lock().unwrap().remove_link(&from_id, &to_id);
+        
+        self.send_command("remove_link", json!({
+            "fromId": from_id,
+            "toId": to_id
+        }))
+    }
+
     /// Insert a node between two nodes (connected by a link)
     /// Creates the node and links from link_from to the node and from the node to link_to
     /// Removes the original link, unless it connects "inputs" to "outputs"
@@ -201,6 +507,8 @@ impl UI {
             NodeType::PortOut => "portOut",
             NodeType::Context => "context",
         };
+        self.graph.lock().unwrap().insert_node(&node_id, node_type.clone(), &link_from, &link_to);
+        self.announcer.lock().unwrap().set_node_label(&node_id, &node_name);
         
         self.send_command("insert_node", json!({
             "id": node_id,
@@ -231,6 +539,23 @@ impl UI {
         }))
     }
     
+    /// Move the grid focus directly to a node
+    pub fn focus_node(&self, id: String) -> Result<()> {
+        trace!("Focus node: {}", id);
+        self.send_command("focus_node", json!({
+            "id": id
+        }))
+    }
+    
+    /// Move the grid focus directly to a link
+    pub fn focus_link(&self, from_id: String, to_id: String) -> Result<()> {
+        trace!("Focus link: {} -> {}", from_id, to_id);
+        self.send_command("focus_link", json!({
+            "fromId": from_id,
+            "toId": to_id
+        }))
+    }
+    
     /// Navigate in the menu (only one direction: up/down)
     pub fn navigate_menu(&self, direction: KnobDirection) -> Result<()> {
         trace!("Navigate menu: {:?}", direction);
@@ -245,6 +570,20 @@ impl UI {
         }))
     }
 
+    /// Jump in a long menu (by first letter or by page)
+    pub fn jump_menu(&self, direction: KnobDirection) -> Result<()> {
+        trace!("Jump menu: {:?}", direction);
+        
+        let direction_str = match direction {
+            KnobDirection::Forward => "forward",
+            KnobDirection::Backward => "backward",
+        };
+        
+        self.send_command("jump_menu", json!({
+            "direction": direction_str
+        }))
+    }
+
     /// Select the currently focused grid element (node or link)
     pub fn select_grid(&self) -> Result<Option<GridElement>> {
         let focused = self.focused_grid_element.lock().unwrap().clone();
@@ -268,22 +607,27 @@ impl UI {
     pub fn open_menu(&self, menu: Menu) -> Result<()> {
         debug!("Opening menu: {}", menu.label);
         
-        let options: Vec<_> = menu.options.iter()
-            .map(|opt| json!({
-                "id": opt.id,
-                "label": opt.label
+        let labels: Vec<(String, String)> = menu.options.iter()
+            .map(|opt| (opt.id.clone(), self.translate(&opt.label)))
+            .collect();
+        let options: Vec<_> = labels.iter()
+            .map(|(id, label)| json!({
+                "id": id,
+                "label": label
             }))
             .collect();
+        let label = self.translate(&menu.label);
+        self.announcer.lock().unwrap().open_menu(labels.into_iter().collect());
         
         self.send_command("open_menu", json!({
             "id": menu.id,
-            "label": menu.label,
+            "label": label,
             "options": options
         }))?;
         
         // Increment menu stack size
         *self.menu_stack_size.lock().unwrap() += 1;
-        Ok(())
+        self.update_header(|header| header.menu_path.push(label))
     }
 
     /// Close the top-most menu
@@ -293,7 +637,12 @@ impl UI {
         let mut size = self.menu_stack_size.lock().unwrap();
         if *size > 0 {
             *size -= 1;
-            self.send_command("close_menu", json!({}))
+            drop(size);
+            self.announcer.lock().unwrap().close_menu();
+            self.send_command("close_menu", json!({}))?;
+            self.update_header(|header| {
+                header.menu_path.pop();
+            })
         } else {
             Ok(())
         }
@@ -318,7 +667,9 @@ impl UI {
         trace!("Closing all menus");
         
         *self.menu_stack_size.lock().unwrap() = 0;
-        self.send_command("close_all_menus", json!({}))
+        self.announcer.lock().unwrap().close_all_menus();
+        self.send_command("close_all_menus", json!({}))?;
+        self.update_header(|header| header.menu_path.clear())
     }
 
     /// Get the current menu stack size
@@ -326,35 +677,280 @@ impl UI {
         *self.menu_stack_size.lock().unwrap()
     }
 
+    /// Show a short message to the user
+    pub fn show_message(&self, message: &str) -> Result<()> {
+        trace!("Message: {}", message);
+        let message = self.translate(message);
+        self.announcer.lock().unwrap().say(&message);
+        self.send_command("prompt", json!({
+            "message": message
+        }))
+    }
+
     /// Prompt user to turn the main selection knob
     pub fn prompt_turn_selection_knob(&self) -> Result<()> {
         trace!("Prompt: turn selection knob");
-        self.send_command("prompt", json!({
-            "message": "Turn the main selection knob"
-        }))
+        self.show_message("Turn the main selection knob")
     }
 
     /// Prompt user to turn the secondary knob
     pub fn prompt_turn_secondary_knob(&self) -> Result<()> {
         trace!("Prompt: turn secondary knob");
-        self.send_command("prompt", json!({
-            "message": "Turn the secondary knob"
-        }))
+        self.show_message("Turn the secondary knob")
     }
 
     /// Prompt user to press the main selection button
     pub fn prompt_press_selection_button(&self) -> Result<()> {
         trace!("Prompt: press selection button");
-        self.send_command("prompt", json!({
-            "message": "Press the main selection button"
-        }))
+        self.show_message("Press the main selection button")
     }
 
     /// Prompt user to press the main back button
     pub fn prompt_press_back_button(&self) -> Result<()> {
         trace!("Prompt: press back button");
-        self.send_command("prompt", json!({
-            "message": "Press the main back button"
+        self.show_message("Press the main back button")
+    }
+    
+    /// Show performance mode with the parameters mapped to the knobs
+    pub fn show_performance(&self, bank_label: String, parameters: Vec<Option<ParameterDisplay>>) -> Result<()> {
+        trace!("Show performance: {}", bank_label);
+        
+        let parameters: Vec<_> = parameters.iter()
+            .map(|parameter| match parameter {
+                Some(p) => json!({
+                    "name": p.name,
+                    "title": p.title,
+                    "value": p.value
+                }),
+                None => serde_json::Value::Null,
+            })
+            .collect();
+        
+        self.send_command("show_performance", json!({
+            "bank": self.translate(&bank_label),
+            "parameters": parameters
+        }))
+    }
+
+    /// Show a text being edited with the knobs, highlighting the character at the cursor
+    pub fn show_text_entry(&self, title: String, text: String, cursor: usize) -> Result<()> {
+        trace!("Show text entry: {} ({})", text, cursor);
+        self.send_command("show_text_entry", json!({
+            "title": self.translate(&title),
+            "text": text,
+            "cursor": cursor
+        }))
+    }
+
+    /// Hide the text entry
+    pub fn hide_text_entry(&self) -> Result<()> {
+        trace!("Hide text entry");
+        self.send_command("hide_text_entry", json!({}))
+    }
+
+    /// Remove a node and all its links
+    pub fn remove_node(&self, id: String) -> Result<()> {
+        debug!("Removing node: {}", id);
+        self.graph.lock().unwrap().remove_node(&id);
+        self.announcer.lock().unwrap().remove_node(&id);
+        self.send_command("remove_node", json!({
+            "id": id
+        }))
+    }
+
+    /// Change the label of a node
+    pub fn set_node_label(&self, id: String, label: String) -> Result<()> {
+        debug!("Setting label of node {} to {}", id, label);
+        self.announcer.lock().unwrap().set_node_label(&id, &label);
+        self.send_command("set_node_label", json!({
+            "id": id,
+            "label": label
+        }))
+    }
+
+    /// Leave performance mode
+    pub fn hide_performance(&self) -> Result<()> {
+        trace!("Hide performance");
+        self.send_command("hide_performance", json!({}))
+    }
+    
+    /// Display the current tempo (None hides it)
+    pub fn set_tempo(&self, bpm: Option<f32>) -> Result<()> {
+        trace!("Set tempo: {:?}", bpm);
+        self.send_command("set_tempo", json!({
+            "bpm": bpm
+        }))?;
+        self.update_header(|header| header.bpm = bpm)
+    }
+    
+    /// Display the elapsed recording time (None hides it)
+    pub fn set_recording(&self, elapsed: Option<Duration>) -> Result<()> {
+        trace!("Set recording: {:?}", elapsed);
+        self.send_command("set_recording", json!({
+            "seconds": elapsed.map(|e| e.as_secs())
+        }))
+    }
+    
+    /// Apply a color theme to the UI
+    pub fn set_theme(&self, theme: String) -> Result<()> {
+        debug!("Set theme: {}", theme);
+        self.send_command("set_theme", json!({
+            "theme": theme
+        }))
+    }
+    
+    /// Zoom the UI and switch the compact layout for small screens
+    pub fn set_display(&self, scale: f32, compact: bool) -> Result<()> {
+        debug!("Set display: scale {}, compact {}", scale, compact);
+        self.send_command("set_display", json!({
+            "scale": scale,
+            "compact": compact
+        }))
+    }
+    
+    /// Show or hide the log panel
+    pub fn set_log_panel(&self, visible: bool) -> Result<()> {
+        debug!("Log panel visible: {}", visible);
+        self.send_command("set_log_panel", json!({
+            "visible": visible
+        }))
+    }
+    
+    /// Add entries to the log panel, as (level, message) pairs
+    pub fn append_log(&self, entries: Vec<(String, String)>) -> Result<()> {
+        let entries: Vec<_> = entries.iter()
+            .map(|(level, message)| json!({
+                "level": level,
+                "message": message
+            }))
+            .collect();
+        self.send_command("append_log", json!({
+            "entries": entries
+        }))
+    }
+    
+    /// Show or hide the inspector of the engine protocol
+    pub fn set_inspector_panel(&self, visible: bool) -> Result<()> {
+        debug!("Inspector visible: {}", visible);
+        self.send_command("set_inspector_panel", json!({
+            "visible": visible
+        }))
+    }
+    
+    /// Add protocol messages to the inspector, as (time, outgoing, text) triples
+    pub fn append_protocol_messages(&self, messages: Vec<(String, bool, String)>) -> Result<()> {
+        let messages: Vec<_> = messages.iter()
+            .map(|(time, outgoing, text)| json!({
+                "time": time,
+                "direction": if *outgoing { "out" } else { "in" },
+                "text": text
+            }))
+            .collect();
+        self.send_command("append_protocol_messages", json!({
+            "messages": messages
+        }))
+    }
+    
+    /// Show the raw engine state read last in the inspector
+    pub fn set_inspector_state(&self, time: &str, text: &str) -> Result<()> {
+        self.send_command("set_inspector_state", json!({
+            "time": time,
+            "text": text
+        }))
+    }
+    
+    /// Display the DSP load meter, in percent
+    pub fn set_dsp_load(&self, percent: u32) -> Result<()> {
+        trace!("Set DSP load: {}%", percent);
+        self.send_command("set_dsp_load", json!({
+            "percent": percent
+        }))?;
+        self.update_header(|header| header.dsp_load = Some(percent))
+    }
+    
+    /// Display the levels of the spectrum bands in dB, None hides the analyzer
+    pub fn set_spectrum(&self, bands: Option<Vec<f32>>) -> Result<()> {
+        self.send_command("set_spectrum", json!({
+            "bands": bands
+        }))
+    }
+    
+    /// Display the number of xruns (0 hides it)
+    pub fn set_xruns(&self, count: usize) -> Result<()> {
+        trace!("Set xruns: {}", count);
+        self.send_command("set_xruns", json!({
+            "count": count
+        }))
+    }
+    
+    /// Show whether the engine answers, with a green or red dot
+    pub fn set_engine_status(&self, connected: bool) -> Result<()> {
+        trace!("Set engine status: {}", connected);
+        self.send_command("set_engine_status", json!({
+            "connected": connected
+        }))
+    }
+    
+    /// Display the audio settings in the status bar
+    pub fn set_audio_status(&self, sample_rate: usize, buffer_size: u32, latency_ms: f32) -> Result<()> {
+        trace!("Set audio status: {} Hz, {} frames, {:.1} ms", sample_rate, buffer_size, latency_ms);
+        self.send_command("set_audio_status", json!({
+            "sample_rate": sample_rate,
+            "buffer_size": buffer_size,
+            "latency_ms": latency_ms
+        }))
+    }
+    
+    /// Display the devices a loaded session is waiting for (empty hides it)
+    pub fn set_waiting_devices(&self, names: Vec<String>) -> Result<()> {
+        trace!("Set waiting devices: {:?}", names);
+        self.send_command("set_waiting", json!({
+            "names": names
+        }))
+    }
+    
+    /// Display the upcoming item of the set list with its position (None hides it)
+    pub fn set_up_next(&self, label: Option<String>) -> Result<()> {
+        trace!("Set up next: {:?}", label);
+        self.send_command("set_up_next", json!({
+            "label": label
+        }))
+    }
+    
+    /// Show a spinner with a message already translated, or hide it with None
+    pub fn set_busy(&self, message: Option<&str>) -> Result<()> {
+        trace!("Set busy: {:?}", message);
+        self.send_command("set_busy", json!({
+            "message": message
+        }))
+    }
+    
+    /// Show the busy indicator until the returned guard is dropped, so that the app does not look frozen
+    pub fn busy(self: &Arc<Self>, message: &str) -> Result<Busy> {
+        info!("{}...", message);
+        self.set_busy(Some(&self.translate(message)))?;
+        Ok(Busy {
+            ui: Arc::clone(self),
+            message: message.to_string(),
+        })
+    }
+    
+    /// Show the startup screen with the step in progress, or hide it with None once the station is up
+    pub fn set_startup(&self, step: Option<&str>) -> Result<()> {
+        if let Some(step) = step {
+            info!("{}...", step);
+        }
+        self.send_command("set_startup", json!({
+            "step": step.map(|step| self.translate(step)),
+            "failed": false
+        }))
+    }
+    
+    /// Show on the startup screen why the station could not start
+    pub fn set_startup_failed(&self, error: &str) -> Result<()> {
+        self.send_command("set_startup", json!({
+            "step": self.translate(&format!("Startup failed: {}", error)),
+            "failed": true
         }))
     }
     
@@ -367,8 +963,25 @@ impl UI {
     /// Set the current session name (mnemonic)
     pub fn set_session_name(&self, name: String) -> Result<()> {
         debug!("Setting session name: {}", name);
-        *self.session_name.lock().unwrap() = Some(name);
-        Ok(())
+        *self.session_name.lock().unwrap() = Some(name.clone());
+        self.update_header(|header| header.session = Some(name))
+    }
+
+    /// Show whether the session has unsaved changes next to its name
+    pub fn set_session_dirty(&self, dirty: bool) -> Result<()> {
+        self.update_header(|header| header.dirty = dirty)
+    }
+
+    /// Get the path of the group shown, None for the main graph
+    pub fn view_scope(&self) -> Option<String> {
+        self.view_scope.lock().unwrap().clone()
+    }
+
+    /// Set the group shown, None for the main graph
+    /// The nodes are not changed, the caller brings them in line with the new scope
+    pub fn set_view_scope(&self, scope: Option<String>) {
+        debug!("Setting view scope: {:?}", scope);
+        *self.view_scope.lock().unwrap() = scope;
     }
 }
 
@@ -377,3 +990,50 @@ impl Default for UI {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands_reach_window_queue_in_order() {
+        let ui = UI::new();
+        ui.set_tempo(Some(120.0)).unwrap();
+        ui.commit().unwrap();
+
+        let queue = ui.get_message_queue();
+        for _ in 0..100 {
+            if queue.lock().unwrap().len() == 3 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let messages: Vec<String> = queue.lock().unwrap().drain(..).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].contains("set_tempo"));
+        // The tempo also goes to the header status
+        assert!(messages[1].contains("set_header"));
+        assert!(messages[2].contains("commit"));
+    }
+
+    #[test]
+    fn test_busy_indicator_hides_when_dropped() {
+        let ui = Arc::new(UI::new());
+        {
+            let busy = ui.busy("Loading session").unwrap();
+            busy.update("Building the graph");
+        }
+
+        let queue = ui.get_message_queue();
+        for _ in 0..100 {
+            if queue.lock().unwrap().len() == 3 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let messages: Vec<String> = queue.lock().unwrap().drain(..).collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[1].contains("Loading session: Building the graph"));
+        assert!(messages[2].contains(r#""message":null"#));
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::NodeType;
+
+/// Navigation order of a link among the links of its start node, and its last visit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkRank {
+    pub from_id: String,
+    pub to_id: String,
+    pub order: i64,
+    /// Visit count when the link was last focused, 0 if never
+    pub visited_last: i64,
+}
+
+/// Nodes and links sent to the frontends, kept to compare the view with the engine
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphModel {
+    /// Node types by node id
+    pub nodes: HashMap<String, NodeType>,
+    /// Links as (from_id, to_id)
+    pub links: HashSet<(String, String)>,
+    /// Navigation rank of each link
+    pub ranks: HashMap<(String, String), LinkRank>,
+    /// Tag and color of the tagged nodes, kept while they are out of view
+    pub tags: HashMap<String, (String, usize)>,
+    /// Number of link visits so far
+    visits: i64,
+}
+
+impl GraphModel {
+    /// Add a node
+    pub fn create_node(&mut self, id: &str, node_type: NodeType) {
+        self.nodes.insert(id.to_string(), node_type);
+    }
+
+    /// Add a link, ordered after the existing ones
+    pub fn create_link(&mut self, from_id: &str, to_id: &str) {
+        let key = (from_id.to_string(), to_id.to_string());
+        self.links.insert(key.clone());
+        let order = self.next_order();
+        self.ranks.entry(key).or_insert_with(|| LinkRank {
+            from_id: from_id.to_string(),
+            to_id: to_id.to_string(),
+            order,
+            visited_last: 0,
+        });
+    }
+
+    /// Remove a link
+    pub fn remove_link(&mut self, from_id: &str, to_id: &str) {
+        let key = (from_id.to_string(), to_id.to_string());
+        self.links.remove(&key);
+        self.ranks.remove(&key);
+    }
+
+    /// Remove a node and all its links
+    pub fn remove_node(&mut self, id: &str) {
+        self.nodes.remove(id);
+        self.links.retain(|(from_id, to_id)| from_id != id && to_id != id);
+        self.ranks.retain(|(from_id, to_id), _| from_id != id && to_id != id);
+    }
+
+    /// Get the order following the ones of the existing links
+    fn next_order(&self) -> i64 {
+        self.ranks.values().map(|rank| rank.order).max().unwrap_or(0) + 1
+    }
+
+    /// Record a visit of a link, unless it is the one visited last
+    pub fn visit(&mut self, from_id: &str, to_id: &str) {
+        let visits = self.visits;
+        if let Some(rank) = self.ranks.get_mut(&(from_id.to_string(), to_id.to_string())) {
+            if visits == 0 || rank.visited_last != visits {
+                self.visits += 1;
+                rank.visited_last = self.visits;
+            }
+        }
+    }
+
+    /// Get the link before (step -1) or after (step 1) a link among the links leaving the same node
+    pub fn sibling(&self, from_id: &str, to_id: &str, step: i64) -> Option<&LinkRank> {
+        let mut siblings: Vec<&LinkRank> = self.ranks.values().filter(|rank| rank.from_id == from_id).collect();
+        siblings.sort_by_key(|rank| rank.order);
+        let index = siblings.iter().position(|rank| rank.to_id == to_id)? as i64 + step;
+        siblings.get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// Swap the order of a link with its sibling before (step -1) or after (step 1), returning the changed ranks
+    pub fn move_link(&mut self, from_id: &str, to_id: &str, step: i64) -> Vec<LinkRank> {
+        let Some(sibling) = self.sibling(from_id, to_id, step).cloned() else {
+            return Vec::new();
+        };
+        let key = (from_id.to_string(), to_id.to_string());
+        let sibling_key = (sibling.from_id.clone(), sibling.to_id.clone());
+        let order = self.ranks[&key].order;
+        if let Some(rank) = self.ranks.get_mut(&sibling_key) {
+            rank.order = order;
+        }
+        if let Some(rank) = self.ranks.get_mut(&key) {
+            rank.order = sibling.order;
+        }
+        vec![self.ranks[&key].clone(), self.ranks[&sibling_key].clone()]
+    }
+
+    /// Get the ranks of the links, in their order
+    pub fn link_ranks(&self) -> Vec<LinkRank> {
+        let mut ranks: Vec<LinkRank> = self.ranks.values().cloned().collect();
+        ranks.sort_by_key(|rank| rank.order);
+        ranks
+    }
+
+    /// Restore saved ranks on the links that still exist, the other links following them in their current order
+    pub fn restore_ranks(&mut self, saved: &[LinkRank]) {
+        let mut unsaved: Vec<LinkRank> = Vec::new();
+        for rank in self.ranks.values_mut() {
+            match saved.iter().find(|s| s.from_id == rank.from_id && s.to_id == rank.to_id) {
+                Some(saved) => {
+                    rank.order = saved.order;
+                    rank.visited_last = saved.visited_last;
+                }
+                None => unsaved.push(rank.clone()),
+            }
+        }
+        unsaved.sort_by_key(|rank| rank.order);
+        let last_saved = saved.iter().map(|rank| rank.order).max().unwrap_or(0);
+        for (index, rank) in unsaved.iter().enumerate() {
+            if let Some(rank) = self.ranks.get_mut(&(rank.from_id.clone(), rank.to_id.clone())) {
+                rank.order = last_saved + 1 + index as i64;
+                rank.visited_last = 0;
+            }
+        }
+        self.visits = self.ranks.values().map(|rank| rank.visited_last).max().unwrap_or(0);
+    }
+
+    /// Insert a node on a link, like the frontends do
+    pub fn insert_node(&mut self, id: &str, node_type: NodeType, link_from: &str, link_to: &str) {
+        self.create_node(id, node_type);
+        if !(link_from == "inputs" && link_to == "outputs") {
+            self.remove_link(link_from, link_to);
+        }
+        self.create_link(link_from, id);
+        self.create_link(id, link_to);
+    }
+
+    /// Check whether a node is one of the fixed context nodes (inputs and outputs)
+    pub fn is_context_node(&self, id: &str) -> bool {
+        self.nodes.get(id) == Some(&NodeType::Context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_remove_node() {
+        let mut model = GraphModel::default();
+        model.create_node("inputs", NodeType::Context);
+        model.create_node("outputs", NodeType::Context);
+        model.create_link("inputs", "outputs");
+
+        model.insert_node("a", NodeType::Normal, "inputs", "outputs");
+        model.insert_node("b", NodeType::Normal, "a", "outputs");
+        assert!(model.links.contains(&("inputs".to_string(), "outputs".to_string())));
+        assert!(model.links.contains(&("a".to_string(), "b".to_string())));
+        assert!(!model.links.contains(&("a".to_string(), "outputs".to_string())));
+
+        model.remove_node("b");
+        assert!(!model.nodes.contains_key("b"));
+        assert_eq!(model.links.len(), 2);
+        assert!(model.is_context_node("inputs"));
+        assert!(!model.is_context_node("a"));
+    }
+
+    #[test]
+    fn test_link_ranks() {
+        let mut model = GraphModel::default();
+        model.create_link("a", "b");
+        model.create_link("a", "c");
+        model.visit("a", "c");
+        model.visit("a", "c");
+        model.visit("a", "b");
+
+        let saved = model.link_ranks();
+        assert_eq!(saved.iter().map(|rank| (rank.order, rank.visited_last)).collect::<Vec<_>>(), vec![(1, 2), (2, 1)]);
+
+        // A new run creates the links again, with a link added since
+        let mut model = GraphModel::default();
+        model.create_link("a", "d");
+        model.create_link("a", "c");
+        model.create_link("a", "b");
+        model.restore_ranks(&saved);
+        let ranks: Vec<(String, i64, i64)> = model.link_ranks().into_iter()
+            .map(|rank| (rank.to_id, rank.order, rank.visited_last))
+            .collect();
+        assert_eq!(ranks, vec![("b".to_string(), 1, 2), ("c".to_string(), 2, 1), ("d".to_string(), 3, 0)]);
+    }
+
+    #[test]
+    fn test_move_link() {
+        let mut model = GraphModel::default();
+        model.create_link("a", "b");
+        model.create_link("x", "y");
+        model.create_link("a", "c");
+        assert_eq!(model.sibling("a", "b", 1).map(|rank| rank.to_id.as_str()), Some("c"));
+        assert!(model.sibling("a", "b", -1).is_none());
+
+        // Moving skips the links of other nodes
+        assert_eq!(model.move_link("a", "c", -1).len(), 2);
+        assert_eq!(model.sibling("a", "c", 1).map(|rank| rank.to_id.as_str()), Some("b"));
+        assert!(model.move_link("a", "c", -1).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use log::{debug, info, warn};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+use crate::controller::repl::ReplCommand;
+use crate::ui::{assets, GridElement, MenuOptionElement, UI};
+
+/// Commands building the graph, replayed to frontends connecting later
+const GRAPH_COMMANDS: [&str; 10] = [
+    "create_node", "create_link", "insert_node", "remove_link",
+    "remove_node", "set_node_label", "set_node_state", "set_node_tag", "set_link_ranks", "commit",
+];
+
+/// Commands of which frontends connecting later only need the latest one
+const STATUS_COMMANDS: [&str; 16] = [
+    "set_theme", "set_display", "set_log_panel", "set_tempo", "set_recording", "set_audio_status",
+    "set_dsp_load", "set_xruns", "set_waiting", "set_up_next", "set_header", "set_busy", "set_startup",
+    "set_inspector_panel", "set_inspector_state", "set_engine_status",
+];
+
+/// Interval at which a connection checks for UI commands, focus changes and frontend messages
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// UI commands seen so far and the frontends receiving them
+#[derive(Default)]
+pub struct RemoteState {
+    graph_history: Vec<String>,
+    latest_status: HashMap<String, String>,
+    subscribers: Vec<Sender<String>>,
+}
+
+impl RemoteState {
+    /// Remember a UI command and forward it to the connected frontends
+    pub fn publish(&mut self, msg_type: &str, message: &str) {
+        if GRAPH_COMMANDS.contains(&msg_type) {
+            self.graph_history.push(message.to_string());
+        } else if STATUS_COMMANDS.contains(&msg_type) {
+            self.latest_status.insert(msg_type.to_string(), message.to_string());
+        }
+        self.subscribers.retain(|subscriber| subscriber.send(message.to_string()).is_ok());
+    }
+
+    /// Get the commands rebuilding the current view, and a channel for the next ones
+    pub fn subscribe(&mut self) -> (Vec<String>, Receiver<String>) {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        let snapshot = self.graph_history.iter()
+            .chain(self.latest_status.values())
+            .cloned()
+            .collect();
+        (snapshot, receiver)
+    }
+}
+
+/// Describe a focus change for the frontends
+fn focus_message(grid: &Option<GridElement>, menu: &Option<MenuOptionElement>) -> String {
+    let grid = match grid {
+        Some(GridElement::Node(id)) => json!({ "kind": "node", "id": id }),
+        Some(GridElement::Link(from_id, to_id, _)) => json!({ "kind": "link", "fromId": from_id, "toId": to_id }),
+        None => serde_json::Value::Null,
+    };
+    let menu = match menu {
+        Some(option) => json!({ "menuId": option.menu_id, "optionId": option.option_id }),
+        None => serde_json::Value::Null,
+    };
+    json!({ "type": "focus", "data": { "grid": grid, "menu": menu } }).to_string()
+}
+
+/// Parse a message of a frontend into a controller command
+/// Frontends send {"type": "command", "data": {"line": "nav fwd"}} using the stdin command syntax
+fn parse_frontend_message(text: &str) -> Result<ReplCommand> {
+    let message: serde_json::Value = serde_json::from_str(text).context("Invalid JSON")?;
+    let line = message.get("data")
+        .and_then(|d| d.get("line"))
+        .and_then(|l| l.as_str())
+        .filter(|_| message.get("type").and_then(|t| t.as_str()) == Some("command"))
+        .ok_or_else(|| anyhow::anyhow!("Expected a command message"))?;
+    ReplCommand::parse(line)
+}
+
+/// Serve a connected frontend until it goes away
+/// Without a command sender the frontend only watches, its commands are dropped
+fn serve(mut socket: WebSocket<TcpStream>, ui: Arc<UI>, commands: Option<Sender<ReplCommand>>) -> Result<()> {
+    let (snapshot, updates) = ui.subscribe();
+    for message in snapshot {
+        socket.send(Message::Text(message))?;
+    }
+
+    let focused_grid = ui.get_focused_grid_element();
+    let focused_menu = ui.get_focused_menu_option();
+    let mut last_focus = String::new();
+
+    loop {
+        for message in updates.try_iter() {
+            socket.write(Message::Text(message))?;
+        }
+        let focus = focus_message(&focused_grid.lock().unwrap(), &focused_menu.lock().unwrap());
+        if focus != last_focus {
+            socket.write(Message::Text(focus.clone()))?;
+            last_focus = focus;
+        }
+        socket.flush()?;
+
+        // The read timeout paces the loop
+        match socket.read() {
+            Ok(Message::Text(text)) => match parse_frontend_message(&text) {
+                Ok(command) => match &commands {
+                    Some(commands) => {
+                        debug!("Command from frontend: {:?}", command);
+                        if commands.send(command).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    None => debug!("Dropping command from read-only frontend: {:?}", command),
+                },
+                Err(e) => {
+                    let error = json!({ "type": "error", "data": { "message": format!("{:#}", e) } });
+                    socket.send(Message::Text(error.to_string()))?;
+                }
+            },
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Check whether a connection asks for a WebSocket upgrade, without consuming the request
+fn is_websocket_request(stream: &TcpStream) -> bool {
+    let mut head = [0u8; 2048];
+    let length = stream.peek(&mut head).unwrap_or(0);
+    String::from_utf8_lossy(&head[..length]).to_ascii_lowercase().contains("upgrade: websocket")
+}
+
+/// Answer a plain HTTP request with an embedded UI file
+fn serve_page(mut stream: TcpStream) -> Result<()> {
+    let mut head = [0u8; 2048];
+    let length = stream.read(&mut head)?;
+    let request = String::from_utf8_lossy(&head[..length]);
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let path = path.split('?').next().unwrap_or(path);
+
+    match assets::get(path) {
+        Some((content_type, content)) => {
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", content_type, content.len())?;
+            stream.write_all(content)?;
+        }
+        None => {
+            debug!("Web UI file not found: {}", path);
+            write!(stream, "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        }
+    }
+    stream.flush()?;
+    Ok(())
+}
+
+/// Listen for frontends on a WebSocket address (e.g. "127.0.0.1:9001")
+/// Frontends receive the UI commands as JSON and can drive the controller with commands, unless no sender is given.
+/// With `web_ui`, plain HTTP requests get the UI pages, which then connect over the same address
+pub fn start(address: &str, ui: Arc<UI>, commands: Option<Sender<ReplCommand>>, web_ui: bool) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .with_context(|| format!("Failed to listen on {}", address))?;
+    if web_ui && commands.is_none() {
+        info!("Read-only web UI served on http://{}", address);
+    } else if web_ui {
+        info!("Web UI served on http://{}", address);
+    } else {
+        info!("WebSocket API listening on ws://{}", address);
+    }
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Failed to accept frontend connection: {}", e);
+                    continue;
+                }
+            };
+            let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+            let ui = Arc::clone(&ui);
+            let commands = commands.clone();
+
+            thread::spawn(move || {
+                if web_ui && !is_websocket_request(&stream) {
+                    if let Err(e) = serve_page(stream) {
+                        warn!("Failed to serve web UI to {}: {}", peer, e);
+                    }
+                    return;
+                }
+                let socket = match tungstenite::accept(stream) {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        warn!("WebSocket handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                };
+                if let Err(e) = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL)) {
+                    warn!("Failed to configure frontend connection: {}", e);
+                    return;
+                }
+                info!("Frontend connected from {}", peer);
+                match serve(socket, ui, commands) {
+                    Ok(()) => info!("Frontend {} disconnected", peer),
+                    Err(e) => warn!("Frontend {} dropped: {}", peer, e),
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_late_subscriber_gets_graph_and_latest_status() {
+        let mut state = RemoteState::default();
+        state.publish("create_node", "node");
+        state.publish("set_tempo", "120");
+        state.publish("set_tempo", "128");
+        state.publish("navigate_grid", "nav");
+
+        let (snapshot, updates) = state.subscribe();
+        assert_eq!(snapshot, vec!["node".to_string(), "128".to_string()]);
+
+        state.publish("prompt", "hello");
+        assert_eq!(updates.try_recv().unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_parse_frontend_message() {
+        assert_eq!(
+            parse_frontend_message(r#"{"type": "command", "data": {"line": "select"}}"#).unwrap(),
+            ReplCommand::Select
+        );
+        assert!(parse_frontend_message(r#"{"type": "hello"}"#).is_err());
+        assert!(parse_frontend_message("nav fwd").is_err());
+    }
+}
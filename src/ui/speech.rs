@@ -0,0 +1,148 @@
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+use super::{GridElement, MenuOptionElement};
+
+/// Speech-dispatcher client speaking the announcements
+const SPEECH_COMMAND: &str = "spd-say";
+
+/// Announcements of the accessibility mode, speaking the focused element and menu option
+/// The labels are tracked even when speech is off, so that it can be turned on at any time
+#[derive(Default)]
+pub struct Announcer {
+    /// Texts waiting for the speaker thread, None when speech is off
+    speaker: Option<Sender<String>>,
+    node_labels: HashMap<String, String>,
+    /// Option labels of the open menus, the top-most last
+    menu_labels: Vec<HashMap<String, String>>,
+    /// Focus spoken last, not repeated while it does not move
+    spoken: Option<String>,
+}
+
+impl Announcer {
+    /// Start or stop speaking
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled == self.speaker.is_some() {
+            return;
+        }
+        self.speaker = enabled.then(spawn_speaker);
+        self.spoken = None;
+    }
+
+    /// Keep the label of a node
+    pub fn set_node_label(&mut self, id: &str, label: &str) {
+        self.node_labels.insert(id.to_string(), label.to_string());
+    }
+
+    /// Forget a removed node
+    pub fn remove_node(&mut self, id: &str) {
+        self.node_labels.remove(id);
+    }
+
+    /// Keep the option labels of an opened menu
+    pub fn open_menu(&mut self, labels: HashMap<String, String>) {
+        self.menu_labels.push(labels);
+    }
+
+    /// Forget the top-most menu
+    pub fn close_menu(&mut self) {
+        self.menu_labels.pop();
+    }
+
+    /// Forget all menus
+    pub fn close_all_menus(&mut self) {
+        self.menu_labels.clear();
+    }
+
+    /// Get the spoken name of a node, its id when it has no label
+    fn node_label(&self, id: &str) -> String {
+        self.node_labels.get(id).cloned()
+            .unwrap_or_else(|| id.rsplit('/').next().unwrap_or(id).to_string())
+    }
+
+    /// Describe a focused grid element, links naming their ends
+    pub fn describe_grid_element(&self, element: &GridElement) -> String {
+        match element {
+            GridElement::Node(id) => self.node_label(id),
+            GridElement::Link(from_id, to_id, _) => format!("{} to {}", self.node_label(from_id), self.node_label(to_id)),
+        }
+    }
+
+    /// Get the label of an option of the top-most menu
+    pub fn menu_option_label(&self, option: &MenuOptionElement) -> Option<String> {
+        self.menu_labels.last()?.get(&option.option_id).cloned()
+    }
+
+    /// Speak a focus description, unless it was the last one spoken
+    pub fn announce_focus(&mut self, description: String) {
+        if self.speaker.is_none() || self.spoken.as_ref() == Some(&description) {
+            return;
+        }
+        self.say(&description);
+        self.spoken = Some(description);
+    }
+
+    /// Speak a text, cutting the one being spoken
+    pub fn say(&self, text: &str) {
+        if let Some(speaker) = &self.speaker {
+            let _ = speaker.send(text.to_string());
+        }
+    }
+}
+
+/// Start the thread running the speech command, only the latest text being spoken when they come fast
+fn spawn_speaker() -> Sender<String> {
+    let (sender, receiver) = channel::<String>();
+    thread::spawn(move || {
+        while let Ok(text) = receiver.recv() {
+            let text = receiver.try_iter().last().unwrap_or(text);
+            if let Err(e) = speak(&text) {
+                warn!("Speech stopped: {}", e);
+                break;
+            }
+        }
+        debug!("Speaker thread stopped");
+    });
+    sender
+}
+
+/// Cut the current speech and say a text
+fn speak(text: &str) -> Result<()> {
+    for args in [vec!["--cancel"], vec![text]] {
+        let status = Command::new(SPEECH_COMMAND)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| anyhow!("Cannot run {}: {}. Make sure speech-dispatcher is installed.", SPEECH_COMMAND, e))?;
+        if !status.success() {
+            debug!("{} exited with {}", SPEECH_COMMAND, status);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::LinkType;
+
+    #[test]
+    fn test_descriptions() {
+        let mut announcer = Announcer::default();
+        announcer.set_node_label("ingen:/main/delay_1", "Delay");
+        let link = GridElement::Link("ingen:/main/delay_1".to_string(), "ingen:/main/reverb_1".to_string(), LinkType::Normal);
+        assert_eq!(announcer.describe_grid_element(&link), "Delay to reverb_1");
+
+        announcer.open_menu(HashMap::from([("load".to_string(), "Load".to_string())]));
+        let option = MenuOptionElement { menu_id: "file".to_string(), option_id: "load".to_string() };
+        assert_eq!(announcer.menu_option_label(&option), Some("Load".to_string()));
+        announcer.close_menu();
+        assert_eq!(announcer.menu_option_label(&option), None);
+    }
+}
@@ -0,0 +1,181 @@
+// Pan, zoom and minimap of the graph
+// While the graph fits in the window it stays where the grid puts it. A larger graph follows the focus,
+// which is kept at the anchor of the page as the navigation moves around. Dragging, pinching and the
+// mouse wheel move the view by hand until the focus moves again, a double click resets the view.
+function createView(svgElement, minimapElement) {
+    const svgNS = "http://www.w3.org/2000/svg";
+    const minZoom = 0.25;
+    const maxZoom = 4;
+    const margin = 40; // Space kept around the graph when checking whether it fits
+
+    // Graph point shown at the anchor of the page, where the grid centers its boxes
+    const viewBox = svgElement.getAttribute('viewBox').split(' ').map(parseFloat);
+    const anchor = { x: viewBox[2] / 2, y: viewBox[3] / 2 };
+
+    let zoom = 1;
+    let target = { ...anchor }; // Graph point at the anchor
+    let pan = { x: 0, y: 0 }; // Offset of the target moved by hand
+    let rects = []; // Boxes of the graph: { x, y, width, height, focused }
+    let focusPoint = null;
+    let pointers = new Map(); // pointer id -> { x, y }
+
+    // Group holding the graph, moved and scaled as a whole
+    const content = document.createElementNS(svgNS, 'g');
+    content.setAttribute('id', 'view');
+    svgElement.firstElementChild.firstElementChild.appendChild(content);
+
+    const minimap = document.createElementNS(svgNS, 'svg');
+    const minimapBoxes = document.createElementNS(svgNS, 'g');
+    const minimapViewport = document.createElementNS(svgNS, 'rect');
+    minimapViewport.setAttribute('class', 'viewport');
+    minimap.appendChild(minimapBoxes);
+    minimap.appendChild(minimapViewport);
+    minimapElement.appendChild(minimap);
+
+    // Get the size of the window in graph units at the current zoom
+    function getVisibleSize() {
+        const ctm = svgElement.getScreenCTM();
+        const scale = (ctm ? ctm.a : 1) * zoom;
+        return { width: window.innerWidth / scale, height: window.innerHeight / scale };
+    }
+
+    // Get the rectangle around all the boxes, null without boxes
+    function getBounds() {
+        if (rects.length === 0) return null;
+        const left = Math.min(...rects.map(r => r.x));
+        const top = Math.min(...rects.map(r => r.y));
+        const right = Math.max(...rects.map(r => r.x + r.width));
+        const bottom = Math.max(...rects.map(r => r.y + r.height));
+        return { x: left, y: top, width: right - left, height: bottom - top };
+    }
+
+    function fits(bounds) {
+        const visible = getVisibleSize();
+        return !bounds || (bounds.width + 2 * margin <= visible.width && bounds.height + 2 * margin <= visible.height);
+    }
+
+    function apply(animate) {
+        const x = target.x + pan.x;
+        const y = target.y + pan.y;
+        content.style.transition = animate ? 'transform 300ms ease-out' : null;
+        content.setAttribute('transform', `translate(${anchor.x}, ${anchor.y}) scale(${zoom}) translate(${-x}, ${-y})`);
+        updateMinimap();
+    }
+
+    function updateMinimap() {
+        const bounds = getBounds();
+        const moved = zoom !== 1 || pan.x !== 0 || pan.y !== 0;
+        const shown = bounds !== null && (moved || !fits(bounds));
+        minimapElement.classList.toggle('visible', shown);
+        if (!shown) return;
+
+        minimap.setAttribute('viewBox', `${bounds.x - margin} ${bounds.y - margin} ${bounds.width + 2 * margin} ${bounds.height + 2 * margin}`);
+        while (minimapBoxes.firstChild) {
+            minimapBoxes.removeChild(minimapBoxes.firstChild);
+        }
+        rects.forEach(({ x, y, width, height, focused }) => {
+            const rect = document.createElementNS(svgNS, 'rect');
+            rect.setAttribute('x', x);
+            rect.setAttribute('y', y);
+            rect.setAttribute('width', width);
+            rect.setAttribute('height', height);
+            if (focused) rect.setAttribute('class', 'focused');
+            minimapBoxes.appendChild(rect);
+        });
+
+        // The window around the graph point at the anchor, the anchor being where the grid centers
+        const visible = getVisibleSize();
+        const anchorRatio = getAnchorRatio();
+        minimapViewport.setAttribute('x', target.x + pan.x - visible.width * anchorRatio.x);
+        minimapViewport.setAttribute('y', target.y + pan.y - visible.height * anchorRatio.y);
+        minimapViewport.setAttribute('width', visible.width);
+        minimapViewport.setAttribute('height', visible.height);
+    }
+
+    // Get where the anchor is in the window, as a ratio of its size
+    function getAnchorRatio() {
+        const ctm = svgElement.firstElementChild.getScreenCTM();
+        if (!ctm) return { x: 0.5, y: 0.5 };
+        return {
+            x: (ctm.a * anchor.x + ctm.e) / window.innerWidth,
+            y: (ctm.d * anchor.y + ctm.f) / window.innerHeight
+        };
+    }
+
+    // Show the boxes of the graph and the focus, following the focus when the graph does not fit
+    function show(newRects, newFocusPoint) {
+        rects = newRects;
+        const focusMoved = newFocusPoint && (!focusPoint || newFocusPoint.x !== focusPoint.x || newFocusPoint.y !== focusPoint.y);
+        focusPoint = newFocusPoint;
+
+        const bounds = getBounds();
+        if (zoom === 1 && fits(bounds)) {
+            target = { ...anchor };
+            pan = { x: 0, y: 0 };
+        } else if (focusMoved) {
+            target = { ...focusPoint };
+            pan = { x: 0, y: 0 };
+        }
+        apply(true);
+    }
+
+    function setZoom(newZoom) {
+        zoom = Math.min(maxZoom, Math.max(minZoom, newZoom));
+        apply(false);
+    }
+
+    function reset() {
+        zoom = 1;
+        pan = { x: 0, y: 0 };
+        target = focusPoint && !fits(getBounds()) ? { ...focusPoint } : { ...anchor };
+        apply(true);
+    }
+
+    // Hand moves, on the page around the menus and panels
+    const surface = svgElement.parentElement;
+
+    surface.addEventListener('wheel', (event) => {
+        event.preventDefault();
+        setZoom(zoom * Math.exp(-event.deltaY * 0.001));
+    }, { passive: false });
+
+    surface.addEventListener('pointerdown', (event) => {
+        pointers.set(event.pointerId, { x: event.clientX, y: event.clientY });
+        surface.setPointerCapture(event.pointerId);
+    });
+
+    surface.addEventListener('pointermove', (event) => {
+        const previous = pointers.get(event.pointerId);
+        if (!previous) return;
+        const current = { x: event.clientX, y: event.clientY };
+
+        if (pointers.size === 1) {
+            const ctm = svgElement.getScreenCTM();
+            const scale = (ctm ? ctm.a : 1) * zoom;
+            pan.x -= (current.x - previous.x) / scale;
+            pan.y -= (current.y - previous.y) / scale;
+            pointers.set(event.pointerId, current);
+            apply(false);
+        } else if (pointers.size === 2) {
+            // Pinch: the zoom follows the distance between the two fingers
+            const other = [...pointers].find(([id]) => id !== event.pointerId)[1];
+            const before = Math.hypot(previous.x - other.x, previous.y - other.y);
+            const after = Math.hypot(current.x - other.x, current.y - other.y);
+            pointers.set(event.pointerId, current);
+            if (before > 0) setZoom(zoom * after / before);
+        }
+    });
+
+    const release = (event) => pointers.delete(event.pointerId);
+    surface.addEventListener('pointerup', release);
+    surface.addEventListener('pointercancel', release);
+    surface.addEventListener('dblclick', reset);
+
+    window.addEventListener('resize', () => apply(false));
+
+    return {
+        content,
+        show,
+        reset
+    };
+}
@@ -100,7 +100,8 @@ async function pollMessages() {
 }
 
 function handleMessage(message) {
-    if(!message.type.startsWith('navigate_')) {
+    // Logging the log entries would echo them back forever
+    if(!message.type.startsWith('navigate_') && !['append_log', 'append_protocol_messages', 'set_inspector_state'].includes(message.type)) {
         console.log('Received message:', message);
     }
     try {
@@ -116,12 +117,42 @@ function handleMessage(message) {
             case 'insert_node':
                 handleInsertNode(data);
                 break;
+            case 'remove_link':
+                handleRemoveLink(data);
+                break;
+            case 'remove_node':
+                handleRemoveNode(data);
+                break;
+            case 'set_node_state':
+                handleSetNodeState(data);
+                break;
+            case 'set_node_tag':
+                handleSetNodeTag(data);
+                break;
+            case 'set_node_clipping':
+                handleSetNodeClipping(data);
+                break;
+            case 'set_node_latency':
+                handleSetNodeLatency(data);
+                break;
+            case 'set_node_label':
+                handleSetNodeLabel(data);
+                break;
             case 'navigate_grid':
                 handleNavigateGrid(data);
                 break;
+            case 'focus_node':
+                handleFocusNode(data);
+                break;
+            case 'focus_link':
+                handleFocusLink(data);
+                break;
             case 'navigate_menu':
                 handleNavigateMenu(data);
                 break;
+            case 'jump_menu':
+                handleJumpMenu(data);
+                break;
             case 'open_menu':
                 handleOpenMenu(data);
                 break;
@@ -131,12 +162,88 @@ function handleMessage(message) {
             case 'close_all_menus':
                 handleCloseAllMenus();
                 break;
+            case 'set_link_ranks':
+                handleSetLinkRanks(data);
+                break;
             case 'commit':
                 handleCommit();
                 break;
             case 'prompt':
                 handlePrompt(data);
                 break;
+            case 'show_performance':
+                handleShowPerformance(data);
+                break;
+            case 'hide_performance':
+                handleHidePerformance();
+                break;
+            case 'show_text_entry':
+                handleShowTextEntry(data);
+                break;
+            case 'hide_text_entry':
+                handleHideTextEntry();
+                break;
+            case 'set_tempo':
+                handleSetTempo(data);
+                break;
+            case 'set_recording':
+                handleSetRecording(data);
+                break;
+            case 'set_waiting':
+                handleSetWaiting(data);
+                break;
+            case 'set_up_next':
+                handleSetUpNext(data);
+                break;
+            case 'set_header':
+                handleSetHeader(data);
+                break;
+            case 'set_busy':
+                handleSetBusy(data);
+                break;
+            case 'set_startup':
+                handleSetStartup(data);
+                break;
+            case 'set_audio_status':
+                handleSetAudioStatus(data);
+                break;
+            case 'set_xruns':
+                handleSetXruns(data);
+                break;
+            case 'set_spectrum':
+                handleSetSpectrum(data);
+                break;
+            case 'set_dsp_load':
+                handleSetDspLoad(data);
+                break;
+            case 'set_engine_status':
+                handleSetEngineStatus(data);
+                break;
+            case 'set_theme':
+                handleSetTheme(data);
+                break;
+            case 'set_display':
+                handleSetDisplay(data);
+                break;
+            case 'set_log_panel':
+                handleSetLogPanel(data);
+                break;
+            case 'append_log':
+                handleAppendLog(data);
+                break;
+            case 'set_inspector_panel':
+                handleSetInspectorPanel(data);
+                break;
+            case 'append_protocol_messages':
+                handleAppendProtocolMessages(data);
+                break;
+            case 'set_inspector_state':
+                handleSetInspectorState(data);
+                break;
+            case 'focus':
+            case 'error':
+                // Sent to remote frontends, the grid tracks its own focus
+                break;
             default:
                 console.warn('Unknown message type:', type);
         }
@@ -151,7 +258,7 @@ function handleMessage(message) {
 // ============================================================================
 
 function handleCreateNode(data) {
-    const { id, label, nodeType } = data;
+    const { id, label, nodeType, engine } = data;
     
     // Map node types to grid styling
     const boxOptions = { label };
@@ -161,8 +268,9 @@ function handleCreateNode(data) {
         boxOptions.invisible = true;
     }
     
-    // For now, use hardcoded positioning
-    grid.setBox(id, boxOptions, 0, id === 'inputs' ? 0 : 1);
+    // Placed by the layout of the next commit
+    grid.setBox(id, boxOptions);
+    grid.setBoxEngine(id, engine);
     
     console.log(`Created node: ${id} (${label})`);
 }
@@ -180,6 +288,58 @@ function handleCreateLink(data) {
     console.log(`Created link: ${fromId} -> ${toId}`);
 }
 
+function handleSetLinkRanks(data) {
+    grid.setLineRanks(data.links);
+}
+
+function handleRemoveLink(data) {
+    const { fromId, toId } = data;
+    
+    grid.removeLine(fromId, toId);
+    
+    console.log(`Removed link: ${fromId} -> ${toId}`);
+}
+
+function handleRemoveNode(data) {
+    const { id } = data;
+    
+    grid.removeNode(id);
+    
+    console.log(`Removed node: ${id}`);
+}
+
+function handleSetNodeState(data) {
+    const { id, state } = data;
+    
+    grid.setBoxState(id, state === 'normal' ? null : state);
+}
+
+function handleSetNodeTag(data) {
+    const { id, color } = data;
+    
+    grid.setBoxTag(id, color);
+}
+
+function handleSetNodeClipping(data) {
+    const { id, clipping } = data;
+    
+    grid.setBoxClipping(id, clipping);
+}
+
+function handleSetNodeLatency(data) {
+    const { id, latency } = data;
+    
+    grid.setBoxLatency(id, latency);
+}
+
+function handleSetNodeLabel(data) {
+    const { id, label } = data;
+    
+    grid.setBox(id, { label });
+    
+    console.log(`Renamed node: ${id} to ${label}`);
+}
+
 function handleInsertNode(data) {
     const { id, label, nodeType, linkFrom, linkTo } = data;
     
@@ -187,7 +347,7 @@ function handleInsertNode(data) {
     
     // Create new node
     const boxOptions = { label };
-    grid.setBox(id, boxOptions, 0, linkFrom === 'inputs' ? 0 : 1);
+    grid.setBox(id, boxOptions);
     
     // Create new links
     if(nodeType !== 'portIn') {
@@ -203,6 +363,14 @@ function handleInsertNode(data) {
     console.log(`Inserted node: ${id} between ${linkFrom} and ${linkTo}`);
 }
 
+function handleFocusNode(data) {
+    grid.focusBox(data.id);
+}
+
+function handleFocusLink(data) {
+    grid.focusLine(data.fromId, data.toId);
+}
+
 function handleNavigateGrid(data) {
     const { level, direction } = data;
     
@@ -234,6 +402,14 @@ function handleNavigateMenu(data) {
     }
 }
 
+function handleJumpMenu(data) {
+    const { direction } = data;
+    
+    if (!currentMenu) return;
+    
+    currentMenu.jump(direction);
+}
+
 function handleCommit() {
     grid.commit();
     console.log('Committed visual changes');
@@ -292,6 +468,315 @@ function handlePrompt(data) {
     }
 }
 
+// ============================================================================
+// Tempo Handler
+// ============================================================================
+
+function handleSetTempo(data) {
+    const { bpm } = data;
+    const tempoArea = document.getElementById('tempo-area');
+    if (tempoArea) {
+        tempoArea.textContent = bpm ? `${bpm.toFixed(1)} BPM` : '';
+    }
+}
+
+// ============================================================================
+// Audio Status Handler
+// ============================================================================
+
+function handleSetAudioStatus(data) {
+    const { sample_rate, buffer_size, latency_ms } = data;
+    const statusArea = document.getElementById('status-area');
+    if (statusArea) {
+        statusArea.textContent = `${(sample_rate / 1000).toFixed(1)} kHz · ${buffer_size} frames · ${latency_ms.toFixed(1)} ms`;
+    }
+}
+
+// ============================================================================
+// Theme Handler
+// ============================================================================
+
+// Number of entries shown in the log panel
+const LOG_PANEL_LINES = 100;
+
+function handleSetLogPanel(data) {
+    const panel = document.getElementById('log-panel');
+    if (panel) {
+        panel.classList.toggle('visible', data.visible);
+    }
+}
+
+function handleAppendLog(data) {
+    const panel = document.getElementById('log-panel');
+    if (!panel) return;
+    for (const entry of data.entries) {
+        const line = document.createElement('div');
+        line.className = entry.level;
+        line.textContent = entry.message;
+        panel.appendChild(line);
+    }
+    while (panel.children.length > LOG_PANEL_LINES) {
+        panel.removeChild(panel.firstChild);
+    }
+}
+
+// Number of protocol messages kept in the inspector
+const INSPECTOR_MESSAGES = 50;
+
+function handleSetInspectorPanel(data) {
+    document.getElementById('inspector-panel').classList.toggle('visible', data.visible);
+}
+
+function handleAppendProtocolMessages(data) {
+    const list = document.querySelector('#inspector-panel .protocol-messages');
+    for (const message of data.messages) {
+        const entry = document.createElement('pre');
+        entry.className = message.direction;
+        entry.textContent = `${message.time} ${message.direction === 'out' ? '→' : '←'}\n${message.text}`;
+        list.appendChild(entry);
+    }
+    while (list.children.length > INSPECTOR_MESSAGES) {
+        list.removeChild(list.firstChild);
+    }
+    list.scrollTop = list.scrollHeight;
+}
+
+function handleSetInspectorState(data) {
+    const state = document.querySelector('#inspector-panel .raw-state');
+    state.textContent = `${data.time}\n${data.text}`;
+}
+
+function handleSetTheme(data) {
+    const { theme } = data;
+    [...document.body.classList]
+        .filter(c => c.startsWith('theme-'))
+        .forEach(c => document.body.classList.remove(c));
+    document.body.classList.add(`theme-${theme}`);
+}
+
+function handleSetDisplay(data) {
+    const { scale, compact } = data;
+    document.body.style.zoom = scale;
+    document.body.classList.toggle('layout-compact', compact);
+}
+
+// ============================================================================
+// DSP Load Handler
+// ============================================================================
+
+// Load above which adding plugins becomes risky
+const DSP_LOAD_WARNING = 75;
+
+function handleSetDspLoad(data) {
+    const { percent } = data;
+    const loadArea = document.getElementById('load-area');
+    if (loadArea) {
+        loadArea.style.display = 'flex';
+        loadArea.classList.toggle('warning', percent >= DSP_LOAD_WARNING);
+        loadArea.querySelector('.load-fill').style.width = `${Math.min(percent, 100)}%`;
+        loadArea.querySelector('.load-value').textContent = `DSP ${percent}%`;
+    }
+}
+
+function handleSetEngineStatus(data) {
+    const { connected } = data;
+    const engineArea = document.getElementById('engine-area');
+    if (engineArea) {
+        engineArea.style.display = 'flex';
+        engineArea.classList.toggle('disconnected', !connected);
+        engineArea.title = connected ? 'Engine answering' : 'Engine not answering';
+    }
+}
+
+// ============================================================================
+// Spectrum Analyzer Handler
+// ============================================================================
+
+// Levels shown by the analyzer bars, in dB
+const SPECTRUM_FLOOR_DB = -90;
+const SPECTRUM_CEILING_DB = 0;
+
+function handleSetSpectrum(data) {
+    const { bands } = data;
+    const panel = document.getElementById('analyzer-panel');
+    if (!panel) return;
+    panel.classList.toggle('visible', bands !== null);
+    if (bands === null) return;
+    while (panel.children.length < bands.length) {
+        panel.appendChild(document.createElement('div'));
+    }
+    while (panel.children.length > bands.length) {
+        panel.removeChild(panel.lastChild);
+    }
+    bands.forEach((level, i) => {
+        const ratio = (level - SPECTRUM_FLOOR_DB) / (SPECTRUM_CEILING_DB - SPECTRUM_FLOOR_DB);
+        panel.children[i].style.height = `${Math.max(0, Math.min(ratio, 1)) * 100}%`;
+    });
+}
+
+// ============================================================================
+// Xrun Handler
+// ============================================================================
+
+function handleSetXruns(data) {
+    const { count } = data;
+    const xrunArea = document.getElementById('xrun-area');
+    if (xrunArea) {
+        xrunArea.textContent = count > 0 ? `⚠ ${count} xrun${count > 1 ? 's' : ''}` : '';
+    }
+}
+
+// ============================================================================
+// Waiting Devices Handler
+// ============================================================================
+
+function handleSetWaiting(data) {
+    const { names } = data;
+    const waitingArea = document.getElementById('waiting-area');
+    if (waitingArea) {
+        waitingArea.textContent = names.length > 0 ? `Waiting for device ${names.join(', ')}` : '';
+    }
+}
+
+// ============================================================================
+// Set List Handler
+// ============================================================================
+
+function handleSetUpNext(data) {
+    const { label } = data;
+    const setListArea = document.getElementById('setlist-area');
+    if (setListArea) {
+        setListArea.textContent = label ? `Next: ${label}` : '';
+    }
+}
+
+function handleSetHeader(data) {
+    const { breadcrumb, status } = data;
+    const headerArea = document.getElementById('header-area');
+    if (headerArea) {
+        headerArea.querySelector('.breadcrumb').textContent = breadcrumb;
+        headerArea.querySelector('.status').textContent = status;
+    }
+}
+
+function handleSetBusy(data) {
+    const { message } = data;
+    const busyArea = document.getElementById('busy-area');
+    if (busyArea) {
+        busyArea.querySelector('.busy-message').textContent = message || '';
+        busyArea.style.display = message ? 'flex' : 'none';
+    }
+}
+
+function handleSetStartup(data) {
+    const { step, failed } = data;
+    const startupArea = document.getElementById('startup-area');
+    if (startupArea) {
+        startupArea.querySelector('.startup-step').textContent = step || '';
+        startupArea.style.display = step ? 'flex' : 'none';
+        startupArea.classList.toggle('failed', !!failed);
+    }
+    // The busy steps of the startup, such as the bundles scanned, show as a detail line under it
+    document.body.classList.toggle('starting', !!step);
+}
+
+// ============================================================================
+// Recording Handler
+// ============================================================================
+
+function handleSetRecording(data) {
+    const { seconds } = data;
+    const recordArea = document.getElementById('record-area');
+    if (recordArea) {
+        if (seconds === null || seconds === undefined) {
+            recordArea.textContent = '';
+        } else {
+            const minutes = String(Math.floor(seconds / 60)).padStart(2, '0');
+            const rest = String(seconds % 60).padStart(2, '0');
+            recordArea.textContent = `● REC ${minutes}:${rest}`;
+        }
+    }
+}
+
+// ============================================================================
+// Performance Handlers
+// ============================================================================
+
+function handleShowPerformance(data) {
+    const { bank, parameters } = data;
+    
+    document.body.classList.add('performance');
+    
+    const bankArea = document.getElementById('bank-area');
+    if (bankArea) {
+        bankArea.textContent = bank;
+    }
+    
+    // Map parameters to the first row of the control board
+    parameters.forEach((parameter, index) => {
+        const control = board.getControl(0, index);
+        if (!control) return;
+        if (parameter) {
+            control.setLabel(parameter.name, parameter.title);
+            control.setValue(parameter.value);
+        } else {
+            control.setLabel('-', '');
+            control.setValue(0);
+        }
+    });
+}
+
+function handleHidePerformance() {
+    document.body.classList.remove('performance');
+    
+    const bankArea = document.getElementById('bank-area');
+    if (bankArea) {
+        bankArea.textContent = '';
+    }
+}
+
+// ============================================================================
+// Text Entry Handlers
+// ============================================================================
+
+// Text of the open text entry, typed over from a browser keyboard
+let textEntryText = null;
+
+function handleShowTextEntry(data) {
+    const { title, text, cursor } = data;
+    textEntryText = text;
+    
+    const area = document.getElementById('text-entry-area');
+    if (!area) return;
+    
+    area.innerHTML = '';
+    const titleDiv = document.createElement('div');
+    titleDiv.className = 'text-entry-title';
+    titleDiv.textContent = title;
+    area.appendChild(titleDiv);
+    
+    // One span per character, the cursor can stand after the last one
+    const textDiv = document.createElement('div');
+    const chars = [...text, ' '];
+    chars.forEach((char, index) => {
+        const span = document.createElement('span');
+        span.textContent = char === ' ' ? '\u00a0' : char;
+        if (index === cursor) span.className = 'cursor';
+        textDiv.appendChild(span);
+    });
+    area.appendChild(textDiv);
+    area.style.display = 'block';
+}
+
+function handleHideTextEntry() {
+    textEntryText = null;
+    const area = document.getElementById('text-entry-area');
+    if (area) {
+        area.style.display = 'none';
+        area.innerHTML = '';
+    }
+}
+
 // ============================================================================
 // Error Reporting
 // ============================================================================
@@ -314,7 +799,8 @@ function sendError(title, message, context) {
 function startUI() {
     // Initialize grid
     const graphSvg = document.getElementById('main');
-    grid = createGrid(graphSvg);
+    const view = createView(graphSvg, document.getElementById('minimap-area'));
+    grid = createGrid(graphSvg, view);
     
     // Start message polling (only in wry WebView)
     if (typeof window.ipc !== 'undefined') {
@@ -322,10 +808,56 @@ function startUI() {
         console.log('UI initialized, polling for messages');
     } else {                
         window.ipc = { postMessage: console.log }; // Fallback to console.log in normal browser
-        console.log('Running in normal browser - message polling disabled');
+        connectRemote();
     }
 }
 
+// Keys driving the controller from a browser, like the knobs and buttons
+const REMOTE_KEY_COMMANDS = {
+    ArrowRight: 'nav fwd',
+    ArrowLeft: 'nav back',
+    ArrowDown: 'jump fwd',
+    ArrowUp: 'jump back',
+    Enter: 'select',
+    Escape: 'back'
+};
+
+// Mirror the UI in a normal browser through the WebSocket of the page's server
+function connectRemote() {
+    const socket = new WebSocket(`ws://${location.host}/`);
+    socket.onopen = () => console.log('Running in browser - connected to TraxDub');
+    socket.onmessage = (event) => {
+        try {
+            handleMessage(JSON.parse(event.data));
+        } catch (e) {
+            console.error('Failed to parse message:', event.data, e);
+        }
+    };
+    socket.onclose = () => {
+        // Start over from a fresh page, which gets the whole graph again
+        console.log('Connection to TraxDub lost, reloading');
+        setTimeout(() => location.reload(), 2000);
+    };
+
+    window.addEventListener('keydown', (event) => {
+        if (socket.readyState !== WebSocket.OPEN) return;
+        // While a text entry is open, typing replaces the knob editing
+        if (textEntryText !== null && (event.key.length === 1 || event.key === 'Backspace')) {
+            event.preventDefault();
+            const text = event.key === 'Backspace'
+                ? textEntryText.trimEnd().slice(0, -1)
+                : textEntryText + event.key;
+            socket.send(JSON.stringify({ type: 'command', data: { line: `text ${text}` } }));
+            return;
+        }
+        const line = REMOTE_KEY_COMMANDS[event.key];
+        if (line) {
+            event.preventDefault();
+            socket.send(JSON.stringify({ type: 'command', data: { line } }));
+        }
+    });
+}
+
 init(() => {}, startUI);
 
 // Create control board
@@ -22,12 +22,13 @@ pub fn close() -> Result<()> {
 }
 
 /// Create and run the UI window  
+/// Closing the window clears `running`, shutting the controller down
 pub fn run(
     message_queue: Arc<Mutex<VecDeque<String>>>,
     focused_grid_element: Arc<Mutex<Option<GridElement>>>,
     focused_menu_option: Arc<Mutex<Option<MenuOptionElement>>>,
+    running: Arc<AtomicBool>,
 ) -> Result<()> {
-    let running = Arc::new(AtomicBool::new(true));
     let _ = RUNNING.set(Arc::clone(&running));
     use wry::{
         dpi::LogicalSize,
@@ -48,17 +49,6 @@ pub fn run(
         .with_inner_size(LogicalSize::new(920.0, 640.0))
         .build(&event_loop)?;
 
-    let window_html = include_str!("window.html").to_string();
-    let window_js = include_str!("window.js").to_string();
-    let console_js = include_str!("console.js").to_string();
-    let css = include_str!("style.css").to_string();
-    let logo = include_str!("logo.svg").to_string();
-    let logo_js = include_str!("logo.js").to_string();
-    let menu_js = include_str!("menu.js").to_string();
-    let grid_js = include_str!("grid.js").to_string();
-    let rotary_js = include_str!("rotary.js").to_string();
-    let control_js = include_str!("control.js").to_string();
-
     let window = Arc::new(window);    
 
     #[cfg(not(any(
@@ -104,63 +94,11 @@ pub fn run(
                         .header("Content-Type", "application/json")
                         .body(Cow::from(json.into_bytes()))
                         .unwrap()
-                } else if path == "/style.css" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "text/css")
-                        .body(Cow::from(css.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/index.html" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "text/html")
-                        .body(Cow::from(window_html.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/window.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(window_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/console.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(console_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/logo.svg" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "image/svg+xml")
-                        .body(Cow::from(logo.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/logo.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(logo_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/menu.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(menu_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/grid.js" {
+                } else if let Some((content_type, content)) = super::assets::get(path) {
                     wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(grid_js.clone().into_bytes()))
+                        .header("Content-Type", content_type)
+                        .body(Cow::from(content))
                         .unwrap()
-                } else if path == "/rotary.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(rotary_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/control.js" {
-                    wry::http::Response::builder()
-                        .header("Content-Type", "application/javascript")
-                        .body(Cow::from(control_js.clone().into_bytes()))
-                        .unwrap()
-                } else if path == "/oxanium.ttf" {
-                    // Include font file
-                    let font_data = include_bytes!("oxanium.ttf");
-                    wry::http::Response::builder()
-                        .header("Content-Type", "font/ttf")
-                        .body(Cow::from(font_data.as_slice()))
-                        .unwrap()                    
                 } else {
                     wry::http::Response::builder()
                         .status(404)